@@ -0,0 +1,70 @@
+use crate::connection_stats::ConnectionStatsStore;
+use crate::dock::DockStatus;
+use crate::event_batcher::EventBatcher;
+use crate::event_script::EventScripts;
+use crate::event_stream::EventStream;
+use crate::evdev_gamepad::EvdevGamepadManager;
+use crate::external_processor::ExternalProcessor;
+use crate::gamepad::GamepadManager;
+use crate::health::HealthMonitor;
+use crate::hidraw::LizardMode;
+use crate::http_client::HttpClients;
+use crate::iio_motion::IioMotion;
+use crate::input_backend::InputBackends;
+use crate::input_pipeline::EventBus;
+use crate::key_emulation::KeyEmulator;
+use crate::light_server::LightServer;
+use crate::midi_output::MidiOutputManager;
+use crate::opc_output::OpcOutput;
+use crate::panic_report::PanicMonitor;
+use crate::recording::InputSession;
+use crate::settings::SettingsManager;
+use crate::show_playback::ShowPlayback;
+use crate::shutdown::ShutdownSignal;
+use crate::slot_assignment::SlotAssignments;
+use crate::steam_input_check::SteamInputCheck;
+use crate::suspend_inhibit::SuspendInhibitor;
+use crate::system_info::SystemInfo;
+use crate::system_power::SystemPowerMonitor;
+use crate::uinput_output::UinputOutput;
+use crate::update_status::UpdateStatus;
+use crate::virtual_pads::VirtualPadFilter;
+use crate::window_focus::WindowFocus;
+use std::sync::{Arc, Mutex};
+
+/// Everything the commands and background threads share, managed once at startup.
+/// Threads clone the parts they need out of it.
+pub struct AppState {
+    pub backends: Arc<InputBackends>,
+    pub gamepad_manager: Arc<GamepadManager>,
+    pub evdev_manager: Arc<EvdevGamepadManager>,
+    pub iio_motion: Arc<IioMotion>,
+    pub event_bus: Arc<EventBus>,
+    pub session: Arc<InputSession>,
+    pub slot_assignments: Arc<SlotAssignments>,
+    pub settings: Arc<SettingsManager>,
+    pub light_server: Arc<LightServer>,
+    pub midi_output: Arc<MidiOutputManager>,
+    pub opc_output: Arc<OpcOutput>,
+    pub show_playback: Arc<ShowPlayback>,
+    pub http: Arc<HttpClients>,
+    pub batcher: Arc<Mutex<EventBatcher>>,
+    pub event_stream: Arc<EventStream>,
+    pub event_scripts: Arc<EventScripts>,
+    pub external_processor: Arc<ExternalProcessor>,
+    pub connection_stats: Arc<ConnectionStatsStore>,
+    pub health_monitor: Arc<HealthMonitor>,
+    pub panic_monitor: Arc<PanicMonitor>,
+    pub shutdown: Arc<ShutdownSignal>,
+    pub system_info: Arc<SystemInfo>,
+    pub system_power: Arc<SystemPowerMonitor>,
+    pub update_status: Arc<Mutex<UpdateStatus>>,
+    pub virtual_pads: Arc<VirtualPadFilter>,
+    pub virtual_gamepad: Arc<UinputOutput>,
+    pub steam_input: Arc<Mutex<SteamInputCheck>>,
+    pub suspend_inhibitor: Arc<SuspendInhibitor>,
+    pub dock: Arc<Mutex<DockStatus>>,
+    pub lizard_mode: Arc<LizardMode>,
+    pub window_focus: Arc<WindowFocus>,
+    pub key_emulator: Arc<KeyEmulator>,
+}
@@ -0,0 +1,107 @@
+use crate::memory_stats::{deque_bytes, BufferStats};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap, VecDeque};
+use std::fmt::Write as _;
+use std::time::{Duration, Instant};
+
+pub const DEFAULT_RATE_HZ: u32 = 60;
+/// 60 seconds at the default rate.
+pub const DEFAULT_MAX_SNAPSHOTS: usize = 3600;
+/// Capture happens on the polling thread, which can't go much past 100Hz anyway.
+pub const MAX_RATE_HZ: u32 = 1000;
+pub const MAX_SNAPSHOTS_LIMIT: usize = 100_000;
+
+/// Every connected controller's axes at one moment.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AxisSnapshot {
+    pub timestamp_ms: u64,
+    pub states: HashMap<usize, HashMap<String, f32>>,
+}
+
+/// Samples axis state at a fixed rate, however often the axes actually move, so replays
+/// and plots get evenly spaced frames.
+pub struct AxisSnapshotRecorder {
+    snapshots: VecDeque<AxisSnapshot>,
+    rate_hz: u32,
+    max_snapshots: usize,
+    last_capture: Instant,
+}
+
+impl Default for AxisSnapshotRecorder {
+    fn default() -> Self {
+        Self {
+            snapshots: VecDeque::new(),
+            rate_hz: DEFAULT_RATE_HZ,
+            max_snapshots: DEFAULT_MAX_SNAPSHOTS,
+            last_capture: Instant::now(),
+        }
+    }
+}
+
+impl AxisSnapshotRecorder {
+    /// Whether a frame is due, resetting the interval if so.
+    pub fn take_due(&mut self, now: Instant) -> bool {
+        let interval = Duration::from_secs(1) / self.rate_hz;
+        if now.duration_since(self.last_capture) < interval {
+            return false;
+        }
+        self.last_capture = now;
+        true
+    }
+
+    pub fn push(&mut self, snapshot: AxisSnapshot) {
+        while self.snapshots.len() >= self.max_snapshots {
+            self.snapshots.pop_front();
+        }
+        self.snapshots.push_back(snapshot);
+    }
+
+    pub fn buffer_stats(&self) -> BufferStats {
+        // Each snapshot also holds its controllers' axis maps, much the same size in each
+        let per_snapshot = self.snapshots.front().map_or(0, |snapshot| {
+            snapshot.states.values()
+                .flat_map(HashMap::keys)
+                .map(|axis| std::mem::size_of::<(String, f32)>() + axis.capacity())
+                .sum::<usize>()
+        });
+        let approx_bytes = deque_bytes(&self.snapshots) + per_snapshot * self.snapshots.len();
+        BufferStats::new("axis_snapshots", self.snapshots.len(), Some(self.max_snapshots), 1, approx_bytes)
+    }
+
+    /// Snapshots taken after `timestamp_ms`, oldest first.
+    pub fn since(&self, timestamp_ms: u64) -> Vec<AxisSnapshot> {
+        self.snapshots.iter()
+            .filter(|snapshot| snapshot.timestamp_ms > timestamp_ms)
+            .cloned()
+            .collect()
+    }
+
+    pub fn configure(&mut self, rate_hz: u32, max_snapshots: usize) -> Result<(), String> {
+        if rate_hz == 0 || rate_hz > MAX_RATE_HZ {
+            return Err(format!("Snapshot rate must be between 1 and {} Hz", MAX_RATE_HZ));
+        }
+        if max_snapshots == 0 || max_snapshots > MAX_SNAPSHOTS_LIMIT {
+            return Err(format!("Snapshot count must be between 1 and {}", MAX_SNAPSHOTS_LIMIT));
+        }
+        self.rate_hz = rate_hz;
+        self.max_snapshots = max_snapshots;
+        let excess = self.snapshots.len().saturating_sub(max_snapshots);
+        self.snapshots.drain(..excess);
+        Ok(())
+    }
+
+    /// One `timestamp_ms,controller_id,axis,value` row per axis per snapshot.
+    pub fn to_csv(&self) -> String {
+        let mut csv = String::from("timestamp_ms,controller_id,axis,value\n");
+        for snapshot in &self.snapshots {
+            let controllers: BTreeMap<_, _> = snapshot.states.iter().collect();
+            for (controller_id, axes) in controllers {
+                let axes: BTreeMap<_, _> = axes.iter().collect();
+                for (axis, value) in axes {
+                    let _ = writeln!(csv, "{},{},{},{}", snapshot.timestamp_ms, controller_id, axis, value);
+                }
+            }
+        }
+        csv
+    }
+}
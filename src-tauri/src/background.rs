@@ -0,0 +1,156 @@
+use crate::app_state::AppState;
+use crate::emit_interest;
+use crate::locking::MutexExt;
+use crate::processing_pause::{self, PauseMode};
+use crate::steam_deck::SessionKind;
+use crate::window_chord::ShowWindowChord;
+use serde::{Deserialize, Serialize};
+use std::sync::Mutex;
+use tauri::menu::{Menu, MenuEvent, MenuItem};
+use tauri::tray::TrayIconBuilder;
+use tauri::{AppHandle, Manager, Window, Wry};
+use tracing::{info, warn};
+
+pub const MAIN_WINDOW: &str = "main";
+const TRAY_ID: &str = "main";
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BackgroundModeStatus {
+    pub enabled: bool,
+    /// False in game mode, or wherever the desktop has no tray
+    pub tray_shown: bool,
+    pub show_window_chord: ShowWindowChord,
+}
+
+/// The tray's pause item, relabelled whenever forwarding is paused or resumed.
+struct TrayMenu {
+    pause: Mutex<Option<MenuItem<Wry>>>,
+}
+
+/// Hides the window on a close request; input keeps flowing to the light server.
+pub fn hide_main_window(window: &Window) {
+    if let Err(e) = window.hide() {
+        warn!("Failed to hide the window: {}", e);
+        return;
+    }
+    emit_interest::interest().set_window_visible(false);
+    info!("Window hidden; input forwarding keeps running in the background");
+}
+
+pub fn show_main_window(app: &AppHandle) {
+    let Some(window) = app.get_webview_window(MAIN_WINDOW) else {
+        return;
+    };
+    if let Err(e) = window.show().and_then(|_| window.unminimize()).and_then(|_| window.set_focus()) {
+        warn!("Failed to show the window: {}", e);
+        return;
+    }
+    emit_interest::interest().set_window_visible(true);
+    info!("Window shown");
+}
+
+/// Adds or removes the tray for the background mode setting. Game mode has no tray, so
+/// the show-window chord is the only way back there.
+pub fn apply_background_mode(app: &AppHandle, enabled: bool) {
+    if !enabled {
+        remove_tray(app);
+        return;
+    }
+    if app.state::<AppState>().system_info.session_kind == SessionKind::GameMode {
+        info!("Background mode on; no tray in game mode, so use the show-window chord");
+        return;
+    }
+    if let Err(e) = create_tray(app) {
+        warn!("No tray icon for background mode: {}", e);
+    }
+}
+
+pub fn status(app: &AppHandle) -> BackgroundModeStatus {
+    let settings = app.state::<AppState>().settings.get();
+    BackgroundModeStatus {
+        enabled: settings.background_mode,
+        tray_shown: app.tray_by_id(TRAY_ID).is_some(),
+        show_window_chord: settings.show_window_chord,
+    }
+}
+
+/// Adds the tray icon if there isn't one. Fails where there's no tray, e.g. game mode.
+fn create_tray(app: &AppHandle) -> tauri::Result<()> {
+    if app.tray_by_id(TRAY_ID).is_some() {
+        return Ok(());
+    }
+    let show = MenuItem::with_id(app, "show", "Show", true, None::<&str>)?;
+    let pause = MenuItem::with_id(app, "pause", pause_label(), true, None::<&str>)?;
+    let quit = MenuItem::with_id(app, "quit", "Quit", true, None::<&str>)?;
+    let menu = Menu::with_items(app, &[&show, &pause, &quit])?;
+    let mut tray = TrayIconBuilder::with_id(TRAY_ID)
+        .menu(&menu)
+        .tooltip(app.package_info().name.clone())
+        .on_menu_event(on_menu_event);
+    if let Some(icon) = app.default_window_icon() {
+        tray = tray.icon(icon.clone());
+    }
+    tray.build(app)?;
+    match app.try_state::<TrayMenu>() {
+        Some(menu) => *menu.pause.lock_or_recover() = Some(pause),
+        None => {
+            app.manage(TrayMenu { pause: Mutex::new(Some(pause)) });
+        }
+    }
+    info!("Tray icon added");
+    Ok(())
+}
+
+fn remove_tray(app: &AppHandle) {
+    if app.remove_tray_by_id(TRAY_ID).is_some() {
+        if let Some(menu) = app.try_state::<TrayMenu>() {
+            *menu.pause.lock_or_recover() = None;
+        }
+        info!("Tray icon removed");
+    }
+}
+
+/// Brings the tray's pause item in line with the current pause state.
+pub fn sync_tray(app: &AppHandle) {
+    let Some(menu) = app.try_state::<TrayMenu>() else {
+        return;
+    };
+    let pause = menu.pause.lock_or_recover();
+    if let Some(pause) = pause.as_ref() {
+        pause.set_text(pause_label()).ok();
+    }
+}
+
+/// The same graceful shutdown as the Exit button: polling stops and pending work is flushed
+/// in `RunEvent::Exit`.
+pub fn quit(app: &AppHandle) {
+    info!("Shutting down");
+    app.state::<AppState>().shutdown.request();
+    app.exit(0);
+}
+
+fn pause_label() -> &'static str {
+    if processing_pause::forwarding_paused() {
+        "Resume forwarding"
+    } else {
+        "Pause forwarding"
+    }
+}
+
+fn on_menu_event(app: &AppHandle, event: MenuEvent) {
+    match event.id().as_ref() {
+        "show" => show_main_window(app),
+        "pause" => {
+            let result = if processing_pause::forwarding_paused() {
+                processing_pause::resume(app)
+            } else {
+                processing_pause::pause(app, PauseMode::ForwardingOnly)
+            };
+            if let Err(e) = result {
+                warn!("Failed to change forwarding from the tray: {}", e);
+            }
+        }
+        "quit" => quit(app),
+        _ => {}
+    }
+}
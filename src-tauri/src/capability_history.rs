@@ -0,0 +1,208 @@
+#[cfg(target_os = "linux")]
+use crate::key_codes;
+#[cfg(target_os = "linux")]
+use evdev::Device;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeSet, HashMap};
+use std::path::{Path, PathBuf};
+use tracing::warn;
+
+/// Snapshots kept per device; the oldest are dropped first.
+pub const MAX_SNAPSHOTS_PER_DEVICE: usize = 32;
+/// Devices kept; the one seen longest ago is dropped first.
+const MAX_DEVICES: usize = 64;
+
+/// The keys and absolute axes a device reports, by kernel name, sorted.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CapabilitySet {
+    pub keys: Vec<String>,
+    pub axes: Vec<String>,
+}
+
+impl CapabilitySet {
+    #[cfg(target_os = "linux")]
+    pub fn read(device: &Device) -> Self {
+        let mut keys: Vec<String> = device.supported_keys()
+            .map(|keys| keys.iter().map(|key| key_codes::key_name(key.code()).to_string()).collect())
+            .unwrap_or_default();
+        keys.sort();
+        let mut axes: Vec<String> = device.supported_absolute_axes()
+            .map(|axes| axes.iter().map(|axis| key_codes::abs_axis_name(axis.0).to_string()).collect())
+            .unwrap_or_default();
+        axes.sort();
+        Self { keys, axes }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CapabilitySnapshot {
+    pub timestamp_ms: u64,
+    pub firmware_version: u16,
+    pub capability_hash: String,
+    pub capabilities: CapabilitySet,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct DeviceCapabilityHistory {
+    /// Oldest first
+    pub entries: Vec<CapabilitySnapshot>,
+}
+
+/// What changed between two snapshots of the same device.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CapabilityDiff {
+    pub added_keys: Vec<String>,
+    pub removed_keys: Vec<String>,
+    pub added_axes: Vec<String>,
+    pub removed_axes: Vec<String>,
+}
+
+pub fn diff_snapshots(a: &CapabilitySnapshot, b: &CapabilitySnapshot) -> CapabilityDiff {
+    let (added_keys, removed_keys) = diff_names(&a.capabilities.keys, &b.capabilities.keys);
+    let (added_axes, removed_axes) = diff_names(&a.capabilities.axes, &b.capabilities.axes);
+    CapabilityDiff { added_keys, removed_keys, added_axes, removed_axes }
+}
+
+/// Names only in `after`, then names only in `before`.
+fn diff_names(before: &[String], after: &[String]) -> (Vec<String>, Vec<String>) {
+    let before: BTreeSet<&String> = before.iter().collect();
+    let after: BTreeSet<&String> = after.iter().collect();
+    (
+        after.difference(&before).map(|name| name.to_string()).collect(),
+        before.difference(&after).map(|name| name.to_string()).collect(),
+    )
+}
+
+/// Identifies a device across firmware updates. The capability hash can't, since a
+/// firmware update that changes the layout changes the hash too.
+pub fn device_identity(vendor_id: u16, product_id: u16, name: &str) -> String {
+    format!("{:04x}:{:04x}:{}", vendor_id, product_id, name)
+}
+
+/// Capability snapshots per device identity, persisted next to the firmware versions:
+/// `~/.cache/steamdeck-controller/capability_history.json`.
+pub struct CapabilityHistoryStore {
+    histories: HashMap<String, DeviceCapabilityHistory>,
+    path: Option<PathBuf>,
+}
+
+impl CapabilityHistoryStore {
+    pub fn load() -> Self {
+        let path = std::env::var_os("HOME").map(|home| {
+            PathBuf::from(home)
+                .join(".cache")
+                .join("steamdeck-controller")
+                .join("capability_history.json")
+        });
+        let histories = path.as_deref().map(load_histories).unwrap_or_default();
+        Self { histories, path }
+    }
+
+    /// Whether the latest snapshot of the device already has this firmware version and
+    /// capability hash, so a rescan can skip reading its capabilities.
+    pub fn is_current(&self, identity: &str, firmware_version: u16, capability_hash: &str) -> bool {
+        self.histories.get(identity)
+            .and_then(|history| history.entries.last())
+            .is_some_and(|latest| latest.firmware_version == firmware_version && latest.capability_hash == capability_hash)
+    }
+
+    /// Stores `snapshot` if the device hasn't been seen before or its firmware version
+    /// or capabilities differ from the latest snapshot. Returns whether it was stored.
+    pub fn record(&mut self, identity: &str, snapshot: CapabilitySnapshot) -> bool {
+        if self.is_current(identity, snapshot.firmware_version, &snapshot.capability_hash) {
+            return false;
+        }
+        let history = self.histories.entry(identity.to_string()).or_default();
+        history.entries.push(snapshot);
+        let excess = history.entries.len().saturating_sub(MAX_SNAPSHOTS_PER_DEVICE);
+        history.entries.drain(..excess);
+        self.forget_oldest_devices();
+
+        if let Some(path) = &self.path {
+            if let Err(e) = save_histories(path, &self.histories) {
+                warn!("{}", e);
+            }
+        }
+        true
+    }
+
+    fn forget_oldest_devices(&mut self) {
+        while self.histories.len() > MAX_DEVICES {
+            let oldest = self.histories.iter()
+                .min_by_key(|(_, history)| history.entries.last().map_or(0, |latest| latest.timestamp_ms))
+                .map(|(identity, _)| identity.clone());
+            let Some(oldest) = oldest else {
+                break;
+            };
+            self.histories.remove(&oldest);
+        }
+    }
+
+    /// Devices and snapshots held, for memory stats.
+    pub fn occupancy(&self) -> (usize, usize) {
+        (self.histories.len(), self.histories.values().map(|history| history.entries.len()).sum())
+    }
+
+    /// Snapshots of the device whose history contains the given capability hash, or
+    /// failing that the one with the given identity, oldest first.
+    pub fn history(&self, fingerprint: &str) -> Vec<CapabilitySnapshot> {
+        self.histories.values()
+            .find(|history| history.entries.iter().any(|entry| entry.capability_hash == fingerprint))
+            .or_else(|| self.histories.get(fingerprint))
+            .map(|history| history.entries.clone())
+            .unwrap_or_default()
+    }
+}
+
+fn load_histories(path: &Path) -> HashMap<String, DeviceCapabilityHistory> {
+    match std::fs::read_to_string(path) {
+        Ok(contents) => serde_json::from_str(&contents).unwrap_or_else(|e| {
+            warn!("Ignoring unreadable capability history file {}: {}", path.display(), e);
+            HashMap::new()
+        }),
+        Err(_) => HashMap::new(),
+    }
+}
+
+fn save_histories(path: &Path, histories: &HashMap<String, DeviceCapabilityHistory>) -> Result<(), String> {
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)
+            .map_err(|e| format!("Failed to create {}: {}", parent.display(), e))?;
+    }
+    let contents = serde_json::to_string_pretty(histories)
+        .map_err(|e| format!("Failed to serialize capability history: {}", e))?;
+    let tmp_path = path.with_extension("json.tmp");
+    std::fs::write(&tmp_path, contents)
+        .map_err(|e| format!("Failed to write capability history: {}", e))?;
+    std::fs::rename(&tmp_path, path)
+        .map_err(|e| format!("Failed to save capability history: {}", e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn snapshot(timestamp_ms: u64, capability_hash: &str) -> CapabilitySnapshot {
+        CapabilitySnapshot {
+            timestamp_ms,
+            firmware_version: 0x0100,
+            capability_hash: capability_hash.to_string(),
+            capabilities: CapabilitySet::default(),
+        }
+    }
+
+    fn store() -> CapabilityHistoryStore {
+        CapabilityHistoryStore { histories: HashMap::new(), path: None }
+    }
+
+    #[test]
+    fn history_is_found_by_hash_or_identity() {
+        let mut store = store();
+        let identity = device_identity(0x28de, 0x1205, "Steam Deck");
+        store.record(&identity, snapshot(1, "abc123"));
+        assert!(store.record(&identity, snapshot(2, "def456")));
+        assert_eq!(store.history(&identity).len(), 2);
+        assert_eq!(store.history("abc123").len(), 2);
+        assert!(store.history("unknown").is_empty());
+    }
+}
@@ -0,0 +1,111 @@
+use crate::hidraw::HapticCue;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::time::Instant;
+
+/// A light effect fired once when every button in `buttons` is held on one controller.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CombinationLightEffect {
+    pub id: String,
+    pub buttons: Vec<String>,
+    /// How long the full combination must be held before firing; fires immediately when `None`.
+    pub hold_ms: Option<u64>,
+    pub effect: LightEffectSpec,
+    /// Played on the Deck's trackpads once the light server accepts the effect.
+    pub haptic: Option<HapticCue>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LightEffectSpec {
+    /// Overrides the configured light server endpoint for this effect.
+    pub endpoint: Option<String>,
+    pub color: (u8, u8, u8),
+    pub fade_in_ms: u64,
+    pub hold_ms: u64,
+    pub fade_out_ms: u64,
+}
+
+/// Tracks held buttons per controller and decides when a combination has completed.
+///
+/// A combination fires once per detection: it has to be released (any of its buttons)
+/// before it can fire again.
+#[derive(Default)]
+pub struct ComboTracker {
+    effects: Vec<CombinationLightEffect>,
+    pressed: HashMap<usize, HashSet<String>>,
+    completed_at: HashMap<(usize, String), Instant>,
+    fired: HashSet<(usize, String)>,
+}
+
+impl ComboTracker {
+    pub fn register(&mut self, effect: CombinationLightEffect) -> Result<(), String> {
+        if effect.id.trim().is_empty() {
+            return Err("Combination effect id must not be empty".to_string());
+        }
+        if effect.buttons.is_empty() {
+            return Err("Combination effect needs at least one button".to_string());
+        }
+        if let Some(haptic) = &effect.haptic {
+            haptic.validate()?;
+        }
+        
+        self.unregister(&effect.id);
+        self.effects.push(effect);
+        Ok(())
+    }
+    
+    pub fn unregister(&mut self, id: &str) -> bool {
+        let before = self.effects.len();
+        self.effects.retain(|effect| effect.id != id);
+        self.completed_at.retain(|(_, effect_id), _| effect_id != id);
+        self.fired.retain(|(_, effect_id)| effect_id != id);
+        self.effects.len() != before
+    }
+    
+    pub fn list(&self) -> Vec<CombinationLightEffect> {
+        self.effects.clone()
+    }
+    
+    pub fn button_changed(&mut self, controller_id: usize, button: &str, pressed: bool) {
+        let held = self.pressed.entry(controller_id).or_default();
+        if pressed {
+            held.insert(button.to_string());
+        } else {
+            held.remove(button);
+        }
+        
+        for effect in &self.effects {
+            let key = (controller_id, effect.id.clone());
+            let complete = effect.buttons.iter().all(|b| held.contains(b));
+            if complete {
+                self.completed_at.entry(key).or_insert_with(Instant::now);
+            } else {
+                self.completed_at.remove(&key);
+                self.fired.remove(&key);
+            }
+        }
+    }
+    
+    pub fn controller_disconnected(&mut self, controller_id: usize) {
+        self.pressed.remove(&controller_id);
+        self.completed_at.retain(|(id, _), _| *id != controller_id);
+        self.fired.retain(|(id, _)| *id != controller_id);
+    }
+    
+    /// Returns the effects whose combination has been held long enough and hasn't fired yet.
+    pub fn take_due(&mut self) -> Vec<(usize, CombinationLightEffect)> {
+        let mut due = Vec::new();
+        for effect in &self.effects {
+            let hold_ms = effect.hold_ms.unwrap_or(0) as u128;
+            for ((controller_id, effect_id), completed_at) in &self.completed_at {
+                if *effect_id != effect.id || completed_at.elapsed().as_millis() < hold_ms {
+                    continue;
+                }
+                if self.fired.insert((*controller_id, effect_id.clone())) {
+                    due.push((*controller_id, effect.clone()));
+                }
+            }
+        }
+        due
+    }
+}
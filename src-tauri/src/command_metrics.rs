@@ -0,0 +1,126 @@
+use crate::locking::RwLockExt;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, OnceLock, RwLock};
+use std::time::Duration;
+
+/// Per-command counters, updated lock-free once the entry exists.
+pub struct CommandMetric {
+    invocation_count: AtomicU64,
+    error_count: AtomicU64,
+    total_duration_ns: AtomicU64,
+    min_duration_ns: AtomicU64,
+    max_duration_ns: AtomicU64,
+}
+
+impl Default for CommandMetric {
+    fn default() -> Self {
+        Self {
+            invocation_count: AtomicU64::new(0),
+            error_count: AtomicU64::new(0),
+            total_duration_ns: AtomicU64::new(0),
+            min_duration_ns: AtomicU64::new(u64::MAX),
+            max_duration_ns: AtomicU64::new(0),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CommandMetricSnapshot {
+    pub invocation_count: u64,
+    pub error_count: u64,
+    pub total_duration_ns: u64,
+    pub min_duration_ns: u64,
+    pub max_duration_ns: u64,
+    pub avg_duration_ns: u64,
+}
+
+impl CommandMetric {
+    fn record(&self, duration_ns: u64, is_error: bool) {
+        self.invocation_count.fetch_add(1, Ordering::Relaxed);
+        if is_error {
+            self.error_count.fetch_add(1, Ordering::Relaxed);
+        }
+        self.total_duration_ns.fetch_add(duration_ns, Ordering::Relaxed);
+        self.min_duration_ns.fetch_min(duration_ns, Ordering::Relaxed);
+        self.max_duration_ns.fetch_max(duration_ns, Ordering::Relaxed);
+    }
+
+    fn snapshot(&self) -> CommandMetricSnapshot {
+        let invocation_count = self.invocation_count.load(Ordering::Relaxed);
+        let total_duration_ns = self.total_duration_ns.load(Ordering::Relaxed);
+        let min_duration_ns = self.min_duration_ns.load(Ordering::Relaxed);
+        CommandMetricSnapshot {
+            invocation_count,
+            error_count: self.error_count.load(Ordering::Relaxed),
+            total_duration_ns,
+            min_duration_ns: if invocation_count == 0 { 0 } else { min_duration_ns },
+            max_duration_ns: self.max_duration_ns.load(Ordering::Relaxed),
+            avg_duration_ns: total_duration_ns.checked_div(invocation_count).unwrap_or(0),
+        }
+    }
+}
+
+#[derive(Default)]
+pub struct CommandMetricsStore {
+    metrics: RwLock<HashMap<String, Arc<CommandMetric>>>,
+}
+
+impl CommandMetricsStore {
+    pub fn record(&self, command: &str, duration: Duration, is_error: bool) {
+        let duration_ns = duration.as_nanos().min(u64::MAX as u128) as u64;
+
+        let existing = self.metrics.read_or_recover().get(command).cloned();
+        let metric = match existing {
+            Some(metric) => metric,
+            None => self.metrics.write_or_recover()
+                .entry(command.to_string())
+                .or_default()
+                .clone(),
+        };
+        metric.record(duration_ns, is_error);
+    }
+
+    pub fn snapshot(&self) -> HashMap<String, CommandMetricSnapshot> {
+        self.metrics.read_or_recover()
+            .iter()
+            .map(|(name, metric)| (name.clone(), metric.snapshot()))
+            .collect()
+    }
+
+    pub fn reset(&self) {
+        self.metrics.write_or_recover().clear();
+    }
+}
+
+static COMMAND_METRICS: OnceLock<CommandMetricsStore> = OnceLock::new();
+
+/// Process-wide store; commands record into it through `timed_command!`.
+pub fn store() -> &'static CommandMetricsStore {
+    COMMAND_METRICS.get_or_init(CommandMetricsStore::default)
+}
+
+/// Wraps a command body, recording its duration and whether it returned `Err`.
+///
+/// Use `timed_command!("name", { ... })` for sync commands and
+/// `timed_command!("name", async { ... })` for async ones. `?` and `return` inside the
+/// body are still timed since the body runs as a closure / async block. The body
+/// returns `Result<_, AppError>`, so `?` converts string, I/O, HTTP and updater errors.
+macro_rules! timed_command {
+    ($name:literal, async $body:block) => {{
+        let started = std::time::Instant::now();
+        let result: Result<_, $crate::error::AppError> = async $body.await;
+        $crate::command_metrics::store().record($name, started.elapsed(), result.is_err());
+        result
+    }};
+    ($name:literal, $body:block) => {{
+        let started = std::time::Instant::now();
+        #[allow(clippy::redundant_closure_call)]
+        let result = (|| -> Result<_, $crate::error::AppError> { $body })();
+        $crate::command_metrics::store().record($name, started.elapsed(), result.is_err());
+        result
+    }};
+}
+
+pub(crate) use timed_command;
@@ -0,0 +1,1421 @@
+use crate::app_state::AppState;
+use crate::axis_snapshots::AxisSnapshot;
+use crate::capability_history::{self, CapabilityDiff, CapabilitySnapshot};
+use crate::combo_effects::CombinationLightEffect;
+use crate::command_metrics::timed_command;
+use crate::controller_event;
+use crate::dead_zone::{DeadZoneShape, StickSide};
+use crate::detection::{self, DetectionDiagnosis};
+use crate::emit_interest::{self, EventChannel, FRONTEND_CONSUMER};
+use crate::ensemble::EnsembleConfig;
+use crate::error::AppError;
+use crate::evdev_types::{self, EvdevGamepadInfo};
+use crate::event_script::{EventScript, EventScriptStatus, EXAMPLE_SCRIPTS};
+use crate::event_stream::{EventStreamConfig, EventStreamStatus, StreamFormat};
+use crate::external_processor::ExternalProcessorStatus;
+use crate::flood_alarm::{FloodAction, FloodAlarmStatus};
+use crate::gamepad::{ControllerLayout, ControllerState, GamepadInfo};
+use crate::health::PollSource;
+use crate::input_backend::{BackendControllerState, BackendDebugInfo};
+use crate::input_pipeline::EventBusConfig;
+use crate::key_codes;
+use crate::key_emulation::{self, KeyEmulationConfig, KeyEmulationStatus};
+use crate::midi_output::{self, MidiRule, MidiStatus, VIRTUAL_PORT};
+use crate::opc_output::{OpcConfig, OpcGenerator, OpcStatus, DEFAULT_FRAME_RATE, DEFAULT_PORT};
+use crate::permissions::{self, DeviceOpenFailure, InputGroupReport, OpenFailureCause, PermissionsAnalysis};
+use crate::poll_cadence::{self, PollCadenceConfig};
+use crate::processing_pause::{self, PauseMode};
+use crate::raw_dump::{self, RawDumpStatus};
+use crate::show_playback::ShowStatus;
+use crate::recording::{self, LiveInputMode, RecordedEvent, RecordingSummary, ReplayOptions, ReplayProgress};
+use crate::signal_quality::DeviceSignalQuality;
+use crate::slot_assignment::SlotAssignment;
+use crate::state_revisions::ControllerStatesDelta;
+use crate::time_series::MAX_RETENTION_MS;
+use crate::touch_zones::{self, TouchZone, TOUCH_CONTROLLER_ID};
+use crate::touchpad::{AbsInfo, TouchpadSide};
+use crate::uinput_output::{PassthroughConfig, PassthroughMapper, VirtualGamepadStatus, MAX_NAME_LEN, UINPUT_PATH};
+use crate::virtual_pads::{self, VirtualPadPolicy, VirtualPadStatus};
+use std::collections::HashMap;
+use tauri::State;
+use tracing::warn;
+
+#[tauri::command]
+pub fn get_connected_controllers(
+    state: State<'_, AppState>,
+) -> Result<HashMap<usize, ControllerState>, AppError> {
+    timed_command!("get_connected_controllers", {
+        Ok(state.gamepad_manager.get_controller_states())
+    })
+}
+
+#[tauri::command]
+pub fn get_controller_state(
+    controller_id: usize,
+    state: State<'_, AppState>,
+) -> Result<Option<ControllerState>, AppError> {
+    timed_command!("get_controller_state", {
+        Ok(state.gamepad_manager.get_controller_state(controller_id))
+    })
+}
+
+#[tauri::command]
+pub fn get_controller_states_delta(
+    since_revision: u64,
+    state: State<'_, AppState>,
+) -> Result<ControllerStatesDelta, AppError> {
+    timed_command!("get_controller_states_delta", {
+        Ok(state.gamepad_manager.get_controller_states_delta(since_revision))
+    })
+}
+
+#[tauri::command]
+pub fn query_axis_time_series(
+    controller_id: usize,
+    axis_name: String,
+    from_ms: u64,
+    to_ms: u64,
+    max_points: usize,
+    state: State<'_, AppState>,
+) -> Result<Vec<(u64, f32)>, AppError> {
+    timed_command!("query_axis_time_series", {
+        if from_ms > to_ms {
+            return Err(AppError::invalid_argument("from_ms must not be after to_ms"));
+        }
+        Ok(state.gamepad_manager.query_axis_time_series(controller_id, &axis_name, from_ms, to_ms, max_points))
+    })
+}
+
+#[tauri::command]
+pub fn configure_time_series_retention(
+    retention_ms: u64,
+    state: State<'_, AppState>,
+) -> Result<(), AppError> {
+    timed_command!("configure_time_series_retention", {
+        if retention_ms == 0 || retention_ms > MAX_RETENTION_MS {
+            return Err(AppError::invalid_argument(format!("Retention must be between 1 and {} ms", MAX_RETENTION_MS)));
+        }
+        state.gamepad_manager.configure_time_series_retention(retention_ms);
+        Ok(())
+    })
+}
+
+#[tauri::command]
+pub fn is_rumble_supported(
+    controller_id: usize,
+    state: State<'_, AppState>,
+) -> Result<bool, AppError> {
+    timed_command!("is_rumble_supported", {
+        Ok(state.gamepad_manager.is_rumble_supported(controller_id))
+    })
+}
+
+#[tauri::command]
+pub fn rumble_controller(
+    controller_id: usize,
+    strong: f32,
+    weak: f32,
+    duration_ms: u32,
+    state: State<'_, AppState>,
+) -> Result<(), AppError> {
+    timed_command!("rumble_controller", {
+        Ok(state.gamepad_manager.rumble(controller_id, strong, weak, duration_ms)?)
+    })
+}
+
+#[tauri::command]
+pub fn get_snapshots_since(
+    timestamp_ms: u64,
+    state: State<'_, AppState>,
+) -> Result<Vec<AxisSnapshot>, AppError> {
+    timed_command!("get_snapshots_since", {
+        Ok(state.gamepad_manager.get_snapshots_since(timestamp_ms))
+    })
+}
+
+#[tauri::command]
+pub fn configure_snapshot_recorder(
+    rate_hz: u32,
+    max_snapshots: usize,
+    state: State<'_, AppState>,
+) -> Result<(), AppError> {
+    timed_command!("configure_snapshot_recorder", {
+        state.gamepad_manager.configure_snapshot_recorder(rate_hz, max_snapshots)
+            .map_err(AppError::invalid_argument)
+    })
+}
+
+#[tauri::command]
+pub fn export_snapshots_csv(
+    state: State<'_, AppState>,
+) -> Result<String, AppError> {
+    timed_command!("export_snapshots_csv", {
+        Ok(state.gamepad_manager.export_snapshots_csv())
+    })
+}
+
+#[tauri::command]
+pub fn get_time_series_memory_usage(
+    state: State<'_, AppState>,
+) -> Result<usize, AppError> {
+    timed_command!("get_time_series_memory_usage", {
+        Ok(state.gamepad_manager.get_time_series_memory_usage())
+    })
+}
+
+#[tauri::command]
+pub fn set_controller_layout_override(
+    controller_id: usize,
+    layout: Option<ControllerLayout>,
+    state: State<'_, AppState>,
+) -> Result<(), AppError> {
+    timed_command!("set_controller_layout_override", {
+        let gamepad = state.gamepad_manager.get_gamepad_info(controller_id)
+            .ok_or_else(|| AppError::device_not_found(format!("Controller {} not found", controller_id)))?;
+        let capability_hash = virtual_pads::capability_hash_of(&gamepad, &state.evdev_manager.get_detected_devices());
+    
+        // Saved under the UUID as well, which still matches once the pad is renamed
+        let keys: Vec<String> = capability_hash.into_iter().chain([gamepad.uuid]).collect();
+        state.settings.update(|settings| {
+            for key in &keys {
+                match layout {
+                    Some(layout) => settings.layout_overrides.insert(key.clone(), layout),
+                    None => settings.layout_overrides.remove(key),
+                };
+            }
+        })?;
+        Ok(())
+    })
+}
+
+#[tauri::command]
+pub fn get_touch_zones(
+    state: State<'_, AppState>,
+) -> Result<Vec<TouchZone>, AppError> {
+    timed_command!("get_touch_zones", {
+        Ok(state.settings.get().touch_zones)
+    })
+}
+
+#[tauri::command]
+pub fn set_touch_zones(
+    zones: Vec<TouchZone>,
+    state: State<'_, AppState>,
+) -> Result<Vec<TouchZone>, AppError> {
+    timed_command!("set_touch_zones", {
+        touch_zones::validate_zones(&zones).map_err(AppError::invalid_argument)?;
+        state.settings.update(|settings| settings.touch_zones = zones.clone())?;
+        Ok(zones)
+    })
+}
+
+/// Presses or releases `button` as synthetic input, on the touch controller unless another
+/// controller is named, so it goes through combos and forwarding like a hardware button.
+#[tauri::command]
+pub fn inject_input_event(
+    button: String,
+    pressed: bool,
+    controller_id: Option<usize>,
+    state: State<'_, AppState>,
+) -> Result<(), AppError> {
+    timed_command!("inject_input_event", {
+        if button.trim().is_empty() {
+            return Err(AppError::invalid_argument("Injected input needs a button name"));
+        }
+        touch_zones::inject_button(&state.gamepad_manager, controller_id.unwrap_or(TOUCH_CONTROLLER_ID), button, pressed);
+        Ok(())
+    })
+}
+
+/// Steam virtual pads, the controllers they mirror, and which side's input is dropped.
+#[tauri::command]
+pub fn get_virtual_pads(
+    state: State<'_, AppState>,
+) -> Result<VirtualPadStatus, AppError> {
+    timed_command!("get_virtual_pads", {
+        Ok(VirtualPadStatus::collect(&state.virtual_pads, &state.evdev_manager.get_detected_devices()))
+    })
+}
+
+#[tauri::command]
+pub fn set_virtual_pad_policy(
+    policy: VirtualPadPolicy,
+    state: State<'_, AppState>,
+) -> Result<VirtualPadStatus, AppError> {
+    timed_command!("set_virtual_pad_policy", {
+        state.settings.update(|settings| settings.virtual_pad_policy = policy)?;
+        let devices = state.evdev_manager.get_detected_devices();
+        state.virtual_pads.set_policy(policy);
+        state.virtual_pads.refresh(&devices, &state.gamepad_manager.gamepad_infos());
+        Ok(VirtualPadStatus::collect(&state.virtual_pads, &devices))
+    })
+}
+
+#[tauri::command]
+pub fn get_virtual_gamepad(state: State<'_, AppState>) -> Result<VirtualGamepadStatus, AppError> {
+    timed_command!("get_virtual_gamepad", {
+        Ok(state.virtual_gamepad.status())
+    })
+}
+
+/// Creates a uinput gamepad with the standard buttons and axes, replacing the one made
+/// before. It stays until destroyed or the app exits.
+#[tauri::command]
+pub fn create_virtual_gamepad(
+    name: String,
+    state: State<'_, AppState>,
+) -> Result<VirtualGamepadStatus, AppError> {
+    timed_command!("create_virtual_gamepad", {
+        let name = name.trim();
+        if name.is_empty() || name.len() > MAX_NAME_LEN {
+            return Err(AppError::invalid_argument(format!("The name must be 1 to {} bytes long", MAX_NAME_LEN)));
+        }
+        let access = permissions::uinput_access();
+        if !access.readable_by_us {
+            return Err(AppError::permission(format!(
+                "Can't open {}: {}. {}",
+                UINPUT_PATH,
+                access.open_error.unwrap_or_default(),
+                permissions::uinput_remediation()
+            )));
+        }
+        let status = state.virtual_gamepad.create(name)?;
+        // Its own node is ignored as input from the start
+        state.virtual_pads.refresh(&state.evdev_manager.get_detected_devices(), &state.gamepad_manager.gamepad_infos());
+        Ok(status)
+    })
+}
+
+/// Returns whether there was a virtual gamepad to destroy.
+#[tauri::command]
+pub fn destroy_virtual_gamepad(state: State<'_, AppState>) -> Result<bool, AppError> {
+    timed_command!("destroy_virtual_gamepad", {
+        Ok(state.virtual_gamepad.destroy())
+    })
+}
+
+/// Forwards an evdev node's input to the virtual gamepad, remapped and with the dead zone
+/// applied, or stops forwarding with `None`. `exclusive` grabs the node so the rest of the
+/// system sees only the virtual gamepad.
+#[tauri::command]
+pub fn set_gamepad_passthrough(
+    config: Option<PassthroughConfig>,
+    state: State<'_, AppState>,
+) -> Result<VirtualGamepadStatus, AppError> {
+    timed_command!("set_gamepad_passthrough", {
+        let output = &state.virtual_gamepad;
+        let Some(config) = config else {
+            return Ok(output.stop_passthrough());
+        };
+        if !output.status().active {
+            return Err(AppError::invalid_argument("Create the virtual gamepad first"));
+        }
+        let ranges = state.evdev_manager.get_axis_ranges(&config.source_device_path).map_err(AppError::device_not_found)?;
+        let mapper = PassthroughMapper::new(&config, ranges).map_err(AppError::invalid_argument)?;
+        Ok(output.start_passthrough(config, mapper, state.evdev_manager.clone())?)
+    })
+}
+
+#[tauri::command]
+pub fn get_key_emulation_config(
+    state: State<'_, AppState>,
+) -> Result<KeyEmulationConfig, AppError> {
+    timed_command!("get_key_emulation_config", {
+        Ok(state.settings.get().key_emulation)
+    })
+}
+
+/// Replaces the keyboard and mouse emulation rules and off chord. Every key name is
+/// checked here, so a rule that's saved can always be acted on.
+#[tauri::command]
+pub fn set_key_emulation_config(
+    config: KeyEmulationConfig,
+    state: State<'_, AppState>,
+) -> Result<KeyEmulationConfig, AppError> {
+    timed_command!("set_key_emulation_config", {
+        key_emulation::validate_config(&config).map_err(AppError::invalid_argument)?;
+        state.settings.update(|settings| settings.key_emulation = config.clone())?;
+        state.key_emulator.set_config(&config);
+        Ok(config)
+    })
+}
+
+#[tauri::command]
+pub fn get_key_emulation_status(state: State<'_, AppState>) -> Result<KeyEmulationStatus, AppError> {
+    timed_command!("get_key_emulation_status", {
+        Ok(state.key_emulator.status())
+    })
+}
+
+/// Turns keyboard and mouse emulation on, creating its uinput keyboard and mouse, or off,
+/// destroying them. It's never on at startup.
+#[tauri::command]
+pub fn set_key_emulation_enabled(
+    enabled: bool,
+    state: State<'_, AppState>,
+) -> Result<KeyEmulationStatus, AppError> {
+    timed_command!("set_key_emulation_enabled", {
+        let emulator = &state.key_emulator;
+        if !enabled {
+            emulator.disable();
+            return Ok(emulator.status());
+        }
+        let access = permissions::uinput_access();
+        if !access.readable_by_us {
+            return Err(AppError::permission(format!(
+                "Can't open {}: {}. {}",
+                UINPUT_PATH,
+                access.open_error.unwrap_or_default(),
+                permissions::uinput_remediation()
+            )));
+        }
+        Ok(emulator.enable()?)
+    })
+}
+
+/// Names of the MIDI output ports other software offers, for `open_midi_output`.
+#[tauri::command]
+pub fn list_midi_outputs() -> Result<Vec<String>, AppError> {
+    timed_command!("list_midi_outputs", {
+        Ok(midi_output::list_outputs()?)
+    })
+}
+
+/// Opens the MIDI output `name` matches, or `virtual` for a port of the app's own that
+/// other software subscribes to. The choice is saved and reopened at startup.
+#[tauri::command]
+pub fn open_midi_output(
+    name: String,
+    state: State<'_, AppState>,
+) -> Result<MidiStatus, AppError> {
+    timed_command!("open_midi_output", {
+        let name = name.trim().to_string();
+        if name.is_empty() {
+            return Err(AppError::invalid_argument("Name a MIDI output, or virtual"));
+        }
+        if name != VIRTUAL_PORT && midi_output::match_port(&name, &midi_output::list_outputs()?).is_none() {
+            return Err(AppError::device_not_found(format!("No MIDI output matches '{}'", name)));
+        }
+        let status = state.midi_output.open(&name)?;
+        state.settings.update(|settings| settings.midi.output = Some(name.clone()))?;
+        Ok(status)
+    })
+}
+
+/// Returns whether a MIDI output was open. It stays closed across restarts.
+#[tauri::command]
+pub fn close_midi_output(
+    state: State<'_, AppState>,
+) -> Result<bool, AppError> {
+    timed_command!("close_midi_output", {
+        state.settings.update(|settings| settings.midi.output = None)?;
+        Ok(state.midi_output.close())
+    })
+}
+
+#[tauri::command]
+pub fn get_midi_status(
+    state: State<'_, AppState>,
+) -> Result<MidiStatus, AppError> {
+    timed_command!("get_midi_status", {
+        Ok(state.midi_output.status())
+    })
+}
+
+#[tauri::command]
+pub fn get_midi_rules(
+    state: State<'_, AppState>,
+) -> Result<Vec<MidiRule>, AppError> {
+    timed_command!("get_midi_rules", {
+        Ok(state.settings.get().midi.rules)
+    })
+}
+
+/// Replaces the rules turning buttons into notes and axes into control changes.
+#[tauri::command]
+pub fn set_midi_rules(
+    rules: Vec<MidiRule>,
+    state: State<'_, AppState>,
+) -> Result<Vec<MidiRule>, AppError> {
+    timed_command!("set_midi_rules", {
+        midi_output::validate_rules(&rules).map_err(AppError::invalid_argument)?;
+        state.settings.update(|settings| settings.midi.rules = rules.clone())?;
+        state.midi_output.set_rules(&rules);
+        Ok(rules)
+    })
+}
+
+/// Compiles `source` and runs it on every controller event from now on, in place of any
+/// script called `name`. Compile errors come back as invalid arguments, with the line and
+/// column. Saved in settings.
+#[tauri::command]
+pub fn set_event_script(
+    name: String,
+    source: String,
+    state: State<'_, AppState>,
+) -> Result<EventScriptStatus, AppError> {
+    timed_command!("set_event_script", {
+        let script = EventScript { name: name.trim().to_string(), source, enabled: true };
+        let status = state.event_scripts.set(script.clone()).map_err(AppError::invalid_argument)?;
+        state.settings.update(|settings| {
+            match settings.event_scripts.iter_mut().find(|saved| saved.name == script.name) {
+                Some(saved) => *saved = script.clone(),
+                None => settings.event_scripts.push(script.clone()),
+            }
+        })?;
+        Ok(status)
+    })
+}
+
+#[tauri::command]
+pub fn remove_event_script(
+    name: String,
+    state: State<'_, AppState>,
+) -> Result<bool, AppError> {
+    timed_command!("remove_event_script", {
+        state.settings.update(|settings| settings.event_scripts.retain(|saved| saved.name != name))?;
+        Ok(state.event_scripts.remove(&name))
+    })
+}
+
+/// Turns a script on or off. Turning on a script the app turned off for failing gives it
+/// a clean slate.
+#[tauri::command]
+pub fn set_event_script_enabled(
+    name: String,
+    enabled: bool,
+    state: State<'_, AppState>,
+) -> Result<EventScriptStatus, AppError> {
+    timed_command!("set_event_script_enabled", {
+        let status = state.event_scripts.set_enabled(&name, enabled).map_err(AppError::invalid_argument)?;
+        state.settings.update(|settings| {
+            if let Some(saved) = settings.event_scripts.iter_mut().find(|saved| saved.name == name) {
+                saved.enabled = enabled;
+            }
+        })?;
+        Ok(status)
+    })
+}
+
+#[tauri::command]
+pub fn list_event_scripts(
+    state: State<'_, AppState>,
+) -> Result<Vec<EventScriptStatus>, AppError> {
+    timed_command!("list_event_scripts", {
+        Ok(state.event_scripts.statuses())
+    })
+}
+
+#[tauri::command]
+pub fn get_event_script_examples() -> Result<Vec<EventScript>, AppError> {
+    timed_command!("get_event_script_examples", {
+        Ok(EXAMPLE_SCRIPTS.iter()
+            .map(|(name, source)| EventScript { name: name.to_string(), source: source.to_string(), enabled: true })
+            .collect())
+    })
+}
+
+/// Runs `command` with `args`, feeding it every controller event as a JSON line on stdin
+/// and carrying out the actions it writes to stdout (see the README for the protocol).
+/// It's restarted if it exits or stops answering heartbeats, until stopped.
+#[tauri::command]
+pub fn start_external_processor(
+    command: String,
+    args: Option<Vec<String>>,
+    app: tauri::AppHandle,
+    state: State<'_, AppState>,
+) -> Result<ExternalProcessorStatus, AppError> {
+    timed_command!("start_external_processor", {
+        let command = command.trim().to_string();
+        if command.is_empty() {
+            return Err(AppError::invalid_argument("Name the program to run"));
+        }
+        Ok(state.external_processor.start(&app, &command, args.unwrap_or_default())?)
+    })
+}
+
+/// Kills the external processor straight away. Returns its final status, if one ran.
+#[tauri::command]
+pub fn stop_external_processor(
+    state: State<'_, AppState>,
+) -> Result<Option<ExternalProcessorStatus>, AppError> {
+    timed_command!("stop_external_processor", {
+        Ok(state.external_processor.stop())
+    })
+}
+
+#[tauri::command]
+pub fn get_external_processor_status(
+    state: State<'_, AppState>,
+) -> Result<ExternalProcessorStatus, AppError> {
+    timed_command!("get_external_processor_status", {
+        Ok(state.external_processor.status())
+    })
+}
+
+/// Sends pixels to the OPC server at `host` (`port` defaults to 7890), reconnecting while
+/// it's away and emitting `opc-status` as it comes and goes. Keeps the generator already
+/// chosen if it fits the new pixel count. Saved and reconnected at startup.
+#[tauri::command]
+pub fn configure_opc(
+    host: String,
+    port: Option<u16>,
+    channel: u8,
+    pixel_count: usize,
+    frame_rate: Option<u32>,
+    app: tauri::AppHandle,
+    state: State<'_, AppState>,
+) -> Result<OpcStatus, AppError> {
+    timed_command!("configure_opc", {
+        let saved = state.settings.get().opc;
+        let mut config = OpcConfig {
+            host: host.trim().to_string(),
+            port: port.unwrap_or(DEFAULT_PORT),
+            channel,
+            pixel_count,
+            frame_rate: frame_rate.or(saved.as_ref().map(|saved| saved.frame_rate)).unwrap_or(DEFAULT_FRAME_RATE),
+            generator: saved.and_then(|saved| saved.generator),
+        };
+        if config.generator.as_ref().is_some_and(|generator| generator.validate(pixel_count).is_err()) {
+            warn!("Dropping the OPC generator, which doesn't fit {} pixels", pixel_count);
+            config.generator = None;
+        }
+        config.validate().map_err(AppError::invalid_argument)?;
+        let status = state.opc_output.configure(&app, config.clone())?;
+        state.settings.update(|settings| settings.opc = Some(config.clone()))?;
+        Ok(status)
+    })
+}
+
+/// Chooses what paints the pixels each frame; `None` leaves them to `send_opc_frame`.
+#[tauri::command]
+pub fn set_opc_generator(
+    generator: Option<OpcGenerator>,
+    state: State<'_, AppState>,
+) -> Result<OpcStatus, AppError> {
+    timed_command!("set_opc_generator", {
+        let status = state.opc_output.set_generator(generator.clone()).map_err(AppError::invalid_argument)?;
+        state.settings.update(|settings| {
+            if let Some(opc) = settings.opc.as_mut() {
+                opc.generator = generator.clone();
+            }
+        })?;
+        Ok(status)
+    })
+}
+
+/// Sends one frame of `[r, g, b]` pixels now, for scripted use.
+#[tauri::command]
+pub fn send_opc_frame(
+    pixels: Vec<[u8; 3]>,
+    app: tauri::AppHandle,
+    state: State<'_, AppState>,
+) -> Result<(), AppError> {
+    timed_command!("send_opc_frame", {
+        Ok(state.opc_output.send_frame(&app, &pixels)?)
+    })
+}
+
+/// Disconnects from the OPC server. Returns whether output was configured. It stays off
+/// across restarts.
+#[tauri::command]
+pub fn stop_opc(
+    state: State<'_, AppState>,
+) -> Result<bool, AppError> {
+    timed_command!("stop_opc", {
+        state.settings.update(|settings| settings.opc = None)?;
+        Ok(state.opc_output.stop())
+    })
+}
+
+#[tauri::command]
+pub fn get_opc_status(
+    state: State<'_, AppState>,
+) -> Result<OpcStatus, AppError> {
+    timed_command!("get_opc_status", {
+        Ok(state.opc_output.status())
+    })
+}
+
+/// Reads the show file at `path` (see the README for the format), paused at the start.
+/// Cues out of time order, or starting while a lasting one runs, are refused with the
+/// cue that's wrong.
+#[tauri::command]
+pub fn load_show(
+    path: String,
+    app: tauri::AppHandle,
+    state: State<'_, AppState>,
+) -> Result<ShowStatus, AppError> {
+    timed_command!("load_show", {
+        let path = path.trim().to_string();
+        if path.is_empty() {
+            return Err(AppError::invalid_argument("Name a show file"));
+        }
+        state.show_playback.load(&app, &path).map_err(AppError::invalid_argument)
+    })
+}
+
+/// Plays the loaded show from the playhead, emitting `show-progress` as it goes.
+#[tauri::command]
+pub fn start_show(
+    app: tauri::AppHandle,
+    state: State<'_, AppState>,
+) -> Result<ShowStatus, AppError> {
+    timed_command!("start_show", {
+        Ok(state.show_playback.start(&app)?)
+    })
+}
+
+#[tauri::command]
+pub fn pause_show(
+    app: tauri::AppHandle,
+    state: State<'_, AppState>,
+) -> Result<ShowStatus, AppError> {
+    timed_command!("pause_show", {
+        Ok(state.show_playback.pause(&app)?)
+    })
+}
+
+/// Moves the playhead to `ms` without sending the cues in between.
+#[tauri::command]
+pub fn seek_show(
+    ms: u64,
+    app: tauri::AppHandle,
+    state: State<'_, AppState>,
+) -> Result<ShowStatus, AppError> {
+    timed_command!("seek_show", {
+        Ok(state.show_playback.seek(&app, ms)?)
+    })
+}
+
+#[tauri::command]
+pub fn get_show_status(
+    state: State<'_, AppState>,
+) -> Result<ShowStatus, AppError> {
+    timed_command!("get_show_status", {
+        Ok(state.show_playback.status())
+    })
+}
+
+/// Turns the pre-`controller-event` channels (`gamepad-input`, `evdev-gamepad-input`)
+/// on or off.
+#[tauri::command]
+pub fn set_legacy_events_enabled(
+    enabled: bool,
+    state: State<'_, AppState>,
+) -> Result<(), AppError> {
+    timed_command!("set_legacy_events_enabled", {
+        state.settings.update(|settings| settings.disable_legacy_events = !enabled)?;
+        controller_event::set_legacy_events_enabled(enabled);
+        Ok(())
+    })
+}
+
+/// Subscribes `consumer` (the built-in frontend when omitted) to `channels`, once per
+/// call. Events on channels nobody is subscribed to aren't emitted, though the frontend
+/// gets every channel until its first subscription.
+#[tauri::command]
+pub fn subscribe_events(
+    channels: Vec<EventChannel>,
+    consumer: Option<String>,
+) -> Result<(), AppError> {
+    timed_command!("subscribe_events", {
+        emit_interest::interest().subscribe(consumer.as_deref().unwrap_or(FRONTEND_CONSUMER), &channels);
+        Ok(())
+    })
+}
+
+/// Undoes one `subscribe_events` call with the same channels.
+#[tauri::command]
+pub fn unsubscribe_events(
+    channels: Vec<EventChannel>,
+    consumer: Option<String>,
+) -> Result<(), AppError> {
+    timed_command!("unsubscribe_events", {
+        emit_interest::interest().unsubscribe(consumer.as_deref().unwrap_or(FRONTEND_CONSUMER), &channels);
+        Ok(())
+    })
+}
+
+/// Stops processing input until resumed, across restarts. `Full` (the default) drops
+/// input before it touches state; `ForwardingOnly` keeps the UI live but sends nothing.
+/// Events still waiting in the batcher are discarded rather than sent.
+#[tauri::command]
+pub fn pause_input_processing(
+    app: tauri::AppHandle,
+    mode: Option<PauseMode>,
+) -> Result<(), AppError> {
+    timed_command!("pause_input_processing", {
+        Ok(processing_pause::pause(&app, mode.unwrap_or_default())?)
+    })
+}
+
+#[tauri::command]
+pub fn resume_input_processing(
+    app: tauri::AppHandle,
+) -> Result<(), AppError> {
+    timed_command!("resume_input_processing", {
+        Ok(processing_pause::resume(&app)?)
+    })
+}
+
+#[tauri::command]
+pub fn get_input_processing_pause() -> Result<Option<PauseMode>, AppError> {
+    timed_command!("get_input_processing_pause", {
+        Ok(processing_pause::pause_mode())
+    })
+}
+
+#[tauri::command]
+pub fn get_poll_cadence() -> Result<PollCadenceConfig, AppError> {
+    timed_command!("get_poll_cadence", {
+        Ok(poll_cadence::cadence().config())
+    })
+}
+
+/// Sets and saves the adaptive polling thresholds.
+#[tauri::command]
+pub fn set_poll_cadence(
+    config: PollCadenceConfig,
+    state: State<'_, AppState>,
+) -> Result<(), AppError> {
+    timed_command!("set_poll_cadence", {
+        poll_cadence::cadence().configure(config).map_err(AppError::invalid_argument)?;
+        Ok(state.settings.update(|settings| settings.poll_cadence = config)?)
+    })
+}
+
+#[tauri::command]
+pub fn get_event_bus_config(
+    state: State<'_, AppState>,
+) -> Result<EventBusConfig, AppError> {
+    timed_command!("get_event_bus_config", {
+        Ok(state.event_bus.config())
+    })
+}
+
+/// Sets and saves the input queue size and overflow policies.
+#[tauri::command]
+pub fn set_event_bus_config(
+    config: EventBusConfig,
+    state: State<'_, AppState>,
+) -> Result<(), AppError> {
+    timed_command!("set_event_bus_config", {
+        state.event_bus.configure(config).map_err(AppError::invalid_argument)?;
+        Ok(state.settings.update(|settings| settings.event_bus = config)?)
+    })
+}
+
+#[tauri::command]
+pub fn assign_controller_to_slot(
+    slot_name: String,
+    controller_id: usize,
+    state: State<'_, AppState>,
+) -> Result<Vec<SlotAssignment>, AppError> {
+    timed_command!("assign_controller_to_slot", {
+        if state.gamepad_manager.get_controller_state(controller_id).is_none() {
+            return Err(AppError::device_not_found(format!("Controller {} is not connected", controller_id)));
+        }
+        state.slot_assignments.assign(&slot_name, controller_id)?;
+        Ok(state.slot_assignments.get())
+    })
+}
+
+#[tauri::command]
+pub fn unassign_controller_slot(
+    controller_id: usize,
+    state: State<'_, AppState>,
+) -> Result<Vec<SlotAssignment>, AppError> {
+    timed_command!("unassign_controller_slot", {
+        state.slot_assignments.unassign(controller_id);
+        Ok(state.slot_assignments.get())
+    })
+}
+
+#[tauri::command]
+pub fn get_slot_assignments(
+    state: State<'_, AppState>,
+) -> Result<Vec<SlotAssignment>, AppError> {
+    timed_command!("get_slot_assignments", {
+        Ok(state.slot_assignments.get())
+    })
+}
+
+#[tauri::command]
+pub fn create_ensemble(
+    name: String,
+    config: EnsembleConfig,
+    state: State<'_, AppState>,
+) -> Result<String, AppError> {
+    timed_command!("create_ensemble", {
+        state.gamepad_manager.create_ensemble(&name, config)
+            .map_err(AppError::invalid_argument)
+    })
+}
+
+#[tauri::command]
+pub fn get_ensemble_state(
+    name: String,
+    state: State<'_, AppState>,
+) -> Result<ControllerState, AppError> {
+    timed_command!("get_ensemble_state", {
+        state.gamepad_manager.get_ensemble_state(&name)
+            .map_err(AppError::invalid_argument)
+    })
+}
+
+#[tauri::command]
+pub fn update_ensemble(
+    name: String,
+    config: EnsembleConfig,
+    state: State<'_, AppState>,
+) -> Result<(), AppError> {
+    timed_command!("update_ensemble", {
+        state.gamepad_manager.update_ensemble(&name, config)
+            .map_err(AppError::invalid_argument)
+    })
+}
+
+#[tauri::command]
+pub fn delete_ensemble(
+    name: String,
+    state: State<'_, AppState>,
+) -> Result<(), AppError> {
+    timed_command!("delete_ensemble", {
+        state.gamepad_manager.delete_ensemble(&name)
+            .map_err(AppError::invalid_argument)
+    })
+}
+
+#[tauri::command]
+pub fn register_combination_effect(
+    spec: CombinationLightEffect,
+    state: State<'_, AppState>,
+) -> Result<(), AppError> {
+    timed_command!("register_combination_effect", {
+        state.gamepad_manager.register_combination_effect(spec)
+            .map_err(AppError::invalid_argument)
+    })
+}
+
+#[tauri::command]
+pub fn unregister_combination_effect(
+    id: String,
+    state: State<'_, AppState>,
+) -> Result<(), AppError> {
+    timed_command!("unregister_combination_effect", {
+        if state.gamepad_manager.unregister_combination_effect(&id) {
+            Ok(())
+        } else {
+            Err(AppError::invalid_argument(format!("No combination effect with id '{}'", id)))
+        }
+    })
+}
+
+#[tauri::command]
+pub fn list_combination_effects(
+    state: State<'_, AppState>,
+) -> Result<Vec<CombinationLightEffect>, AppError> {
+    timed_command!("list_combination_effects", {
+        Ok(state.gamepad_manager.list_combination_effects())
+    })
+}
+
+#[tauri::command]
+pub fn set_ghost_filter_interval(
+    button_name: String,
+    min_interval_us: u64,
+    state: State<'_, AppState>,
+) -> Result<(), AppError> {
+    timed_command!("set_ghost_filter_interval", {
+        state.gamepad_manager.set_ghost_filter_interval(&button_name, min_interval_us);
+        Ok(())
+    })
+}
+
+#[tauri::command]
+pub fn set_global_ghost_filter_interval(
+    min_interval_us: u64,
+    state: State<'_, AppState>,
+) -> Result<(), AppError> {
+    timed_command!("set_global_ghost_filter_interval", {
+        state.gamepad_manager.set_global_ghost_filter_interval(min_interval_us);
+        Ok(())
+    })
+}
+
+/// Sets how a stick's two axes share a dead zone, instead of gilrs' per-axis default.
+#[tauri::command]
+pub fn set_stick_dead_zone_shape(
+    controller_id: usize,
+    stick: StickSide,
+    shape: DeadZoneShape,
+    state: State<'_, AppState>,
+) -> Result<(), AppError> {
+    timed_command!("set_stick_dead_zone_shape", {
+        state.gamepad_manager.set_stick_dead_zone_shape(controller_id, stick, shape);
+        Ok(())
+    })
+}
+
+/// Trips `action` when a controller sends more than `max_eps` events per second,
+/// averaged over `window_ms`.
+#[tauri::command]
+pub fn configure_flood_alarm(
+    max_eps: u32,
+    window_ms: u64,
+    action: FloodAction,
+    state: State<'_, AppState>,
+) -> Result<(), AppError> {
+    timed_command!("configure_flood_alarm", {
+        state.gamepad_manager.configure_flood_alarm(max_eps, window_ms, action)
+            .map_err(AppError::invalid_argument)
+    })
+}
+
+#[tauri::command]
+pub fn get_flood_alarm_status(state: State<'_, AppState>) -> Result<FloodAlarmStatus, AppError> {
+    timed_command!("get_flood_alarm_status", {
+        Ok(state.gamepad_manager.get_flood_alarm_status())
+    })
+}
+
+#[tauri::command]
+pub fn reset_flood_alarm(
+    controller_id: usize,
+    state: State<'_, AppState>,
+) -> Result<bool, AppError> {
+    timed_command!("reset_flood_alarm", {
+        Ok(state.gamepad_manager.reset_flood_alarm(controller_id))
+    })
+}
+
+#[tauri::command]
+pub fn start_recording(
+    path: String,
+    state: State<'_, AppState>,
+) -> Result<(), AppError> {
+    timed_command!("start_recording", {
+        Ok(state.session.start_recording(std::path::PathBuf::from(path))?)
+    })
+}
+
+#[tauri::command]
+pub fn stop_recording(state: State<'_, AppState>) -> Result<RecordingSummary, AppError> {
+    timed_command!("stop_recording", {
+        Ok(state.session.stop_recording()?)
+    })
+}
+
+#[tauri::command]
+pub fn replay_recording(
+    path: String,
+    speed: Option<f64>,
+    looping: Option<bool>,
+    live_input: Option<LiveInputMode>,
+    app: tauri::AppHandle,
+    state: State<'_, AppState>,
+) -> Result<ReplayProgress, AppError> {
+    timed_command!("replay_recording", {
+        let options = ReplayOptions {
+            speed: speed.unwrap_or(1.0),
+            looping: looping.unwrap_or(false),
+            live_input: live_input.unwrap_or_default(),
+        };
+        state.session.start_replay(
+            app,
+            state.gamepad_manager.clone(),
+            state.evdev_manager.clone(),
+            std::path::PathBuf::from(path),
+            options,
+        )
+        .map_err(AppError::from)
+    })
+}
+
+/// Returns the recording at `path` as base64-encoded gzip.
+#[tauri::command]
+pub fn export_recording_compressed(path: String) -> Result<String, AppError> {
+    timed_command!("export_recording_compressed", {
+        Ok(recording::export_compressed(std::path::Path::new(&path))?)
+    })
+}
+
+#[tauri::command]
+pub fn import_recording_compressed(data: String) -> Result<Vec<RecordedEvent>, AppError> {
+    timed_command!("import_recording_compressed", {
+        Ok(recording::import_compressed(&data)?)
+    })
+}
+
+/// Appends every controller event, and with `include_send_results` every light server
+/// request's outcome, to `path` as `jsonl` or `csv`. The stream is saved, so it carries
+/// on in the same file after a restart until stopped.
+#[tauri::command]
+pub fn start_event_stream(
+    path: String,
+    format: StreamFormat,
+    include_send_results: Option<bool>,
+    max_file_bytes: Option<u64>,
+    min_free_bytes: Option<u64>,
+    state: State<'_, AppState>,
+) -> Result<EventStreamStatus, AppError> {
+    timed_command!("start_event_stream", {
+        let defaults = EventStreamConfig::default();
+        let config = EventStreamConfig {
+            path,
+            format,
+            include_send_results: include_send_results.unwrap_or(defaults.include_send_results),
+            max_file_bytes: max_file_bytes.unwrap_or(defaults.max_file_bytes),
+            min_free_bytes: min_free_bytes.unwrap_or(defaults.min_free_bytes),
+        };
+        config.validate().map_err(AppError::invalid_argument)?;
+        let status = state.event_stream.start(config.clone())?;
+        state.settings.update(|settings| settings.event_stream = Some(config))?;
+        Ok(status)
+    })
+}
+
+#[tauri::command]
+pub fn stop_event_stream(
+    state: State<'_, AppState>,
+) -> Result<EventStreamStatus, AppError> {
+    timed_command!("stop_event_stream", {
+        state.settings.update(|settings| settings.event_stream = None)?;
+        Ok(state.event_stream.stop()?)
+    })
+}
+
+#[tauri::command]
+pub fn get_event_stream_status(
+    state: State<'_, AppState>,
+) -> Result<EventStreamStatus, AppError> {
+    timed_command!("get_event_stream_status", {
+        Ok(state.event_stream.status())
+    })
+}
+
+#[tauri::command]
+pub fn stop_replay(state: State<'_, AppState>) -> Result<bool, AppError> {
+    timed_command!("stop_replay", {
+        Ok(state.session.stop_replay())
+    })
+}
+
+#[tauri::command]
+pub fn get_evdev_devices(
+    state: State<'_, AppState>,
+) -> Result<Vec<EvdevGamepadInfo>, AppError> {
+    timed_command!("get_evdev_devices", {
+        Ok(state.evdev_manager.get_detected_devices())
+    })
+}
+
+/// Turns the raw event dump of `device_path` on or off. It turns itself off after
+/// `duration_secs` (60 by default). Returns the dump's status while it's enabled.
+#[tauri::command]
+pub fn set_raw_dump(
+    device_path: String,
+    enabled: bool,
+    duration_secs: Option<u64>,
+    app: tauri::AppHandle,
+    state: State<'_, AppState>,
+) -> Result<Option<RawDumpStatus>, AppError> {
+    timed_command!("set_raw_dump", {
+        if !enabled {
+            state.evdev_manager.stop_raw_dump(&app, &device_path);
+            return Ok(None);
+        }
+        let duration_secs = duration_secs.unwrap_or(raw_dump::DEFAULT_RAW_DUMP_SECS);
+        if duration_secs == 0 || duration_secs > raw_dump::MAX_RAW_DUMP_SECS {
+            return Err(AppError::invalid_argument(format!("duration_secs must be between 1 and {}", raw_dump::MAX_RAW_DUMP_SECS)));
+        }
+        state.evdev_manager
+            .start_raw_dump(&app, &device_path, std::time::Duration::from_secs(duration_secs))
+            .map(Some)
+            .map_err(AppError::from)
+    })
+}
+
+#[tauri::command]
+pub fn set_axis_center_offset(
+    device_path: String,
+    axis_name: String,
+    center_raw: i32,
+    state: State<'_, AppState>,
+) -> Result<(), AppError> {
+    timed_command!("set_axis_center_offset", {
+        Ok(state.evdev_manager.set_axis_center_offset(&device_path, &axis_name, center_raw)?)
+    })
+}
+
+/// Samples the axis for `sample_ms` and stores the median as its center. The stick
+/// must be left untouched while it runs.
+#[tauri::command]
+pub async fn auto_detect_axis_center(
+    device_path: String,
+    axis_name: String,
+    sample_ms: u64,
+    state: State<'_, AppState>,
+) -> Result<i32, AppError> {
+    timed_command!("auto_detect_axis_center", async {
+        if sample_ms == 0 || sample_ms > evdev_types::MAX_CENTER_SAMPLE_MS {
+            return Err(AppError::invalid_argument(format!("sample_ms must be between 1 and {}", evdev_types::MAX_CENTER_SAMPLE_MS)));
+        }
+        let evdev_manager = state.evdev_manager.clone();
+        tauri::async_runtime::spawn_blocking(move || {
+            evdev_manager.auto_detect_axis_center(&device_path, &axis_name, sample_ms)
+        })
+        .await
+        .map_err(|e| format!("Axis sampling failed: {}", e))?
+        .map_err(AppError::from)
+    })
+}
+
+#[tauri::command]
+pub fn get_raw_dump_status(
+    state: State<'_, AppState>,
+) -> Result<Option<RawDumpStatus>, AppError> {
+    timed_command!("get_raw_dump_status", {
+        Ok(state.evdev_manager.get_raw_dump_status())
+    })
+}
+
+#[tauri::command]
+pub fn rescan_evdev_devices(
+    app: tauri::AppHandle,
+    state: State<'_, AppState>,
+) -> Result<Vec<EvdevGamepadInfo>, AppError> {
+    timed_command!("rescan_evdev_devices", {
+        state.evdev_manager.scan_for_gamepad_devices(&app)
+            .map_err(|e| format!("Failed to scan devices: {}", e))?;
+        Ok(state.evdev_manager.get_detected_devices())
+    })
+}
+
+/// Stores the evdev nodes scans should leave alone (capability hashes or names) and
+/// rescans so they drop out straight away.
+#[tauri::command]
+pub fn set_ignored_devices(
+    devices: Vec<String>,
+    app: tauri::AppHandle,
+    state: State<'_, AppState>,
+) -> Result<Vec<EvdevGamepadInfo>, AppError> {
+    timed_command!("set_ignored_devices", {
+        let devices: Vec<String> = devices.into_iter()
+            .map(|entry| entry.trim().to_string())
+            .filter(|entry| !entry.is_empty())
+            .collect();
+        state.settings.update(|settings| settings.ignored_devices = devices.clone())?;
+        state.evdev_manager.set_ignored_devices(devices);
+        state.evdev_manager.scan_for_gamepad_devices(&app)
+            .map_err(|e| format!("Failed to scan devices: {}", e))?;
+        Ok(state.evdev_manager.get_detected_devices())
+    })
+}
+
+#[tauri::command]
+pub fn get_input_backends(
+    state: State<'_, AppState>,
+) -> Result<Vec<BackendDebugInfo>, AppError> {
+    timed_command!("get_input_backends", {
+        Ok(state.backends.debug_info())
+    })
+}
+
+/// Rescans every enabled input backend.
+#[tauri::command]
+pub fn rescan_input_devices(
+    app: tauri::AppHandle,
+    state: State<'_, AppState>,
+) -> Result<Vec<BackendDebugInfo>, AppError> {
+    timed_command!("rescan_input_devices", {
+        let failures = state.backends.scan_all(&app);
+        if !failures.is_empty() {
+            let message = failures.iter()
+                .map(|(source, e)| format!("{}: {}", source.name(), e))
+                .collect::<Vec<_>>()
+                .join("; ");
+            return Err(AppError::internal(format!("Failed to scan devices: {}", message)));
+        }
+        Ok(state.backends.debug_info())
+    })
+}
+
+/// Tears down the gilrs instance and starts a fresh one, returning the gamepads it found.
+#[tauri::command]
+pub fn reinit_gamepad_backend(
+    state: State<'_, AppState>,
+) -> Result<Vec<GamepadInfo>, AppError> {
+    timed_command!("reinit_gamepad_backend", {
+        Ok(state.backends.while_paused(PollSource::Gilrs, || state.gamepad_manager.reinit_gilrs())?)
+    })
+}
+
+/// Enables or disables polling `source`, rescanning it when it comes back on.
+#[tauri::command]
+pub fn set_input_backend_enabled(
+    app: tauri::AppHandle,
+    source: PollSource,
+    enabled: bool,
+    state: State<'_, AppState>,
+) -> Result<(), AppError> {
+    timed_command!("set_input_backend_enabled", {
+        let Some(backend) = state.backends.get(source) else {
+            return Err(AppError::invalid_argument(format!("No {} input backend", source.name())));
+        };
+        state.backends.set_enabled(source, enabled);
+        if enabled {
+            if let Err(e) = backend.scan(&app) {
+                warn!("Failed to scan {} after enabling it: {}", source.name(), e);
+            }
+        }
+        Ok(state.settings.update(|settings| {
+            settings.disabled_backends.retain(|disabled| *disabled != source);
+            if !enabled {
+                settings.disabled_backends.push(source);
+            }
+        })?)
+    })
+}
+
+/// Every controller's current buttons and axes, across the enabled backends.
+#[tauri::command]
+pub fn get_backend_states(
+    state: State<'_, AppState>,
+) -> Result<Vec<BackendControllerState>, AppError> {
+    timed_command!("get_backend_states", {
+        Ok(state.backends.current_states())
+    })
+}
+
+#[tauri::command]
+pub fn get_device_signal_quality(
+    device_path: String,
+    state: State<'_, AppState>,
+) -> Result<DeviceSignalQuality, AppError> {
+    timed_command!("get_device_signal_quality", {
+        state.evdev_manager.get_signal_quality(&device_path)
+            .map_err(AppError::device_not_found)
+    })
+}
+
+#[tauri::command]
+pub fn get_touchpad_abs_info(
+    device_path: String,
+    side: TouchpadSide,
+    state: State<'_, AppState>,
+) -> Result<Option<(AbsInfo, AbsInfo)>, AppError> {
+    timed_command!("get_touchpad_abs_info", {
+        Ok(state.evdev_manager.get_touchpad_abs_info(&device_path, side))
+    })
+}
+
+#[tauri::command]
+pub fn get_device_open_failures(
+    state: State<'_, AppState>,
+) -> Result<Vec<DeviceOpenFailure>, AppError> {
+    timed_command!("get_device_open_failures", {
+        Ok(state.evdev_manager.get_open_failures())
+    })
+}
+
+/// Checks group membership against the node behind the latest permission failure, if any.
+#[tauri::command]
+pub fn check_input_group_membership(
+    state: State<'_, AppState>,
+) -> Result<InputGroupReport, AppError> {
+    timed_command!("check_input_group_membership", {
+        let failures = state.evdev_manager.get_open_failures();
+        let device_path = failures.iter().rev()
+            .find(|failure| failure.cause == OpenFailureCause::Permission)
+            .map(|failure| failure.device_path.as_str());
+        Ok(InputGroupReport::collect(device_path))
+    })
+}
+
+#[tauri::command]
+pub fn get_device_firmware_versions(
+    state: State<'_, AppState>,
+) -> Result<HashMap<String, u16>, AppError> {
+    timed_command!("get_device_firmware_versions", {
+        Ok(state.evdev_manager.get_device_firmware_versions())
+    })
+}
+
+/// Capability snapshots stored for a device across firmware versions, oldest first.
+#[tauri::command]
+pub fn get_capability_history(
+    device_fingerprint: String,
+    state: State<'_, AppState>,
+) -> Result<Vec<CapabilitySnapshot>, AppError> {
+    timed_command!("get_capability_history", {
+        Ok(state.evdev_manager.get_capability_history(&device_fingerprint))
+    })
+}
+
+#[tauri::command]
+pub fn diff_capability_snapshots(a: CapabilitySnapshot, b: CapabilitySnapshot) -> Result<CapabilityDiff, AppError> {
+    timed_command!("diff_capability_snapshots", {
+        Ok(capability_history::diff_snapshots(&a, &b))
+    })
+}
+
+/// Kernel name (e.g. `BTN_SOUTH`, `ABS_X`) for an evdev event's code.
+#[tauri::command]
+pub fn lookup_key_code(event_type: String, code: u16) -> Result<Option<String>, AppError> {
+    timed_command!("lookup_key_code", {
+        Ok(key_codes::lookup(&event_type, code))
+    })
+}
+
+#[tauri::command]
+pub fn list_all_key_codes() -> Result<Vec<(u16, String)>, AppError> {
+    timed_command!("list_all_key_codes", {
+        Ok(key_codes::all_key_codes())
+    })
+}
+
+#[tauri::command]
+pub fn list_all_abs_codes() -> Result<Vec<(u16, String)>, AppError> {
+    timed_command!("list_all_abs_codes", {
+        Ok(key_codes::all_abs_codes())
+    })
+}
+
+#[tauri::command]
+pub fn get_device_hash(
+    device_path: String,
+    state: State<'_, AppState>,
+) -> Result<String, AppError> {
+    timed_command!("get_device_hash", {
+        Ok(state.evdev_manager.get_device_hash(&device_path)?)
+    })
+}
+
+/// Who we are, who owns each gamepad node, whether we can open it, and what to do
+/// about it if not. Briefly grabs each readable node to detect Steam holding it.
+#[tauri::command]
+pub fn analyze_permissions(
+    state: State<'_, AppState>,
+) -> Result<PermissionsAnalysis, AppError> {
+    timed_command!("analyze_permissions", {
+        Ok(PermissionsAnalysis::collect(&state.evdev_manager.get_detected_devices(), true))
+    })
+}
+
+/// Explains, for each evdev gamepad gilrs doesn't list, the probable causes and fixes.
+/// Briefly grabs readable nodes, like `analyze_permissions`.
+#[tauri::command]
+pub fn diagnose_gamepad_detection(
+    state: State<'_, AppState>,
+) -> Result<DetectionDiagnosis, AppError> {
+    timed_command!("diagnose_gamepad_detection", {
+        Ok(detection::diagnose(
+            state.gamepad_manager.gilrs_available(),
+            &state.gamepad_manager.gamepad_infos(),
+            &state.evdev_manager.get_detected_devices(),
+            true,
+        ))
+    })
+}
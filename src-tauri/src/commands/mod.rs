@@ -0,0 +1,212 @@
+//! Every command the frontend can invoke, by domain. New commands go in one of these
+//! modules and in `handler`; `tests/command_registration.rs` fails if one is missed.
+
+pub mod input;
+pub mod network;
+pub mod system;
+pub mod updater;
+
+use tauri::ipc::Invoke;
+
+/// The invoke handler for every command.
+pub fn handler() -> impl Fn(Invoke) -> bool + Send + Sync + 'static {
+    tauri::generate_handler![
+        input::get_connected_controllers,
+        input::get_controller_state,
+        input::get_controller_states_delta,
+        input::query_axis_time_series,
+        input::configure_time_series_retention,
+        input::get_time_series_memory_usage,
+        input::is_rumble_supported,
+        input::rumble_controller,
+        input::get_snapshots_since,
+        input::configure_snapshot_recorder,
+        input::export_snapshots_csv,
+        input::set_controller_layout_override,
+        input::get_touch_zones,
+        input::set_touch_zones,
+        input::inject_input_event,
+        input::get_virtual_pads,
+        input::set_virtual_pad_policy,
+        input::get_virtual_gamepad,
+        input::create_virtual_gamepad,
+        input::destroy_virtual_gamepad,
+        input::set_gamepad_passthrough,
+        input::get_key_emulation_config,
+        input::set_key_emulation_config,
+        input::get_key_emulation_status,
+        input::set_key_emulation_enabled,
+        input::list_midi_outputs,
+        input::open_midi_output,
+        input::close_midi_output,
+        input::get_midi_status,
+        input::get_midi_rules,
+        input::set_midi_rules,
+        input::set_event_script,
+        input::remove_event_script,
+        input::set_event_script_enabled,
+        input::list_event_scripts,
+        input::get_event_script_examples,
+        input::start_external_processor,
+        input::stop_external_processor,
+        input::get_external_processor_status,
+        input::configure_opc,
+        input::set_opc_generator,
+        input::send_opc_frame,
+        input::stop_opc,
+        input::get_opc_status,
+        input::load_show,
+        input::start_show,
+        input::pause_show,
+        input::seek_show,
+        input::get_show_status,
+        input::set_legacy_events_enabled,
+        input::subscribe_events,
+        input::unsubscribe_events,
+        input::pause_input_processing,
+        input::resume_input_processing,
+        input::get_input_processing_pause,
+        input::get_poll_cadence,
+        input::set_poll_cadence,
+        input::get_event_bus_config,
+        input::set_event_bus_config,
+        input::create_ensemble,
+        input::get_ensemble_state,
+        input::update_ensemble,
+        input::delete_ensemble,
+        input::assign_controller_to_slot,
+        input::unassign_controller_slot,
+        input::get_slot_assignments,
+        input::register_combination_effect,
+        input::unregister_combination_effect,
+        input::list_combination_effects,
+        input::set_ghost_filter_interval,
+        input::set_global_ghost_filter_interval,
+        input::set_stick_dead_zone_shape,
+        input::configure_flood_alarm,
+        input::get_flood_alarm_status,
+        input::reset_flood_alarm,
+        input::start_recording,
+        input::stop_recording,
+        input::replay_recording,
+        input::stop_replay,
+        input::start_event_stream,
+        input::stop_event_stream,
+        input::get_event_stream_status,
+        input::export_recording_compressed,
+        input::import_recording_compressed,
+        input::get_evdev_devices,
+        input::rescan_evdev_devices,
+        input::set_ignored_devices,
+        input::get_input_backends,
+        input::rescan_input_devices,
+        input::reinit_gamepad_backend,
+        input::set_input_backend_enabled,
+        input::get_backend_states,
+        input::get_device_open_failures,
+        input::check_input_group_membership,
+        input::get_device_signal_quality,
+        input::get_touchpad_abs_info,
+        input::get_device_hash,
+        input::get_device_firmware_versions,
+        input::get_capability_history,
+        input::diff_capability_snapshots,
+        input::set_raw_dump,
+        input::get_raw_dump_status,
+        input::set_axis_center_offset,
+        input::auto_detect_axis_center,
+        input::lookup_key_code,
+        input::list_all_key_codes,
+        input::list_all_abs_codes,
+        input::analyze_permissions,
+        input::diagnose_gamepad_detection,
+        network::send_to_light_server,
+        network::set_payload_template,
+        network::test_payload_template,
+        network::clear_payload_template,
+        network::set_light_server_endpoint,
+        network::set_self_signed_hosts,
+        network::get_connection_stats,
+        network::reset_connection_stats,
+        network::get_reliability_percentage,
+        network::configure_batching,
+        network::disable_batching,
+        network::flush_batch_now,
+        network::get_send_statistics,
+        network::set_light_server_slow_threshold,
+        system::get_debug_info,
+        system::get_pipeline_metrics,
+        system::reset_pipeline_metrics,
+        system::get_global_pipeline_metrics,
+        system::get_unhandled_events,
+        system::get_full_debug_info,
+        system::health_check,
+        system::export_debug_report,
+        system::get_current_session_summary,
+        system::get_steam_deck_info,
+        system::get_steam_input_check,
+        system::get_background_mode,
+        system::set_background_mode,
+        system::set_show_window_chord,
+        system::get_system_info,
+        system::get_system_power,
+        system::get_dock_status,
+        system::set_dock_policy,
+        system::set_battery_warning_thresholds,
+        system::get_display_settings,
+        system::get_motion_status,
+        system::set_motion_config,
+        system::get_lizard_mode,
+        system::set_lizard_mode,
+        system::play_deck_haptic,
+        system::get_suspend_inhibit,
+        system::set_suspend_inhibit,
+        system::get_window_focus,
+        system::set_background_input,
+        system::set_ui_scale,
+        system::set_kiosk_mode,
+        system::install_steam_shortcut,
+        system::check_steam_shortcut,
+        system::set_fan_speed_mode,
+        system::get_fan_speed_mode,
+        system::get_fan_speed_rpm,
+        system::configure_low_battery_action,
+        system::get_low_battery_config,
+        system::clear_low_battery_action,
+        #[cfg(feature = "steamos")]
+        system::set_cpu_governor,
+        #[cfg(feature = "steamos")]
+        system::get_cpu_governor,
+        #[cfg(feature = "steamos")]
+        system::set_gpu_performance_level,
+        #[cfg(feature = "steamos")]
+        system::get_gpu_performance_level,
+        system::frontend_hello,
+        system::frontend_heartbeat_ack,
+        system::get_log_file_path,
+        system::get_recent_logs,
+        system::log_frontend_message,
+        system::log_frontend_messages,
+        system::get_log_level,
+        system::set_log_level,
+        system::get_command_metrics,
+        system::reset_command_metrics,
+        system::get_runtime_metrics,
+        system::reset_metrics,
+        system::get_error_counters,
+        system::reset_error_counters,
+        system::set_error_threshold,
+        system::get_memory_stats,
+        system::get_input_priority,
+        system::set_input_priority_enabled,
+        system::run_latency_benchmark,
+        system::measure_idle_cpu,
+        system::get_last_panic,
+        system::clear_last_panic,
+        system::exit_app,
+        system::shutdown_app,
+        system::restart_app,
+        updater::check_for_updates,
+        updater::download_and_install_update,
+    ]
+}
@@ -0,0 +1,186 @@
+use crate::app_state::AppState;
+use crate::command_metrics::timed_command;
+use crate::connection_stats::ConnectionStats;
+use crate::controller_event::UnifiedControllerEvent;
+use crate::error::AppError;
+use crate::light_server::{BatchEventResult, LightServer};
+use crate::locking::MutexExt;
+use crate::payload_template::{CompiledTemplate, PayloadTemplate, TemplateFormat};
+use crate::send_statistics::SendStatisticsSnapshot;
+use crate::settings::SettingsManager;
+use tauri::State;
+
+#[tauri::command]
+pub fn send_to_light_server(
+    endpoint: String,
+    data: serde_json::Value,
+    state: State<'_, AppState>,
+) -> Result<String, AppError> {
+    timed_command!("send_to_light_server", {
+        state.light_server.post_json(&endpoint, &data)
+    })
+}
+
+#[tauri::command]
+pub fn set_light_server_endpoint(
+    endpoint: Option<String>,
+    state: State<'_, AppState>,
+) -> Result<(), AppError> {
+    timed_command!("set_light_server_endpoint", {
+        let endpoint = endpoint.filter(|e| !e.trim().is_empty());
+        Ok(state.settings.update(|settings| settings.light_server_endpoint = endpoint)?)
+    })
+}
+
+/// Hosts whose HTTPS certificates aren't verified, for light servers with self-signed certificates.
+#[tauri::command]
+pub fn set_self_signed_hosts(
+    hosts: Vec<String>,
+    state: State<'_, AppState>,
+) -> Result<(), AppError> {
+    timed_command!("set_self_signed_hosts", {
+        let hosts: Vec<String> = hosts.into_iter().filter(|host| !host.trim().is_empty()).collect();
+        state.settings.update(|settings| settings.self_signed_hosts = hosts.clone())?;
+        state.http.set_self_signed_hosts(&hosts);
+        Ok(())
+    })
+}
+
+/// Compiles and saves the template used for light server batches.
+#[tauri::command]
+pub fn set_payload_template(
+    template: String,
+    format: TemplateFormat,
+    state: State<'_, AppState>,
+) -> Result<(), AppError> {
+    timed_command!("set_payload_template", {
+        let template = PayloadTemplate { template, format };
+        let compiled = CompiledTemplate::compile(&template).map_err(AppError::invalid_argument)?;
+        state.settings.update(|settings| settings.payload_template = Some(template))?;
+        state.light_server.set_payload_template(Some(compiled));
+        Ok(())
+    })
+}
+
+/// Renders `template` against `mock_event` without changing the configured template.
+#[tauri::command]
+pub fn test_payload_template(
+    template: String,
+    format: TemplateFormat,
+    mock_event: UnifiedControllerEvent,
+) -> Result<String, AppError> {
+    timed_command!("test_payload_template", {
+        let compiled = CompiledTemplate::compile(&PayloadTemplate { template, format })
+            .map_err(AppError::invalid_argument)?;
+        Ok(compiled.render(&mock_event)?)
+    })
+}
+
+#[tauri::command]
+pub fn clear_payload_template(
+    state: State<'_, AppState>,
+) -> Result<(), AppError> {
+    timed_command!("clear_payload_template", {
+        state.settings.update(|settings| settings.payload_template = None)?;
+        state.light_server.set_payload_template(None);
+        Ok(())
+    })
+}
+
+#[tauri::command]
+pub fn get_connection_stats(
+    state: State<'_, AppState>,
+) -> Result<ConnectionStats, AppError> {
+    timed_command!("get_connection_stats", {
+        Ok(state.connection_stats.get())
+    })
+}
+
+#[tauri::command]
+pub fn reset_connection_stats(
+    state: State<'_, AppState>,
+) -> Result<(), AppError> {
+    timed_command!("reset_connection_stats", {
+        state.connection_stats.reset();
+        Ok(())
+    })
+}
+
+#[tauri::command]
+pub fn get_reliability_percentage(
+    state: State<'_, AppState>,
+) -> Result<f32, AppError> {
+    timed_command!("get_reliability_percentage", {
+        Ok(state.connection_stats.get().reliability_percentage())
+    })
+}
+
+/// Forwards controller events to the light server in batches, flushed every `window_ms`
+/// or as soon as `max_batch_size` events are queued.
+#[tauri::command]
+pub fn configure_batching(
+    window_ms: u64,
+    max_batch_size: usize,
+    state: State<'_, AppState>,
+) -> Result<(), AppError> {
+    timed_command!("configure_batching", {
+        state.batcher.lock_or_recover().configure(window_ms, max_batch_size)
+            .map_err(AppError::invalid_argument)
+    })
+}
+
+/// Stops forwarding, sending whatever was still queued first.
+#[tauri::command]
+pub fn disable_batching(
+    state: State<'_, AppState>,
+) -> Result<(), AppError> {
+    timed_command!("disable_batching", {
+        let pending = state.batcher.lock_or_recover().disable();
+        send_pending_batch(&pending, &state.light_server, &state.settings).map(|_| ())
+    })
+}
+
+#[tauri::command]
+pub fn flush_batch_now(
+    state: State<'_, AppState>,
+) -> Result<Vec<BatchEventResult>, AppError> {
+    timed_command!("flush_batch_now", {
+        let pending = state.batcher.lock_or_recover().take();
+        send_pending_batch(&pending, &state.light_server, &state.settings)
+    })
+}
+
+fn send_pending_batch(
+    events: &[UnifiedControllerEvent],
+    light_server: &LightServer,
+    settings: &SettingsManager,
+) -> Result<Vec<BatchEventResult>, AppError> {
+    if events.is_empty() {
+        return Ok(Vec::new());
+    }
+    let endpoint = settings.get().light_server_endpoint
+        .ok_or_else(|| AppError::invalid_argument(format!("No light server endpoint configured; dropped {} events", events.len())))?;
+    light_server.post_batch(&endpoint, events)
+}
+
+#[tauri::command]
+pub fn get_send_statistics(
+    state: State<'_, AppState>,
+) -> Result<SendStatisticsSnapshot, AppError> {
+    timed_command!("get_send_statistics", {
+        Ok(state.light_server.get_send_statistics())
+    })
+}
+
+/// Sets the last-minute p95 round trip above which `light-server-slow` is emitted;
+/// no threshold turns the warning off.
+#[tauri::command]
+pub fn set_light_server_slow_threshold(
+    threshold_ms: Option<u64>,
+    state: State<'_, AppState>,
+) -> Result<(), AppError> {
+    timed_command!("set_light_server_slow_threshold", {
+        state.light_server.set_slow_threshold(threshold_ms);
+        Ok(())
+    })
+}
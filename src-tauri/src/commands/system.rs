@@ -0,0 +1,828 @@
+use crate::app_state::AppState;
+use crate::background::{self, BackgroundModeStatus};
+use crate::command_metrics::{self, timed_command, CommandMetricSnapshot};
+use crate::cpu_usage::{self, IdleCpuReport};
+use crate::debug_report::{self, DebugReport, DebugReportResult};
+use crate::detection;
+use crate::diagnostics::{self, FullDebugInfo};
+use crate::dock::{self, DockPolicy, DockStatus};
+use crate::emit_interest;
+use crate::error::AppError;
+use crate::error_counters::{self, ErrorCategory, ErrorsSnapshot};
+use crate::fan_control::FanMode;
+use crate::frontend_sync::{self, FrontendSnapshot};
+use crate::gamepad::DebugInfo;
+use crate::health::{self, HealthReport};
+use crate::hidraw::{self, HapticCue, HapticOutcome, HapticPattern, HapticSide, LizardModeStatus};
+use crate::iio_motion::{MotionConfig, MotionStatus};
+use crate::latency_benchmark::{self, LatencyReport};
+use crate::logging::{self, FrontendLogEntry, LogBatch};
+use crate::locking::MutexExt;
+use crate::low_battery::{LowBatteryAction, LowBatteryConfig};
+use crate::memory_stats::{self, MemoryStats};
+use crate::panic_report::PanicReport;
+#[cfg(feature = "steamos")]
+use crate::performance::{self, CpuGovernor, GpuPerfLevel};
+use crate::permissions::PermissionsAnalysis;
+use crate::pipeline_metrics::PipelineMetrics;
+use crate::runtime_metrics::{self, RuntimeMetricsSnapshot};
+use crate::session_display::{self, DisplayStatus};
+use crate::session_summary::{self, SessionSummary};
+use crate::steam_deck;
+use crate::steam_input_check::SteamInputCheck;
+use crate::steam_shortcut::{self, ShortcutArtwork, SteamShortcutCheck, SteamShortcutResult};
+use crate::suspend_inhibit::{self, SuspendInhibitStatus};
+use crate::system_info::SystemInfo;
+use crate::system_power::{self, SystemPower};
+use crate::thread_priority::{self, InputPriorityStatus};
+use crate::unhandled_events::UnhandledEventKind;
+use crate::window_chord::ShowWindowChord;
+use crate::window_focus::{BackgroundInput, WindowFocusStatus};
+use std::collections::HashMap;
+use tauri::{Manager, State};
+use tracing::info;
+
+#[tauri::command]
+pub fn get_debug_info(
+    state: State<'_, AppState>,
+) -> Result<DebugInfo, AppError> {
+    timed_command!("get_debug_info", {
+        let evdev_devices = state.evdev_manager.get_detected_devices();
+        let permissions = PermissionsAnalysis::collect(&evdev_devices, false);
+        let mut debug_info = state.gamepad_manager.get_debug_info(&permissions);
+        debug_info.tag_virtual_pads(&evdev_devices);
+        debug_info.apply_layout_overrides(&state.settings.get().layout_overrides);
+        Ok(debug_info)
+    })
+}
+
+#[tauri::command]
+pub fn get_pipeline_metrics(
+    controller_id: usize,
+    state: State<'_, AppState>,
+) -> Result<PipelineMetrics, AppError> {
+    timed_command!("get_pipeline_metrics", {
+        Ok(state.gamepad_manager.get_pipeline_metrics(controller_id))
+    })
+}
+
+#[tauri::command]
+pub fn reset_pipeline_metrics(
+    controller_id: usize,
+    state: State<'_, AppState>,
+) -> Result<(), AppError> {
+    timed_command!("reset_pipeline_metrics", {
+        state.gamepad_manager.reset_pipeline_metrics(controller_id);
+        Ok(())
+    })
+}
+
+#[tauri::command]
+pub fn get_global_pipeline_metrics(
+    state: State<'_, AppState>,
+) -> Result<PipelineMetrics, AppError> {
+    timed_command!("get_global_pipeline_metrics", {
+        Ok(state.gamepad_manager.get_global_pipeline_metrics())
+    })
+}
+
+/// gilrs event kinds the pipeline ignores, with counts and a sample of each.
+#[tauri::command]
+pub fn get_unhandled_events(
+    state: State<'_, AppState>,
+) -> Result<Vec<UnhandledEventKind>, AppError> {
+    timed_command!("get_unhandled_events", {
+        Ok(state.gamepad_manager.get_unhandled_events())
+    })
+}
+
+#[tauri::command]
+pub fn get_full_debug_info(
+    app: tauri::AppHandle,
+    state: State<'_, AppState>,
+) -> Result<FullDebugInfo, AppError> {
+    timed_command!("get_full_debug_info", {
+        Ok(diagnostics::collect_full_debug_info(&app, &state))
+    })
+}
+
+/// Pass/warn/fail summary of every subsystem, for triage at a glance.
+#[tauri::command]
+pub fn health_check(
+    state: State<'_, AppState>,
+) -> Result<HealthReport, AppError> {
+    timed_command!("health_check", {
+        Ok(health::run_checks(
+            &state.health_monitor,
+            &state.gamepad_manager,
+            &state.evdev_manager,
+            &state.settings,
+            &state.light_server,
+            &state.key_emulator,
+            &state.steam_input,
+        ))
+    })
+}
+
+#[tauri::command]
+pub fn export_debug_report(
+    path: Option<String>,
+    app: tauri::AppHandle,
+    state: State<'_, AppState>,
+) -> Result<DebugReportResult, AppError> {
+    timed_command!("export_debug_report", {
+        let generated_at = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_millis() as u64;
+    
+        let path = match path {
+            Some(path) => std::path::PathBuf::from(path),
+            None => app.path().app_data_dir()
+                .map_err(|e| format!("Failed to resolve app data directory: {}", e))?
+                .join("debug-reports")
+                .join(format!("debug-report-{}.json", generated_at)),
+        };
+    
+        let report = DebugReport {
+            generated_at,
+            health: health::run_checks(
+                &state.health_monitor,
+                &state.gamepad_manager,
+                &state.evdev_manager,
+                &state.settings,
+                &state.light_server,
+                &state.key_emulator,
+                &state.steam_input,
+            ),
+            debug_info: diagnostics::collect_full_debug_info(&app, &state),
+            detection: detection::diagnose(
+                state.gamepad_manager.gilrs_available(),
+                &state.gamepad_manager.gamepad_infos(),
+                &state.evdev_manager.get_detected_devices(),
+                false,
+            ),
+            evdev_device_table: state.evdev_manager.get_device_details(),
+            input_environment: debug_report::input_environment(),
+            recent_log_lines: logging::recent_log_lines(debug_report::REPORT_LOG_LINES),
+            recent_events: state.gamepad_manager.get_recent_events(),
+            recent_sends: state.light_server.get_recent_sends(),
+        };
+    
+        let result = debug_report::write_report(&report, &path)?;
+        info!("Wrote debug report to {} ({} bytes)", result.path, result.size_bytes);
+        Ok(result)
+    })
+}
+
+/// What `session-*.json` would contain if the app shut down now.
+#[tauri::command]
+pub fn get_current_session_summary(app: tauri::AppHandle) -> Result<SessionSummary, AppError> {
+    timed_command!("get_current_session_summary", {
+        Ok(session_summary::collect(&app))
+    })
+}
+
+#[tauri::command]
+pub fn get_steam_deck_info(
+    state: State<'_, AppState>,
+) -> Result<String, AppError> {
+    timed_command!("get_steam_deck_info", {
+        Ok(state.evdev_manager.get_steam_deck_info())
+    })
+}
+
+#[tauri::command]
+pub fn set_fan_speed_mode(
+    mode: FanMode,
+    state: State<'_, AppState>,
+) -> Result<(), AppError> {
+    timed_command!("set_fan_speed_mode", {
+        Ok(state.evdev_manager.set_fan_speed_mode(mode)?)
+    })
+}
+
+#[tauri::command]
+pub fn get_fan_speed_mode(
+    state: State<'_, AppState>,
+) -> Result<FanMode, AppError> {
+    timed_command!("get_fan_speed_mode", {
+        Ok(state.evdev_manager.get_fan_speed_mode())
+    })
+}
+
+#[tauri::command]
+pub fn get_fan_speed_rpm(
+    state: State<'_, AppState>,
+) -> Result<Option<u32>, AppError> {
+    timed_command!("get_fan_speed_rpm", {
+        Ok(state.evdev_manager.get_fan_speed_rpm())
+    })
+}
+
+#[tauri::command]
+pub fn configure_low_battery_action(
+    threshold: u8,
+    action: LowBatteryAction,
+    state: State<'_, AppState>,
+) -> Result<(), AppError> {
+    timed_command!("configure_low_battery_action", {
+        state.evdev_manager
+            .configure_low_battery_action(LowBatteryConfig { threshold, action })
+            .map_err(AppError::invalid_argument)
+    })
+}
+
+#[tauri::command]
+pub fn get_low_battery_config(
+    state: State<'_, AppState>,
+) -> Result<Option<LowBatteryConfig>, AppError> {
+    timed_command!("get_low_battery_config", {
+        Ok(state.evdev_manager.get_low_battery_config())
+    })
+}
+
+#[tauri::command]
+pub fn clear_low_battery_action(
+    state: State<'_, AppState>,
+) -> Result<(), AppError> {
+    timed_command!("clear_low_battery_action", {
+        state.evdev_manager.clear_low_battery_action();
+        Ok(())
+    })
+}
+
+#[cfg(feature = "steamos")]
+#[tauri::command]
+pub fn set_cpu_governor(governor: CpuGovernor) -> Result<(), AppError> {
+    timed_command!("set_cpu_governor", {
+        Ok(performance::set_cpu_governor(governor)?)
+    })
+}
+
+#[cfg(feature = "steamos")]
+#[tauri::command]
+pub fn get_cpu_governor() -> Result<String, AppError> {
+    timed_command!("get_cpu_governor", {
+        Ok(performance::cpu_governor()?)
+    })
+}
+
+#[cfg(feature = "steamos")]
+#[tauri::command]
+pub fn set_gpu_performance_level(level: GpuPerfLevel) -> Result<(), AppError> {
+    timed_command!("set_gpu_performance_level", {
+        Ok(performance::set_gpu_performance_level(level)?)
+    })
+}
+
+#[cfg(feature = "steamos")]
+#[tauri::command]
+pub fn get_gpu_performance_level() -> Result<String, AppError> {
+    timed_command!("get_gpu_performance_level", {
+        Ok(performance::gpu_performance_level()?)
+    })
+}
+
+#[tauri::command]
+pub fn get_background_mode(
+    app: tauri::AppHandle,
+) -> Result<BackgroundModeStatus, AppError> {
+    timed_command!("get_background_mode", {
+        Ok(background::status(&app))
+    })
+}
+
+/// With background mode on, closing the window hides it and forwarding carries on; a tray
+/// icon (outside game mode) can show it again, pause forwarding or quit.
+#[tauri::command]
+pub fn set_background_mode(
+    enabled: bool,
+    app: tauri::AppHandle,
+    state: State<'_, AppState>,
+) -> Result<BackgroundModeStatus, AppError> {
+    timed_command!("set_background_mode", {
+        state.settings.update(|settings| settings.background_mode = enabled)?;
+        background::apply_background_mode(&app, enabled);
+        Ok(background::status(&app))
+    })
+}
+
+/// Buttons held together on one controller to show the hidden window; no buttons turns it off.
+#[tauri::command]
+pub fn set_show_window_chord(
+    chord: ShowWindowChord,
+    state: State<'_, AppState>,
+) -> Result<(), AppError> {
+    timed_command!("set_show_window_chord", {
+        state.settings.update(|settings| settings.show_window_chord = chord.clone())?;
+        state.gamepad_manager.set_window_chord(chord);
+        Ok(())
+    })
+}
+
+#[tauri::command]
+pub fn get_motion_status(
+    state: State<'_, AppState>,
+) -> Result<MotionStatus, AppError> {
+    timed_command!("get_motion_status", {
+        Ok(state.iio_motion.status())
+    })
+}
+
+/// Chooses between the evdev motion node and the IIO sensors, and how the latter is sampled.
+#[tauri::command]
+pub fn set_motion_config(
+    config: MotionConfig,
+    state: State<'_, AppState>,
+) -> Result<MotionStatus, AppError> {
+    timed_command!("set_motion_config", {
+        state.iio_motion.configure(config.clone())?;
+        state.settings.update(|settings| settings.motion = config)?;
+        Ok(state.iio_motion.status())
+    })
+}
+
+#[tauri::command]
+pub fn get_lizard_mode(state: State<'_, AppState>) -> Result<LizardModeStatus, AppError> {
+    timed_command!("get_lizard_mode", {
+        Ok(state.lizard_mode.status())
+    })
+}
+
+/// `false` turns the built-in controller's keyboard and mouse emulation off while the
+/// window is focused; it comes back on blur and exit.
+#[tauri::command]
+pub fn set_lizard_mode(
+    enabled: bool,
+    state: State<'_, AppState>,
+) -> Result<LizardModeStatus, AppError> {
+    timed_command!("set_lizard_mode", {
+        state.settings.update(|settings| settings.disable_lizard_mode = !enabled)?;
+        Ok(state.lizard_mode.set_enabled(enabled))
+    })
+}
+
+#[tauri::command]
+pub fn get_suspend_inhibit(state: State<'_, AppState>) -> Result<SuspendInhibitStatus, AppError> {
+    timed_command!("get_suspend_inhibit", {
+        Ok(state.suspend_inhibitor.status())
+    })
+}
+
+/// Holds off suspend until turned off again, on top of the automatic inhibitor taken while
+/// forwarding or replaying. Not being able to reach logind isn't an error; the status's
+/// `last_error` says why nothing is held.
+#[tauri::command]
+pub fn set_suspend_inhibit(
+    enabled: bool,
+    reason: Option<String>,
+    state: State<'_, AppState>,
+) -> Result<SuspendInhibitStatus, AppError> {
+    timed_command!("set_suspend_inhibit", {
+        let reason = enabled.then(|| {
+            reason.filter(|reason| !reason.trim().is_empty())
+                .unwrap_or_else(|| suspend_inhibit::DEFAULT_MANUAL_REASON.to_string())
+        });
+        Ok(state.suspend_inhibitor.set_manual(reason))
+    })
+}
+
+#[tauri::command]
+pub fn get_window_focus(state: State<'_, AppState>) -> Result<WindowFocusStatus, AppError> {
+    timed_command!("get_window_focus", {
+        Ok(state.window_focus.status())
+    })
+}
+
+/// What's done about controller input while the window isn't focused and input is being
+/// forwarded: warn, or read the physical controller through evdev, optionally grabbed.
+#[tauri::command]
+pub fn set_background_input(
+    mode: BackgroundInput,
+    state: State<'_, AppState>,
+) -> Result<WindowFocusStatus, AppError> {
+    timed_command!("set_background_input", {
+        state.settings.update(|settings| settings.background_input = mode)?;
+        state.window_focus.set_background_input(mode);
+        Ok(state.window_focus.status())
+    })
+}
+
+/// Pulses the Deck's trackpad actuators. Plays nothing, without failing, where there's no
+/// usable Deck controller; the outcome says why.
+#[tauri::command]
+pub fn play_deck_haptic(
+    side: HapticSide,
+    intensity: f32,
+    duration_ms: u32,
+    pattern: HapticPattern,
+) -> Result<HapticOutcome, AppError> {
+    timed_command!("play_deck_haptic", {
+        Ok(hidraw::play_deck_haptic(&HapticCue { side, pattern, intensity, duration_ms })?)
+    })
+}
+
+#[tauri::command]
+pub fn get_display_settings(
+    app: tauri::AppHandle,
+) -> Result<DisplayStatus, AppError> {
+    timed_command!("get_display_settings", {
+        Ok(session_display::status(&app))
+    })
+}
+
+/// Webview zoom; `None` goes back to the session's default.
+#[tauri::command]
+pub fn set_ui_scale(
+    scale: Option<f64>,
+    app: tauri::AppHandle,
+    state: State<'_, AppState>,
+) -> Result<DisplayStatus, AppError> {
+    timed_command!("set_ui_scale", {
+        if let Some(scale) = scale {
+            session_display::validate_ui_scale(scale)?;
+        }
+        state.settings.update(|settings| settings.ui_scale = scale)?;
+        Ok(session_display::apply(&app))
+    })
+}
+
+/// Fullscreen kiosk window or a normal one; `None` goes back to the session's default.
+#[tauri::command]
+pub fn set_kiosk_mode(
+    enabled: Option<bool>,
+    app: tauri::AppHandle,
+    state: State<'_, AppState>,
+) -> Result<DisplayStatus, AppError> {
+    timed_command!("set_kiosk_mode", {
+        state.settings.update(|settings| settings.kiosk_mode = enabled)?;
+        Ok(session_display::apply(&app))
+    })
+}
+
+/// Adds the app to Steam as a non-Steam game, or points the entry at this install,
+/// launching with `--kiosk` while kiosk mode is on.
+#[tauri::command]
+pub fn install_steam_shortcut(
+    artwork: Option<ShortcutArtwork>,
+    app: tauri::AppHandle,
+) -> Result<SteamShortcutResult, AppError> {
+    timed_command!("install_steam_shortcut", {
+        let user_dir = steam_shortcut::current_user_dir()?;
+        let spec = steam_shortcut::current_spec(session_display::status(&app).kiosk_mode)?;
+        Ok(steam_shortcut::install(&user_dir, &spec, &artwork.unwrap_or_default(), steam_deck::process_running("steam"))?)
+    })
+}
+
+#[tauri::command]
+pub fn check_steam_shortcut(app: tauri::AppHandle) -> Result<SteamShortcutCheck, AppError> {
+    timed_command!("check_steam_shortcut", {
+        let user_dir = steam_shortcut::current_user_dir()?;
+        let spec = steam_shortcut::current_spec(session_display::status(&app).kiosk_mode)?;
+        let check = steam_shortcut::check(&user_dir, &spec)?;
+        if !check.up_to_date() {
+            info!("Steam shortcut in {} needs reinstalling", check.shortcuts_path);
+        }
+        Ok(check)
+    })
+}
+
+#[tauri::command]
+pub fn get_dock_status(state: State<'_, AppState>) -> Result<DockStatus, AppError> {
+    timed_command!("get_dock_status", {
+        Ok(state.dock.lock_or_recover().clone())
+    })
+}
+
+/// Saves which controller is primary docked and handheld, and applies it straight away.
+#[tauri::command]
+pub fn set_dock_policy(
+    policy: DockPolicy,
+    app: tauri::AppHandle,
+    state: State<'_, AppState>,
+) -> Result<DockStatus, AppError> {
+    timed_command!("set_dock_policy", {
+        state.settings.update(|settings| settings.dock_policy = policy)?;
+        Ok(dock::refresh(&app))
+    })
+}
+
+/// The machine's battery and whether it's on AC; a machine without one is AC only.
+#[tauri::command]
+pub fn get_system_power(
+    state: State<'_, AppState>,
+) -> Result<SystemPower, AppError> {
+    timed_command!("get_system_power", {
+        Ok(state.system_power.latest())
+    })
+}
+
+/// Battery percentages that emit `system-battery-low`; `None` goes back to 20, 10 and 5.
+#[tauri::command]
+pub fn set_battery_warning_thresholds(
+    thresholds: Option<Vec<u8>>,
+    state: State<'_, AppState>,
+) -> Result<Vec<u8>, AppError> {
+    timed_command!("set_battery_warning_thresholds", {
+        let validated = match &thresholds {
+            Some(thresholds) => system_power::validate_thresholds(thresholds)?,
+            None => system_power::DEFAULT_BATTERY_WARNINGS.to_vec(),
+        };
+        state.settings.update(|settings| settings.battery_warning_thresholds = thresholds)?;
+        state.system_power.set_thresholds(validated.clone());
+        Ok(validated)
+    })
+}
+
+/// Whether Steam Input looks to be turning the controller into keyboard and mouse, with
+/// the evidence; re-evaluated every 10 seconds.
+#[tauri::command]
+pub fn get_steam_input_check(state: State<'_, AppState>) -> Result<SteamInputCheck, AppError> {
+    timed_command!("get_steam_input_check", {
+        Ok(state.steam_input.lock_or_recover().clone())
+    })
+}
+
+#[tauri::command]
+pub fn get_system_info(
+    state: State<'_, AppState>,
+) -> Result<SystemInfo, AppError> {
+    timed_command!("get_system_info", {
+        Ok(state.system_info.with_current_dock(&state.dock))
+    })
+}
+
+/// Called by the frontend as it mounts, including after a reload. Returns the full state
+/// to rebuild from; subscriptions made before the call are dropped.
+#[tauri::command]
+pub fn frontend_hello(
+    app: tauri::AppHandle,
+) -> Result<FrontendSnapshot, AppError> {
+    timed_command!("frontend_hello", {
+        Ok(frontend_sync::hello(&app))
+    })
+}
+
+#[tauri::command]
+pub fn frontend_heartbeat_ack(sequence: u64) -> Result<(), AppError> {
+    timed_command!("frontend_heartbeat_ack", {
+        frontend_sync::liveness().ack(sequence);
+        Ok(())
+    })
+}
+
+#[tauri::command]
+pub fn get_log_file_path() -> Result<Option<String>, AppError> {
+    timed_command!("get_log_file_path", {
+        Ok(logging::log_file_path().map(|path| path.to_string_lossy().to_string()))
+    })
+}
+
+#[tauri::command]
+pub fn get_recent_logs(
+    min_level: Option<String>,
+    limit: Option<usize>,
+    after_seq: Option<u64>,
+) -> Result<LogBatch, AppError> {
+    timed_command!("get_recent_logs", {
+        logging::recent_logs(min_level.as_deref(), limit.unwrap_or(500), after_seq)
+            .map_err(AppError::invalid_argument)
+    })
+}
+
+#[tauri::command]
+pub fn log_frontend_message(level: String, message: String) -> Result<(), AppError> {
+    timed_command!("log_frontend_message", {
+        logging::log_frontend(&level, &message)
+            .map_err(AppError::invalid_argument)
+    })
+}
+
+/// Logs a batch of UI messages; entries with an unknown level are logged as warnings.
+#[tauri::command]
+pub fn log_frontend_messages(entries: Vec<FrontendLogEntry>) -> Result<(), AppError> {
+    timed_command!("log_frontend_messages", {
+        for entry in entries {
+            if logging::log_frontend(&entry.level, &entry.message).is_err() {
+                logging::log_frontend("warn", &entry.message)?;
+            }
+        }
+        Ok(())
+    })
+}
+
+#[tauri::command]
+pub fn get_log_level() -> Result<Option<String>, AppError> {
+    timed_command!("get_log_level", {
+        Ok(logging::current_filter())
+    })
+}
+
+#[tauri::command]
+pub fn set_log_level(
+    filter: String,
+    state: State<'_, AppState>,
+) -> Result<(), AppError> {
+    timed_command!("set_log_level", {
+        let filter = filter.trim().to_string();
+        logging::set_filter(&filter).map_err(AppError::invalid_argument)?;
+        info!("Log filter set to '{}'", filter);
+        Ok(state.settings.update(|s| s.log_filter = Some(filter))?)
+    })
+}
+
+#[tauri::command]
+pub fn get_last_panic(
+    state: State<'_, AppState>,
+) -> Result<Option<PanicReport>, AppError> {
+    timed_command!("get_last_panic", {
+        Ok(state.panic_monitor.last_panic())
+    })
+}
+
+#[tauri::command]
+pub fn clear_last_panic(state: State<'_, AppState>) -> Result<(), AppError> {
+    timed_command!("clear_last_panic", {
+        state.panic_monitor.clear_last_panic();
+        Ok(())
+    })
+}
+
+#[tauri::command]
+pub async fn exit_app(
+    app_handle: tauri::AppHandle,
+) -> Result<(), AppError> {
+    timed_command!("exit_app", async {
+        info!("Exiting application");
+        app_handle.exit(0);
+        Ok(())
+    })
+}
+
+/// Exits after the polling thread has stopped and pending work is flushed (see `RunEvent::Exit` in lib.rs).
+#[tauri::command]
+pub async fn shutdown_app(
+    app_handle: tauri::AppHandle,
+) -> Result<(), AppError> {
+    timed_command!("shutdown_app", async {
+        background::quit(&app_handle);
+        Ok(())
+    })
+}
+
+#[tauri::command]
+pub async fn restart_app(
+    app: tauri::AppHandle,
+) -> Result<String, AppError> {
+    timed_command!("restart_app", async {
+        info!("Restarting application");
+    
+        // Use the process plugin to restart the app
+        app.request_restart();
+    
+        Ok("Restarting...".to_string())
+    })
+}
+
+#[tauri::command]
+pub fn get_command_metrics() -> Result<HashMap<String, CommandMetricSnapshot>, AppError> {
+    timed_command!("get_command_metrics", {
+        Ok(command_metrics::store().snapshot())
+    })
+}
+
+#[tauri::command]
+pub fn reset_command_metrics() -> Result<(), AppError> {
+    timed_command!("reset_command_metrics", {
+        command_metrics::store().reset();
+        Ok(())
+    })
+}
+
+#[tauri::command]
+pub fn get_runtime_metrics() -> Result<RuntimeMetricsSnapshot, AppError> {
+    timed_command!("get_runtime_metrics", {
+        Ok(runtime_metrics::metrics().snapshot())
+    })
+}
+
+/// Clears the runtime metrics and light server send statistics, e.g. before a show.
+#[tauri::command]
+pub fn reset_metrics(
+    state: State<'_, AppState>,
+) -> Result<(), AppError> {
+    timed_command!("reset_metrics", {
+        runtime_metrics::metrics().reset();
+        state.light_server.reset_send_statistics();
+        Ok(())
+    })
+}
+
+#[tauri::command]
+pub fn get_error_counters() -> Result<ErrorsSnapshot, AppError> {
+    timed_command!("get_error_counters", {
+        Ok(error_counters::errors().snapshot())
+    })
+}
+
+#[tauri::command]
+pub fn reset_error_counters() -> Result<(), AppError> {
+    timed_command!("reset_error_counters", {
+        error_counters::errors().reset();
+        Ok(())
+    })
+}
+
+/// Sets how many `category` failures per minute trigger a `backend-warning`;
+/// no threshold turns its warnings off.
+#[tauri::command]
+pub fn set_error_threshold(category: ErrorCategory, per_minute: Option<u32>) -> Result<(), AppError> {
+    timed_command!("set_error_threshold", {
+        error_counters::errors().set_threshold(category, per_minute);
+        Ok(())
+    })
+}
+
+/// What the long-lived buffers hold and roughly how much memory they keep.
+#[tauri::command]
+pub fn get_memory_stats(
+    state: State<'_, AppState>,
+) -> Result<MemoryStats, AppError> {
+    timed_command!("get_memory_stats", {
+        Ok(memory_stats::collect(&state))
+    })
+}
+
+#[tauri::command]
+pub fn get_input_priority(
+    state: State<'_, AppState>,
+) -> Result<InputPriorityStatus, AppError> {
+    timed_command!("get_input_priority", {
+        Ok(InputPriorityStatus {
+            enabled: !state.settings.get().disable_input_priority,
+            threads: thread_priority::snapshot(),
+        })
+    })
+}
+
+/// Whether the polling and dispatch threads get raised scheduling priority. They're
+/// already running, so a change takes effect the next time the app starts.
+#[tauri::command]
+pub fn set_input_priority_enabled(
+    enabled: bool,
+    state: State<'_, AppState>,
+) -> Result<InputPriorityStatus, AppError> {
+    timed_command!("set_input_priority_enabled", {
+        state.settings.update(|settings| settings.disable_input_priority = !enabled)?;
+        Ok(InputPriorityStatus {
+            enabled,
+            threads: thread_priority::snapshot(),
+        })
+    })
+}
+
+/// Samples the app's own CPU use for `seconds`. The idle budget applies with no
+/// controllers connected and the window hidden.
+#[tauri::command]
+pub async fn measure_idle_cpu(
+    seconds: u64,
+    state: State<'_, AppState>,
+) -> Result<IdleCpuReport, AppError> {
+    timed_command!("measure_idle_cpu", async {
+        if seconds == 0 || seconds > cpu_usage::MAX_MEASURE_SECS {
+            return Err(AppError::invalid_argument(format!(
+                "seconds must be between 1 and {}",
+                cpu_usage::MAX_MEASURE_SECS
+            )));
+        }
+        let gamepad_manager = state.gamepad_manager.clone();
+        tauri::async_runtime::spawn_blocking(move || {
+            cpu_usage::measure(std::time::Duration::from_secs(seconds), || {
+                let connected = gamepad_manager.gamepad_infos().iter().filter(|gamepad| gamepad.is_connected).count();
+                (emit_interest::interest().window_visible(), connected)
+            })
+        })
+        .await
+        .map_err(|e| format!("CPU measurement failed: {}", e))?
+        .map_err(AppError::from)
+    })
+}
+
+/// Samples live input latency per pipeline stage for `duration_secs`. Forwarded
+/// events only show up in the dispatch stage while batching is enabled.
+#[tauri::command]
+pub async fn run_latency_benchmark(duration_secs: u64) -> Result<LatencyReport, AppError> {
+    timed_command!("run_latency_benchmark", async {
+        if duration_secs == 0 || duration_secs > latency_benchmark::MAX_DURATION_SECS {
+            return Err(AppError::invalid_argument(format!(
+                "duration_secs must be between 1 and {}",
+                latency_benchmark::MAX_DURATION_SECS
+            )));
+        }
+        latency_benchmark::benchmark()
+            .run(std::time::Duration::from_secs(duration_secs))
+            .await
+            .map_err(AppError::from)
+    })
+}
@@ -0,0 +1,137 @@
+use crate::app_state::AppState;
+use crate::command_metrics::timed_command;
+use crate::emit_interest::EventChannel;
+use crate::emitter::EventEmitter;
+use crate::error::AppError;
+use crate::locking::MutexExt;
+use crate::update_status::{UpdateInfo, UpdateStage};
+use tauri::{Manager, State};
+use tauri_plugin_updater::UpdaterExt;
+use tracing::{debug, error, info};
+
+#[tauri::command]
+pub async fn check_for_updates(
+    app: tauri::AppHandle,
+    state: State<'_, AppState>,
+) -> Result<UpdateInfo, AppError> {
+    timed_command!("check_for_updates", async {
+        let result = check_updater(&app).await;
+        state.health_monitor.record_updater_check(&result);
+        if let Ok(info) = &result {
+            state.update_status.lock_or_recover().last_check = Some(info.clone());
+        }
+        result
+    })
+}
+
+/// Keeps failures to reach the update server as `Network` so the frontend can offer a retry.
+fn updater_error(context: &str, error: tauri_plugin_updater::Error) -> AppError {
+    match AppError::from(error) {
+        AppError::Network { status, message } => AppError::network(status, format!("{}: {}", context, message)),
+        other => AppError::updater(format!("{}: {}", context, other)),
+    }
+}
+
+async fn check_updater(app: &tauri::AppHandle) -> Result<UpdateInfo, AppError> {
+    info!("Checking for updates");
+
+    let updater = app.updater_builder().build()
+        .map_err(|e| {
+            error!("Failed to build updater: {}", e);
+            AppError::updater(format!("Failed to initialize updater: {}", e))
+        })?;
+
+    match updater.check().await {
+        Ok(Some(update)) => {
+            info!("Update available: {}", update.version);
+            Ok(UpdateInfo {
+                available: true,
+                version: Some(update.version.clone()),
+                current_version: update.current_version.clone(),
+                body: update.body.clone(),
+                date: update.date.map(|d| d.to_string()),
+            })
+        }
+        Ok(None) => {
+            info!("No updates available, already on latest version");
+            Ok(UpdateInfo {
+                available: false,
+                version: None,
+                current_version: app.package_info().version.to_string(),
+                body: None,
+                date: None,
+            })
+        }
+        Err(e) => {
+            error!("Error checking for updates: {}", e);
+            Err(updater_error("Failed to check for updates", e))
+        }
+    }
+}
+
+#[tauri::command]
+pub async fn download_and_install_update(
+    app: tauri::AppHandle,
+) -> Result<String, AppError> {
+    timed_command!("download_and_install_update", async {
+        info!("Starting update download and installation");
+    
+        let updater = app.updater_builder().build()
+            .map_err(|e| {
+                error!("Failed to build updater: {}", e);
+                AppError::updater(format!("Failed to initialize updater: {}", e))
+            })?;
+    
+        match updater.check().await {
+            Ok(Some(update)) => {
+                info!("Downloading update version {}", update.version);
+            
+                // Download and install with progress events
+                let mut downloaded_bytes = 0u64;
+                let mut is_first_chunk = true;
+                let app_clone = app.clone();
+                let app_clone2 = app.clone();
+                let update_status = app.state::<AppState>().update_status.clone();
+                let update_status2 = update_status.clone();
+            
+                update.download_and_install(
+                    move |chunk_size, total_size| {
+                        if is_first_chunk {
+                            // First chunk - emit start event
+                            info!("Update download started, total size: {:?} bytes", total_size);
+                            let _ = app_clone.emit_on(EventChannel::Updates, "update-download-started", total_size);
+                            is_first_chunk = false;
+                        }
+                    
+                        downloaded_bytes += chunk_size as u64;
+                        debug!("Downloaded {} bytes (total downloaded: {})", chunk_size, downloaded_bytes);
+                        update_status.lock_or_recover().stage = UpdateStage::Downloading { downloaded_bytes, total_bytes: total_size };
+                    
+                        let _ = app_clone.emit_on(EventChannel::Updates, "update-download-progress", chunk_size as u64);
+                    },
+                    move || {
+                        info!("Update download completed, installing");
+                        update_status2.lock_or_recover().stage = UpdateStage::Installing;
+                        let _ = app_clone2.emit_on(EventChannel::Updates, "update-download-finished", ());
+                        let _ = app_clone2.emit_on(EventChannel::Updates, "update-installing", ());
+                    }
+                ).await.map_err(|e| {
+                    error!("Failed to download/install update: {}", e);
+                    app.state::<AppState>().update_status.lock_or_recover().stage = UpdateStage::Idle;
+                    updater_error("Failed to download/install update", e)
+                })?;
+            
+                info!("Update installed successfully");
+                Ok("Update installed successfully!".to_string())
+            }
+            Ok(None) => {
+                info!("No updates available");
+                Err(AppError::updater("No updates available"))
+            }
+            Err(e) => {
+                error!("Error checking for updates: {}", e);
+                Err(updater_error("Failed to check for updates", e))
+            }
+        }
+    })
+}
@@ -0,0 +1,243 @@
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+use std::sync::OnceLock;
+
+/// Flatpak device permissions that expose `/dev/input`; `input` needs Flatpak 1.15.6.
+const FLATPAK_INPUT_DEVICES: [&str; 2] = ["input", "all"];
+/// `/proc/1/cgroup` path fragments left by container runtimes.
+const CONTAINER_CGROUP_MARKERS: [(&str, &str); 5] = [
+    ("docker", "docker"),
+    ("libpod", "podman"),
+    ("lxc", "lxc"),
+    ("kubepods", "kubernetes"),
+    ("containerd", "containerd"),
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ConfinementKind {
+    Flatpak,
+    /// Steam's container runtime, used for games run under the Steam Linux Runtime
+    PressureVessel,
+    Container,
+}
+
+/// The sandbox or container the app runs in, if any, found once at startup.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Confinement {
+    pub kinds: Vec<ConfinementKind>,
+    /// What gave each one away
+    pub evidence: Vec<String>,
+    pub flatpak_app_id: Option<String>,
+    /// The Flatpak's `devices=` permissions
+    pub flatpak_devices: Vec<String>,
+    /// docker, podman, lxc and so on
+    pub container_runtime: Option<String>,
+    /// Whether `/dev/input` exists inside the sandbox at all
+    pub dev_input_visible: bool,
+}
+
+/// The `[Application] name` and `[Context] devices` of a `/.flatpak-info`.
+pub fn parse_flatpak_info(contents: &str) -> (Option<String>, Vec<String>) {
+    let mut section = "";
+    let mut app_id = None;
+    let mut devices = Vec::new();
+    for line in contents.lines().map(str::trim) {
+        if let Some(name) = line.strip_prefix('[').and_then(|line| line.strip_suffix(']')) {
+            section = name;
+            continue;
+        }
+        let Some((key, value)) = line.split_once('=') else {
+            continue;
+        };
+        match (section, key.trim()) {
+            ("Application", "name") => app_id = Some(value.trim().to_string()),
+            ("Context", "devices") => {
+                devices = value.split(';').map(str::trim).filter(|device| !device.is_empty()).map(str::to_string).collect();
+            }
+            _ => {}
+        }
+    }
+    (app_id, devices)
+}
+
+/// The container runtime `/proc/1/cgroup` points to, if any.
+pub fn container_runtime_from_cgroup(cgroup: &str) -> Option<&'static str> {
+    CONTAINER_CGROUP_MARKERS.iter()
+        .find(|(marker, _)| cgroup.lines().any(|line| line.contains(marker)))
+        .map(|(_, runtime)| *runtime)
+}
+
+impl Confinement {
+    pub fn detect() -> Self {
+        let mut confinement = Confinement {
+            dev_input_visible: Path::new("/dev/input").is_dir(),
+            ..Default::default()
+        };
+        let env = |name: &str| std::env::var(name).ok().filter(|value| !value.is_empty());
+
+        let flatpak_info = std::fs::read_to_string("/.flatpak-info").ok();
+        if flatpak_info.is_some() || env("FLATPAK_ID").is_some() {
+            confinement.kinds.push(ConfinementKind::Flatpak);
+            let (app_id, devices) = flatpak_info.as_deref().map(parse_flatpak_info).unwrap_or_default();
+            confinement.evidence.push(if flatpak_info.is_some() { "/.flatpak-info exists" } else { "FLATPAK_ID is set" }.to_string());
+            confinement.flatpak_app_id = app_id.or_else(|| env("FLATPAK_ID"));
+            confinement.flatpak_devices = devices;
+        }
+
+        let pressure_vessel_env = std::env::vars().find(|(name, _)| name.starts_with("PRESSURE_VESSEL_"));
+        if pressure_vessel_env.is_some() || Path::new("/run/pressure-vessel").exists() {
+            confinement.kinds.push(ConfinementKind::PressureVessel);
+            confinement.evidence.push(match pressure_vessel_env {
+                Some((name, _)) => format!("{} is set", name),
+                None => "/run/pressure-vessel exists".to_string(),
+            });
+        }
+
+        // Flatpak and pressure-vessel are containers too; only an outer one is worth naming
+        if confinement.kinds.is_empty() {
+            let cgroup = std::fs::read_to_string("/proc/1/cgroup").unwrap_or_default();
+            let runtime = container_runtime_from_cgroup(&cgroup)
+                .map(|runtime| (runtime.to_string(), format!("/proc/1/cgroup mentions {}", runtime)))
+                .or_else(|| Path::new("/.dockerenv").exists().then(|| ("docker".to_string(), "/.dockerenv exists".to_string())))
+                .or_else(|| Path::new("/run/.containerenv").exists().then(|| ("podman".to_string(), "/run/.containerenv exists".to_string())))
+                .or_else(|| env("container").map(|runtime| (runtime.clone(), format!("container={} is set", runtime))));
+            if let Some((runtime, evidence)) = runtime {
+                confinement.kinds.push(ConfinementKind::Container);
+                confinement.evidence.push(evidence);
+                confinement.container_runtime = Some(runtime);
+            }
+        }
+        confinement
+    }
+
+    pub fn is_confined(&self) -> bool {
+        !self.kinds.is_empty()
+    }
+
+    /// Whether the Flatpak was granted a device permission covering `/dev/input`.
+    pub fn flatpak_input_granted(&self) -> bool {
+        self.flatpak_devices.iter().any(|device| FLATPAK_INPUT_DEVICES.contains(&device.as_str()))
+    }
+
+    /// What to do about the sandbox hiding input devices. A Flatpak comes first, as
+    /// nothing inside it sees devices it wasn't granted.
+    pub fn remediation(&self) -> Option<String> {
+        let native = "or run the native build instead";
+        let message = match self.kinds.first()? {
+            ConfinementKind::Flatpak => {
+                let app_id = self.flatpak_app_id.as_deref().unwrap_or("<app id>");
+                format!(
+                    "The Flatpak sandbox hides /dev/input{}. Grant it with `flatpak override --user --device=input {}` (`--device=all` before Flatpak 1.15.6), or turn on \"All devices\" in Flatseal, then restart the app; {}",
+                    if self.flatpak_input_granted() { " despite its device permission" } else { "" },
+                    app_id,
+                    native
+                )
+            }
+            ConfinementKind::PressureVessel => format!(
+                "Steam's pressure-vessel container doesn't pass /dev/input through. Launch the app without a Steam Linux Runtime compatibility tool, {}",
+                native
+            ),
+            ConfinementKind::Container => format!(
+                "The {} container has no access to /dev/input. Pass the devices in (e.g. `--device /dev/input` for docker and podman), {}",
+                self.container_runtime.as_deref().unwrap_or("surrounding"),
+                native
+            ),
+        };
+        Some(message)
+    }
+
+    pub fn summary(&self) -> String {
+        let kinds: Vec<String> = self.kinds.iter().map(|kind| format!("{:?}", kind)).collect();
+        format!("{} ({})", kinds.join(", "), self.evidence.join(", "))
+    }
+}
+
+static CONFINEMENT: OnceLock<Confinement> = OnceLock::new();
+
+/// Detected on first use; a sandbox can't change while the app runs.
+pub fn current() -> &'static Confinement {
+    CONFINEMENT.get_or_init(Confinement::detect)
+}
+
+/// Confined with no gamepad readable: the sandbox, not file modes, is in the way.
+pub fn blocks_input(confinement: &Confinement, accessible_devices: usize) -> bool {
+    confinement.is_confined() && accessible_devices == 0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FLATPAK_INFO: &str = "[Application]
+name=io.github.steamdeck.Controller
+runtime=runtime/org.freedesktop.Platform/x86_64/23.08
+
+[Context]
+shared=network;ipc;
+sockets=x11;wayland;pulseaudio;
+devices=dri;input;
+";
+
+    fn flatpak(devices: &[&str]) -> Confinement {
+        Confinement {
+            kinds: vec![ConfinementKind::Flatpak],
+            evidence: vec!["/.flatpak-info exists".to_string()],
+            flatpak_app_id: Some("io.github.steamdeck.Controller".to_string()),
+            flatpak_devices: devices.iter().map(|device| device.to_string()).collect(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn flatpak_info_gives_the_app_id_and_devices() {
+        let (app_id, devices) = parse_flatpak_info(FLATPAK_INFO);
+        assert_eq!(app_id.as_deref(), Some("io.github.steamdeck.Controller"));
+        assert_eq!(devices, vec!["dri", "input"]);
+
+        let (app_id, devices) = parse_flatpak_info("[Application]\nname=a.b.C\n");
+        assert_eq!(app_id.as_deref(), Some("a.b.C"));
+        assert!(devices.is_empty());
+    }
+
+    #[test]
+    fn only_input_or_all_devices_reach_dev_input() {
+        assert!(flatpak(&["dri", "input"]).flatpak_input_granted());
+        assert!(flatpak(&["all"]).flatpak_input_granted());
+        assert!(!flatpak(&["dri"]).flatpak_input_granted());
+    }
+
+    #[test]
+    fn container_runtimes_are_named_from_the_cgroup() {
+        assert_eq!(container_runtime_from_cgroup("0::/system.slice/docker-3f2a.scope"), Some("docker"));
+        assert_eq!(container_runtime_from_cgroup("0::/machine.slice/libpod-1c9e.scope/container"), Some("podman"));
+        assert_eq!(container_runtime_from_cgroup("0::/user.slice/user-1000.slice/session-2.scope"), None);
+        assert_eq!(container_runtime_from_cgroup(""), None);
+    }
+
+    #[test]
+    fn the_sandbox_is_only_blamed_with_no_device_readable() {
+        assert!(blocks_input(&flatpak(&[]), 0));
+        assert!(!blocks_input(&flatpak(&[]), 1));
+        assert!(!blocks_input(&Confinement::default(), 0));
+    }
+
+    #[test]
+    fn remediation_names_the_fix_for_each_sandbox() {
+        assert!(Confinement::default().remediation().is_none());
+
+        let text = flatpak(&["dri"]).remediation().unwrap();
+        assert!(text.contains("--device=input io.github.steamdeck.Controller"));
+        assert!(text.contains("Flatseal"));
+        assert!(text.contains("native build"));
+
+        let container = Confinement {
+            kinds: vec![ConfinementKind::Container],
+            container_runtime: Some("podman".to_string()),
+            ..Default::default()
+        };
+        let text = container.remediation().unwrap();
+        assert!(text.contains("podman"));
+        assert!(text.contains("--device /dev/input"));
+    }
+}
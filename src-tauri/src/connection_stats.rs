@@ -0,0 +1,132 @@
+use crate::locking::MutexExt;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+use std::time::Duration;
+use tracing::{debug, warn};
+
+/// How often changed stats are written to disk.
+pub const SAVE_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Light server request outcomes, kept across restarts.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ConnectionStats {
+    pub requests_sent: u64,
+    pub requests_succeeded: u64,
+    pub requests_failed: u64,
+    pub total_latency_ms: u64,
+    /// Zero until the first request
+    pub min_latency_ms: u64,
+    pub max_latency_ms: u64,
+    pub circuit_open_count: u32,
+    pub last_failure_reason: Option<String>,
+}
+
+impl ConnectionStats {
+    /// Share of requests that succeeded, or 100 when none were sent.
+    pub fn reliability_percentage(&self) -> f32 {
+        if self.requests_sent == 0 {
+            return 100.0;
+        }
+        self.requests_succeeded as f32 / self.requests_sent as f32 * 100.0
+    }
+}
+
+/// `ConnectionStats` behind a lock, saved to the cache directory when changed.
+pub struct ConnectionStatsStore {
+    stats: Mutex<ConnectionStats>,
+    path: Option<PathBuf>,
+    dirty: AtomicBool,
+}
+
+impl ConnectionStatsStore {
+    pub fn load() -> Self {
+        let path = connection_stats_path();
+        let stats = path.as_deref().map(load_stats).unwrap_or_default();
+        Self {
+            stats: Mutex::new(stats),
+            path,
+            dirty: AtomicBool::new(false),
+        }
+    }
+
+    pub fn record<T>(&self, result: &Result<T, String>, latency_ms: u64) {
+        let mut stats = self.stats.lock_or_recover();
+        stats.min_latency_ms = if stats.requests_sent == 0 {
+            latency_ms
+        } else {
+            stats.min_latency_ms.min(latency_ms)
+        };
+        stats.max_latency_ms = stats.max_latency_ms.max(latency_ms);
+        stats.total_latency_ms += latency_ms;
+        stats.requests_sent += 1;
+        match result {
+            Ok(_) => stats.requests_succeeded += 1,
+            Err(e) => {
+                stats.requests_failed += 1;
+                stats.last_failure_reason = Some(e.clone());
+            }
+        }
+        self.dirty.store(true, Ordering::Relaxed);
+    }
+
+    pub fn get(&self) -> ConnectionStats {
+        self.stats.lock_or_recover().clone()
+    }
+
+    pub fn reset(&self) {
+        *self.stats.lock_or_recover() = ConnectionStats::default();
+        self.dirty.store(true, Ordering::Relaxed);
+    }
+
+    /// Writes the stats if they changed since the last save.
+    pub fn save_if_dirty(&self) {
+        if !self.dirty.swap(false, Ordering::Relaxed) {
+            return;
+        }
+        let Some(path) = &self.path else {
+            return;
+        };
+        match save_stats(path, &self.get()) {
+            Ok(()) => debug!("Saved connection stats to {}", path.display()),
+            Err(e) => {
+                warn!("{}", e);
+                self.dirty.store(true, Ordering::Relaxed);
+            }
+        }
+    }
+}
+
+fn connection_stats_path() -> Option<PathBuf> {
+    std::env::var_os("HOME").map(|home| {
+        PathBuf::from(home)
+            .join(".cache")
+            .join("steamdeck-controller")
+            .join("connection_stats.json")
+    })
+}
+
+fn load_stats(path: &Path) -> ConnectionStats {
+    match std::fs::read_to_string(path) {
+        Ok(contents) => serde_json::from_str(&contents).unwrap_or_else(|e| {
+            warn!("Ignoring unreadable connection stats file {}: {}", path.display(), e);
+            ConnectionStats::default()
+        }),
+        Err(_) => ConnectionStats::default(),
+    }
+}
+
+fn save_stats(path: &Path, stats: &ConnectionStats) -> Result<(), String> {
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)
+            .map_err(|e| format!("Failed to create {}: {}", parent.display(), e))?;
+    }
+    let contents = serde_json::to_string_pretty(stats)
+        .map_err(|e| format!("Failed to serialize connection stats: {}", e))?;
+    let tmp_path = path.with_extension("json.tmp");
+    std::fs::write(&tmp_path, contents)
+        .map_err(|e| format!("Failed to write connection stats: {}", e))?;
+    std::fs::rename(&tmp_path, path)
+        .map_err(|e| format!("Failed to save connection stats: {}", e))
+}
@@ -0,0 +1,174 @@
+use crate::emit_interest::EventChannel;
+use crate::emitter::EventEmitter;
+use crate::error_counters::{self, ErrorCategory};
+use crate::evdev_types::EvdevControllerEvent;
+use crate::gamepad::ControllerEvent;
+use crate::key_codes;
+use crate::runtime_metrics;
+use serde::{Deserialize, Serialize};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Channel every input is emitted on, whichever backend read it.
+pub const CONTROLLER_EVENT: &str = "controller-event";
+
+/// Whether `gamepad-input` and `evdev-gamepad-input` are still emitted alongside
+/// `controller-event`. Kept for one release so older consumers can move over.
+static LEGACY_EVENTS: AtomicBool = AtomicBool::new(true);
+
+pub fn set_legacy_events_enabled(enabled: bool) {
+    LEGACY_EVENTS.store(enabled, Ordering::Relaxed);
+}
+
+pub fn legacy_events_enabled() -> bool {
+    LEGACY_EVENTS.load(Ordering::Relaxed)
+}
+
+/// Where an input came from. Replayed and mock input is `Synthetic` whichever backend
+/// it imitates; `controller_id` / `device_path` still say which. `Iio` is motion sampled
+/// from the IIO sensors when there's no evdev motion node.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum EventSource {
+    Gilrs,
+    Evdev,
+    Iio,
+    Synthetic,
+}
+
+/// What the input means, independent of the backend's own encoding.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum NormalizedInput {
+    Connected,
+    Disconnected,
+    Button { name: String, pressed: bool },
+    /// Sticks run -1.0 to 1.0 and triggers 0.0 to 1.0, as with gilrs
+    Axis { name: String, value: f32 },
+    /// Evdev events that are neither keys nor absolute axes, or axes whose range is unknown
+    Other { event_type: String, name: Option<String> },
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UnifiedControllerEvent {
+    pub source: EventSource,
+    /// `gilrs:<uuid>` or `evdev:<capability hash>`, falling back to the controller ID or
+    /// node path when the device isn't known (e.g. mock controllers)
+    pub device_id: String,
+    pub controller_id: Option<usize>,
+    pub device_path: Option<String>,
+    pub input: NormalizedInput,
+    /// Evdev only
+    pub raw_code: Option<u16>,
+    pub raw_value: Option<i32>,
+    /// When the device reported the input, ms since the epoch. Live input only.
+    pub kernel_timestamp_ms: Option<u64>,
+    pub emit_timestamp_ms: u64,
+    /// `kernel_timestamp_ms` unrounded, for latency measurement
+    #[serde(skip)]
+    pub source_time: Option<SystemTime>,
+}
+
+impl UnifiedControllerEvent {
+    pub fn from_gilrs(event: &ControllerEvent, device_id: String) -> Self {
+        let input = match (event.event_type.as_str(), &event.button, &event.axis) {
+            ("connected", _, _) => NormalizedInput::Connected,
+            ("disconnected", _, _) => NormalizedInput::Disconnected,
+            ("button-pressed", Some(button), _) => NormalizedInput::Button { name: button.clone(), pressed: true },
+            ("button-released", Some(button), _) => NormalizedInput::Button { name: button.clone(), pressed: false },
+            ("axis-changed", _, Some(axis)) => NormalizedInput::Axis {
+                name: axis.clone(),
+                value: event.value.unwrap_or_default(),
+            },
+            (event_type, _, _) => NormalizedInput::Other { event_type: event_type.to_string(), name: None },
+        };
+        Self {
+            source: if event.synthetic { EventSource::Synthetic } else { EventSource::Gilrs },
+            device_id,
+            controller_id: Some(event.controller_id),
+            device_path: None,
+            input,
+            raw_code: None,
+            raw_value: None,
+            kernel_timestamp_ms: event.source_time.map(epoch_ms),
+            emit_timestamp_ms: epoch_ms(SystemTime::now()),
+            source_time: event.source_time,
+        }
+    }
+
+    /// `range` is the axis' (minimum, maximum) for absolute events, when known.
+    pub fn from_evdev(event: &EvdevControllerEvent, device_id: String, range: Option<(i32, i32)>) -> Self {
+        let name = key_codes::lookup(&event.event_type, event.code);
+        let input = match (event.event_type.as_ref(), name, range) {
+            ("KEY", Some(name), _) => NormalizedInput::Button { name, pressed: event.value != 0 },
+            ("ABSOLUTE", Some(name), Some(range)) => NormalizedInput::Axis {
+                name,
+                value: normalize_axis(event.value, range),
+            },
+            (event_type, name, _) => NormalizedInput::Other { event_type: event_type.to_string(), name },
+        };
+        Self {
+            source: if event.synthetic { EventSource::Synthetic } else { EventSource::Evdev },
+            device_id,
+            controller_id: None,
+            device_path: Some(event.device_path.clone()),
+            input,
+            raw_code: Some(event.code),
+            raw_value: Some(event.value),
+            kernel_timestamp_ms: event.source_time.map(epoch_ms),
+            emit_timestamp_ms: epoch_ms(SystemTime::now()),
+            source_time: event.source_time,
+        }
+    }
+
+    /// `input` is already normalized like the evdev motion axis it stands in for.
+    pub fn from_iio(device_id: String, device_path: String, input: NormalizedInput, raw: i32, sampled_at: SystemTime) -> Self {
+        Self {
+            source: EventSource::Iio,
+            device_id,
+            controller_id: None,
+            device_path: Some(device_path),
+            input,
+            raw_code: None,
+            raw_value: Some(raw),
+            kernel_timestamp_ms: Some(epoch_ms(sampled_at)),
+            emit_timestamp_ms: epoch_ms(SystemTime::now()),
+            source_time: Some(sampled_at),
+        }
+    }
+}
+
+/// Axes that can go negative are scaled to -1.0..1.0 around zero, the rest to 0.0..1.0.
+pub fn normalize_axis(value: i32, (minimum, maximum): (i32, i32)) -> f32 {
+    if minimum < 0 {
+        let extent = minimum.unsigned_abs().max(maximum.unsigned_abs()).max(1) as f32;
+        (value as f32 / extent).clamp(-1.0, 1.0)
+    } else {
+        let span = (maximum - minimum).max(1) as f32;
+        ((value - minimum) as f32 / span).clamp(0.0, 1.0)
+    }
+}
+
+fn epoch_ms(time: SystemTime) -> u64 {
+    time.duration_since(UNIX_EPOCH).unwrap_or_default().as_millis() as u64
+}
+
+/// Emits `event` on `controller-event` if `channel` has subscribers, counting the result
+/// like any other input emit. The event stream, event scripts and external processor get
+/// it first.
+pub fn emit(app: &impl EventEmitter, event: &UnifiedControllerEvent, channel: EventChannel) {
+    // Whether or not a window is listening
+    if let (Some(handle), Some(state)) = (app.app_handle(), app.app_state()) {
+        state.event_stream.write_event(event);
+        state.event_scripts.handle(handle, event);
+        state.external_processor.send_event(event);
+    }
+    if !app.wants(channel, CONTROLLER_EVENT) {
+        return;
+    }
+    let result = app.emit_event(CONTROLLER_EVENT, event);
+    runtime_metrics::metrics().record_emit(result.is_ok());
+    if let Err(e) = result {
+        error_counters::errors().record(ErrorCategory::Emit, format!("{}: {}", CONTROLLER_EVENT, e));
+    }
+}
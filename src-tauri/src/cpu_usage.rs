@@ -0,0 +1,169 @@
+use serde::{Deserialize, Serialize};
+#[cfg(target_os = "linux")]
+use std::collections::HashMap;
+#[cfg(target_os = "linux")]
+use std::time::{Duration, Instant};
+
+pub const MAX_MEASURE_SECS: u64 = 300;
+/// What an idle app may use, in percent of one core: no controllers, window hidden.
+pub const IDLE_CPU_BUDGET_PERCENT: f64 = 1.0;
+
+/// CPU time from a `/proc/<pid>/stat` or `/proc/self/task/<tid>/stat` line.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StatTimes {
+    /// The thread or process name between the parentheses
+    pub name: String,
+    /// Clock ticks
+    pub user_ticks: u64,
+    pub system_ticks: u64,
+}
+
+/// The name may itself hold spaces and parentheses, so the fields are counted from the
+/// last `)`: `utime` and `stime` are the 12th and 13th after it.
+pub fn parse_stat(line: &str) -> Option<StatTimes> {
+    let open = line.find('(')?;
+    let close = line.rfind(')')?;
+    let fields: Vec<&str> = line.get(close + 1..)?.split_whitespace().collect();
+    Some(StatTimes {
+        name: line.get(open + 1..close)?.to_string(),
+        user_ticks: fields.get(11)?.parse().ok()?,
+        system_ticks: fields.get(12)?.parse().ok()?,
+    })
+}
+
+/// One thread's share of the measurement.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ThreadCpu {
+    pub name: String,
+    pub cpu_percent: f64,
+}
+
+/// Answer to `measure_idle_cpu`. Percentages are of one core.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct IdleCpuReport {
+    pub seconds: f64,
+    pub cpu_percent: f64,
+    pub user_percent: f64,
+    pub system_percent: f64,
+    /// Busiest first, threads that used nothing left out
+    pub threads: Vec<ThreadCpu>,
+    pub window_visible: bool,
+    pub connected_controllers: usize,
+    /// Window hidden and no controllers: the conditions the budget is set for
+    pub idle_conditions: bool,
+    pub budget_percent: f64,
+    pub within_budget: bool,
+}
+
+#[cfg(target_os = "linux")]
+fn clock_ticks_per_sec() -> f64 {
+    // SAFETY: sysconf only reads a configuration value
+    let ticks = unsafe { libc::sysconf(libc::_SC_CLK_TCK) };
+    if ticks > 0 { ticks as f64 } else { 100.0 }
+}
+
+#[cfg(target_os = "linux")]
+fn thread_times() -> HashMap<String, StatTimes> {
+    std::fs::read_dir("/proc/self/task")
+        .map(|entries| entries.flatten().collect::<Vec<_>>())
+        .unwrap_or_default()
+        .into_iter()
+        .filter_map(|entry| {
+            let stat = std::fs::read_to_string(entry.path().join("stat")).ok()?;
+            Some((entry.file_name().to_string_lossy().to_string(), parse_stat(&stat)?))
+        })
+        .collect()
+}
+
+/// Samples this process's CPU time over `duration`. Runs on the caller's thread, which
+/// sleeps throughout, so the measurement hardly counts itself. `conditions` gives the
+/// window's visibility and the connected controllers, and is asked at both ends so a
+/// window shown or a pad plugged in part way through still counts.
+#[cfg(target_os = "linux")]
+pub fn measure(duration: Duration, conditions: impl Fn() -> (bool, usize)) -> Result<IdleCpuReport, String> {
+    let (visible_before, controllers_before) = conditions();
+    let read_process = || {
+        std::fs::read_to_string("/proc/self/stat")
+            .ok()
+            .and_then(|stat| parse_stat(&stat))
+            .ok_or_else(|| "Failed to read /proc/self/stat".to_string())
+    };
+    let before = read_process()?;
+    let threads_before = thread_times();
+    let started = Instant::now();
+    std::thread::sleep(duration);
+    let after = read_process()?;
+    let threads_after = thread_times();
+    let seconds = started.elapsed().as_secs_f64();
+    let (visible_after, controllers_after) = conditions();
+    let window_visible = visible_before || visible_after;
+    let connected_controllers = controllers_before.max(controllers_after);
+
+    let percent = |ticks: u64| ticks as f64 / clock_ticks_per_sec() / seconds * 100.0;
+    let user_percent = percent(after.user_ticks.saturating_sub(before.user_ticks));
+    let system_percent = percent(after.system_ticks.saturating_sub(before.system_ticks));
+    let mut threads: Vec<ThreadCpu> = threads_after.iter()
+        .filter_map(|(tid, after)| {
+            let (user, system) = threads_before.get(tid).map_or((0, 0), |before| (before.user_ticks, before.system_ticks));
+            let ticks = after.user_ticks.saturating_sub(user) + after.system_ticks.saturating_sub(system);
+            (ticks > 0).then(|| ThreadCpu { name: after.name.clone(), cpu_percent: percent(ticks) })
+        })
+        .collect();
+    threads.sort_by(|a, b| b.cpu_percent.total_cmp(&a.cpu_percent));
+
+    let cpu_percent = user_percent + system_percent;
+    let idle_conditions = !window_visible && connected_controllers == 0;
+    Ok(IdleCpuReport {
+        seconds,
+        cpu_percent,
+        user_percent,
+        system_percent,
+        threads,
+        window_visible,
+        connected_controllers,
+        idle_conditions,
+        budget_percent: IDLE_CPU_BUDGET_PERCENT,
+        within_budget: cpu_percent < IDLE_CPU_BUDGET_PERCENT,
+    })
+}
+
+#[cfg(not(target_os = "linux"))]
+pub fn measure(_duration: std::time::Duration, _conditions: impl Fn() -> (bool, usize)) -> Result<IdleCpuReport, String> {
+    Err("CPU measurement reads /proc and is only available on Linux".to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const STAT: &str = "4242 (steam-deck-cont) S 1 4242 4242 0 -1 4194560 2571 0 0 0 137 58 0 0 20 0 24 0 1234 0 0";
+
+    #[test]
+    fn user_and_system_ticks_follow_the_name() {
+        let times = parse_stat(STAT).unwrap();
+        assert_eq!(times.name, "steam-deck-cont");
+        assert_eq!(times.user_ticks, 137);
+        assert_eq!(times.system_ticks, 58);
+    }
+
+    #[test]
+    fn names_with_spaces_and_parentheses_are_skipped_over() {
+        let times = parse_stat(&STAT.replace("(steam-deck-cont)", "(evdev (poll) 2)")).unwrap();
+        assert_eq!(times.name, "evdev (poll) 2");
+        assert_eq!(times.user_ticks, 137);
+        assert_eq!(times.system_ticks, 58);
+    }
+
+    #[test]
+    fn truncated_lines_are_rejected() {
+        assert!(parse_stat("4242 (short) S 1 4242").is_none());
+        assert!(parse_stat("").is_none());
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn this_process_can_be_read() {
+        let stat = std::fs::read_to_string("/proc/self/stat").unwrap();
+        assert!(parse_stat(&stat).is_some());
+    }
+}
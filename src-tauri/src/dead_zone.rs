@@ -0,0 +1,127 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+pub const DEFAULT_INNER_DEAD_ZONE: f32 = 0.1;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum StickSide {
+    Left,
+    Right,
+}
+
+impl StickSide {
+    /// The gilrs (x, y) axis names that make up this stick.
+    pub fn axes(self) -> (&'static str, &'static str) {
+        match self {
+            StickSide::Left => ("LeftStickX", "LeftStickY"),
+            StickSide::Right => ("RightStickX", "RightStickY"),
+        }
+    }
+
+    /// The stick `axis` belongs to, and whether it's the x axis.
+    fn for_axis(axis: &str) -> Option<(StickSide, bool)> {
+        [StickSide::Left, StickSide::Right].into_iter().find_map(|side| {
+            let (x, y) = side.axes();
+            (axis == x || axis == y).then_some((side, axis == x))
+        })
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DeadZoneShape {
+    /// Zeroes the stick only while both axes are inside the dead zone
+    #[default]
+    Square,
+    /// Zeroes the stick while it's within `inner_dead_zone` of center in any direction
+    Circular,
+    /// Zeroes each axis on its own, snapping near-cardinal deflections onto the axis
+    Cross,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct DeadZoneConfig {
+    pub shape: DeadZoneShape,
+    pub inner_dead_zone: f32,
+}
+
+impl Default for DeadZoneConfig {
+    fn default() -> Self {
+        Self {
+            shape: DeadZoneShape::default(),
+            inner_dead_zone: DEFAULT_INNER_DEAD_ZONE,
+        }
+    }
+}
+
+impl DeadZoneConfig {
+    fn apply(&self, x: f32, y: f32) -> (f32, f32) {
+        let inner = self.inner_dead_zone;
+        match self.shape {
+            DeadZoneShape::Square if x.abs() < inner && y.abs() < inner => (0.0, 0.0),
+            DeadZoneShape::Square => (x, y),
+            DeadZoneShape::Circular if (x * x + y * y).sqrt() < inner => (0.0, 0.0),
+            DeadZoneShape::Circular => (x, y),
+            DeadZoneShape::Cross => (
+                if x.abs() < inner { 0.0 } else { x },
+                if y.abs() < inner { 0.0 } else { y },
+            ),
+        }
+    }
+}
+
+#[derive(Default, Clone, Copy)]
+struct StickState {
+    raw: (f32, f32),
+    output: (f32, f32),
+}
+
+/// Applies dead zones to both axes of a stick together. Sticks without a configured
+/// shape pass through untouched, leaving only gilrs' own per-axis dead zone.
+#[derive(Default)]
+pub struct StickDeadZones {
+    configs: HashMap<(usize, StickSide), DeadZoneConfig>,
+    sticks: HashMap<(usize, StickSide), StickState>,
+}
+
+impl StickDeadZones {
+    pub fn set_shape(&mut self, controller_id: usize, stick: StickSide, shape: DeadZoneShape) {
+        self.configs.entry((controller_id, stick)).or_default().shape = shape;
+    }
+
+    /// Takes a raw axis value and returns the (axis, value) changes to send: the axis
+    /// itself, plus the other axis of its stick if the dead zone moved it too.
+    pub fn apply(&mut self, controller_id: usize, axis: &str, value: f32) -> Vec<(String, f32)> {
+        let Some((side, is_x)) = StickSide::for_axis(axis) else {
+            return vec![(axis.to_string(), value)];
+        };
+        let Some(config) = self.configs.get(&(controller_id, side)) else {
+            return vec![(axis.to_string(), value)];
+        };
+        let stick = self.sticks.entry((controller_id, side)).or_default();
+        if is_x {
+            stick.raw.0 = value;
+        } else {
+            stick.raw.1 = value;
+        }
+        let previous = stick.output;
+        stick.output = config.apply(stick.raw.0, stick.raw.1);
+
+        let (x_axis, y_axis) = side.axes();
+        let (own, other, other_axis, other_previous) = if is_x {
+            (stick.output.0, stick.output.1, y_axis, previous.1)
+        } else {
+            (stick.output.1, stick.output.0, x_axis, previous.0)
+        };
+        let mut changes = vec![(axis.to_string(), own)];
+        if other != other_previous {
+            changes.push((other_axis.to_string(), other));
+        }
+        changes
+    }
+
+    pub fn controller_disconnected(&mut self, controller_id: usize) {
+        self.sticks.retain(|(id, _), _| *id != controller_id);
+    }
+}
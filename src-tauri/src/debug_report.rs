@@ -0,0 +1,117 @@
+use crate::detection::DetectionDiagnosis;
+use crate::diagnostics::FullDebugInfo;
+use crate::evdev_types::EvdevDeviceDetails;
+use crate::gamepad::ControllerEvent;
+use crate::health::HealthReport;
+use crate::light_server::SendRecord;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::path::Path;
+
+pub const REPORT_LOG_LINES: usize = 500;
+
+/// Prefixes of environment variables that influence controller input handling.
+const INPUT_ENV_PREFIXES: [&str; 3] = ["SDL_", "STEAM", "Steam"];
+
+/// Keys whose values are dropped entirely, regardless of shape.
+const SECRET_KEY_MARKERS: [&str; 5] = ["token", "password", "secret", "api_key", "auth"];
+
+/// Keys that look token-shaped but are safe identifiers we need for triage.
+const SAFE_KEYS: [&str; 2] = ["capability_hash", "uuid"];
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DebugReport {
+    pub generated_at: u64,
+    pub health: HealthReport,
+    pub debug_info: FullDebugInfo,
+    pub detection: DetectionDiagnosis,
+    pub evdev_device_table: Vec<EvdevDeviceDetails>,
+    pub input_environment: BTreeMap<String, String>,
+    pub recent_log_lines: Vec<String>,
+    pub recent_events: Vec<ControllerEvent>,
+    pub recent_sends: Vec<SendRecord>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DebugReportResult {
+    pub path: String,
+    pub size_bytes: u64,
+}
+
+pub fn input_environment() -> BTreeMap<String, String> {
+    std::env::vars()
+        .filter(|(name, _)| INPUT_ENV_PREFIXES.iter().any(|prefix| name.starts_with(prefix)))
+        .collect()
+}
+
+/// Serializes `report` to `path` as pretty JSON after redacting URLs, tokens and secrets.
+pub fn write_report(report: &DebugReport, path: &Path) -> Result<DebugReportResult, String> {
+    let mut value = serde_json::to_value(report)
+        .map_err(|e| format!("Failed to serialize debug report: {}", e))?;
+    redact(&mut value, None);
+    
+    let contents = serde_json::to_string_pretty(&value)
+        .map_err(|e| format!("Failed to serialize debug report: {}", e))?;
+    
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)
+            .map_err(|e| format!("Failed to create {}: {}", parent.display(), e))?;
+    }
+    std::fs::write(path, &contents)
+        .map_err(|e| format!("Failed to write {}: {}", path.display(), e))?;
+    
+    Ok(DebugReportResult {
+        path: path.to_string_lossy().to_string(),
+        size_bytes: contents.len() as u64,
+    })
+}
+
+fn redact(value: &mut Value, key: Option<&str>) {
+    match value {
+        Value::String(text) if key.is_none_or(|key| !SAFE_KEYS.contains(&key)) => {
+            *text = redact_text(text);
+        }
+        Value::Array(items) => {
+            for item in items {
+                redact(item, key);
+            }
+        }
+        Value::Object(map) => {
+            for (name, item) in map.iter_mut() {
+                let lowered = name.to_lowercase();
+                if SECRET_KEY_MARKERS.iter().any(|marker| lowered.contains(marker)) {
+                    *item = Value::String("[redacted]".to_string());
+                } else {
+                    redact(item, Some(name));
+                }
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Replaces URL- and token-shaped words, keeping the surrounding whitespace intact.
+fn redact_text(text: &str) -> String {
+    text.split_inclusive(char::is_whitespace)
+        .map(|chunk| {
+            let word = chunk.trim_end_matches(char::is_whitespace);
+            let separator = &chunk[word.len()..];
+            if word.contains("://") {
+                format!("[redacted-url]{}", separator)
+            } else if looks_like_token(word) {
+                format!("[redacted-token]{}", separator)
+            } else {
+                chunk.to_string()
+            }
+        })
+        .collect()
+}
+
+fn looks_like_token(word: &str) -> bool {
+    let word = word.trim_matches(|c: char| !c.is_ascii_alphanumeric());
+    word.len() >= 32
+        && word.chars().all(|c| c.is_ascii_alphanumeric() || "-_+/=.".contains(c))
+        && word.chars().any(|c| c.is_ascii_digit())
+        && word.chars().any(|c| c.is_ascii_alphabetic())
+}
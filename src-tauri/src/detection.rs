@@ -0,0 +1,225 @@
+use crate::evdev_types::EvdevGamepadInfo;
+use crate::gamepad::GamepadInfo;
+use crate::permissions::PermissionsAnalysis;
+#[cfg(target_os = "linux")]
+use evdev::{Device, Key};
+use serde::{Deserialize, Serialize};
+#[cfg(target_os = "linux")]
+use std::os::unix::fs::MetadataExt;
+
+#[cfg(target_os = "linux")]
+const UDEV_DATA_DIR: &str = "/run/udev/data";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DetectionCauseKind {
+    NotReadable,
+    Grabbed,
+    NoGamepadButton,
+    MissingUdevJoystickTag,
+    MissingSdlMapping,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DetectionCause {
+    pub kind: DetectionCauseKind,
+    pub detail: String,
+    pub suggested_fix: String,
+}
+
+/// An evdev gamepad with no matching gilrs gamepad, and why that probably is.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UndetectedDevice {
+    pub device_path: String,
+    pub name: String,
+    pub vendor_id: Option<u16>,
+    pub product_id: Option<u16>,
+    /// SDL-style GUID, which is what gilrs uses as the gamepad UUID. `None` when the
+    /// node couldn't be opened to read its bus type.
+    pub sdl_guid: Option<String>,
+    /// Most likely first
+    pub causes: Vec<DetectionCause>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DetectedDevice {
+    pub device_path: String,
+    pub gilrs_id: usize,
+    pub gilrs_name: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DetectionDiagnosis {
+    pub gilrs_available: bool,
+    pub gilrs_gamepads: usize,
+    pub detected: Vec<DetectedDevice>,
+    pub undetected: Vec<UndetectedDevice>,
+}
+
+/// Cross-references evdev gamepads with what gilrs sees. With `probe_grabs`, readable
+/// nodes are briefly grabbed to detect another process holding them exclusively.
+pub fn diagnose(
+    gilrs_available: bool,
+    gilrs_gamepads: &[GamepadInfo],
+    evdev_devices: &[EvdevGamepadInfo],
+    probe_grabs: bool,
+) -> DetectionDiagnosis {
+    let permissions = PermissionsAnalysis::collect(evdev_devices, probe_grabs);
+    let user_mappings = user_sdl_mappings();
+
+    let mut detected = Vec::new();
+    let mut undetected = Vec::new();
+    for device in evdev_devices.iter().filter(|device| device.is_gamepad) {
+        let probe = probe_node(&device.device_path);
+        let sdl_guid = probe.as_ref().map(|probe| probe.sdl_guid.clone());
+
+        let matched = gilrs_gamepads.iter().find(|gamepad| {
+            sdl_guid.as_deref() == Some(gamepad.uuid.as_str())
+                || (device.vendor_id.is_some()
+                    && gamepad.vendor_id == device.vendor_id
+                    && gamepad.product_id == device.product_id)
+        });
+        if let Some(gamepad) = matched {
+            detected.push(DetectedDevice {
+                device_path: device.device_path.clone(),
+                gilrs_id: gamepad.id,
+                gilrs_name: gamepad.name.clone(),
+            });
+            continue;
+        }
+
+        let access = permissions.devices.iter().find(|access| access.device_path == device.device_path);
+        let mut causes = Vec::new();
+        if let Some(access) = access.filter(|access| !access.readable_by_us) {
+            causes.push(DetectionCause {
+                kind: DetectionCauseKind::NotReadable,
+                detail: format!(
+                    "Opening the node failed: {}",
+                    access.open_error.as_deref().unwrap_or("permission denied")
+                ),
+                suggested_fix: permissions.remediations.first()
+                    .map(|remediation| remediation.message.clone())
+                    .unwrap_or_else(|| "Make the node readable by your user, e.g. via the input group".to_string()),
+            });
+        }
+        if access.is_some_and(|access| access.grabbed == Some(true)) {
+            causes.push(DetectionCause {
+                kind: DetectionCauseKind::Grabbed,
+                detail: "Another process holds an exclusive grab on the node".to_string(),
+                suggested_fix: "Disable Steam Input for this controller or close the program grabbing it".to_string(),
+            });
+        }
+        if let Some(probe) = &probe {
+            if !probe.has_gamepad_button {
+                causes.push(DetectionCause {
+                    kind: DetectionCauseKind::NoGamepadButton,
+                    detail: "The node reports neither BTN_GAMEPAD nor BTN_JOYSTICK".to_string(),
+                    suggested_fix: "This is probably a sibling node (motion sensors, touchpad or keyboard); look for the controller's other event node".to_string(),
+                });
+            }
+        }
+        if udev_property(&device.device_path, "ID_INPUT_JOYSTICK") == Some(false) {
+            causes.push(DetectionCause {
+                kind: DetectionCauseKind::MissingUdevJoystickTag,
+                detail: "udev didn't tag the node with ID_INPUT_JOYSTICK=1".to_string(),
+                suggested_fix: "Add a udev rule setting ENV{ID_INPUT_JOYSTICK}=\"1\" for this device, then run `udevadm trigger`".to_string(),
+            });
+        }
+        if let Some(guid) = &sdl_guid {
+            if !user_mappings.contains(guid.as_str()) {
+                causes.push(DetectionCause {
+                    kind: DetectionCauseKind::MissingSdlMapping,
+                    detail: format!(
+                        "No user-supplied SDL mapping for GUID {}; gilrs's bundled database may still cover it",
+                        guid
+                    ),
+                    suggested_fix: format!(
+                        "Create a mapping for {} (e.g. with SDL2 Gamepad Tool) and add it to SDL_GAMECONTROLLERCONFIG",
+                        guid
+                    ),
+                });
+            }
+        }
+
+        undetected.push(UndetectedDevice {
+            device_path: device.device_path.clone(),
+            name: device.name.clone(),
+            vendor_id: device.vendor_id,
+            product_id: device.product_id,
+            sdl_guid,
+            causes,
+        });
+    }
+
+    DetectionDiagnosis {
+        gilrs_available,
+        gilrs_gamepads: gilrs_gamepads.len(),
+        detected,
+        undetected,
+    }
+}
+
+/// What `diagnose` reads from an event node it could open.
+struct NodeProbe {
+    sdl_guid: String,
+    has_gamepad_button: bool,
+}
+
+#[cfg(target_os = "linux")]
+fn probe_node(device_path: &str) -> Option<NodeProbe> {
+    let opened = Device::open(device_path).ok()?;
+    let id = opened.input_id();
+    Some(NodeProbe {
+        sdl_guid: sdl_guid(id.bus_type().0, id.vendor(), id.product(), id.version()),
+        has_gamepad_button: opened.supported_keys()
+            .is_some_and(|keys| keys.contains(Key::BTN_SOUTH) || keys.contains(Key::BTN_TRIGGER)),
+    })
+}
+
+#[cfg(not(target_os = "linux"))]
+fn probe_node(_device_path: &str) -> Option<NodeProbe> {
+    None
+}
+
+/// SDL's joystick GUID for a Linux evdev device (CRC left zero), little-endian
+/// 16-bit fields each followed by a zero word; matches gilrs's UUIDs.
+#[cfg(target_os = "linux")]
+fn sdl_guid(bus: u16, vendor: u16, product: u16, version: u16) -> String {
+    [bus, 0, vendor, 0, product, 0, version, 0]
+        .iter()
+        .flat_map(|word| word.to_le_bytes())
+        .map(|byte| format!("{:02x}", byte))
+        .collect()
+}
+
+/// Mappings from SDL_GAMECONTROLLERCONFIG and the file SDL_GAMECONTROLLERCONFIG_FILE
+/// names, lowercased.
+fn user_sdl_mappings() -> String {
+    let mut mappings = std::env::var("SDL_GAMECONTROLLERCONFIG").unwrap_or_default();
+    if let Some(contents) = std::env::var("SDL_GAMECONTROLLERCONFIG_FILE")
+        .ok()
+        .and_then(|path| std::fs::read_to_string(path).ok())
+    {
+        mappings.push('\n');
+        mappings.push_str(&contents);
+    }
+    mappings.to_lowercase()
+}
+
+/// Whether udev's database sets `property` to 1 for the node. `None` when there's no
+/// udev database to look in (containers, flatpak).
+#[cfg(target_os = "linux")]
+fn udev_property(device_path: &str, property: &str) -> Option<bool> {
+    let rdev = std::fs::metadata(device_path).ok()?.rdev();
+    // glibc's major()/minor() encoding
+    let major = ((rdev >> 8) & 0xfff) | ((rdev >> 32) & !0xfff);
+    let minor = (rdev & 0xff) | ((rdev >> 12) & !0xff);
+    let data = std::fs::read_to_string(format!("{}/c{}:{}", UDEV_DATA_DIR, major, minor)).ok()?;
+    let wanted = format!("E:{}=1", property);
+    Some(data.lines().any(|line| line == wanted))
+}
+
+#[cfg(not(target_os = "linux"))]
+fn udev_property(_device_path: &str, _property: &str) -> Option<bool> {
+    None
+}
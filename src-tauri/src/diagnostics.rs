@@ -0,0 +1,137 @@
+use crate::app_state::AppState;
+use crate::emit_interest::{self, EventSubscriptions};
+use crate::error_counters::{self, ErrorsSnapshot};
+use crate::evdev_types::{EvdevGamepadInfo, EvdevStats};
+use crate::event_script::EventScriptStatus;
+use crate::event_stream::EventStreamStatus;
+use crate::external_processor::ExternalProcessorStatus;
+use crate::gamepad::DebugInfo;
+use crate::health::{PipelineStatus, PollSource};
+use crate::iio_motion::MotionStatus;
+use crate::input_backend::BackendDebugInfo;
+use crate::key_emulation::KeyEmulationStatus;
+use crate::light_server::LightServerStatus;
+use crate::locking::MutexExt;
+use crate::midi_output::MidiStatus;
+use crate::opc_output::OpcStatus;
+use crate::panic_report::PanicReport;
+use crate::permissions::PermissionsAnalysis;
+use crate::settings::Settings;
+use crate::steam_deck::SteamDeckInfo;
+use crate::steam_input_check::SteamInputCheck;
+use crate::suspend_inhibit::SuspendInhibitStatus;
+use crate::system_info::SystemInfo;
+use crate::system_power::SystemPower;
+use crate::thread_priority::{self, InputThreadPriority};
+use crate::uinput_output::VirtualGamepadStatus;
+use crate::virtual_pads::VirtualPadStatus;
+use crate::window_focus::WindowFocusStatus;
+use serde::{Deserialize, Serialize};
+use tauri::AppHandle;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BuildInfo {
+    pub version: String,
+    pub target_os: String,
+    pub target_arch: String,
+    pub debug_build: bool,
+}
+
+impl BuildInfo {
+    pub fn collect(app: &AppHandle) -> Self {
+        Self {
+            version: app.package_info().version.to_string(),
+            target_os: std::env::consts::OS.to_string(),
+            target_arch: std::env::consts::ARCH.to_string(),
+            debug_build: cfg!(debug_assertions),
+        }
+    }
+}
+
+/// Everything the debug panel shows, assembled in one pass. Also the body of exported
+/// debug reports, so it only holds owned, serializable data.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FullDebugInfo {
+    pub build: BuildInfo,
+    pub gamepad: DebugInfo,
+    pub evdev_devices: Vec<EvdevGamepadInfo>,
+    pub evdev_stats: EvdevStats,
+    pub steam_deck: SteamDeckInfo,
+    pub steam_deck_summary: String,
+    pub system: SystemInfo,
+    pub settings: Settings,
+    pub light_server: LightServerStatus,
+    /// Polling passes that panicked and were recovered from
+    pub poll_panics: u64,
+    pub last_panic: Option<PanicReport>,
+    /// Times the watchdog saw a polling thread stall, across pipelines
+    pub poll_stalls: u64,
+    pub pipelines: Vec<PipelineStatus>,
+    pub input_backends: Vec<BackendDebugInfo>,
+    pub errors: ErrorsSnapshot,
+    /// Which event channels are being emitted, and for whom
+    pub event_subscriptions: EventSubscriptions,
+    pub input_threads: Vec<InputThreadPriority>,
+    /// Which motion source is active, evdev or the IIO fallback
+    pub motion: MotionStatus,
+    pub system_power: SystemPower,
+    /// Whether Steam Input looks to be holding back gamepad events, and the evidence
+    pub steam_input: SteamInputCheck,
+    pub virtual_pads: VirtualPadStatus,
+    pub suspend_inhibit: SuspendInhibitStatus,
+    /// Whether the app is focused and what's done about input while it isn't
+    pub window_focus: WindowFocusStatus,
+    pub virtual_gamepad: VirtualGamepadStatus,
+    pub key_emulation: KeyEmulationStatus,
+    pub midi: MidiStatus,
+    /// The event stream to disk: its file, size and what it has written
+    pub event_stream: EventStreamStatus,
+    pub event_scripts: Vec<EventScriptStatus>,
+    pub external_processor: ExternalProcessorStatus,
+    pub opc: OpcStatus,
+}
+
+pub fn collect_full_debug_info(app: &AppHandle, state: &AppState) -> FullDebugInfo {
+    let settings = state.settings.get();
+    let evdev_devices = state.evdev_manager.get_detected_devices();
+    let mut gamepad = state.gamepad_manager.get_debug_info(&PermissionsAnalysis::collect(&evdev_devices, false));
+    gamepad.tag_virtual_pads(&evdev_devices);
+    gamepad.apply_layout_overrides(&settings.layout_overrides);
+    let steam_deck = SteamDeckInfo::collect(&evdev_devices);
+    let virtual_pads = VirtualPadStatus::collect(&state.virtual_pads, &evdev_devices);
+
+    FullDebugInfo {
+        build: BuildInfo::collect(app),
+        gamepad,
+        evdev_devices,
+        evdev_stats: state.evdev_manager.get_stats(),
+        steam_deck_summary: steam_deck.summary(),
+        steam_deck,
+        system: state.system_info.with_current_dock(&state.dock),
+        settings,
+        light_server: state.light_server.get_status(),
+        poll_panics: state.panic_monitor.poll_panics(),
+        last_panic: state.panic_monitor.last_panic(),
+        poll_stalls: state.health_monitor.poll_stalls(),
+        pipelines: PollSource::ALL.iter()
+            .map(|&source| state.health_monitor.pipeline_status(source, state.shutdown.is_poll_thread_running(source)))
+            .collect(),
+        input_backends: state.backends.debug_info(),
+        errors: error_counters::errors().snapshot(),
+        event_subscriptions: emit_interest::interest().snapshot(),
+        input_threads: thread_priority::snapshot(),
+        motion: state.iio_motion.status(),
+        system_power: state.system_power.latest(),
+        steam_input: state.steam_input.lock_or_recover().clone(),
+        virtual_pads,
+        suspend_inhibit: state.suspend_inhibitor.status(),
+        window_focus: state.window_focus.status(),
+        virtual_gamepad: state.virtual_gamepad.status(),
+        key_emulation: state.key_emulator.status(),
+        midi: state.midi_output.status(),
+        event_stream: state.event_stream.status(),
+        event_scripts: state.event_scripts.statuses(),
+        external_processor: state.external_processor.status(),
+        opc: state.opc_output.status(),
+    }
+}
@@ -0,0 +1,296 @@
+use crate::app_state::AppState;
+use crate::gamepad::GamepadInfo;
+use crate::locking::MutexExt;
+use crate::slot_assignment::SlotAssignment;
+use crate::system_power::{self, POWER_SUPPLY_DIR};
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+use std::time::Duration;
+use tauri::{AppHandle, Emitter, Manager};
+use tracing::{info, warn};
+
+pub const DRM_DIR: &str = "/sys/class/drm";
+const DOCK_CHECK_INTERVAL: Duration = Duration::from_secs(5);
+/// The slot the preferred controller is put in.
+const PRIMARY_SLOT: &str = "P1";
+const VALVE_VENDOR_ID: u16 = 0x28de;
+const DECK_CONTROLLER_PRODUCT_ID: u16 = 0x1205;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DockState {
+    /// An external display is connected next to the built-in panel
+    Docked,
+    Handheld,
+    /// No built-in panel, so not a handheld, or DRM isn't readable
+    Unknown,
+}
+
+/// Which controller goes in the primary slot.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PreferredController {
+    BuiltIn,
+    /// The first connected pad that's neither built in nor a Steam virtual pad
+    External,
+    /// Leaves the slots as they are
+    Unchanged,
+}
+
+/// Which controller is primary docked and handheld, saved in settings.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct DockPolicy {
+    pub docked: PreferredController,
+    pub handheld: PreferredController,
+    /// Uses this instead of what the display connectors say
+    pub force_state: Option<DockState>,
+}
+
+impl Default for DockPolicy {
+    fn default() -> Self {
+        Self {
+            docked: PreferredController::External,
+            handheld: PreferredController::BuiltIn,
+            force_state: None,
+        }
+    }
+}
+
+impl DockPolicy {
+    pub fn preferred(&self, state: DockState) -> PreferredController {
+        match state {
+            DockState::Docked => self.docked,
+            DockState::Handheld => self.handheld,
+            DockState::Unknown => PreferredController::Unchanged,
+        }
+    }
+}
+
+/// A `/sys/class/drm` connector such as `card0-eDP-1`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DisplayConnector {
+    pub name: String,
+    /// The built-in panel (eDP, LVDS or DSI)
+    pub internal: bool,
+    pub connected: bool,
+}
+
+/// Answer to `get_dock_status`, payload of `dock-state-changed`, and part of the system info.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DockStatus {
+    /// After `force_state`
+    pub state: DockState,
+    pub detected: DockState,
+    pub external_displays: Vec<String>,
+    /// `None` when there's no mains supply to ask
+    pub ac_online: Option<bool>,
+    pub policy: DockPolicy,
+    /// What the last state change put in the primary slot
+    pub primary_controller: Option<usize>,
+}
+
+impl Default for DockStatus {
+    fn default() -> Self {
+        Self {
+            state: DockState::Unknown,
+            detected: DockState::Unknown,
+            external_displays: Vec::new(),
+            ac_online: None,
+            policy: DockPolicy::default(),
+            primary_controller: None,
+        }
+    }
+}
+
+pub fn read_connectors(root: &Path) -> Vec<DisplayConnector> {
+    let mut connectors: Vec<DisplayConnector> = std::fs::read_dir(root)
+        .map(|entries| entries.flatten().collect::<Vec<_>>())
+        .unwrap_or_default()
+        .into_iter()
+        .filter_map(|entry| {
+            // Connectors are `card<N>-<type>-<index>`; the cards themselves have no dash
+            let name = entry.file_name().to_string_lossy().to_string();
+            let (_, connector) = name.split_once('-')?;
+            let status = std::fs::read_to_string(entry.path().join("status")).ok()?;
+            Some(DisplayConnector {
+                internal: ["eDP", "LVDS", "DSI"].iter().any(|kind| connector.starts_with(kind)),
+                connected: status.trim() == "connected",
+                name: connector.to_string(),
+            })
+        })
+        .collect();
+    connectors.sort_by(|a, b| a.name.cmp(&b.name));
+    connectors
+}
+
+/// Docked when an external display is connected. USB-C docks show up as a DP connector
+/// through alt mode, so there's no separate dock signal to look for.
+pub fn detect(connectors: &[DisplayConnector]) -> DockState {
+    if !connectors.iter().any(|connector| connector.internal) {
+        return DockState::Unknown;
+    }
+    if connectors.iter().any(|connector| !connector.internal && connector.connected) {
+        DockState::Docked
+    } else {
+        DockState::Handheld
+    }
+}
+
+fn is_builtin(gamepad: &GamepadInfo) -> bool {
+    gamepad.vendor_id == Some(VALVE_VENDOR_ID) && gamepad.product_id == Some(DECK_CONTROLLER_PRODUCT_ID)
+}
+
+/// The connected gamepad `preferred` picks, if there is one.
+pub fn pick_primary(preferred: PreferredController, gamepads: &[GamepadInfo]) -> Option<usize> {
+    let mut connected = gamepads.iter().filter(|gamepad| gamepad.is_connected && !gamepad.is_virtual);
+    match preferred {
+        PreferredController::BuiltIn => connected.find(|gamepad| is_builtin(gamepad)),
+        PreferredController::External => connected.find(|gamepad| !is_builtin(gamepad)),
+        PreferredController::Unchanged => None,
+    }
+    .map(|gamepad| gamepad.id)
+}
+
+/// Payload of `dock-state-changed`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DockChange {
+    pub status: DockStatus,
+    pub slots: Vec<SlotAssignment>,
+}
+
+/// Checks the display connectors every few seconds. On a dock state change the preferred
+/// controller is put in the primary slot, and again when it connects later.
+pub fn spawn_monitor(app: AppHandle) {
+    let shutdown = app.state::<AppState>().shutdown.clone();
+    std::thread::Builder::new()
+        .name("dock-monitor".to_string())
+        .spawn(move || {
+            // Whether the current state's preference has been applied
+            let mut applied = false;
+            while !shutdown.is_requested() {
+                applied = check(&app, applied);
+                std::thread::sleep(DOCK_CHECK_INTERVAL);
+            }
+        })
+        .expect("Failed to spawn dock monitor thread");
+}
+
+/// Re-evaluates now, e.g. after the policy changed.
+pub fn refresh(app: &AppHandle) -> DockStatus {
+    check(app, false);
+    app.state::<AppState>().dock.lock_or_recover().clone()
+}
+
+fn check(app: &AppHandle, applied: bool) -> bool {
+    let state = app.state::<AppState>();
+    let policy = state.settings.get().dock_policy;
+    let connectors = read_connectors(Path::new(DRM_DIR));
+    let detected = detect(&connectors);
+    let dock_state = policy.force_state.unwrap_or(detected);
+
+    let mut latest = state.dock.lock_or_recover();
+    let changed = dock_state != latest.state || policy != latest.policy;
+    let mut applied = applied && !changed;
+    latest.state = dock_state;
+    latest.detected = detected;
+    latest.external_displays = connectors.iter()
+        .filter(|connector| !connector.internal && connector.connected)
+        .map(|connector| connector.name.clone())
+        .collect();
+    latest.ac_online = system_power::read_power(Path::new(POWER_SUPPLY_DIR)).ac_online;
+    latest.policy = policy;
+    if changed {
+        info!("Dock state: {:?} (detected {:?})", dock_state, detected);
+        latest.primary_controller = None;
+    }
+
+    let mut assigned = false;
+    if !applied {
+        match pick_primary(policy.preferred(dock_state), &state.gamepad_manager.gamepad_infos()) {
+            Some(controller_id) => match state.slot_assignments.assign(PRIMARY_SLOT, controller_id) {
+                Ok(()) => {
+                    info!("Controller {} is primary while {:?}", controller_id, dock_state);
+                    latest.primary_controller = Some(controller_id);
+                    applied = true;
+                    assigned = true;
+                }
+                Err(e) => warn!("Failed to make controller {} primary: {}", controller_id, e),
+            },
+            // Tried again each check until the preferred pad connects
+            None => applied = policy.preferred(dock_state) == PreferredController::Unchanged,
+        }
+    }
+
+    if changed || assigned {
+        let change = DockChange {
+            status: latest.clone(),
+            slots: state.slot_assignments.get(),
+        };
+        if let Err(e) = app.emit("dock-state-changed", change) {
+            warn!("Failed to emit dock-state-changed: {}", e);
+        }
+    }
+    applied
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::scratch_dir::ScratchDir;
+    use std::path::PathBuf;
+
+    /// A scratch directory standing in for `/sys/class/drm`.
+    struct FakeDrm(ScratchDir);
+
+    impl FakeDrm {
+        fn new(label: &str) -> Self {
+            let root = ScratchDir::new(&format!("drm-{}", label));
+            root.attributes("card0", &[]);
+            Self(root)
+        }
+
+        fn connector(&self, name: &str, status: &str) {
+            self.0.attributes(name, &[("status", status)]);
+        }
+
+        fn state(&self) -> DockState {
+            detect(&read_connectors(self.0.path()))
+        }
+    }
+
+    #[test]
+    fn an_external_display_next_to_the_panel_is_docked() {
+        let drm = FakeDrm::new("docked");
+        drm.connector("card0-eDP-1", "connected");
+        drm.connector("card0-DP-1", "connected");
+        let connectors = read_connectors(drm.0.path());
+        assert_eq!(connectors.len(), 2);
+        assert!(connectors.iter().any(|connector| connector.name == "eDP-1" && connector.internal));
+        assert_eq!(drm.state(), DockState::Docked);
+    }
+
+    #[test]
+    fn only_the_panel_is_handheld() {
+        let drm = FakeDrm::new("handheld");
+        drm.connector("card0-eDP-1", "connected");
+        drm.connector("card0-DP-1", "disconnected");
+        assert_eq!(drm.state(), DockState::Handheld);
+    }
+
+    #[test]
+    fn no_built_in_panel_is_unknown() {
+        let drm = FakeDrm::new("desktop");
+        drm.connector("card0-HDMI-A-1", "connected");
+        assert_eq!(drm.state(), DockState::Unknown);
+        assert_eq!(detect(&read_connectors(&PathBuf::from("/nonexistent/drm"))), DockState::Unknown);
+    }
+
+    #[test]
+    fn the_default_policy_prefers_the_external_pad_when_docked() {
+        let policy = DockPolicy::default();
+        assert_eq!(policy.preferred(DockState::Docked), PreferredController::External);
+        assert_eq!(policy.preferred(DockState::Handheld), PreferredController::BuiltIn);
+        assert_eq!(policy.preferred(DockState::Unknown), PreferredController::Unchanged);
+    }
+}
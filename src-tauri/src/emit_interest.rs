@@ -0,0 +1,198 @@
+use crate::controller_event::{NormalizedInput, CONTROLLER_EVENT};
+use crate::locking::MutexExt;
+use crate::runtime_metrics::{self, Counter};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Mutex, OnceLock};
+use std::time::{Duration, Instant};
+
+/// The built-in frontend. Until it first subscribes it gets every channel, as it did before
+/// there were channels.
+pub const FRONTEND_CONSUMER: &str = "frontend";
+
+/// Named groups of events that consumers subscribe to; events on a channel nobody is
+/// subscribed to aren't serialized or emitted. Events outside every channel (warnings,
+/// pause changes, panics, resumes) are rare enough to always go out.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EventChannel {
+    /// Button and key inputs
+    Buttons,
+    /// Sticks, triggers and other axes
+    Axes,
+    /// Gyro and accelerometer samples
+    Motion,
+    /// Trackpad axes and `touchpad-contact`
+    Trackpad,
+    /// Connects, disconnects, open failures, firmware changes and raw event dumps
+    Devices,
+    /// Light server warnings
+    Network,
+    /// Update download progress
+    Updates,
+}
+
+impl EventChannel {
+    pub const ALL: [EventChannel; 7] = [
+        EventChannel::Buttons,
+        EventChannel::Axes,
+        EventChannel::Motion,
+        EventChannel::Trackpad,
+        EventChannel::Devices,
+        EventChannel::Network,
+        EventChannel::Updates,
+    ];
+
+    /// The channel of a normalized input. Motion and trackpad samples look like any other
+    /// axis here, so callers that can tell them apart pick those channels themselves.
+    pub fn for_input(input: &NormalizedInput) -> Self {
+        match input {
+            NormalizedInput::Connected | NormalizedInput::Disconnected => EventChannel::Devices,
+            NormalizedInput::Button { .. } => EventChannel::Buttons,
+            NormalizedInput::Axis { .. } | NormalizedInput::Other { .. } => EventChannel::Axes,
+        }
+    }
+}
+
+/// Per-input events worth holding back when nobody is looking. The other channels' events
+/// are rare enough to go out whether or not the window is visible.
+pub const THROTTLED_EVENTS: [&str; 5] = [
+    CONTROLLER_EVENT,
+    "gamepad-input",
+    "evdev-gamepad-input",
+    "touchpad-contact",
+    "evdev-raw",
+];
+
+/// While the window is hidden, a subscribed per-input event still goes out this often so
+/// the views are roughly current when it comes back.
+pub const HIDDEN_EMIT_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Who is subscribed to what, for debug info.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EventSubscriptions {
+    /// Each consumer's channels
+    pub consumers: BTreeMap<String, Vec<EventChannel>>,
+    /// True until the frontend first subscribes; it gets every channel until then
+    pub frontend_default: bool,
+    /// Channels with at least one subscriber; events on the rest aren't emitted
+    pub active_channels: Vec<EventChannel>,
+    pub window_visible: bool,
+}
+
+/// Which channels have subscribers right now, and whether the window is visible. Only
+/// emission is affected; state, recording and forwarding see every input.
+pub struct EmitInterest {
+    window_visible: AtomicBool,
+    /// Subscription count per channel, per consumer. Views subscribe as they mount, so a
+    /// consumer can hold the same channel more than once.
+    subscriptions: Mutex<HashMap<String, HashMap<EventChannel, usize>>>,
+    last_hidden_emit: Mutex<HashMap<&'static str, Instant>>,
+}
+
+impl EmitInterest {
+    fn new() -> Self {
+        Self {
+            window_visible: AtomicBool::new(true),
+            subscriptions: Mutex::new(HashMap::new()),
+            last_hidden_emit: Mutex::new(HashMap::new()),
+        }
+    }
+
+    pub fn set_window_visible(&self, visible: bool) {
+        self.window_visible.store(visible, Ordering::Relaxed);
+    }
+
+    pub fn window_visible(&self) -> bool {
+        self.window_visible.load(Ordering::Relaxed)
+    }
+
+    pub fn subscribe(&self, consumer: &str, channels: &[EventChannel]) {
+        let mut subscriptions = self.subscriptions.lock_or_recover();
+        let subscriptions = subscriptions.entry(consumer.to_string()).or_default();
+        for channel in channels {
+            *subscriptions.entry(*channel).or_default() += 1;
+        }
+    }
+
+    /// Undoes `subscribe`. The frontend keeps its (possibly empty) entry, so it doesn't go
+    /// back to getting every channel.
+    pub fn unsubscribe(&self, consumer: &str, channels: &[EventChannel]) {
+        let mut all = self.subscriptions.lock_or_recover();
+        let Some(subscriptions) = all.get_mut(consumer) else {
+            return;
+        };
+        for channel in channels {
+            if let Some(count) = subscriptions.get_mut(channel) {
+                *count -= 1;
+                if *count == 0 {
+                    subscriptions.remove(channel);
+                }
+            }
+        }
+        if subscriptions.is_empty() && consumer != FRONTEND_CONSUMER {
+            all.remove(consumer);
+        }
+    }
+
+    /// Forgets the frontend's subscriptions, for a webview that reloaded: its old listeners
+    /// are gone and never unsubscribed, and the new page subscribes again as it mounts.
+    pub fn reset_subscriptions(&self) {
+        self.subscriptions.lock_or_recover().remove(FRONTEND_CONSUMER);
+    }
+
+    pub fn snapshot(&self) -> EventSubscriptions {
+        let subscriptions = self.subscriptions.lock_or_recover();
+        let consumers = subscriptions.iter()
+            .map(|(consumer, channels)| {
+                let mut channels: Vec<EventChannel> = channels.keys().copied().collect();
+                channels.sort();
+                (consumer.clone(), channels)
+            })
+            .collect();
+        EventSubscriptions {
+            consumers,
+            frontend_default: !subscriptions.contains_key(FRONTEND_CONSUMER),
+            active_channels: EventChannel::ALL.into_iter().filter(|channel| subscribed(&subscriptions, *channel)).collect(),
+            window_visible: self.window_visible(),
+        }
+    }
+
+    /// Whether `event` on `channel` should be emitted now. Suppressed events are counted.
+    pub fn wants(&self, channel: EventChannel, event: &str) -> bool {
+        let mut wanted = subscribed(&self.subscriptions.lock_or_recover(), channel);
+        if wanted && !self.window_visible() {
+            if let Some(throttled) = THROTTLED_EVENTS.iter().find(|throttled| **throttled == event) {
+                wanted = self.hidden_emit_due(throttled);
+            }
+        }
+        if !wanted {
+            runtime_metrics::metrics().increment(Counter::EmitsSuppressed);
+        }
+        wanted
+    }
+
+    fn hidden_emit_due(&self, event: &'static str) -> bool {
+        let now = Instant::now();
+        let mut last = self.last_hidden_emit.lock_or_recover();
+        match last.get(event) {
+            Some(at) if now.duration_since(*at) < HIDDEN_EMIT_INTERVAL => false,
+            _ => {
+                last.insert(event, now);
+                true
+            }
+        }
+    }
+}
+
+fn subscribed(subscriptions: &HashMap<String, HashMap<EventChannel, usize>>, channel: EventChannel) -> bool {
+    !subscriptions.contains_key(FRONTEND_CONSUMER)
+        || subscriptions.values().any(|channels| channels.contains_key(&channel))
+}
+
+static EMIT_INTEREST: OnceLock<EmitInterest> = OnceLock::new();
+
+pub fn interest() -> &'static EmitInterest {
+    EMIT_INTEREST.get_or_init(EmitInterest::new)
+}
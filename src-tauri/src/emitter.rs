@@ -0,0 +1,78 @@
+use crate::app_state::AppState;
+use crate::emit_interest::{self, EventChannel};
+use serde::Serialize;
+use tauri::{AppHandle, Emitter, Manager, State};
+
+#[cfg(feature = "test-support")]
+use crate::locking::MutexExt;
+#[cfg(feature = "test-support")]
+use std::sync::Mutex;
+
+/// Where the input path sends its events. The app emits them to the frontend; tests
+/// record them so the decoding can run without one.
+pub trait EventEmitter {
+    fn emit_event<S: Serialize + Clone>(&self, event: &str, payload: S) -> Result<(), String>;
+
+    /// Whether anyone is subscribed to `channel` right now; `event` is skipped when not.
+    fn wants(&self, _channel: EventChannel, _event: &str) -> bool {
+        true
+    }
+
+    /// Emits `event` only if `channel` has subscribers.
+    fn emit_on<S: Serialize + Clone>(&self, channel: EventChannel, event: &str, payload: S) -> Result<(), String> {
+        if !self.wants(channel, event) {
+            return Ok(());
+        }
+        self.emit_event(event, payload)
+    }
+
+    /// The app behind the emitter, for the outputs input is fed to. Tests have none.
+    fn app_handle(&self) -> Option<&AppHandle> {
+        None
+    }
+
+    fn app_state(&self) -> Option<State<'_, AppState>> {
+        self.app_handle()?.try_state::<AppState>()
+    }
+}
+
+impl EventEmitter for AppHandle {
+    fn emit_event<S: Serialize + Clone>(&self, event: &str, payload: S) -> Result<(), String> {
+        self.emit(event, payload).map_err(|e| e.to_string())
+    }
+
+    fn wants(&self, channel: EventChannel, event: &str) -> bool {
+        emit_interest::interest().wants(channel, event)
+    }
+
+    fn app_handle(&self) -> Option<&AppHandle> {
+        Some(self)
+    }
+}
+
+/// Keeps every event it's given, as JSON, in the order they were emitted.
+#[cfg(feature = "test-support")]
+#[derive(Default)]
+pub struct RecordingEmitter {
+    events: Mutex<Vec<(String, serde_json::Value)>>,
+}
+
+#[cfg(feature = "test-support")]
+impl RecordingEmitter {
+    /// Removes and returns the payloads of every `event` emitted so far.
+    pub fn take(&self, event: &str) -> Vec<serde_json::Value> {
+        let mut events = self.events.lock_or_recover();
+        let (taken, kept) = std::mem::take(&mut *events).into_iter().partition(|(name, _)| name == event);
+        *events = kept;
+        taken.into_iter().map(|(_, payload)| payload).collect()
+    }
+}
+
+#[cfg(feature = "test-support")]
+impl EventEmitter for RecordingEmitter {
+    fn emit_event<S: Serialize + Clone>(&self, event: &str, payload: S) -> Result<(), String> {
+        let payload = serde_json::to_value(payload).map_err(|e| e.to_string())?;
+        self.events.lock_or_recover().push((event.to_string(), payload));
+        Ok(())
+    }
+}
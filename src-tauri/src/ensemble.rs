@@ -0,0 +1,149 @@
+use crate::gamepad::ControllerState;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+
+/// How member states are combined into the ensemble's.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EnsembleStrategy {
+    /// Mean of each axis; a button is pressed when at least half the members press it
+    Average,
+    /// Median of each axis; buttons as for `Average`
+    Median,
+    /// A button is pressed only when more than half the members press it; axes are averaged
+    Majority,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EnsembleConfig {
+    pub controller_ids: Vec<usize>,
+    pub strategy: EnsembleStrategy,
+}
+
+/// Payload of `ensemble-state-changed`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EnsembleStateChanged {
+    pub name: String,
+    pub state: ControllerState,
+}
+
+struct Ensemble {
+    config: EnsembleConfig,
+    /// Last emitted state, so unchanged results aren't re-emitted
+    last_state: Option<ControllerState>,
+}
+
+/// Named groups of controllers whose states are combined into one, e.g. to smooth out
+/// noise across identical controllers.
+#[derive(Default)]
+pub struct Ensembles {
+    ensembles: BTreeMap<String, Ensemble>,
+}
+
+impl Ensembles {
+    pub fn create(&mut self, name: &str, config: EnsembleConfig) -> Result<String, String> {
+        if name.trim().is_empty() {
+            return Err("Ensemble name must not be empty".to_string());
+        }
+        if self.ensembles.contains_key(name) {
+            return Err(format!("Ensemble {} already exists", name));
+        }
+        validate(&config)?;
+        self.ensembles.insert(name.to_string(), Ensemble { config, last_state: None });
+        Ok(name.to_string())
+    }
+
+    pub fn update(&mut self, name: &str, config: EnsembleConfig) -> Result<(), String> {
+        validate(&config)?;
+        let ensemble = self.ensembles.get_mut(name)
+            .ok_or_else(|| format!("No ensemble named {}", name))?;
+        ensemble.config = config;
+        ensemble.last_state = None;
+        Ok(())
+    }
+
+    /// Returns false if there was no such ensemble.
+    pub fn delete(&mut self, name: &str) -> bool {
+        self.ensembles.remove(name).is_some()
+    }
+
+    pub fn state(&self, name: &str, states: &HashMap<usize, ControllerState>) -> Result<ControllerState, String> {
+        self.ensembles.get(name)
+            .map(|ensemble| combine(&ensemble.config, states))
+            .ok_or_else(|| format!("No ensemble named {}", name))
+    }
+
+    /// Recomputes the ensembles `controller_id` belongs to, returning those whose state changed.
+    pub fn member_changed(&mut self, controller_id: usize, states: &HashMap<usize, ControllerState>) -> Vec<EnsembleStateChanged> {
+        self.ensembles.iter_mut()
+            .filter(|(_, ensemble)| ensemble.config.controller_ids.contains(&controller_id))
+            .filter_map(|(name, ensemble)| {
+                let state = combine(&ensemble.config, states);
+                if ensemble.last_state.as_ref() == Some(&state) {
+                    return None;
+                }
+                ensemble.last_state = Some(state.clone());
+                Some(EnsembleStateChanged { name: name.clone(), state })
+            })
+            .collect()
+    }
+}
+
+fn validate(config: &EnsembleConfig) -> Result<(), String> {
+    if config.controller_ids.is_empty() {
+        return Err("An ensemble needs at least one controller".to_string());
+    }
+    Ok(())
+}
+
+/// Combines the connected members' states. The result carries the first member's ID
+/// and is connected while any member is.
+pub fn combine(config: &EnsembleConfig, states: &HashMap<usize, ControllerState>) -> ControllerState {
+    let members: Vec<&ControllerState> = config.controller_ids.iter()
+        .filter_map(|id| states.get(id))
+        .filter(|state| state.connected)
+        .collect();
+
+    let button_names: BTreeSet<&String> = members.iter().flat_map(|state| state.buttons.keys()).collect();
+    let buttons = button_names.into_iter()
+        .map(|button| {
+            let pressed = members.iter().filter(|state| state.buttons.get(button) == Some(&true)).count();
+            let is_pressed = match config.strategy {
+                EnsembleStrategy::Majority => pressed * 2 > members.len(),
+                EnsembleStrategy::Average | EnsembleStrategy::Median => pressed * 2 >= members.len(),
+            };
+            (button.clone(), is_pressed)
+        })
+        .collect();
+
+    let axis_names: BTreeSet<&String> = members.iter().flat_map(|state| state.axes.keys()).collect();
+    let axes = axis_names.into_iter()
+        .map(|axis| {
+            let mut values: Vec<f32> = members.iter().filter_map(|state| state.axes.get(axis).copied()).collect();
+            let value = match config.strategy {
+                EnsembleStrategy::Median => median(&mut values),
+                EnsembleStrategy::Average | EnsembleStrategy::Majority => {
+                    values.iter().sum::<f32>() / values.len() as f32
+                }
+            };
+            (axis.clone(), value)
+        })
+        .collect();
+
+    ControllerState {
+        buttons,
+        axes,
+        connected: !members.is_empty(),
+        controller_id: config.controller_ids[0],
+    }
+}
+
+fn median(values: &mut [f32]) -> f32 {
+    values.sort_unstable_by(f32::total_cmp);
+    let middle = values.len() / 2;
+    if values.len() % 2 == 1 {
+        values[middle]
+    } else {
+        (values[middle - 1] + values[middle]) / 2.0
+    }
+}
@@ -0,0 +1,125 @@
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+/// Error returned by every command.
+///
+/// Serialized as an object with a stable `kind` discriminant and a human-readable
+/// `message`, plus `status` for network errors:
+///
+/// ```json
+/// { "kind": "network", "status": 503, "message": "Server returned error: 503 Service Unavailable" }
+/// ```
+///
+/// `kind` is one of `io`, `permission`, `device_not_found`, `network`, `updater`,
+/// `invalid_argument` and `internal`. The frontend branches on it for user-facing copy
+/// and retries, so renaming a kind is a breaking change (see `tests/app_error.rs`).
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum AppError {
+    Io { message: String },
+    Permission { message: String },
+    DeviceNotFound { message: String },
+    /// `status` is the HTTP status when the server answered, `None` when it couldn't be reached
+    Network { status: Option<u16>, message: String },
+    Updater { message: String },
+    InvalidArgument { message: String },
+    /// Anything without a more specific kind, including errors still reported as plain strings
+    Internal { message: String },
+}
+
+impl AppError {
+    pub fn permission(message: impl Into<String>) -> Self {
+        AppError::Permission { message: message.into() }
+    }
+
+    pub fn device_not_found(message: impl Into<String>) -> Self {
+        AppError::DeviceNotFound { message: message.into() }
+    }
+
+    pub fn network(status: Option<u16>, message: impl Into<String>) -> Self {
+        AppError::Network { status, message: message.into() }
+    }
+
+    pub fn updater(message: impl Into<String>) -> Self {
+        AppError::Updater { message: message.into() }
+    }
+
+    pub fn invalid_argument(message: impl Into<String>) -> Self {
+        AppError::InvalidArgument { message: message.into() }
+    }
+
+    pub fn internal(message: impl Into<String>) -> Self {
+        AppError::Internal { message: message.into() }
+    }
+
+    pub fn message(&self) -> &str {
+        match self {
+            AppError::Io { message }
+            | AppError::Permission { message }
+            | AppError::DeviceNotFound { message }
+            | AppError::Network { message, .. }
+            | AppError::Updater { message }
+            | AppError::InvalidArgument { message }
+            | AppError::Internal { message } => message,
+        }
+    }
+}
+
+impl fmt::Display for AppError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.message())
+    }
+}
+
+impl std::error::Error for AppError {}
+
+impl From<String> for AppError {
+    fn from(message: String) -> Self {
+        AppError::internal(message)
+    }
+}
+
+impl From<&str> for AppError {
+    fn from(message: &str) -> Self {
+        AppError::internal(message)
+    }
+}
+
+impl From<AppError> for String {
+    fn from(error: AppError) -> Self {
+        error.to_string()
+    }
+}
+
+impl From<std::io::Error> for AppError {
+    fn from(error: std::io::Error) -> Self {
+        match error.kind() {
+            std::io::ErrorKind::PermissionDenied => AppError::permission(error.to_string()),
+            _ => AppError::Io { message: error.to_string() },
+        }
+    }
+}
+
+impl From<reqwest::Error> for AppError {
+    fn from(error: reqwest::Error) -> Self {
+        AppError::network(error.status().map(|status| status.as_u16()), error.to_string())
+    }
+}
+
+impl From<tauri_plugin_updater::Error> for AppError {
+    fn from(error: tauri_plugin_updater::Error) -> Self {
+        match error {
+            // The updater's reqwest can be a different version from the app's, so no `From`
+            tauri_plugin_updater::Error::Reqwest(error) => {
+                AppError::network(error.status().map(|status| status.as_u16()), error.to_string())
+            }
+            error => AppError::updater(error.to_string()),
+        }
+    }
+}
+
+impl From<tauri::Error> for AppError {
+    fn from(error: tauri::Error) -> Self {
+        AppError::internal(error.to_string())
+    }
+}
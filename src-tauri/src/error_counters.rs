@@ -0,0 +1,240 @@
+use crate::locking::MutexExt;
+use crate::memory_stats::{deque_bytes, BufferStats};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, VecDeque};
+use std::sync::{Mutex, OnceLock};
+use std::time::{SystemTime, UNIX_EPOCH};
+use tauri::{AppHandle, Emitter};
+use tracing::warn;
+
+const RATE_WINDOW_MS: u64 = 60_000;
+/// Failure times kept per category for the rate; a category failing faster than this
+/// a minute reads as this.
+pub const MAX_RECENT_FAILURES: usize = 10_000;
+/// Failures per minute above which a `backend-warning` is emitted, unless overridden.
+const DEFAULT_THRESHOLD_PER_MIN: u32 = 10;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ErrorCategory {
+    /// `app.emit` to the frontend failed
+    Emit,
+    /// A hot-path lock was held long enough to stall the polling loop
+    LockContention,
+    DeviceRead,
+    LightServerSend,
+    /// Writing an input recording or the event stream to disk failed
+    RecordingWrite,
+}
+
+const CATEGORIES: [ErrorCategory; 5] = [
+    ErrorCategory::Emit,
+    ErrorCategory::LockContention,
+    ErrorCategory::DeviceRead,
+    ErrorCategory::LightServerSend,
+    ErrorCategory::RecordingWrite,
+];
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ErrorCategorySnapshot {
+    pub category: ErrorCategory,
+    pub count: u64,
+    pub last_message: Option<String>,
+    pub last_at: Option<u64>,
+    pub last_minute: u64,
+    /// `None` when warnings are disabled for this category
+    pub threshold_per_min: Option<u32>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ErrorsSnapshot {
+    pub categories: Vec<ErrorCategorySnapshot>,
+    /// When the counters were last reset (or the app started)
+    pub since: u64,
+}
+
+impl ErrorsSnapshot {
+    /// Categories whose failures in the last minute exceed their threshold.
+    pub fn over_threshold(&self) -> impl Iterator<Item = &ErrorCategorySnapshot> {
+        self.categories.iter().filter(|category| {
+            category.threshold_per_min.is_some_and(|threshold| category.last_minute > threshold as u64)
+        })
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BackendWarning {
+    pub category: ErrorCategory,
+    pub last_minute: u64,
+    pub threshold_per_min: u32,
+    pub message: String,
+}
+
+#[derive(Default)]
+struct CategoryState {
+    count: u64,
+    last_message: Option<String>,
+    last_at: Option<u64>,
+    /// Timestamps of failures within the rate window
+    recent: VecDeque<u64>,
+    last_warned_at: Option<u64>,
+}
+
+impl CategoryState {
+    fn prune(&mut self, now: u64) {
+        while self.recent.front().is_some_and(|&at| now.saturating_sub(at) > RATE_WINDOW_MS) {
+            self.recent.pop_front();
+        }
+    }
+}
+
+struct Inner {
+    categories: BTreeMap<ErrorCategory, CategoryState>,
+    thresholds: BTreeMap<ErrorCategory, Option<u32>>,
+    since: u64,
+}
+
+/// Per-category failure counts with the most recent message, for failures that would
+/// otherwise only show up in the log.
+pub struct ErrorCounters {
+    inner: Mutex<Inner>,
+    app: OnceLock<AppHandle>,
+}
+
+impl Default for ErrorCounters {
+    fn default() -> Self {
+        Self {
+            inner: Mutex::new(Inner {
+                categories: BTreeMap::new(),
+                thresholds: BTreeMap::new(),
+                since: now_ms(),
+            }),
+            app: OnceLock::new(),
+        }
+    }
+}
+
+impl ErrorCounters {
+    pub fn attach_app(&self, app: AppHandle) {
+        let _ = self.app.set(app);
+    }
+
+    /// Counts a failure, emitting `backend-warning` (at most once a minute per category)
+    /// when the last minute's failures cross the category's threshold.
+    pub fn record(&self, category: ErrorCategory, message: impl Into<String>) {
+        let message = message.into();
+        let now = now_ms();
+        let warning = {
+            let mut inner = self.inner.lock_or_recover();
+            let threshold = inner.thresholds.get(&category).copied().unwrap_or(Some(DEFAULT_THRESHOLD_PER_MIN));
+            let state = inner.categories.entry(category).or_default();
+            state.count += 1;
+            state.last_at = Some(now);
+            if state.recent.len() >= MAX_RECENT_FAILURES {
+                state.recent.pop_front();
+            }
+            state.recent.push_back(now);
+            state.prune(now);
+
+            let last_minute = state.recent.len() as u64;
+            let cooled_down = state.last_warned_at.is_none_or(|at| now.saturating_sub(at) >= RATE_WINDOW_MS);
+            let warning = threshold
+                .filter(|&threshold| last_minute > threshold as u64 && cooled_down)
+                .map(|threshold_per_min| BackendWarning {
+                    category,
+                    last_minute,
+                    threshold_per_min,
+                    message: message.clone(),
+                });
+            if warning.is_some() {
+                state.last_warned_at = Some(now);
+            }
+            state.last_message = Some(message);
+            warning
+        };
+
+        if let Some(warning) = warning {
+            warn!(
+                "{:?} failures crossed {}/min ({} in the last minute): {}",
+                warning.category, warning.threshold_per_min, warning.last_minute, warning.message
+            );
+            // Not recorded as an emit failure, which could recurse into another warning
+            if let Some(app) = self.app.get() {
+                app.emit("backend-warning", warning).ok();
+            }
+        }
+    }
+
+    pub fn buffer_stats(&self) -> BufferStats {
+        let inner = self.inner.lock_or_recover();
+        let len = inner.categories.values().map(|state| state.recent.len()).sum();
+        let approx_bytes = inner.categories.values()
+            .map(|state| deque_bytes(&state.recent) + state.last_message.as_ref().map_or(0, String::capacity))
+            .sum();
+        BufferStats::new("error_rate_windows", len, Some(MAX_RECENT_FAILURES), inner.categories.len(), approx_bytes)
+    }
+
+    /// Sets the failures-per-minute threshold for `category`; `None` disables its warnings.
+    pub fn set_threshold(&self, category: ErrorCategory, per_minute: Option<u32>) {
+        let mut inner = self.inner.lock_or_recover();
+        inner.thresholds.insert(category, per_minute);
+    }
+
+    pub fn snapshot(&self) -> ErrorsSnapshot {
+        let now = now_ms();
+        let mut inner = self.inner.lock_or_recover();
+        let thresholds = inner.thresholds.clone();
+        let categories = CATEGORIES.iter()
+            .map(|&category| {
+                let threshold_per_min = thresholds.get(&category).copied().unwrap_or(Some(DEFAULT_THRESHOLD_PER_MIN));
+                match inner.categories.get_mut(&category) {
+                    Some(state) => {
+                        state.prune(now);
+                        ErrorCategorySnapshot {
+                            category,
+                            count: state.count,
+                            last_message: state.last_message.clone(),
+                            last_at: state.last_at,
+                            last_minute: state.recent.len() as u64,
+                            threshold_per_min,
+                        }
+                    }
+                    None => ErrorCategorySnapshot {
+                        category,
+                        count: 0,
+                        last_message: None,
+                        last_at: None,
+                        last_minute: 0,
+                        threshold_per_min,
+                    },
+                }
+            })
+            .collect();
+
+        ErrorsSnapshot {
+            categories,
+            since: inner.since,
+        }
+    }
+
+    /// Clears all counts. Thresholds are configuration, so they're kept.
+    pub fn reset(&self) {
+        let mut inner = self.inner.lock_or_recover();
+        inner.categories.clear();
+        inner.since = now_ms();
+    }
+}
+
+fn now_ms() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_millis() as u64
+}
+
+static ERROR_COUNTERS: OnceLock<ErrorCounters> = OnceLock::new();
+
+/// Process-wide error counters, recorded into from wherever a failure is handled.
+pub fn errors() -> &'static ErrorCounters {
+    ERROR_COUNTERS.get_or_init(ErrorCounters::default)
+}
@@ -1,60 +1,428 @@
-use evdev::{Device, EventType};
-use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use crate::capability_history::{self, CapabilityHistoryStore, CapabilitySet, CapabilitySnapshot};
+use crate::controller_event::{self, NormalizedInput, UnifiedControllerEvent};
+use crate::emit_interest::EventChannel;
+use crate::emitter::EventEmitter;
+use crate::error_counters::{self, ErrorCategory};
+use crate::evdev_types::{
+    AbsAxisDetails, DeviceCapability, EvdevControllerEvent, EvdevDeviceDetails, EvdevGamepadInfo, EvdevStats, FirmwareUpdate, UnhealthyDevice,
+};
+use crate::fan_control::{FanController, FanMode};
+use crate::fd_health::FdHealthMonitor;
+use crate::health::PollSource;
+use crate::iio_motion;
+use crate::input_backend::{BackendControllerState, BackendDebugInfo, InputBackend};
+use crate::input_pipeline::{EventCategory, InputMessage, InputSender};
+use crate::key_codes;
+use crate::latency_benchmark::{self, Stage};
+use crate::locking::MutexExt;
+use crate::low_battery::{self, LowBatteryConfig, LowBatteryMonitor};
+use crate::memory_stats::BufferStats;
+use crate::permissions::DeviceOpenFailure;
+use crate::poll_cadence;
+use crate::processing_pause;
+use crate::raw_dump::{RawDump, RawDumpStatus, RawDumpStopReason, RawEvdevEvent};
+use crate::recording::{InputSession, RecordedInput};
+use crate::runtime_metrics::{self, Counter};
+use crate::signal_quality::{self, DeviceSignalQuality, SignalQualityTracker};
+use crate::steam_deck::SteamDeckInfo;
+use crate::touchpad::{AbsInfo, TouchSlotTracker, TouchpadSide};
+use crate::virtual_pads;
+use evdev::{AbsoluteAxisType, Device, EventType, Key, PropType, Synchronization};
+use nix::fcntl::{fcntl, FcntlArg, OFlag};
+use std::os::fd::AsRawFd;
+use sha2::{Digest, Sha256};
+use std::borrow::Cow;
+use std::collections::{HashMap, HashSet, VecDeque};
 use std::fs::read_dir;
-use std::path::Path;
+use std::path::{Path, PathBuf};
 use std::sync::{Arc, Mutex};
-use std::time::{SystemTime, UNIX_EPOCH};
-use tauri::{AppHandle, Emitter};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use tauri::AppHandle;
+use tracing::{debug, info, trace, warn};
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct EvdevGamepadInfo {
-    pub device_path: String,
-    pub name: String,
-    pub vendor_id: Option<u16>,
-    pub product_id: Option<u16>,
-    pub is_gamepad: bool,
-    pub capabilities: Vec<String>,
+fn describe_device(device_path: &str, device: &Device) -> EvdevDeviceDetails {
+    let input_id = device.input_id();
+    let keys = device.supported_keys()
+        .map(|keys| keys.iter().map(|key| key_codes::key_name(key.code()).to_string()).collect())
+        .unwrap_or_default();
+    
+    let abs_state = device.get_abs_state().ok();
+    let axes = device.supported_absolute_axes()
+        .map(|axes| {
+            axes.iter()
+                .filter_map(|axis| {
+                    let info = abs_state.as_ref()?.get(axis.0 as usize)?;
+                    Some(AbsAxisDetails {
+                        axis: key_codes::abs_axis_name(axis.0).to_string(),
+                        code: axis.0,
+                        value: info.value,
+                        minimum: info.minimum,
+                        maximum: info.maximum,
+                        fuzz: info.fuzz,
+                        flat: info.flat,
+                        resolution: info.resolution,
+                    })
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+    
+    EvdevDeviceDetails {
+        device_path: device_path.to_string(),
+        name: device.name().unwrap_or("Unknown").to_string(),
+        bus_type: format!("{:?}", input_id.bus_type()),
+        vendor_id: input_id.vendor(),
+        product_id: input_id.product(),
+        version: input_id.version(),
+        keys,
+        axes,
+    }
+}
+
+/// Reopen attempts for an unhealthy device before giving up (one per health check).
+const MAX_RECONNECT_ATTEMPTS: u32 = 30;
+const CENTER_SAMPLE_INTERVAL: Duration = Duration::from_millis(5);
+/// Open failures kept for `get_open_failures`.
+const MAX_OPEN_FAILURES: usize = 20;
+
+struct PendingReconnect {
+    capability_hash: String,
+    /// `capability_history::device_identity`, for a device whose capabilities changed while it was away
+    identity: String,
+    attempts: u32,
+}
+
+fn now_ms() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_millis() as u64
+}
+
+fn name_suggests_gamepad(name: &str) -> bool {
+    let name = name.to_lowercase();
+    ["gamepad", "controller", "xbox", "steam", "deck", "joy"]
+        .iter()
+        .any(|hint| name.contains(hint))
+}
+
+/// Describes an event node that couldn't be opened from what sysfs exposes to
+/// everyone, or `None` if its name doesn't look like a gamepad.
+fn unopened_gamepad_info(path: &Path, event_name: &str) -> Option<EvdevGamepadInfo> {
+    let sysfs = Path::new("/sys/class/input").join(event_name).join("device");
+    let read_attr = |attr: &str| std::fs::read_to_string(sysfs.join(attr)).ok().map(|value| value.trim().to_string());
+    let read_id = |attr: &str| read_attr(&format!("id/{}", attr)).and_then(|value| u16::from_str_radix(&value, 16).ok());
+    let name = read_attr("name")?;
+    if !name_suggests_gamepad(&name) {
+        return None;
+    }
+    Some(EvdevGamepadInfo {
+        device_path: path.to_string_lossy().to_string(),
+        name: format!("{} (No Access)", name),
+        vendor_id: read_id("vendor"),
+        product_id: read_id("product"),
+        // Left out so an unreadable node never overwrites a stored firmware version
+        version: None,
+        is_gamepad: true,
+        capabilities: vec![DeviceCapability::InferredGamepad],
+        capability_hash: String::new(),
+        accessible: false,
+        fd_errors: 0,
+        is_virtual: virtual_pads::sysfs_is_virtual(event_name),
+    })
+}
+
+/// Switches the descriptor to non-blocking so `fetch_events` returns `WouldBlock`
+/// instead of stalling the polling thread.
+pub fn set_nonblocking(device: &Device) -> Result<(), String> {
+    let fd = device.as_raw_fd();
+    let flags = fcntl(fd, FcntlArg::F_GETFL).map_err(|e| e.to_string())?;
+    fcntl(fd, FcntlArg::F_SETFL(OFlag::from_bits_truncate(flags) | OFlag::O_NONBLOCK))
+        .map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+/// How the event bus may treat `event` under pressure. Slot and tracking ID changes are
+/// kept, since the multitouch positions that follow them depend on them.
+fn event_category(event: &EvdevControllerEvent, motion: bool, touchpad: bool) -> EventCategory {
+    let multitouch_state = event.code == AbsoluteAxisType::ABS_MT_SLOT.0
+        || event.code == AbsoluteAxisType::ABS_MT_TRACKING_ID.0;
+    match event.event_type.as_ref() {
+        "ABSOLUTE" if multitouch_state => EventCategory::Discrete,
+        "ABSOLUTE" | "RELATIVE" | "MISC" if motion => EventCategory::Motion,
+        "ABSOLUTE" if touchpad => EventCategory::Touchpad,
+        "ABSOLUTE" | "RELATIVE" => EventCategory::Axis,
+        _ => EventCategory::Discrete,
+    }
+}
+
+/// Which subscription channel an event goes out on. Motion and trackpad devices report
+/// plain axes, so their category decides.
+fn event_channel(category: EventCategory, input: &NormalizedInput) -> EventChannel {
+    match category {
+        EventCategory::Motion => EventChannel::Motion,
+        EventCategory::Touchpad => EventChannel::Trackpad,
+        EventCategory::Discrete | EventCategory::Axis => EventChannel::for_input(input),
+    }
+}
+
+/// Fingerprints a device from its vendor/product IDs, supported keys, absolute axes
+/// (including their ranges) and name. The current axis values are deliberately left
+/// out so the hash doesn't change while the device is in use.
+pub fn hash_device_capabilities(device: &Device) -> [u8; 32] {
+    let input_id = device.input_id();
+    let keys = device.supported_keys()
+        .map(|keys| keys.iter().map(|key| key.code()).collect())
+        .unwrap_or_default();
+    let abs_state = device.get_abs_state().ok();
+    let axes = device.supported_absolute_axes()
+        .map(|axes| axes.iter().map(|axis| {
+            let info = abs_state.as_ref()
+                .and_then(|state| state.get(axis.0 as usize))
+                .map(|info| [info.minimum, info.maximum, info.fuzz, info.flat, info.resolution]);
+            (axis.0, info)
+        }).collect())
+        .unwrap_or_default();
+    hash_capabilities(input_id.vendor(), input_id.product(), keys, axes, device.name().unwrap_or(""))
+}
+
+/// Key codes and axis codes are sorted first, so the order the kernel lists them in
+/// doesn't matter. Each axis carries its minimum, maximum, fuzz, flat and resolution
+/// when they could be read.
+fn hash_capabilities(
+    vendor: u16,
+    product: u16,
+    mut keys: Vec<u16>,
+    mut axes: Vec<(u16, Option<[i32; 5]>)>,
+    name: &str,
+) -> [u8; 32] {
+    let mut hasher = Sha256::new();
+    hasher.update(vendor.to_le_bytes());
+    hasher.update(product.to_le_bytes());
+    keys.sort_unstable();
+    for code in keys {
+        hasher.update(code.to_le_bytes());
+    }
+    axes.sort_unstable_by_key(|(code, _)| *code);
+    for (code, info) in axes {
+        hasher.update(code.to_le_bytes());
+        for field in info.iter().flatten() {
+            hasher.update(field.to_le_bytes());
+        }
+    }
+    hasher.update(name.as_bytes());
+    hasher.finalize().into()
+}
+
+/// (minimum, maximum) of each absolute axis, by code.
+type AxisRanges = HashMap<u16, (i32, i32)>;
+
+/// The ranges of every absolute axis the device reports.
+fn read_axis_ranges(device: &Device) -> AxisRanges {
+    let (Some(axes), Ok(abs_state)) = (device.supported_absolute_axes(), device.get_abs_state()) else {
+        return HashMap::new();
+    };
+    axes.iter()
+        .filter_map(|axis| {
+            let info = abs_state.get(axis.0 as usize)?;
+            Some((axis.0, (info.minimum, info.maximum)))
+        })
+        .collect()
+}
+
+/// Code of an absolute axis given by its kernel name, e.g. "ABS_RX".
+fn axis_code(axis_name: &str) -> Result<u16, String> {
+    key_codes::all_abs_codes()
+        .into_iter()
+        .find(|(_, name)| name == axis_name)
+        .map(|(code, _)| code)
+        .ok_or_else(|| format!("Unknown absolute axis {}", axis_name))
+}
+
+pub fn hash_to_hex(hash: &[u8]) -> String {
+    hash.iter().map(|byte| format!("{:02x}", byte)).collect()
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct EvdevControllerEvent {
-    pub device_path: String,
-    pub event_type: String,
-    pub code: u16,
-    pub value: i32,
-    pub timestamp: u64,
+enum VersionChange {
+    Unchanged,
+    FirstSeen,
+    Updated(FirmwareUpdate),
+}
+
+/// Stores `info.version` under the device's identity rather than its capability hash,
+/// since an update that changes the device's capabilities changes the hash too.
+fn record_firmware_version(versions: &mut HashMap<String, u16>, info: &EvdevGamepadInfo) -> VersionChange {
+    let Some(version) = info.version else {
+        return VersionChange::Unchanged;
+    };
+    let identity = capability_history::device_identity(
+        info.vendor_id.unwrap_or_default(),
+        info.product_id.unwrap_or_default(),
+        &info.name,
+    );
+    match versions.insert(identity, version) {
+        Some(previous) if previous == version => VersionChange::Unchanged,
+        Some(old_version) => VersionChange::Updated(FirmwareUpdate {
+            device_name: info.name.clone(),
+            old_version,
+            new_version: version,
+        }),
+        None => VersionChange::FirstSeen,
+    }
+}
+
+/// Where last-seen firmware versions are kept: `~/.cache/steamdeck-controller/device_versions.json`.
+fn device_versions_path() -> Option<PathBuf> {
+    std::env::var_os("HOME").map(|home| {
+        PathBuf::from(home)
+            .join(".cache")
+            .join("steamdeck-controller")
+            .join("device_versions.json")
+    })
+}
+
+fn load_device_versions(path: &Path) -> HashMap<String, u16> {
+    match std::fs::read_to_string(path) {
+        Ok(contents) => serde_json::from_str(&contents).unwrap_or_else(|e| {
+            warn!("Ignoring unreadable device versions file {}: {}", path.display(), e);
+            HashMap::new()
+        }),
+        Err(_) => HashMap::new(),
+    }
+}
+
+fn save_device_versions(path: &Path, versions: &HashMap<String, u16>) -> Result<(), String> {
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)
+            .map_err(|e| format!("Failed to create {}: {}", parent.display(), e))?;
+    }
+    let contents = serde_json::to_string_pretty(versions)
+        .map_err(|e| format!("Failed to serialize device versions: {}", e))?;
+    let tmp_path = path.with_extension("json.tmp");
+    std::fs::write(&tmp_path, contents)
+        .map_err(|e| format!("Failed to write device versions: {}", e))?;
+    std::fs::rename(&tmp_path, path)
+        .map_err(|e| format!("Failed to save device versions: {}", e))
 }
 
 pub struct EvdevGamepadManager {
     devices: Arc<Mutex<HashMap<String, Device>>>,
     gamepad_devices: Arc<Mutex<Vec<EvdevGamepadInfo>>>,
+    last_scan_time: Arc<Mutex<Option<u64>>>,
+    session: Arc<InputSession>,
+    /// Last seen `input_id.version()` per device identity (`capability_history::device_identity`)
+    last_known_versions: Arc<Mutex<HashMap<String, u16>>>,
+    versions_path: Option<PathBuf>,
+    health_monitor: Mutex<FdHealthMonitor>,
+    /// Devices removed as unhealthy, keyed by path, that are being reopened
+    pending_reconnects: Mutex<HashMap<String, PendingReconnect>>,
+    fan: Mutex<FanController>,
+    raw_dump: Mutex<Option<RawDump>>,
+    /// Raw value each axis (by code) rests at per device path, subtracted from its events
+    center_offsets: Mutex<HashMap<String, HashMap<u16, i32>>>,
+    /// Most recent failures to open a gamepad node during a scan, oldest first
+    open_failures: Mutex<VecDeque<DeviceOpenFailure>>,
+    signal_quality: Mutex<HashMap<String, SignalQualityTracker>>,
+    capability_history: Mutex<CapabilityHistoryStore>,
+    /// (minimum, maximum) of each absolute axis per device path, for normalizing unified events
+    axis_ranges: Mutex<HashMap<String, AxisRanges>>,
+    /// Multitouch slot state of each device path that reports touchpad positions
+    touchpads: Mutex<HashMap<String, TouchSlotTracker>>,
+    low_battery: LowBatteryMonitor,
+    /// Paths of the nodes grabbed for background input
+    grabbed: Mutex<HashSet<String>>,
+    /// Node grabbed for the virtual gamepad's passthrough, grabbed again whenever it's reopened
+    passthrough_grab: Mutex<Option<String>>,
+    /// `Settings::ignored_devices`, checked on every scan
+    ignored: Mutex<Vec<String>>,
+    input_tx: InputSender,
 }
 
 impl EvdevGamepadManager {
-    pub fn new() -> Result<Self, String> {
-        println!("🔧 Initializing EvdevGamepadManager for Steam Deck compatibility...");
+    pub fn new(session: Arc<InputSession>, input_tx: InputSender) -> Result<Self, String> {
+        info!("Initializing EvdevGamepadManager for Steam Deck compatibility");
+        
+        let versions_path = device_versions_path();
+        let last_known_versions = versions_path.as_deref()
+            .map(load_device_versions)
+            .unwrap_or_default();
         
         Ok(Self {
             devices: Arc::new(Mutex::new(HashMap::new())),
             gamepad_devices: Arc::new(Mutex::new(Vec::new())),
+            last_scan_time: Arc::new(Mutex::new(None)),
+            session,
+            last_known_versions: Arc::new(Mutex::new(last_known_versions)),
+            versions_path,
+            health_monitor: Mutex::new(FdHealthMonitor::default()),
+            pending_reconnects: Mutex::new(HashMap::new()),
+            fan: Mutex::new(FanController::discover()),
+            raw_dump: Mutex::new(None),
+            center_offsets: Mutex::new(HashMap::new()),
+            open_failures: Mutex::new(VecDeque::new()),
+            signal_quality: Mutex::new(HashMap::new()),
+            capability_history: Mutex::new(CapabilityHistoryStore::load()),
+            axis_ranges: Mutex::new(HashMap::new()),
+            touchpads: Mutex::new(HashMap::new()),
+            low_battery: LowBatteryMonitor::default(),
+            grabbed: Mutex::new(HashSet::new()),
+            passthrough_grab: Mutex::new(None),
+            ignored: Mutex::new(Vec::new()),
+            input_tx,
         })
     }
     
-    pub fn scan_for_gamepad_devices(&self) -> Result<(), String> {
-        let mut devices = self.devices.lock().unwrap();
-        let mut gamepad_devices = self.gamepad_devices.lock().unwrap();
+    pub fn scan_for_gamepad_devices(&self, app: &impl EventEmitter) -> Result<(), String> {
+        poll_cadence::cadence().note_activity();
+        let (firmware_updates, open_failures) = self.scan_devices()?;
+        for update in firmware_updates {
+            app.emit_on(EventChannel::Devices, "device-firmware-updated", update).ok();
+        }
+        for failure in open_failures {
+            app.emit_on(EventChannel::Devices, "device-open-failed", failure).ok();
+        }
+        Ok(())
+    }
+    
+    /// Devices later scans leave alone; takes effect on the next scan.
+    pub fn set_ignored_devices(&self, entries: Vec<String>) {
+        *self.ignored.lock_or_recover() = entries;
+    }
+    
+    fn is_ignored(&self, info: &EvdevGamepadInfo) -> bool {
+        self.ignored.lock_or_recover().iter().any(|entry| info.matches_ignore_entry(entry))
+    }
+    
+    /// The last `MAX_OPEN_FAILURES` gamepad nodes a scan couldn't open, oldest first.
+    pub fn get_open_failures(&self) -> Vec<DeviceOpenFailure> {
+        self.open_failures.lock_or_recover().iter().cloned().collect()
+    }
+    
+    /// Rebuilds the device list, returning any devices whose firmware version changed
+    /// since they were last seen and any gamepad nodes that couldn't be opened.
+    fn scan_devices(&self) -> Result<(Vec<FirmwareUpdate>, Vec<DeviceOpenFailure>), String> {
+        // Cleared before taking the device locks; attempt_reconnects takes them in the other order
+        self.pending_reconnects.lock_or_recover().clear();
+        let mut devices = self.devices.lock_or_recover();
+        let mut gamepad_devices = self.gamepad_devices.lock_or_recover();
         
         devices.clear();
         gamepad_devices.clear();
+        self.signal_quality.lock_or_recover().clear();
+        let mut axis_ranges = self.axis_ranges.lock_or_recover();
+        axis_ranges.clear();
+        let mut touchpads = self.touchpads.lock_or_recover();
+        touchpads.clear();
         
-        println!("🔍 Scanning /dev/input for gamepad devices...");
+        debug!("Scanning /dev/input for gamepad devices");
         
         let input_dir = Path::new("/dev/input");
         if !input_dir.exists() {
             return Err("❌ /dev/input directory not found".to_string());
         }
         
+        let mut firmware_updates = Vec::new();
+        let mut open_failures = Vec::new();
         let entries = read_dir(input_dir)
             .map_err(|e| format!("❌ Failed to read /dev/input: {}", e))?;
             
@@ -66,46 +434,134 @@ impl EvdevGamepadManager {
                 if let Some(name_str) = file_name.to_str() {
                     // Only check event devices
                     if name_str.starts_with("event") {
-                        match self.analyze_device(&path) {
-                            Ok(Some(info)) => {
-                                println!("🎮 Found potential gamepad: {}", info.name);
-                                
-                                // Try to open the device
-                                match Device::open(&path) {
-                                    Ok(device) => {
-                                        devices.insert(path.to_string_lossy().to_string(), device);
-                                        gamepad_devices.push(info);
-                                        println!("✅ Successfully opened: {}", path.display());
+                        match Device::open(&path) {
+                            Ok(mut device) => {
+                                let info = self.analyze_device(&path, &device);
+                                if let Some(info) = info.as_ref().filter(|info| self.is_ignored(info)) {
+                                    debug!("Ignoring {} ({})", path.display(), info.name);
+                                } else if let Some(info) = info {
+                                    debug!("Found potential gamepad: {}", info.name);
+                                    firmware_updates.extend(self.check_firmware_version(&info));
+                                    self.record_capabilities(&device);
+                                    info!("Opened evdev device {} ({})", path.display(), info.name);
+                                    if let Err(e) = set_nonblocking(&device) {
+                                        warn!("Could not make {} non-blocking: {}", path.display(), e);
                                     }
-                                    Err(e) => {
-                                        println!("⚠️  Could not open {}: {} (permissions?)", path.display(), e);
-                                        // Still add to list but mark as inaccessible
-                                        let mut info_copy = info;
-                                        info_copy.name = format!("{} (No Access)", info_copy.name);
-                                        gamepad_devices.push(info_copy);
+                                    axis_ranges.insert(info.device_path.clone(), read_axis_ranges(&device));
+                                    if let Some(tracker) = TouchSlotTracker::for_device(&device) {
+                                        touchpads.insert(info.device_path.clone(), tracker);
                                     }
+                                    self.grab_for_passthrough(&info.device_path, &mut device);
+                                    devices.insert(path.to_string_lossy().to_string(), device);
+                                    gamepad_devices.push(info);
                                 }
                             }
-                            Ok(None) => {
-                                // Not a gamepad device, ignore
-                            }
-                            Err(e) => {
-                                println!("⚠️  Error analyzing {}: {}", path.display(), e);
-                            }
+                            // Unopenable nodes are only reported when sysfs says they're gamepads,
+                            // otherwise every keyboard and mouse would show up
+                            Err(e) => match unopened_gamepad_info(&path, name_str) {
+                                Some(info) => {
+                                    warn!("Could not open {}: {}", path.display(), e);
+                                    let failure = DeviceOpenFailure::new(&info.device_path, &info.name, &e, now_ms());
+                                    open_failures.push(failure);
+                                    gamepad_devices.push(info);
+                                }
+                                None => debug!("Could not open {}: {}", path.display(), e),
+                            },
                         }
                     }
                 }
             }
         }
         
-        println!("🎮 Found {} potential gamepad devices", gamepad_devices.len());
-        Ok(())
+        if !open_failures.is_empty() {
+            let mut recent = self.open_failures.lock_or_recover();
+            recent.extend(open_failures.iter().cloned());
+            let excess = recent.len().saturating_sub(MAX_OPEN_FAILURES);
+            recent.drain(..excess);
+        }
+        
+        info!("Found {} potential gamepad devices", gamepad_devices.len());
+        *self.last_scan_time.lock_or_recover() = Some(now_ms());
+        Ok((firmware_updates, open_failures))
     }
     
-    fn analyze_device(&self, path: &Path) -> Result<Option<EvdevGamepadInfo>, String> {
-        let device = Device::open(path)
-            .map_err(|e| format!("Failed to open device: {}", e))?;
-            
+    /// Compares `info.version` to the stored version for its identity, persisting the
+    /// new value if it changed or the device hasn't been seen before.
+    fn check_firmware_version(&self, info: &EvdevGamepadInfo) -> Option<FirmwareUpdate> {
+        let mut versions = self.last_known_versions.lock_or_recover();
+        let update = match record_firmware_version(&mut versions, info) {
+            VersionChange::Unchanged => return None,
+            VersionChange::FirstSeen => None,
+            VersionChange::Updated(update) => Some(update),
+        };
+        
+        if let Some(path) = &self.versions_path {
+            if let Err(e) = save_device_versions(path, &versions) {
+                warn!("{}", e);
+            }
+        }
+        
+        if let Some(update) = &update {
+            info!("Firmware version of {} changed: {:#06x} -> {:#06x}", info.name, update.old_version, update.new_version);
+        }
+        update
+    }
+    
+    pub fn get_device_firmware_versions(&self) -> HashMap<String, u16> {
+        self.last_known_versions.lock_or_recover().clone()
+    }
+    
+    /// Adds a capability snapshot for `device` if it's new or its firmware or
+    /// capabilities changed since the last one.
+    fn record_capabilities(&self, device: &Device) {
+        let input_id = device.input_id();
+        let name = device.name().unwrap_or("Unknown");
+        let identity = capability_history::device_identity(input_id.vendor(), input_id.product(), name);
+        let capability_hash = hash_to_hex(&hash_device_capabilities(device));
+        let mut history = self.capability_history.lock_or_recover();
+        if history.is_current(&identity, input_id.version(), &capability_hash) {
+            return;
+        }
+        let snapshot = CapabilitySnapshot {
+            timestamp_ms: now_ms(),
+            firmware_version: input_id.version(),
+            capability_hash,
+            capabilities: CapabilitySet::read(device),
+        };
+        if history.record(&identity, snapshot) {
+            debug!("Stored capability snapshot for {}", identity);
+        }
+    }
+    
+    /// The manager's long-lived buffers, for `get_memory_stats`.
+    pub fn buffer_stats(&self) -> Vec<BufferStats> {
+        let (quality_len, quality_bytes, quality_devices) = {
+            let signal_quality = self.signal_quality.lock_or_recover();
+            let (len, bytes) = signal_quality.values()
+                .map(SignalQualityTracker::occupancy)
+                .fold((0, 0), |(len, bytes), (more_len, more_bytes)| (len + more_len, bytes + more_bytes));
+            (len, bytes, signal_quality.len())
+        };
+        let (history_devices, history_snapshots) = self.capability_history.lock_or_recover().occupancy();
+        vec![
+            BufferStats::deque("open_failures", &self.open_failures.lock_or_recover(), MAX_OPEN_FAILURES),
+            BufferStats::new("signal_quality", quality_len, Some(signal_quality::WINDOW_CAPACITY), quality_devices, quality_bytes),
+            BufferStats::new(
+                "capability_history",
+                history_snapshots,
+                Some(capability_history::MAX_SNAPSHOTS_PER_DEVICE),
+                history_devices,
+                history_snapshots * std::mem::size_of::<CapabilitySnapshot>(),
+            ),
+        ]
+    }
+    
+    /// Capability snapshots of a device, by identity or capability hash, oldest first.
+    pub fn get_capability_history(&self, device_fingerprint: &str) -> Vec<CapabilitySnapshot> {
+        self.capability_history.lock_or_recover().history(device_fingerprint)
+    }
+    
+    fn analyze_device(&self, path: &Path, device: &Device) -> Option<EvdevGamepadInfo> {
         let name = device.name().unwrap_or("Unknown").to_string();
         let input_id = device.input_id();
         
@@ -115,17 +571,17 @@ impl EvdevGamepadManager {
         let mut has_axes = false;
         
         // Simple capability detection based on device name and path
-        if name.to_lowercase().contains("gamepad") ||
-           name.to_lowercase().contains("controller") ||
-           name.to_lowercase().contains("xbox") ||
-           name.to_lowercase().contains("steam") ||
-           name.to_lowercase().contains("deck") ||
-           name.to_lowercase().contains("joy") {
+        if name_suggests_gamepad(&name) {
             has_buttons = true;
             has_axes = true;
-            capabilities.push("INFERRED_GAMEPAD".to_string());
+            capabilities.push(DeviceCapability::InferredGamepad);
         } else {
-            capabilities.push("UNKNOWN_DEVICE".to_string());
+            capabilities.push(DeviceCapability::UnknownDevice);
+        }
+        let has_back_grips = device.supported_keys()
+            .is_some_and(|keys| key_codes::BACK_GRIPS.iter().any(|&(code, _)| keys.contains(Key::new(code))));
+        if has_back_grips {
+            capabilities.push(DeviceCapability::BackGrips);
         }
         
         // Consider it a gamepad if it has both buttons and axes, or if the name suggests it's a gamepad
@@ -137,66 +593,816 @@ impl EvdevGamepadManager {
                         name.to_lowercase().contains("deck");
         
         if is_gamepad {
-            Ok(Some(EvdevGamepadInfo {
+            Some(EvdevGamepadInfo {
                 device_path: path.to_string_lossy().to_string(),
                 name,
                 vendor_id: Some(input_id.vendor()),
                 product_id: Some(input_id.product()),
+                version: Some(input_id.version()),
                 is_gamepad: true,
                 capabilities,
-            }))
+                capability_hash: hash_to_hex(&hash_device_capabilities(device)),
+                accessible: true,
+                fd_errors: 0,
+                is_virtual: path.file_name().is_some_and(|event_name| virtual_pads::sysfs_is_virtual(&event_name.to_string_lossy())),
+            })
         } else {
-            Ok(None)
+            None
         }
     }
     
-    pub fn poll_events(&self, _app: &AppHandle) -> Result<(), String> {
-        // Simplified event polling - just indicate that evdev is available
-        // In a real implementation, this would use epoll or similar for non-blocking reads
-        // For now, we'll just provide device enumeration
+    /// Drops devices whose file descriptors went bad, tries to reopen them, and reports fan
+    /// speed changes. Kept off the polling thread since opening devices and reading sysfs
+    /// can block.
+    pub fn maintain(&self, app: &impl EventEmitter) {
+        if self.health_monitor.lock_or_recover().due() {
+            let unhealthy = {
+                let devices = self.devices.lock_or_recover();
+                self.health_monitor.lock_or_recover().check(&devices)
+            };
+            for fd in unhealthy {
+                self.remove_unhealthy_device(app, &fd.device_path, &fd.reason);
+            }
+            self.attempt_reconnects();
+        }
+        
+        let fan_change = self.fan.lock_or_recover().poll_change();
+        if let Some(change) = fan_change {
+            app.emit_event("fan-speed-changed", change).ok();
+        }
+    }
+    
+    /// Reads whatever events are pending on every open device without blocking.
+    pub fn poll_events(&self, app: &impl EventEmitter) -> Result<(), String> {
+        let dump_path = self.expire_raw_dump(app);
+        // The virtual gamepad belongs to the app, so a harness without one has no passthrough
+        let virtual_gamepad = app.app_state().map(|state| state.virtual_gamepad.clone());
+        let passthrough_source = virtual_gamepad.as_ref().and_then(|output| output.source_path());
+        let mut raw_events = Vec::new();
+        let mut events = Vec::new();
+        let mut passthrough = Vec::new();
+        let mut failed = Vec::new();
+        {
+            let mut devices = self.devices.lock_or_recover();
+            let mut signal_quality = self.signal_quality.lock_or_recover();
+            let touchpads = self.touchpads.lock_or_recover();
+            let center_offsets = self.center_offsets.lock_or_recover();
+            for (path, device) in devices.iter_mut() {
+                let dumping = dump_path.as_deref() == Some(path.as_str());
+                let passing_through = passthrough_source.as_deref() == Some(path.as_str());
+                let motion = device.properties().contains(PropType::ACCELEROMETER);
+                let touchpad = touchpads.contains_key(path);
+                match device.fetch_events() {
+                    Ok(iter) => {
+                        // Looked up before inserting so an idle pass doesn't clone every path
+                        if !signal_quality.contains_key(path) {
+                            signal_quality.insert(path.clone(), Default::default());
+                        }
+                        let Some(quality) = signal_quality.get_mut(path) else {
+                            continue;
+                        };
+                        for event in iter {
+                            let timestamp_us = event.timestamp()
+                                .duration_since(UNIX_EPOCH)
+                                .unwrap_or_default()
+                                .as_micros() as u64;
+                            let is_report = event.event_type() == EventType::SYNCHRONIZATION
+                                && event.code() == Synchronization::SYN_REPORT.0;
+                            quality.record(event.event_type().0, event.code(), event.value(), timestamp_us, is_report);
+                            let event_type = key_codes::event_type_name(event.event_type());
+                            if dumping {
+                                raw_events.push(RawEvdevEvent {
+                                    code_name: key_codes::lookup(event_type, event.code()),
+                                    event_type: event_type.to_string(),
+                                    code: event.code(),
+                                    value: event.value(),
+                                    timestamp_us,
+                                });
+                            }
+                            if event.event_type() == EventType::SYNCHRONIZATION {
+                                if passing_through && is_report {
+                                    passthrough.push((event.event_type().0, event.code(), 0));
+                                }
+                                continue;
+                            }
+                            let mut value = event.value();
+                            if event.event_type() == EventType::ABSOLUTE {
+                                if let Some(offset) = center_offsets.get(path).and_then(|axes| axes.get(&event.code())) {
+                                    value = value.saturating_sub(*offset);
+                                }
+                            }
+                            if passing_through {
+                                passthrough.push((event.event_type().0, event.code(), value));
+                            }
+                            let event = EvdevControllerEvent {
+                                device_path: path.clone(),
+                                event_type: Cow::Borrowed(event_type),
+                                code: event.code(),
+                                value,
+                                timestamp: event.timestamp()
+                                    .duration_since(UNIX_EPOCH)
+                                    .unwrap_or_default()
+                                    .as_millis() as u64,
+                                synthetic: false,
+                                source_time: Some(event.timestamp()),
+                            };
+                            let category = event_category(&event, motion, touchpad);
+                            events.push(InputMessage::Evdev { event, category });
+                        }
+                    }
+                    Err(e) if e.kind() == std::io::ErrorKind::WouldBlock => {}
+                    Err(e) => failed.push((path.clone(), e)),
+                }
+            }
+        }
+        
+        // Written before the events are queued, so the virtual gamepad doesn't wait on the dispatcher
+        if let Some(output) = virtual_gamepad.filter(|_| !passthrough.is_empty()) {
+            output.forward(&passthrough);
+        }
+        if !raw_events.is_empty() {
+            self.emit_raw_events(app, raw_events);
+        }
+        if !events.is_empty() {
+            poll_cadence::cadence().note_activity();
+        }
+        for message in events {
+            self.input_tx.send(message);
+        }
+        for (path, e) in failed {
+            // ENODEV means the device is gone; anything else is counted and retried
+            error_counters::errors().record(ErrorCategory::DeviceRead, format!("{}: {}", path, e));
+            if e.raw_os_error() == Some(nix::libc::ENODEV) {
+                self.remove_unhealthy_device(app, &path, &format!("read failed: {}", e));
+            } else {
+                debug!("Failed to read events from {}: {}", path, e);
+                self.count_fd_error(&path);
+            }
+        }
         Ok(())
     }
     
-    pub fn get_detected_devices(&self) -> Vec<EvdevGamepadInfo> {
-        self.gamepad_devices.lock().unwrap().clone()
+    pub fn get_signal_quality(&self, device_path: &str) -> Result<DeviceSignalQuality, String> {
+        if !self.devices.lock_or_recover().contains_key(device_path) {
+            return Err(format!("Device {} is not open", device_path));
+        }
+        let now_us = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_micros() as u64;
+        Ok(self.signal_quality.lock_or_recover()
+            .get(device_path)
+            .map(|tracker| tracker.quality(now_us))
+            .unwrap_or_default())
     }
     
-    pub fn get_steam_deck_info(&self) -> String {
-        let mut info = Vec::new();
+    fn count_fd_error(&self, device_path: &str) {
+        if let Some(info) = self.gamepad_devices.lock_or_recover()
+            .iter_mut()
+            .find(|info| info.device_path == device_path)
+        {
+            info.fd_errors += 1;
+        }
+    }
+    
+    /// Closes and forgets an unhealthy device, notifies the frontend and queues it for
+    /// reopening.
+    fn remove_unhealthy_device(&self, app: &impl EventEmitter, device_path: &str, reason: &str) {
+        if self.devices.lock_or_recover().remove(device_path).is_none() {
+            return;
+        }
+        self.signal_quality.lock_or_recover().remove(device_path);
         
-        // Check for Steam Deck specific indicators
-        if Path::new("/home/deck").exists() {
-            info.push("✅ Running on Steam Deck (deck user detected)".to_string());
-        } else {
-            info.push("❓ Not running on Steam Deck (no deck user)".to_string());
+        let mut name = String::new();
+        let mut capability_hash = String::new();
+        let mut identity = String::new();
+        if let Some(info) = self.gamepad_devices.lock_or_recover()
+            .iter_mut()
+            .find(|info| info.device_path == device_path)
+        {
+            info.fd_errors += 1;
+            info.accessible = false;
+            name = info.name.clone();
+            capability_hash = info.capability_hash.clone();
+            identity = capability_history::device_identity(
+                info.vendor_id.unwrap_or_default(),
+                info.product_id.unwrap_or_default(),
+                &info.name,
+            );
         }
         
-        // Check for Steam processes
-        match std::process::Command::new("pgrep").arg("steam").output() {
-            Ok(output) => {
-                if output.status.success() && !output.stdout.is_empty() {
-                    info.push("🎮 Steam is running".to_string());
-                } else {
-                    info.push("❌ Steam is not running".to_string());
+        warn!("Evdev device {} ({}) is unhealthy: {}", device_path, name, reason);
+        poll_cadence::cadence().note_activity();
+        self.pending_reconnects.lock_or_recover().insert(device_path.to_string(), PendingReconnect {
+            capability_hash,
+            identity,
+            attempts: 0,
+        });
+        app.emit_on(EventChannel::Devices, "evdev-device-unhealthy", UnhealthyDevice {
+            device_path: device_path.to_string(),
+            name,
+            reason: reason.to_string(),
+        }).ok();
+    }
+    
+    /// Tries to reopen each unhealthy device at its old path, accepting it only if it's
+    /// still the same device: same fingerprint, or failing that same IDs and name.
+    fn attempt_reconnects(&self) {
+        let mut pending = self.pending_reconnects.lock_or_recover();
+        pending.retain(|path, reconnect| {
+            reconnect.attempts += 1;
+            let reopened = Device::open(path).ok().filter(|device| {
+                let input_id = device.input_id();
+                hash_to_hex(&hash_device_capabilities(device)) == reconnect.capability_hash
+                    || capability_history::device_identity(input_id.vendor(), input_id.product(), device.name().unwrap_or("Unknown"))
+                        == reconnect.identity
+            });
+            
+            match reopened {
+                Some(mut device) => {
+                    info!("Reopened evdev device {} after {} attempt(s)", path, reconnect.attempts);
+                    poll_cadence::cadence().note_activity();
+                    self.record_capabilities(&device);
+                    self.axis_ranges.lock_or_recover().insert(path.clone(), read_axis_ranges(&device));
+                    if let Some(tracker) = TouchSlotTracker::for_device(&device) {
+                        self.touchpads.lock_or_recover().insert(path.clone(), tracker);
+                    }
+                    if let Err(e) = set_nonblocking(&device) {
+                        warn!("Could not make {} non-blocking: {}", path, e);
+                    }
+                    self.grab_for_passthrough(path, &mut device);
+                    self.devices.lock_or_recover().insert(path.clone(), device);
+                    if let Some(info) = self.gamepad_devices.lock_or_recover()
+                        .iter_mut()
+                        .find(|info| &info.device_path == path)
+                    {
+                        info.accessible = true;
+                    }
+                    false
                 }
+                None if reconnect.attempts >= MAX_RECONNECT_ATTEMPTS => {
+                    warn!("Giving up on reopening {} after {} attempts", path, reconnect.attempts);
+                    false
+                }
+                None => true,
+            }
+        });
+    }
+    
+    /// Queues an event from a source other than the device readers (replays) for the dispatcher.
+    pub fn submit_event(&self, event: EvdevControllerEvent) {
+        let motion = self.devices.lock_or_recover()
+            .get(&event.device_path)
+            .is_some_and(|device| device.properties().contains(PropType::ACCELEROMETER));
+        let touchpad = self.touchpads.lock_or_recover().contains_key(&event.device_path);
+        let category = event_category(&event, motion, touchpad);
+        self.input_tx.send(InputMessage::Evdev { event, category });
+    }
+    
+    /// Records (when live) and emits one evdev event; live events are dropped while a
+    /// replay has paused live sources. Only called by the input dispatcher.
+    pub fn handle_event(&self, app: &impl EventEmitter, event: EvdevControllerEvent, category: EventCategory) {
+        if processing_pause::processing_paused() {
+            return;
+        }
+        if category == EventCategory::Motion && !iio_motion::evdev_motion_enabled() {
+            return;
+        }
+        if !event.synthetic && app.app_state().is_some_and(|state| state.virtual_pads.evdev_suppressed(&event.device_path)) {
+            return;
+        }
+        if !event.synthetic {
+            if self.session.live_input_paused() {
+                return;
             }
-            Err(_) => {
-                info.push("❓ Could not check Steam status".to_string());
+            if self.session.is_recording() {
+                self.session.record(RecordedInput::Evdev {
+                    device_path: event.device_path.clone(),
+                    event_type: event.event_type.clone(),
+                    code: event.code,
+                    value: event.value,
+                });
             }
         }
-        
-        // Check for Steam Input environment variables
-        for var in ["STEAM_COMPAT_DATA_PATH", "STEAM_COMPAT_CLIENT_INSTALL_PATH", "SteamAppId"] {
-            match std::env::var(var) {
-                Ok(value) => {
-                    info.push(format!("🎮 {}: {}", var, value));
-                }
-                Err(_) => {
-                    info.push(format!("❌ {} not set", var));
+        let metrics = runtime_metrics::metrics();
+        metrics.increment(Counter::EvdevEvents);
+        let source_time = event.source_time;
+        if let Some(source_time) = source_time {
+            latency_benchmark::benchmark().record(Stage::Processed, source_time);
+        }
+        let unified = self.to_unified(&event);
+        let channel = event_channel(category, &unified.input);
+        controller_event::emit(app, &unified, channel);
+        if event.event_type == "ABSOLUTE" {
+            let contact = self.touchpads.lock_or_recover()
+                .get_mut(&event.device_path)
+                .and_then(|tracker| tracker.handle(&event.device_path, event.code, event.value));
+            if let Some(contact) = contact {
+                app.emit_on(EventChannel::Trackpad, "touchpad-contact", contact).ok();
+            }
+        }
+        if controller_event::legacy_events_enabled() && app.wants(channel, "evdev-gamepad-input") {
+            let result = app.emit_event("evdev-gamepad-input", event);
+            metrics.record_emit(result.is_ok());
+            if let Err(e) = result {
+                error_counters::errors().record(ErrorCategory::Emit, format!("evdev-gamepad-input: {}", e));
+            }
+        }
+        if let Some(source_time) = source_time {
+            latency_benchmark::benchmark().record(Stage::Emitted, source_time);
+        }
+    }
+    
+    /// Identifies the device by its fingerprint, or its path if it's no longer detected
+    /// (e.g. a replayed node).
+    fn device_id(&self, device_path: &str) -> String {
+        self.gamepad_devices.lock_or_recover().iter()
+            .find(|info| info.device_path == device_path && !info.capability_hash.is_empty())
+            .map(|info| format!("evdev:{}", info.capability_hash))
+            .unwrap_or_else(|| format!("evdev:{}", device_path))
+    }
+    
+    fn to_unified(&self, event: &EvdevControllerEvent) -> UnifiedControllerEvent {
+        let device_id = self.device_id(&event.device_path);
+        let range = self.axis_ranges.lock_or_recover()
+            .get(&event.device_path)
+            .and_then(|ranges| ranges.get(&event.code))
+            .copied();
+        UnifiedControllerEvent::from_evdev(event, device_id, range)
+    }
+    
+    /// Cached (x, y) position axis info of the `side` touchpad at `device_path`.
+    pub fn get_touchpad_abs_info(&self, device_path: &str, side: TouchpadSide) -> Option<(AbsInfo, AbsInfo)> {
+        self.touchpads.lock_or_recover()
+            .get(device_path)
+            .filter(|tracker| tracker.side() == side)
+            .map(TouchSlotTracker::abs_info)
+    }
+    
+    pub fn get_detected_devices(&self) -> Vec<EvdevGamepadInfo> {
+        self.gamepad_devices.lock_or_recover().clone()
+    }
+    
+    /// Whether an open node reports motion, which the IIO fallback defers to.
+    pub fn has_motion_device(&self) -> bool {
+        self.devices.lock_or_recover()
+            .values()
+            .any(|device| device.properties().contains(PropType::ACCELEROMETER))
+    }
+    
+    pub fn get_device_details(&self) -> Vec<EvdevDeviceDetails> {
+        let devices = self.devices.lock_or_recover();
+        let mut details: Vec<EvdevDeviceDetails> = devices.iter()
+            .map(|(path, device)| describe_device(path, device))
+            .collect();
+        details.sort_by(|a, b| a.device_path.cmp(&b.device_path));
+        details
+    }
+    
+    pub fn get_stats(&self) -> EvdevStats {
+        let devices = self.gamepad_devices.lock_or_recover();
+        let accessible_devices = devices.iter().filter(|d| d.accessible).count();
+        EvdevStats {
+            detected_devices: devices.len(),
+            accessible_devices,
+            inaccessible_devices: devices.len() - accessible_devices,
+            last_scan_time: *self.last_scan_time.lock_or_recover(),
+        }
+    }
+    
+    /// Grabs the physical gamepads' open nodes so nothing else reads their input, or lets
+    /// them go. Returns how many are grabbed; call again to pick up nodes opened since.
+    pub fn set_exclusive(&self, exclusive: bool) -> usize {
+        let passthrough_grab = self.passthrough_grab.lock_or_recover().clone();
+        let physical: Vec<String> = if exclusive {
+            self.gamepad_devices.lock_or_recover()
+                .iter()
+                // Already held for the virtual gamepad, which outlasts background input
+                .filter(|device| device.is_gamepad && !device.is_virtual && passthrough_grab.as_ref() != Some(&device.device_path))
+                .map(|device| device.device_path.clone())
+                .collect()
+        } else {
+            Vec::new()
+        };
+        let mut devices = self.devices.lock_or_recover();
+        let mut grabbed = self.grabbed.lock_or_recover();
+        // A closed node's grab went with it
+        grabbed.retain(|path| devices.contains_key(path));
+        grabbed.retain(|path| {
+            if physical.contains(path) {
+                return true;
+            }
+            if let Some(Err(e)) = devices.get_mut(path).map(Device::ungrab) {
+                warn!("Could not release {}: {}", path, e);
+            }
+            info!("Released {}", path);
+            false
+        });
+        for path in physical {
+            if grabbed.contains(&path) {
+                continue;
+            }
+            let Some(device) = devices.get_mut(&path) else {
+                continue;
+            };
+            // Retried on every call, so failures (usually another grab) stay quiet
+            match device.grab() {
+                Ok(()) => {
+                    info!("Grabbed {} for background input", path);
+                    grabbed.insert(path);
                 }
+                Err(e) => debug!("Could not grab {}: {}", path, e),
+            }
+        }
+        grabbed.len()
+    }
+    
+    /// Grabs `device_path` for the virtual gamepad's passthrough, keeping it grabbed across
+    /// rescans and reconnects, or with `None` releases the node grabbed before.
+    pub fn set_passthrough_grab(&self, device_path: Option<&str>) -> Result<(), String> {
+        let mut devices = self.devices.lock_or_recover();
+        let mut passthrough_grab = self.passthrough_grab.lock_or_recover();
+        if passthrough_grab.as_deref() == device_path {
+            return Ok(());
+        }
+        if let Some(previous) = passthrough_grab.take() {
+            if let Some(Err(e)) = devices.get_mut(&previous).map(Device::ungrab) {
+                warn!("Could not release {}: {}", previous, e);
+            }
+            info!("Released {} from passthrough", previous);
+        }
+        let Some(path) = device_path else {
+            return Ok(());
+        };
+        let device = devices.get_mut(path).ok_or_else(|| format!("Device {} is not open", path))?;
+        // Background input may hold it already; the passthrough takes the grab over
+        if !self.grabbed.lock_or_recover().remove(path) {
+            device.grab().map_err(|e| match e.raw_os_error() {
+                Some(nix::libc::EBUSY) => format!("{} is grabbed by another process, usually Steam Input", path),
+                _ => format!("Could not grab {}: {}", path, e),
+            })?;
+        }
+        info!("Grabbed {} for passthrough", path);
+        *passthrough_grab = Some(path.to_string());
+        Ok(())
+    }
+
+    /// Grabs a freshly opened node if it's the passthrough's source.
+    fn grab_for_passthrough(&self, device_path: &str, device: &mut Device) {
+        if self.passthrough_grab.lock_or_recover().as_deref() != Some(device_path) {
+            return;
+        }
+        match device.grab() {
+            Ok(()) => info!("Grabbed {} again for passthrough", device_path),
+            Err(e) => warn!("Could not grab {} again for passthrough: {}", device_path, e),
+        }
+    }
+
+    /// (minimum, maximum) of each absolute axis of an open node, by code.
+    pub fn get_axis_ranges(&self, device_path: &str) -> Result<AxisRanges, String> {
+        if !self.devices.lock_or_recover().contains_key(device_path) {
+            return Err(format!("Device {} is not open", device_path));
+        }
+        Ok(self.axis_ranges.lock_or_recover().get(device_path).cloned().unwrap_or_default())
+    }
+
+    pub fn get_device_hash(&self, device_path: &str) -> Result<String, String> {
+        if let Some(info) = self.gamepad_devices.lock_or_recover()
+            .iter()
+            .find(|info| info.device_path == device_path)
+        {
+            return Ok(info.capability_hash.clone());
+        }
+        
+        // Not a detected gamepad; fingerprint it directly so any input node can be identified
+        let device = Device::open(device_path)
+            .map_err(|e| format!("Failed to open {}: {}", device_path, e))?;
+        Ok(hash_to_hex(&hash_device_capabilities(&device)))
+    }
+    
+    pub fn set_fan_speed_mode(&self, mode: FanMode) -> Result<(), String> {
+        self.fan.lock_or_recover().set_mode(mode)
+    }
+    
+    pub fn get_fan_speed_mode(&self) -> FanMode {
+        self.fan.lock_or_recover().mode()
+    }
+    
+    pub fn get_fan_speed_rpm(&self) -> Option<u32> {
+        self.fan.lock_or_recover().read_rpm()
+    }
+    
+    pub fn configure_low_battery_action(&self, config: LowBatteryConfig) -> Result<(), String> {
+        self.low_battery.configure(config)
+    }
+    
+    pub fn get_low_battery_config(&self) -> Option<LowBatteryConfig> {
+        self.low_battery.config()
+    }
+    
+    pub fn clear_low_battery_action(&self) {
+        self.low_battery.clear();
+    }
+    
+    /// Reads the Deck's battery and runs the low battery action if the threshold was just reached.
+    pub async fn check_low_battery(&self, app: &AppHandle) {
+        let Some(reading) = low_battery::read_battery() else {
+            return;
+        };
+        if let Some(config) = self.low_battery.check(reading) {
+            if let Err(e) = low_battery::run_action(app, config, reading.percent).await {
+                warn!("Low battery action failed: {}", e);
+            }
+        }
+    }
+    
+    /// Starts dumping every event from `device_path` on `evdev-raw` for `duration`,
+    /// replacing any dump already running. Only one device is dumped at a time.
+    pub fn start_raw_dump(&self, app: &impl EventEmitter, device_path: &str, duration: Duration) -> Result<RawDumpStatus, String> {
+        if !self.devices.lock_or_recover().contains_key(device_path) {
+            return Err(format!("{} is not an open evdev device", device_path));
+        }
+        
+        let dump = RawDump::new(device_path.to_string(), duration);
+        let status = dump.status();
+        let previous = self.raw_dump.lock_or_recover().replace(dump);
+        if let Some(previous) = previous {
+            app.emit_on(EventChannel::Devices, "evdev-raw-stopped", previous.stopped(RawDumpStopReason::Replaced)).ok();
+        }
+        info!("Dumping raw events from {} for {}s", device_path, duration.as_secs());
+        Ok(status)
+    }
+    
+    /// Stops the dump of `device_path`. Returns false if it wasn't being dumped.
+    pub fn stop_raw_dump(&self, app: &impl EventEmitter, device_path: &str) -> bool {
+        let stopped = {
+            let mut raw_dump = self.raw_dump.lock_or_recover();
+            if raw_dump.as_ref().is_none_or(|dump| dump.device_path != device_path) {
+                return false;
             }
+            raw_dump.take()
+        };
+        if let Some(dump) = stopped {
+            info!("Stopped dumping raw events from {}", device_path);
+            app.emit_on(EventChannel::Devices, "evdev-raw-stopped", dump.stopped(RawDumpStopReason::Disabled)).ok();
+        }
+        true
+    }
+    
+    /// Treats `center_raw` as the resting value of `axis_name` (e.g. "ABS_X") on
+    /// `device_path`, so its events are reported relative to it.
+    pub fn set_axis_center_offset(&self, device_path: &str, axis_name: &str, center_raw: i32) -> Result<(), String> {
+        let code = axis_code(axis_name)?;
+        if !self.gamepad_devices.lock_or_recover().iter().any(|device| device.device_path == device_path) {
+            return Err(format!("Unknown evdev device {}", device_path));
+        }
+        self.center_offsets.lock_or_recover()
+            .entry(device_path.to_string())
+            .or_default()
+            .insert(code, center_raw);
+        info!("Center of {} on {} set to {}", axis_name, device_path, center_raw);
+        Ok(())
+    }
+    
+    /// Samples `axis_name` for `sample_ms` while the stick is left alone and stores the
+    /// median as its center. Blocks for the whole sampling period.
+    pub fn auto_detect_axis_center(&self, device_path: &str, axis_name: &str, sample_ms: u64) -> Result<i32, String> {
+        let code = axis_code(axis_name)?;
+        let started = Instant::now();
+        let mut samples = Vec::new();
+        while started.elapsed() < Duration::from_millis(sample_ms) {
+            // Reads the kernel's current axis state, so the polling loop's events are untouched
+            let state = {
+                let devices = self.devices.lock_or_recover();
+                let device = devices.get(device_path)
+                    .ok_or_else(|| format!("{} is not an open evdev device", device_path))?;
+                device.get_abs_state()
+                    .map_err(|e| format!("Failed to read axes of {}: {}", device_path, e))?
+            };
+            let info = state.get(code as usize)
+                .ok_or_else(|| format!("{} has no {}", device_path, axis_name))?;
+            samples.push(info.value);
+            std::thread::sleep(CENTER_SAMPLE_INTERVAL);
         }
         
-        info.join("\n")
+        samples.sort_unstable();
+        let center = *samples.get(samples.len() / 2)
+            .ok_or("No samples were taken")?;
+        info!("Detected center {} for {} on {} from {} samples", center, axis_name, device_path, samples.len());
+        self.set_axis_center_offset(device_path, axis_name, center)?;
+        Ok(center)
+    }
+    
+    pub fn get_raw_dump_status(&self) -> Option<RawDumpStatus> {
+        self.raw_dump.lock_or_recover().as_ref().map(RawDump::status)
+    }
+    
+    /// Ends the dump if its time is up, returning the path still being dumped.
+    fn expire_raw_dump(&self, app: &impl EventEmitter) -> Option<String> {
+        let mut raw_dump = self.raw_dump.lock_or_recover();
+        if raw_dump.as_ref().is_some_and(RawDump::expired) {
+            if let Some(dump) = raw_dump.take() {
+                info!("Raw event dump of {} expired", dump.device_path);
+                app.emit_on(EventChannel::Devices, "evdev-raw-stopped", dump.stopped(RawDumpStopReason::Expired)).ok();
+            }
+        }
+        raw_dump.as_ref().map(|dump| dump.device_path.clone())
+    }
+    
+    fn emit_raw_events(&self, app: &impl EventEmitter, events: Vec<RawEvdevEvent>) {
+        let Some(batch) = self.raw_dump.lock_or_recover().as_mut().map(|dump| dump.admit(events)) else {
+            return;
+        };
+        for event in &batch.events {
+            trace!("raw {} {} {} ({}) = {}",
+                   batch.device_path,
+                   event.event_type,
+                   event.code_name.as_deref().unwrap_or("?"),
+                   event.code,
+                   event.value);
+        }
+        if batch.dropped > 0 {
+            trace!("raw {}: dropped {} events over the rate cap", batch.device_path, batch.dropped);
+        }
+        app.emit_on(EventChannel::Devices, "evdev-raw", batch).ok();
+    }
+    
+    pub fn get_steam_deck_info(&self) -> String {
+        SteamDeckInfo::collect(&self.get_detected_devices()).summary()
+    }
+
+    /// Panics while holding every lock the manager has, poisoning them. Lets tests check
+    /// that a panic mid-update doesn't break scanning, polling or the accessors.
+    #[cfg(feature = "test-support")]
+    pub(crate) fn poison_locks(&self) {
+        let _ = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+            let _devices = self.devices.lock_or_recover();
+            let _gamepad_devices = self.gamepad_devices.lock_or_recover();
+            let _last_scan_time = self.last_scan_time.lock_or_recover();
+            let _last_known_versions = self.last_known_versions.lock_or_recover();
+            let _health_monitor = self.health_monitor.lock_or_recover();
+            let _pending_reconnects = self.pending_reconnects.lock_or_recover();
+            let _fan = self.fan.lock_or_recover();
+            let _raw_dump = self.raw_dump.lock_or_recover();
+            let _center_offsets = self.center_offsets.lock_or_recover();
+            let _open_failures = self.open_failures.lock_or_recover();
+            let _signal_quality = self.signal_quality.lock_or_recover();
+            let _capability_history = self.capability_history.lock_or_recover();
+            let _axis_ranges = self.axis_ranges.lock_or_recover();
+            let _touchpads = self.touchpads.lock_or_recover();
+            let _grabbed = self.grabbed.lock_or_recover();
+            let _passthrough_grab = self.passthrough_grab.lock_or_recover();
+            let _ignored = self.ignored.lock_or_recover();
+            panic!("Injected panic while holding the evdev manager's locks");
+        }));
+    }
+}
+
+impl InputBackend for EvdevGamepadManager {
+    fn source(&self) -> PollSource {
+        PollSource::Evdev
+    }
+
+    fn init(&self, app: &AppHandle) -> Result<(), String> {
+        self.scan_for_gamepad_devices(app)
+    }
+
+    fn scan(&self, app: &AppHandle) -> Result<(), String> {
+        self.scan_for_gamepad_devices(app)
     }
-}
\ No newline at end of file
+
+    fn drain_events(&self, app: &AppHandle) -> Result<(), String> {
+        self.poll_events(app)
+    }
+
+    fn maintain(&self, app: &AppHandle) {
+        EvdevGamepadManager::maintain(self, app);
+    }
+
+    /// Read from the kernel rather than tracked from events: held keys, and every
+    /// absolute axis with a known range.
+    fn current_states(&self) -> Vec<BackendControllerState> {
+        let devices = self.devices.lock_or_recover();
+        let axis_ranges = self.axis_ranges.lock_or_recover();
+        devices.iter()
+            .map(|(path, device)| {
+                let buttons = device.get_key_state()
+                    .map(|keys| keys.iter()
+                        .filter_map(|key| Some((key_codes::lookup("KEY", key.code())?, true)))
+                        .collect())
+                    .unwrap_or_default();
+                let axes = match (axis_ranges.get(path), device.get_abs_state()) {
+                    (Some(ranges), Ok(abs_state)) => ranges.iter()
+                        .filter_map(|(&code, &range)| {
+                            let name = key_codes::lookup("ABSOLUTE", code)?;
+                            let value = abs_state.get(code as usize)?.value;
+                            Some((name, controller_event::normalize_axis(value, range)))
+                        })
+                        .collect(),
+                    _ => HashMap::new(),
+                };
+                BackendControllerState {
+                    source: PollSource::Evdev,
+                    device_id: self.device_id(path),
+                    buttons,
+                    axes,
+                }
+            })
+            .collect()
+    }
+
+    fn debug_info(&self) -> BackendDebugInfo {
+        BackendDebugInfo {
+            source: PollSource::Evdev,
+            enabled: true,
+            available: Path::new("/dev/input").exists(),
+            device_count: self.devices.lock_or_recover().len(),
+            last_scan_time: *self.last_scan_time.lock_or_recover(),
+        }
+    }
+
+    /// Closes the open event nodes, releasing any grabs, and hands the fan back to the EC.
+    fn shutdown(&self) {
+        let mut devices = self.devices.lock_or_recover();
+        for device in devices.values_mut() {
+            // Fails with EINVAL for nodes that weren't grabbed
+            let _ = device.ungrab();
+        }
+        let closed = devices.len();
+        devices.clear();
+        drop(devices);
+        
+        let mut fan = self.fan.lock_or_recover();
+        if fan.mode() != FanMode::Auto {
+            if let Err(e) = fan.set_mode(FanMode::Auto) {
+                warn!("Failed to return fan control to the EC: {}", e);
+            }
+        }
+        info!("Closed {} evdev devices", closed);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DECK: &str = "Steam Deck";
+
+    #[test]
+    fn the_fingerprint_ignores_listing_order() {
+        let axes = vec![(0, Some([-32768, 32767, 16, 128, 0])), (1, None)];
+        let reordered = vec![(1, None), (0, Some([-32768, 32767, 16, 128, 0]))];
+        assert_eq!(
+            hash_capabilities(0x28de, 0x1205, vec![304, 305, 307], axes.clone(), DECK),
+            hash_capabilities(0x28de, 0x1205, vec![307, 304, 305], reordered, DECK),
+        );
+        assert_ne!(
+            hash_capabilities(0x28de, 0x1205, vec![304, 305], axes.clone(), DECK),
+            hash_capabilities(0x28de, 0x1205, vec![304, 305, 307], axes.clone(), DECK),
+        );
+        assert_ne!(
+            hash_capabilities(0x28de, 0x1205, Vec::new(), axes, DECK),
+            hash_capabilities(0x28de, 0x1205, Vec::new(), vec![(0, Some([-100, 100, 16, 128, 0])), (1, None)], DECK),
+        );
+    }
+
+    fn deck(version: u16, capability_hash: &str) -> EvdevGamepadInfo {
+        EvdevGamepadInfo {
+            device_path: "/dev/input/event5".to_string(),
+            name: DECK.to_string(),
+            vendor_id: Some(0x28de),
+            product_id: Some(0x1205),
+            version: Some(version),
+            is_gamepad: true,
+            capabilities: Vec::new(),
+            capability_hash: capability_hash.to_string(),
+            accessible: true,
+            fd_errors: 0,
+            is_virtual: false,
+        }
+    }
+
+    #[test]
+    fn an_update_that_changes_the_capabilities_is_still_reported() {
+        let mut versions = HashMap::new();
+        assert!(matches!(record_firmware_version(&mut versions, &deck(0x0100, "abc123")), VersionChange::FirstSeen));
+        assert!(matches!(record_firmware_version(&mut versions, &deck(0x0100, "abc123")), VersionChange::Unchanged));
+        match record_firmware_version(&mut versions, &deck(0x0101, "def456")) {
+            VersionChange::Updated(update) => {
+                assert_eq!((update.old_version, update.new_version), (0x0100, 0x0101));
+                assert_eq!(update.device_name, DECK);
+            }
+            _ => panic!("the firmware update wasn't reported"),
+        }
+    }
+
+    #[test]
+    fn devices_differing_only_by_name_get_different_fingerprints() {
+        let axes = vec![(0, Some([-32768, 32767, 16, 128, 0]))];
+        assert_ne!(
+            hash_capabilities(0x28de, 0x1205, vec![304, 305], axes.clone(), DECK),
+            hash_capabilities(0x28de, 0x1205, vec![304, 305], axes, "Steam Deck Controller"),
+        );
+    }
+}
+
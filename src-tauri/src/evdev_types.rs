@@ -0,0 +1,172 @@
+use serde::{Deserialize, Serialize};
+use std::borrow::Cow;
+use std::time::SystemTime;
+
+/// How a device was judged to be a gamepad. Serialized the way the old string list was.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum DeviceCapability {
+    /// Only its name suggested a gamepad
+    InferredGamepad,
+    UnknownDevice,
+    /// Reports the Steam Deck's back grips (L4/L5/R4/R5)
+    BackGrips,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EvdevGamepadInfo {
+    pub device_path: String,
+    pub name: String,
+    pub vendor_id: Option<u16>,
+    pub product_id: Option<u16>,
+    /// `input_id.version()`, which changes when the controller's firmware is updated
+    pub version: Option<u16>,
+    pub is_gamepad: bool,
+    pub capabilities: Vec<DeviceCapability>,
+    /// Hex-encoded SHA-256 of the device's identity and capabilities, stable across reboots
+    /// and re-enumeration (unlike the `/dev/input/eventN` path).
+    pub capability_hash: String,
+    /// False when the node could be analyzed but not kept open (usually permissions),
+    /// or while an unhealthy device is waiting to be reopened
+    pub accessible: bool,
+    /// Hangups, errors and failed reads seen on this device's descriptor
+    pub fd_errors: u64,
+    /// Made through uinput (no physical parent in sysfs), like Steam's virtual pads
+    pub is_virtual: bool,
+}
+
+const VALVE_VENDOR_ID: u16 = 0x28de;
+const STEAM_VIRTUAL_GAMEPAD_PRODUCT_ID: u16 = 0x11ff;
+const MICROSOFT_VENDOR_ID: u16 = 0x045e;
+const XBOX_360_PRODUCT_ID: u16 = 0x028e;
+
+impl EvdevGamepadInfo {
+    pub fn has_back_grips(&self) -> bool {
+        self.capabilities.contains(&DeviceCapability::BackGrips)
+    }
+
+    /// A virtual pad Steam Input created to feed translated input to games. Older Steam
+    /// clients present it as a uinput Xbox 360 pad instead.
+    pub fn is_steam_virtual(&self) -> bool {
+        let steam_ids = self.vendor_id == Some(VALVE_VENDOR_ID)
+            && self.product_id == Some(STEAM_VIRTUAL_GAMEPAD_PRODUCT_ID);
+        let emulated_xbox = self.is_virtual
+            && self.vendor_id == Some(MICROSOFT_VENDOR_ID)
+            && self.product_id == Some(XBOX_360_PRODUCT_ID);
+        steam_ids || emulated_xbox || self.name.contains("Steam Virtual")
+    }
+
+    /// Whether an entry of `Settings::ignored_devices` names this device: by capability
+    /// hash, falling back to the name for nodes that couldn't be fingerprinted or entries
+    /// written by hand.
+    pub fn matches_ignore_entry(&self, entry: &str) -> bool {
+        (!self.capability_hash.is_empty() && self.capability_hash == entry) || self.name == entry
+    }
+}
+
+/// Full capability dump of an open device, for debug reports.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EvdevDeviceDetails {
+    pub device_path: String,
+    pub name: String,
+    pub bus_type: String,
+    pub vendor_id: u16,
+    pub product_id: u16,
+    pub version: u16,
+    pub keys: Vec<String>,
+    pub axes: Vec<AbsAxisDetails>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AbsAxisDetails {
+    pub axis: String,
+    pub code: u16,
+    pub value: i32,
+    pub minimum: i32,
+    pub maximum: i32,
+    pub fuzz: i32,
+    pub flat: i32,
+    pub resolution: i32,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EvdevStats {
+    pub detected_devices: usize,
+    pub accessible_devices: usize,
+    pub inaccessible_devices: usize,
+    pub last_scan_time: Option<u64>,
+}
+
+/// Payload of the `evdev-device-unhealthy` event.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UnhealthyDevice {
+    pub device_path: String,
+    pub name: String,
+    pub reason: String,
+}
+
+/// Longest `auto_detect_axis_center` sampling period.
+pub const MAX_CENTER_SAMPLE_MS: u64 = 10_000;
+
+/// Payload of the `device-firmware-updated` event.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FirmwareUpdate {
+    pub device_name: String,
+    pub old_version: u16,
+    pub new_version: u16,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EvdevControllerEvent {
+    pub device_path: String,
+    /// Borrowed from the name tables for live input, so reading events doesn't allocate it
+    pub event_type: Cow<'static, str>,
+    pub code: u16,
+    pub value: i32,
+    pub timestamp: u64,
+    /// True for events injected by a replay rather than read from the device
+    pub synthetic: bool,
+    /// Kernel timestamp of the event, for latency measurement. Live input only.
+    #[serde(skip)]
+    pub source_time: Option<SystemTime>,
+}
+
+/// What evdev-only commands answer with on platforms other than Linux.
+#[cfg(not(target_os = "linux"))]
+pub const EVDEV_UNSUPPORTED: &str = "evdev input is not supported on this platform";
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn deck(name: &str, capability_hash: &str) -> EvdevGamepadInfo {
+        EvdevGamepadInfo {
+            device_path: "/dev/input/event5".to_string(),
+            name: name.to_string(),
+            vendor_id: Some(VALVE_VENDOR_ID),
+            product_id: Some(0x1205),
+            version: Some(0x0100),
+            is_gamepad: true,
+            capabilities: vec![DeviceCapability::BackGrips],
+            capability_hash: capability_hash.to_string(),
+            accessible: true,
+            fd_errors: 0,
+            is_virtual: false,
+        }
+    }
+
+    #[test]
+    fn entries_match_the_capability_hash() {
+        let deck = deck("Steam Deck", "abc123");
+        assert!(deck.matches_ignore_entry("abc123"));
+        assert!(deck.matches_ignore_entry("Steam Deck"));
+        assert!(!deck.matches_ignore_entry("def456"));
+    }
+
+    #[test]
+    fn names_match_when_there_is_no_hash() {
+        let unopened = deck("Steam Deck", "");
+        assert!(unopened.matches_ignore_entry("Steam Deck"));
+        assert!(!unopened.matches_ignore_entry(""));
+    }
+}
@@ -0,0 +1,174 @@
+use crate::capability_history::CapabilitySnapshot;
+use crate::emitter::EventEmitter;
+use crate::evdev_types::{EvdevControllerEvent, EvdevDeviceDetails, EvdevGamepadInfo, EvdevStats, EVDEV_UNSUPPORTED};
+use crate::fan_control::{FanController, FanMode};
+use crate::input_pipeline::{EventCategory, InputSender};
+use crate::locking::MutexExt;
+use crate::low_battery::{self, LowBatteryConfig, LowBatteryMonitor};
+use crate::memory_stats::BufferStats;
+use crate::permissions::DeviceOpenFailure;
+use crate::raw_dump::RawDumpStatus;
+use crate::recording::InputSession;
+use crate::signal_quality::DeviceSignalQuality;
+use crate::steam_deck::SteamDeckInfo;
+use crate::touchpad::{AbsInfo, TouchpadSide};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tauri::AppHandle;
+use tracing::{debug, info, warn};
+
+/// Stands in for the evdev backend where there is no evdev: every device list is empty
+/// and device operations fail with `EVDEV_UNSUPPORTED`. It isn't an input backend, so
+/// gilrs is the only source of input. The fan and low battery action don't depend on
+/// evdev and behave as on Linux.
+pub struct EvdevGamepadManager {
+    fan: Mutex<FanController>,
+    low_battery: LowBatteryMonitor,
+}
+
+impl EvdevGamepadManager {
+    pub fn new(_session: Arc<InputSession>, _input_tx: InputSender) -> Result<Self, String> {
+        info!("evdev is not available on this platform; using gilrs only");
+        Ok(Self {
+            fan: Mutex::new(FanController::discover()),
+            low_battery: LowBatteryMonitor::default(),
+        })
+    }
+
+    pub fn scan_for_gamepad_devices(&self, _app: &impl EventEmitter) -> Result<(), String> {
+        Err(EVDEV_UNSUPPORTED.to_string())
+    }
+
+    pub fn set_ignored_devices(&self, _entries: Vec<String>) {}
+
+    pub fn get_open_failures(&self) -> Vec<DeviceOpenFailure> {
+        Vec::new()
+    }
+
+    pub fn get_device_firmware_versions(&self) -> HashMap<String, u16> {
+        HashMap::new()
+    }
+
+    pub fn get_capability_history(&self, _device_fingerprint: &str) -> Vec<CapabilitySnapshot> {
+        Vec::new()
+    }
+
+    pub fn buffer_stats(&self) -> Vec<BufferStats> {
+        Vec::new()
+    }
+
+    pub fn get_signal_quality(&self, _device_path: &str) -> Result<DeviceSignalQuality, String> {
+        Err(EVDEV_UNSUPPORTED.to_string())
+    }
+
+    /// Replayed evdev events have no device to go to here, so they're dropped.
+    pub fn submit_event(&self, event: EvdevControllerEvent) {
+        debug!("Dropping evdev event for {}: {}", event.device_path, EVDEV_UNSUPPORTED);
+    }
+
+    pub fn handle_event(&self, _app: &impl EventEmitter, _event: EvdevControllerEvent, _category: EventCategory) {}
+
+    pub fn get_touchpad_abs_info(&self, _device_path: &str, _side: TouchpadSide) -> Option<(AbsInfo, AbsInfo)> {
+        None
+    }
+
+    pub fn get_detected_devices(&self) -> Vec<EvdevGamepadInfo> {
+        Vec::new()
+    }
+
+    pub fn has_motion_device(&self) -> bool {
+        false
+    }
+
+    pub fn get_device_details(&self) -> Vec<EvdevDeviceDetails> {
+        Vec::new()
+    }
+
+    pub fn get_stats(&self) -> EvdevStats {
+        EvdevStats {
+            detected_devices: 0,
+            accessible_devices: 0,
+            inaccessible_devices: 0,
+            last_scan_time: None,
+        }
+    }
+
+    pub fn set_exclusive(&self, _exclusive: bool) -> usize {
+        0
+    }
+
+    pub fn set_passthrough_grab(&self, device_path: Option<&str>) -> Result<(), String> {
+        match device_path {
+            Some(_) => Err(EVDEV_UNSUPPORTED.to_string()),
+            None => Ok(()),
+        }
+    }
+
+    pub fn get_axis_ranges(&self, _device_path: &str) -> Result<HashMap<u16, (i32, i32)>, String> {
+        Err(EVDEV_UNSUPPORTED.to_string())
+    }
+
+    pub fn get_device_hash(&self, _device_path: &str) -> Result<String, String> {
+        Err(EVDEV_UNSUPPORTED.to_string())
+    }
+
+    pub fn set_fan_speed_mode(&self, mode: FanMode) -> Result<(), String> {
+        self.fan.lock_or_recover().set_mode(mode)
+    }
+
+    pub fn get_fan_speed_mode(&self) -> FanMode {
+        self.fan.lock_or_recover().mode()
+    }
+
+    pub fn get_fan_speed_rpm(&self) -> Option<u32> {
+        self.fan.lock_or_recover().read_rpm()
+    }
+
+    pub fn configure_low_battery_action(&self, config: LowBatteryConfig) -> Result<(), String> {
+        self.low_battery.configure(config)
+    }
+
+    pub fn get_low_battery_config(&self) -> Option<LowBatteryConfig> {
+        self.low_battery.config()
+    }
+
+    pub fn clear_low_battery_action(&self) {
+        self.low_battery.clear();
+    }
+
+    pub async fn check_low_battery(&self, app: &AppHandle) {
+        let Some(reading) = low_battery::read_battery() else {
+            return;
+        };
+        if let Some(config) = self.low_battery.check(reading) {
+            if let Err(e) = low_battery::run_action(app, config, reading.percent).await {
+                warn!("Low battery action failed: {}", e);
+            }
+        }
+    }
+
+    pub fn start_raw_dump(&self, _app: &impl EventEmitter, _device_path: &str, _duration: Duration) -> Result<RawDumpStatus, String> {
+        Err(EVDEV_UNSUPPORTED.to_string())
+    }
+
+    pub fn stop_raw_dump(&self, _app: &impl EventEmitter, _device_path: &str) -> bool {
+        false
+    }
+
+    pub fn set_axis_center_offset(&self, _device_path: &str, _axis_name: &str, _center_raw: i32) -> Result<(), String> {
+        Err(EVDEV_UNSUPPORTED.to_string())
+    }
+
+    pub fn auto_detect_axis_center(&self, _device_path: &str, _axis_name: &str, _sample_ms: u64) -> Result<i32, String> {
+        Err(EVDEV_UNSUPPORTED.to_string())
+    }
+
+    pub fn get_raw_dump_status(&self) -> Option<RawDumpStatus> {
+        None
+    }
+
+    pub fn get_steam_deck_info(&self) -> String {
+        SteamDeckInfo::collect(&[]).summary()
+    }
+}
@@ -0,0 +1,76 @@
+use crate::controller_event::UnifiedControllerEvent;
+use crate::memory_stats::BufferStats;
+use std::time::{Duration, Instant};
+
+pub const DEFAULT_WINDOW_MS: u64 = 50;
+pub const DEFAULT_MAX_BATCH_SIZE: usize = 64;
+/// Largest batch that can be configured; the pending list never grows past it.
+pub const MAX_BATCH_SIZE_LIMIT: usize = 4096;
+
+/// Collects controller events for the light server so they go out as one `POST /batch`
+/// per window instead of one request each. Disabled until configured.
+pub struct EventBatcher {
+    pending: Vec<UnifiedControllerEvent>,
+    window_ms: u64,
+    last_flush: Instant,
+    max_batch_size: usize,
+    enabled: bool,
+}
+
+impl Default for EventBatcher {
+    fn default() -> Self {
+        Self {
+            pending: Vec::new(),
+            window_ms: DEFAULT_WINDOW_MS,
+            last_flush: Instant::now(),
+            max_batch_size: DEFAULT_MAX_BATCH_SIZE,
+            enabled: false,
+        }
+    }
+}
+
+impl EventBatcher {
+    /// Sets the flush window and size limit and enables batching.
+    pub fn configure(&mut self, window_ms: u64, max_batch_size: usize) -> Result<(), String> {
+        if max_batch_size == 0 || max_batch_size > MAX_BATCH_SIZE_LIMIT {
+            return Err(format!("max_batch_size must be between 1 and {}", MAX_BATCH_SIZE_LIMIT));
+        }
+        self.window_ms = window_ms;
+        self.max_batch_size = max_batch_size;
+        self.enabled = true;
+        Ok(())
+    }
+
+    /// Stops batching and hands back whatever was still queued.
+    pub fn disable(&mut self) -> Vec<UnifiedControllerEvent> {
+        self.enabled = false;
+        self.take()
+    }
+
+    /// Queues `event` when batching is enabled, returning the batch if this filled it.
+    pub fn push(&mut self, event: UnifiedControllerEvent) -> Option<Vec<UnifiedControllerEvent>> {
+        if !self.enabled {
+            return None;
+        }
+        self.pending.push(event);
+        (self.pending.len() >= self.max_batch_size).then(|| self.take())
+    }
+
+    /// The queued events, once the window since the last flush has passed.
+    pub fn take_due(&mut self) -> Option<Vec<UnifiedControllerEvent>> {
+        if self.pending.is_empty() || self.last_flush.elapsed() < Duration::from_millis(self.window_ms) {
+            return None;
+        }
+        Some(self.take())
+    }
+
+    pub fn buffer_stats(&self) -> BufferStats {
+        let approx_bytes = self.pending.capacity() * std::mem::size_of::<UnifiedControllerEvent>();
+        BufferStats::new("batcher_pending", self.pending.len(), Some(self.max_batch_size), 1, approx_bytes)
+    }
+
+    pub fn take(&mut self) -> Vec<UnifiedControllerEvent> {
+        self.last_flush = Instant::now();
+        std::mem::take(&mut self.pending)
+    }
+}
@@ -0,0 +1,654 @@
+use crate::app_state::AppState;
+use crate::controller_event::{EventSource, NormalizedInput, UnifiedControllerEvent};
+use crate::emitter::EventEmitter;
+use crate::error_counters::{self, ErrorCategory};
+use crate::gamepad::{ControllerState, GamepadInput};
+use crate::hidraw::{self, HapticCue};
+use crate::input_pipeline::InputMessage;
+use crate::locking::MutexExt;
+use crate::runtime_metrics;
+use crate::thread_priority;
+use rhai::{Dynamic, Engine, EvalAltResult, Map, Scope, AST};
+use serde::{Deserialize, Serialize};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Manager};
+use tracing::{debug, info, warn};
+
+pub const MAX_SCRIPTS: usize = 16;
+pub const MAX_SOURCE_BYTES: usize = 64 * 1024;
+/// How long one script may run on one event before it's stopped
+pub const TIME_BUDGET: Duration = Duration::from_millis(2);
+/// Failures in a row, errors or overruns, that turn a script off
+pub const MAX_FAILURES: u32 = 5;
+/// Actions one script may return for one event
+pub const MAX_ACTIONS: usize = 16;
+/// A failing script's errors are logged at most this often
+const ERROR_LOG_INTERVAL: Duration = Duration::from_secs(10);
+/// Operations between checks of the time budget
+const BUDGET_CHECK_OPERATIONS: u64 = 256;
+
+/// Emitted when a script is turned off for failing too often
+pub const SCRIPT_DISABLED: &str = "event-script-disabled";
+
+/// A script run on each controller event, as saved in settings.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct EventScript {
+    pub name: String,
+    pub source: String,
+    pub enabled: bool,
+}
+
+/// Something a script asks for in answer to an event.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ScriptAction {
+    /// Posts `payload` to `endpoint`, or to the light server endpoint
+    Send {
+        payload: serde_json::Value,
+        #[serde(default)]
+        endpoint: Option<String>,
+    },
+    /// Plays on the Deck's trackpads
+    Haptic {
+        #[serde(default)]
+        cue: HapticCue,
+    },
+    /// A button or axis fed back in as gilrs input, from the event's controller unless
+    /// `controller_id` says otherwise. Scripts don't run on it, so it can't loop.
+    Synthetic {
+        #[serde(default)]
+        controller_id: Option<usize>,
+        input: NormalizedInput,
+    },
+}
+
+/// Scripts showing what the API can do, offered by the editor as starting points.
+pub const EXAMPLE_SCRIPTS: [(&str, &str); 3] = [
+    (
+        "forward-a-with-trigger",
+        r#"// Forward A only while the left trigger is held more than halfway, scaled by
+// how far the left stick is pushed
+let stick = state.axes;
+if event.input.kind == "button" && event.input.name == "South" && event.input.pressed
+    && stick.LeftZ != () && stick.LeftZ > 0.5 {
+    let x = if stick.LeftStickX == () { 0.0 } else { stick.LeftStickX };
+    let y = if stick.LeftStickY == () { 0.0 } else { stick.LeftStickY };
+    let magnitude = (x * x + y * y).sqrt();
+    send(#{ action: "a", value: if magnitude > 1.0 { 1.0 } else { magnitude } })
+}"#,
+    ),
+    (
+        "tick-on-start",
+        r#"// A short tick on the right trackpad whenever Start is pressed
+if event.input.kind == "button" && event.input.name == "Start" && event.input.pressed {
+    haptic(#{ side: "right", pattern: "tick", intensity: 0.6, duration_ms: 30 })
+}"#,
+    ),
+    (
+        "trigger-as-button",
+        r#"// Press RightTrigger2 as a button once the right trigger passes 0.8
+if event.input.kind == "axis" && event.input.name == "RightZ" {
+    let held = state.buttons.RightTrigger2 == true;
+    if event.input.value > 0.8 && !held {
+        button("RightTrigger2", true)
+    } else if event.input.value < 0.7 && held {
+        button("RightTrigger2", false)
+    }
+}"#,
+    ),
+];
+
+fn action(kind: &str, mut fields: Map) -> Map {
+    fields.insert("type".into(), Dynamic::from(kind.to_string()));
+    fields
+}
+
+fn synthetic(input: [(&str, Dynamic); 3]) -> Map {
+    let input: Map = input.into_iter().map(|(key, value)| (key.into(), value)).collect();
+    action("synthetic", Map::from_iter([("input".into(), Dynamic::from_map(input))]))
+}
+
+/// An engine with the action helpers and the limits that keep scripts small: operations,
+/// call depth, and the size of strings, arrays and maps.
+fn build_engine() -> Engine {
+    let mut engine = Engine::new();
+    engine.set_max_operations(1_000_000);
+    engine.set_max_call_levels(32);
+    engine.set_max_expr_depths(64, 32);
+    engine.set_max_string_size(16 * 1024);
+    engine.set_max_array_size(1024);
+    engine.set_max_map_size(256);
+    engine.register_fn("send", |payload: Dynamic| action("send", Map::from_iter([("payload".into(), payload)])));
+    engine.register_fn("send", |endpoint: &str, payload: Dynamic| {
+        action("send", Map::from_iter([("payload".into(), payload), ("endpoint".into(), Dynamic::from(endpoint.to_string()))]))
+    });
+    engine.register_fn("haptic", |cue: Map| action("haptic", Map::from_iter([("cue".into(), Dynamic::from_map(cue))])));
+    engine.register_fn("button", |name: &str, pressed: bool| {
+        synthetic([("kind", Dynamic::from("button".to_string())), ("name", Dynamic::from(name.to_string())), ("pressed", Dynamic::from(pressed))])
+    });
+    engine.register_fn("axis", |name: &str, value: f64| {
+        synthetic([("kind", Dynamic::from("axis".to_string())), ("name", Dynamic::from(name.to_string())), ("value", Dynamic::from(value))])
+    });
+    engine
+}
+
+/// The actions in what a script evaluated to: one action, an array of them, or `()`.
+fn parse_actions(value: Dynamic) -> Result<Vec<ScriptAction>, String> {
+    if value.is_unit() {
+        return Ok(Vec::new());
+    }
+    let values = if value.is_array() {
+        value.into_array().unwrap_or_default()
+    } else {
+        vec![value]
+    };
+    if values.len() > MAX_ACTIONS {
+        return Err(format!("Returned {} actions; at most {} are allowed", values.len(), MAX_ACTIONS));
+    }
+    values.into_iter()
+        .filter(|value| !value.is_unit())
+        .map(|value| {
+            let action: ScriptAction = rhai::serde::from_dynamic(&value).map_err(|e| format!("Returned an action that isn't one: {}", e))?;
+            if let ScriptAction::Haptic { cue } = &action {
+                cue.validate()?;
+            }
+            Ok(action)
+        })
+        .collect()
+}
+
+/// How a script has been getting on, for the editor and debug info.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct EventScriptStatus {
+    pub name: String,
+    pub source: String,
+    pub enabled: bool,
+    pub runs: u64,
+    pub failures: u64,
+    /// Failures since the script last ran cleanly
+    pub consecutive_failures: u32,
+    pub last_error: Option<String>,
+    /// Longest run so far, in microseconds
+    pub max_run_us: u64,
+    /// Why the app turned it off, if it did
+    pub disabled_reason: Option<String>,
+}
+
+struct LoadedScript {
+    script: EventScript,
+    ast: AST,
+    runs: u64,
+    failures: u64,
+    consecutive_failures: u32,
+    last_error: Option<String>,
+    max_run_us: u64,
+    disabled_reason: Option<String>,
+    logged_at: Option<Instant>,
+    unlogged_errors: u64,
+}
+
+impl LoadedScript {
+    fn status(&self) -> EventScriptStatus {
+        EventScriptStatus {
+            name: self.script.name.clone(),
+            source: self.script.source.clone(),
+            enabled: self.script.enabled,
+            runs: self.runs,
+            failures: self.failures,
+            consecutive_failures: self.consecutive_failures,
+            last_error: self.last_error.clone(),
+            max_run_us: self.max_run_us,
+            disabled_reason: self.disabled_reason.clone(),
+        }
+    }
+
+    /// Counts a failure, logging it unless one was logged recently. Returns whether it
+    /// turned the script off.
+    fn failed(&mut self, error: String) -> bool {
+        self.failures += 1;
+        self.consecutive_failures += 1;
+        let logged_recently = self.logged_at.is_some_and(|logged_at| logged_at.elapsed() < ERROR_LOG_INTERVAL);
+        if !logged_recently {
+            warn!(
+                "Event script '{}' failed: {}{}",
+                self.script.name,
+                error,
+                match self.unlogged_errors {
+                    0 => String::new(),
+                    n => format!(" ({} more since the last report)", n),
+                }
+            );
+            self.logged_at = Some(Instant::now());
+            self.unlogged_errors = 0;
+        } else {
+            self.unlogged_errors += 1;
+        }
+        self.last_error = Some(error);
+        if self.consecutive_failures < MAX_FAILURES {
+            return false;
+        }
+        self.script.enabled = false;
+        let reason = format!("Failed {} times in a row; last error: {}", self.consecutive_failures, self.last_error.as_deref().unwrap_or_default());
+        warn!("Turned event script '{}' off: {}", self.script.name, reason);
+        self.disabled_reason = Some(reason);
+        true
+    }
+}
+
+/// What running the scripts on one event came to.
+#[derive(Debug, Default)]
+pub struct ScriptRun {
+    /// Each action with the name of the script that asked for it
+    pub actions: Vec<(String, ScriptAction)>,
+    /// Scripts turned off by this run
+    pub disabled: Vec<EventScriptStatus>,
+}
+
+/// Compiles and runs the scripts. Holds no app, so tests can drive it alone.
+pub struct ScriptEngine {
+    engine: Engine,
+    scripts: Vec<LoadedScript>,
+}
+
+impl Default for ScriptEngine {
+    fn default() -> Self {
+        Self { engine: build_engine(), scripts: Vec::new() }
+    }
+}
+
+impl ScriptEngine {
+    /// Compiles `script` and puts it in place of any script of the same name. Compile
+    /// errors come back with their line and column.
+    pub fn set(&mut self, script: EventScript) -> Result<EventScriptStatus, String> {
+        if script.name.trim().is_empty() {
+            return Err("Event script name must not be empty".to_string());
+        }
+        if script.source.len() > MAX_SOURCE_BYTES {
+            return Err(format!("Event script '{}' is {} bytes; at most {} are allowed", script.name, script.source.len(), MAX_SOURCE_BYTES));
+        }
+        let replacing = self.scripts.iter().position(|loaded| loaded.script.name == script.name);
+        if replacing.is_none() && self.scripts.len() >= MAX_SCRIPTS {
+            return Err(format!("At most {} event scripts", MAX_SCRIPTS));
+        }
+        let ast = self.engine.compile(&script.source)
+            .map_err(|e| format!("Event script '{}' doesn't compile: {}", script.name, e))?;
+        let loaded = LoadedScript {
+            script,
+            ast,
+            runs: 0,
+            failures: 0,
+            consecutive_failures: 0,
+            last_error: None,
+            max_run_us: 0,
+            disabled_reason: None,
+            logged_at: None,
+            unlogged_errors: 0,
+        };
+        let status = loaded.status();
+        match replacing {
+            Some(index) => self.scripts[index] = loaded,
+            None => self.scripts.push(loaded),
+        }
+        Ok(status)
+    }
+
+    pub fn remove(&mut self, name: &str) -> bool {
+        let before = self.scripts.len();
+        self.scripts.retain(|loaded| loaded.script.name != name);
+        self.scripts.len() != before
+    }
+
+    /// Turns a script on or off; turning it on forgets its failures.
+    pub fn set_enabled(&mut self, name: &str, enabled: bool) -> Result<EventScriptStatus, String> {
+        let loaded = self.scripts.iter_mut()
+            .find(|loaded| loaded.script.name == name)
+            .ok_or_else(|| format!("No event script named '{}'", name))?;
+        loaded.script.enabled = enabled;
+        if enabled {
+            loaded.consecutive_failures = 0;
+            loaded.disabled_reason = None;
+        }
+        Ok(loaded.status())
+    }
+
+    pub fn has_enabled(&self) -> bool {
+        self.scripts.iter().any(|loaded| loaded.script.enabled)
+    }
+
+    pub fn statuses(&self) -> Vec<EventScriptStatus> {
+        self.scripts.iter().map(LoadedScript::status).collect()
+    }
+
+    /// Runs every enabled script on `event`, with `state` the event's controller as of the
+    /// last published snapshot. A script that errors, overruns `TIME_BUDGET` or returns
+    /// something that isn't an action contributes nothing.
+    pub fn run(&mut self, event: &UnifiedControllerEvent, state: Option<&ControllerState>) -> ScriptRun {
+        let mut run = ScriptRun::default();
+        let (Ok(event_value), Ok(state_value)) = (
+            rhai::serde::to_dynamic(event),
+            state.map_or(Ok(Dynamic::from_map(Map::new())), rhai::serde::to_dynamic),
+        ) else {
+            return run;
+        };
+        let Self { engine, scripts } = self;
+        for loaded in scripts.iter_mut().filter(|loaded| loaded.script.enabled) {
+            let mut scope = Scope::new();
+            scope.push_constant_dynamic("event", event_value.clone());
+            scope.push_constant_dynamic("state", state_value.clone());
+            let started = Instant::now();
+            engine.on_progress(move |operations| {
+                (operations % BUDGET_CHECK_OPERATIONS == 0 && started.elapsed() > TIME_BUDGET).then_some(Dynamic::UNIT)
+            });
+            let result = engine.eval_ast_with_scope::<Dynamic>(&mut scope, &loaded.ast);
+            loaded.runs += 1;
+            loaded.max_run_us = loaded.max_run_us.max(started.elapsed().as_micros() as u64);
+            let actions = match result {
+                Ok(value) => parse_actions(value),
+                Err(e) => Err(match *e {
+                    EvalAltResult::ErrorTerminated(..) => format!("Took longer than {}ms", TIME_BUDGET.as_millis()),
+                    e => e.to_string(),
+                }),
+            };
+            match actions {
+                Ok(actions) => {
+                    loaded.consecutive_failures = 0;
+                    run.actions.extend(actions.into_iter().map(|action| (loaded.script.name.clone(), action)));
+                }
+                Err(e) => {
+                    if loaded.failed(e) {
+                        run.disabled.push(loaded.status());
+                    }
+                }
+            }
+        }
+        run
+    }
+}
+
+/// The scripts run on every controller event.
+#[derive(Default)]
+pub struct EventScripts {
+    /// Checked before locking, so events cost nothing while no script is enabled
+    active: AtomicBool,
+    engine: Mutex<ScriptEngine>,
+}
+
+impl EventScripts {
+    /// Compiles and loads the saved scripts at startup. One that no longer compiles is
+    /// skipped, and stays in settings for the editor to fix.
+    pub fn load_saved(&self, saved: &[EventScript]) {
+        for script in saved {
+            let name = script.name.clone();
+            match self.set(script.clone()) {
+                Ok(_) => info!("Loaded event script '{}'", name),
+                Err(e) => warn!("Skipping event script: {}", e),
+            }
+        }
+    }
+
+    pub fn set(&self, script: EventScript) -> Result<EventScriptStatus, String> {
+        let mut engine = self.engine.lock_or_recover();
+        let status = engine.set(script)?;
+        self.active.store(engine.has_enabled(), Ordering::Relaxed);
+        Ok(status)
+    }
+
+    pub fn remove(&self, name: &str) -> bool {
+        let mut engine = self.engine.lock_or_recover();
+        let removed = engine.remove(name);
+        self.active.store(engine.has_enabled(), Ordering::Relaxed);
+        removed
+    }
+
+    pub fn set_enabled(&self, name: &str, enabled: bool) -> Result<EventScriptStatus, String> {
+        let mut engine = self.engine.lock_or_recover();
+        let status = engine.set_enabled(name, enabled)?;
+        self.active.store(engine.has_enabled(), Ordering::Relaxed);
+        Ok(status)
+    }
+
+    pub fn statuses(&self) -> Vec<EventScriptStatus> {
+        self.engine.lock_or_recover().statuses()
+    }
+
+    /// Runs the scripts on `event` and carries out their actions through `app`'s pipeline.
+    /// Called by the dispatcher for every unified event; input the scripts made themselves
+    /// is left alone.
+    pub fn handle(&self, app: &AppHandle, event: &UnifiedControllerEvent) {
+        if !self.active.load(Ordering::Relaxed) || event.source == EventSource::Synthetic {
+            return;
+        }
+        let state = app.state::<AppState>();
+        let controller_state = event.controller_id.and_then(|id| state.gamepad_manager.get_controller_state(id));
+        let mut engine = self.engine.lock_or_recover();
+        let run = engine.run(event, controller_state.as_ref());
+        if !run.disabled.is_empty() {
+            self.active.store(engine.has_enabled(), Ordering::Relaxed);
+        }
+        drop(engine);
+        for status in run.disabled {
+            let name = status.name.clone();
+            let saved = state.settings.update(|settings| {
+                if let Some(script) = settings.event_scripts.iter_mut().find(|script| script.name == name) {
+                    script.enabled = false;
+                }
+            });
+            if let Err(e) = saved {
+                warn!("Failed to save settings: {}", e);
+            }
+            app.emit_event(SCRIPT_DISABLED, status).ok();
+        }
+        for (script, action) in run.actions {
+            execute(app, &format!("Event script '{}'", script), event.controller_id, action);
+        }
+    }
+}
+
+/// Carries out `action` for `origin` (e.g. "Event script 'x'"), which appears in its log
+/// lines. Synthetic input comes from `controller_id` unless the action names one.
+pub fn execute(app: &AppHandle, origin: &str, controller_id: Option<usize>, action: ScriptAction) {
+    let state = app.state::<AppState>();
+    match action {
+        ScriptAction::Send { payload, endpoint } => {
+            let Some(endpoint) = endpoint.or(state.settings.get().light_server_endpoint) else {
+                debug!("{} sent with no light server endpoint configured", origin);
+                return;
+            };
+            // The HTTP round trip stays off the caller's thread, as with combination effects
+            let light_server = state.light_server.clone();
+            let origin = origin.to_string();
+            runtime_metrics::metrics().light_send_started();
+            thread_priority::spawn_normal(move || {
+                if let Err(e) = light_server.post_json(&endpoint, &payload) {
+                    debug!("{} failed to send: {}", origin, e);
+                    error_counters::errors().record(ErrorCategory::LightServerSend, format!("{}: {}", origin, e));
+                }
+                runtime_metrics::metrics().light_send_finished();
+            });
+        }
+        ScriptAction::Haptic { cue } => {
+            let origin = origin.to_string();
+            thread_priority::spawn_normal(move || {
+                if let Err(e) = hidraw::play_deck_haptic(&cue) {
+                    debug!("{} haptic failed: {}", origin, e);
+                }
+            });
+        }
+        ScriptAction::Synthetic { controller_id: target, input } => {
+            let input = match input {
+                NormalizedInput::Button { name, pressed: true } => GamepadInput::ButtonPressed { button: name },
+                NormalizedInput::Button { name, pressed: false } => GamepadInput::ButtonReleased { button: name },
+                NormalizedInput::Axis { name, value } => GamepadInput::AxisChanged { axis: name, value: value.clamp(-1.0, 1.0) },
+                other => {
+                    debug!("{} can't make {:?} input", origin, other);
+                    return;
+                }
+            };
+            state.event_bus.push(InputMessage::Gilrs {
+                controller_id: target.or(controller_id).unwrap_or_default(),
+                input,
+                synthetic: true,
+                source_time: None,
+            });
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::{json, Value};
+    use std::collections::HashMap;
+
+    fn script(name: &str, source: &str) -> EventScript {
+        EventScript { name: name.to_string(), source: source.to_string(), enabled: true }
+    }
+
+    fn event(input: Value) -> UnifiedControllerEvent {
+        serde_json::from_value(json!({
+            "source": "gilrs",
+            "device_id": "gilrs:test",
+            "controller_id": 0,
+            "device_path": null,
+            "input": input,
+            "raw_code": null,
+            "raw_value": null,
+            "kernel_timestamp_ms": null,
+            "emit_timestamp_ms": 0,
+        }))
+        .unwrap()
+    }
+
+    fn press(button: &str) -> UnifiedControllerEvent {
+        event(json!({ "kind": "button", "name": button, "pressed": true }))
+    }
+
+    fn state(axes: &[(&str, f32)]) -> ControllerState {
+        ControllerState {
+            buttons: HashMap::new(),
+            axes: axes.iter().map(|(name, value)| (name.to_string(), *value)).collect(),
+            connected: true,
+            controller_id: 0,
+        }
+    }
+
+    fn actions(engine: &mut ScriptEngine, event: &UnifiedControllerEvent, state: Option<&ControllerState>) -> Vec<ScriptAction> {
+        engine.run(event, state).actions.into_iter().map(|(_, action)| action).collect()
+    }
+
+    #[test]
+    fn compile_errors_are_refused_at_registration() {
+        let mut engine = ScriptEngine::default();
+        let error = engine.set(script("broken", "if event.input.kind == { send(1) }")).unwrap_err();
+        assert!(error.contains("broken"), "{}", error);
+        assert!(error.contains("line 1"), "{}", error);
+        assert!(engine.statuses().is_empty());
+    }
+
+    #[test]
+    fn examples_compile() {
+        let mut engine = ScriptEngine::default();
+        for (name, source) in EXAMPLE_SCRIPTS {
+            engine.set(script(name, source)).unwrap();
+        }
+        assert_eq!(engine.statuses().len(), EXAMPLE_SCRIPTS.len());
+    }
+
+    #[test]
+    fn scripts_see_the_event_and_controller_state() {
+        let (name, source) = EXAMPLE_SCRIPTS[0];
+        let mut engine = ScriptEngine::default();
+        engine.set(script(name, source)).unwrap();
+
+        let released = state(&[("LeftZ", 0.2), ("LeftStickX", 0.6)]);
+        assert!(actions(&mut engine, &press("South"), Some(&released)).is_empty());
+
+        let held = state(&[("LeftZ", 0.9), ("LeftStickX", 0.6), ("LeftStickY", 0.8)]);
+        let sent = actions(&mut engine, &press("South"), Some(&held));
+        assert_eq!(sent.len(), 1);
+        let ScriptAction::Send { payload, endpoint } = &sent[0] else {
+            panic!("expected a send, got {:?}", sent[0]);
+        };
+        assert_eq!(endpoint, &None);
+        assert_eq!(payload["action"], "a");
+        assert!((payload["value"].as_f64().unwrap() - 1.0).abs() < 1e-6);
+
+        assert!(actions(&mut engine, &press("East"), Some(&held)).is_empty());
+    }
+
+    #[test]
+    fn helpers_build_each_kind_of_action() {
+        let mut engine = ScriptEngine::default();
+        engine.set(script("all", r#"[
+            send("http://lights.local/cue", #{ cue: 3 }),
+            haptic(#{ side: "left", intensity: 0.25, duration_ms: 40 }),
+            button("North", true),
+            axis("RightStickX", -0.5),
+        ]"#)).unwrap();
+        let returned = actions(&mut engine, &press("South"), None);
+        assert_eq!(returned.len(), 4);
+        assert_eq!(returned[0], ScriptAction::Send {
+            payload: json!({ "cue": 3 }),
+            endpoint: Some("http://lights.local/cue".to_string()),
+        });
+        assert!(matches!(&returned[1], ScriptAction::Haptic { cue } if cue.duration_ms == 40));
+        assert_eq!(returned[2], ScriptAction::Synthetic {
+            controller_id: None,
+            input: NormalizedInput::Button { name: "North".to_string(), pressed: true },
+        });
+        assert_eq!(returned[3], ScriptAction::Synthetic {
+            controller_id: None,
+            input: NormalizedInput::Axis { name: "RightStickX".to_string(), value: -0.5 },
+        });
+    }
+
+    #[test]
+    fn failing_scripts_are_turned_off() {
+        let mut engine = ScriptEngine::default();
+        engine.set(script("fails", "event.input.name.missing_function()")).unwrap();
+        engine.set(script("fine", "()")).unwrap();
+        for _ in 1..MAX_FAILURES {
+            assert!(engine.run(&press("South"), None).disabled.is_empty());
+        }
+        let run = engine.run(&press("South"), None);
+        assert_eq!(run.disabled.len(), 1);
+        assert_eq!(run.disabled[0].name, "fails");
+        assert!(run.disabled[0].disabled_reason.is_some());
+        assert!(engine.has_enabled());
+
+        let statuses = engine.statuses();
+        assert!(!statuses[0].enabled);
+        assert_eq!(statuses[0].failures, MAX_FAILURES as u64);
+        assert_eq!(statuses[1].failures, 0);
+
+        engine.set_enabled("fails", true).unwrap();
+        assert_eq!(engine.statuses()[0].consecutive_failures, 0);
+        assert_eq!(engine.statuses()[0].disabled_reason, None);
+    }
+
+    #[test]
+    fn runaway_scripts_are_stopped() {
+        let mut engine = ScriptEngine::default();
+        engine.set(script("spins", "loop { }")).unwrap();
+        let run = engine.run(&press("South"), None);
+        assert!(run.actions.is_empty());
+        let status = &engine.statuses()[0];
+        assert_eq!(status.failures, 1);
+        assert!(status.max_run_us < 1_000_000, "ran for {}us", status.max_run_us);
+
+        engine.remove("spins");
+        engine.set(script("grows", "let s = \"x\"; loop { s += s; }")).unwrap();
+        engine.run(&press("South"), None);
+        assert_eq!(engine.statuses()[0].failures, 1);
+    }
+
+    #[test]
+    fn results_that_arent_actions_count_as_failures() {
+        let mut engine = ScriptEngine::default();
+        engine.set(script("wrong", r#"#{ "type": "teleport" }"#)).unwrap();
+        assert!(engine.run(&press("South"), None).actions.is_empty());
+        assert_eq!(engine.statuses()[0].failures, 1);
+    }
+}
@@ -0,0 +1,557 @@
+use crate::controller_event::{NormalizedInput, UnifiedControllerEvent};
+use crate::error_counters::{self, ErrorCategory};
+use crate::locking::MutexExt;
+use serde::{Deserialize, Serialize};
+use std::fs::{File, OpenOptions};
+use std::io::{BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use tracing::{info, warn};
+
+/// How often buffered lines are written out, and free space checked.
+pub const FLUSH_INTERVAL: Duration = Duration::from_secs(2);
+const DEFAULT_MAX_FILE_BYTES: u64 = 100 * 1024 * 1024;
+const DEFAULT_MIN_FREE_BYTES: u64 = 500 * 1024 * 1024;
+/// Rotated files kept next to the stream, `<path>.1` being the newest.
+const ROTATED_FILES: u32 = 5;
+const CSV_HEADER: &str = "type,timestamp_ms,source,device_id,controller_id,device_path,input,name,value,raw_code,raw_value,kernel_timestamp_ms,endpoint,ok,duration_ms,error\n";
+
+fn now_ms() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_millis() as u64
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum StreamFormat {
+    /// One JSON object per line, tagged `event` or `send`
+    #[default]
+    Jsonl,
+    /// One row per line under a fixed header; columns a record doesn't have are empty
+    Csv,
+}
+
+/// A stream to disk. Saved while it runs, so it picks up again after a restart.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct EventStreamConfig {
+    pub path: String,
+    pub format: StreamFormat,
+    /// Also writes the outcome of every light server request
+    pub include_send_results: bool,
+    /// Size at which the file is moved to `<path>.1` and a new one started
+    pub max_file_bytes: u64,
+    /// Free space on the stream's disk below which it stops
+    pub min_free_bytes: u64,
+}
+
+impl Default for EventStreamConfig {
+    fn default() -> Self {
+        Self {
+            path: String::new(),
+            format: StreamFormat::Jsonl,
+            include_send_results: false,
+            max_file_bytes: DEFAULT_MAX_FILE_BYTES,
+            min_free_bytes: DEFAULT_MIN_FREE_BYTES,
+        }
+    }
+}
+
+impl EventStreamConfig {
+    pub fn validate(&self) -> Result<(), String> {
+        if self.path.trim().is_empty() {
+            return Err("The event stream needs a file path".to_string());
+        }
+        if self.max_file_bytes < 1024 * 1024 {
+            return Err(format!("Rotate at 1 MiB or more, got {} bytes", self.max_file_bytes));
+        }
+        Ok(())
+    }
+}
+
+#[derive(Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum Record<'a> {
+    Event(&'a UnifiedControllerEvent),
+    Send {
+        timestamp_ms: u64,
+        endpoint: &'a str,
+        ok: bool,
+        duration_ms: u64,
+        error: Option<&'a str>,
+    },
+}
+
+/// Quotes a CSV field that needs it.
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+fn csv_optional<T: ToString>(value: Option<T>) -> String {
+    value.map(|value| value.to_string()).unwrap_or_default()
+}
+
+/// One line of the stream, newline included.
+pub fn format_event(format: StreamFormat, event: &UnifiedControllerEvent) -> String {
+    match format {
+        StreamFormat::Jsonl => json_line(&Record::Event(event)),
+        StreamFormat::Csv => {
+            let (input, name, value) = match &event.input {
+                NormalizedInput::Connected => ("connected", None, None),
+                NormalizedInput::Disconnected => ("disconnected", None, None),
+                NormalizedInput::Button { name, pressed } => ("button", Some(name.clone()), Some(if *pressed { "1" } else { "0" }.to_string())),
+                NormalizedInput::Axis { name, value } => ("axis", Some(name.clone()), Some(value.to_string())),
+                NormalizedInput::Other { event_type, name } => (event_type.as_str(), name.clone(), None),
+            };
+            let source = serde_json::to_value(event.source).ok().and_then(|source| source.as_str().map(str::to_string));
+            let fields = [
+                "event".to_string(),
+                event.emit_timestamp_ms.to_string(),
+                source.unwrap_or_default(),
+                event.device_id.clone(),
+                csv_optional(event.controller_id),
+                event.device_path.clone().unwrap_or_default(),
+                input.to_string(),
+                name.unwrap_or_default(),
+                value.unwrap_or_default(),
+                csv_optional(event.raw_code),
+                csv_optional(event.raw_value),
+                csv_optional(event.kernel_timestamp_ms),
+                String::new(),
+                String::new(),
+                String::new(),
+                String::new(),
+            ];
+            csv_line(&fields)
+        }
+    }
+}
+
+pub fn format_send(format: StreamFormat, timestamp_ms: u64, endpoint: &str, result: &Result<String, String>, duration_ms: u64) -> String {
+    let error = result.as_ref().err().map(String::as_str);
+    match format {
+        StreamFormat::Jsonl => json_line(&Record::Send { timestamp_ms, endpoint, ok: result.is_ok(), duration_ms, error }),
+        StreamFormat::Csv => {
+            let mut fields = vec![String::new(); 16];
+            fields[0] = "send".to_string();
+            fields[1] = timestamp_ms.to_string();
+            fields[12] = endpoint.to_string();
+            fields[13] = result.is_ok().to_string();
+            fields[14] = duration_ms.to_string();
+            fields[15] = error.unwrap_or_default().to_string();
+            csv_line(&fields)
+        }
+    }
+}
+
+fn json_line(record: &Record) -> String {
+    let mut line = serde_json::to_string(record).unwrap_or_default();
+    line.push('\n');
+    line
+}
+
+fn csv_line(fields: &[String]) -> String {
+    let mut line = fields.iter().map(|field| csv_field(field)).collect::<Vec<_>>().join(",");
+    line.push('\n');
+    line
+}
+
+/// Bytes free to unprivileged users on the disk holding `path`, where that can be told.
+#[cfg(target_os = "linux")]
+fn free_bytes(path: &Path) -> Option<u64> {
+    let dir = path.parent().filter(|parent| !parent.as_os_str().is_empty()).unwrap_or(Path::new("."));
+    let stats = nix::sys::statvfs::statvfs(dir).ok()?;
+    Some(stats.blocks_available() * stats.fragment_size())
+}
+
+#[cfg(not(target_os = "linux"))]
+fn free_bytes(_path: &Path) -> Option<u64> {
+    None
+}
+
+fn rotated_path(path: &Path, index: u32) -> PathBuf {
+    let mut name = path.as_os_str().to_owned();
+    name.push(format!(".{}", index));
+    PathBuf::from(name)
+}
+
+/// Opens `path` for appending, writing the CSV header into a new file. Returns the writer
+/// and the file's size.
+fn open(path: &Path, format: StreamFormat) -> Result<(BufWriter<File>, u64), String> {
+    let file = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .map_err(|e| format!("Failed to open event stream {}: {}", path.display(), e))?;
+    let mut size = file.metadata().map(|metadata| metadata.len()).unwrap_or_default();
+    let mut writer = BufWriter::new(file);
+    if format == StreamFormat::Csv && size == 0 {
+        writer.write_all(CSV_HEADER.as_bytes())
+            .map_err(|e| format!("Failed to write to event stream {}: {}", path.display(), e))?;
+        size = CSV_HEADER.len() as u64;
+    }
+    Ok((writer, size))
+}
+
+/// Why a stream stopped without being asked to; emitted as `event-stream-stopped`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EventStreamStopped {
+    pub path: String,
+    pub reason: String,
+    pub event_count: u64,
+    /// Milliseconds since the epoch
+    pub stopped_at: u64,
+}
+
+/// Answer to the stream commands, and part of debug info.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct EventStreamStatus {
+    pub active: bool,
+    pub config: Option<EventStreamConfig>,
+    /// Milliseconds since the epoch
+    pub started_at: Option<u64>,
+    /// Size of the current file
+    pub file_bytes: u64,
+    /// Events and send results written since the stream started or was reopened
+    pub event_count: u64,
+    pub send_count: u64,
+    pub rotations: u32,
+    pub write_errors: u64,
+    pub last_stopped: Option<EventStreamStopped>,
+}
+
+struct ActiveStream {
+    config: EventStreamConfig,
+    path: PathBuf,
+    writer: BufWriter<File>,
+    file_bytes: u64,
+    started_at: u64,
+    event_count: u64,
+    send_count: u64,
+    rotations: u32,
+    write_errors: u64,
+    unflushed: bool,
+    flushed_at: Instant,
+}
+
+impl ActiveStream {
+    fn write_line(&mut self, line: &str) -> bool {
+        if self.file_bytes > 0 && self.file_bytes + line.len() as u64 > self.config.max_file_bytes {
+            if let Err(e) = self.rotate() {
+                self.write_failed(e);
+                return false;
+            }
+        }
+        if let Err(e) = self.writer.write_all(line.as_bytes()) {
+            self.write_failed(e.to_string());
+            return false;
+        }
+        self.file_bytes += line.len() as u64;
+        self.unflushed = true;
+        if self.flushed_at.elapsed() >= FLUSH_INTERVAL {
+            self.flush();
+        }
+        true
+    }
+
+    fn flush(&mut self) {
+        self.flushed_at = Instant::now();
+        if !self.unflushed {
+            return;
+        }
+        self.unflushed = false;
+        if let Err(e) = self.writer.flush() {
+            self.write_failed(e.to_string());
+        }
+    }
+
+    /// Shifts `<path>.1`… up one, dropping the oldest, and starts a new file at `path`.
+    fn rotate(&mut self) -> Result<(), String> {
+        self.writer.flush().map_err(|e| e.to_string())?;
+        for index in (1..ROTATED_FILES).rev() {
+            let from = rotated_path(&self.path, index);
+            if from.exists() {
+                std::fs::rename(&from, rotated_path(&self.path, index + 1)).map_err(|e| e.to_string())?;
+            }
+        }
+        std::fs::rename(&self.path, rotated_path(&self.path, 1)).map_err(|e| e.to_string())?;
+        let (writer, size) = open(&self.path, self.config.format)?;
+        self.writer = writer;
+        self.file_bytes = size;
+        self.rotations += 1;
+        info!("Rotated event stream {}", self.path.display());
+        Ok(())
+    }
+
+    fn write_failed(&mut self, error: String) {
+        self.write_errors += 1;
+        if self.write_errors == 1 {
+            warn!("Failed to write to event stream {}: {}", self.path.display(), error);
+        }
+        error_counters::errors().record(ErrorCategory::RecordingWrite, format!("{}: {}", self.path.display(), error));
+    }
+}
+
+/// Every unified event, and optionally every send result, appended to one file.
+#[derive(Default)]
+pub struct EventStream {
+    /// Checked before locking, so events cost nothing while nothing is streamed
+    streaming: AtomicBool,
+    active: Mutex<Option<ActiveStream>>,
+    last_stopped: Mutex<Option<EventStreamStopped>>,
+}
+
+impl EventStream {
+    /// Starts appending to `config.path`, which is created if missing and otherwise added
+    /// to, as when a stream resumes after a restart.
+    pub fn start(&self, config: EventStreamConfig) -> Result<EventStreamStatus, String> {
+        config.validate()?;
+        let mut active = self.active.lock_or_recover();
+        if let Some(current) = active.as_ref() {
+            return Err(format!("Already streaming events to {}", current.path.display()));
+        }
+        let path = PathBuf::from(&config.path);
+        if let Some(free) = free_bytes(&path).filter(|free| *free < config.min_free_bytes) {
+            return Err(format!(
+                "Only {} MiB free on the disk holding {}; the stream needs {} MiB",
+                free / (1024 * 1024),
+                path.display(),
+                config.min_free_bytes / (1024 * 1024)
+            ));
+        }
+        let (writer, file_bytes) = open(&path, config.format)?;
+        info!("Streaming events to {} as {:?}", path.display(), config.format);
+        *active = Some(ActiveStream {
+            config,
+            path,
+            writer,
+            file_bytes,
+            started_at: now_ms(),
+            event_count: 0,
+            send_count: 0,
+            rotations: 0,
+            write_errors: 0,
+            unflushed: false,
+            flushed_at: Instant::now(),
+        });
+        self.streaming.store(true, Ordering::Relaxed);
+        drop(active);
+        Ok(self.status())
+    }
+
+    /// Flushes and closes the stream, returning how it ended up.
+    pub fn stop(&self) -> Result<EventStreamStatus, String> {
+        let mut active = self.active.lock_or_recover();
+        let Some(mut current) = active.take() else {
+            return Err("No event stream is running".to_string());
+        };
+        self.streaming.store(false, Ordering::Relaxed);
+        let status = status_of(Some(&current), self.last_stopped.lock_or_recover().clone());
+        current.writer.flush()
+            .map_err(|e| format!("Failed to flush event stream {}: {}", current.path.display(), e))?;
+        info!("Stopped streaming events to {} ({} events)", current.path.display(), current.event_count);
+        Ok(EventStreamStatus { active: false, ..status })
+    }
+
+    pub fn write_event(&self, event: &UnifiedControllerEvent) {
+        if !self.streaming.load(Ordering::Relaxed) {
+            return;
+        }
+        let mut active = self.active.lock_or_recover();
+        let Some(current) = active.as_mut() else {
+            return;
+        };
+        let line = format_event(current.config.format, event);
+        if current.write_line(&line) {
+            current.event_count += 1;
+        }
+    }
+
+    pub fn write_send(&self, endpoint: &str, result: &Result<String, String>, duration_ms: u64) {
+        if !self.streaming.load(Ordering::Relaxed) {
+            return;
+        }
+        let mut active = self.active.lock_or_recover();
+        let Some(current) = active.as_mut().filter(|current| current.config.include_send_results) else {
+            return;
+        };
+        let line = format_send(current.config.format, now_ms(), endpoint, result, duration_ms);
+        if current.write_line(&line) {
+            current.send_count += 1;
+        }
+    }
+
+    /// Writes out what's buffered and checks the disk, stopping the stream when free
+    /// space falls under its threshold. Returns why it stopped, if it did.
+    pub fn maintain(&self) -> Option<EventStreamStopped> {
+        if !self.streaming.load(Ordering::Relaxed) {
+            return None;
+        }
+        let mut active = self.active.lock_or_recover();
+        let current = active.as_mut()?;
+        current.flush();
+        let free = free_bytes(&current.path).filter(|free| *free < current.config.min_free_bytes)?;
+        let mut current = active.take()?;
+        self.streaming.store(false, Ordering::Relaxed);
+        let _ = current.writer.flush();
+        let stopped = EventStreamStopped {
+            path: current.config.path.clone(),
+            reason: format!(
+                "Only {} MiB free on the disk (threshold {} MiB)",
+                free / (1024 * 1024),
+                current.config.min_free_bytes / (1024 * 1024)
+            ),
+            event_count: current.event_count,
+            stopped_at: now_ms(),
+        };
+        warn!("Stopped streaming events to {}: {}", stopped.path, stopped.reason);
+        *self.last_stopped.lock_or_recover() = Some(stopped.clone());
+        Some(stopped)
+    }
+
+    pub fn status(&self) -> EventStreamStatus {
+        status_of(self.active.lock_or_recover().as_ref(), self.last_stopped.lock_or_recover().clone())
+    }
+}
+
+fn status_of(active: Option<&ActiveStream>, last_stopped: Option<EventStreamStopped>) -> EventStreamStatus {
+    let Some(active) = active else {
+        return EventStreamStatus { last_stopped, ..EventStreamStatus::default() };
+    };
+    EventStreamStatus {
+        active: true,
+        config: Some(active.config.clone()),
+        started_at: Some(active.started_at),
+        file_bytes: active.file_bytes,
+        event_count: active.event_count,
+        send_count: active.send_count,
+        rotations: active.rotations,
+        write_errors: active.write_errors,
+        last_stopped,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::{json, Value};
+
+    fn event(input: Value) -> UnifiedControllerEvent {
+        serde_json::from_value(json!({
+            "source": "evdev",
+            "device_id": "evdev:test",
+            "controller_id": null,
+            "device_path": "/dev/input/event5",
+            "input": input,
+            "raw_code": 304,
+            "raw_value": 1,
+            "kernel_timestamp_ms": 990,
+            "emit_timestamp_ms": 1000,
+        }))
+        .unwrap()
+    }
+
+    fn south(pressed: bool) -> UnifiedControllerEvent {
+        event(json!({ "kind": "button", "name": "South", "pressed": pressed }))
+    }
+
+    #[test]
+    fn jsonl_lines_are_tagged_records() {
+        let line = format_event(StreamFormat::Jsonl, &south(true));
+        assert!(line.ends_with('\n'));
+        let record: Value = serde_json::from_str(&line).unwrap();
+        assert_eq!(record["type"], "event");
+        assert_eq!(record["source"], "evdev");
+        assert_eq!(record["input"], json!({ "kind": "button", "name": "South", "pressed": true }));
+
+        let line = format_send(StreamFormat::Jsonl, 1200, "/api/lights", &Err("timed out".to_string()), 250);
+        let record: Value = serde_json::from_str(&line).unwrap();
+        assert_eq!(record, json!({
+            "type": "send",
+            "timestamp_ms": 1200,
+            "endpoint": "/api/lights",
+            "ok": false,
+            "duration_ms": 250,
+            "error": "timed out",
+        }));
+    }
+
+    #[test]
+    fn csv_rows_fill_their_own_columns() {
+        assert_eq!(
+            format_event(StreamFormat::Csv, &south(false)),
+            "event,1000,evdev,evdev:test,,/dev/input/event5,button,South,0,304,1,990,,,,\n"
+        );
+        assert_eq!(
+            format_event(StreamFormat::Csv, &event(json!({ "kind": "axis", "name": "LeftStickX", "value": 0.5 }))),
+            "event,1000,evdev,evdev:test,,/dev/input/event5,axis,LeftStickX,0.5,304,1,990,,,,\n"
+        );
+        assert_eq!(
+            format_send(StreamFormat::Csv, 1200, "/api/lights", &Ok(String::new()), 40),
+            "send,1200,,,,,,,,,,,/api/lights,true,40,\n"
+        );
+    }
+
+    #[test]
+    fn csv_quotes_fields_that_need_it() {
+        let line = format_send(StreamFormat::Csv, 1200, "/api/lights", &Err("bad \"token\", retry\nlater".to_string()), 40);
+        assert_eq!(line, "send,1200,,,,,,,,,,,/api/lights,false,40,\"bad \"\"token\"\", retry\nlater\"\n");
+    }
+
+    #[test]
+    fn streams_append_and_rotate_by_size() {
+        let dir = std::env::temp_dir().join(format!("event-stream-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("events.csv");
+        let config = EventStreamConfig {
+            path: path.to_string_lossy().to_string(),
+            format: StreamFormat::Csv,
+            include_send_results: false,
+            max_file_bytes: 1024 * 1024,
+            min_free_bytes: 0,
+        };
+
+        let stream = EventStream::default();
+        stream.start(config.clone()).unwrap();
+        assert!(stream.start(config.clone()).is_err());
+        let line_len = format_event(StreamFormat::Csv, &south(true)).len() as u64;
+        let events = (1024 * 1024) / line_len + 10;
+        for _ in 0..events {
+            stream.write_event(&south(true));
+        }
+        // Sends aren't wanted by this stream
+        stream.write_send("/api/lights", &Ok(String::new()), 10);
+        let status = stream.stop().unwrap();
+        assert!(!status.active);
+        assert_eq!(status.event_count, events);
+        assert_eq!(status.send_count, 0);
+        assert_eq!(status.rotations, 1);
+        assert!(stream.stop().is_err());
+
+        let rotated = std::fs::read_to_string(dir.join("events.csv.1")).unwrap();
+        let current = std::fs::read_to_string(&path).unwrap();
+        assert!(rotated.len() as u64 <= config.max_file_bytes);
+        // Each file starts with the header, so it reads on its own
+        assert!(rotated.starts_with("type,timestamp_ms,"));
+        assert!(current.starts_with("type,timestamp_ms,"));
+        assert_eq!(rotated.lines().count() as u64 + current.lines().count() as u64 - 2, events);
+
+        // Starting again appends rather than writing a second header
+        stream.start(config).unwrap();
+        stream.write_event(&south(false));
+        stream.stop().unwrap();
+        let resumed = std::fs::read_to_string(&path).unwrap();
+        assert_eq!(resumed.matches("type,timestamp_ms,").count(), 1);
+        assert!(resumed.ends_with(",South,0,304,1,990,,,,\n"));
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
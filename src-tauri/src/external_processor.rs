@@ -0,0 +1,646 @@
+use crate::controller_event::{EventSource, NormalizedInput, UnifiedControllerEvent};
+use crate::event_script::{self, ScriptAction};
+use crate::locking::MutexExt;
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::io::{BufRead, BufReader, BufWriter, Write};
+use std::process::{Child, ChildStdin, Command, Stdio};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, Condvar, Mutex, PoisonError};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use tauri::AppHandle;
+use tracing::{debug, error, info, warn};
+
+/// Version of the line protocol, sent in the app's hello and expected in the child's.
+pub const PROTOCOL_VERSION: u32 = 1;
+/// Events waiting for the child beyond which new ones are dropped; queued axis samples
+/// are replaced by newer ones of the same axis long before that
+pub const QUEUE_CAPACITY: usize = 1024;
+pub const HEARTBEAT_INTERVAL: Duration = Duration::from_secs(2);
+/// Silence after which the child is taken to be hung and restarted
+pub const HEARTBEAT_TIMEOUT: Duration = Duration::from_secs(10);
+const SUPERVISE_INTERVAL: Duration = Duration::from_millis(200);
+const MAX_RESTART_DELAY: Duration = Duration::from_secs(30);
+/// A child that ran this long before exiting is restarted straight away
+const STABLE_RUN: Duration = Duration::from_secs(60);
+
+fn now_ms() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_millis() as u64
+}
+
+/// A line from the app to the child.
+#[derive(Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum Outgoing<'a> {
+    /// First line of every run
+    Hello { protocol: u32, app: &'a str, version: &'a str },
+    Event { event: &'a UnifiedControllerEvent },
+    /// Echoed back by the child to show it's alive, with what it has missed so far
+    Heartbeat { seq: u64, dropped: u64, coalesced: u64 },
+}
+
+fn line(outgoing: &Outgoing) -> String {
+    serde_json::to_string(outgoing).unwrap_or_default()
+}
+
+pub fn hello_line() -> String {
+    line(&Outgoing::Hello { protocol: PROTOCOL_VERSION, app: "steam-deck-controller", version: env!("CARGO_PKG_VERSION") })
+}
+
+/// A line from the child to the app.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ChildMessage {
+    Hello { protocol: u32 },
+    Heartbeat { seq: u64 },
+    /// Same schema as the actions event scripts return
+    Action(ScriptAction),
+}
+
+#[derive(Deserialize)]
+struct ChildHello {
+    protocol: u32,
+}
+
+#[derive(Deserialize)]
+struct ChildHeartbeat {
+    seq: u64,
+}
+
+pub fn parse_line(line: &str) -> Result<ChildMessage, String> {
+    let value: serde_json::Value = serde_json::from_str(line).map_err(|e| format!("Not JSON: {}", e))?;
+    let parsed = match value.get("type").and_then(|kind| kind.as_str()) {
+        Some("hello") => serde_json::from_value::<ChildHello>(value).map(|hello| ChildMessage::Hello { protocol: hello.protocol }),
+        Some("heartbeat") => serde_json::from_value::<ChildHeartbeat>(value).map(|heartbeat| ChildMessage::Heartbeat { seq: heartbeat.seq }),
+        Some(_) => serde_json::from_value::<ScriptAction>(value).map(ChildMessage::Action),
+        None => return Err("Missing \"type\"".to_string()),
+    };
+    let message = parsed.map_err(|e| e.to_string())?;
+    if let ChildMessage::Action(ScriptAction::Haptic { cue }) = &message {
+        cue.validate()?;
+    }
+    Ok(message)
+}
+
+enum Queued {
+    Event(UnifiedControllerEvent),
+    /// Hello and heartbeats, which never count against the capacity
+    Line(String),
+}
+
+/// What happened to an event offered to the queue.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Pushed {
+    Queued,
+    /// Replaced a queued sample of the same axis
+    Coalesced,
+    /// The queue was full
+    Dropped,
+}
+
+fn same_axis(a: &UnifiedControllerEvent, b: &UnifiedControllerEvent) -> bool {
+    match (&a.input, &b.input) {
+        (NormalizedInput::Axis { name, .. }, NormalizedInput::Axis { name: other, .. }) => {
+            name == other && a.device_id == b.device_id && a.controller_id == b.controller_id
+        }
+        _ => false,
+    }
+}
+
+/// Lines waiting for the child. Holds no process, so tests can drive it alone.
+pub struct ProcessorQueue {
+    items: VecDeque<Queued>,
+    events: usize,
+    capacity: usize,
+}
+
+impl ProcessorQueue {
+    pub fn new(capacity: usize) -> Self {
+        Self { items: VecDeque::new(), events: 0, capacity }
+    }
+
+    /// Queues `event`, unless a sample of the same axis is still waiting, which then takes
+    /// its value instead.
+    pub fn push_event(&mut self, event: UnifiedControllerEvent) -> Pushed {
+        let waiting = self.items.iter_mut().rev().find_map(|queued| match queued {
+            Queued::Event(queued) if same_axis(queued, &event) => Some(queued),
+            _ => None,
+        });
+        if let Some(waiting) = waiting {
+            *waiting = event;
+            return Pushed::Coalesced;
+        }
+        if self.events >= self.capacity {
+            return Pushed::Dropped;
+        }
+        self.items.push_back(Queued::Event(event));
+        self.events += 1;
+        Pushed::Queued
+    }
+
+    pub fn push_line(&mut self, line: String) {
+        self.items.push_back(Queued::Line(line));
+    }
+
+    /// Empties the queue for a new run, starting it with the hello.
+    pub fn restart(&mut self) {
+        self.items.clear();
+        self.events = 0;
+        self.push_line(hello_line());
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.items.is_empty()
+    }
+
+    /// The next line to write, without its newline, and whether it's an event.
+    pub fn pop(&mut self) -> Option<(String, bool)> {
+        match self.items.pop_front()? {
+            Queued::Event(event) => {
+                self.events -= 1;
+                Some((line(&Outgoing::Event { event: &event }), true))
+            }
+            Queued::Line(line) => Some((line, false)),
+        }
+    }
+}
+
+/// Answer to the external processor commands, and part of debug info.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ExternalProcessorStatus {
+    pub running: bool,
+    pub command: Option<String>,
+    pub args: Vec<String>,
+    pub pid: Option<u32>,
+    /// When the current run started, ms since the epoch
+    pub started_at: Option<u64>,
+    /// Protocol version from the child's hello, once it has said it
+    pub protocol: Option<u32>,
+    pub restarts: u32,
+    pub events_sent: u64,
+    /// Axis samples replaced by newer ones before the child read them
+    pub coalesced_events: u64,
+    /// Events dropped with the queue full
+    pub dropped_events: u64,
+    pub actions_received: u64,
+    /// Lines from the child that weren't a known message
+    pub bad_lines: u64,
+    pub last_error: Option<String>,
+    /// Last line the child wrote to stderr
+    pub last_stderr: Option<String>,
+}
+
+#[derive(Default)]
+struct Stats {
+    pid: Option<u32>,
+    started_at: Option<u64>,
+    protocol: Option<u32>,
+    restarts: u32,
+    events_sent: u64,
+    coalesced_events: u64,
+    dropped_events: u64,
+    actions_received: u64,
+    bad_lines: u64,
+    last_error: Option<String>,
+    last_stderr: Option<String>,
+}
+
+/// One `start_external_processor`, across however many runs of the child it takes.
+struct Session {
+    command: String,
+    args: Vec<String>,
+    queue: Mutex<ProcessorQueue>,
+    queued: Condvar,
+    stop: AtomicBool,
+    /// Bumped at each run, so threads left over from the last one know to finish
+    run: AtomicU64,
+    child: Mutex<Option<Child>>,
+    /// When anything was last heard from the child
+    heard_at: Mutex<Instant>,
+    stats: Mutex<Stats>,
+}
+
+impl Session {
+    fn current(&self, run: u64) -> bool {
+        !self.stop.load(Ordering::Relaxed) && self.run.load(Ordering::Relaxed) == run
+    }
+
+    fn push_line(&self, line: String) {
+        self.queue.lock_or_recover().push_line(line);
+        self.queued.notify_one();
+    }
+
+    fn kill(&self) {
+        if let Some(mut child) = self.child.lock_or_recover().take() {
+            let _ = child.kill();
+            let _ = child.wait();
+        }
+        self.queued.notify_all();
+    }
+
+    fn status(&self) -> ExternalProcessorStatus {
+        let stats = self.stats.lock_or_recover();
+        ExternalProcessorStatus {
+            running: self.child.lock_or_recover().is_some(),
+            command: Some(self.command.clone()),
+            args: self.args.clone(),
+            pid: stats.pid,
+            started_at: stats.started_at,
+            protocol: stats.protocol,
+            restarts: stats.restarts,
+            events_sent: stats.events_sent,
+            coalesced_events: stats.coalesced_events,
+            dropped_events: stats.dropped_events,
+            actions_received: stats.actions_received,
+            bad_lines: stats.bad_lines,
+            last_error: stats.last_error.clone(),
+            last_stderr: stats.last_stderr.clone(),
+        }
+    }
+}
+
+/// Spawns the child and the threads feeding it, reading it and logging its stderr.
+fn launch(session: &Arc<Session>, app: &AppHandle) -> Result<(), String> {
+    let mut child = Command::new(&session.command)
+        .args(&session.args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|e| format!("Failed to start external processor {}: {}", session.command, e))?;
+    let (Some(stdin), Some(stdout), Some(stderr)) = (child.stdin.take(), child.stdout.take(), child.stderr.take()) else {
+        let _ = child.kill();
+        return Err("External processor started without its pipes".to_string());
+    };
+    let run = session.run.fetch_add(1, Ordering::Relaxed) + 1;
+    session.queue.lock_or_recover().restart();
+    *session.heard_at.lock_or_recover() = Instant::now();
+    {
+        let mut stats = session.stats.lock_or_recover();
+        stats.pid = Some(child.id());
+        stats.started_at = Some(now_ms());
+        stats.protocol = None;
+    }
+    info!("Started external processor {} (pid {})", session.command, child.id());
+    *session.child.lock_or_recover() = Some(child);
+
+    let writer = session.clone();
+    std::thread::spawn(move || write_lines(&writer, run, stdin));
+    let reader = session.clone();
+    let app = app.clone();
+    std::thread::spawn(move || read_lines(&reader, run, stdout, &app));
+    let logger = session.clone();
+    std::thread::spawn(move || {
+        for line in BufReader::new(stderr).lines().map_while(Result::ok) {
+            info!("External processor: {}", line);
+            logger.stats.lock_or_recover().last_stderr = Some(line);
+        }
+    });
+    Ok(())
+}
+
+fn write_lines(session: &Session, run: u64, stdin: ChildStdin) {
+    let mut writer = BufWriter::new(stdin);
+    loop {
+        let (line, event, more) = {
+            let mut queue = session.queue.lock_or_recover();
+            loop {
+                if !session.current(run) {
+                    return;
+                }
+                if let Some((line, event)) = queue.pop() {
+                    break (line, event, !queue.is_empty());
+                }
+                queue = session.queued
+                    .wait_timeout(queue, SUPERVISE_INTERVAL)
+                    .unwrap_or_else(PoisonError::into_inner)
+                    .0;
+            }
+        };
+        if event {
+            session.stats.lock_or_recover().events_sent += 1;
+        }
+        let written = writer.write_all(line.as_bytes())
+            .and_then(|()| writer.write_all(b"\n"))
+            .and_then(|()| if more { Ok(()) } else { writer.flush() });
+        if let Err(e) = written {
+            // The supervisor sees the exit and restarts it
+            debug!("External processor stopped reading: {}", e);
+            return;
+        }
+    }
+}
+
+fn read_lines(session: &Session, run: u64, stdout: std::process::ChildStdout, app: &AppHandle) {
+    for line in BufReader::new(stdout).lines().map_while(Result::ok) {
+        if !session.current(run) {
+            return;
+        }
+        if line.trim().is_empty() {
+            continue;
+        }
+        let message = match parse_line(&line) {
+            Ok(message) => message,
+            Err(e) => {
+                let mut stats = session.stats.lock_or_recover();
+                stats.bad_lines += 1;
+                if stats.bad_lines == 1 {
+                    warn!("Ignoring external processor line {:?}: {}", line, e);
+                } else {
+                    debug!("Ignoring external processor line {:?}: {}", line, e);
+                }
+                continue;
+            }
+        };
+        *session.heard_at.lock_or_recover() = Instant::now();
+        match message {
+            ChildMessage::Hello { protocol } if protocol != PROTOCOL_VERSION => {
+                let e = format!("External processor speaks protocol {}; this app speaks {}", protocol, PROTOCOL_VERSION);
+                error!("{}", e);
+                session.stats.lock_or_recover().last_error = Some(e);
+                // Restarting wouldn't change its mind
+                session.stop.store(true, Ordering::Relaxed);
+                session.kill();
+                return;
+            }
+            ChildMessage::Hello { protocol } => session.stats.lock_or_recover().protocol = Some(protocol),
+            ChildMessage::Heartbeat { .. } => {}
+            ChildMessage::Action(action) => {
+                session.stats.lock_or_recover().actions_received += 1;
+                event_script::execute(app, "External processor", None, action);
+            }
+        }
+    }
+}
+
+/// Restarts the child when it exits or stops answering, backing off while it keeps
+/// failing, and sends the heartbeats.
+fn supervise(session: Arc<Session>, app: AppHandle) {
+    let mut heartbeat_sent = Instant::now();
+    let mut heartbeat_seq = 0;
+    let mut run_started = Instant::now();
+    let mut failures = 0u32;
+    loop {
+        std::thread::sleep(SUPERVISE_INTERVAL);
+        if session.stop.load(Ordering::Relaxed) {
+            session.kill();
+            return;
+        }
+        if heartbeat_sent.elapsed() >= HEARTBEAT_INTERVAL {
+            heartbeat_seq += 1;
+            let (dropped, coalesced) = {
+                let stats = session.stats.lock_or_recover();
+                (stats.dropped_events, stats.coalesced_events)
+            };
+            session.push_line(line(&Outgoing::Heartbeat { seq: heartbeat_seq, dropped, coalesced }));
+            heartbeat_sent = Instant::now();
+        }
+        let exited = match session.child.lock_or_recover().as_mut() {
+            Some(child) => match child.try_wait() {
+                Ok(Some(status)) => Some(format!("External processor exited ({})", status)),
+                Ok(None) => None,
+                Err(e) => Some(format!("Lost track of the external processor: {}", e)),
+            },
+            // Its last restart failed, and said why
+            None => Some(session.stats.lock_or_recover().last_error.clone().unwrap_or_default()),
+        };
+        let hung = session.heard_at.lock_or_recover().elapsed() > HEARTBEAT_TIMEOUT;
+        let reason = match (exited, hung) {
+            (Some(reason), _) => reason,
+            (None, true) => format!("External processor hasn't answered for {}s", HEARTBEAT_TIMEOUT.as_secs()),
+            (None, false) => continue,
+        };
+        session.kill();
+        if session.stop.load(Ordering::Relaxed) {
+            return;
+        }
+        failures = if run_started.elapsed() >= STABLE_RUN { 0 } else { failures + 1 };
+        // 0s, then 1s, 2s, 4s… while it keeps failing
+        let delay = match failures {
+            0 => Duration::ZERO,
+            n => Duration::from_secs(1 << (n - 1).min(5)).min(MAX_RESTART_DELAY),
+        };
+        warn!("{}; restarting it in {}s", reason, delay.as_secs());
+        session.stats.lock_or_recover().last_error = Some(reason);
+        let waiting = Instant::now();
+        while waiting.elapsed() < delay {
+            if session.stop.load(Ordering::Relaxed) {
+                return;
+            }
+            std::thread::sleep(SUPERVISE_INTERVAL);
+        }
+        session.stats.lock_or_recover().restarts += 1;
+        run_started = Instant::now();
+        if let Err(e) = launch(&session, &app) {
+            warn!("{}", e);
+            session.stats.lock_or_recover().last_error = Some(e);
+        }
+    }
+}
+
+/// A program of the user's fed every controller event, whose answers are carried out
+/// like an event script's.
+#[derive(Default)]
+pub struct ExternalProcessor {
+    /// Checked before locking, so events cost nothing while no processor is running
+    active: AtomicBool,
+    session: Mutex<Option<Arc<Session>>>,
+}
+
+impl ExternalProcessor {
+    /// Spawns `command` with `args` and keeps it running until `stop`, carrying out its
+    /// answers through `app`'s pipeline. Fails if it can't be spawned at all, or one is
+    /// already running.
+    pub fn start(&self, app: &AppHandle, command: &str, args: Vec<String>) -> Result<ExternalProcessorStatus, String> {
+        let mut current = self.session.lock_or_recover();
+        if let Some(session) = current.as_ref().filter(|session| !session.stop.load(Ordering::Relaxed)) {
+            return Err(format!("External processor {} is already running; stop it first", session.command));
+        }
+        let session = Arc::new(Session {
+            command: command.to_string(),
+            args,
+            queue: Mutex::new(ProcessorQueue::new(QUEUE_CAPACITY)),
+            queued: Condvar::new(),
+            stop: AtomicBool::new(false),
+            run: AtomicU64::new(0),
+            child: Mutex::new(None),
+            heard_at: Mutex::new(Instant::now()),
+            stats: Mutex::new(Stats::default()),
+        });
+        launch(&session, app)?;
+        let supervised = session.clone();
+        let app = app.clone();
+        std::thread::spawn(move || supervise(supervised, app));
+        let status = session.status();
+        *current = Some(session);
+        self.active.store(true, Ordering::Relaxed);
+        Ok(status)
+    }
+
+    /// Kills the child straight away and stops restarting it. Returns how the session
+    /// ended up, if there was one.
+    pub fn stop(&self) -> Option<ExternalProcessorStatus> {
+        self.active.store(false, Ordering::Relaxed);
+        let session = self.session.lock_or_recover().clone()?;
+        session.stop.store(true, Ordering::Relaxed);
+        session.kill();
+        info!("Stopped external processor {}", session.command);
+        Some(session.status())
+    }
+
+    /// Queues `event` for the child. Input the child made itself, like replays, isn't sent.
+    pub fn send_event(&self, event: &UnifiedControllerEvent) {
+        if !self.active.load(Ordering::Relaxed) || event.source == EventSource::Synthetic {
+            return;
+        }
+        let Some(session) = self.session.lock_or_recover().clone() else {
+            return;
+        };
+        if session.stop.load(Ordering::Relaxed) {
+            return;
+        }
+        let pushed = session.queue.lock_or_recover().push_event(event.clone());
+        match pushed {
+            Pushed::Queued => session.queued.notify_one(),
+            Pushed::Coalesced => session.stats.lock_or_recover().coalesced_events += 1,
+            Pushed::Dropped => {
+                let mut stats = session.stats.lock_or_recover();
+                stats.dropped_events += 1;
+                if stats.dropped_events == 1 {
+                    warn!("External processor can't keep up; dropping events");
+                }
+            }
+        }
+    }
+
+    pub fn status(&self) -> ExternalProcessorStatus {
+        match self.session.lock_or_recover().as_ref() {
+            Some(session) => session.status(),
+            None => ExternalProcessorStatus::default(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::{json, Value};
+
+    fn event(device: &str, input: Value) -> UnifiedControllerEvent {
+        serde_json::from_value(json!({
+            "source": "evdev",
+            "device_id": device,
+            "controller_id": null,
+            "device_path": "/dev/input/event5",
+            "input": input,
+            "raw_code": null,
+            "raw_value": null,
+            "kernel_timestamp_ms": null,
+            "emit_timestamp_ms": 0,
+        }))
+        .unwrap()
+    }
+
+    fn axis(name: &str, value: f32) -> UnifiedControllerEvent {
+        event("evdev:pad", json!({ "kind": "axis", "name": name, "value": value }))
+    }
+
+    fn button(name: &str, pressed: bool) -> UnifiedControllerEvent {
+        event("evdev:pad", json!({ "kind": "button", "name": name, "pressed": pressed }))
+    }
+
+    fn drain(queue: &mut ProcessorQueue) -> Vec<Value> {
+        std::iter::from_fn(|| queue.pop()).map(|(line, _)| serde_json::from_str(&line).unwrap()).collect()
+    }
+
+    #[test]
+    fn runs_start_with_a_versioned_hello() {
+        let hello: Value = serde_json::from_str(&hello_line()).unwrap();
+        assert_eq!(hello["type"], "hello");
+        assert_eq!(hello["protocol"], PROTOCOL_VERSION);
+
+        let mut queue = ProcessorQueue::new(8);
+        queue.push_event(button("South", true));
+        queue.restart();
+        let lines = drain(&mut queue);
+        assert_eq!(lines.len(), 1);
+        assert_eq!(lines[0]["type"], "hello");
+    }
+
+    #[test]
+    fn events_go_out_in_order() {
+        let mut queue = ProcessorQueue::new(8);
+        queue.restart();
+        assert_eq!(queue.push_event(button("South", true)), Pushed::Queued);
+        assert_eq!(queue.push_event(axis("ABS_X", 0.25)), Pushed::Queued);
+        let lines = drain(&mut queue);
+        assert_eq!(lines.len(), 3);
+        assert_eq!(lines[1]["type"], "event");
+        assert_eq!(lines[1]["event"]["input"]["name"], "South");
+        assert_eq!(lines[2]["event"]["input"]["name"], "ABS_X");
+        assert!(queue.is_empty());
+    }
+
+    #[test]
+    fn waiting_axis_samples_are_coalesced() {
+        let mut queue = ProcessorQueue::new(8);
+        assert_eq!(queue.push_event(axis("ABS_X", 0.1)), Pushed::Queued);
+        assert_eq!(queue.push_event(axis("ABS_Y", 0.2)), Pushed::Queued);
+        assert_eq!(queue.push_event(axis("ABS_X", 0.9)), Pushed::Coalesced);
+        // Another device's axis of the same name is its own sample
+        let other = event("evdev:other", json!({ "kind": "axis", "name": "ABS_X", "value": 0.5 }));
+        assert_eq!(queue.push_event(other), Pushed::Queued);
+
+        let lines = drain(&mut queue);
+        assert_eq!(lines.len(), 3);
+        assert_eq!(lines[0]["event"]["input"]["value"], 0.9);
+        assert_eq!(lines[1]["event"]["input"]["name"], "ABS_Y");
+        assert_eq!(lines[2]["event"]["device_id"], "evdev:other");
+    }
+
+    #[test]
+    fn a_full_queue_drops_new_events_but_still_coalesces() {
+        let mut queue = ProcessorQueue::new(2);
+        queue.restart();
+        assert_eq!(queue.push_event(button("South", true)), Pushed::Queued);
+        assert_eq!(queue.push_event(axis("ABS_X", 0.1)), Pushed::Queued);
+        assert_eq!(queue.push_event(button("South", false)), Pushed::Dropped);
+        assert_eq!(queue.push_event(axis("ABS_X", 0.3)), Pushed::Coalesced);
+        // Heartbeats don't count against the capacity
+        queue.push_line(r#"{"type":"heartbeat","seq":1}"#.to_string());
+
+        let lines = drain(&mut queue);
+        assert_eq!(lines.len(), 4);
+        assert_eq!(lines[2]["event"]["input"]["value"], 0.3);
+        assert_eq!(lines[3]["type"], "heartbeat");
+        assert_eq!(queue.push_event(button("South", false)), Pushed::Queued);
+    }
+
+    #[test]
+    fn child_lines_parse_to_messages() {
+        assert_eq!(parse_line(r#"{"type":"hello","protocol":1}"#), Ok(ChildMessage::Hello { protocol: 1 }));
+        assert_eq!(parse_line(r#"{"type":"heartbeat","seq":7}"#), Ok(ChildMessage::Heartbeat { seq: 7 }));
+        assert_eq!(
+            parse_line(r#"{"type":"send","payload":{"scene":2}}"#),
+            Ok(ChildMessage::Action(ScriptAction::Send { payload: json!({ "scene": 2 }), endpoint: None }))
+        );
+        assert_eq!(
+            parse_line(r#"{"type":"synthetic","controller_id":1,"input":{"kind":"button","name":"North","pressed":true}}"#),
+            Ok(ChildMessage::Action(ScriptAction::Synthetic {
+                controller_id: Some(1),
+                input: NormalizedInput::Button { name: "North".to_string(), pressed: true },
+            }))
+        );
+    }
+
+    #[test]
+    fn bad_child_lines_are_refused() {
+        assert!(parse_line("print('hi')").is_err());
+        assert!(parse_line(r#"{"payload":{}}"#).is_err());
+        assert!(parse_line(r#"{"type":"teleport"}"#).is_err());
+        assert!(parse_line(r#"{"type":"hello"}"#).is_err());
+        assert!(parse_line(r#"{"type":"haptic","cue":{"intensity":3.0}}"#).is_err());
+    }
+}
@@ -0,0 +1,136 @@
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+use tracing::{info, warn};
+
+const HWMON_ROOT: &str = "/sys/class/hwmon";
+/// hwmon `name`s of the Steam Deck EC driver and the ASUS EC driver (ROG Ally)
+const HWMON_NAMES: [&str; 2] = ["steamdeck", "asus-ec"];
+/// Highest manual target accepted; well above what the Deck's fan can reach.
+pub const MAX_FAN_RPM: u32 = 8000;
+/// Smaller changes in the measured speed aren't reported.
+const FAN_SPEED_CHANGE_THRESHOLD_RPM: u32 = 50;
+const FAN_POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum FanMode {
+    /// The EC controls the fan
+    #[default]
+    Auto,
+    /// Fixed target speed in RPM
+    Manual(u32),
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FanSpeedChanged {
+    pub rpm: u32,
+    pub previous_rpm: Option<u32>,
+}
+
+/// Fan speed control through the EC's hwmon sysfs interface. Writing needs root (or a
+/// udev rule granting access to the attributes).
+#[derive(Default)]
+pub struct FanController {
+    hwmon_dir: Option<PathBuf>,
+    mode: FanMode,
+    last_reported_rpm: Option<u32>,
+    last_check: Option<Instant>,
+}
+
+impl FanController {
+    pub fn discover() -> Self {
+        let hwmon_dir = find_hwmon_dir(Path::new(HWMON_ROOT));
+        match &hwmon_dir {
+            Some(dir) => info!("Fan control available via {}", dir.display()),
+            None => info!("No Steam Deck fan hwmon found; fan control unavailable"),
+        }
+        Self {
+            hwmon_dir,
+            ..Self::default()
+        }
+    }
+
+    pub fn mode(&self) -> FanMode {
+        self.mode
+    }
+
+    pub fn set_mode(&mut self, mode: FanMode) -> Result<(), String> {
+        let dir = self.hwmon_dir.as_deref()
+            .ok_or("Fan control is not available on this device")?;
+
+        match mode {
+            FanMode::Auto => {
+                // steamdeck_hwmon hands control back to the EC via `recalculate`;
+                // other drivers use the standard pwm enable attribute
+                if dir.join("recalculate").exists() {
+                    write_attr(dir, "recalculate", "1")?;
+                } else {
+                    write_attr(dir, "pwm1_enable", "2")?;
+                }
+            }
+            FanMode::Manual(rpm) => {
+                if rpm > MAX_FAN_RPM {
+                    return Err(format!("Fan speed must be at most {} RPM", MAX_FAN_RPM));
+                }
+                if dir.join("recalculate").exists() {
+                    write_attr(dir, "recalculate", "0")?;
+                } else if dir.join("pwm1_enable").exists() {
+                    write_attr(dir, "pwm1_enable", "1")?;
+                }
+                write_attr(dir, "fan1_target", &rpm.to_string())?;
+            }
+        }
+
+        info!("Fan mode set to {:?}", mode);
+        self.mode = mode;
+        Ok(())
+    }
+
+    pub fn read_rpm(&self) -> Option<u32> {
+        let dir = self.hwmon_dir.as_deref()?;
+        std::fs::read_to_string(dir.join("fan1_input"))
+            .ok()?
+            .trim()
+            .parse()
+            .ok()
+    }
+
+    /// Reads the fan once per interval, returning a change when the speed moved by more
+    /// than the threshold since the last reported value.
+    pub fn poll_change(&mut self) -> Option<FanSpeedChanged> {
+        self.hwmon_dir.as_ref()?;
+        let now = Instant::now();
+        if self.last_check.is_some_and(|last| now.duration_since(last) < FAN_POLL_INTERVAL) {
+            return None;
+        }
+        self.last_check = Some(now);
+
+        let rpm = self.read_rpm()?;
+        let previous_rpm = self.last_reported_rpm;
+        if previous_rpm.is_some_and(|previous| previous.abs_diff(rpm) <= FAN_SPEED_CHANGE_THRESHOLD_RPM) {
+            return None;
+        }
+        self.last_reported_rpm = Some(rpm);
+        Some(FanSpeedChanged { rpm, previous_rpm })
+    }
+}
+
+fn find_hwmon_dir(root: &Path) -> Option<PathBuf> {
+    let entries = std::fs::read_dir(root).ok()?;
+    entries
+        .flatten()
+        .map(|entry| entry.path())
+        .filter(|path| path.file_name().is_some_and(|name| name.to_string_lossy().starts_with("hwmon")))
+        .find(|path| {
+            std::fs::read_to_string(path.join("name"))
+                .is_ok_and(|name| HWMON_NAMES.iter().any(|wanted| name.contains(wanted)))
+        })
+}
+
+fn write_attr(dir: &Path, attr: &str, value: &str) -> Result<(), String> {
+    let path = dir.join(attr);
+    std::fs::write(&path, value).map_err(|e| {
+        warn!("Failed to write {} to {}: {}", value, path.display(), e);
+        format!("Failed to write {}: {}", path.display(), e)
+    })
+}
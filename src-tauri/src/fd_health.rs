@@ -0,0 +1,77 @@
+use evdev::Device;
+use nix::poll::{poll, PollFd, PollFlags, PollTimeout};
+use std::collections::HashMap;
+use std::os::fd::{AsRawFd, BorrowedFd};
+use std::time::{Duration, Instant};
+use tracing::warn;
+
+pub const HEALTH_CHECK_INTERVAL: Duration = Duration::from_secs(1);
+
+/// An open device whose descriptor reported hangup, error or invalid.
+#[derive(Debug, Clone)]
+pub struct UnhealthyFd {
+    pub device_path: String,
+    pub reason: String,
+}
+
+/// Periodically polls the open evdev descriptors (without blocking) for
+/// `POLLHUP | POLLERR | POLLNVAL`, which is how an unplugged or revoked device shows up.
+pub struct FdHealthMonitor {
+    interval: Duration,
+    last_check: Option<Instant>,
+}
+
+impl Default for FdHealthMonitor {
+    fn default() -> Self {
+        Self {
+            interval: HEALTH_CHECK_INTERVAL,
+            last_check: None,
+        }
+    }
+}
+
+impl FdHealthMonitor {
+    /// True once per interval; the caller runs its checks when this returns true.
+    pub fn due(&mut self) -> bool {
+        let now = Instant::now();
+        if self.last_check.is_some_and(|last| now.duration_since(last) < self.interval) {
+            return false;
+        }
+        self.last_check = Some(now);
+        true
+    }
+
+    pub fn check(&self, devices: &HashMap<String, Device>) -> Vec<UnhealthyFd> {
+        if devices.is_empty() {
+            return Vec::new();
+        }
+
+        let paths: Vec<&String> = devices.keys().collect();
+        let mut fds: Vec<PollFd> = paths.iter()
+            .map(|path| {
+                // SAFETY: the descriptor belongs to a Device in `devices`, which outlives `fds`
+                let fd = unsafe { BorrowedFd::borrow_raw(devices[*path].as_raw_fd()) };
+                PollFd::new(fd, PollFlags::empty())
+            })
+            .collect();
+
+        // POLLHUP/POLLERR/POLLNVAL are always reported, even when not requested
+        if let Err(e) = poll(&mut fds, PollTimeout::ZERO) {
+            // A failed poll (e.g. EINTR) says nothing about the devices; try again next interval
+            warn!("Evdev health poll failed: {}", e);
+            return Vec::new();
+        }
+
+        let bad = PollFlags::POLLHUP | PollFlags::POLLERR | PollFlags::POLLNVAL;
+        paths.iter()
+            .zip(&fds)
+            .filter_map(|(path, fd)| {
+                let revents = fd.revents().unwrap_or(PollFlags::empty()) & bad;
+                (!revents.is_empty()).then(|| UnhealthyFd {
+                    device_path: path.to_string(),
+                    reason: format!("{:?}", revents),
+                })
+            })
+            .collect()
+    }
+}
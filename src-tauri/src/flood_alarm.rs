@@ -0,0 +1,193 @@
+use crate::memory_stats::{deque_bytes, BufferStats};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, VecDeque};
+
+pub const DEFAULT_MAX_EVENTS_PER_SECOND: u32 = 1000;
+pub const DEFAULT_WINDOW_MS: u64 = 1000;
+pub const MIN_WINDOW_MS: u64 = 100;
+pub const MAX_WINDOW_MS: u64 = 60_000;
+
+/// What happens when a controller exceeds the event rate.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FloodAction {
+    /// Only log a warning
+    #[default]
+    Log,
+    /// Emit `event-flood-detected` and otherwise keep going
+    EmitEvent,
+    /// Drop all further input from the controller until it's reset
+    DisableController,
+    /// Keep tracking the controller's state but stop emitting and forwarding its events
+    /// until it's reset
+    DisableEmission,
+}
+
+/// What the pipeline should do with an event from a controller.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FloodGate {
+    Pass,
+    DropInput,
+    SkipEmit,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FloodDetected {
+    pub controller_id: usize,
+    pub events_per_second: f64,
+    pub action: FloodAction,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ControllerFloodStatus {
+    pub controller_id: usize,
+    pub events_per_second: f64,
+    pub tripped: bool,
+    pub tripped_at: Option<u64>,
+    /// Whether a disabling action is still in effect
+    pub disabled: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FloodAlarmStatus {
+    pub max_events_per_second: u32,
+    pub window_ms: u64,
+    pub action: FloodAction,
+    pub controllers: Vec<ControllerFloodStatus>,
+}
+
+#[derive(Default)]
+struct ControllerFloodState {
+    /// Event times within the window, in milliseconds
+    events: VecDeque<u64>,
+    tripped_at: Option<u64>,
+    /// The action taken when tripped, if it disables the controller
+    disabled_by: Option<FloodAction>,
+}
+
+/// Per-controller sliding-window event rate, tripping an action when a controller
+/// (typically a stuck button or a noisy axis) sends more than `max_events_per_second`.
+pub struct EventFloodAlarm {
+    pub max_events_per_second: u32,
+    pub window_ms: u64,
+    pub action: FloodAction,
+    controllers: HashMap<usize, ControllerFloodState>,
+}
+
+impl Default for EventFloodAlarm {
+    fn default() -> Self {
+        Self {
+            max_events_per_second: DEFAULT_MAX_EVENTS_PER_SECOND,
+            window_ms: DEFAULT_WINDOW_MS,
+            action: FloodAction::default(),
+            controllers: HashMap::new(),
+        }
+    }
+}
+
+impl EventFloodAlarm {
+    /// Replaces the limits and action. Controllers already disabled stay disabled.
+    pub fn configure(&mut self, max_events_per_second: u32, window_ms: u64, action: FloodAction) -> Result<(), String> {
+        if max_events_per_second == 0 {
+            return Err("max_events_per_second must be greater than 0".to_string());
+        }
+        if !(MIN_WINDOW_MS..=MAX_WINDOW_MS).contains(&window_ms) {
+            return Err(format!("window_ms must be between {} and {}", MIN_WINDOW_MS, MAX_WINDOW_MS));
+        }
+        self.max_events_per_second = max_events_per_second;
+        self.window_ms = window_ms;
+        self.action = action;
+        Ok(())
+    }
+
+    /// Counts an event at `now_ms`, returning a detection when this event pushed the
+    /// controller over the limit. A tripped alarm re-arms once the rate drops back under.
+    pub fn record(&mut self, controller_id: usize, now_ms: u64) -> Option<FloodDetected> {
+        let window_ms = self.window_ms;
+        let cap = self.window_capacity();
+        let state = self.controllers.entry(controller_id).or_default();
+        if state.events.len() >= cap {
+            state.events.pop_front();
+        }
+        state.events.push_back(now_ms);
+        while state.events.front().is_some_and(|&at| now_ms.saturating_sub(at) >= window_ms) {
+            state.events.pop_front();
+        }
+
+        let events_per_second = state.events.len() as f64 * 1000.0 / window_ms as f64;
+        let over = events_per_second > self.max_events_per_second as f64;
+        if !over {
+            if state.disabled_by.is_none() {
+                state.tripped_at = None;
+            }
+            return None;
+        }
+        if state.tripped_at.is_some() {
+            return None;
+        }
+
+        state.tripped_at = Some(now_ms);
+        if matches!(self.action, FloodAction::DisableController | FloodAction::DisableEmission) {
+            state.disabled_by = Some(self.action);
+        }
+        Some(FloodDetected {
+            controller_id,
+            events_per_second,
+            action: self.action,
+        })
+    }
+
+    /// Event times kept per controller: one more than the limit allows in a window, which
+    /// is all it takes to tell a flood, so a flood can't grow the window any further.
+    fn window_capacity(&self) -> usize {
+        (self.max_events_per_second as u64 * self.window_ms / 1000) as usize + 1
+    }
+
+    pub fn buffer_stats(&self) -> BufferStats {
+        let len = self.controllers.values().map(|state| state.events.len()).sum();
+        let approx_bytes = self.controllers.values().map(|state| deque_bytes(&state.events)).sum();
+        BufferStats::new("flood_alarm_windows", len, Some(self.window_capacity()), self.controllers.len(), approx_bytes)
+    }
+
+    pub fn gate(&self, controller_id: usize) -> FloodGate {
+        match self.controllers.get(&controller_id).and_then(|state| state.disabled_by) {
+            Some(FloodAction::DisableController) => FloodGate::DropInput,
+            Some(FloodAction::DisableEmission) => FloodGate::SkipEmit,
+            _ => FloodGate::Pass,
+        }
+    }
+
+    /// Clears the controller's window and re-enables it. Returns false if it wasn't tracked.
+    pub fn reset(&mut self, controller_id: usize) -> bool {
+        self.controllers.remove(&controller_id).is_some()
+    }
+
+    pub fn controller_disconnected(&mut self, controller_id: usize) {
+        self.controllers.remove(&controller_id);
+    }
+
+    pub fn status(&self, now_ms: u64) -> FloodAlarmStatus {
+        let mut controllers: Vec<ControllerFloodStatus> = self.controllers.iter()
+            .map(|(&controller_id, state)| {
+                let recent = state.events.iter()
+                    .filter(|&&at| now_ms.saturating_sub(at) < self.window_ms)
+                    .count();
+                ControllerFloodStatus {
+                    controller_id,
+                    events_per_second: recent as f64 * 1000.0 / self.window_ms as f64,
+                    tripped: state.tripped_at.is_some(),
+                    tripped_at: state.tripped_at,
+                    disabled: state.disabled_by.is_some(),
+                }
+            })
+            .collect();
+        controllers.sort_by_key(|controller| controller.controller_id);
+
+        FloodAlarmStatus {
+            max_events_per_second: self.max_events_per_second,
+            window_ms: self.window_ms,
+            action: self.action,
+            controllers,
+        }
+    }
+}
@@ -0,0 +1,151 @@
+use crate::app_state::AppState;
+use crate::background::{self, BackgroundModeStatus};
+use crate::emit_interest;
+use crate::error_counters::{self, ErrorCategory};
+use crate::evdev_types::EvdevGamepadInfo;
+use crate::locking::MutexExt;
+use crate::logging;
+use crate::processing_pause::{self, PauseMode};
+use crate::session_display::{self, DisplayStatus};
+use crate::settings::Settings;
+use crate::state_revisions::ControllerStatesDelta;
+use crate::update_status::UpdateStatus;
+use serde::{Deserialize, Serialize};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::OnceLock;
+use std::time::Duration;
+use tauri::{AppHandle, Emitter, Manager};
+use tracing::{info, warn};
+
+/// Emitted with an increasing sequence number; the frontend answers with
+/// `frontend_heartbeat_ack`.
+pub const FRONTEND_HEARTBEAT: &str = "frontend-heartbeat";
+pub const HEARTBEAT_INTERVAL: Duration = Duration::from_secs(5);
+/// Heartbeats that go unanswered before the webview counts as no longer listening.
+const MISSED_HEARTBEATS: u64 = 3;
+
+/// Everything the frontend shows that would otherwise only arrive as events, sent in answer
+/// to `frontend_hello` so a reloaded webview starts from current state.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FrontendSnapshot {
+    pub controllers: ControllerStatesDelta,
+    pub evdev_devices: Vec<EvdevGamepadInfo>,
+    pub settings: Settings,
+    pub processing_paused: Option<PauseMode>,
+    pub background_mode: BackgroundModeStatus,
+    pub display: DisplayStatus,
+    pub update: UpdateStatus,
+    pub app_version: String,
+    pub log_file_path: Option<String>,
+    /// 1 for the first page load, higher after reloads
+    pub hello_count: u64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ListeningChange {
+    Stopped { unanswered: u64 },
+    Resumed,
+}
+
+/// Whether the webview is still answering heartbeats. Nothing is known until its first
+/// `frontend_hello`, so a slow first load isn't reported.
+pub struct WebviewLiveness {
+    sent: AtomicU64,
+    acked: AtomicU64,
+    listening: AtomicBool,
+    hellos: AtomicU64,
+}
+
+impl WebviewLiveness {
+    fn new() -> Self {
+        Self {
+            sent: AtomicU64::new(0),
+            acked: AtomicU64::new(0),
+            listening: AtomicBool::new(false),
+            hellos: AtomicU64::new(0),
+        }
+    }
+
+    /// Returns how many hellos there have been, this one included.
+    pub fn hello(&self) -> u64 {
+        self.acked.fetch_max(self.sent.load(Ordering::SeqCst), Ordering::SeqCst);
+        self.listening.store(true, Ordering::SeqCst);
+        self.hellos.fetch_add(1, Ordering::SeqCst) + 1
+    }
+
+    pub fn ack(&self, sequence: u64) {
+        self.acked.fetch_max(sequence, Ordering::SeqCst);
+    }
+
+    pub fn next_heartbeat(&self) -> u64 {
+        self.sent.fetch_add(1, Ordering::SeqCst) + 1
+    }
+
+    /// Reports when the webview stops answering and when it answers again.
+    pub fn check(&self) -> Option<ListeningChange> {
+        if self.hellos.load(Ordering::SeqCst) == 0 {
+            return None;
+        }
+        let unanswered = self.sent.load(Ordering::SeqCst).saturating_sub(self.acked.load(Ordering::SeqCst));
+        if unanswered > MISSED_HEARTBEATS {
+            self.listening.swap(false, Ordering::SeqCst).then_some(ListeningChange::Stopped { unanswered })
+        } else if unanswered <= 1 {
+            (!self.listening.swap(true, Ordering::SeqCst)).then_some(ListeningChange::Resumed)
+        } else {
+            None
+        }
+    }
+}
+
+static WEBVIEW_LIVENESS: OnceLock<WebviewLiveness> = OnceLock::new();
+
+pub fn liveness() -> &'static WebviewLiveness {
+    WEBVIEW_LIVENESS.get_or_init(WebviewLiveness::new)
+}
+
+/// Answers a page load: subscriptions left by a previous page are dropped and the current
+/// state comes back in one piece.
+pub fn hello(app: &AppHandle) -> FrontendSnapshot {
+    let hello_count = liveness().hello();
+    emit_interest::interest().reset_subscriptions();
+    if hello_count > 1 {
+        info!("Frontend reloaded (load {}); sending it a full snapshot", hello_count);
+    }
+    let state = app.state::<AppState>();
+    let update = state.update_status.lock_or_recover().clone();
+    FrontendSnapshot {
+        controllers: state.gamepad_manager.get_controller_states_delta(0),
+        evdev_devices: state.evdev_manager.get_detected_devices(),
+        settings: state.settings.get(),
+        processing_paused: processing_pause::pause_mode(),
+        background_mode: background::status(app),
+        display: session_display::status(app),
+        update,
+        app_version: app.package_info().version.to_string(),
+        log_file_path: logging::log_file_path().map(|path| path.to_string_lossy().to_string()),
+        hello_count,
+    }
+}
+
+/// Emits `frontend-heartbeat` and logs when the webview stops answering, which means its
+/// listeners are gone and events go nowhere until it loads again.
+pub fn spawn_heartbeat(app: AppHandle) {
+    let shutdown = app.state::<AppState>().shutdown.clone();
+    std::thread::spawn(move || {
+        while !shutdown.is_requested() {
+            std::thread::sleep(HEARTBEAT_INTERVAL);
+            let liveness = liveness();
+            match liveness.check() {
+                Some(ListeningChange::Stopped { unanswered }) => {
+                    warn!("The webview hasn't answered {} heartbeats; events aren't reaching it", unanswered);
+                }
+                Some(ListeningChange::Resumed) => info!("The webview is answering heartbeats again"),
+                None => {}
+            }
+            let sequence = liveness.next_heartbeat();
+            if let Err(e) = app.emit(FRONTEND_HEARTBEAT, sequence) {
+                error_counters::errors().record(ErrorCategory::Emit, format!("{}: {}", FRONTEND_HEARTBEAT, e));
+            }
+        }
+    });
+}
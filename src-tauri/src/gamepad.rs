@@ -1,12 +1,51 @@
-use gilrs::{Axis, Button, Event, EventType, Gilrs};
+use crate::axis_snapshots::{AxisSnapshot, AxisSnapshotRecorder};
+use crate::combo_effects::{ComboTracker, CombinationLightEffect};
+use crate::controller_event::{self, UnifiedControllerEvent};
+use crate::dead_zone::{DeadZoneShape, StickDeadZones, StickSide};
+use crate::emit_interest::EventChannel;
+use crate::emitter::EventEmitter;
+use crate::ensemble::{EnsembleConfig, Ensembles};
+use crate::error_counters::{self, ErrorCategory};
+use crate::evdev_types::EvdevGamepadInfo;
+use crate::event_batcher::EventBatcher;
+use crate::flood_alarm::{EventFloodAlarm, FloodAction, FloodAlarmStatus, FloodGate};
+use crate::ghost_filter::GhostInputFilter;
+use crate::health::PollSource;
+use crate::hidraw;
+use crate::input_backend::{BackendControllerState, BackendDebugInfo, InputBackend};
+use crate::input_pipeline::{InputMessage, InputSender};
+use crate::latency_benchmark::{self, Stage};
+use crate::light_server::LightServer;
+use crate::locking::MutexExt;
+use crate::memory_stats::BufferStats;
+use crate::permissions::PermissionsAnalysis;
+use crate::pipeline_metrics::{PipelineCounter, PipelineMetrics, PipelineMetricsStore};
+use crate::poll_cadence;
+use crate::processing_pause::{self, PauseMode};
+use crate::recording::{InputSession, RecordedInput};
+use crate::runtime_metrics::{self, Counter, Timing};
+use crate::session_summary::SessionInputStats;
+use crate::settings::SettingsManager;
+use crate::shutdown::FLUSH_TIMEOUT;
+use crate::state_revisions::{ControllerStatesDelta, StateRevisions, StateSnapshot, MAX_REMOVED_CONTROLLERS};
+use crate::thread_priority;
+use crate::time_series::AxisTimeSeriesStore;
+use crate::touch_zones;
+use crate::unhandled_events::{UnhandledEventKind, UnhandledEvents};
+use crate::virtual_pads;
+use crate::window_chord::{ChordTracker, ShowWindowChord};
+use arc_swap::ArcSwap;
+use gilrs::ff::{BaseEffect, BaseEffectType, Effect, EffectBuilder, Repeat, Replay, Ticks};
+use gilrs::{Event, EventType, Gilrs, PowerInfo};
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
-use std::os::unix::fs::PermissionsExt;
-use std::sync::{Arc, Mutex};
-use std::time::{SystemTime, UNIX_EPOCH};
-use tauri::{AppHandle, Emitter};
+use std::collections::{HashMap, VecDeque};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{mpsc, Arc, Mutex, OnceLock};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use tauri::AppHandle;
+use tracing::{debug, info, trace, warn};
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct ControllerState {
     pub buttons: HashMap<String, bool>,
     pub axes: HashMap<String, f32>,
@@ -22,6 +61,43 @@ pub struct ControllerEvent {
     pub axis: Option<String>,
     pub value: Option<f32>,
     pub timestamp: u64,
+    /// True for events injected by a replay or mock rather than read from a device
+    #[serde(default)]
+    pub synthetic: bool,
+    /// When the device reported the input, for latency measurement. Live gilrs input only.
+    #[serde(skip)]
+    pub source_time: Option<SystemTime>,
+}
+
+impl ControllerEvent {
+    /// The pipeline input this event describes. Besides the emitted event types this
+    /// accepts `connected` / `disconnected`, so scripted events can describe hotplug.
+    #[cfg(feature = "mock")]
+    pub fn to_input(&self) -> Option<GamepadInput> {
+        match self.event_type.as_str() {
+            "connected" => Some(GamepadInput::Connected),
+            "disconnected" => Some(GamepadInput::Disconnected),
+            "button-pressed" => Some(GamepadInput::ButtonPressed { button: self.button.clone()? }),
+            "button-released" => Some(GamepadInput::ButtonReleased { button: self.button.clone()? }),
+            "axis-changed" => Some(GamepadInput::AxisChanged {
+                axis: self.axis.clone()?,
+                value: self.value?,
+            }),
+            _ => None,
+        }
+    }
+}
+
+/// A gilrs event reduced to what the input pipeline acts on, so live and replayed
+/// input take the same path.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum GamepadInput {
+    Connected,
+    Disconnected,
+    ButtonPressed { button: String },
+    ButtonReleased { button: String },
+    AxisChanged { axis: String, value: f32 },
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -32,6 +108,26 @@ pub struct DebugInfo {
     pub input_devices: Vec<String>,
     pub permissions_check: String,
     pub last_event_time: Option<u64>,
+    /// gilrs event kinds the pipeline ignores, most frequent first
+    pub unhandled_events: Vec<UnhandledEventKind>,
+    /// Unhandled events not broken down because too many kinds were already tracked
+    pub unhandled_events_overflowed: u64,
+    /// How input processing is paused, if it is
+    pub processing_paused: Option<PauseMode>,
+}
+
+impl DebugInfo {
+    /// Call after `tag_virtual_pads`, which fills in the capability hashes overrides are keyed by.
+    pub fn apply_layout_overrides(&mut self, overrides: &HashMap<String, ControllerLayout>) {
+        for gamepad in &mut self.connected_gamepads {
+            gamepad.layout_override = gamepad.saved_layout(overrides);
+        }
+    }
+
+    /// Tells Steam virtual pads apart from physical controllers using the evdev nodes.
+    pub fn tag_virtual_pads(&mut self, evdev_devices: &[EvdevGamepadInfo]) {
+        virtual_pads::tag_gamepads(&mut self.connected_gamepads, evdev_devices);
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -40,237 +136,1292 @@ pub struct GamepadInfo {
     pub name: String,
     pub is_connected: bool,
     pub power_info: String,
+    pub uuid: String,
+    pub vendor_id: Option<u16>,
+    pub product_id: Option<u16>,
+    pub mapping_name: Option<String>,
+    pub layout: ControllerLayout,
+    /// Set from settings by the command layer when the user has corrected the inferred layout
+    pub layout_override: Option<ControllerLayout>,
+    /// Whether the gamepad supports force feedback (rumble)
+    pub ff_supported: bool,
+    /// A Steam virtual pad rather than a physical controller; set by the command layer,
+    /// which also sees the evdev nodes
+    pub is_virtual: bool,
+    /// Name of the physical controller a virtual pad mirrors, when it can be told
+    pub physical_source: Option<String>,
+    /// Capability hash of the pad's evdev node, set alongside `is_virtual`
+    pub capability_hash: Option<String>,
+}
+
+impl GamepadInfo {
+    /// The user's layout for this pad: by capability hash, falling back to the gilrs UUID
+    /// for pads without a known evdev node or renamed since the override was saved.
+    pub fn saved_layout(&self, overrides: &HashMap<String, ControllerLayout>) -> Option<ControllerLayout> {
+        self.capability_hash.as_ref()
+            .and_then(|hash| overrides.get(hash))
+            .or_else(|| overrides.get(&self.uuid))
+            .copied()
+    }
+}
+
+/// Face button arrangement, used by the frontend to pick button labels.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ControllerLayout {
+    Xbox,
+    Playstation,
+    Nintendo,
+    SteamDeck,
+    Unknown,
 }
 
+/// Same as the button's `Debug` name, without formatting a new string per event.
+fn button_name(button: gilrs::Button) -> &'static str {
+    use gilrs::Button;
+    match button {
+        Button::South => "South",
+        Button::East => "East",
+        Button::North => "North",
+        Button::West => "West",
+        Button::C => "C",
+        Button::Z => "Z",
+        Button::LeftTrigger => "LeftTrigger",
+        Button::LeftTrigger2 => "LeftTrigger2",
+        Button::RightTrigger => "RightTrigger",
+        Button::RightTrigger2 => "RightTrigger2",
+        Button::Select => "Select",
+        Button::Start => "Start",
+        Button::Mode => "Mode",
+        Button::LeftThumb => "LeftThumb",
+        Button::RightThumb => "RightThumb",
+        Button::DPadUp => "DPadUp",
+        Button::DPadDown => "DPadDown",
+        Button::DPadLeft => "DPadLeft",
+        Button::DPadRight => "DPadRight",
+        Button::Unknown => "Unknown",
+    }
+}
+
+/// Gilrs has no button for the Steam Deck's back grips, so they arrive as `Unknown` with
+/// the evdev code in the low 16 bits of `code`.
+#[cfg(target_os = "linux")]
+fn gilrs_button_name(button: gilrs::Button, code: gilrs::ev::Code) -> &'static str {
+    match button {
+        gilrs::Button::Unknown => crate::key_codes::back_grip_name(code.into_u32() as u16).unwrap_or("Unknown"),
+        button => button_name(button),
+    }
+}
+
+#[cfg(not(target_os = "linux"))]
+fn gilrs_button_name(button: gilrs::Button, _code: gilrs::ev::Code) -> &'static str {
+    button_name(button)
+}
+
+/// Same as the axis's `Debug` name, without formatting a new string per event.
+fn axis_name(axis: gilrs::Axis) -> &'static str {
+    use gilrs::Axis;
+    match axis {
+        Axis::LeftStickX => "LeftStickX",
+        Axis::LeftStickY => "LeftStickY",
+        Axis::LeftZ => "LeftZ",
+        Axis::RightStickX => "RightStickX",
+        Axis::RightStickY => "RightStickY",
+        Axis::RightZ => "RightZ",
+        Axis::DPadX => "DPadX",
+        Axis::DPadY => "DPadY",
+        Axis::Unknown => "Unknown",
+    }
+}
+
+/// Guesses the layout from the USB vendor first, falling back to the device and mapping names.
+pub fn infer_layout(name: &str, mapping_name: Option<&str>, vendor_id: Option<u16>) -> ControllerLayout {
+    match vendor_id {
+        Some(0x045e) => return ControllerLayout::Xbox,
+        Some(0x054c) => return ControllerLayout::Playstation,
+        Some(0x057e) => return ControllerLayout::Nintendo,
+        Some(0x28de) => return ControllerLayout::SteamDeck,
+        _ => {}
+    }
+    
+    let haystack = format!("{} {}", name, mapping_name.unwrap_or("")).to_lowercase();
+    if ["steam deck", "neptune", "valve"].iter().any(|s| haystack.contains(s)) {
+        ControllerLayout::SteamDeck
+    } else if ["xbox", "x-box", "xinput"].iter().any(|s| haystack.contains(s)) {
+        ControllerLayout::Xbox
+    } else if ["playstation", "dualshock", "dualsense", "ps3", "ps4", "ps5", "sony"].iter().any(|s| haystack.contains(s)) {
+        ControllerLayout::Playstation
+    } else if ["nintendo", "switch", "pro controller", "joy-con"].iter().any(|s| haystack.contains(s)) {
+        ControllerLayout::Nintendo
+    } else {
+        ControllerLayout::Unknown
+    }
+}
+
+/// Reads the gamepads gilrs currently sees.
+fn read_gamepad_infos(gilrs: &Gilrs) -> Vec<GamepadInfo> {
+    gilrs.gamepads()
+        .map(|(id, gamepad)| {
+            let mapping_name = gamepad.map_name().map(|name| name.to_string());
+            GamepadInfo {
+                id: usize::from(id),
+                name: gamepad.name().to_string(),
+                is_connected: gamepad.is_connected(),
+                power_info: format!("{:?}", gamepad.power_info()),
+                uuid: uuid_string(gamepad.uuid()),
+                vendor_id: gamepad.vendor_id(),
+                product_id: gamepad.product_id(),
+                layout: infer_layout(gamepad.name(), mapping_name.as_deref(), gamepad.vendor_id()),
+                mapping_name,
+                layout_override: None,
+                ff_supported: gamepad.is_ff_supported(),
+                // Steam's own pad is recognizable by name before the evdev nodes are compared
+                is_virtual: gamepad.name().contains("Steam Virtual"),
+                physical_source: None,
+                capability_hash: None,
+            }
+        })
+        .collect()
+}
+
+fn uuid_string(uuid: [u8; 16]) -> String {
+    uuid.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+/// Every event and joystick node under /dev/input, sorted.
+#[cfg(target_os = "linux")]
+fn enumerate_input_devices() -> Vec<String> {
+    let mut devices = Vec::new();
+    
+    // Check /dev/input/event* devices
+    if let Ok(entries) = std::fs::read_dir("/dev/input") {
+        for entry in entries.flatten() {
+            let path = entry.path();
+            if let Some(name) = path.file_name() {
+                if let Some(name_str) = name.to_str() {
+                    if name_str.starts_with("event") || name_str.starts_with("js") {
+                        devices.push(format!("/dev/input/{}", name_str));
+                    }
+                }
+            }
+        }
+    }
+    
+    devices.sort();
+    devices
+}
+
+#[cfg(not(target_os = "linux"))]
+fn enumerate_input_devices() -> Vec<String> {
+    Vec::new()
+}
+
+/// Number of recent input events kept for debug reports.
+const RECENT_EVENTS_CAPACITY: usize = 200;
+const BATTERY_SAMPLE_INTERVAL: Duration = Duration::from_secs(10);
+/// Longest rumble a single request may ask for.
+pub const MAX_RUMBLE_MS: u32 = 5000;
+/// How often the gamepad list commands read is refreshed, besides on connection changes.
+const GAMEPAD_INFO_REFRESH_INTERVAL: Duration = Duration::from_secs(1);
+
 pub struct GamepadManager {
     gilrs: Arc<Mutex<Gilrs>>,
+    /// False when gilrs fell back to its dummy backend
+    gilrs_available: AtomicBool,
+    /// Only touched by the input dispatcher; everything else reads `state_snapshot`
     states: Arc<Mutex<HashMap<usize, ControllerState>>>,
+    revisions: Mutex<StateRevisions>,
+    state_snapshot: ArcSwap<StateSnapshot>,
+    /// Refreshed by the poller, so commands never wait on the gilrs lock
+    gamepad_info_snapshot: ArcSwap<Vec<GamepadInfo>>,
+    gamepad_info_refreshed_at: Mutex<Instant>,
+    input_tx: InputSender,
     last_event_time: Arc<Mutex<Option<u64>>>,
+    combo_tracker: Arc<Mutex<ComboTracker>>,
+    ghost_filter: Arc<Mutex<GhostInputFilter>>,
+    dead_zones: Mutex<StickDeadZones>,
+    pipeline_metrics: Mutex<PipelineMetricsStore>,
+    flood_alarm: Arc<Mutex<EventFloodAlarm>>,
+    ensembles: Arc<Mutex<Ensembles>>,
+    time_series: Arc<Mutex<AxisTimeSeriesStore>>,
+    axis_snapshots: Mutex<AxisSnapshotRecorder>,
+    settings: Arc<SettingsManager>,
+    light_server: Arc<LightServer>,
+    batcher: Arc<Mutex<EventBatcher>>,
+    session: Arc<InputSession>,
+    recent_events: Arc<Mutex<VecDeque<ControllerEvent>>>,
+    session_stats: Mutex<SessionInputStats>,
+    battery_sampled_at: Mutex<Option<Instant>>,
+    unhandled_events: Mutex<UnhandledEvents>,
+    /// Last rumble per controller; dropping an effect stops it, so it's kept until replaced
+    rumble_effects: Mutex<HashMap<usize, Effect>>,
+    window_chord: Mutex<ChordTracker>,
+    on_window_chord: OnceLock<Box<dyn Fn() + Send + Sync>>,
 }
 
 impl GamepadManager {
-    pub fn new() -> Result<Self, String> {
-        println!("🎮 Initializing GamepadManager...");
-        let gilrs = Gilrs::new().map_err(|e| format!("Failed to initialize gamepad: {}", e))?;
+    pub fn new(
+        settings: Arc<SettingsManager>,
+        light_server: Arc<LightServer>,
+        batcher: Arc<Mutex<EventBatcher>>,
+        session: Arc<InputSession>,
+        input_tx: InputSender,
+    ) -> Result<Self, String> {
+        info!("Initializing GamepadManager");
+        let mut gilrs_available = true;
+        let gilrs = match Gilrs::new() {
+            Ok(gilrs) => gilrs,
+            // No usable backend (e.g. headless CI); gilrs hands back an instance that never has gamepads
+            Err(gilrs::Error::NotImplemented(dummy)) => {
+                warn!("Gamepad backend not available on this platform, continuing without gilrs input");
+                gilrs_available = false;
+                dummy
+            }
+            Err(e) => return Err(format!("Failed to initialize gamepad: {}", e)),
+        };
         
         // Log all available gamepads at startup
-        println!("🔍 Scanning for gamepads at startup...");
+        debug!("Scanning for gamepads at startup");
         for (id, gamepad) in gilrs.gamepads() {
-            println!("🎮 Found gamepad: ID={:?}, Name='{}', Connected={}", 
-                     id, gamepad.name(), gamepad.is_connected());
+            info!("Found gamepad: ID={:?}, Name='{}', Connected={}",
+                  id, gamepad.name(), gamepad.is_connected());
         }
         
+        let gamepad_infos = read_gamepad_infos(&gilrs);
+        let mut window_chord = ChordTracker::default();
+        window_chord.set_chord(settings.get().show_window_chord);
+        
         Ok(Self {
             gilrs: Arc::new(Mutex::new(gilrs)),
+            gilrs_available: AtomicBool::new(gilrs_available),
             states: Arc::new(Mutex::new(HashMap::new())),
+            revisions: Mutex::new(StateRevisions::default()),
+            state_snapshot: ArcSwap::from_pointee(StateSnapshot::default()),
+            gamepad_info_snapshot: ArcSwap::from_pointee(gamepad_infos),
+            gamepad_info_refreshed_at: Mutex::new(Instant::now()),
+            input_tx,
             last_event_time: Arc::new(Mutex::new(None)),
+            combo_tracker: Arc::new(Mutex::new(ComboTracker::default())),
+            ghost_filter: Arc::new(Mutex::new(GhostInputFilter::default())),
+            dead_zones: Mutex::new(StickDeadZones::default()),
+            pipeline_metrics: Mutex::new(PipelineMetricsStore::default()),
+            flood_alarm: Arc::new(Mutex::new(EventFloodAlarm::default())),
+            ensembles: Arc::new(Mutex::new(Ensembles::default())),
+            time_series: Arc::new(Mutex::new(AxisTimeSeriesStore::default())),
+            axis_snapshots: Mutex::new(AxisSnapshotRecorder::default()),
+            settings,
+            light_server,
+            batcher,
+            session,
+            recent_events: Arc::new(Mutex::new(VecDeque::with_capacity(RECENT_EVENTS_CAPACITY))),
+            session_stats: Mutex::new(SessionInputStats::default()),
+            battery_sampled_at: Mutex::new(None),
+            unhandled_events: Mutex::new(UnhandledEvents::default()),
+            rumble_effects: Mutex::new(HashMap::new()),
+            window_chord: Mutex::new(window_chord),
+            on_window_chord: OnceLock::new(),
         })
     }
     
+    /// Drains gilrs and sends the decoded inputs to the dispatcher.
     pub fn poll_events(&self, app: &AppHandle) {
-        let mut gilrs = self.gilrs.lock().unwrap();
+        let mut gilrs = runtime_metrics::metrics().lock(Timing::GilrsLockWait, &self.gilrs);
+        let mut connection_changed = false;
         
-        while let Some(Event { id, event, time: _, .. }) = gilrs.next_event() {
+        while let Some(Event { id, event, time, .. }) = gilrs.next_event() {
             let controller_id = id.into();
-            let timestamp = SystemTime::now()
-                .duration_since(UNIX_EPOCH)
-                .unwrap_or_default()
-                .as_millis() as u64;
+            self.count(controller_id, PipelineCounter::Received);
+            poll_cadence::cadence().note_activity();
             
-            // Update last event time
-            {
-                let mut last_time = self.last_event_time.lock().unwrap();
-                *last_time = Some(timestamp);
+            if let EventType::ButtonPressed(button, code) | EventType::ButtonReleased(button, code) = event {
+                let timestamp_us = time.duration_since(UNIX_EPOCH).unwrap_or_default().as_micros() as u64;
+                let button_name = gilrs_button_name(button, code);
+                if !self.ghost_filter.lock_or_recover().accept(controller_id, button_name, timestamp_us) {
+                    trace!("Suppressed ghost input: ID={:?}, Button={}", id, button_name);
+                    self.count(controller_id, PipelineCounter::DroppedDedup);
+                    continue;
+                }
             }
             
-            match event {
+            let input = match event {
                 EventType::Connected => {
-                    let gamepad_name = gilrs.gamepad(id).name().to_string();
-                    println!("🔗 Gamepad CONNECTED: ID={:?}, Name='{}', Time={}", 
-                             id, gamepad_name, timestamp);
-                    
-                    let mut states = self.states.lock().unwrap();
-                    states.insert(controller_id, ControllerState {
-                        buttons: HashMap::new(),
-                        axes: HashMap::new(),
-                        connected: true,
-                        controller_id,
-                    });
-                    
-                    app.emit("gamepad-connected", controller_id).ok();
+                    let gamepad = gilrs.gamepad(id);
+                    let name = gamepad.name();
+                    info!("Gamepad connected: ID={:?}, Name='{}'", id, name);
+                    self.session_stats.lock_or_recover().controller_connected(controller_id, name);
+                    connection_changed = true;
+                    GamepadInput::Connected
                 }
                 EventType::Disconnected => {
-                    println!("🔌 Gamepad DISCONNECTED: ID={:?}, Time={}", id, timestamp);
-                    let mut states = self.states.lock().unwrap();
-                    states.remove(&controller_id);
-                    
-                    app.emit("gamepad-disconnected", controller_id).ok();
+                    connection_changed = true;
+                    GamepadInput::Disconnected
                 }
-                EventType::ButtonPressed(button, _) => {
-                    println!("🔘 Button PRESSED: ID={:?}, Button={:?}, Time={}", 
-                             id, button, timestamp);
-                    self.update_button_state(controller_id, button, true);
-                    let event = ControllerEvent {
-                        controller_id,
-                        event_type: "button-pressed".to_string(),
-                        button: Some(format!("{:?}", button)),
-                        axis: None,
-                        value: None,
-                        timestamp,
-                    };
-                    app.emit("gamepad-input", event).ok();
-                }
-                EventType::ButtonReleased(button, _) => {
-                    println!("⚪ Button RELEASED: ID={:?}, Button={:?}, Time={}", 
-                             id, button, timestamp);
-                    self.update_button_state(controller_id, button, false);
-                    let event = ControllerEvent {
-                        controller_id,
-                        event_type: "button-released".to_string(),
-                        button: Some(format!("{:?}", button)),
-                        axis: None,
-                        value: None,
-                        timestamp,
-                    };
-                    app.emit("gamepad-input", event).ok();
+                EventType::ButtonPressed(button, code) => GamepadInput::ButtonPressed {
+                    button: gilrs_button_name(button, code).to_string(),
+                },
+                EventType::ButtonReleased(button, code) => GamepadInput::ButtonReleased {
+                    button: gilrs_button_name(button, code).to_string(),
+                },
+                EventType::AxisChanged(axis, value, _) => GamepadInput::AxisChanged {
+                    axis: axis_name(axis).to_string(),
+                    value,
+                },
+                _ => {
+                    debug!("Unhandled gilrs event: ID={:?}, Event={:?}", id, event);
+                    let now_ms = time.duration_since(UNIX_EPOCH).unwrap_or_default().as_millis() as u64;
+                    self.unhandled_events.lock_or_recover().record(controller_id, &event, now_ms);
+                    self.count(controller_id, PipelineCounter::DroppedFilter);
+                    continue;
                 }
-                EventType::AxisChanged(axis, value, _) => {
-                    // Only log significant axis changes to avoid spam
-                    if value.abs() > 0.1 {
-                        println!("🎚️ Axis CHANGED: ID={:?}, Axis={:?}, Value={:.3}, Time={}", 
-                                 id, axis, value, timestamp);
+            };
+            let inputs = match input {
+                GamepadInput::AxisChanged { axis, value } => {
+                    let changes = self.dead_zones.lock_or_recover().apply(controller_id, &axis, value);
+                    for (changed_axis, changed_value) in &changes {
+                        if *changed_axis != axis || *changed_value != value {
+                            self.count(controller_id, PipelineCounter::Transformed);
+                        }
                     }
-                    self.update_axis_state(controller_id, axis, value);
-                    let event = ControllerEvent {
-                        controller_id,
-                        event_type: "axis-changed".to_string(),
-                        button: None,
-                        axis: Some(format!("{:?}", axis)),
-                        value: Some(value),
-                        timestamp,
-                    };
-                    app.emit("gamepad-input", event).ok();
-                }
-                _ => {
-                    println!("❓ Unknown event: ID={:?}, Event={:?}, Time={}", 
-                             id, event, timestamp);
+                    changes.into_iter()
+                        .map(|(axis, value)| GamepadInput::AxisChanged { axis, value })
+                        .collect()
                 }
+                input => vec![input],
+            };
+            for input in inputs {
+                self.input_tx.send(InputMessage::Gilrs {
+                    controller_id,
+                    input,
+                    synthetic: false,
+                    source_time: Some(time),
+                });
             }
         }
+        
+        self.sample_battery(&gilrs);
+        self.refresh_gamepad_infos(&gilrs, connection_changed);
+        drop(gilrs);
+        
+        self.fire_due_combinations(app);
     }
     
-    pub fn get_controller_states(&self) -> HashMap<usize, ControllerState> {
-        self.states.lock().unwrap().clone()
+    /// Replaces the gilrs instance with a fresh one, for when it stops noticing new pads
+    /// (after a suspend, or Steam restarting). Gamepad IDs aren't stable across instances,
+    /// so every known controller is disconnected and everything the new instance finds is
+    /// connected again, through the dispatcher like live input. Returns the connected
+    /// gamepads; on failure the old instance stays in place.
+    pub fn reinit_gilrs(&self) -> Result<Vec<GamepadInfo>, String> {
+        let fresh = match Gilrs::new() {
+            Ok(gilrs) => gilrs,
+            Err(gilrs::Error::NotImplemented(_)) => {
+                return Err("No gamepad backend is available; kept the current instance".to_string());
+            }
+            Err(e) => return Err(format!("Failed to initialize gamepad backend: {}; kept the current instance", e)),
+        };
+        let gamepad_infos = read_gamepad_infos(&fresh);
+        let old = {
+            let mut gilrs = runtime_metrics::metrics().lock(Timing::GilrsLockWait, &self.gilrs);
+            *self.gamepad_info_refreshed_at.lock_or_recover() = Instant::now();
+            self.gamepad_info_snapshot.store(Arc::new(gamepad_infos.clone()));
+            std::mem::replace(&mut *gilrs, fresh)
+        };
+        // Effects belong to the old instance's force feedback thread, so they go first
+        self.rumble_effects.lock_or_recover().clear();
+        drop(old);
+        self.gilrs_available.store(true, Ordering::Relaxed);
+
+        for &controller_id in self.state_snapshot.load().states.keys() {
+            self.input_tx.send(InputMessage::Gilrs {
+                controller_id,
+                input: GamepadInput::Disconnected,
+                synthetic: false,
+                source_time: None,
+            });
+        }
+        let connected: Vec<GamepadInfo> = gamepad_infos.into_iter().filter(|info| info.is_connected).collect();
+        for info in &connected {
+            self.session_stats.lock_or_recover().controller_connected(info.id, &info.name);
+            self.input_tx.send(InputMessage::Gilrs {
+                controller_id: info.id,
+                input: GamepadInput::Connected,
+                synthetic: false,
+                source_time: None,
+            });
+        }
+        info!("Gamepad backend reinitialized; {} gamepad(s) connected", connected.len());
+        Ok(connected)
     }
     
-    pub fn get_controller_state(&self, id: usize) -> Option<ControllerState> {
-        self.states.lock().unwrap().get(&id).cloned()
+    /// Fires combinations whose hold time has elapsed, and moves the emulated mouse. Called
+    /// every polling pass rather than per event so hold_ms combos fire, and the pointer
+    /// keeps moving, while the controls are held still.
+    pub fn fire_due_combinations(&self, app: &impl EventEmitter) {
+        let due = runtime_metrics::metrics()
+            .lock(Timing::ComboTrackerLockWait, &self.combo_tracker)
+            .take_due();
+        for (controller_id, effect) in due {
+            self.fire_combination_effect(app, controller_id, effect);
+        }
+        if self.window_chord.lock_or_recover().take_due() {
+            info!("Show-window chord held");
+            if let Some(on_window_chord) = self.on_window_chord.get() {
+                on_window_chord();
+            }
+        }
+        if let Some(state) = app.app_state() {
+            state.key_emulator.tick();
+        }
+    }
+    
+    /// What holding the show-window chord does; set once at startup.
+    pub fn on_window_chord(&self, action: impl Fn() + Send + Sync + 'static) {
+        let _ = self.on_window_chord.set(Box::new(action));
+    }
+    
+    pub fn set_window_chord(&self, chord: ShowWindowChord) {
+        self.window_chord.lock_or_recover().set_chord(chord);
     }
     
-    pub fn get_debug_info(&self) -> DebugInfo {
-        let gilrs = self.gilrs.lock().unwrap();
-        let last_event_time = *self.last_event_time.lock().unwrap();
+    /// Queues an input from a source other than gilrs (mock input, replays) for the dispatcher.
+    pub fn handle_input(&self, controller_id: usize, input: GamepadInput, synthetic: bool) {
+        self.count(controller_id, PipelineCounter::Received);
+        self.input_tx.send(InputMessage::Gilrs { controller_id, input, synthetic, source_time: None });
+    }
+    
+    /// Applies one input to controller state, combos and the time series, then emits it.
+    /// Only called by the input dispatcher.
+    ///
+    /// Live input is recorded when a recording is active, and dropped while a replay has
+    /// paused live sources (connection changes still apply so state stays accurate).
+    pub fn process_input(
+        &self,
+        app: &impl EventEmitter,
+        controller_id: usize,
+        input: GamepadInput,
+        synthetic: bool,
+        source_time: Option<SystemTime>,
+    ) {
+        let is_connection_change = matches!(input, GamepadInput::Connected | GamepadInput::Disconnected);
+        let gate = if is_connection_change {
+            FloodGate::Pass
+        } else {
+            self.check_flood(app, controller_id)
+        };
+        if gate == FloodGate::DropInput {
+            self.count(controller_id, PipelineCounter::DroppedRateLimit);
+            return;
+        }
+        // Connection changes still apply so state is accurate once processing resumes
+        if processing_pause::processing_paused() && !is_connection_change {
+            self.count(controller_id, PipelineCounter::DroppedFilter);
+            return;
+        }
         
-        let mut connected_gamepads = Vec::new();
-        for (id, gamepad) in gilrs.gamepads() {
-            connected_gamepads.push(GamepadInfo {
-                id: usize::from(id),
-                name: gamepad.name().to_string(),
-                is_connected: gamepad.is_connected(),
-                power_info: format!("{:?}", gamepad.power_info()),
-            });
+        if !synthetic && !is_connection_change && app.app_state().is_some_and(|state| state.virtual_pads.gilrs_suppressed(controller_id)) {
+            self.count(controller_id, PipelineCounter::DroppedFilter);
+            return;
+        }
+        if !synthetic {
+            if self.session.live_input_paused() && !is_connection_change {
+                self.count(controller_id, PipelineCounter::DroppedFilter);
+                return;
+            }
+            if self.session.is_recording() {
+                self.session.record(RecordedInput::Gilrs {
+                    controller_id,
+                    input: input.clone(),
+                });
+            }
+        }
+        runtime_metrics::metrics().increment(Counter::GilrsEvents);
+        {
+            let mut session_stats = self.session_stats.lock_or_recover();
+            session_stats.controller_seen(controller_id);
+            match input {
+                GamepadInput::ButtonPressed { .. } => session_stats.button_presses += 1,
+                GamepadInput::Disconnected => session_stats.disconnects += 1,
+                _ => {}
+            }
+        }
+        if let Some(source_time) = source_time {
+            latency_benchmark::benchmark().record(Stage::Processed, source_time);
         }
         
-        let input_devices = self.enumerate_input_devices();
-        let permissions_check = self.check_permissions();
+        let timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_millis() as u64;
+        *self.last_event_time.lock_or_recover() = Some(timestamp);
         
-        DebugInfo {
-            gilrs_initialized: true,
-            total_gamepads: gilrs.gamepads().count(),
-            connected_gamepads,
-            input_devices,
-            permissions_check,
-            last_event_time,
+        let event = match input {
+            GamepadInput::Connected => {
+                debug!("Controller {} connected (synthetic={}), Time={}", controller_id, synthetic, timestamp);
+                self.states.lock_or_recover().insert(controller_id, ControllerState {
+                    buttons: HashMap::new(),
+                    axes: HashMap::new(),
+                    connected: true,
+                    controller_id,
+                });
+                self.revisions.lock_or_recover().changed(controller_id);
+                
+                app.emit_on(EventChannel::Devices, "gamepad-connected", controller_id).ok();
+                self.count(controller_id, PipelineCounter::Emitted);
+                self.update_ensembles(app, controller_id);
+                return;
+            }
+            GamepadInput::Disconnected => {
+                info!("Gamepad disconnected: ID={}, Time={}", controller_id, timestamp);
+                self.states.lock_or_recover().remove(&controller_id);
+                self.revisions.lock_or_recover().removed(controller_id);
+                self.combo_tracker.lock_or_recover().controller_disconnected(controller_id);
+                self.window_chord.lock_or_recover().controller_disconnected(controller_id);
+                if let Some(state) = app.app_state() {
+                    state.key_emulator.controller_disconnected(controller_id);
+                    state.midi_output.controller_disconnected(controller_id);
+                    state.opc_output.controller_disconnected(controller_id);
+                }
+                self.ghost_filter.lock_or_recover().controller_disconnected(controller_id);
+                self.dead_zones.lock_or_recover().controller_disconnected(controller_id);
+                self.flood_alarm.lock_or_recover().controller_disconnected(controller_id);
+                self.time_series.lock_or_recover().remove_controller(controller_id);
+                self.rumble_effects.lock_or_recover().remove(&controller_id);
+                
+                app.emit_on(EventChannel::Devices, "gamepad-disconnected", controller_id).ok();
+                self.count(controller_id, PipelineCounter::Emitted);
+                self.update_ensembles(app, controller_id);
+                return;
+            }
+            GamepadInput::ButtonPressed { button } => {
+                debug!("Button pressed: ID={}, Button={}, Time={}",
+                       controller_id, button, timestamp);
+                self.update_button_state(app, controller_id, &button, true);
+                ControllerEvent {
+                    controller_id,
+                    event_type: "button-pressed".to_string(),
+                    button: Some(button),
+                    axis: None,
+                    value: None,
+                    timestamp,
+                    synthetic,
+                    source_time,
+                }
+            }
+            GamepadInput::ButtonReleased { button } => {
+                debug!("Button released: ID={}, Button={}, Time={}",
+                       controller_id, button, timestamp);
+                self.update_button_state(app, controller_id, &button, false);
+                ControllerEvent {
+                    controller_id,
+                    event_type: "button-released".to_string(),
+                    button: Some(button),
+                    axis: None,
+                    value: None,
+                    timestamp,
+                    synthetic,
+                    source_time,
+                }
+            }
+            GamepadInput::AxisChanged { axis, value } => {
+                trace!("Axis changed: ID={}, Axis={}, Value={:.3}, Time={}",
+                       controller_id, axis, value, timestamp);
+                self.update_axis_state(app, controller_id, &axis, value, timestamp);
+                ControllerEvent {
+                    controller_id,
+                    event_type: "axis-changed".to_string(),
+                    button: None,
+                    axis: Some(axis),
+                    value: Some(value),
+                    timestamp,
+                    synthetic,
+                    source_time,
+                }
+            }
+        };
+        self.update_ensembles(app, controller_id);
+        if gate == FloodGate::Pass {
+            self.count(controller_id, PipelineCounter::Emitted);
+            self.emit_input(app, event);
+        } else {
+            self.count(controller_id, PipelineCounter::DroppedRateLimit);
         }
     }
     
-    fn enumerate_input_devices(&self) -> Vec<String> {
-        let mut devices = Vec::new();
-        
-        // Check /dev/input/event* devices
-        if let Ok(entries) = std::fs::read_dir("/dev/input") {
-            for entry in entries.flatten() {
-                let path = entry.path();
-                if let Some(name) = path.file_name() {
-                    if let Some(name_str) = name.to_str() {
-                        if name_str.starts_with("event") || name_str.starts_with("js") {
-                            devices.push(format!("/dev/input/{}", name_str));
-                        }
-                    }
-                }
+    fn count(&self, controller_id: usize, counter: PipelineCounter) {
+        self.pipeline_metrics.lock_or_recover().increment(controller_id, counter);
+    }
+    
+    pub fn get_pipeline_metrics(&self, controller_id: usize) -> PipelineMetrics {
+        self.pipeline_metrics.lock_or_recover().get(controller_id)
+    }
+    
+    pub fn reset_pipeline_metrics(&self, controller_id: usize) {
+        self.pipeline_metrics.lock_or_recover().reset(controller_id);
+    }
+    
+    /// Pipeline counters summed across every controller seen since they were last reset.
+    pub fn get_global_pipeline_metrics(&self) -> PipelineMetrics {
+        self.pipeline_metrics.lock_or_recover().global()
+    }
+    
+    /// Folds the connected gamepads' battery levels into the session stats every
+    /// `BATTERY_SAMPLE_INTERVAL`.
+    fn sample_battery(&self, gilrs: &Gilrs) {
+        {
+            let mut sampled_at = self.battery_sampled_at.lock_or_recover();
+            if sampled_at.is_some_and(|at| at.elapsed() < BATTERY_SAMPLE_INTERVAL) {
+                return;
             }
+            *sampled_at = Some(Instant::now());
         }
-        
-        devices.sort();
-        devices
+        let mut session_stats = self.session_stats.lock_or_recover();
+        for (_, gamepad) in gilrs.gamepads() {
+            match gamepad.power_info() {
+                PowerInfo::Discharging(percent) | PowerInfo::Charging(percent) => session_stats.battery_sample(percent),
+                PowerInfo::Charged => session_stats.battery_sample(100),
+                PowerInfo::Wired | PowerInfo::Unknown => {}
+            }
+        }
+    }
+    
+    fn refresh_gamepad_infos(&self, gilrs: &Gilrs, force: bool) {
+        let mut refreshed_at = self.gamepad_info_refreshed_at.lock_or_recover();
+        if !force && refreshed_at.elapsed() < GAMEPAD_INFO_REFRESH_INTERVAL {
+            return;
+        }
+        *refreshed_at = Instant::now();
+        self.gamepad_info_snapshot.store(Arc::new(read_gamepad_infos(gilrs)));
+    }
+    
+    /// Makes the dispatcher's latest controller states visible to commands. Skipped
+    /// when nothing changed since the last publish, e.g. inputs that were all dropped.
+    pub fn publish_state_snapshot(&self) {
+        let revisions = self.revisions.lock_or_recover().clone();
+        if revisions.revision() == self.state_snapshot.load().revisions.revision() {
+            return;
+        }
+        let started = Instant::now();
+        let states = self.states.lock_or_recover().clone();
+        self.state_snapshot.store(Arc::new(StateSnapshot { states, revisions }));
+        runtime_metrics::metrics().record_timing(Timing::StatePublish, started.elapsed());
+    }
+    
+    pub fn session_input_stats(&self) -> SessionInputStats {
+        self.session_stats.lock_or_recover().clone()
     }
     
-    fn check_permissions(&self) -> String {
-        let mut checks = Vec::new();
+    /// Recomputes the ensembles `controller_id` is in and emits those that changed.
+    fn update_ensembles(&self, app: &impl EventEmitter, controller_id: usize) {
+        let changed = {
+            let mut ensembles = self.ensembles.lock_or_recover();
+            let states = self.states.lock_or_recover();
+            ensembles.member_changed(controller_id, &states)
+        };
+        for change in changed {
+            app.emit_event("ensemble-state-changed", change).ok();
+        }
+    }
+    
+    /// Counts an input against the flood alarm, reporting a newly tripped alarm.
+    fn check_flood(&self, app: &impl EventEmitter, controller_id: usize) -> FloodGate {
+        let now_ms = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_millis() as u64;
+        let mut alarm = self.flood_alarm.lock_or_recover();
+        let detected = alarm.record(controller_id, now_ms);
+        let gate = alarm.gate(controller_id);
+        let limit = alarm.max_events_per_second;
+        drop(alarm);
         
-        // Check /dev/uinput permissions
-        match std::fs::metadata("/dev/uinput") {
-            Ok(metadata) => {
-                checks.push(format!("✅ /dev/uinput exists (mode: {:o})", 
-                                   metadata.permissions().mode()));
+        if let Some(detected) = detected {
+            warn!("Controller {} is flooding input: {:.0} events/s (limit {}), action {:?}",
+                  controller_id, detected.events_per_second, limit, detected.action);
+            if detected.action != FloodAction::Log {
+                app.emit_event("event-flood-detected", detected).ok();
             }
-            Err(e) => {
-                checks.push(format!("❌ /dev/uinput: {}", e));
+        }
+        gate
+    }
+    
+    fn emit_input(&self, app: &impl EventEmitter, event: ControllerEvent) {
+        {
+            let mut recent = self.recent_events.lock_or_recover();
+            if recent.len() == RECENT_EVENTS_CAPACITY {
+                recent.pop_front();
             }
+            recent.push_back(event.clone());
         }
-        
-        // Check /dev/input/event* permissions
-        let event_devices: Vec<_> = (0..32)
-            .map(|i| format!("/dev/input/event{}", i))
-            .filter(|path| std::path::Path::new(path).exists())
-            .collect();
-            
-        if event_devices.is_empty() {
-            checks.push("❌ No /dev/input/event* devices found".to_string());
+        let unified = UnifiedControllerEvent::from_gilrs(&event, self.device_id(event.controller_id));
+        let channel = EventChannel::for_input(&unified.input);
+        controller_event::emit(app, &unified, channel);
+        let batch = if processing_pause::forwarding_paused() {
+            None
         } else {
-            checks.push(format!("✅ Found {} /dev/input/event* devices", event_devices.len()));
-            
-            // Check permissions on first few devices
-            for (_i, device) in event_devices.iter().take(3).enumerate() {
-                match std::fs::metadata(device) {
-                    Ok(metadata) => {
-                        let mode = metadata.permissions().mode();
-                        let readable = mode & 0o444 != 0;
-                        let writable = mode & 0o222 != 0;
-                        checks.push(format!("  {} (mode: {:o}, r:{}, w:{})", 
-                                           device, mode, readable, writable));
+            self.batcher.lock_or_recover().push(unified)
+        };
+        let source_time = event.source_time;
+        if controller_event::legacy_events_enabled() && app.wants(channel, "gamepad-input") {
+            let result = app.emit_event("gamepad-input", event);
+            runtime_metrics::metrics().record_emit(result.is_ok());
+            if let Err(e) = result {
+                error_counters::errors().record(ErrorCategory::Emit, format!("gamepad-input: {}", e));
+            }
+        }
+        if let Some(source_time) = source_time {
+            latency_benchmark::benchmark().record(Stage::Emitted, source_time);
+        }
+        if let Some(batch) = batch {
+            self.send_batch(batch);
+        }
+    }
+    
+    /// Stable identity of a gilrs controller for unified events: its UUID when gilrs
+    /// knows it, otherwise its ID (mock controllers).
+    fn device_id(&self, controller_id: usize) -> String {
+        self.gamepad_info_snapshot.load().iter()
+            .find(|info| info.id == controller_id)
+            .map(|info| format!("gilrs:{}", info.uuid))
+            .unwrap_or_else(|| match controller_id {
+                touch_zones::TOUCH_CONTROLLER_ID => "touch:screen".to_string(),
+                _ => format!("gilrs:{}", controller_id),
+            })
+    }
+    
+    /// Sends the batched events once their window has passed. Called every polling pass.
+    pub fn flush_due_batch(&self) {
+        let due = self.batcher.lock_or_recover().take_due();
+        if let Some(batch) = due {
+            self.send_batch(batch);
+        }
+    }
+    
+    fn send_batch(&self, events: Vec<UnifiedControllerEvent>) {
+        let Some(endpoint) = self.settings.get().light_server_endpoint else {
+            debug!("Dropping batch of {} events: no light server endpoint is configured", events.len());
+            return;
+        };
+        
+        // Don't stall the polling loop on the HTTP round trip
+        let light_server = self.light_server.clone();
+        runtime_metrics::metrics().light_send_started();
+        thread_priority::spawn_normal(move || {
+            match light_server.post_batch(&endpoint, &events) {
+                Ok(results) => {
+                    let failed = results.iter().filter(|result| !result.ok).count();
+                    if failed > 0 {
+                        warn!("Light server rejected {} of {} batched events", failed, events.len());
+                        error_counters::errors().record(
+                            ErrorCategory::LightServerSend,
+                            format!("Light server rejected {} of {} batched events", failed, events.len()),
+                        );
                     }
-                    Err(e) => {
-                        checks.push(format!("  {} - Error: {}", device, e));
+                }
+                Err(e) => {
+                    warn!("Batch of {} events failed to send: {}", events.len(), e);
+                    error_counters::errors().record(ErrorCategory::LightServerSend, e);
+                }
+            }
+            runtime_metrics::metrics().light_send_finished();
+        });
+    }
+    
+    /// Sends any batched events before exit, waiting at most `timeout` for the light server.
+    /// Sends whatever is still batched, waiting at most `timeout` for the light server.
+    fn flush_pending(&self, timeout: Duration) {
+        let pending = self.batcher.lock_or_recover().take();
+        if pending.is_empty() {
+            return;
+        }
+        let Some(endpoint) = self.settings.get().light_server_endpoint else {
+            return;
+        };
+        let count = pending.len();
+        let light_server = self.light_server.clone();
+        let (done_tx, done_rx) = mpsc::channel();
+        std::thread::spawn(move || {
+            let _ = done_tx.send(light_server.post_batch(&endpoint, &pending));
+        });
+        match done_rx.recv_timeout(timeout) {
+            Ok(Ok(_)) => info!("Flushed {} batched events on shutdown", count),
+            Ok(Err(e)) => warn!("Failed to flush {} batched events on shutdown: {}", count, e),
+            Err(_) => warn!("Gave up flushing {} batched events after {:?}", count, timeout),
+        }
+    }
+    
+    pub fn get_recent_events(&self) -> Vec<ControllerEvent> {
+        self.recent_events.lock_or_recover().iter().cloned().collect()
+    }
+    
+    fn fire_combination_effect(&self, app: &impl EventEmitter, controller_id: usize, combo: CombinationLightEffect) {
+        self.count(controller_id, PipelineCounter::MacroTriggered);
+        if processing_pause::forwarding_paused() {
+            debug!("Combination '{}' not sent: input processing is paused", combo.id);
+            return;
+        }
+        let endpoint = match combo.effect.endpoint.clone().or(self.settings.get().light_server_endpoint) {
+            Some(endpoint) => endpoint,
+            None => {
+                warn!("Combination '{}' triggered but no light server endpoint is configured", combo.id);
+                return;
+            }
+        };
+        
+        info!("Combination '{}' triggered on controller {}", combo.id, controller_id);
+        app.emit_event("combination-effect-triggered", &combo.id).ok();
+        
+        let (r, g, b) = combo.effect.color;
+        let payload = serde_json::json!({
+            "controller_id": controller_id,
+            "action": format!("combo:{}", combo.id),
+            "effect": {
+                "color": [r, g, b],
+                "fade_in_ms": combo.effect.fade_in_ms,
+                "hold_ms": combo.effect.hold_ms,
+                "fade_out_ms": combo.effect.fade_out_ms,
+            },
+            "timestamp": SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .unwrap_or_default()
+                .as_millis() as u64,
+        });
+        
+        // Don't stall the polling loop on the HTTP round trip
+        let light_server = self.light_server.clone();
+        runtime_metrics::metrics().light_send_started();
+        thread_priority::spawn_normal(move || {
+            match light_server.post_json(&endpoint, &payload) {
+                Ok(_) => {
+                    if let Some(haptic) = &combo.haptic {
+                        if let Err(e) = hidraw::play_deck_haptic(haptic) {
+                            warn!("Combination '{}' haptic failed: {}", combo.id, e);
+                        }
                     }
                 }
+                Err(e) => {
+                    warn!("Combination '{}' failed to send: {}", combo.id, e);
+                    error_counters::errors().record(
+                        ErrorCategory::LightServerSend,
+                        format!("Combination '{}': {}", combo.id, e),
+                    );
+                }
             }
+            runtime_metrics::metrics().light_send_finished();
+        });
+    }
+    
+    pub fn register_combination_effect(&self, effect: CombinationLightEffect) -> Result<(), String> {
+        self.combo_tracker.lock_or_recover().register(effect)
+    }
+    
+    pub fn unregister_combination_effect(&self, id: &str) -> bool {
+        self.combo_tracker.lock_or_recover().unregister(id)
+    }
+    
+    pub fn list_combination_effects(&self) -> Vec<CombinationLightEffect> {
+        self.combo_tracker.lock_or_recover().list()
+    }
+    
+    pub fn set_ghost_filter_interval(&self, button: &str, min_interval_us: u64) {
+        self.ghost_filter.lock_or_recover().set_interval(button, min_interval_us);
+    }
+    
+    pub fn set_global_ghost_filter_interval(&self, min_interval_us: u64) {
+        self.ghost_filter.lock_or_recover().set_global_interval(min_interval_us);
+    }
+    
+    pub fn set_stick_dead_zone_shape(&self, controller_id: usize, stick: StickSide, shape: DeadZoneShape) {
+        self.dead_zones.lock_or_recover().set_shape(controller_id, stick, shape);
+    }
+    
+    pub fn configure_flood_alarm(&self, max_events_per_second: u32, window_ms: u64, action: FloodAction) -> Result<(), String> {
+        self.flood_alarm.lock_or_recover().configure(max_events_per_second, window_ms, action)?;
+        info!("Flood alarm set to {} events/s over {}ms, action {:?}", max_events_per_second, window_ms, action);
+        Ok(())
+    }
+    
+    pub fn create_ensemble(&self, name: &str, config: EnsembleConfig) -> Result<String, String> {
+        self.ensembles.lock_or_recover().create(name, config)
+    }
+    
+    pub fn update_ensemble(&self, name: &str, config: EnsembleConfig) -> Result<(), String> {
+        self.ensembles.lock_or_recover().update(name, config)
+    }
+    
+    pub fn delete_ensemble(&self, name: &str) -> Result<(), String> {
+        if !self.ensembles.lock_or_recover().delete(name) {
+            return Err(format!("No ensemble named {}", name));
         }
+        Ok(())
+    }
+    
+    pub fn get_ensemble_state(&self, name: &str) -> Result<ControllerState, String> {
+        let snapshot = self.state_snapshot.load();
+        self.ensembles.lock_or_recover().state(name, &snapshot.states)
+    }
+    
+    pub fn get_flood_alarm_status(&self) -> FloodAlarmStatus {
+        let now_ms = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_millis() as u64;
+        self.flood_alarm.lock_or_recover().status(now_ms)
+    }
+    
+    /// Re-enables a controller disabled by the flood alarm and clears its rate.
+    pub fn reset_flood_alarm(&self, controller_id: usize) -> bool {
+        self.flood_alarm.lock_or_recover().reset(controller_id)
+    }
+    
+    pub fn gilrs_available(&self) -> bool {
+        self.gilrs_available.load(Ordering::Relaxed)
+    }
+    
+    pub fn get_controller_states(&self) -> HashMap<usize, ControllerState> {
+        self.state_snapshot.load().states.clone()
+    }
+    
+    pub fn get_controller_state(&self, id: usize) -> Option<ControllerState> {
+        self.state_snapshot.load().states.get(&id).cloned()
+    }
+    
+    /// Controllers that changed or went away since `since_revision`; 0 gets everything.
+    pub fn get_controller_states_delta(&self, since_revision: u64) -> ControllerStatesDelta {
+        self.state_snapshot.load().delta(since_revision)
+    }
+    
+    /// The gamepads gilrs saw as of the last refresh (at most a second old).
+    pub fn gamepad_infos(&self) -> Vec<GamepadInfo> {
+        Vec::clone(&self.gamepad_info_snapshot.load())
+    }
+    
+    pub fn get_debug_info(&self, permissions: &PermissionsAnalysis) -> DebugInfo {
+        // Filesystem work happens before any lock is taken
+        let input_devices = enumerate_input_devices();
+        let permissions_check = permissions.summary();
+        let connected_gamepads = self.gamepad_infos();
+        let last_event_time = *self.last_event_time.lock_or_recover();
+        let (unhandled, unhandled_events_overflowed) = {
+            let unhandled_events = self.unhandled_events.lock_or_recover();
+            (unhandled_events.snapshot(), unhandled_events.overflowed())
+        };
         
-        checks.join("\n")
+        DebugInfo {
+            gilrs_initialized: self.gilrs_available(),
+            total_gamepads: connected_gamepads.len(),
+            connected_gamepads,
+            input_devices,
+            permissions_check,
+            last_event_time,
+            unhandled_events: unhandled,
+            unhandled_events_overflowed,
+            processing_paused: processing_pause::pause_mode(),
+        }
+    }
+    
+    pub fn get_unhandled_events(&self) -> Vec<UnhandledEventKind> {
+        self.unhandled_events.lock_or_recover().snapshot()
+    }
+    
+    pub fn query_axis_time_series(
+        &self,
+        controller_id: usize,
+        axis_name: &str,
+        from_ms: u64,
+        to_ms: u64,
+        max_points: usize,
+    ) -> Vec<(u64, f32)> {
+        self.time_series.lock_or_recover()
+            .query(controller_id, axis_name, from_ms, to_ms, max_points)
+    }
+    
+    pub fn configure_time_series_retention(&self, retention_ms: u64) {
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_millis() as u64;
+        self.time_series.lock_or_recover().set_retention(retention_ms, now);
+    }
+    
+    /// Records every connected controller's axes when the snapshot interval has passed.
+    /// Called every polling pass.
+    pub fn capture_due_snapshot(&self) {
+        if !self.axis_snapshots.lock_or_recover().take_due(Instant::now()) {
+            return;
+        }
+        let states: HashMap<usize, HashMap<String, f32>> = self.state_snapshot.load().states.iter()
+            .filter(|(_, state)| state.connected)
+            .map(|(&id, state)| (id, state.axes.clone()))
+            .collect();
+        if states.is_empty() {
+            return;
+        }
+        let timestamp_ms = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_millis() as u64;
+        self.axis_snapshots.lock_or_recover().push(AxisSnapshot { timestamp_ms, states });
+    }
+    
+    pub fn get_snapshots_since(&self, timestamp_ms: u64) -> Vec<AxisSnapshot> {
+        self.axis_snapshots.lock_or_recover().since(timestamp_ms)
+    }
+    
+    pub fn configure_snapshot_recorder(&self, rate_hz: u32, max_snapshots: usize) -> Result<(), String> {
+        self.axis_snapshots.lock_or_recover().configure(rate_hz, max_snapshots)
+    }
+    
+    pub fn export_snapshots_csv(&self) -> String {
+        self.axis_snapshots.lock_or_recover().to_csv()
+    }
+    
+    pub fn get_time_series_memory_usage(&self) -> usize {
+        self.time_series.lock_or_recover().memory_usage()
+    }
+    
+    /// The dispatcher's long-lived buffers, for `get_memory_stats`.
+    pub fn buffer_stats(&self) -> Vec<BufferStats> {
+        let removed = self.revisions.lock_or_recover().removed_count();
+        vec![
+            BufferStats::deque("recent_events", &self.recent_events.lock_or_recover(), RECENT_EVENTS_CAPACITY),
+            self.time_series.lock_or_recover().buffer_stats(),
+            self.axis_snapshots.lock_or_recover().buffer_stats(),
+            self.flood_alarm.lock_or_recover().buffer_stats(),
+            self.pipeline_metrics.lock_or_recover().buffer_stats(),
+            BufferStats::new(
+                "removed_controllers",
+                removed,
+                Some(MAX_REMOVED_CONTROLLERS),
+                1,
+                removed * std::mem::size_of::<(usize, u64)>(),
+            ),
+        ]
+    }
+    
+    pub fn is_rumble_supported(&self, controller_id: usize) -> bool {
+        self.gamepad_info_snapshot.load().iter()
+            .any(|info| info.id == controller_id && info.ff_supported)
+    }
+    
+    /// Rumbles a controller with the strong (low frequency) and weak (high frequency)
+    /// motors at the given magnitudes, 0.0 to 1.0, replacing any rumble already playing.
+    pub fn rumble(&self, controller_id: usize, strong: f32, weak: f32, duration_ms: u32) -> Result<(), String> {
+        if !(0.0..=1.0).contains(&strong) || !(0.0..=1.0).contains(&weak) {
+            return Err("Rumble magnitudes must be between 0 and 1".to_string());
+        }
+        if duration_ms == 0 || duration_ms > MAX_RUMBLE_MS {
+            return Err(format!("Rumble duration must be between 1 and {} ms", MAX_RUMBLE_MS));
+        }
+        
+        let mut gilrs = self.gilrs.lock_or_recover();
+        let (id, ff_supported) = gilrs.gamepads()
+            .find(|(id, _)| usize::from(*id) == controller_id)
+            .map(|(id, gamepad)| (id, gamepad.is_ff_supported()))
+            .ok_or_else(|| format!("Controller {} is not connected", controller_id))?;
+        if !ff_supported {
+            return Err(format!("Controller {} does not support rumble", controller_id));
+        }
+        
+        let duration = Ticks::from_ms(duration_ms);
+        let scheduling = Replay { play_for: duration, ..Default::default() };
+        let motor = |kind| BaseEffect { kind, scheduling, ..Default::default() };
+        let effect = EffectBuilder::new()
+            .add_effect(motor(BaseEffectType::Strong { magnitude: (strong * u16::MAX as f32) as u16 }))
+            .add_effect(motor(BaseEffectType::Weak { magnitude: (weak * u16::MAX as f32) as u16 }))
+            .repeat(Repeat::For(duration))
+            .gamepads(&[id])
+            .finish(&mut gilrs)
+            .map_err(|e| format!("Failed to create rumble effect: {}", e))?;
+        drop(gilrs);
+        effect.play().map_err(|e| format!("Failed to play rumble effect: {}", e))?;
+        
+        debug!("Rumbling controller {} (strong={:.2}, weak={:.2}) for {}ms", controller_id, strong, weak, duration_ms);
+        self.rumble_effects.lock_or_recover().insert(controller_id, effect);
+        Ok(())
+    }
+    
+    pub fn get_gamepad_info(&self, controller_id: usize) -> Option<GamepadInfo> {
+        self.gamepad_info_snapshot.load().iter()
+            .find(|info| info.id == controller_id)
+            .cloned()
     }
     
-    fn update_button_state(&self, controller_id: usize, button: Button, pressed: bool) {
-        let mut states = self.states.lock().unwrap();
+    /// Panics while holding every lock the manager has, poisoning them. Lets tests check
+    /// that a panic mid-update doesn't break the accessors the commands read from.
+    #[cfg(feature = "test-support")]
+    pub(crate) fn poison_locks(&self) {
+        let _ = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+            let _gilrs = self.gilrs.lock_or_recover();
+            let _states = self.states.lock_or_recover();
+            let _revisions = self.revisions.lock_or_recover();
+            let _gamepad_info_refreshed_at = self.gamepad_info_refreshed_at.lock_or_recover();
+            let _last_event_time = self.last_event_time.lock_or_recover();
+            let _combo_tracker = self.combo_tracker.lock_or_recover();
+            let _ghost_filter = self.ghost_filter.lock_or_recover();
+            let _dead_zones = self.dead_zones.lock_or_recover();
+            let _pipeline_metrics = self.pipeline_metrics.lock_or_recover();
+            let _flood_alarm = self.flood_alarm.lock_or_recover();
+            let _ensembles = self.ensembles.lock_or_recover();
+            let _time_series = self.time_series.lock_or_recover();
+            let _axis_snapshots = self.axis_snapshots.lock_or_recover();
+            let _batcher = self.batcher.lock_or_recover();
+            let _recent_events = self.recent_events.lock_or_recover();
+            let _session_stats = self.session_stats.lock_or_recover();
+            let _battery_sampled_at = self.battery_sampled_at.lock_or_recover();
+            let _unhandled_events = self.unhandled_events.lock_or_recover();
+            let _rumble_effects = self.rumble_effects.lock_or_recover();
+            let _window_chord = self.window_chord.lock_or_recover();
+            panic!("Injected panic while holding the gamepad manager's locks");
+        }));
+    }
+    
+    fn update_button_state(&self, app: &impl EventEmitter, controller_id: usize, button: &str, pressed: bool) {
+        let metrics = runtime_metrics::metrics();
+        metrics.lock(Timing::ComboTrackerLockWait, &self.combo_tracker)
+            .button_changed(controller_id, button, pressed);
+        self.window_chord.lock_or_recover().button_changed(controller_id, button, pressed);
+        if let (Some(handle), Some(state)) = (app.app_handle(), app.app_state()) {
+            state.key_emulator.button_changed(controller_id, button, pressed);
+            state.midi_output.button_changed(controller_id, button, pressed);
+            state.opc_output.button_changed(controller_id, button, pressed);
+            state.show_playback.button_changed(handle, button, pressed);
+        }
+        
+        let mut states = metrics.lock(Timing::StatesLockWait, &self.states);
         if let Some(state) = states.get_mut(&controller_id) {
-            state.buttons.insert(format!("{:?}", button), pressed);
+            // Only allocate a key the first time a button is seen
+            let changed = match state.buttons.get_mut(button) {
+                Some(current) => std::mem::replace(current, pressed) != pressed,
+                None => {
+                    state.buttons.insert(button.to_string(), pressed);
+                    true
+                }
+            };
+            if changed {
+                self.revisions.lock_or_recover().changed(controller_id);
+            }
         }
     }
     
-    fn update_axis_state(&self, controller_id: usize, axis: Axis, value: f32) {
-        let mut states = self.states.lock().unwrap();
+    fn update_axis_state(&self, app: &impl EventEmitter, controller_id: usize, axis: &str, value: f32, timestamp: u64) {
+        let metrics = runtime_metrics::metrics();
+        metrics.lock(Timing::TimeSeriesLockWait, &self.time_series)
+            .push(controller_id, axis, timestamp, value);
+        if let Some(state) = app.app_state() {
+            state.key_emulator.axis_changed(controller_id, axis, value);
+            state.midi_output.axis_changed(controller_id, axis, value);
+        }
+        
+        let mut states = metrics.lock(Timing::StatesLockWait, &self.states);
         if let Some(state) = states.get_mut(&controller_id) {
-            state.axes.insert(format!("{:?}", axis), value);
+            let changed = match state.axes.get_mut(axis) {
+                Some(current) => std::mem::replace(current, value) != value,
+                None => {
+                    state.axes.insert(axis.to_string(), value);
+                    true
+                }
+            };
+            if changed {
+                self.revisions.lock_or_recover().changed(controller_id);
+            }
+        }
+    }
+}
+
+impl InputBackend for GamepadManager {
+    fn source(&self) -> PollSource {
+        PollSource::Gilrs
+    }
+
+    /// Gilrs finds gamepads itself; this just refreshes the gamepad info right away.
+    fn scan(&self, _app: &AppHandle) -> Result<(), String> {
+        let gilrs = runtime_metrics::metrics().lock(Timing::GilrsLockWait, &self.gilrs);
+        self.refresh_gamepad_infos(&gilrs, true);
+        Ok(())
+    }
+
+    fn drain_events(&self, app: &AppHandle) -> Result<(), String> {
+        // With the mock feature the scripted source feeds the pipeline instead of gilrs
+        if cfg!(feature = "mock") {
+            self.fire_due_combinations(app);
+        } else {
+            self.poll_events(app);
         }
+        self.flush_due_batch();
+        self.capture_due_snapshot();
+        Ok(())
     }
-}
\ No newline at end of file
+
+    fn current_states(&self) -> Vec<BackendControllerState> {
+        self.state_snapshot.load().states.values()
+            .map(|state| BackendControllerState {
+                source: PollSource::Gilrs,
+                device_id: self.device_id(state.controller_id),
+                buttons: state.buttons.clone(),
+                axes: state.axes.clone(),
+            })
+            .collect()
+    }
+
+    fn debug_info(&self) -> BackendDebugInfo {
+        BackendDebugInfo {
+            source: PollSource::Gilrs,
+            enabled: true,
+            available: self.gilrs_available(),
+            device_count: self.gamepad_info_snapshot.load().len(),
+            last_scan_time: None,
+        }
+    }
+
+    fn shutdown(&self) {
+        self.flush_pending(FLUSH_TIMEOUT);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pad(name: &str, uuid: &str, capability_hash: Option<&str>) -> GamepadInfo {
+        GamepadInfo {
+            id: 0,
+            name: name.to_string(),
+            is_connected: true,
+            power_info: "Wired".to_string(),
+            uuid: uuid.to_string(),
+            vendor_id: Some(0x2dc8),
+            product_id: Some(0x6003),
+            mapping_name: None,
+            layout: ControllerLayout::Xbox,
+            layout_override: None,
+            ff_supported: false,
+            is_virtual: false,
+            physical_source: None,
+            capability_hash: capability_hash.map(str::to_string),
+        }
+    }
+
+    #[test]
+    fn a_renamed_pad_keeps_its_layout_through_the_uuid() {
+        let uuid = "05000000c82d00000360000000010000";
+        let overrides = HashMap::from([
+            ("abc123".to_string(), ControllerLayout::Nintendo),
+            (uuid.to_string(), ControllerLayout::Nintendo),
+        ]);
+        // A new name changes the capability hash but not the gilrs UUID
+        let renamed = pad("8BitDo Pro 2 (renamed)", uuid, Some("def456"));
+        assert_eq!(renamed.saved_layout(&overrides), Some(ControllerLayout::Nintendo));
+    }
+
+    #[test]
+    fn the_uuid_is_the_fallback() {
+        let overrides = HashMap::from([
+            ("0300".to_string(), ControllerLayout::Playstation),
+            ("abc123".to_string(), ControllerLayout::Nintendo),
+        ]);
+        assert_eq!(pad("Pad", "0300", None).saved_layout(&overrides), Some(ControllerLayout::Playstation));
+        assert_eq!(pad("Pad", "0300", Some("abc123")).saved_layout(&overrides), Some(ControllerLayout::Nintendo));
+        assert_eq!(pad("Pad", "0300", Some("def456")).saved_layout(&overrides), Some(ControllerLayout::Playstation));
+        assert_eq!(pad("Pad", "0400", Some("def456")).saved_layout(&overrides), None);
+    }
+}
+
@@ -0,0 +1,55 @@
+use std::collections::HashMap;
+
+pub const DEFAULT_MIN_INTERVAL_US: u64 = 1000;
+
+/// Drops button events that follow the previous accepted event for the same button
+/// too closely to be real presses (contact bounce, electrical noise).
+pub struct GhostInputFilter {
+    /// Per-button overrides of `default_min_interval_us`
+    min_interval_us: HashMap<String, u64>,
+    default_min_interval_us: u64,
+    /// Time of the last accepted event per (controller, button), in microseconds
+    last_event_time: HashMap<(usize, String), u64>,
+}
+
+impl Default for GhostInputFilter {
+    fn default() -> Self {
+        Self {
+            min_interval_us: HashMap::new(),
+            default_min_interval_us: DEFAULT_MIN_INTERVAL_US,
+            last_event_time: HashMap::new(),
+        }
+    }
+}
+
+impl GhostInputFilter {
+    /// Returns false if the event should be discarded. Accepted events become the new
+    /// reference point; discarded ones don't, so a burst can't keep extending itself.
+    pub fn accept(&mut self, controller_id: usize, button: &str, timestamp_us: u64) -> bool {
+        let min_interval = self.min_interval_us.get(button)
+            .copied()
+            .unwrap_or(self.default_min_interval_us);
+        let key = (controller_id, button.to_string());
+        if let Some(last) = self.last_event_time.get(&key) {
+            if timestamp_us.saturating_sub(*last) < min_interval {
+                return false;
+            }
+        }
+        self.last_event_time.insert(key, timestamp_us);
+        true
+    }
+    
+    pub fn set_interval(&mut self, button: &str, min_interval_us: u64) {
+        self.min_interval_us.insert(button.to_string(), min_interval_us);
+    }
+    
+    /// Sets the interval for every button, replacing any per-button overrides.
+    pub fn set_global_interval(&mut self, min_interval_us: u64) {
+        self.default_min_interval_us = min_interval_us;
+        self.min_interval_us.clear();
+    }
+    
+    pub fn controller_disconnected(&mut self, controller_id: usize) {
+        self.last_event_time.retain(|(id, _), _| *id != controller_id);
+    }
+}
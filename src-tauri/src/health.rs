@@ -0,0 +1,429 @@
+#[cfg(target_os = "linux")]
+use crate::confinement;
+use crate::error_counters;
+use crate::evdev_gamepad::EvdevGamepadManager;
+use crate::gamepad::GamepadManager;
+use crate::key_emulation::KeyEmulator;
+use crate::light_server::LightServer;
+use crate::locking::MutexExt;
+#[cfg(target_os = "linux")]
+use crate::permissions;
+use crate::settings::SettingsManager;
+use crate::steam_deck::{BackGripStatus, SteamDeckInfo};
+use crate::steam_input_check::{SteamInputCheck, SteamInputInterference};
+use serde::{Deserialize, Serialize};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// A polling heartbeat older than this means the polling thread has stalled.
+const HEARTBEAT_STALE_MS: u64 = 2000;
+/// How often the watchdog looks at the heartbeat.
+pub const WATCHDOG_INTERVAL: Duration = Duration::from_millis(500);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum HealthStatus {
+    Pass,
+    Warn,
+    Fail,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HealthCheck {
+    pub name: String,
+    pub status: HealthStatus,
+    pub message: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HealthReport {
+    /// The worst status of any check
+    pub overall: HealthStatus,
+    pub checks: Vec<HealthCheck>,
+    pub checked_at: u64,
+}
+
+/// Outcome of a check that's too slow to run on demand, kept from when it last ran.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CachedCheck {
+    pub ok: bool,
+    pub message: String,
+    pub checked_at: u64,
+}
+
+/// One of the independently running polling pipelines.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PollSource {
+    Gilrs,
+    Evdev,
+}
+
+impl PollSource {
+    /// The sources with a polling thread on this platform; evdev is Linux-only.
+    #[cfg(target_os = "linux")]
+    pub const ALL: [PollSource; 2] = [PollSource::Gilrs, PollSource::Evdev];
+    #[cfg(not(target_os = "linux"))]
+    pub const ALL: [PollSource; 1] = [PollSource::Gilrs];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            PollSource::Gilrs => "gilrs",
+            PollSource::Evdev => "evdev",
+        }
+    }
+
+    pub fn index(self) -> usize {
+        self as usize
+    }
+}
+
+/// Payload of `backend-stalled`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BackendStalled {
+    pub source: PollSource,
+    pub heartbeat_age_ms: u64,
+    /// Whether the polling thread had exited and was started again
+    pub restarted_poll_thread: bool,
+}
+
+/// Payload of `backend-recovered`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BackendRecovered {
+    pub source: PollSource,
+    pub stalled_for_ms: u64,
+}
+
+/// A change in polling liveness seen by the watchdog.
+pub enum StallChange {
+    Stalled { heartbeat_age_ms: u64 },
+    Recovered { stalled_for_ms: u64 },
+}
+
+/// Liveness of one polling pipeline, for debug info.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PipelineStatus {
+    pub source: PollSource,
+    /// Whether its thread is running
+    pub alive: bool,
+    pub stalled: bool,
+    /// `None` before the first pass
+    pub heartbeat_age_ms: Option<u64>,
+    pub stalls: u64,
+}
+
+#[derive(Default)]
+struct Liveness {
+    heartbeat: AtomicU64,
+    stalled: AtomicBool,
+    /// When the current stall was detected
+    stalled_at: AtomicU64,
+    stalls: AtomicU64,
+}
+
+/// Liveness and cached results that `health_check` can't work out on the spot.
+#[derive(Default)]
+pub struct HealthMonitor {
+    pipelines: [Liveness; 2],
+    updater: Mutex<Option<CachedCheck>>,
+}
+
+impl HealthMonitor {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn pipeline(&self, source: PollSource) -> &Liveness {
+        &self.pipelines[source.index()]
+    }
+
+    /// Called by a polling thread on every pass.
+    pub fn heartbeat(&self, source: PollSource) {
+        self.pipeline(source).heartbeat.store(now_ms(), Ordering::Relaxed);
+    }
+
+    /// Milliseconds since the pipeline's last heartbeat; `None` before the first.
+    pub fn heartbeat_age_ms(&self, source: PollSource) -> Option<u64> {
+        match self.pipeline(source).heartbeat.load(Ordering::Relaxed) {
+            0 => None,
+            last => Some(now_ms().saturating_sub(last)),
+        }
+    }
+    
+    /// Stalls across all pipelines.
+    pub fn poll_stalls(&self) -> u64 {
+        self.pipelines.iter().map(|pipeline| pipeline.stalls.load(Ordering::Relaxed)).sum()
+    }
+
+    pub fn pipeline_status(&self, source: PollSource, alive: bool) -> PipelineStatus {
+        let pipeline = self.pipeline(source);
+        PipelineStatus {
+            source,
+            alive,
+            stalled: pipeline.stalled.load(Ordering::Relaxed),
+            heartbeat_age_ms: self.heartbeat_age_ms(source),
+            stalls: pipeline.stalls.load(Ordering::Relaxed),
+        }
+    }
+    
+    /// Called by the watchdog; reports when a pipeline stalls and when it resumes.
+    pub fn check_stall(&self, source: PollSource) -> Option<StallChange> {
+        let age = self.heartbeat_age_ms(source)?;
+        let pipeline = self.pipeline(source);
+        let now = now_ms();
+        if age > HEARTBEAT_STALE_MS {
+            if pipeline.stalled.swap(true, Ordering::Relaxed) {
+                return None;
+            }
+            pipeline.stalled_at.store(now, Ordering::Relaxed);
+            pipeline.stalls.fetch_add(1, Ordering::Relaxed);
+            Some(StallChange::Stalled { heartbeat_age_ms: age })
+        } else if pipeline.stalled.swap(false, Ordering::Relaxed) {
+            // The stall began when the last heartbeat before it was taken
+            let stalled_at = pipeline.stalled_at.load(Ordering::Relaxed);
+            Some(StallChange::Recovered {
+                stalled_for_ms: now.saturating_sub(stalled_at) + HEARTBEAT_STALE_MS,
+            })
+        } else {
+            None
+        }
+    }
+    
+    pub fn record_updater_check<T, E: std::fmt::Display>(&self, result: &Result<T, E>) {
+        *self.updater.lock_or_recover() = Some(CachedCheck {
+            ok: result.is_ok(),
+            message: match result {
+                Ok(_) => "Update server reachable".to_string(),
+                Err(e) => e.to_string(),
+            },
+            checked_at: now_ms(),
+        });
+    }
+}
+
+pub fn run_checks(
+    monitor: &HealthMonitor,
+    gamepad_manager: &GamepadManager,
+    evdev_manager: &EvdevGamepadManager,
+    settings: &SettingsManager,
+    light_server: &LightServer,
+    key_emulator: &KeyEmulator,
+    steam_input: &Mutex<SteamInputCheck>,
+) -> HealthReport {
+    let now = now_ms();
+    let evdev_devices = evdev_manager.get_detected_devices();
+
+    let mut checks = vec![if gamepad_manager.gilrs_available() {
+        check("gilrs", HealthStatus::Pass, "Gamepad backend initialized")
+    } else {
+        check("gilrs", HealthStatus::Fail, "Gamepad backend unavailable; only evdev input works")
+    }];
+
+    let connected = gamepad_manager.get_controller_states().len();
+    checks.push(if connected > 0 {
+        check("controllers", HealthStatus::Pass, format!("{} controller(s) connected", connected))
+    } else {
+        check("controllers", HealthStatus::Warn, "No controllers connected")
+    });
+
+    // evdev and the input group only exist on Linux; elsewhere gilrs is the only backend
+    #[cfg(target_os = "linux")]
+    {
+        let evdev_stats = evdev_manager.get_stats();
+        checks.push(match (evdev_stats.detected_devices, evdev_stats.accessible_devices) {
+            (0, _) => check("evdev", HealthStatus::Warn, "No evdev gamepads detected"),
+            (detected, 0) => check("evdev", HealthStatus::Fail, format!("None of the {} evdev gamepads can be opened", detected)),
+            (detected, accessible) if accessible < detected => check(
+                "evdev",
+                HealthStatus::Warn,
+                format!("{} of {} evdev gamepads can be opened", accessible, detected),
+            ),
+            (detected, _) => check("evdev", HealthStatus::Pass, format!("All {} evdev gamepads can be opened", detected)),
+        });
+
+        let confinement = confinement::current();
+        let sandboxed = confinement::blocks_input(confinement, evdev_stats.accessible_devices);
+        if sandboxed {
+            checks.push(check(
+                "sandbox",
+                HealthStatus::Fail,
+                format!(
+                    "Running in {}, which keeps the input devices out. {}",
+                    confinement.summary(),
+                    confinement.remediation().unwrap_or_default()
+                ),
+            ));
+        } else if confinement.is_confined() {
+            checks.push(check(
+                "sandbox",
+                HealthStatus::Pass,
+                format!("Running in {}, with input devices reachable", confinement.summary()),
+            ));
+        }
+
+        let all_accessible = evdev_stats.detected_devices > 0 && evdev_stats.inaccessible_devices == 0;
+        checks.push(match permissions::in_input_group() {
+            // Group membership and file modes don't matter while the sandbox hides the devices
+            _ if sandboxed => check("permissions", HealthStatus::Warn, "Not the cause: the sandbox keeps the devices out, not file modes"),
+            Some(true) => check("permissions", HealthStatus::Pass, "User is in the input group"),
+            Some(false) if all_accessible => check(
+                "permissions",
+                HealthStatus::Pass,
+                "Not in the input group, but every evdev gamepad is readable",
+            ),
+            Some(false) => check(
+                "permissions",
+                HealthStatus::Warn,
+                "Not in the input group; run `sudo usermod -aG input $USER` and log in again",
+            ),
+            None => check("permissions", HealthStatus::Warn, "Could not determine input group membership"),
+        });
+    }
+
+    let steam_virtual = evdev_devices.iter().filter(|device| device.is_steam_virtual()).count();
+    let steam_deck = SteamDeckInfo::collect(&evdev_devices);
+    checks.push(match steam_deck.steam_running {
+        Some(true) if steam_virtual > 0 => check(
+            "steam_input",
+            HealthStatus::Warn,
+            format!(
+                "Steam is running with {} virtual pad(s); Steam Input may be grabbing the physical controller",
+                steam_virtual
+            ),
+        ),
+        Some(true) if steam_deck.session_kind.defaults().warn_steam_input => check(
+            "steam_input",
+            HealthStatus::Warn,
+            "Game mode: Steam Input is always on and may take over the controller; disable it in this app's controller settings in Steam",
+        ),
+        Some(true) => check("steam_input", HealthStatus::Pass, "Steam is running without virtual pads"),
+        Some(false) => check("steam_input", HealthStatus::Pass, "Steam is not running"),
+        None => check("steam_input", HealthStatus::Warn, "Could not check whether Steam is running"),
+    });
+
+    let steam_input = steam_input.lock_or_recover().clone();
+    if let Some(advice) = &steam_input.advice {
+        let status = match steam_input.interference {
+            SteamInputInterference::DesktopLayout => HealthStatus::Fail,
+            _ => HealthStatus::Warn,
+        };
+        checks.push(check("steam_input_layout", status, advice.clone()));
+    }
+
+    if steam_deck.is_steam_deck {
+        checks.push(match steam_deck.back_grips {
+            BackGripStatus::Available => check("back_grips", HealthStatus::Pass, steam_deck.back_grips.hint()),
+            BackGripStatus::SteamInputOnly | BackGripStatus::Unavailable => {
+                check("back_grips", HealthStatus::Warn, steam_deck.back_grips.hint())
+            }
+        });
+    }
+
+    let status = light_server.get_status();
+    let configured = settings.get().light_server_endpoint.is_some() || status.last_endpoint.is_some();
+    checks.push(match (status.last_success_time, status.last_error_time) {
+        (success, Some(error_time)) if success.is_none_or(|success| success < error_time) => check(
+            "light_server",
+            HealthStatus::Fail,
+            format!(
+                "Last request failed {}: {}",
+                ago(now, error_time),
+                status.last_error.as_deref().unwrap_or("unknown error")
+            ),
+        ),
+        (Some(success), _) => check("light_server", HealthStatus::Pass, format!("Last request succeeded {}", ago(now, success))),
+        _ if configured => check("light_server", HealthStatus::Warn, "Not contacted yet"),
+        _ => check("light_server", HealthStatus::Warn, "No light server endpoint configured"),
+    });
+
+    let emulation = key_emulator.status();
+    if emulation.enabled {
+        let off = if emulation.off_chord.is_empty() {
+            "turn it off from the app".to_string()
+        } else {
+            format!("press {} together to turn it off", emulation.off_chord.join(" + "))
+        };
+        checks.push(if emulation.write_errors > 0 {
+            check(
+                "key_emulation",
+                HealthStatus::Warn,
+                format!("Keyboard and mouse emulation is on, but {} write(s) failed; {}", emulation.write_errors, off),
+            )
+        } else {
+            check(
+                "key_emulation",
+                HealthStatus::Pass,
+                format!("Keyboard and mouse emulation is on with {} active rule(s); {}", emulation.active_rules, off),
+            )
+        });
+    }
+
+    checks.push(match settings.check_writable() {
+        Ok(()) => check("settings", HealthStatus::Pass, "Settings file is writable"),
+        Err(e) => check("settings", HealthStatus::Fail, e),
+    });
+
+    checks.push(match monitor.updater.lock_or_recover().clone() {
+        Some(cached) if cached.ok => check("updater", HealthStatus::Pass, format!("{} ({})", cached.message, ago(now, cached.checked_at))),
+        Some(cached) => check("updater", HealthStatus::Warn, format!("{} ({})", cached.message, ago(now, cached.checked_at))),
+        None => check("updater", HealthStatus::Warn, "Not checked since startup"),
+    });
+
+    let errors = error_counters::errors().snapshot();
+    let over_threshold: Vec<String> = errors.over_threshold()
+        .map(|category| format!("{:?} ({}/min)", category.category, category.last_minute))
+        .collect();
+    let total: u64 = errors.categories.iter().map(|category| category.count).sum();
+    checks.push(if !over_threshold.is_empty() {
+        check("errors", HealthStatus::Warn, format!("Error rate over threshold: {}", over_threshold.join(", ")))
+    } else if total > 0 {
+        check("errors", HealthStatus::Pass, format!("{} error(s) recorded (counters reset {}), none over threshold", total, ago(now, errors.since)))
+    } else {
+        check("errors", HealthStatus::Pass, "No backend errors recorded")
+    });
+
+    for source in PollSource::ALL {
+        let name = format!("{}_polling", source.name());
+        let stalls = monitor.pipeline_status(source, true).stalls;
+        checks.push(match monitor.heartbeat_age_ms(source) {
+            None => check(&name, HealthStatus::Fail, format!("{} polling thread has not started", source.name())),
+            Some(age) if age > HEARTBEAT_STALE_MS => check(
+                &name,
+                HealthStatus::Fail,
+                format!("{} polling thread stalled; last heartbeat {}ms ago", source.name(), age),
+            ),
+            Some(age) if stalls > 0 => check(
+                &name,
+                HealthStatus::Warn,
+                format!("{} polling thread alive (heartbeat {}ms ago), but stalled {} time(s)", source.name(), age, stalls),
+            ),
+            Some(age) => check(&name, HealthStatus::Pass, format!("{} polling thread alive (heartbeat {}ms ago)", source.name(), age)),
+        });
+    }
+
+    HealthReport {
+        overall: checks.iter().map(|check| check.status).max().unwrap_or(HealthStatus::Pass),
+        checks,
+        checked_at: now,
+    }
+}
+
+fn check(name: &str, status: HealthStatus, message: impl Into<String>) -> HealthCheck {
+    HealthCheck {
+        name: name.to_string(),
+        status,
+        message: message.into(),
+    }
+}
+
+fn ago(now: u64, then: u64) -> String {
+    format!("{}s ago", now.saturating_sub(then) / 1000)
+}
+
+fn now_ms() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_millis() as u64
+}
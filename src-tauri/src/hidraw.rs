@@ -0,0 +1,695 @@
+use crate::locking::MutexExt;
+use crate::session_summary;
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+use std::sync::{Mutex, OnceLock};
+use std::time::Duration;
+use tracing::{debug, info, warn};
+
+/// How often the lizard mode reports are resent. The Deck turns lizard mode back on by
+/// itself after a few seconds without them, so a crashed or hung app never leaves it off.
+pub const LIZARD_HEARTBEAT_INTERVAL: Duration = Duration::from_secs(1);
+/// Longest the shutdown path waits for lizard mode to be restored.
+pub const RESTORE_TIMEOUT: Duration = Duration::from_millis(500);
+
+const HIDRAW_CLASS: &str = "/sys/class/hidraw";
+const VALVE_VENDOR_ID: u32 = 0x28de;
+const STEAM_DECK_PRODUCT_ID: u32 = 0x1205;
+/// The controller interface; the Deck's others are its keyboard and mouse emulation
+const CONTROLLER_INTERFACE: &str = "input2";
+/// Name of the kernel's hid-steam driver, which manages lizard mode itself
+const HID_STEAM_DRIVER: &str = "steam";
+#[cfg(target_os = "linux")]
+const FEATURE_REPORT_SIZE: usize = 64;
+
+// Steam Controller feature report commands and settings, as the kernel's hid-steam uses them
+#[cfg(target_os = "linux")]
+const ID_CLEAR_DIGITAL_MAPPINGS: u8 = 0x81;
+#[cfg(target_os = "linux")]
+const ID_SET_DEFAULT_DIGITAL_MAPPINGS: u8 = 0x85;
+#[cfg(target_os = "linux")]
+const ID_SET_SETTINGS_VALUES: u8 = 0x87;
+#[cfg(target_os = "linux")]
+const ID_LOAD_DEFAULT_SETTINGS: u8 = 0x8e;
+const ID_GET_ATTRIBUTES_VALUES: u8 = 0x83;
+#[cfg(target_os = "linux")]
+const ID_TRIGGER_HAPTIC_PULSE: u8 = 0x8f;
+#[cfg(target_os = "linux")]
+const SETTING_LEFT_TRACKPAD_MODE: u8 = 0x07;
+#[cfg(target_os = "linux")]
+const SETTING_RIGHT_TRACKPAD_MODE: u8 = 0x08;
+#[cfg(target_os = "linux")]
+const SETTING_LEFT_TRACKPAD_CLICK_PRESSURE: u8 = 0x34;
+#[cfg(target_os = "linux")]
+const SETTING_RIGHT_TRACKPAD_CLICK_PRESSURE: u8 = 0x35;
+#[cfg(target_os = "linux")]
+const TRACKPAD_NONE: u16 = 0x07;
+/// Trackpad presses never count as a click
+#[cfg(target_os = "linux")]
+const CLICK_PRESSURE_NEVER: u16 = 0xffff;
+// Attribute tags in the `ID_GET_ATTRIBUTES_VALUES` reply
+const ATTRIB_PRODUCT_ID: u8 = 0x01;
+const ATTRIB_FIRMWARE_BUILD_TIME: u8 = 0x04;
+const ATTRIB_BOARD_REVISION: u8 = 0x09;
+const ATTRIB_BOOTLOADER_BUILD_TIME: u8 = 0x0a;
+
+/// A Valve controller's hidraw node.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HidrawNode {
+    /// `/dev/hidrawN`
+    pub device_path: String,
+    pub name: String,
+    pub product_id: u32,
+    /// Kernel driver bound to the device, e.g. `steam` or `hid-generic`
+    pub driver: Option<String>,
+}
+
+impl HidrawNode {
+    /// hid-steam turns lizard mode off while its gamepad node is open and treats anything
+    /// opening its hidraw node as the Steam client, dropping that gamepad node; so lizard
+    /// mode is left to it.
+    pub fn managed_by_kernel(&self) -> bool {
+        self.driver.as_deref() == Some(HID_STEAM_DRIVER)
+    }
+}
+
+/// The controller interfaces of connected Valve controllers.
+pub fn find_controller_nodes() -> Vec<HidrawNode> {
+    let Ok(entries) = std::fs::read_dir(HIDRAW_CLASS) else {
+        return Vec::new();
+    };
+    let mut nodes: Vec<HidrawNode> = entries
+        .flatten()
+        .filter_map(|entry| {
+            let device = entry.path().join("device");
+            let uevent = std::fs::read_to_string(device.join("uevent")).ok()?;
+            let field = |key: &str| {
+                uevent
+                    .lines()
+                    .find_map(|line| line.strip_prefix(key)?.strip_prefix('='))
+                    .map(str::to_string)
+            };
+            // HID_ID is bus:vendor:product in hex, e.g. 0003:000028DE:00001205
+            let hid_id = field("HID_ID")?;
+            let mut ids = hid_id.split(':').skip(1).map(|id| u32::from_str_radix(id, 16).ok());
+            let (vendor_id, product_id) = (ids.next()??, ids.next()??);
+            let controller_interface = field("HID_PHYS").is_some_and(|phys| phys.ends_with(CONTROLLER_INTERFACE));
+            if vendor_id != VALVE_VENDOR_ID || !controller_interface {
+                return None;
+            }
+            Some(HidrawNode {
+                device_path: Path::new("/dev").join(entry.file_name()).to_string_lossy().to_string(),
+                name: field("HID_NAME").unwrap_or_default(),
+                product_id,
+                driver: driver_name(&device),
+            })
+        })
+        .collect();
+    nodes.sort_by(|a, b| a.device_path.cmp(&b.device_path));
+    nodes
+}
+
+fn driver_name(device: &Path) -> Option<String> {
+    let driver = std::fs::read_link(device.join("driver")).ok()?;
+    Some(driver.file_name()?.to_string_lossy().to_string())
+}
+
+/// `HIDIOCSFEATURE(len)`: `_IOWR('H', 0x06, len)`.
+#[cfg(target_os = "linux")]
+const fn hidiocsfeature(len: usize) -> u64 {
+    (3 << 30) | ((len as u64) << 16) | ((b'H' as u64) << 8) | 0x06
+}
+
+/// `HIDIOCGFEATURE(len)`: `_IOWR('H', 0x07, len)`.
+#[cfg(target_os = "linux")]
+const fn hidiocgfeature(len: usize) -> u64 {
+    (3 << 30) | ((len as u64) << 16) | ((b'H' as u64) << 8) | 0x07
+}
+
+/// Sends one feature report: report ID 0, then `command`, the payload length and payload.
+#[cfg(target_os = "linux")]
+fn send_feature_report(file: &std::fs::File, command: u8, payload: &[u8]) -> Result<(), String> {
+    use std::os::fd::AsRawFd;
+    // The command and length bytes come first
+    let max_payload = FEATURE_REPORT_SIZE - 2;
+    if payload.len() > max_payload {
+        return Err(format!(
+            "feature report {:#04x} payload is {} bytes; at most {} fit",
+            command,
+            payload.len(),
+            max_payload
+        ));
+    }
+    let mut report = [0u8; FEATURE_REPORT_SIZE + 1];
+    report[1] = command;
+    report[2] = payload.len() as u8;
+    report[3..3 + payload.len()].copy_from_slice(payload);
+    // SAFETY: the request encodes the buffer's length and the buffer outlives the call
+    let result = unsafe { libc::ioctl(file.as_raw_fd(), hidiocsfeature(report.len()) as _, report.as_mut_ptr()) };
+    if result < 0 {
+        return Err(format!("feature report {:#04x} failed: {}", command, std::io::Error::last_os_error()));
+    }
+    Ok(())
+}
+
+/// Sends `command` and reads the controller's answer, from the command byte on.
+#[cfg(target_os = "linux")]
+fn query_feature_report(file: &std::fs::File, command: u8) -> Result<Vec<u8>, String> {
+    use std::os::fd::AsRawFd;
+    send_feature_report(file, command, &[])?;
+    let mut report = [0u8; FEATURE_REPORT_SIZE + 1];
+    // SAFETY: the request encodes the buffer's length and the buffer outlives the call
+    let result = unsafe { libc::ioctl(file.as_raw_fd(), hidiocgfeature(report.len()) as _, report.as_mut_ptr()) };
+    if result < 0 {
+        return Err(format!("reading the reply to {:#04x} failed: {}", command, std::io::Error::last_os_error()));
+    }
+    // The first byte is the report ID
+    Ok(report[1..].to_vec())
+}
+
+/// Clears the keyboard mappings (Enter, Escape, arrows, trigger clicks) and turns the
+/// trackpads' mouse off.
+#[cfg(target_os = "linux")]
+fn disable_lizard_mode(file: &std::fs::File) -> Result<(), String> {
+    send_feature_report(file, ID_CLEAR_DIGITAL_MAPPINGS, &[])?;
+    let mut settings = Vec::new();
+    for (setting, value) in [
+        (SETTING_LEFT_TRACKPAD_MODE, TRACKPAD_NONE),
+        (SETTING_RIGHT_TRACKPAD_MODE, TRACKPAD_NONE),
+        (SETTING_LEFT_TRACKPAD_CLICK_PRESSURE, CLICK_PRESSURE_NEVER),
+        (SETTING_RIGHT_TRACKPAD_CLICK_PRESSURE, CLICK_PRESSURE_NEVER),
+    ] {
+        settings.push(setting);
+        settings.extend_from_slice(&value.to_le_bytes());
+    }
+    send_feature_report(file, ID_SET_SETTINGS_VALUES, &settings)
+}
+
+#[cfg(target_os = "linux")]
+fn enable_lizard_mode(file: &std::fs::File) -> Result<(), String> {
+    send_feature_report(file, ID_SET_DEFAULT_DIGITAL_MAPPINGS, &[])?;
+    send_feature_report(file, ID_LOAD_DEFAULT_SETTINGS, &[])
+}
+
+#[cfg(target_os = "linux")]
+fn open_node(node: &HidrawNode) -> Result<std::fs::File, String> {
+    std::fs::OpenOptions::new()
+        .read(true)
+        .write(true)
+        .open(&node.device_path)
+        .map_err(|e| format!("Failed to open {}: {}", node.device_path, e))
+}
+
+#[cfg(not(target_os = "linux"))]
+fn open_node(_node: &HidrawNode) -> Result<std::fs::File, String> {
+    Err("hidraw is only available on Linux".to_string())
+}
+
+#[cfg(not(target_os = "linux"))]
+fn disable_lizard_mode(_file: &std::fs::File) -> Result<(), String> {
+    Err("hidraw is only available on Linux".to_string())
+}
+
+#[cfg(not(target_os = "linux"))]
+fn enable_lizard_mode(_file: &std::fs::File) -> Result<(), String> {
+    Ok(())
+}
+
+/// Answer to `get_lizard_mode` / `set_lizard_mode`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LizardModeStatus {
+    /// False when the user asked for lizard mode off while the window is focused
+    pub enabled: bool,
+    /// Whether this app has it turned off right now
+    pub suppressed: bool,
+    pub device_path: Option<String>,
+    /// Set when the controller's driver handles lizard mode itself
+    pub managed_by_kernel: bool,
+    pub last_error: Option<String>,
+}
+
+/// The node lizard mode is off on, kept open until it's turned back on.
+struct SuppressedNode {
+    node: HidrawNode,
+    file: std::fs::File,
+}
+
+impl SuppressedNode {
+    /// Sends on the open node, reopening it once if that fails (e.g. after a suspend).
+    fn send(&mut self, send: fn(&std::fs::File) -> Result<(), String>) -> Result<(), String> {
+        if send(&self.file).is_ok() {
+            return Ok(());
+        }
+        self.file = open_node(&self.node)?;
+        send(&self.file)
+    }
+}
+
+struct LizardState {
+    enabled: bool,
+    focused: bool,
+    suppressed: Option<SuppressedNode>,
+    last_node: Option<HidrawNode>,
+    last_error: Option<String>,
+}
+
+/// Keeps the built-in controller's keyboard and mouse emulation off while the window is
+/// focused and the user asked for that, and on otherwise.
+pub struct LizardMode {
+    state: Mutex<LizardState>,
+}
+
+impl Default for LizardMode {
+    fn default() -> Self {
+        Self {
+            state: Mutex::new(LizardState {
+                enabled: true,
+                focused: false,
+                suppressed: None,
+                last_node: None,
+                last_error: None,
+            }),
+        }
+    }
+}
+
+impl LizardMode {
+    pub fn set_enabled(&self, enabled: bool) -> LizardModeStatus {
+        let mut state = self.state.lock_or_recover();
+        state.enabled = enabled;
+        apply(&mut state);
+        status(&state)
+    }
+
+    /// Lizard mode comes back as soon as the window loses focus.
+    pub fn set_focused(&self, focused: bool) {
+        let mut state = self.state.lock_or_recover();
+        if state.focused != focused {
+            state.focused = focused;
+            apply(&mut state);
+        }
+    }
+
+    /// Resends the reports while lizard mode is suppressed, before the Deck's own timeout.
+    pub fn heartbeat(&self) {
+        let mut state = self.state.lock_or_recover();
+        let Some(suppressed) = state.suppressed.as_mut() else {
+            return;
+        };
+        if let Err(e) = suppressed.send(disable_lizard_mode) {
+            warn!("Lost the lizard mode heartbeat: {}", e);
+            state.suppressed = None;
+            state.last_error = Some(e);
+        }
+    }
+
+    /// Turns lizard mode back on for exit. Doesn't change the setting.
+    pub fn restore(&self) {
+        let mut state = self.state.lock_or_recover();
+        state.focused = false;
+        apply(&mut state);
+    }
+
+    pub fn status(&self) -> LizardModeStatus {
+        status(&self.state.lock_or_recover())
+    }
+}
+
+fn apply(state: &mut LizardState) {
+    let suppress = !state.enabled && state.focused;
+    if !suppress {
+        if let Some(mut suppressed) = state.suppressed.take() {
+            match suppressed.send(enable_lizard_mode) {
+                Ok(()) => info!("Lizard mode restored on {}", suppressed.node.device_path),
+                // The Deck's timeout restores it anyway once the heartbeat stops
+                Err(e) => state.last_error = Some(e),
+            }
+        }
+        return;
+    }
+    if state.suppressed.is_some() {
+        return;
+    }
+    let Some(node) = find_controller_nodes().into_iter().next() else {
+        state.last_error = Some("No Steam controller hidraw node found".to_string());
+        return;
+    };
+    state.last_node = Some(node.clone());
+    if node.managed_by_kernel() {
+        state.last_error = None;
+        return;
+    }
+    match open_node(&node).and_then(|file| disable_lizard_mode(&file).map(|()| file)) {
+        Ok(file) => {
+            info!("Lizard mode off on {} while the window is focused", node.device_path);
+            state.last_error = None;
+            state.suppressed = Some(SuppressedNode { node, file });
+        }
+        Err(e) => {
+            warn!("Failed to turn lizard mode off: {}", e);
+            state.last_error = Some(e);
+        }
+    }
+}
+
+fn status(state: &LizardState) -> LizardModeStatus {
+    LizardModeStatus {
+        enabled: state.enabled,
+        suppressed: state.suppressed.is_some(),
+        device_path: state.last_node.as_ref().map(|node| node.device_path.clone()),
+        managed_by_kernel: state.last_node.as_ref().is_some_and(HidrawNode::managed_by_kernel),
+        last_error: state.last_error.clone(),
+    }
+}
+
+/// Longest haptic `play_deck_haptic` plays.
+pub const MAX_HAPTIC_DURATION_MS: u32 = 2000;
+/// Gain sent at intensity 0, in dB; intensity 1 is 0dB.
+const MIN_HAPTIC_GAIN_DB: f32 = -24.0;
+/// A buzz is pulses this long with gaps as long, i.e. 200Hz.
+const BUZZ_HALF_PERIOD_US: u32 = 2500;
+
+/// Which trackpad's haptic actuator plays.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum HapticSide {
+    Left,
+    Right,
+    Both,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum HapticPattern {
+    /// One pulse lasting the whole duration
+    Tick,
+    /// Two pulses with a gap, each a third of the duration
+    DoubleTick,
+    /// 200Hz pulses for the duration
+    Buzz,
+}
+
+impl HapticPattern {
+    /// On time and off time in µs, and the number of pulses.
+    fn pulses(self, duration_ms: u32) -> (u16, u16, u16) {
+        let duration_us = duration_ms * 1000;
+        let clamp = |us: u32| us.min(u16::MAX as u32) as u16;
+        match self {
+            Self::Tick => (clamp(duration_us), 0, 1),
+            Self::DoubleTick => (clamp(duration_us / 3), clamp(duration_us / 3), 2),
+            Self::Buzz => (
+                clamp(BUZZ_HALF_PERIOD_US),
+                clamp(BUZZ_HALF_PERIOD_US),
+                clamp((duration_us / (2 * BUZZ_HALF_PERIOD_US)).max(1)),
+            ),
+        }
+    }
+}
+
+/// A haptic played as confirmation, e.g. after a combination effect's light send succeeds.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct HapticCue {
+    pub side: HapticSide,
+    pub pattern: HapticPattern,
+    /// 0 to 1
+    pub intensity: f32,
+    pub duration_ms: u32,
+}
+
+impl Default for HapticCue {
+    fn default() -> Self {
+        Self {
+            side: HapticSide::Right,
+            pattern: HapticPattern::Tick,
+            intensity: 0.5,
+            duration_ms: 20,
+        }
+    }
+}
+
+impl HapticCue {
+    pub fn validate(&self) -> Result<(), String> {
+        if !(0.0..=1.0).contains(&self.intensity) {
+            return Err(format!("Haptic intensity must be between 0 and 1, got {}", self.intensity));
+        }
+        if !(1..=MAX_HAPTIC_DURATION_MS).contains(&self.duration_ms) {
+            return Err(format!(
+                "Haptic duration must be between 1 and {}ms, got {}",
+                MAX_HAPTIC_DURATION_MS, self.duration_ms
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// Whether the Deck's trackpad haptics can be driven from here.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum HapticCapability {
+    Available,
+    /// hid-steam owns the controller; its hidraw node is left alone (see [`HidrawNode::managed_by_kernel`])
+    KernelManaged,
+    /// The controller's hidraw node can't be opened for writing
+    NoPermission,
+    /// No Steam Deck controller, e.g. other hardware
+    NoController,
+}
+
+impl HapticCapability {
+    pub fn hint(self) -> &'static str {
+        match self {
+            Self::Available => "Trackpad haptics are available",
+            Self::KernelManaged => "The hid-steam driver owns the built-in controller, so trackpad haptics aren't played",
+            Self::NoPermission => "Trackpad haptics need write access to the controller's hidraw node; see the permissions check",
+            Self::NoController => "No Steam Deck controller, so there are no trackpad haptics",
+        }
+    }
+}
+
+/// Answer to `play_deck_haptic`; anything but `Available` means nothing was played.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HapticOutcome {
+    pub played: bool,
+    pub capability: HapticCapability,
+}
+
+fn deck_node() -> Option<HidrawNode> {
+    find_controller_nodes().into_iter().find(|node| node.product_id == STEAM_DECK_PRODUCT_ID)
+}
+
+pub fn haptic_capability() -> HapticCapability {
+    match deck_node() {
+        None => HapticCapability::NoController,
+        Some(node) if node.managed_by_kernel() => HapticCapability::KernelManaged,
+        Some(node) => match open_for_haptics(&node) {
+            Ok(_) => HapticCapability::Available,
+            Err(_) => HapticCapability::NoPermission,
+        },
+    }
+}
+
+/// Pulses a trackpad actuator. Quietly does nothing where there's no usable Deck controller.
+pub fn play_deck_haptic(cue: &HapticCue) -> Result<HapticOutcome, String> {
+    cue.validate()?;
+    let skipped = |capability| Ok(HapticOutcome { played: false, capability });
+    let Some(node) = deck_node() else {
+        return skipped(HapticCapability::NoController);
+    };
+    if node.managed_by_kernel() {
+        return skipped(HapticCapability::KernelManaged);
+    }
+    let Ok(file) = open_for_haptics(&node) else {
+        return skipped(HapticCapability::NoPermission);
+    };
+    send_haptic_pulse(&file, &haptic_payload(cue))?;
+    Ok(HapticOutcome {
+        played: true,
+        capability: HapticCapability::Available,
+    })
+}
+
+#[cfg(target_os = "linux")]
+fn open_for_haptics(node: &HidrawNode) -> Result<std::fs::File, String> {
+    open_node(node)
+}
+
+#[cfg(not(target_os = "linux"))]
+fn open_for_haptics(_node: &HidrawNode) -> Result<(), String> {
+    Err("hidraw is only available on Linux".to_string())
+}
+
+/// Payload: pad, on µs, off µs, count (u16 LE each), gain in dB. The report numbers the
+/// pads the other way round from `HapticSide`: 0 is right and 1 is left.
+fn haptic_payload(cue: &HapticCue) -> Vec<u8> {
+    let pad = match cue.side {
+        HapticSide::Right => 0,
+        HapticSide::Left => 1,
+        HapticSide::Both => 2,
+    };
+    let (on_us, off_us, count) = cue.pattern.pulses(cue.duration_ms);
+    let gain = (MIN_HAPTIC_GAIN_DB * (1.0 - cue.intensity)).round() as i8;
+    let mut payload = vec![pad];
+    for value in [on_us, off_us, count] {
+        payload.extend_from_slice(&value.to_le_bytes());
+    }
+    payload.push(gain as u8);
+    payload
+}
+
+#[cfg(target_os = "linux")]
+fn send_haptic_pulse(file: &std::fs::File, payload: &[u8]) -> Result<(), String> {
+    send_feature_report(file, ID_TRIGGER_HAPTIC_PULSE, payload)
+}
+
+#[cfg(not(target_os = "linux"))]
+fn send_haptic_pulse(_file: &(), _payload: &[u8]) -> Result<(), String> {
+    Ok(())
+}
+
+/// Build info the built-in controller reports about itself. Input quirks such as the
+/// paddle codes and haptics vary between firmware revisions.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ControllerFirmwareInfo {
+    /// Unix time the firmware was built, which Steam uses as its version
+    pub firmware_build_time: u32,
+    /// `firmware_build_time` in UTC, `YYYY-MM-DD HH:MM`
+    pub firmware_build: String,
+    pub bootloader_build_time: Option<u32>,
+    pub board_revision: Option<u32>,
+    pub product_id: Option<u32>,
+}
+
+/// Reads an `ID_GET_ATTRIBUTES_VALUES` reply: the command, the payload length, then five
+/// bytes per attribute, a tag and a little-endian value.
+pub fn parse_firmware_attributes(reply: &[u8]) -> Result<ControllerFirmwareInfo, String> {
+    let [command, length, rest @ ..] = reply else {
+        return Err("The attributes reply is empty".to_string());
+    };
+    if *command != ID_GET_ATTRIBUTES_VALUES {
+        return Err(format!("Expected an attributes reply, got one to {:#04x}", command));
+    }
+    let payload = rest
+        .get(..*length as usize)
+        .ok_or_else(|| format!("The attributes reply is cut short at {} of {} bytes", rest.len(), length))?;
+    let (mut firmware_build_time, mut bootloader_build_time, mut board_revision, mut product_id) = (None, None, None, None);
+    for attribute in payload.chunks_exact(5) {
+        let value = Some(u32::from_le_bytes([attribute[1], attribute[2], attribute[3], attribute[4]]));
+        match attribute[0] {
+            ATTRIB_FIRMWARE_BUILD_TIME => firmware_build_time = value,
+            ATTRIB_BOOTLOADER_BUILD_TIME => bootloader_build_time = value,
+            ATTRIB_BOARD_REVISION => board_revision = value,
+            ATTRIB_PRODUCT_ID => product_id = value,
+            _ => {}
+        }
+    }
+    let firmware_build_time = firmware_build_time.ok_or_else(|| "The attributes reply has no firmware build time".to_string())?;
+    Ok(ControllerFirmwareInfo {
+        firmware_build_time,
+        firmware_build: format_build_time(firmware_build_time),
+        bootloader_build_time,
+        board_revision,
+        product_id,
+    })
+}
+
+fn format_build_time(secs: u32) -> String {
+    let (year, month, day) = session_summary::civil_from_days(i64::from(secs / 86_400));
+    let minute_of_day = secs % 86_400 / 60;
+    format!("{:04}-{:02}-{:02} {:02}:{:02}", year, month, day, minute_of_day / 60, minute_of_day % 60)
+}
+
+static CONTROLLER_FIRMWARE: OnceLock<Option<ControllerFirmwareInfo>> = OnceLock::new();
+
+/// The built-in controller's build info, asked for once a session as it means opening the
+/// device. `None` where it can't be had, with the reason logged. While hid-steam holds the
+/// controller it's only asked with Steam running: hid-steam drops its gamepad while anything
+/// but Steam has the node open, and Steam opening it already has.
+pub fn controller_firmware_info(steam_running: Option<bool>) -> Option<ControllerFirmwareInfo> {
+    if let Some(cached) = CONTROLLER_FIRMWARE.get() {
+        return cached.clone();
+    }
+    let node = deck_node();
+    if node.as_ref().is_some_and(|node| node.managed_by_kernel() && steam_running != Some(true)) {
+        debug!("Not asking for the controller firmware: hid-steam holds the controller and Steam isn't running");
+        return None;
+    }
+    let result = match node {
+        Some(node) => query_firmware(&node),
+        None => Err("no Steam Deck controller hidraw node".to_string()),
+    };
+    let info = match result {
+        Ok(info) => {
+            info!("Controller firmware built {}", info.firmware_build);
+            Some(info)
+        }
+        Err(reason) => {
+            info!("Controller firmware info unavailable: {}", reason);
+            None
+        }
+    };
+    CONTROLLER_FIRMWARE.get_or_init(|| info).clone()
+}
+
+#[cfg(target_os = "linux")]
+fn query_firmware(node: &HidrawNode) -> Result<ControllerFirmwareInfo, String> {
+    let file = open_node(node)?;
+    parse_firmware_attributes(&query_feature_report(&file, ID_GET_ATTRIBUTES_VALUES)?)
+}
+
+#[cfg(not(target_os = "linux"))]
+fn query_firmware(_node: &HidrawNode) -> Result<ControllerFirmwareInfo, String> {
+    Err("hidraw is only available on Linux".to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// An attributes reply: command, payload length, then tag and little-endian value pairs.
+    fn reply(attributes: &[(u8, u32)]) -> Vec<u8> {
+        let mut reply = vec![0x83, (attributes.len() * 5) as u8];
+        for (tag, value) in attributes {
+            reply.push(*tag);
+            reply.extend_from_slice(&value.to_le_bytes());
+        }
+        // Feature reports are always padded to 64 bytes
+        reply.resize(64, 0);
+        reply
+    }
+
+    #[test]
+    fn build_info_is_read_from_the_attributes() {
+        let info = parse_firmware_attributes(&reply(&[(0x01, 0x1205), (0x02, 0), (0x04, 1_700_000_000), (0x09, 3), (0x0a, 1_650_000_000)])).unwrap();
+        assert_eq!(
+            info,
+            ControllerFirmwareInfo {
+                firmware_build_time: 1_700_000_000,
+                firmware_build: "2023-11-14 22:13".to_string(),
+                bootloader_build_time: Some(1_650_000_000),
+                board_revision: Some(3),
+                product_id: Some(0x1205),
+            }
+        );
+    }
+
+    #[test]
+    fn only_the_firmware_build_time_is_required() {
+        let info = parse_firmware_attributes(&reply(&[(0x04, 1_700_000_000)])).unwrap();
+        assert_eq!(info.board_revision, None);
+        assert_eq!(info.product_id, None);
+
+        assert!(parse_firmware_attributes(&reply(&[(0x01, 0x1205)])).is_err());
+    }
+
+    #[test]
+    fn other_replies_and_short_reports_are_rejected() {
+        let mut other = reply(&[(0x04, 1_700_000_000)]);
+        other[0] = 0x87;
+        assert!(parse_firmware_attributes(&other).is_err());
+
+        let full = reply(&[(0x01, 0x1205), (0x04, 1_700_000_000)]);
+        assert!(parse_firmware_attributes(&full[..8]).is_err());
+        assert!(parse_firmware_attributes(&[]).is_err());
+    }
+}
@@ -0,0 +1,73 @@
+use crate::error::AppError;
+use crate::locking::MutexExt;
+use reqwest::blocking::Client;
+use reqwest::Url;
+use std::collections::{HashMap, HashSet};
+use std::sync::Mutex;
+use std::time::Duration;
+
+pub const CONNECT_TIMEOUT: Duration = Duration::from_secs(3);
+pub const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+/// How long an idle connection is kept for the next send to the same server.
+const POOL_IDLE_TIMEOUT: Duration = Duration::from_secs(90);
+
+/// HTTP clients for everything the app sends, built once so repeat requests to a server
+/// reuse its connection and TLS session. Hosts allowed to use a self-signed certificate
+/// get their own client, built the first time it's needed and kept after that.
+pub struct HttpClients {
+    shared: Mutex<Client>,
+    self_signed_hosts: Mutex<HashSet<String>>,
+    self_signed_clients: Mutex<HashMap<String, Client>>,
+}
+
+impl HttpClients {
+    pub fn new() -> Result<Self, AppError> {
+        Ok(Self {
+            shared: Mutex::new(builder().build()?),
+            self_signed_hosts: Mutex::new(HashSet::new()),
+            self_signed_clients: Mutex::new(HashMap::new()),
+        })
+    }
+
+    /// Replaces the hosts whose certificates aren't verified. Clients for hosts no longer
+    /// listed are dropped, closing their connections.
+    pub fn set_self_signed_hosts(&self, hosts: &[String]) {
+        let hosts: HashSet<String> = hosts.iter().map(|host| host.trim().to_ascii_lowercase()).collect();
+        self.self_signed_clients.lock_or_recover().retain(|host, _| hosts.contains(host));
+        *self.self_signed_hosts.lock_or_recover() = hosts;
+    }
+
+    /// Rebuilds every client, dropping pooled connections that may have gone stale (e.g.
+    /// across a suspend). Requests already in flight finish on the old ones.
+    pub fn reset_connections(&self) -> Result<(), AppError> {
+        *self.shared.lock_or_recover() = builder().build()?;
+        self.self_signed_clients.lock_or_recover().clear();
+        Ok(())
+    }
+
+    /// The client to use for `url`. Cloning a client is cheap and shares its pool.
+    pub fn for_url(&self, url: &Url) -> Result<Client, AppError> {
+        let Some(host) = url.host_str().map(str::to_ascii_lowercase) else {
+            return Ok(self.shared.lock_or_recover().clone());
+        };
+        if !self.self_signed_hosts.lock_or_recover().contains(&host) {
+            return Ok(self.shared.lock_or_recover().clone());
+        }
+        let mut clients = self.self_signed_clients.lock_or_recover();
+        if let Some(client) = clients.get(&host) {
+            return Ok(client.clone());
+        }
+        let client = builder().danger_accept_invalid_certs(true).build()?;
+        clients.insert(host, client.clone());
+        Ok(client)
+    }
+}
+
+fn builder() -> reqwest::blocking::ClientBuilder {
+    // Proxies come from the usual environment variables, which reqwest reads itself
+    Client::builder()
+        .connect_timeout(CONNECT_TIMEOUT)
+        .timeout(REQUEST_TIMEOUT)
+        .pool_idle_timeout(POOL_IDLE_TIMEOUT)
+        .user_agent(concat!("steam-deck-controller/", env!("CARGO_PKG_VERSION")))
+}
@@ -0,0 +1,478 @@
+use crate::app_state::AppState;
+use crate::controller_event::{self, NormalizedInput, UnifiedControllerEvent};
+use crate::emit_interest::EventChannel;
+use crate::emitter::EventEmitter;
+use crate::input_pipeline::{InputMessage, InputSender};
+use crate::locking::MutexExt;
+use crate::processing_pause;
+use crate::recording::InputSession;
+use crate::thread_priority;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, AtomicU8, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant, SystemTime};
+use tauri::{AppHandle, Manager};
+use tracing::{info, warn};
+
+const SAMPLER_THREAD: &str = "iio-motion";
+pub const IIO_ROOT: &str = "/sys/bus/iio/devices";
+pub const MAX_SAMPLE_RATE_HZ: u32 = 1000;
+/// How often the sampler looks for IIO devices while it has none.
+const RESCAN_INTERVAL: Duration = Duration::from_secs(5);
+/// How often the sampler checks whether it's needed while it isn't sampling.
+const IDLE_INTERVAL: Duration = Duration::from_millis(500);
+const STANDARD_GRAVITY: f64 = 9.80665;
+/// Full scale of hid-steam's motion node (±2g, ±2048°/s at 16 bits), so IIO samples are
+/// normalized the same way its `ABS_*` values are.
+const ACCEL_FULL_SCALE_G: f64 = 2.0;
+const GYRO_FULL_SCALE_DPS: f64 = 2048.0;
+
+/// IIO channel prefix, axis suffix and the evdev motion axis it stands in for.
+const CHANNELS: [(&str, &str, &str); 6] = [
+    ("accel", "x", "ABS_X"),
+    ("accel", "y", "ABS_Y"),
+    ("accel", "z", "ABS_Z"),
+    ("anglvel", "x", "ABS_RX"),
+    ("anglvel", "y", "ABS_RY"),
+    ("anglvel", "z", "ABS_RZ"),
+];
+
+/// Where gyro and accelerometer samples come from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum MotionSource {
+    /// The controller's evdev motion node, or IIO sensors when there is none
+    #[default]
+    Auto,
+    Evdev,
+    Iio,
+    Off,
+}
+
+impl MotionSource {
+    fn from_u8(value: u8) -> Self {
+        match value {
+            1 => Self::Evdev,
+            2 => Self::Iio,
+            3 => Self::Off,
+            _ => Self::Auto,
+        }
+    }
+}
+
+/// Which motion source is in use and how the IIO fallback samples.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct MotionConfig {
+    pub source: MotionSource,
+    /// IIO device to read, by name (e.g. `bmi260`) or id (`iio:device0`); the first one
+    /// with motion channels when unset
+    pub iio_device: Option<String>,
+    pub iio_sample_rate_hz: u32,
+}
+
+impl Default for MotionConfig {
+    fn default() -> Self {
+        Self {
+            source: MotionSource::Auto,
+            iio_device: None,
+            iio_sample_rate_hz: 100,
+        }
+    }
+}
+
+impl MotionConfig {
+    pub fn validate(&self) -> Result<(), String> {
+        if !(1..=MAX_SAMPLE_RATE_HZ).contains(&self.iio_sample_rate_hz) {
+            return Err(format!("iio_sample_rate_hz must be between 1 and {}", MAX_SAMPLE_RATE_HZ));
+        }
+        Ok(())
+    }
+
+    fn sample_interval(&self) -> Duration {
+        Duration::from_secs(1) / self.iio_sample_rate_hz.max(1)
+    }
+}
+
+/// The configured source, for the evdev path to check per event without a lock.
+static SOURCE: AtomicU8 = AtomicU8::new(0);
+
+/// Whether motion read from evdev nodes is emitted; not when IIO was chosen or motion is off.
+pub fn evdev_motion_enabled() -> bool {
+    matches!(MotionSource::from_u8(SOURCE.load(Ordering::Relaxed)), MotionSource::Auto | MotionSource::Evdev)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ActiveMotionSource {
+    Evdev,
+    Iio,
+    None,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct IioDeviceInfo {
+    /// `iio:deviceN`
+    pub id: String,
+    pub name: String,
+    pub path: String,
+    /// The motion axes it has channels for
+    pub axes: Vec<String>,
+}
+
+/// Answer to `get_motion_status`, also part of the debug info.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MotionStatus {
+    pub config: MotionConfig,
+    pub active: ActiveMotionSource,
+    /// The IIO device being sampled
+    pub iio_device: Option<String>,
+    pub iio_devices: Vec<IioDeviceInfo>,
+    pub iio_samples: u64,
+    pub last_error: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum SensorKind {
+    Accel,
+    Gyro,
+}
+
+#[derive(Debug, Clone)]
+struct IioChannel {
+    axis: &'static str,
+    kind: SensorKind,
+    raw_path: PathBuf,
+    scale: f64,
+    offset: f64,
+}
+
+/// One reading of an IIO channel, normalized like the evdev motion axis it stands in for.
+#[derive(Debug, Clone, PartialEq)]
+pub struct IioSample {
+    pub axis: &'static str,
+    pub value: f32,
+    pub raw: i32,
+}
+
+/// An IIO device with accelerometer or gyro channels.
+#[derive(Debug, Clone)]
+pub struct IioDevice {
+    id: String,
+    name: String,
+    path: PathBuf,
+    channels: Vec<IioChannel>,
+}
+
+impl IioDevice {
+    /// `None` when the device has no accelerometer or gyro channels.
+    pub fn open(path: &Path) -> Option<Self> {
+        let id = path.file_name()?.to_string_lossy().to_string();
+        let name = read_trimmed(&path.join("name")).unwrap_or_else(|| id.clone());
+        let channels: Vec<IioChannel> = CHANNELS
+            .iter()
+            .filter_map(|&(prefix, suffix, axis)| {
+                let raw_path = path.join(format!("in_{}_{}_raw", prefix, suffix));
+                if !raw_path.exists() {
+                    return None;
+                }
+                // Scale and offset are per channel or shared by the channel type
+                let attribute = |attribute: &str| {
+                    read_f64(&path.join(format!("in_{}_{}_{}", prefix, suffix, attribute)))
+                        .or_else(|| read_f64(&path.join(format!("in_{}_{}", prefix, attribute))))
+                };
+                Some(IioChannel {
+                    axis,
+                    kind: if prefix == "accel" { SensorKind::Accel } else { SensorKind::Gyro },
+                    raw_path,
+                    scale: attribute("scale").unwrap_or(1.0),
+                    offset: attribute("offset").unwrap_or(0.0),
+                })
+            })
+            .collect();
+        (!channels.is_empty()).then_some(Self { id, name, path: path.to_path_buf(), channels })
+    }
+
+    pub fn info(&self) -> IioDeviceInfo {
+        IioDeviceInfo {
+            id: self.id.clone(),
+            name: self.name.clone(),
+            path: self.path.to_string_lossy().to_string(),
+            axes: self.channels.iter().map(|channel| channel.axis.to_string()).collect(),
+        }
+    }
+
+    fn matches(&self, wanted: &str) -> bool {
+        self.id == wanted || self.name == wanted
+    }
+
+    /// Reads every channel once. Accelerometer channels are in m/s² and gyro channels in
+    /// rad/s once scaled, per the IIO ABI.
+    pub fn sample(&self) -> Result<Vec<IioSample>, String> {
+        self.channels
+            .iter()
+            .map(|channel| {
+                let raw = read_trimmed(&channel.raw_path)
+                    .and_then(|raw| raw.parse::<i32>().ok())
+                    .ok_or_else(|| format!("Failed to read {}", channel.raw_path.display()))?;
+                let physical = (raw as f64 + channel.offset) * channel.scale;
+                let normalized = match channel.kind {
+                    SensorKind::Accel => physical / STANDARD_GRAVITY / ACCEL_FULL_SCALE_G,
+                    SensorKind::Gyro => physical.to_degrees() / GYRO_FULL_SCALE_DPS,
+                };
+                Ok(IioSample { axis: channel.axis, value: normalized.clamp(-1.0, 1.0) as f32, raw })
+            })
+            .collect()
+    }
+}
+
+/// IIO devices under `root` with motion channels, in id order.
+pub fn scan_iio_devices(root: &Path) -> Vec<IioDevice> {
+    let Ok(entries) = std::fs::read_dir(root) else {
+        return Vec::new();
+    };
+    let mut devices: Vec<IioDevice> = entries
+        .flatten()
+        .filter(|entry| entry.file_name().to_string_lossy().starts_with("iio:device"))
+        .filter_map(|entry| IioDevice::open(&entry.path()))
+        .collect();
+    devices.sort_by(|a, b| a.id.cmp(&b.id));
+    devices
+}
+
+fn read_trimmed(path: &Path) -> Option<String> {
+    std::fs::read_to_string(path).ok().map(|contents| contents.trim().to_string())
+}
+
+fn read_f64(path: &Path) -> Option<f64> {
+    read_trimmed(path)?.parse().ok()
+}
+
+struct SamplerStatus {
+    active: ActiveMotionSource,
+    iio_device: Option<String>,
+    iio_devices: Vec<IioDeviceInfo>,
+    last_error: Option<String>,
+}
+
+/// Picks the motion source and, when it's IIO, samples the sensors on a thread of its own
+/// and queues the samples for the dispatcher as motion axes.
+pub struct IioMotion {
+    config: Mutex<MotionConfig>,
+    status: Mutex<SamplerStatus>,
+    samples: AtomicU64,
+    input_tx: InputSender,
+    session: Arc<InputSession>,
+}
+
+impl IioMotion {
+    pub fn new(config: MotionConfig, session: Arc<InputSession>, input_tx: InputSender) -> Self {
+        SOURCE.store(config.source as u8, Ordering::Relaxed);
+        Self {
+            config: Mutex::new(config),
+            status: Mutex::new(SamplerStatus {
+                active: ActiveMotionSource::None,
+                iio_device: None,
+                iio_devices: Vec::new(),
+                last_error: None,
+            }),
+            samples: AtomicU64::new(0),
+            input_tx,
+            session,
+        }
+    }
+
+    pub fn configure(&self, config: MotionConfig) -> Result<(), String> {
+        config.validate()?;
+        SOURCE.store(config.source as u8, Ordering::Relaxed);
+        info!("Motion source {:?}, IIO at {}Hz", config.source, config.iio_sample_rate_hz);
+        *self.config.lock_or_recover() = config;
+        Ok(())
+    }
+
+    pub fn status(&self) -> MotionStatus {
+        let status = self.status.lock_or_recover();
+        MotionStatus {
+            config: self.config.lock_or_recover().clone(),
+            active: status.active,
+            iio_device: status.iio_device.clone(),
+            iio_devices: status.iio_devices.clone(),
+            iio_samples: self.samples.load(Ordering::Relaxed),
+            last_error: status.last_error.clone(),
+        }
+    }
+
+    /// Emits one queued IIO sample, unless processing or live input is paused. Only called
+    /// by the input dispatcher.
+    pub fn handle_event(&self, app: &impl EventEmitter, event: UnifiedControllerEvent) {
+        if processing_pause::processing_paused() || self.session.live_input_paused() {
+            return;
+        }
+        controller_event::emit(app, &event, EventChannel::Motion);
+    }
+
+    fn set_active(&self, active: ActiveMotionSource, device: Option<&IioDevice>) {
+        let mut status = self.status.lock_or_recover();
+        if status.active != active {
+            info!("Motion source now {:?}", active);
+        }
+        status.active = active;
+        status.iio_device = device.map(|device| device.name.clone());
+    }
+}
+
+/// Starts the sampler thread. It idles while the evdev node is the motion source.
+pub fn spawn_sampler(app: AppHandle) {
+    let state = app.state::<AppState>();
+    let motion = state.iio_motion.clone();
+    let evdev_manager = state.evdev_manager.clone();
+    let shutdown = state.shutdown.clone();
+    let raise_priority = !state.settings.get().disable_input_priority;
+    std::thread::Builder::new()
+        .name(SAMPLER_THREAD.to_string())
+        .spawn(move || {
+            thread_priority::raise_input_thread(SAMPLER_THREAD, raise_priority);
+            let mut devices: Vec<IioDevice> = Vec::new();
+            let mut scanned_at: Option<Instant> = None;
+            let mut last_values: HashMap<&'static str, f32> = HashMap::new();
+            while !shutdown.is_requested() {
+                let config = motion.config.lock_or_recover().clone();
+                let has_evdev_motion = evdev_manager.has_motion_device();
+                let use_iio = match config.source {
+                    MotionSource::Iio => true,
+                    MotionSource::Auto => !has_evdev_motion,
+                    MotionSource::Evdev | MotionSource::Off => false,
+                };
+                if !use_iio {
+                    let evdev_active = has_evdev_motion && config.source != MotionSource::Off;
+                    motion.set_active(if evdev_active { ActiveMotionSource::Evdev } else { ActiveMotionSource::None }, None);
+                    last_values.clear();
+                    std::thread::sleep(IDLE_INTERVAL);
+                    continue;
+                }
+                let rescan = match scanned_at {
+                    None => true,
+                    Some(at) => devices.is_empty() && at.elapsed() >= RESCAN_INTERVAL,
+                };
+                if rescan {
+                    devices = scan_iio_devices(Path::new(IIO_ROOT));
+                    scanned_at = Some(Instant::now());
+                    motion.status.lock_or_recover().iio_devices = devices.iter().map(IioDevice::info).collect();
+                }
+                let device = match &config.iio_device {
+                    Some(wanted) => devices.iter().find(|device| device.matches(wanted)),
+                    None => devices.first(),
+                };
+                let Some(device) = device else {
+                    motion.set_active(ActiveMotionSource::None, None);
+                    std::thread::sleep(IDLE_INTERVAL);
+                    continue;
+                };
+                motion.set_active(ActiveMotionSource::Iio, Some(device));
+
+                let started = Instant::now();
+                match device.sample() {
+                    Ok(samples) => {
+                        let sampled_at = SystemTime::now();
+                        let device_id = format!("iio:{}", device.name);
+                        let device_path = device.path.to_string_lossy().to_string();
+                        for sample in samples {
+                            if last_values.insert(sample.axis, sample.value) == Some(sample.value) {
+                                continue;
+                            }
+                            let event = UnifiedControllerEvent::from_iio(
+                                device_id.clone(),
+                                device_path.clone(),
+                                NormalizedInput::Axis { name: sample.axis.to_string(), value: sample.value },
+                                sample.raw,
+                                sampled_at,
+                            );
+                            motion.input_tx.send(InputMessage::Iio { event });
+                        }
+                        motion.samples.fetch_add(1, Ordering::Relaxed);
+                    }
+                    Err(e) => {
+                        warn!("IIO motion sampling failed, rescanning: {}", e);
+                        motion.status.lock_or_recover().last_error = Some(e);
+                        devices.clear();
+                        last_values.clear();
+                    }
+                }
+                std::thread::sleep(config.sample_interval().saturating_sub(started.elapsed()));
+            }
+        })
+        .expect("Failed to spawn IIO motion thread");
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::scratch_dir::ScratchDir;
+
+    fn value(samples: &[IioSample], axis: &str) -> f32 {
+        samples.iter().find(|sample| sample.axis == axis).unwrap_or_else(|| panic!("no {} sample", axis)).value
+    }
+
+    #[test]
+    fn samples_are_scaled_like_the_evdev_motion_node() {
+        let root = ScratchDir::new("iio-scaled");
+        root.attributes(
+            "iio:device0",
+            &[
+                ("name", "bmi260"),
+                // 1g on z with a shared scale, in m/s² per LSB
+                ("in_accel_x_raw", "0"),
+                ("in_accel_y_raw", "0"),
+                ("in_accel_z_raw", "8192"),
+                ("in_accel_scale", "0.001197097"),
+                // 1024°/s on x with a per-channel scale and offset, in rad/s per LSB
+                ("in_anglvel_x_raw", "1000"),
+                ("in_anglvel_x_scale", "0.0174533"),
+                ("in_anglvel_x_offset", "24"),
+            ],
+        );
+
+        let devices = scan_iio_devices(root.path());
+        assert_eq!(devices.len(), 1);
+        let info = devices[0].info();
+        assert_eq!(info.name, "bmi260");
+        assert_eq!(info.axes, ["ABS_X", "ABS_Y", "ABS_Z", "ABS_RX"]);
+
+        let samples = devices[0].sample().expect("sample");
+        assert_eq!(value(&samples, "ABS_X"), 0.0);
+        assert!((value(&samples, "ABS_Z") - 0.5).abs() < 0.001, "{:?}", samples);
+        assert!((value(&samples, "ABS_RX") - 0.5).abs() < 0.001, "{:?}", samples);
+        assert_eq!(samples.iter().find(|sample| sample.axis == "ABS_RX").unwrap().raw, 1000);
+    }
+
+    #[test]
+    fn devices_without_motion_channels_are_skipped() {
+        let root = ScratchDir::new("iio-skipped");
+        root.attributes("iio:device0", &[("name", "als"), ("in_illuminance_raw", "40")]);
+        root.attributes("iio:device1", &[("name", "gyro_3d"), ("in_anglvel_z_raw", "-50")]);
+        root.attributes("trigger0", &[("name", "not-a-device"), ("in_accel_x_raw", "1")]);
+
+        let devices = scan_iio_devices(root.path());
+        assert_eq!(devices.iter().map(|device| device.info().id).collect::<Vec<_>>(), ["iio:device1"]);
+        // No scale attribute: raw values are taken as already in rad/s
+        let samples = devices[0].sample().expect("sample");
+        assert_eq!(samples[0].value, -1.0);
+    }
+
+    #[test]
+    fn a_vanished_channel_fails_the_sample() {
+        let root = ScratchDir::new("iio-vanished");
+        let path = root.attributes("iio:device0", &[("in_accel_x_raw", "1"), ("in_accel_scale", "0.01")]);
+        let devices = scan_iio_devices(root.path());
+        std::fs::remove_file(path.join("in_accel_x_raw")).unwrap();
+
+        assert!(devices[0].sample().is_err());
+    }
+
+    #[test]
+    fn a_missing_root_has_no_devices() {
+        assert!(scan_iio_devices(Path::new("/nonexistent/iio/devices")).is_empty());
+    }
+}
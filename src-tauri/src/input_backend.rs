@@ -0,0 +1,160 @@
+use crate::health::PollSource;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+use tauri::AppHandle;
+use tracing::{info, warn};
+
+/// How often the maintenance thread runs each backend's `maintain`.
+pub const MAINTENANCE_INTERVAL: Duration = Duration::from_millis(250);
+
+/// A source of controller input with its own polling thread. Backends only decode and
+/// queue what they read; the input dispatcher applies it and emits `controller-event`s.
+pub trait InputBackend: Send + Sync {
+    fn source(&self) -> PollSource;
+
+    /// Runs once at startup, before the first poll.
+    fn init(&self, _app: &AppHandle) -> Result<(), String> {
+        Ok(())
+    }
+
+    /// Looks for devices again.
+    fn scan(&self, app: &AppHandle) -> Result<(), String>;
+
+    /// One polling pass: queues whatever input is pending for the dispatcher.
+    fn drain_events(&self, app: &AppHandle) -> Result<(), String>;
+
+    /// Slow upkeep (health checks, reopening devices, sysfs reads) run off the polling
+    /// thread, so a hung filesystem call can't hold up input.
+    fn maintain(&self, _app: &AppHandle) {}
+
+    fn current_states(&self) -> Vec<BackendControllerState>;
+
+    fn debug_info(&self) -> BackendDebugInfo;
+
+    /// Releases devices and flushes pending work on exit, after polling has stopped.
+    fn shutdown(&self);
+}
+
+/// A controller's current buttons and axes, named and scaled as in `controller-event`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BackendControllerState {
+    pub source: PollSource,
+    /// Same ID as `controller-event`'s `device_id`
+    pub device_id: String,
+    pub buttons: HashMap<String, bool>,
+    pub axes: HashMap<String, f32>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BackendDebugInfo {
+    pub source: PollSource,
+    /// Disabled backends keep their thread but skip polling
+    pub enabled: bool,
+    /// False when the backend couldn't start, e.g. gilrs fell back to its dummy backend
+    pub available: bool,
+    pub device_count: usize,
+    pub last_scan_time: Option<u64>,
+}
+
+struct RegisteredBackend {
+    backend: Arc<dyn InputBackend>,
+    enabled: AtomicBool,
+}
+
+/// Every input backend, in the order they're polled and shut down.
+#[derive(Default)]
+pub struct InputBackends {
+    backends: Vec<RegisteredBackend>,
+}
+
+impl InputBackends {
+    pub fn register(&mut self, backend: Arc<dyn InputBackend>, enabled: bool) {
+        if !enabled {
+            info!("Input backend {} is disabled", backend.source().name());
+        }
+        self.backends.push(RegisteredBackend { backend, enabled: AtomicBool::new(enabled) });
+    }
+
+    pub fn get(&self, source: PollSource) -> Option<Arc<dyn InputBackend>> {
+        self.find(source).map(|registered| registered.backend.clone())
+    }
+
+    fn find(&self, source: PollSource) -> Option<&RegisteredBackend> {
+        self.backends.iter().find(|registered| registered.backend.source() == source)
+    }
+
+    pub fn sources(&self) -> Vec<PollSource> {
+        self.backends.iter().map(|registered| registered.backend.source()).collect()
+    }
+
+    pub fn is_enabled(&self, source: PollSource) -> bool {
+        self.find(source).is_some_and(|registered| registered.enabled.load(Ordering::Relaxed))
+    }
+
+    /// Returns false if no backend has that source.
+    pub fn set_enabled(&self, source: PollSource, enabled: bool) -> bool {
+        let Some(registered) = self.find(source) else {
+            return false;
+        };
+        registered.enabled.store(enabled, Ordering::Relaxed);
+        info!("Input backend {} {}", source.name(), if enabled { "enabled" } else { "disabled" });
+        true
+    }
+
+    /// Runs `f` with `source`'s polling paused, then restores whether it was enabled.
+    /// A pass that had already started may still be finishing while `f` runs.
+    pub fn while_paused<T>(&self, source: PollSource, f: impl FnOnce() -> T) -> T {
+        let Some(registered) = self.find(source) else {
+            return f();
+        };
+        let was_enabled = registered.enabled.swap(false, Ordering::Relaxed);
+        let result = f();
+        if was_enabled {
+            registered.enabled.store(true, Ordering::Relaxed);
+        }
+        result
+    }
+
+    fn enabled(&self) -> impl Iterator<Item = &Arc<dyn InputBackend>> {
+        self.backends.iter()
+            .filter(|registered| registered.enabled.load(Ordering::Relaxed))
+            .map(|registered| &registered.backend)
+    }
+
+    pub fn init_all(&self, app: &AppHandle) {
+        for backend in self.enabled() {
+            if let Err(e) = backend.init(app) {
+                warn!("Failed to initialize {} input backend: {}", backend.source().name(), e);
+            }
+        }
+    }
+
+    pub fn maintain_all(&self, app: &AppHandle) {
+        for backend in self.enabled() {
+            backend.maintain(app);
+        }
+    }
+
+    /// Rescans every enabled backend, returning the errors of those that failed.
+    pub fn scan_all(&self, app: &AppHandle) -> Vec<(PollSource, String)> {
+        self.enabled()
+            .filter_map(|backend| backend.scan(app).err().map(|e| (backend.source(), e)))
+            .collect()
+    }
+
+    pub fn current_states(&self) -> Vec<BackendControllerState> {
+        self.enabled().flat_map(|backend| backend.current_states()).collect()
+    }
+
+    pub fn debug_info(&self) -> Vec<BackendDebugInfo> {
+        self.backends.iter()
+            .map(|registered| BackendDebugInfo {
+                enabled: registered.enabled.load(Ordering::Relaxed),
+                ..registered.backend.debug_info()
+            })
+            .collect()
+    }
+}
@@ -0,0 +1,364 @@
+use crate::app_state::AppState;
+use crate::controller_event::{NormalizedInput, UnifiedControllerEvent};
+use crate::evdev_types::EvdevControllerEvent;
+use crate::gamepad::GamepadInput;
+use crate::locking::MutexExt;
+use crate::memory_stats::BufferStats;
+use crate::runtime_metrics::{self, Counter, Timing};
+use crate::thread_priority;
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::panic::{catch_unwind, AssertUnwindSafe};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Instant, SystemTime};
+use tauri::{AppHandle, Emitter, Manager};
+use tokio::sync::Notify;
+use tracing::warn;
+
+const DISPATCH_THREAD: &str = "input-dispatch";
+
+/// Messages handled in one go before the controller state snapshot is republished.
+const MAX_DRAIN: usize = 256;
+
+/// Emitted once when the event bus starts coalescing or dropping samples; again only
+/// after the queue has drained and filled up again.
+pub const EVENT_PRESSURE: &str = "event-pressure";
+
+pub const MIN_CAPACITY: usize = 64;
+pub const MAX_CAPACITY: usize = 65_536;
+/// Most messages the queue ever holds, whatever the policies say; past it the oldest
+/// message is dropped, so a stalled dispatcher can't take the rest of memory with it.
+pub const QUEUE_HARD_LIMIT: usize = 4 * MAX_CAPACITY;
+
+/// A decoded input on its way from a source to the dispatcher.
+pub enum InputMessage {
+    Gilrs {
+        controller_id: usize,
+        input: GamepadInput,
+        synthetic: bool,
+        source_time: Option<SystemTime>,
+    },
+    Evdev {
+        event: EvdevControllerEvent,
+        category: EventCategory,
+    },
+    /// A motion axis sampled from the IIO sensors
+    Iio {
+        event: UnifiedControllerEvent,
+    },
+}
+
+/// What the event bus may do with a message once it's full.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EventCategory {
+    /// Buttons, keys, connects and disconnects: always queued
+    Discrete,
+    Axis,
+    /// Gyro and accelerometer samples
+    Motion,
+    Touchpad,
+}
+
+impl InputMessage {
+    fn category(&self) -> EventCategory {
+        match self {
+            InputMessage::Gilrs { input: GamepadInput::AxisChanged { .. }, .. } => EventCategory::Axis,
+            InputMessage::Gilrs { .. } => EventCategory::Discrete,
+            InputMessage::Evdev { category, .. } => *category,
+            InputMessage::Iio { .. } => EventCategory::Motion,
+        }
+    }
+
+    /// Whether `other` is a newer sample of the same axis, so it can replace this one.
+    fn coalesces_with(&self, other: &InputMessage) -> bool {
+        match (self, other) {
+            (
+                InputMessage::Gilrs { controller_id, input: GamepadInput::AxisChanged { axis, .. }, synthetic, .. },
+                InputMessage::Gilrs {
+                    controller_id: other_id,
+                    input: GamepadInput::AxisChanged { axis: other_axis, .. },
+                    synthetic: other_synthetic,
+                    ..
+                },
+            ) => controller_id == other_id && axis == other_axis && synthetic == other_synthetic,
+            (
+                InputMessage::Evdev { event, category },
+                InputMessage::Evdev { event: other, category: other_category },
+            ) => {
+                *category != EventCategory::Discrete
+                    && category == other_category
+                    && event.code == other.code
+                    && event.synthetic == other.synthetic
+                    && event.event_type == other.event_type
+                    && event.device_path == other.device_path
+            }
+            (InputMessage::Iio { event }, InputMessage::Iio { event: other }) => {
+                let axis = |event: &UnifiedControllerEvent| match &event.input {
+                    NormalizedInput::Axis { name, .. } => Some(name.clone()),
+                    _ => None,
+                };
+                event.device_id == other.device_id && axis(event).is_some() && axis(event) == axis(other)
+            }
+            _ => false,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum OverflowPolicy {
+    /// Replace the queued sample of the same axis with the new one (latest value wins)
+    Coalesce,
+    /// Discard the new sample
+    Drop,
+    /// Queue it anyway, past the capacity, up to `QUEUE_HARD_LIMIT`
+    Queue,
+}
+
+/// Size of the queue between the input sources and the dispatcher, and what happens to
+/// continuous samples once it's full. Discrete events are only dropped past `QUEUE_HARD_LIMIT`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct EventBusConfig {
+    pub capacity: usize,
+    pub axis: OverflowPolicy,
+    pub motion: OverflowPolicy,
+    pub touchpad: OverflowPolicy,
+}
+
+impl Default for EventBusConfig {
+    fn default() -> Self {
+        Self {
+            capacity: 1024,
+            axis: OverflowPolicy::Coalesce,
+            motion: OverflowPolicy::Coalesce,
+            touchpad: OverflowPolicy::Coalesce,
+        }
+    }
+}
+
+impl EventBusConfig {
+    pub fn validate(&self) -> Result<(), String> {
+        if !(MIN_CAPACITY..=MAX_CAPACITY).contains(&self.capacity) {
+            return Err(format!("capacity must be between {} and {}", MIN_CAPACITY, MAX_CAPACITY));
+        }
+        Ok(())
+    }
+
+    fn policy(&self, category: EventCategory) -> OverflowPolicy {
+        match category {
+            EventCategory::Discrete => OverflowPolicy::Queue,
+            EventCategory::Axis => self.axis,
+            EventCategory::Motion => self.motion,
+            EventCategory::Touchpad => self.touchpad,
+        }
+    }
+}
+
+/// Payload of `event-pressure`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EventPressure {
+    pub capacity: usize,
+    pub queued: usize,
+    pub coalesced_total: u64,
+    pub dropped_total: u64,
+}
+
+struct Queued {
+    message: InputMessage,
+    queued_at: Instant,
+}
+
+/// Bounded queue between the input sources and the dispatcher.
+pub struct EventBus {
+    queue: Mutex<VecDeque<Queued>>,
+    config: Mutex<EventBusConfig>,
+    notify: Notify,
+    /// Set when a sample is coalesced or dropped, cleared once the queue drains
+    under_pressure: AtomicBool,
+    pressure_reported: AtomicBool,
+    coalesced_total: AtomicU64,
+    dropped_total: AtomicU64,
+}
+
+impl EventBus {
+    fn new() -> Self {
+        Self {
+            queue: Mutex::new(VecDeque::new()),
+            config: Mutex::new(EventBusConfig::default()),
+            notify: Notify::new(),
+            under_pressure: AtomicBool::new(false),
+            pressure_reported: AtomicBool::new(false),
+            coalesced_total: AtomicU64::new(0),
+            dropped_total: AtomicU64::new(0),
+        }
+    }
+
+    pub fn configure(&self, config: EventBusConfig) -> Result<(), String> {
+        config.validate()?;
+        *self.config.lock_or_recover() = config;
+        Ok(())
+    }
+
+    pub fn config(&self) -> EventBusConfig {
+        *self.config.lock_or_recover()
+    }
+
+    pub fn push(&self, message: InputMessage) {
+        let config = self.config();
+        let mut queue = self.queue.lock_or_recover();
+        if queue.len() >= config.capacity {
+            match config.policy(message.category()) {
+                OverflowPolicy::Queue => {}
+                OverflowPolicy::Coalesce => {
+                    // A sample with nothing of its own axis queued still goes in, so the
+                    // overshoot is bounded by the number of distinct axes
+                    if let Some(pending) = queue.iter_mut().rev().find(|q| q.message.coalesces_with(&message)) {
+                        pending.message = message;
+                        self.overflowed(Counter::EventsCoalesced, &self.coalesced_total);
+                        return;
+                    }
+                }
+                OverflowPolicy::Drop => {
+                    self.overflowed(Counter::EventsDropped, &self.dropped_total);
+                    return;
+                }
+            }
+        }
+        if queue.len() >= QUEUE_HARD_LIMIT {
+            queue.pop_front();
+            self.overflowed(Counter::EventsDropped, &self.dropped_total);
+        }
+        queue.push_back(Queued { message, queued_at: Instant::now() });
+        drop(queue);
+        self.notify.notify_one();
+    }
+
+    fn overflowed(&self, counter: Counter, total: &AtomicU64) {
+        total.fetch_add(1, Ordering::Relaxed);
+        runtime_metrics::metrics().increment(counter);
+        self.under_pressure.store(true, Ordering::Relaxed);
+    }
+
+    fn try_recv(&self) -> Option<Queued> {
+        let mut queue = self.queue.lock_or_recover();
+        let queued = queue.pop_front();
+        // A drained queue ends the episode, but only once it has been reported
+        if queue.is_empty() && self.pressure_reported.load(Ordering::Relaxed) {
+            self.under_pressure.store(false, Ordering::Relaxed);
+            self.pressure_reported.store(false, Ordering::Relaxed);
+        }
+        queued
+    }
+
+    async fn recv(&self) -> Queued {
+        loop {
+            if let Some(queued) = self.try_recv() {
+                return queued;
+            }
+            self.notify.notified().await;
+        }
+    }
+
+    pub fn buffer_stats(&self) -> BufferStats {
+        BufferStats::deque("event_bus", &self.queue.lock_or_recover(), QUEUE_HARD_LIMIT)
+    }
+
+    /// The `event-pressure` payload, the first time it's asked for in each episode of pressure.
+    fn take_pressure_warning(&self) -> Option<EventPressure> {
+        if !self.under_pressure.load(Ordering::Relaxed) || self.pressure_reported.swap(true, Ordering::Relaxed) {
+            return None;
+        }
+        Some(EventPressure {
+            capacity: self.config().capacity,
+            queued: self.queue.lock_or_recover().len(),
+            coalesced_total: self.coalesced_total.load(Ordering::Relaxed),
+            dropped_total: self.dropped_total.load(Ordering::Relaxed),
+        })
+    }
+}
+
+/// Sending half given to every input source: the gilrs and evdev pollers, the mock
+/// source and replays.
+#[derive(Clone)]
+pub struct InputSender(Arc<EventBus>);
+
+impl InputSender {
+    pub fn send(&self, message: InputMessage) {
+        self.0.push(message);
+    }
+
+    pub fn bus(&self) -> Arc<EventBus> {
+        self.0.clone()
+    }
+}
+
+pub struct InputReceiver(Arc<EventBus>);
+
+impl InputReceiver {
+    /// Takes the next message without waiting, for driving the pipeline without a dispatcher.
+    #[cfg(feature = "test-support")]
+    pub fn try_recv(&self) -> Option<InputMessage> {
+        self.0.try_recv().map(|queued| queued.message)
+    }
+}
+
+pub fn channel() -> (InputSender, InputReceiver) {
+    let bus = Arc::new(EventBus::new());
+    (InputSender(bus.clone()), InputReceiver(bus))
+}
+
+/// Starts the dispatcher, the only place input is applied to controller state and emitted.
+/// Sources just decode and send, so neither they nor commands wait on it; commands read the
+/// state snapshot it publishes after each burst of messages. It has a raised-priority
+/// thread of its own rather than sharing the async runtime's workers with commands.
+pub fn spawn_dispatcher(app: AppHandle, receiver: InputReceiver) {
+    let state = app.state::<AppState>();
+    let gamepad_manager = state.gamepad_manager.clone();
+    let evdev_manager = state.evdev_manager.clone();
+    let iio_motion = state.iio_motion.clone();
+    let panic_monitor = state.panic_monitor.clone();
+    let raise_priority = !state.settings.get().disable_input_priority;
+    let InputReceiver(bus) = receiver;
+    let dispatch = async move {
+        let metrics = runtime_metrics::metrics();
+        loop {
+            let mut next = Some(bus.recv().await);
+            let mut handled = 0;
+            while let Some(Queued { message, queued_at }) = next.take() {
+                metrics.record_timing(Timing::DispatchQueueWait, queued_at.elapsed());
+                // Same as the polling thread: a panicking input is counted, not fatal
+                let result = catch_unwind(AssertUnwindSafe(|| match message {
+                    InputMessage::Gilrs { controller_id, input, synthetic, source_time } => {
+                        gamepad_manager.process_input(&app, controller_id, input, synthetic, source_time);
+                    }
+                    InputMessage::Evdev { event, category } => evdev_manager.handle_event(&app, event, category),
+                    InputMessage::Iio { event } => iio_motion.handle_event(&app, event),
+                }));
+                if result.is_err() {
+                    panic_monitor.record_poll_panic();
+                }
+                handled += 1;
+                if handled < MAX_DRAIN {
+                    next = bus.try_recv();
+                }
+            }
+            gamepad_manager.publish_state_snapshot();
+            if let Some(pressure) = bus.take_pressure_warning() {
+                warn!(
+                    "Input event queue is full ({} queued): coalescing or dropping samples",
+                    pressure.queued
+                );
+                app.emit(EVENT_PRESSURE, pressure).ok();
+            }
+        }
+    };
+    std::thread::Builder::new()
+        .name(DISPATCH_THREAD.to_string())
+        .spawn(move || {
+            thread_priority::raise_input_thread(DISPATCH_THREAD, raise_priority);
+            tauri::async_runtime::block_on(dispatch);
+        })
+        .expect("Failed to spawn the input dispatcher");
+}
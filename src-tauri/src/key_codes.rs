@@ -0,0 +1,168 @@
+#[cfg(target_os = "linux")]
+use evdev::{AbsoluteAxisType, EventType, Key, LedType, MiscType, RelativeAxisType, SoundType, SwitchType, Synchronization};
+#[cfg(target_os = "linux")]
+use std::fmt::Debug;
+#[cfg(target_os = "linux")]
+use std::sync::OnceLock;
+
+/// Highest key/button code (`KEY_MAX`).
+#[cfg(target_os = "linux")]
+const KEY_MAX: u16 = 0x2ff;
+/// Highest absolute axis code (`ABS_MAX`).
+#[cfg(target_os = "linux")]
+const ABS_MAX: u16 = 0x3f;
+/// Highest event type (`EV_MAX`).
+#[cfg(target_os = "linux")]
+const EV_MAX: u16 = 0x1f;
+/// The Steam Deck's back grips, as hid-steam reports them (`BTN_GRIPL`, `BTN_GRIPR`,
+/// `BTN_GRIPL2`, `BTN_GRIPR2`). The evdev crate doesn't name these codes.
+#[cfg(target_os = "linux")]
+pub const BACK_GRIPS: [(u16, &str); 4] = [(0x224, "L4"), (0x225, "R4"), (0x226, "L5"), (0x227, "R5")];
+/// Name handed out for codes past the tables.
+#[cfg(target_os = "linux")]
+const OUT_OF_RANGE: &str = "unknown";
+
+/// Every key, absolute axis and event type name, formatted once and kept for the life of
+/// the process (under 20KB), so per-event and per-rescan paths share them instead of
+/// formatting a fresh string each time.
+#[cfg(target_os = "linux")]
+struct NameTables {
+    keys: Vec<&'static str>,
+    axes: Vec<&'static str>,
+    event_types: Vec<&'static str>,
+}
+
+#[cfg(target_os = "linux")]
+static NAME_TABLES: OnceLock<NameTables> = OnceLock::new();
+
+#[cfg(target_os = "linux")]
+fn tables() -> &'static NameTables {
+    NAME_TABLES.get_or_init(|| {
+        let mut keys: Vec<&'static str> = (0..=KEY_MAX).map(|code| leak_name(Key::new(code))).collect();
+        for (code, name) in BACK_GRIPS {
+            keys[code as usize] = name;
+        }
+        NameTables {
+            keys,
+            axes: (0..=ABS_MAX).map(|code| leak_name(AbsoluteAxisType(code))).collect(),
+            event_types: (0..=EV_MAX).map(|code| leak_name(EventType(code))).collect(),
+        }
+    })
+}
+
+#[cfg(target_os = "linux")]
+fn leak_name<T: Debug>(value: T) -> &'static str {
+    Box::leak(format!("{:?}", value).into_boxed_str())
+}
+
+/// The `Debug` name of a key code, e.g. `BTN_SOUTH`, `L4`..`R5` for the back grips, or
+/// "unknown ..." for unassigned codes.
+#[cfg(target_os = "linux")]
+pub fn key_name(code: u16) -> &'static str {
+    tables().keys.get(code as usize).copied().unwrap_or(OUT_OF_RANGE)
+}
+
+/// The `Debug` name of an absolute axis code, e.g. `ABS_X`.
+#[cfg(target_os = "linux")]
+pub fn abs_axis_name(code: u16) -> &'static str {
+    tables().axes.get(code as usize).copied().unwrap_or(OUT_OF_RANGE)
+}
+
+/// The name `EvdevControllerEvent` spells the event type with, e.g. `KEY` or `ABSOLUTE`.
+#[cfg(target_os = "linux")]
+pub fn event_type_name(event_type: EventType) -> &'static str {
+    tables().event_types.get(event_type.0 as usize).copied().unwrap_or(OUT_OF_RANGE)
+}
+
+/// Approximate bytes held by the name tables, or 0 before anything asked for a name.
+#[cfg(target_os = "linux")]
+pub fn name_table_bytes() -> usize {
+    NAME_TABLES.get().map_or(0, |tables| {
+        [&tables.keys, &tables.axes, &tables.event_types].iter()
+            .map(|names| names.capacity() * std::mem::size_of::<&str>() + names.iter().map(|name| name.len()).sum::<usize>())
+            .sum()
+    })
+}
+
+/// `L4`, `L5`, `R4` or `R5` if `code` is one of the back grips.
+#[cfg(target_os = "linux")]
+pub fn back_grip_name(code: u16) -> Option<&'static str> {
+    BACK_GRIPS.iter().find(|(grip, _)| *grip == code).map(|(_, name)| *name)
+}
+
+#[cfg(not(target_os = "linux"))]
+pub fn name_table_bytes() -> usize {
+    0
+}
+
+/// Name of `code` for an event of `event_type`, which may be spelled the way
+/// `EvdevControllerEvent` does ("KEY", "ABSOLUTE") or the kernel way ("EV_KEY", "EV_ABS").
+#[cfg(target_os = "linux")]
+pub fn lookup(event_type: &str, code: u16) -> Option<String> {
+    let kind = match event_type.get(..3) {
+        Some(prefix) if prefix.eq_ignore_ascii_case("EV_") => &event_type[3..],
+        _ => event_type,
+    };
+    let is = |names: &[&str]| names.iter().any(|name| name.eq_ignore_ascii_case(kind));
+    if is(&["KEY"]) {
+        known(key_name(code))
+    } else if is(&["ABSOLUTE", "ABS"]) {
+        known(abs_axis_name(code))
+    } else if is(&["RELATIVE", "REL"]) {
+        known_name(RelativeAxisType(code))
+    } else if is(&["LED"]) {
+        known_name(LedType(code))
+    } else if is(&["SOUND", "SND"]) {
+        known_name(SoundType(code))
+    } else if is(&["SWITCH", "SW"]) {
+        known_name(SwitchType(code))
+    } else if is(&["SYNCHRONIZATION", "SYN"]) {
+        known_name(Synchronization(code))
+    } else if is(&["MISC", "MSC"]) {
+        known_name(MiscType(code))
+    } else {
+        None
+    }
+}
+
+#[cfg(not(target_os = "linux"))]
+pub fn lookup(_event_type: &str, _code: u16) -> Option<String> {
+    None
+}
+
+#[cfg(target_os = "linux")]
+pub fn all_key_codes() -> Vec<(u16, String)> {
+    (0..=KEY_MAX)
+        .filter_map(|code| Some((code, known(key_name(code))?)))
+        .collect()
+}
+
+#[cfg(target_os = "linux")]
+pub fn all_abs_codes() -> Vec<(u16, String)> {
+    (0..=ABS_MAX)
+        .filter_map(|code| Some((code, known(abs_axis_name(code))?)))
+        .collect()
+}
+
+/// Without the evdev crate there are no code names to list.
+#[cfg(not(target_os = "linux"))]
+pub fn all_key_codes() -> Vec<(u16, String)> {
+    Vec::new()
+}
+
+#[cfg(not(target_os = "linux"))]
+pub fn all_abs_codes() -> Vec<(u16, String)> {
+    Vec::new()
+}
+
+/// The evdev types' `Debug` output is the kernel name for known codes and
+/// "unknown ..." otherwise.
+#[cfg(target_os = "linux")]
+fn known_name<T: Debug>(value: T) -> Option<String> {
+    known(&format!("{:?}", value))
+}
+
+#[cfg(target_os = "linux")]
+fn known(name: &str) -> Option<String> {
+    (!name.starts_with("unknown")).then(|| name.to_string())
+}
@@ -0,0 +1,783 @@
+use crate::dead_zone::StickSide;
+use crate::locking::MutexExt;
+#[cfg(not(target_os = "linux"))]
+use crate::evdev_types::EVDEV_UNSUPPORTED;
+#[cfg(target_os = "linux")]
+use evdev::uinput::{VirtualDevice, VirtualDeviceBuilder};
+#[cfg(target_os = "linux")]
+use evdev::{AttributeSet, BusType, EventType, InputEvent, InputId, Key, RelativeAxisType};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use tracing::{debug, info, warn};
+
+pub const KEYBOARD_NAME: &str = "Steam Deck Controller Keyboard";
+pub const MOUSE_NAME: &str = "Steam Deck Controller Mouse";
+pub const MAX_RULES: usize = 64;
+pub const MAX_COMBO_KEYS: usize = 8;
+/// Fastest mouse_move, in pixels per second at full deflection.
+pub const MAX_MOUSE_SCALE: f32 = 10_000.0;
+/// Stick deflection below which mouse_move doesn't move, so a resting stick can't drift
+const MOUSE_DEAD_ZONE: f32 = 0.1;
+
+const BTN_LEFT: u16 = 0x110;
+const BTN_RIGHT: u16 = 0x111;
+const BTN_MIDDLE: u16 = 0x112;
+#[cfg(target_os = "linux")]
+const REL_X: u16 = 0x00;
+#[cfg(target_os = "linux")]
+const REL_Y: u16 = 0x01;
+
+/// Kernel key codes of A to Z.
+const LETTER_KEYS: [u16; 26] = [
+    30, 48, 46, 32, 18, 33, 34, 35, 23, 36, 37, 38, 50, 49, 24, 25, 16, 19, 31, 20, 22, 47, 17, 45, 21, 44,
+];
+
+/// Keys other than letters, digits and function keys, by their lowercased name and the
+/// aliases people write them as.
+const NAMED_KEYS: [(&str, u16); 52] = [
+    ("ctrl", 29), ("control", 29), ("leftctrl", 29), ("rightctrl", 97),
+    ("shift", 42), ("leftshift", 42), ("rightshift", 54),
+    ("alt", 56), ("leftalt", 56), ("rightalt", 100), ("altgr", 100),
+    ("super", 125), ("meta", 125), ("win", 125), ("leftmeta", 125), ("rightmeta", 126),
+    ("escape", 1), ("esc", 1), ("tab", 15), ("space", 57), ("enter", 28), ("return", 28),
+    ("backspace", 14), ("delete", 111), ("del", 111), ("insert", 110),
+    ("home", 102), ("end", 107), ("pageup", 104), ("pagedown", 109),
+    ("up", 103), ("down", 108), ("left", 105), ("right", 106),
+    ("capslock", 58), ("pause", 119), ("printscreen", 99),
+    ("minus", 12), ("equal", 13), ("comma", 51), ("period", 52), ("dot", 52), ("slash", 53),
+    ("semicolon", 39), ("apostrophe", 40), ("grave", 41), ("backslash", 43),
+    ("leftbrace", 26), ("rightbrace", 27),
+    ("mouseleft", BTN_LEFT), ("mouseright", BTN_RIGHT), ("mousemiddle", BTN_MIDDLE),
+];
+
+fn now_ms() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_millis() as u64
+}
+
+fn function_key(number: u16) -> Option<u16> {
+    match number {
+        1..=10 => Some(58 + number),
+        11 | 12 => Some(76 + number),
+        13..=24 => Some(170 + number),
+        _ => None,
+    }
+}
+
+/// Kernel key code for a key name: F1 to F24, letters, digits, modifiers (Ctrl, Shift, Alt,
+/// Super, each with Left/Right forms), common keys like Space, Enter or PageUp, and
+/// MouseLeft/MouseRight/MouseMiddle. Case, spaces, dashes, underscores and a `KEY_` prefix
+/// don't matter.
+pub fn parse_key(name: &str) -> Result<u16, String> {
+    let lowered = name.trim().to_ascii_lowercase();
+    let normalized: String = lowered.strip_prefix("key_").unwrap_or(&lowered)
+        .chars()
+        .filter(|c| !matches!(c, ' ' | '-' | '_'))
+        .collect();
+    let unknown = || format!("Unknown key '{}'; use a letter, digit, F1 to F24, a modifier or a name like Space or PageUp", name);
+    if let [c] = normalized.as_bytes() {
+        return match c {
+            b'a'..=b'z' => Ok(LETTER_KEYS[(c - b'a') as usize]),
+            b'0' => Ok(11),
+            b'1'..=b'9' => Ok((c - b'1') as u16 + 2),
+            _ => Err(unknown()),
+        };
+    }
+    if let Some(number) = normalized.strip_prefix('f').and_then(|digits| digits.parse::<u16>().ok()) {
+        return function_key(number).ok_or_else(unknown);
+    }
+    NAMED_KEYS.iter()
+        .find(|(known, _)| *known == normalized)
+        .map(|(_, code)| *code)
+        .ok_or_else(unknown)
+}
+
+fn is_mouse_button(code: u16) -> bool {
+    (BTN_LEFT..=BTN_MIDDLE).contains(&code)
+}
+
+/// Every key the virtual keyboard has.
+#[cfg(target_os = "linux")]
+fn keyboard_keys() -> impl Iterator<Item = u16> {
+    LETTER_KEYS.into_iter()
+        .chain(2..=11)
+        .chain((1..=24).filter_map(function_key))
+        .chain(NAMED_KEYS.into_iter().map(|(_, code)| code).filter(|&code| !is_mouse_button(code)))
+}
+
+fn stick_side(input: &str) -> Option<StickSide> {
+    match input {
+        "LeftStick" => Some(StickSide::Left),
+        "RightStick" => Some(StickSide::Right),
+        _ => None,
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum EmulationAction {
+    /// Holds `key` down while the button is
+    KeyPress { key: String },
+    /// Presses `keys` in order while the button is held, and releases them in reverse
+    KeyCombo { keys: Vec<String> },
+    /// Moves the mouse with a stick, `scale` pixels per second at full deflection;
+    /// negative inverts it
+    MouseMove { scale: f32 },
+}
+
+/// Turns one controller input into keyboard or mouse input.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct EmulationRule {
+    pub id: String,
+    /// gilrs button name for key actions; `LeftStick` or `RightStick` for mouse_move
+    pub input: String,
+    pub action: EmulationAction,
+    pub enabled: bool,
+}
+
+impl EmulationRule {
+    pub fn validate(&self) -> Result<(), String> {
+        if self.id.trim().is_empty() {
+            return Err("Emulation rule id must not be empty".to_string());
+        }
+        if self.input.trim().is_empty() {
+            return Err(format!("Emulation rule '{}' needs an input", self.id));
+        }
+        match &self.action {
+            EmulationAction::KeyPress { .. } | EmulationAction::KeyCombo { .. } => {
+                if stick_side(&self.input).is_some() {
+                    return Err(format!("Emulation rule '{}' presses keys, so its input must be a button", self.id));
+                }
+                let keys = self.keys()?;
+                if keys.is_empty() || keys.len() > MAX_COMBO_KEYS {
+                    return Err(format!("Emulation rule '{}' needs 1 to {} keys", self.id, MAX_COMBO_KEYS));
+                }
+            }
+            EmulationAction::MouseMove { scale } => {
+                if stick_side(&self.input).is_none() {
+                    return Err(format!("Emulation rule '{}' moves the mouse, so its input must be LeftStick or RightStick", self.id));
+                }
+                if !scale.is_finite() || *scale == 0.0 || scale.abs() > MAX_MOUSE_SCALE {
+                    return Err(format!("Mouse scale must be non-zero and at most {} in size, got {}", MAX_MOUSE_SCALE, scale));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Key codes the rule presses, in order; empty for mouse_move.
+    fn keys(&self) -> Result<Vec<u16>, String> {
+        match &self.action {
+            EmulationAction::KeyPress { key } => Ok(vec![parse_key(key)?]),
+            EmulationAction::KeyCombo { keys } => keys.iter().map(|key| parse_key(key)).collect(),
+            EmulationAction::MouseMove { .. } => Ok(Vec::new()),
+        }
+    }
+}
+
+pub fn validate_config(config: &KeyEmulationConfig) -> Result<(), String> {
+    if config.rules.len() > MAX_RULES {
+        return Err(format!("At most {} emulation rules, got {}", MAX_RULES, config.rules.len()));
+    }
+    if config.off_chord.iter().any(|button| button.trim().is_empty()) {
+        return Err("Off chord button names must not be empty".to_string());
+    }
+    let mut ids = HashSet::new();
+    for rule in &config.rules {
+        rule.validate()?;
+        if !ids.insert(rule.id.as_str()) {
+            return Err(format!("Emulation rule '{}' is defined twice", rule.id));
+        }
+    }
+    Ok(())
+}
+
+/// Emulation rules and the off chord, as saved in settings. Whether emulation is on isn't
+/// saved, so every session starts with it off.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct KeyEmulationConfig {
+    pub rules: Vec<EmulationRule>,
+    /// gilrs button names that, pressed together on one controller, turn emulation off
+    /// at once; empty leaves only the app to do it
+    pub off_chord: Vec<String>,
+}
+
+impl Default for KeyEmulationConfig {
+    fn default() -> Self {
+        Self {
+            rules: Vec::new(),
+            off_chord: vec!["LeftThumb".to_string(), "RightThumb".to_string()],
+        }
+    }
+}
+
+/// One write to the virtual keyboard or mouse.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EmulatedInput {
+    Key { code: u16, pressed: bool },
+    MouseMove { dx: i32, dy: i32 },
+}
+
+/// Turns button and stick changes into key presses and mouse motion. Holds no devices, so
+/// tests can drive it alone.
+#[derive(Default)]
+pub struct EmulationEngine {
+    /// Enabled rules with their parsed keys
+    rules: Vec<(EmulationRule, Vec<u16>)>,
+    off_chord: Vec<String>,
+    pressed: HashMap<usize, HashSet<String>>,
+    /// Keys each (controller, rule id) is holding, in the order they went down
+    holding: HashMap<(usize, String), Vec<u16>>,
+    /// Holders of each key that's down, so one two rules press stays down until both let go
+    key_holders: HashMap<u16, u32>,
+    /// Stick axis values by (controller, gilrs axis name)
+    axes: HashMap<(usize, String), f32>,
+    /// Motion under a pixel, carried to the next tick
+    remainder: (f32, f32),
+}
+
+impl EmulationEngine {
+    /// Replaces the rules, releasing every key held under the old ones. Disabled and
+    /// invalid rules are left out.
+    pub fn set_config(&mut self, config: &KeyEmulationConfig) -> Vec<EmulatedInput> {
+        let released = self.release_all();
+        self.rules = config.rules.iter()
+            .filter(|rule| rule.enabled && rule.validate().is_ok())
+            .filter_map(|rule| Some((rule.clone(), rule.keys().ok()?)))
+            .collect();
+        self.off_chord = config.off_chord.clone();
+        released
+    }
+
+    pub fn active_rules(&self) -> usize {
+        self.rules.len()
+    }
+
+    pub fn button_changed(&mut self, controller_id: usize, button: &str, pressed: bool) -> Vec<EmulatedInput> {
+        let held = self.pressed.entry(controller_id).or_default();
+        if pressed {
+            held.insert(button.to_string());
+        } else {
+            held.remove(button);
+        }
+        let mut inputs = Vec::new();
+        for (rule, keys) in &self.rules {
+            if rule.input != button || keys.is_empty() {
+                continue;
+            }
+            let holder = (controller_id, rule.id.clone());
+            if pressed && !self.holding.contains_key(&holder) {
+                for &code in keys {
+                    let holders = self.key_holders.entry(code).or_insert(0);
+                    *holders += 1;
+                    if *holders == 1 {
+                        inputs.push(EmulatedInput::Key { code, pressed: true });
+                    }
+                }
+                self.holding.insert(holder, keys.clone());
+            } else if !pressed {
+                if let Some(codes) = self.holding.remove(&holder) {
+                    inputs.extend(release(&mut self.key_holders, &codes));
+                }
+            }
+        }
+        inputs
+    }
+
+    /// Whether the off chord is now held on `controller_id`.
+    pub fn off_chord_held(&self, controller_id: usize) -> bool {
+        !self.off_chord.is_empty()
+            && self.pressed.get(&controller_id).is_some_and(|held| self.off_chord.iter().all(|button| held.contains(button)))
+    }
+
+    pub fn axis_changed(&mut self, controller_id: usize, axis: &str, value: f32) {
+        self.axes.insert((controller_id, axis.to_string()), value);
+    }
+
+    /// Releases what `controller_id` was holding and forgets its sticks.
+    pub fn controller_disconnected(&mut self, controller_id: usize) -> Vec<EmulatedInput> {
+        self.pressed.remove(&controller_id);
+        self.axes.retain(|(id, _), _| *id != controller_id);
+        let holders: Vec<(usize, String)> = self.holding.keys().filter(|(id, _)| *id == controller_id).cloned().collect();
+        let mut inputs = Vec::new();
+        for holder in holders {
+            if let Some(codes) = self.holding.remove(&holder) {
+                inputs.extend(release(&mut self.key_holders, &codes));
+            }
+        }
+        inputs
+    }
+
+    pub fn release_all(&mut self) -> Vec<EmulatedInput> {
+        let mut inputs = Vec::new();
+        for (_, codes) in self.holding.drain() {
+            inputs.extend(release(&mut self.key_holders, &codes));
+        }
+        self.key_holders.clear();
+        self.remainder = (0.0, 0.0);
+        inputs
+    }
+
+    /// Mouse motion for `elapsed` of the mouse_move sticks' current deflection, if it adds
+    /// up to a pixel.
+    pub fn tick(&mut self, elapsed: Duration) -> Option<EmulatedInput> {
+        let seconds = elapsed.as_secs_f32();
+        let (mut dx, mut dy) = self.remainder;
+        for (rule, _) in &self.rules {
+            let EmulationAction::MouseMove { scale } = rule.action else {
+                continue;
+            };
+            let Some(side) = stick_side(&rule.input) else {
+                continue;
+            };
+            let (x_axis, y_axis) = side.axes();
+            for ((controller_id, axis), &x) in &self.axes {
+                if axis != x_axis {
+                    continue;
+                }
+                let y = self.axes.get(&(*controller_id, y_axis.to_string())).copied().unwrap_or(0.0);
+                if x.hypot(y) < MOUSE_DEAD_ZONE {
+                    continue;
+                }
+                dx += x * scale * seconds;
+                // gilrs' y is up; the pointer's is down
+                dy -= y * scale * seconds;
+            }
+        }
+        let moved = (dx.trunc() as i32, dy.trunc() as i32);
+        self.remainder = (dx.fract(), dy.fract());
+        (moved != (0, 0)).then_some(EmulatedInput::MouseMove { dx: moved.0, dy: moved.1 })
+    }
+}
+
+/// Releases `codes` in reverse, for the keys no other rule still holds.
+fn release(key_holders: &mut HashMap<u16, u32>, codes: &[u16]) -> Vec<EmulatedInput> {
+    let mut inputs = Vec::new();
+    for &code in codes.iter().rev() {
+        if let Some(holders) = key_holders.get_mut(&code) {
+            *holders -= 1;
+            if *holders == 0 {
+                key_holders.remove(&code);
+                inputs.push(EmulatedInput::Key { code, pressed: false });
+            }
+        }
+    }
+    inputs
+}
+
+#[cfg(target_os = "linux")]
+struct VirtualDevices {
+    keyboard: VirtualDevice,
+    mouse: VirtualDevice,
+}
+
+#[cfg(target_os = "linux")]
+fn build_devices() -> std::io::Result<VirtualDevices> {
+    let mut keys = AttributeSet::<Key>::new();
+    for code in keyboard_keys() {
+        keys.insert(Key::new(code));
+    }
+    let keyboard = VirtualDeviceBuilder::new()?
+        .name(KEYBOARD_NAME)
+        .input_id(InputId::new(BusType::BUS_VIRTUAL, 0, 0, 1))
+        .with_keys(&keys)?
+        .build()?;
+    let mut buttons = AttributeSet::<Key>::new();
+    for code in [BTN_LEFT, BTN_RIGHT, BTN_MIDDLE] {
+        buttons.insert(Key::new(code));
+    }
+    let mut axes = AttributeSet::<RelativeAxisType>::new();
+    axes.insert(RelativeAxisType::REL_X);
+    axes.insert(RelativeAxisType::REL_Y);
+    let mouse = VirtualDeviceBuilder::new()?
+        .name(MOUSE_NAME)
+        .input_id(InputId::new(BusType::BUS_VIRTUAL, 0, 0, 1))
+        .with_keys(&buttons)?
+        .with_relative_axes(&axes)?
+        .build()?;
+    Ok(VirtualDevices { keyboard, mouse })
+}
+
+/// Answer to the emulation commands, and part of debug info and the health check.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct KeyEmulationStatus {
+    pub enabled: bool,
+    /// Milliseconds since the epoch
+    pub enabled_at: Option<u64>,
+    /// Enabled, valid rules
+    pub active_rules: usize,
+    pub off_chord: Vec<String>,
+    /// Whether the off chord, rather than the app, last turned emulation off
+    pub stopped_by_chord: bool,
+    /// Key and mouse events written since emulation was turned on
+    pub written_events: u64,
+    pub write_errors: u64,
+}
+
+struct Emulator {
+    engine: EmulationEngine,
+    #[cfg(target_os = "linux")]
+    devices: Option<VirtualDevices>,
+    enabled_at: Option<u64>,
+    last_tick: Option<Instant>,
+    stopped_by_chord: bool,
+    written_events: u64,
+    write_errors: u64,
+}
+
+impl Emulator {
+    fn turn_off(&mut self) -> bool {
+        let released = self.engine.release_all();
+        self.write(&released);
+        self.last_tick = None;
+        #[cfg(target_os = "linux")]
+        let had_devices = self.devices.take().is_some();
+        #[cfg(not(target_os = "linux"))]
+        let had_devices = false;
+        if had_devices {
+            info!("Destroyed the virtual keyboard and mouse");
+        }
+        self.enabled_at.take().is_some()
+    }
+
+    #[cfg(target_os = "linux")]
+    fn write(&mut self, inputs: &[EmulatedInput]) {
+        let Some(devices) = self.devices.as_mut() else {
+            return;
+        };
+        let mut keyboard = Vec::new();
+        let mut mouse = Vec::new();
+        for input in inputs {
+            match *input {
+                EmulatedInput::Key { code, pressed } => {
+                    let event = InputEvent::new(EventType::KEY, code, pressed as i32);
+                    if is_mouse_button(code) {
+                        mouse.push(event);
+                    } else {
+                        keyboard.push(event);
+                    }
+                }
+                EmulatedInput::MouseMove { dx, dy } => {
+                    mouse.push(InputEvent::new(EventType::RELATIVE, REL_X, dx));
+                    mouse.push(InputEvent::new(EventType::RELATIVE, REL_Y, dy));
+                }
+            }
+        }
+        for (device, events) in [(&mut devices.keyboard, keyboard), (&mut devices.mouse, mouse)] {
+            if events.is_empty() {
+                continue;
+            }
+            // emit adds the SYN_REPORT
+            match device.emit(&events) {
+                Ok(()) => self.written_events += events.len() as u64,
+                Err(e) => {
+                    self.write_errors += 1;
+                    if self.write_errors == 1 {
+                        warn!("Failed to write emulated keyboard or mouse input: {}", e);
+                    } else {
+                        debug!("Failed to write emulated keyboard or mouse input: {}", e);
+                    }
+                }
+            }
+        }
+    }
+
+    #[cfg(not(target_os = "linux"))]
+    fn write(&mut self, _inputs: &[EmulatedInput]) {}
+}
+
+/// Keyboard and mouse emulation: the rules, and the virtual devices while it's on.
+pub struct KeyEmulator {
+    /// Checked before locking, so input costs nothing while emulation is off
+    enabled: AtomicBool,
+    inner: Mutex<Emulator>,
+}
+
+impl Default for KeyEmulator {
+    fn default() -> Self {
+        Self {
+            enabled: AtomicBool::new(false),
+            inner: Mutex::new(Emulator {
+                engine: EmulationEngine::default(),
+                #[cfg(target_os = "linux")]
+                devices: None,
+                enabled_at: None,
+                last_tick: None,
+                stopped_by_chord: false,
+                written_events: 0,
+                write_errors: 0,
+            }),
+        }
+    }
+}
+
+impl KeyEmulator {
+    /// Creates the virtual keyboard and mouse and starts acting on the rules. Already on
+    /// is fine.
+    #[cfg(target_os = "linux")]
+    pub fn enable(&self) -> Result<KeyEmulationStatus, String> {
+        let mut inner = self.inner.lock_or_recover();
+        if inner.devices.is_none() {
+            let devices = build_devices().map_err(|e| format!("Failed to create the virtual keyboard and mouse through /dev/uinput: {}", e))?;
+            info!("Created the virtual keyboard and mouse; {} emulation rule(s) active", inner.engine.active_rules());
+            inner.devices = Some(devices);
+            inner.enabled_at = Some(now_ms());
+            inner.stopped_by_chord = false;
+            inner.written_events = 0;
+            inner.write_errors = 0;
+        }
+        self.enabled.store(true, Ordering::Relaxed);
+        Ok(status_of(&inner))
+    }
+
+    #[cfg(not(target_os = "linux"))]
+    pub fn enable(&self) -> Result<KeyEmulationStatus, String> {
+        Err(EVDEV_UNSUPPORTED.to_string())
+    }
+
+    /// Releases every emulated key and destroys the virtual devices. Returns whether
+    /// emulation was on.
+    pub fn disable(&self) -> bool {
+        self.enabled.store(false, Ordering::Relaxed);
+        self.inner.lock_or_recover().turn_off()
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.enabled.load(Ordering::Relaxed)
+    }
+
+    pub fn set_config(&self, config: &KeyEmulationConfig) {
+        let mut inner = self.inner.lock_or_recover();
+        let released = inner.engine.set_config(config);
+        inner.write(&released);
+    }
+
+    /// Acts on a button change; holding the off chord turns emulation off instead.
+    pub fn button_changed(&self, controller_id: usize, button: &str, pressed: bool) {
+        if !self.is_enabled() {
+            return;
+        }
+        let mut inner = self.inner.lock_or_recover();
+        let inputs = inner.engine.button_changed(controller_id, button, pressed);
+        if pressed && inner.engine.off_chord_held(controller_id) {
+            self.enabled.store(false, Ordering::Relaxed);
+            warn!("Off chord held on controller {}; turning keyboard and mouse emulation off", controller_id);
+            inner.turn_off();
+            inner.stopped_by_chord = true;
+            return;
+        }
+        inner.write(&inputs);
+    }
+
+    pub fn axis_changed(&self, controller_id: usize, axis: &str, value: f32) {
+        if !self.is_enabled() {
+            return;
+        }
+        self.inner.lock_or_recover().engine.axis_changed(controller_id, axis, value);
+    }
+
+    pub fn controller_disconnected(&self, controller_id: usize) {
+        if !self.is_enabled() {
+            return;
+        }
+        let mut inner = self.inner.lock_or_recover();
+        let released = inner.engine.controller_disconnected(controller_id);
+        inner.write(&released);
+    }
+
+    /// Moves the mouse for the time since the last tick. Called every polling pass, so a
+    /// held stick keeps moving it.
+    pub fn tick(&self) {
+        if !self.is_enabled() {
+            return;
+        }
+        let mut inner = self.inner.lock_or_recover();
+        let now = Instant::now();
+        let elapsed = inner.last_tick.map(|last| now - last).unwrap_or_default();
+        inner.last_tick = Some(now);
+        if let Some(motion) = inner.engine.tick(elapsed) {
+            inner.write(&[motion]);
+        }
+    }
+
+    pub fn status(&self) -> KeyEmulationStatus {
+        status_of(&self.inner.lock_or_recover())
+    }
+}
+
+fn status_of(inner: &Emulator) -> KeyEmulationStatus {
+    KeyEmulationStatus {
+        enabled: inner.enabled_at.is_some(),
+        enabled_at: inner.enabled_at,
+        active_rules: inner.engine.active_rules(),
+        off_chord: inner.engine.off_chord.clone(),
+        stopped_by_chord: inner.stopped_by_chord,
+        written_events: inner.written_events,
+        write_errors: inner.write_errors,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    const KEY_A: u16 = 30;
+    const KEY_F5: u16 = 63;
+    const KEY_F24: u16 = 194;
+    const KEY_LEFTCTRL: u16 = 29;
+    const KEY_LEFTSHIFT: u16 = 42;
+    const KEY_S: u16 = 31;
+
+    fn rule(id: &str, input: &str, action: EmulationAction) -> EmulationRule {
+        EmulationRule { id: id.to_string(), input: input.to_string(), action, enabled: true }
+    }
+
+    fn key_press(key: &str) -> EmulationAction {
+        EmulationAction::KeyPress { key: key.to_string() }
+    }
+
+    fn engine(rules: Vec<EmulationRule>) -> EmulationEngine {
+        let mut engine = EmulationEngine::default();
+        engine.set_config(&KeyEmulationConfig { rules, ..KeyEmulationConfig::default() });
+        engine
+    }
+
+    fn key(code: u16, pressed: bool) -> EmulatedInput {
+        EmulatedInput::Key { code, pressed }
+    }
+
+    #[test]
+    fn common_key_names_parse() {
+        assert_eq!(parse_key("F5"), Ok(KEY_F5));
+        assert_eq!(parse_key("f24"), Ok(KEY_F24));
+        assert_eq!(parse_key("a"), Ok(KEY_A));
+        assert_eq!(parse_key("Ctrl"), Ok(KEY_LEFTCTRL));
+        assert_eq!(parse_key("Left Shift"), Ok(KEY_LEFTSHIFT));
+        assert_eq!(parse_key("KEY_LEFTCTRL"), Ok(KEY_LEFTCTRL));
+        assert_eq!(parse_key("1"), Ok(2));
+        assert_eq!(parse_key("0"), Ok(11));
+    }
+
+    #[test]
+    fn unknown_key_names_are_refused() {
+        for name in ["F25", "F0", "", "Hyper", "ä"] {
+            assert!(parse_key(name).is_err(), "{} should be refused", name);
+        }
+    }
+
+    #[test]
+    fn rules_are_checked_when_saved() {
+        let valid = KeyEmulationConfig {
+            rules: vec![
+                rule("refresh", "North", key_press("F5")),
+                rule("pointer", "RightStick", EmulationAction::MouseMove { scale: 800.0 }),
+            ],
+            ..KeyEmulationConfig::default()
+        };
+        assert!(validate_config(&valid).is_ok());
+
+        let invalid = [
+            rule("typo", "North", key_press("F55")),
+            rule("empty", "North", EmulationAction::KeyCombo { keys: Vec::new() }),
+            rule("stick", "RightStick", key_press("F5")),
+            rule("button", "North", EmulationAction::MouseMove { scale: 800.0 }),
+            rule("still", "LeftStick", EmulationAction::MouseMove { scale: 0.0 }),
+            rule("", "North", key_press("F5")),
+        ];
+        for invalid in invalid {
+            let config = KeyEmulationConfig { rules: vec![invalid.clone()], ..KeyEmulationConfig::default() };
+            assert!(validate_config(&config).is_err(), "{:?} should be refused", invalid);
+        }
+
+        let duplicate = KeyEmulationConfig {
+            rules: vec![rule("same", "North", key_press("F5")), rule("same", "South", key_press("F6"))],
+            ..KeyEmulationConfig::default()
+        };
+        assert!(validate_config(&duplicate).is_err());
+    }
+
+    #[test]
+    fn a_key_is_held_while_its_button_is() {
+        let mut engine = engine(vec![rule("refresh", "North", key_press("F5"))]);
+        assert_eq!(engine.button_changed(0, "North", true), vec![key(KEY_F5, true)]);
+        assert_eq!(engine.button_changed(0, "North", true), vec![]);
+        assert_eq!(engine.button_changed(0, "South", true), vec![]);
+        assert_eq!(engine.button_changed(0, "North", false), vec![key(KEY_F5, false)]);
+    }
+
+    #[test]
+    fn combos_press_in_order_and_release_in_reverse() {
+        let combo = EmulationAction::KeyCombo { keys: vec!["Ctrl".to_string(), "Shift".to_string(), "S".to_string()] };
+        let mut engine = engine(vec![rule("save", "Start", combo)]);
+        assert_eq!(
+            engine.button_changed(0, "Start", true),
+            vec![key(KEY_LEFTCTRL, true), key(KEY_LEFTSHIFT, true), key(KEY_S, true)]
+        );
+        assert_eq!(
+            engine.button_changed(0, "Start", false),
+            vec![key(KEY_S, false), key(KEY_LEFTSHIFT, false), key(KEY_LEFTCTRL, false)]
+        );
+    }
+
+    #[test]
+    fn a_key_two_rules_hold_stays_down_until_both_let_go() {
+        let mut engine = engine(vec![rule("one", "North", key_press("Ctrl")), rule("two", "South", key_press("Ctrl"))]);
+        assert_eq!(engine.button_changed(0, "North", true), vec![key(KEY_LEFTCTRL, true)]);
+        assert_eq!(engine.button_changed(0, "South", true), vec![]);
+        assert_eq!(engine.button_changed(0, "North", false), vec![]);
+        assert_eq!(engine.button_changed(0, "South", false), vec![key(KEY_LEFTCTRL, false)]);
+    }
+
+    #[test]
+    fn disabled_rules_do_nothing() {
+        let mut disabled = rule("refresh", "North", key_press("F5"));
+        disabled.enabled = false;
+        let mut engine = engine(vec![disabled]);
+        assert_eq!(engine.active_rules(), 0);
+        assert_eq!(engine.button_changed(0, "North", true), vec![]);
+    }
+
+    #[test]
+    fn disconnecting_or_reconfiguring_releases_held_keys() {
+        let mut engine = engine(vec![rule("refresh", "North", key_press("F5"))]);
+        engine.button_changed(3, "North", true);
+        assert_eq!(engine.controller_disconnected(3), vec![key(KEY_F5, false)]);
+
+        engine.button_changed(3, "North", true);
+        assert_eq!(engine.set_config(&KeyEmulationConfig::default()), vec![key(KEY_F5, false)]);
+    }
+
+    #[test]
+    fn the_off_chord_is_held_only_with_every_button() {
+        let mut engine = engine(Vec::new());
+        engine.button_changed(0, "LeftThumb", true);
+        assert!(!engine.off_chord_held(0));
+        engine.button_changed(1, "RightThumb", true);
+        assert!(!engine.off_chord_held(1), "the chord is per controller");
+        engine.button_changed(0, "RightThumb", true);
+        assert!(engine.off_chord_held(0));
+    }
+
+    #[test]
+    fn a_held_stick_moves_the_mouse_by_elapsed_time() {
+        let mut engine = engine(vec![rule("pointer", "RightStick", EmulationAction::MouseMove { scale: 1000.0 })]);
+        engine.axis_changed(0, "RightStickX", 0.5);
+        engine.axis_changed(0, "RightStickY", 0.5);
+        assert_eq!(engine.tick(Duration::from_millis(100)), Some(EmulatedInput::MouseMove { dx: 50, dy: -50 }));
+
+        // Inside the dead zone, and sub-pixel motion carries over
+        engine.axis_changed(0, "RightStickX", 0.05);
+        engine.axis_changed(0, "RightStickY", 0.0);
+        assert_eq!(engine.tick(Duration::from_secs(1)), None);
+        engine.axis_changed(0, "RightStickX", 0.2);
+        assert_eq!(engine.tick(Duration::from_millis(3)), None);
+        assert_eq!(engine.tick(Duration::from_millis(3)), Some(EmulatedInput::MouseMove { dx: 1, dy: 0 }));
+    }
+}
@@ -0,0 +1,130 @@
+use crate::locking::MutexExt;
+use crate::thread_priority::{self, InputThreadPriority};
+use serde::{Deserialize, Serialize};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Mutex, OnceLock};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tracing::info;
+
+/// Longest run accepted, so a typo can't leave sampling on for hours.
+pub const MAX_DURATION_SECS: u64 = 300;
+
+/// Points in the pipeline measured from the kernel/gilrs timestamp of the input.
+#[derive(Debug, Clone, Copy)]
+pub enum Stage {
+    /// Input taken off the device queue and about to be applied
+    Processed,
+    /// `app.emit` returned
+    Emitted,
+    /// Handed to the light server as part of a batch
+    Dispatched,
+}
+
+const STAGE_COUNT: usize = 3;
+const STAGE_NAMES: [&str; STAGE_COUNT] = ["processed", "emitted", "dispatched"];
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StageLatency {
+    pub stage: String,
+    pub samples: usize,
+    pub p50_us: u64,
+    pub p95_us: u64,
+    pub p99_us: u64,
+    pub max_us: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LatencyReport {
+    pub duration_secs: u64,
+    pub finished_at: u64,
+    pub stages: Vec<StageLatency>,
+    /// How the input threads were scheduled during the run
+    pub input_threads: Vec<InputThreadPriority>,
+}
+
+/// Collects per-stage latencies while a benchmark runs; outside a run `record` is a
+/// single atomic load.
+#[derive(Default)]
+pub struct LatencyBenchmark {
+    running: AtomicBool,
+    samples: Mutex<[Vec<u64>; STAGE_COUNT]>,
+}
+
+impl LatencyBenchmark {
+    /// Records how long after `source` the input reached `stage`.
+    pub fn record(&self, stage: Stage, source: SystemTime) {
+        if !self.running.load(Ordering::Relaxed) {
+            return;
+        }
+        let latency_us = SystemTime::now()
+            .duration_since(source)
+            .unwrap_or_default()
+            .as_micros()
+            .min(u64::MAX as u128) as u64;
+        self.samples.lock_or_recover()[stage as usize].push(latency_us);
+    }
+
+    /// Samples for `duration`, then logs and returns the percentiles per stage.
+    pub async fn run(&self, duration: Duration) -> Result<LatencyReport, String> {
+        {
+            let mut samples = self.samples.lock_or_recover();
+            if self.running.swap(true, Ordering::Relaxed) {
+                return Err("A latency benchmark is already running".to_string());
+            }
+            samples.iter_mut().for_each(Vec::clear);
+        }
+        info!("Latency benchmark started for {}s", duration.as_secs());
+
+        tokio::time::sleep(duration).await;
+
+        self.running.store(false, Ordering::Relaxed);
+        let mut samples = std::mem::take(&mut *self.samples.lock_or_recover());
+        let report = LatencyReport {
+            duration_secs: duration.as_secs(),
+            finished_at: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .unwrap_or_default()
+                .as_millis() as u64,
+            stages: samples.iter_mut()
+                .zip(STAGE_NAMES)
+                .map(|(samples, stage)| summarize(stage, samples))
+                .collect(),
+            input_threads: thread_priority::snapshot(),
+        };
+
+        for stage in &report.stages {
+            info!(
+                "Latency benchmark {}: {} samples, p50={}us p95={}us p99={}us max={}us",
+                stage.stage, stage.samples, stage.p50_us, stage.p95_us, stage.p99_us, stage.max_us
+            );
+        }
+        info!("Latency benchmark input threads: {:?}", report.input_threads);
+        Ok(report)
+    }
+}
+
+fn summarize(stage: &str, samples: &mut [u64]) -> StageLatency {
+    samples.sort_unstable();
+    let percentile = |p: f64| -> u64 {
+        if samples.is_empty() {
+            return 0;
+        }
+        let index = ((samples.len() - 1) as f64 * p / 100.0).round() as usize;
+        samples[index]
+    };
+    StageLatency {
+        stage: stage.to_string(),
+        samples: samples.len(),
+        p50_us: percentile(50.0),
+        p95_us: percentile(95.0),
+        p99_us: percentile(99.0),
+        max_us: samples.last().copied().unwrap_or(0),
+    }
+}
+
+static LATENCY_BENCHMARK: OnceLock<LatencyBenchmark> = OnceLock::new();
+
+/// Process-wide benchmark, sampled from the input pipeline and the light server.
+pub fn benchmark() -> &'static LatencyBenchmark {
+    LATENCY_BENCHMARK.get_or_init(LatencyBenchmark::default)
+}
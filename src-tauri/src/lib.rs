@@ -1,64 +1,710 @@
+// Much of the evdev plumbing (trackers, payloads, helpers) is only reachable from the
+// Linux evdev backend
+#![cfg_attr(not(target_os = "linux"), allow(dead_code))]
+
 mod gamepad;
+mod app_state;
+mod axis_snapshots;
+mod background;
+mod capability_history;
 mod commands;
+mod confinement;
+mod connection_stats;
+mod controller_event;
+mod cpu_usage;
+mod dead_zone;
+mod combo_effects;
+mod command_metrics;
+mod debug_report;
+mod detection;
+mod diagnostics;
+mod dock;
+mod emit_interest;
+mod emitter;
+mod ensemble;
+pub mod error;
+mod error_counters;
+#[cfg(target_os = "linux")]
+mod evdev_gamepad;
+#[cfg(not(target_os = "linux"))]
+#[path = "evdev_unsupported.rs"]
 mod evdev_gamepad;
+mod evdev_types;
+mod event_batcher;
+mod event_script;
+mod event_stream;
+mod external_processor;
+mod fan_control;
+#[cfg(target_os = "linux")]
+mod fd_health;
+mod flood_alarm;
+mod frontend_sync;
+mod ghost_filter;
+mod health;
+mod hidraw;
+mod http_client;
+mod iio_motion;
+mod input_backend;
+mod input_pipeline;
+mod key_codes;
+mod key_emulation;
+mod latency_benchmark;
+mod light_server;
+mod locking;
+mod logging;
+mod low_battery;
+mod memory_stats;
+mod midi_output;
+mod opc_output;
+mod panic_report;
+mod payload_template;
+#[cfg(feature = "steamos")]
+mod performance;
+mod permissions;
+mod pipeline_metrics;
+mod poll_cadence;
+mod processing_pause;
+#[cfg(feature = "mock")]
+pub mod mock_gamepad;
+mod raw_dump;
+mod recording;
+mod resume_detector;
+mod resume_recovery;
+mod runtime_metrics;
+#[cfg(test)]
+mod scratch_dir;
+mod send_statistics;
+mod session_display;
+mod session_summary;
+mod settings;
+mod show_playback;
+mod shutdown;
+mod signal_quality;
+mod slot_assignment;
+mod state_revisions;
+mod steam_deck;
+mod steam_input_check;
+mod steam_shortcut;
+mod suspend_inhibit;
+mod system_info;
+mod system_power;
+#[cfg(feature = "test-support")]
+pub mod test_support;
+mod thread_priority;
+mod time_series;
+mod touch_zones;
+mod touchpad;
+mod uinput_output;
+mod unhandled_events;
+mod update_status;
+mod virtual_pads;
+mod window_chord;
+mod window_focus;
+
+#[cfg(feature = "mock")]
+pub use gamepad::{ControllerEvent, ControllerState, GamepadInput};
 
+use app_state::AppState;
+use connection_stats::ConnectionStatsStore;
 use gamepad::GamepadManager;
 use evdev_gamepad::EvdevGamepadManager;
-use std::sync::Arc;
-use std::time::Duration;
-use tauri::Manager;
+use dock::DockStatus;
+use error_counters::ErrorCategory;
+use event_batcher::EventBatcher;
+use event_script::EventScripts;
+use external_processor::ExternalProcessor;
+use event_stream::EventStream;
+use http_client::HttpClients;
+use hidraw::LizardMode;
+use health::{BackendRecovered, BackendStalled, HealthMonitor, PollSource, StallChange};
+use iio_motion::{IioMotion, MotionConfig};
+use input_backend::InputBackends;
+use key_emulation::KeyEmulator;
+use light_server::LightServer;
+use midi_output::MidiOutputManager;
+use opc_output::OpcOutput;
+use payload_template::CompiledTemplate;
+use panic_report::PanicMonitor;
+use recording::InputSession;
+use resume_detector::{LogindResume, ResumeDetector, ResumeTrigger};
+use runtime_metrics::Timing;
+use settings::SettingsManager;
+use show_playback::ShowPlayback;
+use shutdown::{ShutdownSignal, POLL_THREAD_JOIN_TIMEOUT};
+use slot_assignment::SlotAssignments;
+use steam_deck::SessionKind;
+use steam_input_check::SteamInputCheck;
+use suspend_inhibit::SuspendInhibitor;
+use system_info::SystemInfo;
+use system_power::SystemPowerMonitor;
+use uinput_output::UinputOutput;
+use update_status::UpdateStatus;
+use virtual_pads::VirtualPadFilter;
+use window_focus::WindowFocus;
+use std::panic::{catch_unwind, AssertUnwindSafe};
+use std::sync::mpsc::RecvTimeoutError;
+use std::sync::{Arc, Mutex};
+use std::thread::JoinHandle;
+use std::time::Instant;
+use tauri::{AppHandle, Emitter, Manager, RunEvent, WindowEvent};
+use tracing::{error, info, warn};
+
+/// Runs `pass` until `source` is told to stop, sleeping between passes for as long as
+/// the adaptive poll cadence says and updating its heartbeat each pass. A panicking pass
+/// is reported by the panic hook; it's counted and polling carries on rather than
+/// letting it end the thread.
+fn spawn_poll_loop<F: FnMut() + Send + 'static>(
+    app_handle: &AppHandle,
+    source: PollSource,
+    mut pass: F,
+) -> JoinHandle<()> {
+    let state = app_handle.state::<AppState>();
+    let shutdown = state.shutdown.clone();
+    let health_monitor = state.health_monitor.clone();
+    let panic_monitor = state.panic_monitor.clone();
+    let raise_priority = !state.settings.get().disable_input_priority;
+    let name = format!("{}-poll", source.name());
+    std::thread::Builder::new()
+        .name(name.clone())
+        .spawn(move || {
+            thread_priority::raise_input_thread(&name, raise_priority);
+            while shutdown.should_poll(source) {
+                health_monitor.heartbeat(source);
+                if catch_unwind(AssertUnwindSafe(&mut pass)).is_err() {
+                    panic_monitor.record_poll_panic();
+                }
+                std::thread::sleep(poll_cadence::cadence().next_interval(source));
+            }
+            info!("{} polling thread stopped", source.name());
+        })
+        .expect("Failed to spawn polling thread")
+}
+
+/// Polls the `source` backend from the registry; while it's disabled, passes do nothing
+/// but keep the heartbeat going.
+fn spawn_polling_thread(app_handle: AppHandle, source: PollSource) -> JoinHandle<()> {
+    let backends = app_handle.state::<AppState>().backends.clone();
+    let backend = backends.get(source).expect("polling thread started for an unregistered backend");
+    let timing = match source {
+        PollSource::Gilrs => Timing::GilrsPoll,
+        PollSource::Evdev => Timing::EvdevPoll,
+    };
+    spawn_poll_loop(&app_handle.clone(), source, move || {
+        if !backends.is_enabled(source) {
+            return;
+        }
+        let started = Instant::now();
+        if let Err(e) = backend.drain_events(&app_handle) {
+            error!("{} polling error: {}", source.name(), e);
+            error_counters::errors().record(ErrorCategory::DeviceRead, e);
+        }
+        runtime_metrics::metrics().record_timing(timing, started.elapsed());
+    })
+}
+
+/// Runs the backends' slow upkeep at normal priority, away from the polling threads.
+fn spawn_maintenance(app_handle: AppHandle) {
+    let state = app_handle.state::<AppState>();
+    let backends = state.backends.clone();
+    let evdev_manager = state.evdev_manager.clone();
+    let gamepad_manager = state.gamepad_manager.clone();
+    let shutdown = state.shutdown.clone();
+    let panic_monitor = state.panic_monitor.clone();
+    let virtual_pads = state.virtual_pads.clone();
+    std::thread::Builder::new()
+        .name("input-maintenance".to_string())
+        .spawn(move || {
+            while !shutdown.is_requested() {
+                if catch_unwind(AssertUnwindSafe(|| backends.maintain_all(&app_handle))).is_err() {
+                    panic_monitor.record_poll_panic();
+                }
+                // Devices come and go with maintenance, so the policy and cadence follow them here
+                let devices = evdev_manager.get_detected_devices();
+                let gamepads = gamepad_manager.gamepad_infos();
+                virtual_pads.refresh(&devices, &gamepads);
+                poll_cadence::cadence().set_controllers_present(
+                    gamepads.iter().any(|gamepad| gamepad.is_connected) || devices.iter().any(|device| device.is_gamepad && device.accessible),
+                );
+                std::thread::sleep(input_backend::MAINTENANCE_INTERVAL);
+            }
+        })
+        .expect("Failed to spawn input maintenance thread");
+}
+
+/// Writes out the event stream every few seconds, and stops it once its disk runs low,
+/// emitting `event-stream-stopped`.
+fn spawn_event_stream_flusher(app_handle: AppHandle) {
+    let state = app_handle.state::<AppState>();
+    let settings = state.settings.clone();
+    let shutdown = state.shutdown.clone();
+    let event_stream = state.event_stream.clone();
+    std::thread::spawn(move || loop {
+        std::thread::sleep(event_stream::FLUSH_INTERVAL);
+        if shutdown.is_requested() {
+            break;
+        }
+        let Some(stopped) = event_stream.maintain() else {
+            continue;
+        };
+        if let Err(e) = settings.update(|s| s.event_stream = None) {
+            warn!("Failed to save settings: {}", e);
+        }
+        if let Err(e) = app_handle.emit("event-stream-stopped", stopped) {
+            error_counters::errors().record(ErrorCategory::Emit, format!("event-stream-stopped: {}", e));
+        }
+    });
+}
+
+/// Keeps lizard mode off while it's suppressed; the Deck turns it back on by itself once
+/// these stop, so a crash or hang restores it too.
+fn spawn_lizard_heartbeat(app_handle: AppHandle) {
+    let state = app_handle.state::<AppState>();
+    let lizard_mode = state.lizard_mode.clone();
+    let shutdown = state.shutdown.clone();
+    std::thread::Builder::new()
+        .name("lizard-heartbeat".to_string())
+        .spawn(move || {
+            while !shutdown.is_requested() {
+                lizard_mode.heartbeat();
+                std::thread::sleep(hidraw::LIZARD_HEARTBEAT_INTERVAL);
+            }
+        })
+        .expect("Failed to spawn lizard mode heartbeat thread");
+}
+
+/// Keeps the system from suspending while input is being forwarded or a recording replays.
+fn spawn_suspend_inhibitor(app_handle: AppHandle) {
+    let state = app_handle.state::<AppState>();
+    let settings = state.settings.clone();
+    let session = state.session.clone();
+    let shutdown = state.shutdown.clone();
+    let inhibitor = state.suspend_inhibitor.clone();
+    std::thread::Builder::new()
+        .name("suspend-inhibitor".to_string())
+        .spawn(move || {
+            while !shutdown.is_requested() {
+                let forwarding = settings.get().light_server_endpoint.is_some() && !processing_pause::forwarding_paused();
+                inhibitor.set_automatic(forwarding, session.is_replaying());
+                std::thread::sleep(suspend_inhibit::INHIBIT_CHECK_INTERVAL);
+            }
+        })
+        .expect("Failed to spawn suspend inhibitor thread");
+}
+
+/// Polls the system battery for `get_system_power` and its warnings, and checks it against
+/// the low battery action, if one is configured.
+fn spawn_battery_monitor(app_handle: AppHandle) {
+    let state = app_handle.state::<AppState>();
+    let evdev_manager = state.evdev_manager.clone();
+    let system_power = state.system_power.clone();
+    let shutdown = state.shutdown.clone();
+    tauri::async_runtime::spawn(async move {
+        loop {
+            system_power.poll(&app_handle);
+            tokio::time::sleep(low_battery::BATTERY_CHECK_INTERVAL).await;
+            if shutdown.is_requested() {
+                break;
+            }
+            evdev_manager.check_low_battery(&app_handle).await;
+        }
+    });
+}
+
+/// Recovers input devices after the system resumes from suspend. logind says when it
+/// wakes; without it, a jump of the wall clock past the monotonic clock gives it away.
+fn spawn_resume_monitor(app_handle: AppHandle) {
+    let shutdown = app_handle.state::<AppState>().shutdown.clone();
+    let (resume_tx, resume_rx) = std::sync::mpsc::channel();
+    let mut logind = match resume_detector::watch_logind(resume_tx) {
+        Ok(()) => {
+            info!("Watching logind for suspend and resume");
+            true
+        }
+        Err(e) => {
+            info!("{}; detecting resume from clock jumps instead", e);
+            false
+        }
+    };
+    std::thread::spawn(move || {
+        let mut detector = ResumeDetector::default();
+        loop {
+            let resume = if logind {
+                match resume_rx.recv_timeout(resume_detector::RESUME_CHECK_INTERVAL) {
+                    Ok(resume) => Some(resume),
+                    Err(RecvTimeoutError::Timeout) => None,
+                    Err(RecvTimeoutError::Disconnected) => {
+                        logind = false;
+                        None
+                    }
+                }
+            } else {
+                std::thread::sleep(resume_detector::RESUME_CHECK_INTERVAL);
+                None
+            };
+            if shutdown.is_requested() {
+                break;
+            }
+            // Checked every time so a logind resume also absorbs the jump it caused
+            let clock_jump = detector.check();
+            match resume {
+                Some(LogindResume { suspended_for }) => {
+                    resume_recovery::recover(&app_handle, ResumeTrigger::Logind, suspended_for.or(clock_jump));
+                }
+                None if !logind => {
+                    if let Some(suspended_for) = clock_jump {
+                        resume_recovery::recover(&app_handle, ResumeTrigger::ClockJump, Some(suspended_for));
+                    }
+                }
+                None => {}
+            }
+        }
+    });
+}
+
+/// Watches each pipeline's heartbeat, emitting `backend-stalled` when it goes stale and
+/// `backend-recovered` when it resumes. A polling thread that has exited is started
+/// again; one stuck inside a manager (e.g. on a lock) can only be reported, since the
+/// managers are shared with commands and can't be rebuilt underneath them.
+fn spawn_watchdog(app_handle: AppHandle) {
+    let state = app_handle.state::<AppState>();
+    let health_monitor = state.health_monitor.clone();
+    let shutdown = state.shutdown.clone();
+    std::thread::spawn(move || loop {
+        std::thread::sleep(health::WATCHDOG_INTERVAL);
+        if shutdown.is_requested() {
+            break;
+        }
+        for source in PollSource::ALL {
+            let restarted_poll_thread = shutdown.restart_poll_thread_if_finished(source, || {
+                error!("{} polling thread exited; restarting it", source.name());
+                spawn_polling_thread(app_handle.clone(), source)
+            });
+            match health_monitor.check_stall(source) {
+                Some(StallChange::Stalled { heartbeat_age_ms }) => {
+                    error!("{} polling thread stalled: no heartbeat for {}ms", source.name(), heartbeat_age_ms);
+                    let stalled = BackendStalled { source, heartbeat_age_ms, restarted_poll_thread };
+                    if let Err(e) = app_handle.emit("backend-stalled", stalled) {
+                        error_counters::errors().record(ErrorCategory::Emit, format!("backend-stalled: {}", e));
+                    }
+                }
+                Some(StallChange::Recovered { stalled_for_ms }) => {
+                    info!("{} polling thread recovered after about {}ms", source.name(), stalled_for_ms);
+                    app_handle.emit("backend-recovered", BackendRecovered { source, stalled_for_ms }).ok();
+                }
+                None => {}
+            }
+        }
+    });
+}
 
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
+    let panic_monitor = Arc::new(PanicMonitor::new());
+    panic_report::install(panic_monitor.clone());
+    
     tauri::Builder::default()
         .plugin(tauri_plugin_opener::init())
         .plugin(tauri_plugin_updater::Builder::new().build())
         .plugin(tauri_plugin_process::init())
-        .setup(|app| {
-            let gamepad_manager = GamepadManager::new()
-                .expect("Failed to initialize gamepad manager");
+        .setup(move |app| {
+            match logging::init(&app.path().app_log_dir()?) {
+                Ok(path) => info!("Logging to {}", path.display()),
+                Err(e) => eprintln!("Failed to initialize file logging: {}", e),
+            }
+            info!("Starting steam-deck-controller v{}", app.package_info().version);
+            
+            let system_info = Arc::new(SystemInfo::collect());
+            info!("System: {}", system_info.summary());
+            poll_cadence::cadence().set_game_mode(system_info.session_kind == SessionKind::GameMode);
+            
+            panic_monitor.attach_app(app.handle().clone());
+            error_counters::errors().attach_app(app.handle().clone());
+            
+            let settings_path = app.path().app_config_dir()?.join("settings.json");
+            let settings = Arc::new(SettingsManager::load(settings_path));
+            if let Some(filter) = settings.get().log_filter.filter(|_| !logging::filter_set_by_env()) {
+                if let Err(e) = logging::set_filter(&filter) {
+                    warn!("Ignoring saved log filter: {}", e);
+                }
+            }
+            controller_event::set_legacy_events_enabled(!settings.get().disable_legacy_events);
+            if let Some(mode) = settings.get().processing_paused {
+                warn!("Input processing is paused ({:?}) from the last session", mode);
+                processing_pause::set_pause_mode(Some(mode));
+            }
+            if let Err(e) = poll_cadence::cadence().configure(settings.get().poll_cadence) {
+                warn!("Ignoring saved poll cadence: {}", e);
+            }
+            
+            let connection_stats = Arc::new(ConnectionStatsStore::load());
+            let http = Arc::new(HttpClients::new()?);
+            http.set_self_signed_hosts(&settings.get().self_signed_hosts);
+            let light_server = Arc::new(LightServer::new(connection_stats.clone(), http.clone()));
+            light_server.attach_app(app.handle().clone());
+            if let Some(template) = settings.get().payload_template {
+                match CompiledTemplate::compile(&template) {
+                    Ok(compiled) => light_server.set_payload_template(Some(compiled)),
+                    Err(e) => warn!("Ignoring saved payload template: {}", e),
+                }
+            }
+            let stats_to_save = connection_stats.clone();
+            std::thread::spawn(move || loop {
+                std::thread::sleep(connection_stats::SAVE_INTERVAL);
+                stats_to_save.save_if_dirty();
+            });
+            
+            let batcher = Arc::new(Mutex::new(EventBatcher::default()));
+            let session = Arc::new(InputSession::new());
+            
+            let disabled_backends = settings.get().disabled_backends;
+            let background_mode = settings.get().background_mode;
+            let virtual_gamepad = Arc::new(UinputOutput::default());
+            let virtual_pads = Arc::new(VirtualPadFilter::new(virtual_gamepad.clone()));
+            virtual_pads.set_policy(settings.get().virtual_pad_policy);
+            let window_focus = Arc::new(WindowFocus::default());
+            window_focus.set_background_input(settings.get().background_input);
+            let key_emulator = Arc::new(KeyEmulator::default());
+            key_emulator.set_config(&settings.get().key_emulation);
+            let midi = settings.get().midi;
+            let midi_output = Arc::new(MidiOutputManager::default());
+            midi_output.set_rules(&midi.rules);
+            if let Some(port) = midi.output {
+                if let Err(e) = midi_output.open(&port) {
+                    warn!("Couldn't reopen MIDI output {}: {}", port, e);
+                }
+            }
+            let event_scripts = Arc::new(EventScripts::default());
+            event_scripts.load_saved(&settings.get().event_scripts);
+            let opc_output = Arc::new(OpcOutput::default());
+            if let Some(opc) = settings.get().opc {
+                if let Err(e) = opc_output.configure(app.handle(), opc) {
+                    warn!("Ignoring saved OPC output: {}", e);
+                }
+            }
+            let event_stream = Arc::new(EventStream::default());
+            if let Some(stream) = settings.get().event_stream {
+                if let Err(e) = event_stream.start(stream) {
+                    warn!("Couldn't resume the event stream: {}", e);
+                }
+            }
+            let (input_tx, input_rx) = input_pipeline::channel();
+            if let Err(e) = input_tx.bus().configure(settings.get().event_bus) {
+                warn!("Ignoring saved event bus config: {}", e);
+            }
+            let event_bus = input_tx.bus();
+            let gamepad_manager = GamepadManager::new(
+                settings.clone(),
+                light_server.clone(),
+                batcher.clone(),
+                session.clone(),
+                input_tx.clone(),
+            )
+            .expect("Failed to initialize gamepad manager");
             
             let gamepad_manager = Arc::new(gamepad_manager);
-            app.manage(gamepad_manager.clone());
             
             // Initialize evdev gamepad manager for Steam Deck compatibility
-            let evdev_manager = EvdevGamepadManager::new()
+            let evdev_manager = EvdevGamepadManager::new(session.clone(), input_tx.clone())
                 .expect("Failed to initialize evdev gamepad manager");
+            evdev_manager.set_ignored_devices(settings.get().ignored_devices);
             let evdev_manager = Arc::new(evdev_manager);
-            app.manage(evdev_manager.clone());
+            let mut motion_config = settings.get().motion;
+            if let Err(e) = motion_config.validate() {
+                warn!("Ignoring saved motion settings: {}", e);
+                motion_config = MotionConfig::default();
+            }
+            let iio_motion = Arc::new(IioMotion::new(motion_config, session.clone(), input_tx));
+            let battery_warnings = match settings.get().battery_warning_thresholds.map(|thresholds| system_power::validate_thresholds(&thresholds)) {
+                Some(Ok(thresholds)) => thresholds,
+                Some(Err(e)) => {
+                    warn!("Ignoring saved battery warning thresholds: {}", e);
+                    system_power::DEFAULT_BATTERY_WARNINGS.to_vec()
+                }
+                None => system_power::DEFAULT_BATTERY_WARNINGS.to_vec(),
+            };
             
-            // Scan for evdev devices on startup
-            if let Err(e) = evdev_manager.scan_for_gamepad_devices() {
-                println!("⚠️  Failed to scan evdev devices: {}", e);
+            let mut backends = InputBackends::default();
+            backends.register(gamepad_manager.clone(), !disabled_backends.contains(&PollSource::Gilrs));
+            // Elsewhere the evdev manager is a stub and gilrs is the only backend
+            #[cfg(target_os = "linux")]
+            backends.register(evdev_manager.clone(), !disabled_backends.contains(&PollSource::Evdev));
+            backends.init_all(app.handle());
+            let backends = Arc::new(backends);
+            
+            app.manage(AppState {
+                backends: backends.clone(),
+                gamepad_manager: gamepad_manager.clone(),
+                evdev_manager,
+                iio_motion,
+                event_bus,
+                session,
+                slot_assignments: Arc::new(SlotAssignments::default()),
+                settings,
+                light_server,
+                midi_output,
+                opc_output,
+                show_playback: Arc::new(ShowPlayback::default()),
+                http,
+                batcher,
+                event_stream,
+                event_scripts,
+                external_processor: Arc::new(ExternalProcessor::default()),
+                connection_stats,
+                health_monitor: Arc::new(HealthMonitor::new()),
+                panic_monitor: panic_monitor.clone(),
+                shutdown: Arc::new(ShutdownSignal::default()),
+                system_info,
+                system_power: Arc::new(SystemPowerMonitor::new(battery_warnings)),
+                update_status: Arc::new(Mutex::new(UpdateStatus::default())),
+                virtual_pads,
+                virtual_gamepad,
+                steam_input: Arc::new(Mutex::new(SteamInputCheck::default())),
+                suspend_inhibitor: Arc::new(SuspendInhibitor::default()),
+                dock: Arc::new(Mutex::new(DockStatus::default())),
+                lizard_mode: Arc::new(LizardMode::default()),
+                window_focus,
+                key_emulator,
+            });
+            // Input queued by the backends' first scans waits here until the dispatcher starts
+            input_pipeline::spawn_dispatcher(app.handle().clone(), input_rx);
+            
+            background::apply_background_mode(app.handle(), background_mode);
+            session_display::apply(app.handle());
+            {
+                let state = app.state::<AppState>();
+                state.lizard_mode.set_enabled(!state.settings.get().disable_lizard_mode);
+                if let Some(window) = app.get_webview_window(background::MAIN_WINDOW) {
+                    let focused = window.is_focused().unwrap_or(false);
+                    state.lizard_mode.set_focused(focused);
+                    state.window_focus.set_window_focused(focused);
+                }
             }
+            let handle = app.handle().clone();
+            gamepad_manager.on_window_chord(move || background::show_main_window(&handle));
             
-            let app_handle = app.handle().clone();
-            let evdev_manager_clone = evdev_manager.clone();
-            std::thread::spawn(move || {
-                loop {
-                    gamepad_manager.poll_events(&app_handle);
-                    if let Err(e) = evdev_manager_clone.poll_events(&app_handle) {
-                        println!("⚠️  Evdev polling error: {}", e);
+            // With the mock feature, scripted/generated input replaces gilrs as the source
+            #[cfg(feature = "mock")]
+            {
+                let mock = mock_gamepad::MockGamepadManager::new(mock_gamepad::MockGamepadConfig::from_env());
+                let gamepad_manager = gamepad_manager.clone();
+                tauri::async_runtime::spawn(async move {
+                    let result = mock.run(|event| {
+                        if let Some(input) = event.to_input() {
+                            gamepad_manager.handle_input(event.controller_id, input, true);
+                        }
+                    }).await;
+                    if let Err(e) = result {
+                        error!("Mock gamepad failed: {}", e);
                     }
-                    std::thread::sleep(Duration::from_millis(10));
-                }
-            });
+                });
+            }
+            
+            for source in backends.sources() {
+                app.state::<AppState>().shutdown.set_poll_thread(source, spawn_polling_thread(app.handle().clone(), source));
+            }
+            iio_motion::spawn_sampler(app.handle().clone());
+            spawn_maintenance(app.handle().clone());
+            spawn_event_stream_flusher(app.handle().clone());
+            spawn_lizard_heartbeat(app.handle().clone());
+            spawn_suspend_inhibitor(app.handle().clone());
+            dock::spawn_monitor(app.handle().clone());
+            steam_input_check::spawn_monitor(app.handle().clone());
+            window_focus::spawn_monitor(app.handle().clone());
+            spawn_watchdog(app.handle().clone());
+            spawn_battery_monitor(app.handle().clone());
+            spawn_resume_monitor(app.handle().clone());
+            frontend_sync::spawn_heartbeat(app.handle().clone());
             
             Ok(())
         })
-        .invoke_handler(tauri::generate_handler![
-            commands::get_connected_controllers,
-            commands::get_controller_state,
-            commands::get_debug_info,
-            commands::send_to_light_server,
-            commands::get_evdev_devices,
-            commands::rescan_evdev_devices,
-            commands::get_steam_deck_info,
-            commands::check_for_updates,
-            commands::download_and_install_update,
-            commands::exit_app,
-            commands::restart_app,
-        ])
-        .run(tauri::generate_context!())
-        .expect("error while running tauri application");
+        .on_window_event(|window, event| match event {
+            WindowEvent::CloseRequested { api, .. } => {
+                let background_mode = window.try_state::<AppState>().is_some_and(|state| state.settings.get().background_mode);
+                if background_mode {
+                    api.prevent_close();
+                    background::hide_main_window(window);
+                }
+            }
+            WindowEvent::Focused(_) | WindowEvent::Resized(_) => {
+                if let WindowEvent::Focused(focused) = event {
+                    if let Some(state) = window.try_state::<AppState>() {
+                        state.lizard_mode.set_focused(*focused);
+                        state.window_focus.set_window_focused(*focused);
+                    }
+                }
+                // Minimizing resizes the window on most platforms; hiding it to the tray takes focus
+                let visible = window.is_visible().unwrap_or(true) && !window.is_minimized().unwrap_or(false);
+                emit_interest::interest().set_window_visible(visible);
+            }
+            _ => {}
+        })
+        .invoke_handler(commands::handler())
+        .build(tauri::generate_context!())
+        .expect("error while building tauri application")
+        .run(|app, event| match event {
+            RunEvent::ExitRequested { .. } => {
+                if let Some(state) = app.try_state::<AppState>() {
+                    if state.shutdown.request() {
+                        info!("Exit requested; stopping background polling");
+                    }
+                }
+            }
+            RunEvent::Exit => {
+                shut_down_background(app);
+                session_summary::write_on_shutdown(app);
+                if let Some(state) = app.try_state::<AppState>() {
+                    state.connection_stats.save_if_dirty();
+                }
+            }
+            _ => {}
+        });
+}
+
+/// Stops each polling thread, then lets its backend release devices and flush pending
+/// work. A thread that doesn't stop in time may hold its backend's locks, so that backend
+/// is left alone rather than risking a hang on exit; the others still shut down.
+fn shut_down_background(app: &AppHandle) {
+    let Some(state) = app.try_state::<AppState>() else {
+        return;
+    };
+    state.shutdown.request();
+    state.suspend_inhibitor.release();
+    restore_lizard_mode(state.lizard_mode.clone());
+    let mut evdev_stopped = true;
+    for source in state.backends.sources() {
+        if !state.shutdown.stop_poll_thread(source, POLL_THREAD_JOIN_TIMEOUT) {
+            warn!("{} polling thread didn't stop within {:?}; skipping its backend's shutdown", source.name(), POLL_THREAD_JOIN_TIMEOUT);
+            evdev_stopped &= source != PollSource::Evdev;
+            continue;
+        }
+        if let Some(backend) = state.backends.get(source) {
+            backend.shutdown();
+        }
+    }
+    // Releasing the passthrough's grab takes the evdev locks; exiting removes the device anyway
+    if evdev_stopped {
+        state.virtual_gamepad.destroy();
+    }
+    state.key_emulator.disable();
+    state.midi_output.close();
+    state.external_processor.stop();
+    // No cue should go out while the app is on its way down
+    state.show_playback.pause(app).ok();
+    // Left in settings, so it reconnects when the app next starts
+    state.opc_output.stop();
+    // Flushed but left in settings, so the stream resumes when the app next starts
+    state.event_stream.stop().ok();
+}
+
+/// Turns lizard mode back on, without letting a stuck hidraw write hold up exit; the
+/// Deck's own timeout restores it in that case.
+fn restore_lizard_mode(lizard_mode: Arc<LizardMode>) {
+    let (done_tx, done_rx) = std::sync::mpsc::channel();
+    let spawned = std::thread::Builder::new().name("lizard-restore".to_string()).spawn(move || {
+        lizard_mode.restore();
+        let _ = done_tx.send(());
+    });
+    if spawned.is_ok() && done_rx.recv_timeout(hidraw::RESTORE_TIMEOUT).is_err() {
+        warn!("Lizard mode wasn't restored within {:?}", hidraw::RESTORE_TIMEOUT);
+    }
 }
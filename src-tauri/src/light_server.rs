@@ -0,0 +1,267 @@
+use crate::app_state::AppState;
+use crate::connection_stats::ConnectionStatsStore;
+use crate::controller_event::UnifiedControllerEvent;
+use crate::emit_interest::EventChannel;
+use crate::emitter::EventEmitter;
+use crate::error::AppError;
+use crate::http_client::HttpClients;
+use crate::latency_benchmark::{self, Stage};
+use crate::locking::MutexExt;
+use crate::memory_stats::{deque_bytes, BufferStats};
+use crate::payload_template::CompiledTemplate;
+use crate::processing_pause;
+use crate::send_statistics::{SendStatistics, SendStatisticsSnapshot};
+use reqwest::Url;
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex, OnceLock};
+use std::time::{SystemTime, UNIX_EPOCH};
+use tauri::{AppHandle, Manager};
+use tracing::warn;
+
+/// Outcome of recent light server sends, for diagnostics.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct LightServerStatus {
+    pub last_endpoint: Option<String>,
+    pub requests_sent: u64,
+    pub requests_failed: u64,
+    pub last_success_time: Option<u64>,
+    pub last_error: Option<String>,
+    pub last_error_time: Option<u64>,
+    /// Batches the server accepted, and the events they carried
+    pub batches_sent: u64,
+    pub batched_events_sent: u64,
+    pub events_per_batch_avg: f64,
+}
+
+/// The server's verdict on one event of a batch, in the order the events were sent.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BatchEventResult {
+    pub ok: bool,
+    #[serde(default)]
+    pub error: Option<String>,
+}
+
+/// Number of recent sends kept for debug reports.
+const RECENT_SENDS_CAPACITY: usize = 100;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SendRecord {
+    pub endpoint: String,
+    pub timestamp: u64,
+    pub duration_ms: u64,
+    pub result: Result<String, String>,
+}
+
+/// Sends controller actions to the light server and keeps track of how that's going.
+pub struct LightServer {
+    status: Mutex<LightServerStatus>,
+    recent_sends: Mutex<VecDeque<SendRecord>>,
+    connection_stats: Arc<ConnectionStatsStore>,
+    http: Arc<HttpClients>,
+    send_statistics: Mutex<SendStatistics>,
+    payload_template: Mutex<Option<CompiledTemplate>>,
+    app: OnceLock<AppHandle>,
+}
+
+impl LightServer {
+    pub fn new(connection_stats: Arc<ConnectionStatsStore>, http: Arc<HttpClients>) -> Self {
+        Self {
+            status: Mutex::new(LightServerStatus::default()),
+            recent_sends: Mutex::new(VecDeque::new()),
+            connection_stats,
+            http,
+            send_statistics: Mutex::new(SendStatistics::default()),
+            payload_template: Mutex::new(None),
+            app: OnceLock::new(),
+        }
+    }
+    
+    pub fn attach_app(&self, app: AppHandle) {
+        let _ = self.app.set(app);
+    }
+    
+    /// POSTs `data` as JSON to the light server, returning an error for non-2xx responses.
+    pub fn post_json(&self, endpoint: &str, data: &serde_json::Value) -> Result<String, AppError> {
+        check_not_paused()?;
+        let started = std::time::Instant::now();
+        let result = Url::parse(endpoint)
+            .map_err(|e| AppError::invalid_argument(format!("Invalid light server endpoint {}: {}", endpoint, e)))
+            .and_then(|url| self.http.for_url(&url).map(|client| (client, url)))
+            .and_then(|(client, url)| {
+                client
+                    .post(url)
+                    .json(data)
+                    .send()
+                    .map_err(|e| AppError::network(None, format!("Failed to send to server: {}", e)))
+            })
+            .and_then(|response| {
+                if response.status().is_success() {
+                    Ok("Success".to_string())
+                } else {
+                    Err(server_error(response.status()))
+                }
+            });
+        
+        let outcome = result.clone().map_err(String::from);
+        self.record(endpoint, &outcome, started.elapsed().as_millis() as u64);
+        result
+    }
+    
+    /// POSTs `events` as a JSON array to `/batch` on the endpoint's server and returns
+    /// the per-event results from the response. With a payload template set, each event
+    /// is sent as its rendered template instead.
+    pub fn post_batch(&self, endpoint: &str, events: &[UnifiedControllerEvent]) -> Result<Vec<BatchEventResult>, AppError> {
+        check_not_paused()?;
+        let url = Url::parse(endpoint)
+            .and_then(|url| url.join("/batch"))
+            .map_err(|e| AppError::invalid_argument(format!("Invalid light server endpoint {}: {}", endpoint, e)))?;
+        
+        let benchmark = latency_benchmark::benchmark();
+        for source_time in events.iter().filter_map(|event| event.source_time) {
+            benchmark.record(Stage::Dispatched, source_time);
+        }
+        
+        let payload = self.batch_payload(events)?;
+        let started = std::time::Instant::now();
+        let result = self.http.for_url(&url)
+            .and_then(|client| {
+                client
+                    .post(url.clone())
+                    .json(&payload)
+                    .send()
+                    .map_err(|e| AppError::network(None, format!("Failed to send batch to server: {}", e)))
+            })
+            .and_then(|response| {
+                let status = response.status();
+                if status.is_success() {
+                    response
+                        .json::<Vec<BatchEventResult>>()
+                        .map_err(|e| AppError::network(Some(status.as_u16()), format!("Invalid batch response: {}", e)))
+                } else {
+                    Err(server_error(status))
+                }
+            });
+        
+        let outcome = result.as_ref().map(|_| "Success".to_string()).map_err(AppError::to_string);
+        self.record(url.as_str(), &outcome, started.elapsed().as_millis() as u64);
+        if result.is_ok() {
+            let mut status = self.status.lock_or_recover();
+            status.batches_sent += 1;
+            status.batched_events_sent += events.len() as u64;
+            status.events_per_batch_avg = status.batched_events_sent as f64 / status.batches_sent as f64;
+        }
+        result
+    }
+    
+    /// Sets how events are rendered for `/batch`; `None` sends them as plain `UnifiedControllerEvent`s.
+    pub fn set_payload_template(&self, template: Option<CompiledTemplate>) {
+        *self.payload_template.lock_or_recover() = template;
+    }
+    
+    fn batch_payload(&self, events: &[UnifiedControllerEvent]) -> Result<serde_json::Value, String> {
+        match self.payload_template.lock_or_recover().as_ref() {
+            Some(template) => events.iter()
+                .map(|event| template.render_value(event))
+                .collect::<Result<Vec<_>, _>>()
+                .map(serde_json::Value::Array),
+            None => serde_json::to_value(events)
+                .map_err(|e| format!("Failed to serialize batch: {}", e)),
+        }
+    }
+    
+    pub fn get_status(&self) -> LightServerStatus {
+        self.status.lock_or_recover().clone()
+    }
+    
+    pub fn get_recent_sends(&self) -> Vec<SendRecord> {
+        self.recent_sends.lock_or_recover().iter().cloned().collect()
+    }
+    
+    pub fn buffer_stats(&self) -> Vec<BufferStats> {
+        let recent_sends = self.recent_sends.lock_or_recover();
+        let send_bytes = deque_bytes(&recent_sends)
+            + recent_sends.iter()
+                .map(|send| send.endpoint.capacity() + send.result.as_ref().map_or_else(String::capacity, String::capacity))
+                .sum::<usize>();
+        vec![
+            BufferStats::new("recent_sends", recent_sends.len(), Some(RECENT_SENDS_CAPACITY), 1, send_bytes),
+            self.send_statistics.lock_or_recover().buffer_stats(),
+        ]
+    }
+    
+    pub fn get_send_statistics(&self) -> SendStatisticsSnapshot {
+        self.send_statistics.lock_or_recover().snapshot(now_ms())
+    }
+    
+    pub fn reset_send_statistics(&self) {
+        self.send_statistics.lock_or_recover().reset();
+    }
+    
+    /// Sets the p95 round trip above which `light-server-slow` is emitted; `None` turns it off.
+    pub fn set_slow_threshold(&self, threshold_ms: Option<u64>) {
+        self.send_statistics.lock_or_recover().set_slow_threshold(threshold_ms);
+    }
+    
+    fn record(&self, endpoint: &str, result: &Result<String, String>, duration_ms: u64) {
+        let now = now_ms();
+        
+        let mut status = self.status.lock_or_recover();
+        status.last_endpoint = Some(endpoint.to_string());
+        status.requests_sent += 1;
+        match result {
+            Ok(_) => status.last_success_time = Some(now),
+            Err(e) => {
+                status.requests_failed += 1;
+                status.last_error = Some(e.clone());
+                status.last_error_time = Some(now);
+            }
+        }
+        drop(status);
+        self.connection_stats.record(result, duration_ms);
+        if let Some(state) = self.app.get().and_then(|app| app.try_state::<AppState>()) {
+            state.event_stream.write_send(endpoint, result, duration_ms);
+        }
+        
+        let slow = self.send_statistics.lock_or_recover().record(endpoint, result.is_ok(), duration_ms, now);
+        if let Some(slow) = slow {
+            warn!(
+                "Light server {} is slow: p95 {}ms over the last minute (threshold {}ms)",
+                slow.endpoint, slow.p95_ms, slow.threshold_ms
+            );
+            if let Some(app) = self.app.get() {
+                app.emit_on(EventChannel::Network, "light-server-slow", slow).ok();
+            }
+        }
+        
+        let mut recent = self.recent_sends.lock_or_recover();
+        if recent.len() == RECENT_SENDS_CAPACITY {
+            recent.pop_front();
+        }
+        recent.push_back(SendRecord {
+            endpoint: endpoint.to_string(),
+            timestamp: now,
+            duration_ms,
+            result: result.clone(),
+        });
+    }
+}
+
+/// Last line of defence for pausing: nothing reaches the server while paused, whoever asks.
+fn check_not_paused() -> Result<(), AppError> {
+    if processing_pause::forwarding_paused() {
+        return Err(AppError::invalid_argument("Input processing is paused; nothing is sent to the light server"));
+    }
+    Ok(())
+}
+
+fn server_error(status: reqwest::StatusCode) -> AppError {
+    AppError::network(Some(status.as_u16()), format!("Server returned error: {}", status))
+}
+
+fn now_ms() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_millis() as u64
+}
@@ -0,0 +1,386 @@
+use crate::locking::MutexExt;
+use crate::memory_stats::BufferStats;
+use serde::{Deserialize, Serialize};
+use std::fmt::Write as _;
+use std::fs::{File, OpenOptions};
+use std::io::{IsTerminal, Write};
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::sync::{Mutex, MutexGuard, OnceLock};
+use std::time::{SystemTime, UNIX_EPOCH};
+use tracing::field::{Field, Visit};
+use tracing::{Event, Level, Subscriber};
+use tracing_subscriber::fmt::MakeWriter;
+use tracing_subscriber::layer::Context;
+use tracing_subscriber::layer::SubscriberExt;
+use tracing_subscriber::util::SubscriberInitExt;
+use tracing_subscriber::{reload, EnvFilter, Registry};
+
+const LOG_FILE_NAME: &str = "steam-deck-controller.log";
+const MAX_LOG_FILE_BYTES: u64 = 5 * 1024 * 1024;
+/// Total number of log files kept, including the active one.
+const KEEP_LOG_FILES: usize = 5;
+const DEFAULT_FILTER: &str = "info";
+/// Number of records kept in memory for the in-app log viewer.
+const LOG_BUFFER_CAPACITY: usize = 2000;
+/// A slot's message keeps at most this much capacity once overwritten, so one huge record
+/// doesn't pin its size in that slot for the rest of the session.
+const SLOT_RETAINED_BYTES: usize = 1024;
+
+static LOG_FILE_PATH: OnceLock<PathBuf> = OnceLock::new();
+static FILTER_HANDLE: OnceLock<reload::Handle<EnvFilter, Registry>> = OnceLock::new();
+static LOG_BUFFER: OnceLock<Mutex<LogRingBuffer>> = OnceLock::new();
+
+fn log_buffer() -> &'static Mutex<LogRingBuffer> {
+    LOG_BUFFER.get_or_init(|| Mutex::new(LogRingBuffer::with_capacity(LOG_BUFFER_CAPACITY)))
+}
+
+/// Sets up the global tracing subscriber: a size-rotated file in `log_dir`, plus stdout
+/// when the process is attached to a terminal (e.g. `cargo tauri dev`).
+///
+/// `RUST_LOG` overrides the default `info` filter.
+pub fn init(log_dir: &Path) -> Result<PathBuf, String> {
+    std::fs::create_dir_all(log_dir)
+        .map_err(|e| format!("Failed to create log directory {}: {}", log_dir.display(), e))?;
+
+    let path = log_dir.join(LOG_FILE_NAME);
+    let file_writer = RotatingFileWriter::open(path.clone(), MAX_LOG_FILE_BYTES, KEEP_LOG_FILES)
+        .map_err(|e| format!("Failed to open log file {}: {}", path.display(), e))?;
+
+    let filter = EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new(DEFAULT_FILTER));
+    let (filter, filter_handle) = reload::Layer::new(filter);
+
+    let file_layer = tracing_subscriber::fmt::layer()
+        .with_ansi(false)
+        .with_writer(file_writer);
+
+    let stdout_layer = std::io::stdout()
+        .is_terminal()
+        .then(|| tracing_subscriber::fmt::layer().with_writer(std::io::stdout));
+
+    tracing_subscriber::registry()
+        .with(filter)
+        .with(file_layer)
+        .with(stdout_layer)
+        .with(RingBufferLayer)
+        .try_init()
+        .map_err(|e| format!("Failed to install log subscriber: {}", e))?;
+
+    let _ = FILTER_HANDLE.set(filter_handle);
+    let _ = LOG_FILE_PATH.set(path.clone());
+    Ok(path)
+}
+
+/// Whether `RUST_LOG` was set, in which case it wins over a persisted filter.
+pub fn filter_set_by_env() -> bool {
+    std::env::var_os(EnvFilter::DEFAULT_ENV).is_some()
+}
+
+/// Replaces the active filter with an env-filter style directive string such as
+/// `info,steam_deck_controller_lib::evdev_gamepad=trace`.
+pub fn set_filter(filter: &str) -> Result<(), String> {
+    let parsed = EnvFilter::try_new(filter)
+        .map_err(|e| format!("Invalid log filter '{}': {}", filter, e))?;
+    FILTER_HANDLE.get()
+        .ok_or_else(|| "Logging is not initialized".to_string())?
+        .reload(parsed)
+        .map_err(|e| format!("Failed to apply log filter: {}", e))
+}
+
+pub fn current_filter() -> Option<String> {
+    FILTER_HANDLE.get()?.with_current(|filter| filter.to_string()).ok()
+}
+
+pub fn log_file_path() -> Option<PathBuf> {
+    LOG_FILE_PATH.get().cloned()
+}
+
+/// The last `limit` lines of the active log file (older rotated files aren't consulted).
+pub fn recent_log_lines(limit: usize) -> Vec<String> {
+    let Some(contents) = log_file_path().and_then(|path| std::fs::read_to_string(path).ok()) else {
+        return Vec::new();
+    };
+    let mut lines: Vec<String> = contents.lines().rev().take(limit).map(str::to_string).collect();
+    lines.reverse();
+    lines
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LogRecord {
+    pub seq: u64,
+    pub timestamp: u64,
+    pub level: String,
+    pub target: String,
+    pub message: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LogBatch {
+    pub records: Vec<LogRecord>,
+    /// Pass as `after_seq` on the next call to continue where this batch stopped
+    pub next_after_seq: u64,
+    /// True when records after the requested `after_seq` were already evicted
+    pub missed: bool,
+}
+
+/// Buffered records from the in-memory log, oldest first.
+///
+/// With `after_seq`, returns up to `limit` records following it, for incremental polling;
+/// without it, returns the most recent `limit` records. `min_level` (e.g. `"warn"`) drops
+/// more verbose records.
+pub fn recent_logs(min_level: Option<&str>, limit: usize, after_seq: Option<u64>) -> Result<LogBatch, String> {
+    let min_level = min_level
+        .map(|level| Level::from_str(level).map_err(|_| format!("Unknown log level '{}'", level)))
+        .transpose()?
+        .unwrap_or(Level::TRACE);
+    Ok(log_buffer().lock_or_recover()
+        .query(min_level, limit, after_seq))
+}
+
+/// Target that messages forwarded from the UI are logged under.
+pub const FRONTEND_TARGET: &str = "frontend";
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FrontendLogEntry {
+    pub level: String,
+    pub message: String,
+}
+
+/// Logs a message forwarded from the UI, so it lands in the log file and debug report
+/// next to the backend's own records.
+pub fn buffer_stats() -> BufferStats {
+    let buffer = log_buffer().lock_or_recover();
+    let approx_bytes = buffer.slots.capacity() * std::mem::size_of::<LogSlot>()
+        + buffer.slots.iter().map(|slot| slot.target.capacity() + slot.message.capacity()).sum::<usize>();
+    BufferStats::new("log_buffer", buffer.slots.len(), Some(buffer.capacity), 1, approx_bytes)
+}
+
+pub fn log_frontend(level: &str, message: &str) -> Result<(), String> {
+    let level = Level::from_str(level).map_err(|_| format!("Unknown log level '{}'", level))?;
+    match level {
+        Level::ERROR => tracing::error!(target: FRONTEND_TARGET, "{}", message),
+        Level::WARN => tracing::warn!(target: FRONTEND_TARGET, "{}", message),
+        Level::INFO => tracing::info!(target: FRONTEND_TARGET, "{}", message),
+        Level::DEBUG => tracing::debug!(target: FRONTEND_TARGET, "{}", message),
+        Level::TRACE => tracing::trace!(target: FRONTEND_TARGET, "{}", message),
+    }
+    Ok(())
+}
+
+/// One reusable slot; its strings keep their capacity when overwritten.
+struct LogSlot {
+    seq: u64,
+    timestamp: u64,
+    level: Level,
+    target: String,
+    message: String,
+}
+
+/// Fixed-capacity ring of log records. Once full, new records overwrite the oldest slot
+/// in place, so steady-state logging doesn't allocate unless a message outgrows its slot.
+struct LogRingBuffer {
+    slots: Vec<LogSlot>,
+    capacity: usize,
+    /// Sequence number the next record will get; sequence numbers start at 1
+    next_seq: u64,
+}
+
+impl LogRingBuffer {
+    fn with_capacity(capacity: usize) -> Self {
+        Self {
+            slots: Vec::with_capacity(capacity),
+            capacity,
+            next_seq: 1,
+        }
+    }
+
+    fn next_slot(&mut self) -> &mut LogSlot {
+        let seq = self.next_seq;
+        self.next_seq += 1;
+        let index = ((seq - 1) % self.capacity as u64) as usize;
+        if index == self.slots.len() {
+            self.slots.push(LogSlot {
+                seq,
+                timestamp: 0,
+                level: Level::INFO,
+                target: String::new(),
+                message: String::new(),
+            });
+        }
+        let slot = &mut self.slots[index];
+        slot.seq = seq;
+        slot.target.clear();
+        slot.message.clear();
+        slot.message.shrink_to(SLOT_RETAINED_BYTES);
+        slot
+    }
+
+    fn slot(&self, seq: u64) -> &LogSlot {
+        &self.slots[((seq - 1) % self.capacity as u64) as usize]
+    }
+
+    fn oldest_seq(&self) -> u64 {
+        self.next_seq - self.slots.len() as u64
+    }
+
+    fn query(&self, min_level: Level, limit: usize, after_seq: Option<u64>) -> LogBatch {
+        let oldest = self.oldest_seq();
+        let latest = self.next_seq - 1;
+        let start = after_seq.map_or(oldest, |after| (after + 1).max(oldest));
+        // Level::TRACE > Level::ERROR in tracing's ordering, so "at least as severe" is <=
+        let matching = (start..=latest).filter(|seq| self.slot(*seq).level <= min_level);
+
+        let (seqs, next_after_seq): (Vec<u64>, u64) = match after_seq {
+            Some(_) => {
+                let seqs: Vec<u64> = matching.take(limit).collect();
+                let next = if seqs.len() == limit { *seqs.last().unwrap_or(&latest) } else { latest };
+                (seqs, next)
+            }
+            None => {
+                let mut seqs: Vec<u64> = matching.rev().take(limit).collect();
+                seqs.reverse();
+                (seqs, latest)
+            }
+        };
+
+        LogBatch {
+            records: seqs.into_iter()
+                .map(|seq| {
+                    let slot = self.slot(seq);
+                    LogRecord {
+                        seq: slot.seq,
+                        timestamp: slot.timestamp,
+                        level: slot.level.to_string(),
+                        target: slot.target.clone(),
+                        message: slot.message.clone(),
+                    }
+                })
+                .collect(),
+            next_after_seq,
+            missed: after_seq.is_some_and(|after| after + 1 < oldest),
+        }
+    }
+}
+
+/// Writes an event's message, then its other fields as ` key=value`.
+struct MessageVisitor<'a>(&'a mut String);
+
+impl Visit for MessageVisitor<'_> {
+    fn record_debug(&mut self, field: &Field, value: &dyn std::fmt::Debug) {
+        if field.name() == "message" {
+            let _ = write!(self.0, "{:?}", value);
+        } else {
+            let _ = write!(self.0, " {}={:?}", field.name(), value);
+        }
+    }
+
+    fn record_str(&mut self, field: &Field, value: &str) {
+        if field.name() == "message" {
+            self.0.push_str(value);
+        } else {
+            let _ = write!(self.0, " {}={}", field.name(), value);
+        }
+    }
+}
+
+/// Copies every record that passes the filter into the in-memory ring buffer.
+struct RingBufferLayer;
+
+impl<S: Subscriber> tracing_subscriber::Layer<S> for RingBufferLayer {
+    fn on_event(&self, event: &Event<'_>, _ctx: Context<'_, S>) {
+        let mut buffer = log_buffer().lock_or_recover();
+        let slot = buffer.next_slot();
+        slot.timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_millis() as u64;
+        slot.level = *event.metadata().level();
+        slot.target.push_str(event.metadata().target());
+        event.record(&mut MessageVisitor(&mut slot.message));
+    }
+}
+
+/// Appends to `path`, rotating to `path.1` .. `path.N` once the file exceeds `max_bytes`.
+pub struct RotatingFileWriter {
+    state: Mutex<RotatingState>,
+}
+
+struct RotatingState {
+    path: PathBuf,
+    file: File,
+    written: u64,
+    max_bytes: u64,
+    keep: usize,
+}
+
+impl RotatingFileWriter {
+    pub fn open(path: PathBuf, max_bytes: u64, keep: usize) -> std::io::Result<Self> {
+        let file = OpenOptions::new().create(true).append(true).open(&path)?;
+        let written = file.metadata().map(|m| m.len()).unwrap_or(0);
+        Ok(Self {
+            state: Mutex::new(RotatingState {
+                path,
+                file,
+                written,
+                max_bytes,
+                keep,
+            }),
+        })
+    }
+}
+
+impl RotatingState {
+    fn rotated_path(&self, index: usize) -> PathBuf {
+        let mut name = self.path.as_os_str().to_owned();
+        name.push(format!(".{}", index));
+        PathBuf::from(name)
+    }
+
+    fn rotate(&mut self) -> std::io::Result<()> {
+        self.file.flush()?;
+        let oldest = self.keep.saturating_sub(1);
+        if oldest == 0 {
+            self.file = File::create(&self.path)?;
+            self.written = 0;
+            return Ok(());
+        }
+
+        let _ = std::fs::remove_file(self.rotated_path(oldest));
+        for index in (1..oldest).rev() {
+            let _ = std::fs::rename(self.rotated_path(index), self.rotated_path(index + 1));
+        }
+        std::fs::rename(&self.path, self.rotated_path(1))?;
+
+        self.file = OpenOptions::new().create(true).append(true).open(&self.path)?;
+        self.written = 0;
+        Ok(())
+    }
+}
+
+pub struct RotatingFileGuard<'a>(MutexGuard<'a, RotatingState>);
+
+impl Write for RotatingFileGuard<'_> {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        let state = &mut *self.0;
+        if state.written > 0 && state.written + buf.len() as u64 > state.max_bytes {
+            // A failed rotation shouldn't lose the record; keep appending to the current file
+            let _ = state.rotate();
+        }
+        let written = state.file.write(buf)?;
+        state.written += written as u64;
+        Ok(written)
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        self.0.file.flush()
+    }
+}
+
+impl<'a> MakeWriter<'a> for RotatingFileWriter {
+    type Writer = RotatingFileGuard<'a>;
+
+    fn make_writer(&'a self) -> Self::Writer {
+        // A panic while logging must not disable logging for the rest of the session
+        RotatingFileGuard(self.state.lock_or_recover())
+    }
+}
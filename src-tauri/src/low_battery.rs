@@ -0,0 +1,124 @@
+use crate::locking::MutexExt;
+use crate::system_power::{self, POWER_SUPPLY_DIR};
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+use std::time::Duration;
+use tauri::{AppHandle, Emitter};
+use tracing::{info, warn};
+
+/// How often the battery is checked against the threshold.
+pub const BATTERY_CHECK_INTERVAL: Duration = Duration::from_secs(30);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LowBatteryAction {
+    /// Only emits `battery-action-threshold`
+    Notify,
+    Hibernate,
+    Suspend,
+    ShutDown,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LowBatteryConfig {
+    /// Battery percentage at or below which the action runs
+    pub threshold: u8,
+    pub action: LowBatteryAction,
+}
+
+/// Payload of `battery-action-threshold`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BatteryThresholdReached {
+    pub percent: u8,
+    pub threshold: u8,
+}
+
+/// The Deck's own battery, from the first `Battery` power supply in sysfs.
+#[derive(Debug, Clone, Copy)]
+pub struct BatteryReading {
+    pub percent: u8,
+    pub charging: bool,
+}
+
+pub fn read_battery() -> Option<BatteryReading> {
+    let battery = system_power::read_power(Path::new(POWER_SUPPLY_DIR)).battery?;
+    Some(BatteryReading {
+        percent: battery.percent,
+        charging: battery.state.on_external_power(),
+    })
+}
+
+/// Runs the configured action once each time the battery drops to the threshold while
+/// discharging; it has to climb back above the threshold before it can run again.
+#[derive(Default)]
+pub struct LowBatteryMonitor {
+    config: Mutex<Option<LowBatteryConfig>>,
+    triggered: AtomicBool,
+}
+
+impl LowBatteryMonitor {
+    pub fn configure(&self, config: LowBatteryConfig) -> Result<(), String> {
+        if !(1..=100).contains(&config.threshold) {
+            return Err("threshold must be between 1 and 100".to_string());
+        }
+        info!("Low battery action set to {:?} at {}%", config.action, config.threshold);
+        *self.config.lock_or_recover() = Some(config);
+        self.triggered.store(false, Ordering::SeqCst);
+        Ok(())
+    }
+
+    pub fn config(&self) -> Option<LowBatteryConfig> {
+        *self.config.lock_or_recover()
+    }
+
+    pub fn clear(&self) {
+        info!("Low battery action cleared");
+        *self.config.lock_or_recover() = None;
+    }
+
+    /// Compares `reading` to the threshold, returning the action to run if it was just reached.
+    pub fn check(&self, reading: BatteryReading) -> Option<LowBatteryConfig> {
+        let config = self.config()?;
+        if reading.charging || reading.percent > config.threshold {
+            self.triggered.store(false, Ordering::SeqCst);
+            return None;
+        }
+        if self.triggered.swap(true, Ordering::SeqCst) {
+            return None;
+        }
+        Some(config)
+    }
+}
+
+pub async fn run_action(app: &AppHandle, config: LowBatteryConfig, percent: u8) -> Result<(), String> {
+    warn!("Battery at {}% (threshold {}%): running {:?}", percent, config.threshold, config.action);
+    match config.action {
+        LowBatteryAction::Notify => {
+            app.emit("battery-action-threshold", BatteryThresholdReached { percent, threshold: config.threshold })
+                .map_err(|e| format!("Failed to emit battery-action-threshold: {}", e))
+        }
+        LowBatteryAction::Hibernate => write_power_state("disk").await,
+        LowBatteryAction::Suspend => write_power_state("mem").await,
+        LowBatteryAction::ShutDown => {
+            let status = tokio::process::Command::new("systemctl")
+                .arg("poweroff")
+                .status()
+                .await
+                .map_err(|e| format!("Failed to run systemctl poweroff: {}", e))?;
+            if status.success() {
+                Ok(())
+            } else {
+                Err(format!("systemctl poweroff exited with {}", status))
+            }
+        }
+    }
+}
+
+async fn write_power_state(state: &'static str) -> Result<(), String> {
+    let path = Path::new("/sys/power/state");
+    tokio::fs::write(path, state)
+        .await
+        .map_err(|e| format!("Failed to write '{}' to {}: {}", state, path.display(), e))
+}
@@ -0,0 +1,73 @@
+use crate::app_state::AppState;
+use crate::error_counters;
+use crate::key_codes;
+use crate::locking::MutexExt;
+use crate::logging;
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use tracing::warn;
+
+/// Occupancy of one bounded buffer or map, summed over its per-controller or per-device
+/// parts where it has them.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BufferStats {
+    pub name: String,
+    pub len: usize,
+    /// The cap on `len`, per part for buffers kept per controller, device or endpoint.
+    /// `None` for buffers bounded by a time window alone.
+    pub capacity: Option<usize>,
+    /// Controllers, devices or endpoints the buffer is kept for; 1 for a single buffer
+    pub parts: usize,
+    /// Heap held, counting allocated rather than used capacity
+    pub approx_bytes: usize,
+}
+
+impl BufferStats {
+    pub fn new(name: &str, len: usize, capacity: Option<usize>, parts: usize, approx_bytes: usize) -> Self {
+        Self { name: name.to_string(), len, capacity, parts, approx_bytes }
+    }
+
+    /// A single `VecDeque` whose entries own no heap of their own.
+    pub fn deque<T>(name: &str, deque: &VecDeque<T>, capacity: usize) -> Self {
+        Self::new(name, deque.len(), Some(capacity), 1, deque_bytes(deque))
+    }
+
+    /// Over its cap, which a soak test or `get_memory_stats` reader should treat as a leak.
+    pub fn over_capacity(&self) -> bool {
+        self.capacity.is_some_and(|capacity| self.len > capacity * self.parts.max(1))
+    }
+}
+
+/// Heap held by a deque's slots, ignoring anything the entries point to.
+pub fn deque_bytes<T>(deque: &VecDeque<T>) -> usize {
+    deque.capacity() * std::mem::size_of::<T>()
+}
+
+/// Answer to `get_memory_stats`: what the long-lived buffers hold, so slow growth over a
+/// long session shows up as a number rather than as the Deck running out of memory.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MemoryStats {
+    pub buffers: Vec<BufferStats>,
+    /// The shared key, axis and event type names
+    pub name_table_bytes: usize,
+    /// Sum of the above; approximate, since entries' own heap is only partly counted
+    pub total_approx_bytes: usize,
+}
+
+pub fn collect(state: &AppState) -> MemoryStats {
+    let mut buffers = vec![
+        state.event_bus.buffer_stats(),
+        logging::buffer_stats(),
+        state.batcher.lock_or_recover().buffer_stats(),
+    ];
+    buffers.extend(state.gamepad_manager.buffer_stats());
+    buffers.extend(state.evdev_manager.buffer_stats());
+    buffers.extend(state.light_server.buffer_stats());
+    buffers.push(error_counters::errors().buffer_stats());
+    for buffer in buffers.iter().filter(|buffer| buffer.over_capacity()) {
+        warn!("{} holds {} entries, over its cap of {} per part", buffer.name, buffer.len, buffer.capacity.unwrap_or_default());
+    }
+    let name_table_bytes = key_codes::name_table_bytes();
+    let total_approx_bytes = buffers.iter().map(|buffer| buffer.approx_bytes).sum::<usize>() + name_table_bytes;
+    MemoryStats { buffers, name_table_bytes, total_approx_bytes }
+}
@@ -0,0 +1,495 @@
+use crate::locking::MutexExt;
+#[cfg(target_os = "linux")]
+use midir::os::unix::VirtualOutput;
+use midir::{MidiOutput, MidiOutputConnection};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+use std::time::{SystemTime, UNIX_EPOCH};
+use tracing::{debug, info, warn};
+
+/// How the app shows up to other MIDI software, and its virtual port's name.
+pub const CLIENT_NAME: &str = "Steam Deck Controller";
+/// Output name that makes a virtual port instead of connecting to an existing one.
+pub const VIRTUAL_PORT: &str = "virtual";
+pub const MAX_RULES: usize = 128;
+
+const NOTE_OFF: u8 = 0x80;
+const NOTE_ON: u8 = 0x90;
+const CONTROL_CHANGE: u8 = 0xB0;
+
+fn now_ms() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_millis() as u64
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum MidiAction {
+    /// Note on while the button is held, note off when it's released
+    Note { channel: u8, note: u8, velocity: u8 },
+    /// The axis as a control change, -1..1 scaled to 0..127. A new value is sent only once
+    /// it's `threshold` steps (at least 1) from the last one sent
+    ControlChange {
+        channel: u8,
+        controller: u8,
+        #[serde(default)]
+        threshold: u8,
+    },
+}
+
+/// Turns one controller input into MIDI messages.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MidiRule {
+    pub id: String,
+    /// gilrs button name for notes, gilrs axis name for control changes
+    pub input: String,
+    pub action: MidiAction,
+    pub enabled: bool,
+}
+
+impl MidiRule {
+    pub fn validate(&self) -> Result<(), String> {
+        if self.id.trim().is_empty() {
+            return Err("MIDI rule id must not be empty".to_string());
+        }
+        if self.input.trim().is_empty() {
+            return Err(format!("MIDI rule '{}' needs an input", self.id));
+        }
+        let (channel, data) = match self.action {
+            MidiAction::Note { channel, note, velocity } => (channel, [("note", note), ("velocity", velocity)]),
+            MidiAction::ControlChange { channel, controller, threshold } => (channel, [("controller", controller), ("threshold", threshold)]),
+        };
+        if !(1..=16).contains(&channel) {
+            return Err(format!("MIDI rule '{}': channel must be 1 to 16, got {}", self.id, channel));
+        }
+        if let Some((name, value)) = data.into_iter().find(|(_, value)| *value > 127) {
+            return Err(format!("MIDI rule '{}': {} must be 0 to 127, got {}", self.id, name, value));
+        }
+        Ok(())
+    }
+}
+
+pub fn validate_rules(rules: &[MidiRule]) -> Result<(), String> {
+    if rules.len() > MAX_RULES {
+        return Err(format!("At most {} MIDI rules, got {}", MAX_RULES, rules.len()));
+    }
+    let mut ids = HashSet::new();
+    for rule in rules {
+        rule.validate()?;
+        if !ids.insert(rule.id.as_str()) {
+            return Err(format!("MIDI rule '{}' is defined twice", rule.id));
+        }
+    }
+    Ok(())
+}
+
+/// The MIDI output and rules, as saved in settings.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct MidiConfig {
+    /// Port name, or `virtual`, reopened at startup; `None` leaves MIDI closed
+    pub output: Option<String>,
+    pub rules: Vec<MidiRule>,
+}
+
+/// A normalized axis value as a 7-bit controller value.
+fn cc_value(value: f32) -> u8 {
+    (((value.clamp(-1.0, 1.0) + 1.0) / 2.0) * 127.0).round() as u8
+}
+
+/// Turns button and axis changes into MIDI messages. Holds no connection, so tests can
+/// drive it alone.
+#[derive(Default)]
+pub struct MidiMapper {
+    /// Enabled, valid rules
+    rules: Vec<MidiRule>,
+    /// Notes on, by (controller, rule id), with the note off that ends each
+    sounding: HashMap<(usize, String), [u8; 3]>,
+    /// Last control change value sent, by (controller, rule id)
+    last_cc: HashMap<(usize, String), u8>,
+    /// Control changes not sent for being under their rule's threshold
+    suppressed: u64,
+}
+
+impl MidiMapper {
+    /// Replaces the rules, ending every note that's on.
+    pub fn set_rules(&mut self, rules: &[MidiRule]) -> Vec<[u8; 3]> {
+        let offs = self.all_notes_off();
+        self.rules = rules.iter()
+            .filter(|rule| rule.enabled && rule.validate().is_ok())
+            .cloned()
+            .collect();
+        self.last_cc.clear();
+        offs
+    }
+
+    pub fn active_rules(&self) -> usize {
+        self.rules.len()
+    }
+
+    pub fn suppressed(&self) -> u64 {
+        self.suppressed
+    }
+
+    pub fn button_changed(&mut self, controller_id: usize, button: &str, pressed: bool) -> Vec<[u8; 3]> {
+        let mut messages = Vec::new();
+        for rule in &self.rules {
+            let MidiAction::Note { channel, note, velocity } = rule.action else {
+                continue;
+            };
+            if rule.input != button {
+                continue;
+            }
+            let holder = (controller_id, rule.id.clone());
+            let status = channel - 1;
+            if pressed && !self.sounding.contains_key(&holder) {
+                messages.push([NOTE_ON | status, note, velocity]);
+                self.sounding.insert(holder, [NOTE_OFF | status, note, 0]);
+            } else if !pressed {
+                messages.extend(self.sounding.remove(&holder));
+            }
+        }
+        messages
+    }
+
+    pub fn axis_changed(&mut self, controller_id: usize, axis: &str, value: f32) -> Vec<[u8; 3]> {
+        let mut messages = Vec::new();
+        for rule in &self.rules {
+            let MidiAction::ControlChange { channel, controller, threshold } = rule.action else {
+                continue;
+            };
+            if rule.input != axis {
+                continue;
+            }
+            let value = cc_value(value);
+            let last = self.last_cc.entry((controller_id, rule.id.clone())).or_insert(u8::MAX);
+            // The ends always go out, so a control can be brought all the way to 0 or 127
+            let at_end = (value == 0 || value == 127) && *last != value;
+            if *last != u8::MAX && !at_end && last.abs_diff(value) < threshold.max(1) {
+                self.suppressed += 1;
+                continue;
+            }
+            *last = value;
+            messages.push([CONTROL_CHANGE | (channel - 1), controller, value]);
+        }
+        messages
+    }
+
+    /// Ends the notes `controller_id` had on and forgets its control values.
+    pub fn controller_disconnected(&mut self, controller_id: usize) -> Vec<[u8; 3]> {
+        self.last_cc.retain(|(id, _), _| *id != controller_id);
+        let holders: Vec<(usize, String)> = self.sounding.keys().filter(|(id, _)| *id == controller_id).cloned().collect();
+        holders.into_iter().filter_map(|holder| self.sounding.remove(&holder)).collect()
+    }
+
+    pub fn all_notes_off(&mut self) -> Vec<[u8; 3]> {
+        self.sounding.drain().map(|(_, off)| off).collect()
+    }
+}
+
+/// The output port `name` means: an exact match, or else the one port whose name contains
+/// it, ignoring case.
+pub fn match_port<'a>(name: &str, ports: &'a [String]) -> Option<&'a String> {
+    if let Some(exact) = ports.iter().find(|port| *port == name) {
+        return Some(exact);
+    }
+    let needle = name.to_lowercase();
+    let mut partial = ports.iter().filter(|port| port.to_lowercase().contains(&needle));
+    match (partial.next(), partial.next()) {
+        (Some(port), None) => Some(port),
+        _ => None,
+    }
+}
+
+/// Names of the MIDI output ports other software offers.
+pub fn list_outputs() -> Result<Vec<String>, String> {
+    let output = MidiOutput::new(CLIENT_NAME).map_err(|e| format!("MIDI is unavailable: {}", e))?;
+    Ok(output.ports().iter().filter_map(|port| output.port_name(port).ok()).collect())
+}
+
+struct Connection {
+    port: String,
+    virtual_port: bool,
+    opened_at: u64,
+    connection: MidiOutputConnection,
+}
+
+/// Answer to the MIDI commands, and part of debug info.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct MidiStatus {
+    pub open: bool,
+    pub port: Option<String>,
+    pub virtual_port: bool,
+    /// Milliseconds since the epoch
+    pub opened_at: Option<u64>,
+    pub active_rules: usize,
+    pub sent_messages: u64,
+    /// Control changes held back by their threshold
+    pub suppressed_messages: u64,
+    pub send_errors: u64,
+    /// Why the last open or send failed
+    pub last_error: Option<String>,
+}
+
+#[derive(Default)]
+struct MidiState {
+    mapper: MidiMapper,
+    connection: Option<Connection>,
+    sent_messages: u64,
+    send_errors: u64,
+    last_error: Option<String>,
+}
+
+impl MidiState {
+    fn send(&mut self, messages: &[[u8; 3]]) {
+        let Some(connection) = self.connection.as_mut() else {
+            return;
+        };
+        for message in messages {
+            match connection.connection.send(message) {
+                Ok(()) => self.sent_messages += 1,
+                Err(e) => {
+                    self.send_errors += 1;
+                    if self.send_errors == 1 {
+                        warn!("Failed to send MIDI to {}: {}", connection.port, e);
+                    } else {
+                        debug!("Failed to send MIDI to {}: {}", connection.port, e);
+                    }
+                    self.last_error = Some(e.to_string());
+                }
+            }
+        }
+    }
+
+    fn close(&mut self) -> bool {
+        let offs = self.mapper.all_notes_off();
+        self.send(&offs);
+        match self.connection.take() {
+            Some(connection) => {
+                info!("Closed MIDI output {}", connection.port);
+                connection.connection.close();
+                true
+            }
+            None => false,
+        }
+    }
+}
+
+/// The app's MIDI output, if one is open, and the rules feeding it.
+#[derive(Default)]
+pub struct MidiOutputManager {
+    /// Checked before locking, so input costs nothing while no output is open
+    open: AtomicBool,
+    state: Mutex<MidiState>,
+}
+
+impl MidiOutputManager {
+    /// Connects to the port `name` matches (see `match_port`), or with `virtual` creates a
+    /// port of the app's own for other software to subscribe to. Replaces any output open
+    /// before.
+    pub fn open(&self, name: &str) -> Result<MidiStatus, String> {
+        let mut state = self.state.lock_or_recover();
+        state.close();
+        self.open.store(false, Ordering::Relaxed);
+        let connection = match connect(name) {
+            Ok(connection) => connection,
+            Err(e) => {
+                state.last_error = Some(e.clone());
+                return Err(e);
+            }
+        };
+        info!("Opened MIDI output {}{}", connection.port, if connection.virtual_port { " (virtual)" } else { "" });
+        state.connection = Some(connection);
+        state.sent_messages = 0;
+        state.send_errors = 0;
+        state.last_error = None;
+        self.open.store(true, Ordering::Relaxed);
+        Ok(status_of(&state))
+    }
+
+    /// Sends note off for every note on and closes the output. Returns whether one was open.
+    pub fn close(&self) -> bool {
+        self.open.store(false, Ordering::Relaxed);
+        self.state.lock_or_recover().close()
+    }
+
+    pub fn set_rules(&self, rules: &[MidiRule]) {
+        let mut state = self.state.lock_or_recover();
+        let offs = state.mapper.set_rules(rules);
+        state.send(&offs);
+    }
+
+    pub fn button_changed(&self, controller_id: usize, button: &str, pressed: bool) {
+        if !self.open.load(Ordering::Relaxed) {
+            return;
+        }
+        let mut state = self.state.lock_or_recover();
+        let messages = state.mapper.button_changed(controller_id, button, pressed);
+        state.send(&messages);
+    }
+
+    pub fn axis_changed(&self, controller_id: usize, axis: &str, value: f32) {
+        if !self.open.load(Ordering::Relaxed) {
+            return;
+        }
+        let mut state = self.state.lock_or_recover();
+        let messages = state.mapper.axis_changed(controller_id, axis, value);
+        state.send(&messages);
+    }
+
+    pub fn controller_disconnected(&self, controller_id: usize) {
+        if !self.open.load(Ordering::Relaxed) {
+            return;
+        }
+        let mut state = self.state.lock_or_recover();
+        let offs = state.mapper.controller_disconnected(controller_id);
+        state.send(&offs);
+    }
+
+    pub fn status(&self) -> MidiStatus {
+        status_of(&self.state.lock_or_recover())
+    }
+}
+
+fn connect(name: &str) -> Result<Connection, String> {
+    let output = MidiOutput::new(CLIENT_NAME).map_err(|e| format!("MIDI is unavailable: {}", e))?;
+    if name == VIRTUAL_PORT {
+        return connect_virtual(output);
+    }
+    let ports = output.ports();
+    let names: Vec<String> = ports.iter().map(|port| output.port_name(port).unwrap_or_default()).collect();
+    let Some(port_name) = match_port(name, &names).cloned() else {
+        return Err(format!("No MIDI output matches '{}'; found {}", name, names.join(", ")));
+    };
+    let index = names.iter().position(|known| *known == port_name).unwrap_or_default();
+    let connection = output.connect(&ports[index], CLIENT_NAME)
+        .map_err(|e| format!("Failed to connect to MIDI output {}: {}", port_name, e))?;
+    Ok(Connection { port: port_name, virtual_port: false, opened_at: now_ms(), connection })
+}
+
+/// An ALSA sequencer port of the app's own, so software on the same machine can
+/// subscribe without a cable.
+#[cfg(target_os = "linux")]
+fn connect_virtual(output: MidiOutput) -> Result<Connection, String> {
+    let connection = output.create_virtual(CLIENT_NAME)
+        .map_err(|e| format!("Failed to create a virtual MIDI port: {}", e))?;
+    Ok(Connection { port: CLIENT_NAME.to_string(), virtual_port: true, opened_at: now_ms(), connection })
+}
+
+#[cfg(not(target_os = "linux"))]
+fn connect_virtual(_output: MidiOutput) -> Result<Connection, String> {
+    Err("Virtual MIDI ports are only supported on Linux; open an existing port instead".to_string())
+}
+
+fn status_of(state: &MidiState) -> MidiStatus {
+    MidiStatus {
+        open: state.connection.is_some(),
+        port: state.connection.as_ref().map(|connection| connection.port.clone()),
+        virtual_port: state.connection.as_ref().is_some_and(|connection| connection.virtual_port),
+        opened_at: state.connection.as_ref().map(|connection| connection.opened_at),
+        active_rules: state.mapper.active_rules(),
+        sent_messages: state.sent_messages,
+        suppressed_messages: state.mapper.suppressed(),
+        send_errors: state.send_errors,
+        last_error: state.last_error.clone(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rule(id: &str, input: &str, action: MidiAction) -> MidiRule {
+        MidiRule { id: id.to_string(), input: input.to_string(), action, enabled: true }
+    }
+
+    fn mapper(rules: &[MidiRule]) -> MidiMapper {
+        let mut mapper = MidiMapper::default();
+        mapper.set_rules(rules);
+        mapper
+    }
+
+    fn cc(threshold: u8) -> MidiAction {
+        MidiAction::ControlChange { channel: 2, controller: 7, threshold }
+    }
+
+    #[test]
+    fn buttons_play_notes_while_held() {
+        let mut mapper = mapper(&[rule("go", "South", MidiAction::Note { channel: 1, note: 60, velocity: 100 })]);
+        assert_eq!(mapper.button_changed(0, "South", true), vec![[0x90, 60, 100]]);
+        assert_eq!(mapper.button_changed(0, "South", true), Vec::<[u8; 3]>::new());
+        assert_eq!(mapper.button_changed(0, "South", false), vec![[0x80, 60, 0]]);
+        assert_eq!(mapper.button_changed(0, "South", false), Vec::<[u8; 3]>::new());
+    }
+
+    #[test]
+    fn axes_scale_to_control_values() {
+        let mut mapper = mapper(&[rule("fader", "LeftStickY", cc(0))]);
+        assert_eq!(mapper.axis_changed(0, "LeftStickY", -1.0), vec![[0xB1, 7, 0]]);
+        assert_eq!(mapper.axis_changed(0, "LeftStickY", 0.0), vec![[0xB1, 7, 64]]);
+        assert_eq!(mapper.axis_changed(0, "LeftStickY", 1.0), vec![[0xB1, 7, 127]]);
+        assert_eq!(mapper.axis_changed(0, "LeftStickX", 1.0), Vec::<[u8; 3]>::new());
+    }
+
+    #[test]
+    fn small_changes_are_held_back_by_the_threshold() {
+        let mut mapper = mapper(&[rule("fader", "LeftStickY", cc(8))]);
+        assert_eq!(mapper.axis_changed(0, "LeftStickY", 0.0), vec![[0xB1, 7, 64]]);
+        assert!(mapper.axis_changed(0, "LeftStickY", 0.05).is_empty());
+        assert_eq!(mapper.axis_changed(0, "LeftStickY", 0.2), vec![[0xB1, 7, 76]]);
+        assert_eq!(mapper.axis_changed(0, "LeftStickY", 0.97), vec![[0xB1, 7, 125]]);
+        // Either end goes out however close the last value was
+        assert_eq!(mapper.axis_changed(0, "LeftStickY", 1.0), vec![[0xB1, 7, 127]]);
+        assert_eq!(mapper.suppressed(), 1);
+    }
+
+    #[test]
+    fn notes_end_when_their_controller_goes_or_the_rules_change() {
+        let note = rule("go", "South", MidiAction::Note { channel: 10, note: 36, velocity: 127 });
+        let mut mapper = mapper(std::slice::from_ref(&note));
+        mapper.button_changed(4, "South", true);
+        assert_eq!(mapper.controller_disconnected(4), vec![[0x89, 36, 0]]);
+
+        mapper.button_changed(4, "South", true);
+        assert_eq!(mapper.set_rules(&[]), vec![[0x89, 36, 0]]);
+        assert_eq!(mapper.active_rules(), 0);
+    }
+
+    #[test]
+    fn out_of_range_rules_are_refused() {
+        let valid = [
+            rule("go", "South", MidiAction::Note { channel: 16, note: 127, velocity: 0 }),
+            rule("fader", "RightStickX", cc(4)),
+        ];
+        assert!(validate_rules(&valid).is_ok());
+
+        let invalid = [
+            rule("low", "South", MidiAction::Note { channel: 0, note: 60, velocity: 100 }),
+            rule("high", "South", MidiAction::Note { channel: 17, note: 60, velocity: 100 }),
+            rule("note", "South", MidiAction::Note { channel: 1, note: 128, velocity: 100 }),
+            rule("cc", "LeftZ", MidiAction::ControlChange { channel: 1, controller: 200, threshold: 0 }),
+            rule("", "South", MidiAction::Note { channel: 1, note: 60, velocity: 100 }),
+        ];
+        for invalid in invalid {
+            assert!(validate_rules(std::slice::from_ref(&invalid)).is_err(), "{:?} should be refused", invalid);
+        }
+        assert!(validate_rules(&[valid[0].clone(), valid[0].clone()]).is_err());
+    }
+
+    #[test]
+    fn output_names_match_exactly_or_by_unique_part() {
+        let ports = vec![
+            "Midi Through:Midi Through Port-0 14:0".to_string(),
+            "QLC+:QLC+ Input 128:0".to_string(),
+            "QLC+:QLC+ Feedback 128:1".to_string(),
+        ];
+        assert_eq!(match_port("QLC+:QLC+ Input 128:0", &ports), Some(&ports[1]));
+        assert_eq!(match_port("through", &ports), Some(&ports[0]));
+        assert_eq!(match_port("qlc+", &ports), None, "ambiguous");
+        assert_eq!(match_port("Ableton", &ports), None);
+    }
+}
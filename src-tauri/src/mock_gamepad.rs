@@ -0,0 +1,243 @@
+use crate::gamepad::{ControllerEvent, ControllerState, GamepadInput};
+use crate::locking::MutexExt;
+use crate::state_revisions::{ControllerStatesDelta, StateRevisions, StateSnapshot};
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tracing::{info, warn};
+
+/// Interval between generated noise samples (~60 Hz, like a real stick).
+const NOISE_INTERVAL: Duration = Duration::from_millis(16);
+const NOISE_CONTROLLER_ID: usize = 0;
+const NOISE_AXES: [&str; 2] = ["LeftStickX", "LeftStickY"];
+
+#[derive(Debug, Clone, Default)]
+pub struct MockGamepadConfig {
+    /// NDJSON file of `ControllerEvent`s, played back using their timestamps for pacing
+    pub script_file: Option<String>,
+    /// Generate random stick movement on controller 0 after the script (if any) ends
+    pub random_noise: bool,
+    pub noise_amplitude: f32,
+}
+
+impl MockGamepadConfig {
+    /// Reads `MOCK_GAMEPAD_SCRIPT`, `MOCK_GAMEPAD_NOISE` and `MOCK_GAMEPAD_NOISE_AMPLITUDE`.
+    pub fn from_env() -> Self {
+        Self {
+            script_file: std::env::var("MOCK_GAMEPAD_SCRIPT").ok(),
+            random_noise: std::env::var("MOCK_GAMEPAD_NOISE").is_ok_and(|v| v != "0"),
+            noise_amplitude: std::env::var("MOCK_GAMEPAD_NOISE_AMPLITUDE")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(0.5),
+        }
+    }
+}
+
+/// Stand-in for gilrs in CI and on machines without controllers: produces
+/// `ControllerEvent`s from a script or random noise, tracks controller state the same
+/// way `GamepadManager` does, and hands each event to a caller-supplied sink.
+pub struct MockGamepadManager {
+    config: MockGamepadConfig,
+    states: Mutex<HashMap<usize, ControllerState>>,
+    revisions: Mutex<StateRevisions>,
+    stopped: AtomicBool,
+}
+
+impl MockGamepadManager {
+    pub fn new(config: MockGamepadConfig) -> Self {
+        info!("Initializing MockGamepadManager ({:?})", config);
+        Self {
+            config,
+            states: Mutex::new(HashMap::new()),
+            revisions: Mutex::new(StateRevisions::default()),
+            stopped: AtomicBool::new(false),
+        }
+    }
+
+    pub fn load_script(path: &str) -> Result<Vec<ControllerEvent>, String> {
+        let contents = std::fs::read_to_string(path)
+            .map_err(|e| format!("Failed to read mock script {}: {}", path, e))?;
+        contents
+            .lines()
+            .enumerate()
+            .filter(|(_, line)| !line.trim().is_empty())
+            .map(|(index, line)| {
+                serde_json::from_str(line)
+                    .map_err(|e| format!("Invalid event on line {} of {}: {}", index + 1, path, e))
+            })
+            .collect()
+    }
+
+    /// Plays the configured script, then generates noise until `stop` if enabled.
+    ///
+    /// Each event is applied to the mock's controller state before being passed to `emit`,
+    /// with its timestamp set to the time it was emitted.
+    pub async fn run<F: FnMut(ControllerEvent)>(&self, mut emit: F) -> Result<(), String> {
+        if let Some(path) = &self.config.script_file {
+            let script = Self::load_script(path)?;
+            self.play(&script, &mut emit).await;
+        }
+
+        if self.config.random_noise {
+            self.generate_noise(&mut emit).await;
+        }
+        Ok(())
+    }
+
+    /// Emits `events` in order, sleeping for the gaps between their timestamps.
+    pub async fn play<F: FnMut(ControllerEvent)>(&self, events: &[ControllerEvent], emit: &mut F) {
+        let mut previous = events.first().map(|event| event.timestamp);
+        for event in events {
+            if self.stopped.load(Ordering::Relaxed) {
+                return;
+            }
+            if let Some(previous) = previous {
+                let gap = event.timestamp.saturating_sub(previous);
+                if gap > 0 {
+                    tokio::time::sleep(Duration::from_millis(gap)).await;
+                }
+            }
+            previous = Some(event.timestamp);
+            self.dispatch(event.clone(), emit);
+        }
+    }
+
+    async fn generate_noise<F: FnMut(ControllerEvent)>(&self, emit: &mut F) {
+        if !self.states.lock_or_recover().contains_key(&NOISE_CONTROLLER_ID) {
+            self.dispatch(mock_event(NOISE_CONTROLLER_ID, "connected", None, None, None), emit);
+        }
+
+        let mut rng = XorShift::seeded();
+        while !self.stopped.load(Ordering::Relaxed) {
+            for axis in NOISE_AXES {
+                let value = (rng.next_f32() * 2.0 - 1.0) * self.config.noise_amplitude;
+                let event = mock_event(NOISE_CONTROLLER_ID, "axis-changed", None, Some(axis), Some(value));
+                self.dispatch(event, emit);
+            }
+            tokio::time::sleep(NOISE_INTERVAL).await;
+        }
+    }
+
+    fn dispatch<F: FnMut(ControllerEvent)>(&self, mut event: ControllerEvent, emit: &mut F) {
+        event.timestamp = now_ms();
+        event.synthetic = true;
+
+        match event.to_input() {
+            Some(input) => self.apply(event.controller_id, &input),
+            None => warn!("Mock event has unknown type '{}'", event.event_type),
+        }
+        emit(event);
+    }
+
+    fn apply(&self, controller_id: usize, input: &GamepadInput) {
+        let mut states = self.states.lock_or_recover();
+        let mut revisions = self.revisions.lock_or_recover();
+        match input {
+            GamepadInput::Connected => {
+                states.insert(controller_id, ControllerState {
+                    buttons: HashMap::new(),
+                    axes: HashMap::new(),
+                    connected: true,
+                    controller_id,
+                });
+                revisions.changed(controller_id);
+            }
+            GamepadInput::Disconnected => {
+                states.remove(&controller_id);
+                revisions.removed(controller_id);
+            }
+            GamepadInput::ButtonPressed { button } | GamepadInput::ButtonReleased { button } => {
+                let pressed = matches!(input, GamepadInput::ButtonPressed { .. });
+                if let Some(state) = states.get_mut(&controller_id) {
+                    if state.buttons.insert(button.clone(), pressed) != Some(pressed) {
+                        revisions.changed(controller_id);
+                    }
+                }
+            }
+            GamepadInput::AxisChanged { axis, value } => {
+                if let Some(state) = states.get_mut(&controller_id) {
+                    if state.axes.insert(axis.clone(), *value) != Some(*value) {
+                        revisions.changed(controller_id);
+                    }
+                }
+            }
+        }
+    }
+
+    /// Ends `run` after the event currently being emitted.
+    pub fn stop(&self) {
+        self.stopped.store(true, Ordering::Relaxed);
+    }
+
+    pub fn get_controller_states(&self) -> HashMap<usize, ControllerState> {
+        self.states.lock_or_recover().clone()
+    }
+
+    pub fn get_controller_state(&self, id: usize) -> Option<ControllerState> {
+        self.states.lock_or_recover().get(&id).cloned()
+    }
+
+    pub fn get_controller_states_delta(&self, since_revision: u64) -> ControllerStatesDelta {
+        let snapshot = StateSnapshot {
+            states: self.states.lock_or_recover().clone(),
+            revisions: self.revisions.lock_or_recover().clone(),
+        };
+        snapshot.delta(since_revision)
+    }
+
+    /// Panics while holding the controller state lock, poisoning it. Lets tests check
+    /// that a panic mid-update doesn't break every later caller.
+    #[doc(hidden)]
+    pub fn panic_while_locked(&self) {
+        let _states = self.states.lock_or_recover();
+        panic!("Injected panic while holding the mock state lock");
+    }
+}
+
+fn mock_event(
+    controller_id: usize,
+    event_type: &str,
+    button: Option<&str>,
+    axis: Option<&str>,
+    value: Option<f32>,
+) -> ControllerEvent {
+    ControllerEvent {
+        controller_id,
+        event_type: event_type.to_string(),
+        button: button.map(str::to_string),
+        axis: axis.map(str::to_string),
+        value,
+        timestamp: now_ms(),
+        synthetic: true,
+        source_time: None,
+    }
+}
+
+fn now_ms() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_millis() as u64
+}
+
+/// Small PRNG so noise doesn't pull in a dependency; quality is irrelevant here.
+struct XorShift(u64);
+
+impl XorShift {
+    fn seeded() -> Self {
+        let seed = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_nanos() as u64;
+        Self(seed | 1)
+    }
+
+    fn next_f32(&mut self) -> f32 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        (self.0 >> 40) as f32 / (1u64 << 24) as f32
+    }
+}
@@ -0,0 +1,635 @@
+use crate::app_state::AppState;
+use crate::emitter::EventEmitter;
+use crate::gamepad::ControllerState;
+use crate::locking::MutexExt;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::io::Write;
+use std::net::{TcpStream, ToSocketAddrs};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use tauri::{AppHandle, Manager};
+use tracing::{debug, info, warn};
+
+/// Port Fadecandy and most other OPC servers listen on.
+pub const DEFAULT_PORT: u16 = 7890;
+/// The most pixels one OPC message's 16-bit length can hold.
+pub const MAX_PIXELS: usize = u16::MAX as usize / 3;
+pub const MIN_FRAME_RATE: u32 = 30;
+pub const MAX_FRAME_RATE: u32 = 60;
+pub const DEFAULT_FRAME_RATE: u32 = 40;
+/// Emitted with an `OpcStatus` when the server connects or drops.
+pub const STATUS_EVENT: &str = "opc-status";
+
+const SET_PIXEL_COLORS: u8 = 0;
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(2);
+/// A server that stops reading is treated as gone, rather than holding up frames
+const WRITE_TIMEOUT: Duration = Duration::from_millis(500);
+const FIRST_RECONNECT_DELAY: Duration = Duration::from_millis(500);
+const MAX_RECONNECT_DELAY: Duration = Duration::from_secs(30);
+
+fn now_ms() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_millis() as u64
+}
+
+fn default_frame_rate() -> u32 {
+    DEFAULT_FRAME_RATE
+}
+
+fn default_fade_ms() -> u32 {
+    300
+}
+
+/// What paints the pixels each frame, from the first connected controller.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum OpcGenerator {
+    /// Every pixel one color: the stick's direction as the hue and how far it's pushed as
+    /// the brightness
+    Solid { x_axis: String, y_axis: String },
+    /// Lights pixels from the first in proportion to the axis, 0 to 1 as triggers report
+    /// it, green through yellow to red
+    Bar { axis: String },
+    /// Each button gets an equal run of pixels, lit in `color` while held and fading over
+    /// `fade_ms` once released. Presses from any controller count
+    ButtonFlashes {
+        buttons: Vec<String>,
+        color: [u8; 3],
+        #[serde(default = "default_fade_ms")]
+        fade_ms: u32,
+    },
+}
+
+impl OpcGenerator {
+    pub fn validate(&self, pixel_count: usize) -> Result<(), String> {
+        match self {
+            OpcGenerator::Solid { x_axis, y_axis } => {
+                if x_axis.trim().is_empty() || y_axis.trim().is_empty() {
+                    return Err("The solid generator needs an x and a y axis".to_string());
+                }
+            }
+            OpcGenerator::Bar { axis } => {
+                if axis.trim().is_empty() {
+                    return Err("The bar generator needs an axis".to_string());
+                }
+            }
+            OpcGenerator::ButtonFlashes { buttons, .. } => {
+                if buttons.is_empty() || buttons.iter().any(|button| button.trim().is_empty()) {
+                    return Err("The button flash generator needs at least one button".to_string());
+                }
+                if buttons.len() > pixel_count {
+                    return Err(format!("{} buttons can't share {} pixels", buttons.len(), pixel_count));
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// The OPC server, its pixels and what paints them, as saved in settings.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct OpcConfig {
+    pub host: String,
+    pub port: u16,
+    /// OPC channel, 0 for every channel the server has
+    pub channel: u8,
+    pub pixel_count: usize,
+    /// Frames a second the generator sends
+    #[serde(default = "default_frame_rate")]
+    pub frame_rate: u32,
+    /// `None` sends nothing by itself, leaving the pixels to `send_opc_frame`
+    #[serde(default)]
+    pub generator: Option<OpcGenerator>,
+}
+
+impl OpcConfig {
+    pub fn validate(&self) -> Result<(), String> {
+        if self.host.trim().is_empty() {
+            return Err("OPC host must not be empty".to_string());
+        }
+        if self.port == 0 {
+            return Err("OPC port must not be 0".to_string());
+        }
+        if !(1..=MAX_PIXELS).contains(&self.pixel_count) {
+            return Err(format!("Pixel count must be 1 to {}, got {}", MAX_PIXELS, self.pixel_count));
+        }
+        if !(MIN_FRAME_RATE..=MAX_FRAME_RATE).contains(&self.frame_rate) {
+            return Err(format!("Frame rate must be {} to {} per second, got {}", MIN_FRAME_RATE, MAX_FRAME_RATE, self.frame_rate));
+        }
+        if let Some(generator) = &self.generator {
+            generator.validate(self.pixel_count)?;
+        }
+        Ok(())
+    }
+}
+
+/// One "set pixel colors" message: channel, command, big-endian length, then RGB.
+pub fn encode_frame(channel: u8, pixels: &[[u8; 3]]) -> Result<Vec<u8>, String> {
+    if pixels.len() > MAX_PIXELS {
+        return Err(format!("At most {} pixels per frame, got {}", MAX_PIXELS, pixels.len()));
+    }
+    let length = (pixels.len() * 3) as u16;
+    let mut message = Vec::with_capacity(4 + pixels.len() * 3);
+    message.extend_from_slice(&[channel, SET_PIXEL_COLORS]);
+    message.extend_from_slice(&length.to_be_bytes());
+    message.extend(pixels.iter().flatten());
+    Ok(message)
+}
+
+/// `hue` in degrees, `saturation` and `value` 0 to 1.
+pub fn hsv_to_rgb(hue: f32, saturation: f32, value: f32) -> [u8; 3] {
+    let hue = hue.rem_euclid(360.0) / 60.0;
+    let chroma = value.clamp(0.0, 1.0) * saturation.clamp(0.0, 1.0);
+    let second = chroma * (1.0 - (hue % 2.0 - 1.0).abs());
+    let (r, g, b) = match hue as u32 {
+        0 => (chroma, second, 0.0),
+        1 => (second, chroma, 0.0),
+        2 => (0.0, chroma, second),
+        3 => (0.0, second, chroma),
+        4 => (second, 0.0, chroma),
+        _ => (chroma, 0.0, second),
+    };
+    let base = value.clamp(0.0, 1.0) - chroma;
+    let byte = |channel: f32| ((channel + base) * 255.0).round() as u8;
+    [byte(r), byte(g), byte(b)]
+}
+
+fn scale(color: [u8; 3], brightness: f32) -> [u8; 3] {
+    color.map(|channel| (channel as f32 * brightness.clamp(0.0, 1.0)).round() as u8)
+}
+
+/// Turns controller state into frames. Holds no connection, so tests can drive it alone.
+#[derive(Default)]
+pub struct OpcRenderer {
+    held: HashSet<(usize, String)>,
+    /// When each button was last let go of, in milliseconds since the epoch
+    released_at: HashMap<String, u64>,
+}
+
+impl OpcRenderer {
+    pub fn button_changed(&mut self, controller_id: usize, button: &str, pressed: bool, now_ms: u64) {
+        let holder = (controller_id, button.to_string());
+        if pressed {
+            self.held.insert(holder);
+        } else if self.held.remove(&holder) {
+            self.released_at.insert(button.to_string(), now_ms);
+        }
+    }
+
+    pub fn controller_disconnected(&mut self, controller_id: usize, now_ms: u64) {
+        let released: Vec<String> = self.held.iter()
+            .filter(|(id, _)| *id == controller_id)
+            .map(|(_, button)| button.clone())
+            .collect();
+        for button in released {
+            self.button_changed(controller_id, &button, false, now_ms);
+        }
+    }
+
+    pub fn render(&self, generator: &OpcGenerator, state: Option<&ControllerState>, pixel_count: usize, now_ms: u64) -> Vec<[u8; 3]> {
+        let axis = |name: &str| state.and_then(|state| state.axes.get(name).copied()).unwrap_or(0.0);
+        match generator {
+            OpcGenerator::Solid { x_axis, y_axis } => {
+                let (x, y) = (axis(x_axis), axis(y_axis));
+                let hue = y.atan2(x).to_degrees();
+                vec![hsv_to_rgb(hue, 1.0, x.hypot(y).min(1.0)); pixel_count]
+            }
+            OpcGenerator::Bar { axis: name } => {
+                let lit = (axis(name).clamp(0.0, 1.0) * pixel_count as f32).round() as usize;
+                let last = pixel_count.saturating_sub(1).max(1) as f32;
+                (0..pixel_count)
+                    .map(|i| if i < lit { hsv_to_rgb(120.0 * (1.0 - i as f32 / last), 1.0, 1.0) } else { [0, 0, 0] })
+                    .collect()
+            }
+            OpcGenerator::ButtonFlashes { buttons, color, fade_ms } => {
+                let run = pixel_count / buttons.len().max(1);
+                let mut pixels = vec![[0, 0, 0]; pixel_count];
+                for (index, button) in buttons.iter().enumerate() {
+                    let brightness = if self.held.iter().any(|(_, held)| held == button) {
+                        1.0
+                    } else {
+                        match self.released_at.get(button) {
+                            Some(&at) if *fade_ms > 0 => 1.0 - now_ms.saturating_sub(at) as f32 / *fade_ms as f32,
+                            _ => 0.0,
+                        }
+                    };
+                    pixels[index * run..(index + 1) * run].fill(scale(*color, brightness));
+                }
+                pixels
+            }
+        }
+    }
+}
+
+/// Answer to the OPC commands, emitted as `opc-status`, and part of debug info.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct OpcStatus {
+    pub configured: bool,
+    pub host: Option<String>,
+    pub port: Option<u16>,
+    pub channel: Option<u8>,
+    pub pixel_count: Option<usize>,
+    pub frame_rate: Option<u32>,
+    pub generator: Option<OpcGenerator>,
+    pub connected: bool,
+    /// Milliseconds since the epoch
+    pub connected_at: Option<u64>,
+    pub frames_sent: u64,
+    /// Connections made after the first
+    pub reconnects: u64,
+    /// Why the last connection or write failed
+    pub last_error: Option<String>,
+}
+
+struct Link {
+    config: Option<OpcConfig>,
+    stream: Option<TcpStream>,
+    connected_at: Option<u64>,
+    connections: u64,
+    frames_sent: u64,
+    last_error: Option<String>,
+    /// Failed connections in a row, for the backoff
+    failures: u32,
+    next_attempt: Instant,
+}
+
+impl Link {
+    fn write(&mut self, pixels: &[[u8; 3]]) -> Result<(), String> {
+        let channel = self.config.as_ref().map(|config| config.channel).ok_or("OPC output isn't configured")?;
+        let message = encode_frame(channel, pixels)?;
+        let stream = self.stream.as_mut().ok_or("The OPC server isn't connected")?;
+        match stream.write_all(&message) {
+            Ok(()) => {
+                self.frames_sent += 1;
+                Ok(())
+            }
+            Err(e) => {
+                let e = format!("Lost the OPC server: {}", e);
+                warn!("{}", e);
+                self.drop_connection(e.clone());
+                Err(e)
+            }
+        }
+    }
+
+    fn drop_connection(&mut self, reason: String) {
+        self.stream = None;
+        self.connected_at = None;
+        self.last_error = Some(reason);
+        self.next_attempt = Instant::now() + FIRST_RECONNECT_DELAY;
+    }
+}
+
+/// Sends frames to an Open Pixel Control server, such as Fadecandy's, reconnecting while
+/// it's away.
+pub struct OpcOutput {
+    /// Checked before locking, so input costs nothing while no output is configured
+    active: AtomicBool,
+    /// Bumped by each configure and stop, which ends the output thread started before
+    generation: AtomicU64,
+    renderer: Mutex<OpcRenderer>,
+    link: Mutex<Link>,
+}
+
+impl Default for OpcOutput {
+    fn default() -> Self {
+        Self {
+            active: AtomicBool::new(false),
+            generation: AtomicU64::new(0),
+            renderer: Mutex::new(OpcRenderer::default()),
+            link: Mutex::new(Link {
+                config: None,
+                stream: None,
+                connected_at: None,
+                connections: 0,
+                frames_sent: 0,
+                last_error: None,
+                failures: 0,
+                next_attempt: Instant::now(),
+            }),
+        }
+    }
+}
+
+impl OpcOutput {
+    /// Replaces the server, pixels and generator, and starts the output thread, which
+    /// connects in the background and emits `opc-status` through `app`.
+    pub fn configure(self: &Arc<Self>, app: &AppHandle, config: OpcConfig) -> Result<OpcStatus, String> {
+        config.validate()?;
+        let output = self.clone();
+        let app = app.clone();
+        let generation = self.generation.fetch_add(1, Ordering::SeqCst) + 1;
+        let status = {
+            let mut link = self.link.lock_or_recover();
+            info!("Sending OPC to {}:{} channel {}, {} pixels", config.host, config.port, config.channel, config.pixel_count);
+            link.config = Some(config);
+            link.stream = None;
+            link.connected_at = None;
+            link.connections = 0;
+            link.frames_sent = 0;
+            link.last_error = None;
+            link.failures = 0;
+            link.next_attempt = Instant::now();
+            status_of(&link)
+        };
+        self.active.store(true, Ordering::Relaxed);
+        std::thread::Builder::new()
+            .name("opc-output".to_string())
+            .spawn(move || run(&output, generation, app))
+            .map_err(|e| format!("Failed to start OPC output: {}", e))?;
+        Ok(status)
+    }
+
+    /// Changes what paints the pixels, keeping the connection.
+    pub fn set_generator(&self, generator: Option<OpcGenerator>) -> Result<OpcStatus, String> {
+        let mut link = self.link.lock_or_recover();
+        let config = link.config.as_mut().ok_or("Configure OPC output first")?;
+        if let Some(generator) = &generator {
+            generator.validate(config.pixel_count)?;
+        }
+        config.generator = generator;
+        Ok(status_of(&link))
+    }
+
+    /// Sends `pixels` now. They stay lit until the next frame, so while a generator runs
+    /// that's one frame later.
+    pub fn send_frame(&self, app: &AppHandle, pixels: &[[u8; 3]]) -> Result<(), String> {
+        let mut link = self.link.lock_or_recover();
+        let was_connected = link.stream.is_some();
+        let written = link.write(pixels);
+        if was_connected && link.stream.is_none() {
+            app.emit_event(STATUS_EVENT, status_of(&link)).ok();
+        }
+        written
+    }
+
+    /// Disconnects and ends the output thread. Returns whether output was configured.
+    pub fn stop(&self) -> bool {
+        self.active.store(false, Ordering::Relaxed);
+        self.generation.fetch_add(1, Ordering::SeqCst);
+        let mut link = self.link.lock_or_recover();
+        link.stream = None;
+        link.connected_at = None;
+        let configured = link.config.take().is_some();
+        if configured {
+            info!("Stopped OPC output");
+        }
+        configured
+    }
+
+    pub fn button_changed(&self, controller_id: usize, button: &str, pressed: bool) {
+        if !self.active.load(Ordering::Relaxed) {
+            return;
+        }
+        self.renderer.lock_or_recover().button_changed(controller_id, button, pressed, now_ms());
+    }
+
+    pub fn controller_disconnected(&self, controller_id: usize) {
+        if !self.active.load(Ordering::Relaxed) {
+            return;
+        }
+        self.renderer.lock_or_recover().controller_disconnected(controller_id, now_ms());
+    }
+
+    pub fn status(&self) -> OpcStatus {
+        status_of(&self.link.lock_or_recover())
+    }
+
+    fn current(&self, generation: u64) -> bool {
+        self.generation.load(Ordering::SeqCst) == generation
+    }
+
+    /// Connects if it's time to try again. Connecting happens without the lock, so
+    /// `send_frame` and `status` don't wait on a slow server.
+    fn reconnect(&self, generation: u64, app: &AppHandle) {
+        let address = {
+            let link = self.link.lock_or_recover();
+            match &link.config {
+                Some(config) if link.stream.is_none() && Instant::now() >= link.next_attempt => {
+                    format!("{}:{}", config.host, config.port)
+                }
+                _ => return,
+            }
+        };
+        let connected = connect(&address);
+        let mut link = self.link.lock_or_recover();
+        if !self.current(generation) {
+            return;
+        }
+        match connected {
+            Ok(stream) => {
+                info!("Connected to OPC server {}", address);
+                if link.connections > 0 {
+                    debug!("Reconnected to OPC server after {} failed attempt(s)", link.failures);
+                }
+                link.stream = Some(stream);
+                link.connected_at = Some(now_ms());
+                link.connections += 1;
+                link.failures = 0;
+                link.last_error = None;
+                app.emit_event(STATUS_EVENT, status_of(&link)).ok();
+            }
+            Err(e) => {
+                // 0.5s, then 1s, 2s… up to 30s while it stays away
+                let delay = FIRST_RECONNECT_DELAY.saturating_mul(1 << link.failures.min(6)).min(MAX_RECONNECT_DELAY);
+                if link.failures == 0 {
+                    warn!("{}; retrying in {:?}", e, delay);
+                } else {
+                    debug!("{}; retrying in {:?}", e, delay);
+                }
+                link.failures += 1;
+                link.last_error = Some(e);
+                link.next_attempt = Instant::now() + delay;
+            }
+        }
+    }
+
+    fn send_generated(&self, generation: u64, app: &AppHandle) {
+        let Some((generator, pixel_count)) = ({
+            let link = self.link.lock_or_recover();
+            link.config.as_ref()
+                .filter(|_| link.stream.is_some())
+                .and_then(|config| Some((config.generator.clone()?, config.pixel_count)))
+        }) else {
+            return;
+        };
+        let controller = app.try_state::<AppState>().and_then(|state| {
+            state.gamepad_manager.get_controller_states()
+                .into_values()
+                .filter(|state| state.connected)
+                .min_by_key(|state| state.controller_id)
+        });
+        let pixels = self.renderer.lock_or_recover().render(&generator, controller.as_ref(), pixel_count, now_ms());
+        let mut link = self.link.lock_or_recover();
+        if !self.current(generation) {
+            return;
+        }
+        if link.write(&pixels).is_err() {
+            app.emit_event(STATUS_EVENT, status_of(&link)).ok();
+        }
+    }
+}
+
+fn connect(address: &str) -> Result<TcpStream, String> {
+    let resolved = address.to_socket_addrs()
+        .map_err(|e| format!("Couldn't resolve OPC server {}: {}", address, e))?
+        .next()
+        .ok_or_else(|| format!("OPC server {} has no address", address))?;
+    let stream = TcpStream::connect_timeout(&resolved, CONNECT_TIMEOUT)
+        .map_err(|e| format!("Couldn't connect to OPC server {}: {}", address, e))?;
+    stream.set_nodelay(true).ok();
+    stream.set_write_timeout(Some(WRITE_TIMEOUT)).ok();
+    Ok(stream)
+}
+
+/// The output thread: reconnects and sends a generated frame at the configured rate,
+/// until the output is configured again or stopped.
+fn run(output: &OpcOutput, generation: u64, app: AppHandle) {
+    let mut next_frame = Instant::now();
+    while output.current(generation) {
+        let frame_rate = output.link.lock_or_recover().config.as_ref().map_or(MIN_FRAME_RATE, |config| config.frame_rate);
+        next_frame += Duration::from_secs(1) / frame_rate;
+        output.reconnect(generation, &app);
+        output.send_generated(generation, &app);
+        let now = Instant::now();
+        match next_frame.checked_duration_since(now) {
+            Some(wait) => std::thread::sleep(wait),
+            // Fell behind, such as while connecting; don't rush to catch up
+            None => next_frame = now,
+        }
+    }
+}
+
+fn status_of(link: &Link) -> OpcStatus {
+    let config = link.config.as_ref();
+    OpcStatus {
+        configured: config.is_some(),
+        host: config.map(|config| config.host.clone()),
+        port: config.map(|config| config.port),
+        channel: config.map(|config| config.channel),
+        pixel_count: config.map(|config| config.pixel_count),
+        frame_rate: config.map(|config| config.frame_rate),
+        generator: config.and_then(|config| config.generator.clone()),
+        connected: link.stream.is_some(),
+        connected_at: link.connected_at,
+        frames_sent: link.frames_sent,
+        reconnects: link.connections.saturating_sub(1),
+        last_error: link.last_error.clone(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    fn state(axes: &[(&str, f32)]) -> ControllerState {
+        ControllerState {
+            buttons: HashMap::new(),
+            axes: axes.iter().map(|(name, value)| (name.to_string(), *value)).collect(),
+            connected: true,
+            controller_id: 0,
+        }
+    }
+
+    fn config(pixel_count: usize, generator: Option<OpcGenerator>) -> OpcConfig {
+        OpcConfig { host: "localhost".to_string(), port: 7890, channel: 0, pixel_count, frame_rate: 40, generator }
+    }
+
+    fn flashes(buttons: &[&str]) -> OpcGenerator {
+        OpcGenerator::ButtonFlashes {
+            buttons: buttons.iter().map(|button| button.to_string()).collect(),
+            color: [200, 100, 0],
+            fade_ms: 100,
+        }
+    }
+
+    #[test]
+    fn frames_have_the_opc_header() {
+        assert_eq!(encode_frame(3, &[[1, 2, 3], [4, 5, 6]]).unwrap(), vec![3, 0, 0, 6, 1, 2, 3, 4, 5, 6]);
+        assert_eq!(encode_frame(0, &[]).unwrap(), vec![0, 0, 0, 0]);
+        let longest = encode_frame(1, &vec![[0, 0, 0]; MAX_PIXELS]).unwrap();
+        assert_eq!(&longest[..4], &[1, 0, 0xFF, 0xFF]);
+        assert!(encode_frame(1, &vec![[0, 0, 0]; MAX_PIXELS + 1]).is_err());
+    }
+
+    #[test]
+    fn hues_go_round_the_color_wheel() {
+        assert_eq!(hsv_to_rgb(0.0, 1.0, 1.0), [255, 0, 0]);
+        assert_eq!(hsv_to_rgb(120.0, 1.0, 1.0), [0, 255, 0]);
+        assert_eq!(hsv_to_rgb(240.0, 1.0, 1.0), [0, 0, 255]);
+        assert_eq!(hsv_to_rgb(-120.0, 1.0, 1.0), [0, 0, 255]);
+        assert_eq!(hsv_to_rgb(60.0, 1.0, 0.0), [0, 0, 0]);
+        assert_eq!(hsv_to_rgb(60.0, 0.0, 1.0), [255, 255, 255]);
+    }
+
+    #[test]
+    fn the_stick_picks_one_color_for_every_pixel() {
+        let renderer = OpcRenderer::default();
+        let generator = OpcGenerator::Solid { x_axis: "LeftStickX".to_string(), y_axis: "LeftStickY".to_string() };
+        let right = state(&[("LeftStickX", 1.0), ("LeftStickY", 0.0)]);
+        assert_eq!(renderer.render(&generator, Some(&right), 3, 0), vec![[255, 0, 0]; 3]);
+        let up_halfway = state(&[("LeftStickX", 0.0), ("LeftStickY", 0.5)]);
+        assert_eq!(renderer.render(&generator, Some(&up_halfway), 2, 0), vec![[64, 128, 0]; 2]);
+        assert_eq!(renderer.render(&generator, None, 2, 0), vec![[0, 0, 0]; 2]);
+    }
+
+    #[test]
+    fn the_bar_grows_with_the_trigger() {
+        let renderer = OpcRenderer::default();
+        let generator = OpcGenerator::Bar { axis: "RightZ".to_string() };
+        let pixels = renderer.render(&generator, Some(&state(&[("RightZ", 0.5)])), 4, 0);
+        assert_eq!(pixels, vec![[0, 255, 0], [170, 255, 0], [0, 0, 0], [0, 0, 0]]);
+        let full = renderer.render(&generator, Some(&state(&[("RightZ", 1.0)])), 4, 0);
+        assert_eq!(full[3], [255, 0, 0]);
+        let released = renderer.render(&generator, Some(&state(&[("RightZ", -1.0)])), 4, 0);
+        assert_eq!(released, vec![[0, 0, 0]; 4]);
+    }
+
+    #[test]
+    fn buttons_light_their_pixels_and_fade_once_released() {
+        let mut renderer = OpcRenderer::default();
+        let generator = flashes(&["South", "East"]);
+        renderer.button_changed(0, "East", true, 1_000);
+        assert_eq!(renderer.render(&generator, None, 5, 1_050), vec![[0, 0, 0], [0, 0, 0], [200, 100, 0], [200, 100, 0], [0, 0, 0]]);
+        renderer.button_changed(0, "East", false, 1_100);
+        assert_eq!(renderer.render(&generator, None, 5, 1_150)[2], [100, 50, 0]);
+        assert_eq!(renderer.render(&generator, None, 5, 1_300)[2], [0, 0, 0]);
+    }
+
+    #[test]
+    fn a_disconnect_lets_go_of_held_buttons() {
+        let mut renderer = OpcRenderer::default();
+        let generator = flashes(&["South"]);
+        renderer.button_changed(1, "South", true, 0);
+        renderer.button_changed(0, "South", true, 0);
+        renderer.controller_disconnected(1, 10);
+        assert_eq!(renderer.render(&generator, None, 1, 500), vec![[200, 100, 0]]);
+        renderer.controller_disconnected(0, 500);
+        assert_eq!(renderer.render(&generator, None, 1, 700), vec![[0, 0, 0]]);
+    }
+
+    #[test]
+    fn configs_are_checked() {
+        assert!(config(60, None).validate().is_ok());
+        assert!(config(0, None).validate().is_err());
+        assert!(config(MAX_PIXELS + 1, None).validate().is_err());
+        assert!(OpcConfig { frame_rate: 120, ..config(60, None) }.validate().is_err());
+        assert!(OpcConfig { host: " ".to_string(), ..config(60, None) }.validate().is_err());
+        assert!(config(1, Some(flashes(&["South", "East"]))).validate().is_err());
+        assert!(config(2, Some(OpcGenerator::Bar { axis: String::new() })).validate().is_err());
+    }
+
+    #[test]
+    fn generators_read_from_json() {
+        let generator: OpcGenerator = serde_json::from_str(r#"{"type": "button_flashes", "buttons": ["South"], "color": [255, 0, 0]}"#).unwrap();
+        assert_eq!(generator, OpcGenerator::ButtonFlashes { buttons: vec!["South".to_string()], color: [255, 0, 0], fade_ms: 300 });
+        let config: OpcConfig = serde_json::from_str(r#"{"host": "fc.local", "port": 7890, "channel": 1, "pixel_count": 64}"#).unwrap();
+        assert_eq!(config.frame_rate, 40);
+        assert_eq!(config.generator, None);
+    }
+}
@@ -0,0 +1,98 @@
+use crate::locking::MutexExt;
+use serde::{Deserialize, Serialize};
+use std::backtrace::Backtrace;
+use std::panic::PanicHookInfo;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, OnceLock};
+use std::time::{SystemTime, UNIX_EPOCH};
+use tauri::{AppHandle, Emitter};
+use tracing::error;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PanicReport {
+    pub message: String,
+    pub location: Option<String>,
+    pub thread: Option<String>,
+    pub backtrace: String,
+    pub timestamp: u64,
+}
+
+impl PanicReport {
+    fn from_hook_info(info: &PanicHookInfo<'_>) -> Self {
+        let payload = info.payload();
+        let message = payload.downcast_ref::<&str>()
+            .map(|s| s.to_string())
+            .or_else(|| payload.downcast_ref::<String>().cloned())
+            .unwrap_or_else(|| "Box<dyn Any>".to_string());
+
+        Self {
+            message,
+            location: info.location().map(|location| location.to_string()),
+            thread: std::thread::current().name().map(str::to_string),
+            backtrace: Backtrace::force_capture().to_string(),
+            timestamp: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .unwrap_or_default()
+                .as_millis() as u64,
+        }
+    }
+}
+
+/// Keeps the most recent backend panic and how many poll passes have panicked, and
+/// forwards panics to the frontend once the app handle is known.
+#[derive(Default)]
+pub struct PanicMonitor {
+    last_panic: Mutex<Option<PanicReport>>,
+    poll_panics: AtomicU64,
+    app: OnceLock<AppHandle>,
+}
+
+impl PanicMonitor {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn attach_app(&self, app: AppHandle) {
+        let _ = self.app.set(app);
+    }
+
+    pub fn last_panic(&self) -> Option<PanicReport> {
+        self.last_panic.lock_or_recover().clone()
+    }
+
+    pub fn clear_last_panic(&self) {
+        *self.last_panic.lock_or_recover() = None;
+    }
+
+    pub fn record_poll_panic(&self) {
+        self.poll_panics.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn poll_panics(&self) -> u64 {
+        self.poll_panics.load(Ordering::Relaxed)
+    }
+
+    fn report(&self, report: PanicReport) {
+        error!(
+            "Panic in thread '{}' at {}: {}\n{}",
+            report.thread.as_deref().unwrap_or("<unnamed>"),
+            report.location.as_deref().unwrap_or("<unknown>"),
+            report.message,
+            report.backtrace
+        );
+        *self.last_panic.lock_or_recover() = Some(report.clone());
+        if let Some(app) = self.app.get() {
+            app.emit("backend-panic", report).ok();
+        }
+    }
+}
+
+/// Installs a panic hook that logs, stores and emits every panic, then runs the
+/// previous hook (which prints to stderr).
+pub fn install(monitor: Arc<PanicMonitor>) {
+    let previous = std::panic::take_hook();
+    std::panic::set_hook(Box::new(move |info| {
+        monitor.report(PanicReport::from_hook_info(info));
+        previous(info);
+    }));
+}
@@ -0,0 +1,78 @@
+use crate::controller_event::UnifiedControllerEvent;
+use handlebars::Handlebars;
+use serde::{Deserialize, Serialize};
+use tera::Tera;
+
+const TEMPLATE_NAME: &str = "payload";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TemplateFormat {
+    Handlebars,
+    Tera,
+    /// Sent verbatim, without substituting anything
+    Raw,
+}
+
+/// How each event is turned into what the light server receives, in place of the raw
+/// `UnifiedControllerEvent`. Templates see its fields, e.g. `source`, `device_id`,
+/// `input.kind`, `input.name`, `input.value` and `emit_timestamp_ms`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PayloadTemplate {
+    pub template: String,
+    pub format: TemplateFormat,
+}
+
+enum Engine {
+    Handlebars(Box<Handlebars<'static>>),
+    Tera(Box<Tera>),
+    Raw(String),
+}
+
+/// A `PayloadTemplate` parsed once, when it's configured, and rendered per event.
+pub struct CompiledTemplate {
+    engine: Engine,
+}
+
+impl CompiledTemplate {
+    pub fn compile(template: &PayloadTemplate) -> Result<Self, String> {
+        let engine = match template.format {
+            TemplateFormat::Handlebars => {
+                let mut handlebars = Handlebars::new();
+                // Payloads are JSON, not HTML
+                handlebars.register_escape_fn(handlebars::no_escape);
+                handlebars.register_template_string(TEMPLATE_NAME, &template.template)
+                    .map_err(|e| format!("Invalid Handlebars template: {}", e))?;
+                Engine::Handlebars(Box::new(handlebars))
+            }
+            TemplateFormat::Tera => {
+                let mut tera = Tera::default();
+                tera.add_raw_template(TEMPLATE_NAME, &template.template)
+                    .map_err(|e| format!("Invalid Tera template: {}", e))?;
+                Engine::Tera(Box::new(tera))
+            }
+            TemplateFormat::Raw => Engine::Raw(template.template.clone()),
+        };
+        Ok(Self { engine })
+    }
+
+    pub fn render(&self, event: &UnifiedControllerEvent) -> Result<String, String> {
+        match &self.engine {
+            Engine::Handlebars(handlebars) => handlebars.render(TEMPLATE_NAME, event)
+                .map_err(|e| format!("Failed to render payload template: {}", e)),
+            Engine::Tera(tera) => {
+                let context = tera::Context::from_serialize(event)
+                    .map_err(|e| format!("Failed to build template context: {}", e))?;
+                tera.render(TEMPLATE_NAME, &context)
+                    .map_err(|e| format!("Failed to render payload template: {}", e))
+            }
+            Engine::Raw(template) => Ok(template.clone()),
+        }
+    }
+
+    /// Renders `event` as a JSON value, keeping output that isn't valid JSON as a string.
+    pub fn render_value(&self, event: &UnifiedControllerEvent) -> Result<serde_json::Value, String> {
+        let rendered = self.render(event)?;
+        Ok(serde_json::from_str(&rendered).unwrap_or(serde_json::Value::String(rendered)))
+    }
+}
@@ -0,0 +1,129 @@
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use tracing::{info, warn};
+
+const CPU_ROOT: &str = "/sys/devices/system/cpu";
+const DRM_ROOT: &str = "/sys/class/drm";
+const GPU_LEVEL_ATTR: &str = "power_dpm_force_performance_level";
+const GPU_PROFILE_ATTR: &str = "pp_power_profile_mode";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum CpuGovernor {
+    Powersave,
+    Schedutil,
+    Performance,
+}
+
+impl CpuGovernor {
+    fn sysfs_name(self) -> &'static str {
+        match self {
+            CpuGovernor::Powersave => "powersave",
+            CpuGovernor::Schedutil => "schedutil",
+            CpuGovernor::Performance => "performance",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum GpuPerfLevel {
+    /// The driver picks clocks by load
+    Auto,
+    Low,
+    High,
+    /// Power profile index from `pp_power_profile_mode`
+    Manual(u32),
+}
+
+/// Sets the governor on every CPU. Writing needs root (or a udev rule granting access).
+pub fn set_cpu_governor(governor: CpuGovernor) -> Result<(), String> {
+    let paths = governor_paths();
+    if paths.is_empty() {
+        return Err("No cpufreq governors found; CPU frequency scaling is unavailable".to_string());
+    }
+    for path in &paths {
+        write_sysfs(path, governor.sysfs_name())?;
+    }
+    info!("CPU governor set to {} on {} CPUs", governor.sysfs_name(), paths.len());
+    Ok(())
+}
+
+/// The first CPU's governor, or `mixed` when CPUs disagree.
+pub fn cpu_governor() -> Result<String, String> {
+    let governors = governor_paths().iter()
+        .map(|path| read_sysfs(path))
+        .collect::<Result<Vec<_>, _>>()?;
+    match governors.first() {
+        None => Err("No cpufreq governors found; CPU frequency scaling is unavailable".to_string()),
+        Some(first) if governors.iter().all(|governor| governor == first) => Ok(first.clone()),
+        Some(_) => Ok("mixed".to_string()),
+    }
+}
+
+pub fn set_gpu_performance_level(level: GpuPerfLevel) -> Result<(), String> {
+    let dir = gpu_device_dir().ok_or("No AMD GPU performance controls found")?;
+    match level {
+        GpuPerfLevel::Auto => write_sysfs(&dir.join(GPU_LEVEL_ATTR), "auto")?,
+        GpuPerfLevel::Low => write_sysfs(&dir.join(GPU_LEVEL_ATTR), "low")?,
+        GpuPerfLevel::High => write_sysfs(&dir.join(GPU_LEVEL_ATTR), "high")?,
+        GpuPerfLevel::Manual(profile) => {
+            // Power profiles only apply while the level is manual
+            write_sysfs(&dir.join(GPU_LEVEL_ATTR), "manual")?;
+            write_sysfs(&dir.join(GPU_PROFILE_ATTR), &profile.to_string())?;
+        }
+    }
+    info!("GPU performance level set to {:?}", level);
+    Ok(())
+}
+
+pub fn gpu_performance_level() -> Result<String, String> {
+    let dir = gpu_device_dir().ok_or("No AMD GPU performance controls found")?;
+    read_sysfs(&dir.join(GPU_LEVEL_ATTR))
+}
+
+fn governor_paths() -> Vec<PathBuf> {
+    let Ok(entries) = std::fs::read_dir(CPU_ROOT) else {
+        return Vec::new();
+    };
+    let mut paths: Vec<PathBuf> = entries
+        .flatten()
+        .filter(|entry| {
+            entry.file_name().to_str()
+                .and_then(|name| name.strip_prefix("cpu"))
+                .is_some_and(|index| !index.is_empty() && index.bytes().all(|b| b.is_ascii_digit()))
+        })
+        .map(|entry| entry.path().join("cpufreq/scaling_governor"))
+        .filter(|path| path.exists())
+        .collect();
+    paths.sort();
+    paths
+}
+
+/// The `device` dir of the first DRM card exposing the amdgpu performance level.
+fn gpu_device_dir() -> Option<PathBuf> {
+    let mut cards: Vec<PathBuf> = std::fs::read_dir(DRM_ROOT).ok()?
+        .flatten()
+        .map(|entry| entry.path())
+        .filter(|path| {
+            path.file_name().and_then(|name| name.to_str())
+                .and_then(|name| name.strip_prefix("card"))
+                .is_some_and(|index| index.bytes().all(|b| b.is_ascii_digit()))
+        })
+        .collect();
+    cards.sort();
+    cards.into_iter()
+        .map(|card| card.join("device"))
+        .find(|dir| dir.join(GPU_LEVEL_ATTR).exists())
+}
+
+fn read_sysfs(path: &Path) -> Result<String, String> {
+    std::fs::read_to_string(path)
+        .map(|value| value.trim().to_string())
+        .map_err(|e| format!("Failed to read {}: {}", path.display(), e))
+}
+
+fn write_sysfs(path: &Path, value: &str) -> Result<(), String> {
+    std::fs::write(path, value).map_err(|e| {
+        warn!("Failed to write {} to {}: {}", value, path.display(), e);
+        format!("Failed to write {}: {}", path.display(), e)
+    })
+}
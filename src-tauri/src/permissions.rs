@@ -0,0 +1,602 @@
+use crate::confinement;
+#[cfg(not(target_os = "linux"))]
+use crate::evdev_types::EVDEV_UNSUPPORTED;
+use crate::evdev_types::EvdevGamepadInfo;
+use crate::hidraw::{self, HidrawNode};
+use crate::uinput_output::UINPUT_PATH;
+#[cfg(target_os = "linux")]
+use evdev::Device;
+#[cfg(target_os = "linux")]
+use nix::unistd::{getgrouplist, getuid, User};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+#[cfg(target_os = "linux")]
+use std::ffi::CString;
+#[cfg(target_os = "linux")]
+use std::os::unix::fs::MetadataExt;
+
+const INPUT_GROUP: &str = "input";
+const UDEV_RULE: &str = r#"SUBSYSTEM=="input", KERNEL=="event*", MODE="0660", GROUP="input""#;
+const HIDRAW_UDEV_RULE: &str = r#"KERNEL=="hidraw*", ATTRS{idVendor}=="28de", MODE="0660", TAG+="uaccess""#;
+const UINPUT_UDEV_RULE: &str = r#"KERNEL=="uinput", SUBSYSTEM=="misc", MODE="0660", GROUP="input", OPTIONS+="static_node=uinput""#;
+const RELOGIN_MESSAGE: &str = "You were added to the input group, but this session predates it; log out and back in";
+const ADD_TO_INPUT_GROUP_MESSAGE: &str = "Add your user to the input group: `sudo usermod -aG input $USER`, then log out and back in";
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GroupInfo {
+    pub gid: u32,
+    pub name: Option<String>,
+}
+
+/// Who owns a gamepad event node and whether this process can actually open it.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DeviceAccess {
+    pub device_path: String,
+    pub name: String,
+    pub owner_uid: Option<u32>,
+    pub group: Option<GroupInfo>,
+    /// Permission bits in octal, e.g. "660"
+    pub mode: Option<String>,
+    pub readable_by_us: bool,
+    pub open_error: Option<String>,
+    /// Whether another process holds an exclusive grab. `None` when not probed.
+    pub grabbed: Option<bool>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RemediationKind {
+    AddToInputGroup,
+    Relogin,
+    InstallUdevRule,
+    ReleaseGrab,
+    ConnectController,
+    InstallHidrawUdevRule,
+    /// Grant the sandbox the devices, or leave it
+    GrantSandboxAccess,
+    InstallUinputUdevRule,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Remediation {
+    pub kind: RemediationKind,
+    pub message: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PermissionsAnalysis {
+    pub uid: Option<u32>,
+    pub gid: Option<u32>,
+    pub groups: Vec<GroupInfo>,
+    pub in_input_group: bool,
+    pub devices: Vec<DeviceAccess>,
+    /// Controller hidraw nodes, opened read-write as lizard mode needs. Nodes owned by
+    /// hid-steam are left out: opening one takes the controller away from its evdev node.
+    pub hidraw_devices: Vec<DeviceAccess>,
+    /// `/dev/uinput`, opened for writing as the virtual gamepad needs
+    pub uinput: DeviceAccess,
+    pub remediations: Vec<Remediation>,
+    /// Set when no gamepad is readable because of the sandbox, not file modes
+    pub sandbox: Option<String>,
+}
+
+impl PermissionsAnalysis {
+    /// Checks every gamepad-classified node in `devices` by opening it.
+    ///
+    /// With `probe_grabs`, each opened device is also grabbed and released immediately
+    /// to find out whether another process (usually Steam) holds it exclusively.
+    pub fn collect(devices: &[EvdevGamepadInfo], probe_grabs: bool) -> Self {
+        let group_names = read_group_names();
+        let status = std::fs::read_to_string("/proc/self/status").unwrap_or_default();
+        // Uid/Gid lines list real, effective, saved and filesystem IDs; access uses effective
+        let uid = status_ids(&status, "Uid:").and_then(|ids| ids.get(1).copied());
+        let gid = status_ids(&status, "Gid:").and_then(|ids| ids.get(1).copied());
+        let groups: Vec<GroupInfo> = status_ids(&status, "Groups:")
+            .unwrap_or_default()
+            .into_iter()
+            .map(|gid| GroupInfo { gid, name: group_names.get(&gid).cloned() })
+            .collect();
+        let in_input_group = groups.iter().any(|group| group.name.as_deref() == Some(INPUT_GROUP));
+
+        let devices: Vec<DeviceAccess> = devices.iter()
+            .filter(|device| device.is_gamepad)
+            .map(|device| check_device(device, &group_names, probe_grabs))
+            .collect();
+        let hidraw_devices: Vec<DeviceAccess> = hidraw::find_controller_nodes()
+            .iter()
+            .filter(|node| !node.managed_by_kernel())
+            .map(|node| check_hidraw_node(node, &group_names))
+            .collect();
+        let uinput = check_uinput(&group_names);
+        let confinement = confinement::current();
+        let readable = devices.iter().filter(|device| device.readable_by_us).count();
+        let sandbox = confinement::blocks_input(confinement, readable).then(|| {
+            format!("Running in {}: the sandbox keeps the input devices out, not their file modes", confinement.summary())
+        });
+        // Group and udev advice would send users after the wrong problem
+        let mut remediations = match (&sandbox, confinement.remediation()) {
+            (Some(_), Some(message)) => vec![Remediation { kind: RemediationKind::GrantSandboxAccess, message }],
+            _ => suggest_remediations(&devices, in_input_group),
+        };
+        if hidraw_devices.iter().any(|device| !device.readable_by_us) {
+            remediations.push(Remediation {
+                kind: RemediationKind::InstallHidrawUdevRule,
+                message: format!(
+                    "Turning lizard mode off and trackpad haptics need write access to the controller's hidraw node; install a udev rule such as `{}` in /etc/udev/rules.d/ and reload udev",
+                    HIDRAW_UDEV_RULE
+                ),
+            });
+        }
+        if !uinput.readable_by_us && cfg!(target_os = "linux") {
+            remediations.push(Remediation {
+                kind: RemediationKind::InstallUinputUdevRule,
+                message: uinput_remediation(),
+            });
+        }
+
+        Self {
+            uid,
+            gid,
+            groups,
+            in_input_group,
+            devices,
+            hidraw_devices,
+            uinput,
+            remediations,
+            sandbox,
+        }
+    }
+
+    /// Plain-text rendering, the format `DebugInfo.permissions_check` has carried.
+    pub fn summary(&self) -> String {
+        let mut lines = Vec::new();
+        let groups: Vec<String> = self.groups.iter()
+            .map(|group| group.name.clone().unwrap_or_else(|| group.gid.to_string()))
+            .collect();
+        lines.push(format!(
+            "uid={} gid={} groups: {}",
+            self.uid.map_or("?".to_string(), |uid| uid.to_string()),
+            self.gid.map_or("?".to_string(), |gid| gid.to_string()),
+            groups.join(", ")
+        ));
+        lines.push(format!(
+            "{} {} the input group",
+            if self.in_input_group { "✅" } else { "❌" },
+            if self.in_input_group { "In" } else { "Not in" }
+        ));
+
+        if let Some(sandbox) = &self.sandbox {
+            lines.push(format!("❌ {}", sandbox));
+        }
+        if self.devices.is_empty() {
+            lines.push("❌ No gamepad event devices found".to_string());
+        }
+        for device in &self.devices {
+            let group = device.group.as_ref()
+                .map(|group| group.name.clone().unwrap_or_else(|| group.gid.to_string()))
+                .unwrap_or_else(|| "?".to_string());
+            lines.push(format!(
+                "{} {} ({}) group: {}, mode: {}{}",
+                if device.readable_by_us { "✅" } else { "❌" },
+                device.device_path,
+                device.name,
+                group,
+                device.mode.as_deref().unwrap_or("?"),
+                device.open_error.as_ref().map(|e| format!(", open failed: {}", e)).unwrap_or_default()
+            ));
+        }
+
+        for device in &self.hidraw_devices {
+            lines.push(format!(
+                "{} {} ({}) hidraw, mode: {}{}",
+                if device.readable_by_us { "✅" } else { "❌" },
+                device.device_path,
+                device.name,
+                device.mode.as_deref().unwrap_or("?"),
+                device.open_error.as_ref().map(|e| format!(", open failed: {}", e)).unwrap_or_default()
+            ));
+        }
+
+        lines.push(format!(
+            "{} {} (virtual gamepad), mode: {}{}",
+            if self.uinput.readable_by_us { "✅" } else { "❌" },
+            self.uinput.device_path,
+            self.uinput.mode.as_deref().unwrap_or("?"),
+            self.uinput.open_error.as_ref().map(|e| format!(", open failed: {}", e)).unwrap_or_default()
+        ));
+
+        for remediation in &self.remediations {
+            lines.push(format!("👉 {}", remediation.message));
+        }
+        lines.join("\n")
+    }
+}
+
+#[cfg(target_os = "linux")]
+fn check_device(device: &EvdevGamepadInfo, group_names: &HashMap<u32, String>, probe_grabs: bool) -> DeviceAccess {
+    let metadata = std::fs::metadata(&device.device_path).ok();
+    let (readable_by_us, open_error, grabbed) = match Device::open(&device.device_path) {
+        Ok(mut opened) => {
+            let grabbed = probe_grabs.then(|| match opened.grab() {
+                Ok(()) => {
+                    let _ = opened.ungrab();
+                    false
+                }
+                Err(e) => e.raw_os_error() == Some(nix::libc::EBUSY),
+            });
+            (true, None, grabbed)
+        }
+        Err(e) => (false, Some(e.to_string()), None),
+    };
+
+    DeviceAccess {
+        device_path: device.device_path.clone(),
+        name: device.name.clone(),
+        owner_uid: metadata.as_ref().map(|metadata| metadata.uid()),
+        group: metadata.as_ref().map(|metadata| GroupInfo {
+            gid: metadata.gid(),
+            name: group_names.get(&metadata.gid()).cloned(),
+        }),
+        mode: metadata.as_ref().map(|metadata| format!("{:o}", metadata.mode() & 0o777)),
+        readable_by_us,
+        open_error,
+        grabbed,
+    }
+}
+
+#[cfg(not(target_os = "linux"))]
+fn check_device(device: &EvdevGamepadInfo, _group_names: &HashMap<u32, String>, _probe_grabs: bool) -> DeviceAccess {
+    DeviceAccess {
+        device_path: device.device_path.clone(),
+        name: device.name.clone(),
+        owner_uid: None,
+        group: None,
+        mode: None,
+        readable_by_us: false,
+        open_error: Some(EVDEV_UNSUPPORTED.to_string()),
+        grabbed: None,
+    }
+}
+
+#[cfg(target_os = "linux")]
+fn check_hidraw_node(node: &HidrawNode, group_names: &HashMap<u32, String>) -> DeviceAccess {
+    let metadata = std::fs::metadata(&node.device_path).ok();
+    let open_error = std::fs::OpenOptions::new()
+        .read(true)
+        .write(true)
+        .open(&node.device_path)
+        .err()
+        .map(|e| e.to_string());
+
+    DeviceAccess {
+        device_path: node.device_path.clone(),
+        name: node.name.clone(),
+        owner_uid: metadata.as_ref().map(|metadata| metadata.uid()),
+        group: metadata.as_ref().map(|metadata| GroupInfo {
+            gid: metadata.gid(),
+            name: group_names.get(&metadata.gid()).cloned(),
+        }),
+        mode: metadata.as_ref().map(|metadata| format!("{:o}", metadata.mode() & 0o777)),
+        readable_by_us: open_error.is_none(),
+        open_error,
+        grabbed: None,
+    }
+}
+
+#[cfg(not(target_os = "linux"))]
+fn check_hidraw_node(node: &HidrawNode, _group_names: &HashMap<u32, String>) -> DeviceAccess {
+    DeviceAccess {
+        device_path: node.device_path.clone(),
+        name: node.name.clone(),
+        owner_uid: None,
+        group: None,
+        mode: None,
+        readable_by_us: false,
+        open_error: Some(EVDEV_UNSUPPORTED.to_string()),
+        grabbed: None,
+    }
+}
+
+/// Whether the virtual gamepad can be created: `/dev/uinput` opened read-write. Checked
+/// before creating one, so a failure comes with its fix.
+pub fn uinput_access() -> DeviceAccess {
+    check_uinput(&read_group_names())
+}
+
+pub fn uinput_remediation() -> String {
+    format!(
+        "The virtual gamepad needs write access to {}; load the module with `sudo modprobe uinput` if it's missing, then install a udev rule such as `{}` in /etc/udev/rules.d/ and reload udev",
+        UINPUT_PATH, UINPUT_UDEV_RULE
+    )
+}
+
+#[cfg(target_os = "linux")]
+fn check_uinput(group_names: &HashMap<u32, String>) -> DeviceAccess {
+    let metadata = std::fs::metadata(UINPUT_PATH).ok();
+    let open_error = std::fs::OpenOptions::new()
+        .read(true)
+        .write(true)
+        .open(UINPUT_PATH)
+        .err()
+        .map(|e| e.to_string());
+
+    DeviceAccess {
+        device_path: UINPUT_PATH.to_string(),
+        name: "uinput".to_string(),
+        owner_uid: metadata.as_ref().map(|metadata| metadata.uid()),
+        group: metadata.as_ref().map(|metadata| GroupInfo {
+            gid: metadata.gid(),
+            name: group_names.get(&metadata.gid()).cloned(),
+        }),
+        mode: metadata.as_ref().map(|metadata| format!("{:o}", metadata.mode() & 0o777)),
+        readable_by_us: open_error.is_none(),
+        open_error,
+        grabbed: None,
+    }
+}
+
+#[cfg(not(target_os = "linux"))]
+fn check_uinput(_group_names: &HashMap<u32, String>) -> DeviceAccess {
+    DeviceAccess {
+        device_path: UINPUT_PATH.to_string(),
+        name: "uinput".to_string(),
+        owner_uid: None,
+        group: None,
+        mode: None,
+        readable_by_us: false,
+        open_error: Some(EVDEV_UNSUPPORTED.to_string()),
+        grabbed: None,
+    }
+}
+
+fn suggest_remediations(devices: &[DeviceAccess], in_input_group: bool) -> Vec<Remediation> {
+    let mut remediations = Vec::new();
+    if devices.is_empty() {
+        remediations.push(Remediation {
+            kind: RemediationKind::ConnectController,
+            message: "No gamepad event nodes found; check that the controller is connected and rescan".to_string(),
+        });
+    }
+
+    let unreadable: Vec<&DeviceAccess> = devices.iter().filter(|device| !device.readable_by_us).collect();
+    let input_owned = |device: &&DeviceAccess| {
+        device.group.as_ref().and_then(|group| group.name.as_deref()) == Some(INPUT_GROUP)
+    };
+    if unreadable.iter().any(input_owned) && !in_input_group {
+        if user_listed_in_input_group() {
+            remediations.push(Remediation {
+                kind: RemediationKind::Relogin,
+                message: RELOGIN_MESSAGE.to_string(),
+            });
+        } else {
+            remediations.push(Remediation {
+                kind: RemediationKind::AddToInputGroup,
+                message: ADD_TO_INPUT_GROUP_MESSAGE.to_string(),
+            });
+        }
+    }
+    if unreadable.iter().any(|device| !input_owned(device)) {
+        remediations.push(Remediation {
+            kind: RemediationKind::InstallUdevRule,
+            message: udev_rule_message("Some gamepad nodes aren't"),
+        });
+    }
+
+    for device in devices.iter().filter(|device| device.grabbed == Some(true)) {
+        remediations.push(Remediation {
+            kind: RemediationKind::ReleaseGrab,
+            message: format!(
+                "{} ({}) is grabbed by another process, usually Steam Input; disable Steam Input for it or close Steam",
+                device.device_path, device.name
+            ),
+        });
+    }
+    remediations
+}
+
+fn udev_rule_message(subject: &str) -> String {
+    format!(
+        "{} group-readable by input; install a udev rule such as `{}` in /etc/udev/rules.d/ and reload udev",
+        subject, UDEV_RULE
+    )
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum OpenFailureCause {
+    Permission,
+    /// Held exclusively by another process
+    Busy,
+    /// Unplugged between listing /dev/input and opening the node
+    Gone,
+    Other,
+}
+
+#[cfg(target_os = "linux")]
+impl OpenFailureCause {
+    pub fn classify(error: &std::io::Error) -> Self {
+        match error.raw_os_error() {
+            Some(nix::libc::EACCES | nix::libc::EPERM) => Self::Permission,
+            Some(nix::libc::EBUSY) => Self::Busy,
+            Some(nix::libc::ENOENT | nix::libc::ENODEV | nix::libc::ENXIO) => Self::Gone,
+            _ => Self::Other,
+        }
+    }
+}
+
+/// Payload of `device-open-failed`: a gamepad node the scan found but couldn't open.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DeviceOpenFailure {
+    pub device_path: String,
+    pub name: String,
+    pub errno: Option<i32>,
+    pub error: String,
+    pub cause: OpenFailureCause,
+    pub remediation: String,
+    pub failed_at: u64,
+}
+
+#[cfg(target_os = "linux")]
+impl DeviceOpenFailure {
+    pub fn new(device_path: &str, name: &str, error: &std::io::Error, failed_at: u64) -> Self {
+        let cause = OpenFailureCause::classify(error);
+        Self {
+            device_path: device_path.to_string(),
+            name: name.to_string(),
+            errno: error.raw_os_error(),
+            error: error.to_string(),
+            cause,
+            remediation: open_failure_remediation(device_path, cause),
+            failed_at,
+        }
+    }
+}
+
+#[cfg(target_os = "linux")]
+fn open_failure_remediation(device_path: &str, cause: OpenFailureCause) -> String {
+    match cause {
+        OpenFailureCause::Permission => {
+            let input_owned = std::fs::metadata(device_path).ok()
+                .and_then(|metadata| read_group_names().remove(&metadata.gid()))
+                .is_some_and(|group| group == INPUT_GROUP);
+            if !input_owned {
+                udev_rule_message("The node isn't")
+            } else if in_input_group() == Some(true) {
+                "The node belongs to the input group you're in; check its mode allows group reads".to_string()
+            } else if user_listed_in_input_group() {
+                RELOGIN_MESSAGE.to_string()
+            } else {
+                ADD_TO_INPUT_GROUP_MESSAGE.to_string()
+            }
+        }
+        OpenFailureCause::Busy => "Another process holds the node, usually Steam Input; disable Steam Input for it or close Steam".to_string(),
+        OpenFailureCause::Gone => "The device disappeared during the scan; reconnect the controller and rescan".to_string(),
+        OpenFailureCause::Other => "See the log for details, then rescan".to_string(),
+    }
+}
+
+/// Whether the process has the `input` group among its supplementary groups.
+pub fn in_input_group() -> Option<bool> {
+    process_in_group(INPUT_GROUP)
+}
+
+fn process_in_group(group: &str) -> Option<bool> {
+    let group_names = read_group_names();
+    if group_names.is_empty() {
+        return None;
+    }
+    let status = std::fs::read_to_string("/proc/self/status").ok()?;
+    let groups = status_ids(&status, "Groups:")?;
+    Some(groups.iter().any(|gid| group_names.get(gid).map(String::as_str) == Some(group)))
+}
+
+/// Whether joining a group would let the user open evdev nodes, and the command to do it.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct InputGroupReport {
+    pub user_name: String,
+    /// Groups the user database gives the user, which this session may predate
+    pub current_groups: Vec<String>,
+    pub input_group_exists: bool,
+    pub user_in_input_group: bool,
+    /// Group owning the node that failed to open, when there is one
+    pub device_group: Option<String>,
+    pub suggestion: String,
+}
+
+impl InputGroupReport {
+    #[cfg(target_os = "linux")]
+    pub fn collect(device_path: Option<&str>) -> Self {
+        let group_names = read_group_names();
+        let group_name = |gid: u32| group_names.get(&gid).cloned().unwrap_or_else(|| gid.to_string());
+
+        let uid = getuid();
+        let user = User::from_uid(uid).ok().flatten();
+        let user_name = user.as_ref().map(|user| user.name.clone())
+            .or_else(|| std::env::var("USER").ok())
+            .unwrap_or_else(|| uid.to_string());
+        let current_groups: Vec<String> = user.as_ref()
+            .and_then(|user| getgrouplist(&CString::new(user.name.as_str()).ok()?, user.gid).ok())
+            .unwrap_or_default()
+            .into_iter()
+            .map(|gid| group_name(gid.as_raw()))
+            .collect();
+        let input_group_exists = group_names.values().any(|name| name == INPUT_GROUP);
+        let user_in_input_group = current_groups.iter().any(|group| group == INPUT_GROUP);
+        let device_group = device_path
+            .and_then(|path| std::fs::metadata(path).ok())
+            .map(|metadata| group_name(metadata.gid()));
+
+        let suggestion = match device_group.as_deref() {
+            // Nobody should join root's group to read a gamepad
+            Some("root") => udev_rule_message("The node is owned by root rather than"),
+            None if !input_group_exists => format!(
+                "There's no input group; create it with `sudo groupadd input`, then run `sudo usermod -aG input {}` and log out and back in",
+                user_name
+            ),
+            group => {
+                let group = group.unwrap_or(INPUT_GROUP);
+                if !current_groups.iter().any(|current| current == group) {
+                    format!("Run `sudo usermod -aG {} {}`, then log out and back in", group, user_name)
+                } else if process_in_group(group) == Some(false) {
+                    RELOGIN_MESSAGE.to_string()
+                } else {
+                    format!("{} is already in the {} group; check the node's mode allows group reads", user_name, group)
+                }
+            }
+        };
+
+        Self {
+            user_name,
+            current_groups,
+            input_group_exists,
+            user_in_input_group,
+            device_group,
+            suggestion,
+        }
+    }
+
+    #[cfg(not(target_os = "linux"))]
+    pub fn collect(_device_path: Option<&str>) -> Self {
+        Self {
+            user_name: std::env::var("USERNAME").or_else(|_| std::env::var("USER")).unwrap_or_default(),
+            current_groups: Vec::new(),
+            input_group_exists: false,
+            user_in_input_group: false,
+            device_group: None,
+            suggestion: EVDEV_UNSUPPORTED.to_string(),
+        }
+    }
+}
+
+/// Whether /etc/group lists `$USER` in the input group, regardless of this session.
+fn user_listed_in_input_group() -> bool {
+    let Ok(user) = std::env::var("USER") else {
+        return false;
+    };
+    std::fs::read_to_string("/etc/group")
+        .unwrap_or_default()
+        .lines()
+        .map(|line| line.split(':').collect::<Vec<_>>())
+        .find(|fields| fields.first() == Some(&INPUT_GROUP))
+        .and_then(|fields| fields.get(3).map(|members| members.split(',').any(|member| member == user)))
+        .unwrap_or(false)
+}
+
+fn read_group_names() -> HashMap<u32, String> {
+    std::fs::read_to_string("/etc/group")
+        .unwrap_or_default()
+        .lines()
+        .filter_map(|line| {
+            let mut fields = line.split(':');
+            let name = fields.next()?;
+            let gid = fields.nth(1)?.parse().ok()?;
+            Some((gid, name.to_string()))
+        })
+        .collect()
+}
+
+fn status_ids(status: &str, field: &str) -> Option<Vec<u32>> {
+    status.lines()
+        .find_map(|line| line.strip_prefix(field))
+        .map(|ids| ids.split_whitespace().filter_map(|id| id.parse().ok()).collect())
+}
@@ -0,0 +1,117 @@
+use crate::memory_stats::BufferStats;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// Controllers with counters of their own. Past it, the one counted longest ago is folded
+/// into the global totals, so ids that never come back don't pile up.
+pub const MAX_TRACKED_CONTROLLERS: usize = 64;
+
+/// What happened to a controller's inputs on their way through the gilrs pipeline.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PipelineMetrics {
+    /// Events read from gilrs or submitted by mock input and replays
+    pub received: u64,
+    pub emitted: u64,
+    /// Dropped or left unemitted by the flood alarm
+    pub dropped_rate_limit: u64,
+    /// Suppressed by the ghost input filter
+    pub dropped_dedup: u64,
+    /// Event kinds the pipeline ignores, and live input dropped while a replay runs
+    pub dropped_filter: u64,
+    /// Axis events the dead zone changed or added
+    pub transformed: u64,
+    /// Combination light effects fired
+    pub macro_triggered: u64,
+}
+
+impl PipelineMetrics {
+    fn add(&mut self, other: &PipelineMetrics) {
+        self.received += other.received;
+        self.emitted += other.emitted;
+        self.dropped_rate_limit += other.dropped_rate_limit;
+        self.dropped_dedup += other.dropped_dedup;
+        self.dropped_filter += other.dropped_filter;
+        self.transformed += other.transformed;
+        self.macro_triggered += other.macro_triggered;
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub enum PipelineCounter {
+    Received,
+    Emitted,
+    DroppedRateLimit,
+    DroppedDedup,
+    DroppedFilter,
+    Transformed,
+    MacroTriggered,
+}
+
+/// Per-controller pipeline counters. Kept across disconnects so a controller that
+/// dropped out can still be inspected, until reset or evicted.
+#[derive(Default)]
+pub struct PipelineMetricsStore {
+    controllers: HashMap<usize, PipelineMetrics>,
+    /// When each controller was last counted, in increments
+    counted_at: HashMap<usize, u64>,
+    increments: u64,
+    /// Counters of evicted controllers, still part of `global`
+    evicted: PipelineMetrics,
+}
+
+impl PipelineMetricsStore {
+    pub fn increment(&mut self, controller_id: usize, counter: PipelineCounter) {
+        self.increments += 1;
+        self.counted_at.insert(controller_id, self.increments);
+        if !self.controllers.contains_key(&controller_id) && self.controllers.len() >= MAX_TRACKED_CONTROLLERS {
+            self.evict_oldest();
+        }
+        let metrics = self.controllers.entry(controller_id).or_default();
+        let field = match counter {
+            PipelineCounter::Received => &mut metrics.received,
+            PipelineCounter::Emitted => &mut metrics.emitted,
+            PipelineCounter::DroppedRateLimit => &mut metrics.dropped_rate_limit,
+            PipelineCounter::DroppedDedup => &mut metrics.dropped_dedup,
+            PipelineCounter::DroppedFilter => &mut metrics.dropped_filter,
+            PipelineCounter::Transformed => &mut metrics.transformed,
+            PipelineCounter::MacroTriggered => &mut metrics.macro_triggered,
+        };
+        *field += 1;
+    }
+
+    pub fn get(&self, controller_id: usize) -> PipelineMetrics {
+        self.controllers.get(&controller_id).copied().unwrap_or_default()
+    }
+
+    pub fn reset(&mut self, controller_id: usize) {
+        self.controllers.remove(&controller_id);
+        self.counted_at.remove(&controller_id);
+    }
+
+    fn evict_oldest(&mut self) {
+        let oldest = self.controllers.keys()
+            .min_by_key(|id| self.counted_at.get(id).copied().unwrap_or(0))
+            .copied();
+        if let Some(metrics) = oldest.and_then(|id| self.controllers.remove(&id)) {
+            self.evicted.add(&metrics);
+        }
+        if let Some(id) = oldest {
+            self.counted_at.remove(&id);
+        }
+    }
+
+    pub fn buffer_stats(&self) -> BufferStats {
+        let approx_bytes = self.controllers.capacity() * std::mem::size_of::<(usize, PipelineMetrics)>()
+            + self.counted_at.capacity() * std::mem::size_of::<(usize, u64)>();
+        BufferStats::new("pipeline_metrics", self.controllers.len(), Some(MAX_TRACKED_CONTROLLERS), 1, approx_bytes)
+    }
+
+    /// Every controller's counters summed, evicted ones included.
+    pub fn global(&self) -> PipelineMetrics {
+        let mut total = self.evicted;
+        for metrics in self.controllers.values() {
+            total.add(metrics);
+        }
+        total
+    }
+}
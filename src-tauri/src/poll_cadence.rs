@@ -0,0 +1,143 @@
+use crate::emit_interest;
+use crate::health::PollSource;
+use crate::locking::MutexExt;
+use serde::{Deserialize, Serialize};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Mutex, OnceLock};
+use std::time::{Duration, Instant};
+
+/// Slowest idle interval allowed; also the worst-case latency of the first input after idle.
+pub const MAX_SLOW_INTERVAL_MS: u64 = 250;
+
+/// How fast the polling threads run, and when they slow down to save battery.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PollCadenceConfig {
+    /// Interval while input is flowing
+    pub fast_interval_ms: u64,
+    /// Interval once nothing has happened for `idle_after_ms`
+    pub slow_interval_ms: u64,
+    pub idle_after_ms: u64,
+    /// Stay at the fast interval in game mode, where battery matters less than latency
+    pub pin_fast_in_game_mode: bool,
+}
+
+impl Default for PollCadenceConfig {
+    fn default() -> Self {
+        Self {
+            fast_interval_ms: 10,
+            slow_interval_ms: 75,
+            idle_after_ms: 30_000,
+            pin_fast_in_game_mode: false,
+        }
+    }
+}
+
+impl PollCadenceConfig {
+    pub fn validate(&self) -> Result<(), String> {
+        if self.fast_interval_ms == 0 {
+            return Err("fast_interval_ms must be at least 1".to_string());
+        }
+        if !(self.fast_interval_ms..=MAX_SLOW_INTERVAL_MS).contains(&self.slow_interval_ms) {
+            return Err(format!(
+                "slow_interval_ms must be between fast_interval_ms and {}",
+                MAX_SLOW_INTERVAL_MS
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// Adaptive polling: fast while input or hotplug activity is recent, slow once idle.
+/// A thread asleep on the slow interval sees the first new input on its next pass,
+/// so waking costs at most one slow interval, and the pass after that is fast again.
+pub struct PollCadence {
+    config: Mutex<PollCadenceConfig>,
+    started: Instant,
+    /// Ms since `started` of the last input or hotplug, from either pipeline
+    last_activity_ms: AtomicU64,
+    game_mode: AtomicBool,
+    /// Whether any controller is connected, kept current by the maintenance thread
+    controllers_present: AtomicBool,
+    /// Interval each pipeline last slept for, for runtime metrics
+    current_interval_ms: [AtomicU64; 2],
+}
+
+impl PollCadence {
+    fn new() -> Self {
+        let config = PollCadenceConfig::default();
+        Self {
+            current_interval_ms: std::array::from_fn(|_| AtomicU64::new(config.fast_interval_ms)),
+            config: Mutex::new(config),
+            started: Instant::now(),
+            last_activity_ms: AtomicU64::new(0),
+            game_mode: AtomicBool::new(false),
+            controllers_present: AtomicBool::new(true),
+        }
+    }
+
+    pub fn configure(&self, config: PollCadenceConfig) -> Result<(), String> {
+        config.validate()?;
+        *self.config.lock_or_recover() = config;
+        Ok(())
+    }
+
+    pub fn config(&self) -> PollCadenceConfig {
+        *self.config.lock_or_recover()
+    }
+
+    pub fn set_game_mode(&self, game_mode: bool) {
+        self.game_mode.store(game_mode, Ordering::Relaxed);
+    }
+
+    pub fn set_controllers_present(&self, present: bool) {
+        self.controllers_present.store(present, Ordering::Relaxed);
+    }
+
+    /// Snaps both pipelines back to the fast interval.
+    pub fn note_activity(&self) {
+        let now = self.started.elapsed().as_millis() as u64;
+        self.last_activity_ms.store(now, Ordering::Relaxed);
+    }
+
+    pub fn is_idle(&self) -> bool {
+        let config = self.config();
+        if config.pin_fast_in_game_mode && self.game_mode.load(Ordering::Relaxed) {
+            return false;
+        }
+        let now = self.started.elapsed().as_millis() as u64;
+        now.saturating_sub(self.last_activity_ms.load(Ordering::Relaxed)) >= config.idle_after_ms
+    }
+
+    /// Idle with no controller connected and the window hidden: nothing can arrive but a
+    /// hotplug, which snaps the cadence back to fast anyway.
+    pub fn is_dormant(&self) -> bool {
+        self.is_idle()
+            && !self.controllers_present.load(Ordering::Relaxed)
+            && !emit_interest::interest().window_visible()
+    }
+
+    /// How long `source` should sleep before its next pass.
+    pub fn next_interval(&self, source: PollSource) -> Duration {
+        let config = self.config();
+        let interval_ms = if self.is_dormant() {
+            MAX_SLOW_INTERVAL_MS
+        } else if self.is_idle() {
+            config.slow_interval_ms
+        } else {
+            config.fast_interval_ms
+        };
+        self.current_interval_ms[source.index()].store(interval_ms, Ordering::Relaxed);
+        Duration::from_millis(interval_ms)
+    }
+
+    pub fn current_interval_ms(&self, source: PollSource) -> u64 {
+        self.current_interval_ms[source.index()].load(Ordering::Relaxed)
+    }
+}
+
+static POLL_CADENCE: OnceLock<PollCadence> = OnceLock::new();
+
+pub fn cadence() -> &'static PollCadence {
+    POLL_CADENCE.get_or_init(PollCadence::new)
+}
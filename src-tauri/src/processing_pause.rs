@@ -0,0 +1,76 @@
+use crate::app_state::AppState;
+use crate::background;
+use crate::locking::MutexExt;
+use serde::{Deserialize, Serialize};
+use std::sync::atomic::{AtomicU8, Ordering};
+use tauri::{AppHandle, Emitter, Manager};
+use tracing::{info, warn};
+
+/// Emitted with the new `Option<PauseMode>` whenever processing is paused or resumed.
+pub const PROCESSING_PAUSE_CHANGED: &str = "input-processing-pause-changed";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PauseMode {
+    /// Input is read and discarded: no state updates, emits or light server sends
+    #[default]
+    Full,
+    /// Controller state and the UI keep updating, but nothing goes to the light server
+    ForwardingOnly,
+}
+
+const RUNNING: u8 = 0;
+const FULL: u8 = 1;
+const FORWARDING_ONLY: u8 = 2;
+
+/// Whether input processing is paused, and how. Persisted in settings and applied at startup.
+static PAUSE: AtomicU8 = AtomicU8::new(RUNNING);
+
+pub fn set_pause_mode(mode: Option<PauseMode>) {
+    let value = match mode {
+        None => RUNNING,
+        Some(PauseMode::Full) => FULL,
+        Some(PauseMode::ForwardingOnly) => FORWARDING_ONLY,
+    };
+    PAUSE.store(value, Ordering::SeqCst);
+}
+
+/// Pauses and persists it, for the command and the tray. Events still waiting in the
+/// batcher are discarded rather than sent.
+pub fn pause(app: &AppHandle, mode: PauseMode) -> Result<(), String> {
+    let state = app.state::<AppState>();
+    state.settings.update(|settings| settings.processing_paused = Some(mode))?;
+    set_pause_mode(Some(mode));
+    let discarded = state.batcher.lock_or_recover().take().len();
+    warn!("Input processing paused ({:?}); discarded {} queued events", mode, discarded);
+    app.emit(PROCESSING_PAUSE_CHANGED, Some(mode)).ok();
+    background::sync_tray(app);
+    Ok(())
+}
+
+pub fn resume(app: &AppHandle) -> Result<(), String> {
+    app.state::<AppState>().settings.update(|settings| settings.processing_paused = None)?;
+    set_pause_mode(None);
+    info!("Input processing resumed");
+    app.emit(PROCESSING_PAUSE_CHANGED, None::<PauseMode>).ok();
+    background::sync_tray(app);
+    Ok(())
+}
+
+pub fn pause_mode() -> Option<PauseMode> {
+    match PAUSE.load(Ordering::SeqCst) {
+        FULL => Some(PauseMode::Full),
+        FORWARDING_ONLY => Some(PauseMode::ForwardingOnly),
+        _ => None,
+    }
+}
+
+/// Input should be dropped before it touches controller state.
+pub fn processing_paused() -> bool {
+    pause_mode() == Some(PauseMode::Full)
+}
+
+/// Nothing may be sent to the light server, whichever way processing is paused.
+pub fn forwarding_paused() -> bool {
+    pause_mode().is_some()
+}
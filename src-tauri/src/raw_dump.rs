@@ -0,0 +1,122 @@
+use serde::{Deserialize, Serialize};
+use std::time::{Duration, Instant};
+
+pub const DEFAULT_RAW_DUMP_SECS: u64 = 60;
+pub const MAX_RAW_DUMP_SECS: u64 = 600;
+/// Events emitted per second before the rest are dropped and counted.
+const MAX_RAW_EVENTS_PER_SEC: u32 = 500;
+
+/// One event exactly as the kernel reported it, SYN and MSC included.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RawEvdevEvent {
+    pub event_type: String,
+    pub code: u16,
+    pub code_name: Option<String>,
+    pub value: i32,
+    /// Kernel timestamp in microseconds since the epoch
+    pub timestamp_us: u64,
+}
+
+/// Payload of `evdev-raw`: the events from one polling pass that fit under the rate cap.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RawEventBatch {
+    pub device_path: String,
+    pub events: Vec<RawEvdevEvent>,
+    /// Events from this pass that were dropped by the rate cap
+    pub dropped: u64,
+    pub total_dropped: u64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RawDumpStopReason {
+    Disabled,
+    Expired,
+    Replaced,
+}
+
+/// Payload of `evdev-raw-stopped`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RawDumpStopped {
+    pub device_path: String,
+    pub reason: RawDumpStopReason,
+    pub total_events: u64,
+    pub total_dropped: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RawDumpStatus {
+    pub device_path: String,
+    pub remaining_secs: u64,
+    pub total_events: u64,
+    pub total_dropped: u64,
+}
+
+/// Raw event dump for a single device, which turns itself off after `duration`.
+pub struct RawDump {
+    pub device_path: String,
+    expires_at: Instant,
+    window_started: Instant,
+    sent_in_window: u32,
+    total_events: u64,
+    total_dropped: u64,
+}
+
+impl RawDump {
+    pub fn new(device_path: String, duration: Duration) -> Self {
+        let now = Instant::now();
+        Self {
+            device_path,
+            expires_at: now + duration,
+            window_started: now,
+            sent_in_window: 0,
+            total_events: 0,
+            total_dropped: 0,
+        }
+    }
+
+    pub fn expired(&self) -> bool {
+        Instant::now() >= self.expires_at
+    }
+
+    /// Keeps as many of `events` as this second's budget allows, counting the rest as dropped.
+    pub fn admit(&mut self, mut events: Vec<RawEvdevEvent>) -> RawEventBatch {
+        let now = Instant::now();
+        if now.duration_since(self.window_started) >= Duration::from_secs(1) {
+            self.window_started = now;
+            self.sent_in_window = 0;
+        }
+
+        let budget = MAX_RAW_EVENTS_PER_SEC.saturating_sub(self.sent_in_window) as usize;
+        let dropped = events.len().saturating_sub(budget) as u64;
+        events.truncate(budget);
+        self.sent_in_window += events.len() as u32;
+        self.total_events += events.len() as u64;
+        self.total_dropped += dropped;
+
+        RawEventBatch {
+            device_path: self.device_path.clone(),
+            events,
+            dropped,
+            total_dropped: self.total_dropped,
+        }
+    }
+
+    pub fn stopped(&self, reason: RawDumpStopReason) -> RawDumpStopped {
+        RawDumpStopped {
+            device_path: self.device_path.clone(),
+            reason,
+            total_events: self.total_events,
+            total_dropped: self.total_dropped,
+        }
+    }
+
+    pub fn status(&self) -> RawDumpStatus {
+        RawDumpStatus {
+            device_path: self.device_path.clone(),
+            remaining_secs: self.expires_at.saturating_duration_since(Instant::now()).as_secs(),
+            total_events: self.total_events,
+            total_dropped: self.total_dropped,
+        }
+    }
+}
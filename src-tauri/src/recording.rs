@@ -0,0 +1,421 @@
+use crate::error_counters::{self, ErrorCategory};
+use crate::evdev_gamepad::EvdevGamepadManager;
+use crate::evdev_types::EvdevControllerEvent;
+use crate::gamepad::{GamepadInput, GamepadManager};
+use crate::locking::MutexExt;
+use base64::engine::general_purpose::STANDARD as BASE64;
+use base64::Engine;
+use flate2::read::GzDecoder;
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use serde::{Deserialize, Serialize};
+use std::borrow::Cow;
+use std::fs::File;
+use std::io::{BufRead, BufReader, BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use tauri::{AppHandle, Emitter};
+use tracing::{info, warn};
+
+const RECORDING_FORMAT: &str = "steamdeck-controller-recording";
+const RECORDING_VERSION: u32 = 1;
+/// Upper bound on how long a replay sleeps between cancellation checks.
+const REPLAY_TICK: Duration = Duration::from_millis(10);
+const PROGRESS_INTERVAL: Duration = Duration::from_millis(100);
+
+/// First line of every recording file.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct RecordingHeader {
+    format: String,
+    version: u32,
+}
+
+/// One line of a recording: an input and its offset from the start of the recording.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RecordedEvent {
+    pub offset_ms: u64,
+    #[serde(flatten)]
+    pub input: RecordedInput,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "source", rename_all = "snake_case")]
+pub enum RecordedInput {
+    Gilrs {
+        controller_id: usize,
+        input: GamepadInput,
+    },
+    Evdev {
+        device_path: String,
+        event_type: Cow<'static, str>,
+        code: u16,
+        value: i32,
+    },
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RecordingSummary {
+    pub path: String,
+    pub event_count: u64,
+    pub duration_ms: u64,
+}
+
+/// What happens to real controller input while a replay is running.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LiveInputMode {
+    /// Live input is dropped until the replay ends
+    #[default]
+    Pause,
+    /// Live and replayed input both go through the pipeline
+    Merge,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct ReplayOptions {
+    /// Playback rate; 2.0 plays twice as fast
+    pub speed: f64,
+    pub looping: bool,
+    pub live_input: LiveInputMode,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReplayProgress {
+    pub path: String,
+    pub position_ms: u64,
+    pub duration_ms: u64,
+    pub events_played: usize,
+    pub total_events: usize,
+    pub iteration: u32,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReplayFinished {
+    pub path: String,
+    pub cancelled: bool,
+    pub iterations: u32,
+}
+
+struct ActiveRecording {
+    path: PathBuf,
+    writer: BufWriter<File>,
+    started: Instant,
+    event_count: u64,
+}
+
+/// Records live input to JSONL files and replays them back through the input pipeline.
+///
+/// Replayed events are handed to the same managers as live ones, tagged `synthetic`, and
+/// are never themselves recorded.
+#[derive(Default)]
+pub struct InputSession {
+    recording: Mutex<Option<ActiveRecording>>,
+    replay_cancel: Mutex<Option<Arc<AtomicBool>>>,
+    live_paused: AtomicBool,
+}
+
+impl InputSession {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn start_recording(&self, path: PathBuf) -> Result<(), String> {
+        let mut recording = self.recording.lock_or_recover();
+        if let Some(active) = recording.as_ref() {
+            return Err(format!("Already recording to {}", active.path.display()));
+        }
+
+        let file = File::create(&path)
+            .map_err(|e| format!("Failed to create recording {}: {}", path.display(), e))?;
+        let mut writer = BufWriter::new(file);
+        let header = RecordingHeader {
+            format: RECORDING_FORMAT.to_string(),
+            version: RECORDING_VERSION,
+        };
+        serde_json::to_writer(&mut writer, &header)
+            .map_err(|e| format!("Failed to write recording header: {}", e))?;
+        writer.write_all(b"\n")
+            .map_err(|e| format!("Failed to write recording header: {}", e))?;
+
+        info!("Recording input to {}", path.display());
+        *recording = Some(ActiveRecording {
+            path,
+            writer,
+            started: Instant::now(),
+            event_count: 0,
+        });
+        Ok(())
+    }
+
+    pub fn stop_recording(&self) -> Result<RecordingSummary, String> {
+        let mut active = self.recording.lock_or_recover().take()
+            .ok_or_else(|| "No recording in progress".to_string())?;
+        active.writer.flush()
+            .map_err(|e| format!("Failed to flush recording {}: {}", active.path.display(), e))?;
+
+        info!("Stopped recording to {} ({} events)", active.path.display(), active.event_count);
+        Ok(RecordingSummary {
+            path: active.path.to_string_lossy().to_string(),
+            event_count: active.event_count,
+            duration_ms: active.started.elapsed().as_millis() as u64,
+        })
+    }
+
+    /// Whether `record` would write anything, so callers can skip building the input.
+    pub fn is_recording(&self) -> bool {
+        self.recording.lock_or_recover().is_some()
+    }
+
+    /// Appends `input` to the active recording, if any.
+    pub fn record(&self, input: RecordedInput) {
+        let mut recording = self.recording.lock_or_recover();
+        let Some(active) = recording.as_mut() else {
+            return;
+        };
+
+        let event = RecordedEvent {
+            offset_ms: active.started.elapsed().as_millis() as u64,
+            input,
+        };
+        let result = serde_json::to_writer(&mut active.writer, &event)
+            .map_err(|e| e.to_string())
+            .and_then(|_| active.writer.write_all(b"\n").map_err(|e| e.to_string()));
+        match result {
+            Ok(()) => active.event_count += 1,
+            Err(e) => {
+                warn!("Failed to write to recording {}: {}", active.path.display(), e);
+                error_counters::errors().record(
+                    ErrorCategory::RecordingWrite,
+                    format!("{}: {}", active.path.display(), e),
+                );
+            }
+        }
+    }
+
+    pub fn live_input_paused(&self) -> bool {
+        self.live_paused.load(Ordering::Relaxed)
+    }
+
+    pub fn is_replaying(&self) -> bool {
+        self.replay_cancel.lock_or_recover().is_some()
+    }
+
+    /// Starts replaying `path` on a background thread, cancelling any replay already running.
+    ///
+    /// Progress is reported through `replay-progress` events and completion through
+    /// `replay-finished`.
+    pub fn start_replay(
+        self: &Arc<Self>,
+        app: AppHandle,
+        gamepad_manager: Arc<GamepadManager>,
+        evdev_manager: Arc<EvdevGamepadManager>,
+        path: PathBuf,
+        options: ReplayOptions,
+    ) -> Result<ReplayProgress, String> {
+        if !options.speed.is_finite() || options.speed <= 0.0 {
+            return Err(format!("Replay speed must be positive, got {}", options.speed));
+        }
+
+        let events = load_recording(&path)?;
+        let duration_ms = events.last().map(|event| event.offset_ms).unwrap_or(0);
+        let path_str = path.to_string_lossy().to_string();
+
+        let cancel = Arc::new(AtomicBool::new(false));
+        {
+            let mut current = self.replay_cancel.lock_or_recover();
+            if let Some(previous) = current.replace(cancel.clone()) {
+                previous.store(true, Ordering::Relaxed);
+            }
+            self.live_paused.store(options.live_input == LiveInputMode::Pause, Ordering::Relaxed);
+        }
+
+        info!("Replaying {} ({} events, {:.2}x, loop={}, live={:?})",
+              path.display(), events.len(), options.speed, options.looping, options.live_input);
+
+        let initial = ReplayProgress {
+            path: path_str.clone(),
+            position_ms: 0,
+            duration_ms,
+            events_played: 0,
+            total_events: events.len(),
+            iteration: 0,
+        };
+
+        let session = self.clone();
+        std::thread::spawn(move || {
+            let (cancelled, iterations) = run_replay(
+                &app, &gamepad_manager, &evdev_manager, &events, &path_str, options, &cancel,
+            );
+            session.finish_replay(&cancel);
+            info!("Replay of {} {} after {} iteration(s)",
+                  path_str, if cancelled { "cancelled" } else { "finished" }, iterations);
+            app.emit("replay-finished", ReplayFinished {
+                path: path_str,
+                cancelled,
+                iterations,
+            }).ok();
+        });
+
+        Ok(initial)
+    }
+
+    /// Cancels the running replay and resumes live input. Returns false if nothing was replaying.
+    pub fn stop_replay(&self) -> bool {
+        let mut current = self.replay_cancel.lock_or_recover();
+        let Some(cancel) = current.take() else {
+            return false;
+        };
+        cancel.store(true, Ordering::Relaxed);
+        self.live_paused.store(false, Ordering::Relaxed);
+        true
+    }
+
+    fn finish_replay(&self, cancel: &Arc<AtomicBool>) {
+        // A newer replay may have taken over; leave its state alone
+        let mut current = self.replay_cancel.lock_or_recover();
+        if current.as_ref().is_some_and(|active| Arc::ptr_eq(active, cancel)) {
+            *current = None;
+            self.live_paused.store(false, Ordering::Relaxed);
+        }
+    }
+}
+
+fn load_recording(path: &Path) -> Result<Vec<RecordedEvent>, String> {
+    let file = File::open(path)
+        .map_err(|e| format!("Failed to open recording {}: {}", path.display(), e))?;
+    parse_recording(BufReader::new(file), &path.display().to_string())
+}
+
+/// Reads a header line and then one event per line from `reader`; `name` is only used
+/// in error messages.
+fn parse_recording(reader: impl BufRead, name: &str) -> Result<Vec<RecordedEvent>, String> {
+    let mut lines = reader.lines();
+
+    let header_line = lines.next()
+        .ok_or_else(|| format!("{} is empty", name))?
+        .map_err(|e| format!("Failed to read {}: {}", name, e))?;
+    let header: RecordingHeader = serde_json::from_str(&header_line)
+        .map_err(|_| format!("{} is not a controller recording", name))?;
+    if header.format != RECORDING_FORMAT || header.version > RECORDING_VERSION {
+        return Err(format!("Unsupported recording format {} v{}", header.format, header.version));
+    }
+
+    let mut events = Vec::new();
+    for (index, line) in lines.enumerate() {
+        let line = line.map_err(|e| format!("Failed to read {}: {}", name, e))?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let event: RecordedEvent = serde_json::from_str(&line)
+            .map_err(|e| format!("Invalid event on line {} of {}: {}", index + 2, name, e))?;
+        events.push(event);
+    }
+    Ok(events)
+}
+
+/// Re-serializes the recording at `path` into gzip-compressed JSONL, base64-encoded so it
+/// can travel through IPC as a JSON string.
+pub fn export_compressed(path: &Path) -> Result<String, String> {
+    let events = load_recording(path)?;
+    let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+    let header = RecordingHeader {
+        format: RECORDING_FORMAT.to_string(),
+        version: RECORDING_VERSION,
+    };
+    serde_json::to_writer(&mut encoder, &header)
+        .map_err(|e| format!("Failed to compress recording: {}", e))?;
+    encoder.write_all(b"\n")
+        .map_err(|e| format!("Failed to compress recording: {}", e))?;
+    for event in &events {
+        serde_json::to_writer(&mut encoder, event)
+            .map_err(|e| format!("Failed to compress recording: {}", e))?;
+        encoder.write_all(b"\n")
+            .map_err(|e| format!("Failed to compress recording: {}", e))?;
+    }
+    let compressed = encoder.finish()
+        .map_err(|e| format!("Failed to compress recording: {}", e))?;
+
+    info!("Exported {} ({} events, {} bytes compressed)", path.display(), events.len(), compressed.len());
+    Ok(BASE64.encode(compressed))
+}
+
+/// Decodes an export from [`export_compressed`], decompressing it line by line.
+pub fn import_compressed(data: &str) -> Result<Vec<RecordedEvent>, String> {
+    let compressed = BASE64.decode(data.trim())
+        .map_err(|e| format!("Compressed recording is not valid base64: {}", e))?;
+    parse_recording(BufReader::new(GzDecoder::new(compressed.as_slice())), "compressed recording")
+}
+
+/// Plays `events` until done (or until cancelled when looping), returning whether it was
+/// cancelled and how many full passes completed.
+fn run_replay(
+    app: &AppHandle,
+    gamepad_manager: &GamepadManager,
+    evdev_manager: &EvdevGamepadManager,
+    events: &[RecordedEvent],
+    path: &str,
+    options: ReplayOptions,
+    cancel: &AtomicBool,
+) -> (bool, u32) {
+    let duration_ms = events.last().map(|event| event.offset_ms).unwrap_or(0);
+    let mut iteration = 0;
+
+    loop {
+        let started = Instant::now();
+        let mut last_progress = Instant::now();
+
+        for (index, event) in events.iter().enumerate() {
+            let due = Duration::from_secs_f64(event.offset_ms as f64 / 1000.0 / options.speed);
+            loop {
+                if cancel.load(Ordering::Relaxed) {
+                    return (true, iteration);
+                }
+                let elapsed = started.elapsed();
+                if elapsed >= due {
+                    break;
+                }
+                std::thread::sleep((due - elapsed).min(REPLAY_TICK));
+            }
+
+            match &event.input {
+                RecordedInput::Gilrs { controller_id, input } => {
+                    gamepad_manager.handle_input(*controller_id, input.clone(), true);
+                }
+                RecordedInput::Evdev { device_path, event_type, code, value } => {
+                    evdev_manager.submit_event(EvdevControllerEvent {
+                        device_path: device_path.clone(),
+                        event_type: event_type.clone(),
+                        code: *code,
+                        value: *value,
+                        timestamp: SystemTime::now()
+                            .duration_since(UNIX_EPOCH)
+                            .unwrap_or_default()
+                            .as_millis() as u64,
+                        synthetic: true,
+                        source_time: None,
+                    });
+                }
+            }
+
+            let played = index + 1;
+            if last_progress.elapsed() >= PROGRESS_INTERVAL || played == events.len() {
+                last_progress = Instant::now();
+                app.emit("replay-progress", ReplayProgress {
+                    path: path.to_string(),
+                    position_ms: event.offset_ms,
+                    duration_ms,
+                    events_played: played,
+                    total_events: events.len(),
+                    iteration,
+                }).ok();
+            }
+        }
+
+        iteration += 1;
+        if !options.looping || events.is_empty() {
+            return (false, iteration);
+        }
+    }
+}
@@ -0,0 +1,116 @@
+use serde::{Deserialize, Serialize};
+use std::sync::mpsc::Sender;
+use std::time::{Duration, Instant, SystemTime};
+#[cfg(target_os = "linux")]
+use std::sync::mpsc;
+#[cfg(target_os = "linux")]
+use tracing::{info, warn};
+
+/// How often the clocks are compared.
+pub const RESUME_CHECK_INTERVAL: Duration = Duration::from_secs(5);
+/// The wall clock getting this far ahead of the monotonic clock means the system slept.
+const SUSPEND_GAP_THRESHOLD: Duration = Duration::from_secs(10);
+#[cfg(target_os = "linux")]
+const LOGIND_SUBSCRIBE_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// Notices the system resuming from suspend: the monotonic clock stops while suspended,
+/// the wall clock doesn't.
+pub struct ResumeDetector {
+    wall: SystemTime,
+    monotonic: Instant,
+}
+
+impl Default for ResumeDetector {
+    fn default() -> Self {
+        Self {
+            wall: SystemTime::now(),
+            monotonic: Instant::now(),
+        }
+    }
+}
+
+impl ResumeDetector {
+    /// Roughly how long the system was suspended, if it resumed since the last check.
+    pub fn check(&mut self) -> Option<Duration> {
+        let wall = SystemTime::now();
+        let monotonic = Instant::now();
+        let wall_elapsed = wall.duration_since(self.wall).unwrap_or_default();
+        let monotonic_elapsed = monotonic.duration_since(self.monotonic);
+        self.wall = wall;
+        self.monotonic = monotonic;
+        let gap = wall_elapsed.saturating_sub(monotonic_elapsed);
+        (gap >= SUSPEND_GAP_THRESHOLD).then_some(gap)
+    }
+}
+
+/// What noticed the resume.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ResumeTrigger {
+    /// logind's `PrepareForSleep(false)`
+    Logind,
+    /// The wall clock jumping ahead of the monotonic clock
+    ClockJump,
+}
+
+/// A resume reported by logind, with how long the system was asleep.
+#[derive(Debug, Clone, Copy)]
+pub struct LogindResume {
+    pub suspended_for: Option<Duration>,
+}
+
+/// Subscribes to logind's `PrepareForSleep` signal and sends a `LogindResume` each time
+/// the system wakes. Returns once subscribed, or with an error when logind isn't reachable
+/// (no system bus, or not systemd), in which case only the clock heuristic is left.
+#[cfg(target_os = "linux")]
+pub fn watch_logind(resumes: Sender<LogindResume>) -> Result<(), String> {
+    let (ready_tx, ready_rx) = mpsc::channel();
+    std::thread::spawn(move || {
+        let subscribe = || -> zbus::Result<_> {
+            let connection = zbus::blocking::Connection::system()?;
+            let proxy = zbus::blocking::Proxy::new(
+                &connection,
+                "org.freedesktop.login1",
+                "/org/freedesktop/login1",
+                "org.freedesktop.login1.Manager",
+            )?;
+            let signals = proxy.receive_signal("PrepareForSleep")?;
+            Ok((connection, signals))
+        };
+        let (_connection, signals) = match subscribe() {
+            Ok(subscribed) => subscribed,
+            Err(e) => {
+                let _ = ready_tx.send(Err(format!("Failed to subscribe to logind sleep signals: {}", e)));
+                return;
+            }
+        };
+        let _ = ready_tx.send(Ok(()));
+        let mut sleeping_since = None;
+        for message in signals {
+            let going_to_sleep = match message.body().deserialize::<bool>() {
+                Ok(start) => start,
+                Err(e) => {
+                    warn!("Ignoring malformed PrepareForSleep signal: {}", e);
+                    continue;
+                }
+            };
+            if going_to_sleep {
+                info!("logind: system is going to sleep");
+                sleeping_since = Some(SystemTime::now());
+                continue;
+            }
+            let suspended_for = sleeping_since.take().and_then(|since| since.elapsed().ok());
+            if resumes.send(LogindResume { suspended_for }).is_err() {
+                break;
+            }
+        }
+        warn!("logind sleep signal stream ended; falling back to the clock heuristic");
+    });
+    ready_rx.recv_timeout(LOGIND_SUBSCRIBE_TIMEOUT)
+        .map_err(|_| "Timed out subscribing to logind sleep signals".to_string())?
+}
+
+#[cfg(not(target_os = "linux"))]
+pub fn watch_logind(_resumes: Sender<LogindResume>) -> Result<(), String> {
+    Err("logind is only available on Linux".to_string())
+}
@@ -0,0 +1,96 @@
+use crate::app_state::AppState;
+use crate::error_counters::{self, ErrorCategory};
+use crate::fan_control::FanMode;
+use crate::health::PollSource;
+use crate::resume_detector::ResumeTrigger;
+use serde::{Deserialize, Serialize};
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Emitter, Manager};
+use tracing::{info, warn};
+
+pub const SYSTEM_RESUMED: &str = "system-resumed";
+
+/// Payload of `system-resumed`: what the recovery after a resume found and redid.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SystemResumed {
+    pub trigger: ResumeTrigger,
+    pub suspended_for_ms: Option<u64>,
+    pub gilrs_gamepads_before: usize,
+    /// `None` when the gamepad backend couldn't be restarted
+    pub gilrs_gamepads_after: Option<usize>,
+    pub evdev_devices_before: usize,
+    pub evdev_devices_after: usize,
+    pub fan_mode_reapplied: bool,
+    /// Steps that failed, in order; the rest of the recovery still ran
+    pub errors: Vec<String>,
+    pub recovery_ms: u64,
+}
+
+/// Brings input back after the system wakes: evdev file descriptors are usually stale and
+/// gilrs may have lost its pads. Every device is reopened (per-device settings such as axis
+/// centers are keyed by path, so they apply again), gilrs is restarted, a manual fan speed
+/// is written again since the EC resets it, and pooled light server connections are dropped.
+pub fn recover(app: &AppHandle, trigger: ResumeTrigger, suspended_for: Option<Duration>) {
+    let started = Instant::now();
+    let state = app.state::<AppState>();
+    match suspended_for {
+        Some(duration) => info!("Resume detected ({:?}) after about {}s asleep; recovering input", trigger, duration.as_secs()),
+        None => info!("Resume detected ({:?}); recovering input", trigger),
+    }
+    let mut errors = Vec::new();
+
+    let evdev = state.backends.get(PollSource::Evdev);
+    let evdev_device_count = || evdev.as_ref().map_or(0, |backend| backend.debug_info().device_count);
+    let evdev_devices_before = evdev_device_count();
+    if let Some(backend) = &evdev {
+        info!("Resume: reopening {} evdev device(s)", evdev_devices_before);
+        if let Err(e) = state.backends.while_paused(PollSource::Evdev, || backend.scan(app)) {
+            errors.push(format!("evdev rescan: {}", e));
+        }
+    }
+    let evdev_devices_after = evdev_device_count();
+    info!("Resume: {} evdev device(s) after the rescan", evdev_devices_after);
+
+    let gilrs_gamepads_before = state.gamepad_manager.gamepad_infos().iter().filter(|info| info.is_connected).count();
+    info!("Resume: restarting the gamepad backend ({} gamepad(s) before)", gilrs_gamepads_before);
+    let gilrs_gamepads_after = match state.backends.while_paused(PollSource::Gilrs, || state.gamepad_manager.reinit_gilrs()) {
+        Ok(connected) => Some(connected.len()),
+        Err(e) => {
+            errors.push(format!("gamepad backend restart: {}", e));
+            None
+        }
+    };
+
+    let fan_mode = state.evdev_manager.get_fan_speed_mode();
+    let fan_mode_reapplied = fan_mode != FanMode::Auto;
+    if fan_mode_reapplied {
+        info!("Resume: reapplying fan mode {:?}", fan_mode);
+        if let Err(e) = state.evdev_manager.set_fan_speed_mode(fan_mode) {
+            errors.push(format!("fan mode: {}", e));
+        }
+    }
+
+    info!("Resume: dropping pooled light server connections");
+    if let Err(e) = state.http.reset_connections() {
+        errors.push(format!("HTTP clients: {}", e));
+    }
+
+    for error in &errors {
+        warn!("Resume recovery step failed: {}", error);
+    }
+    let resumed = SystemResumed {
+        trigger,
+        suspended_for_ms: suspended_for.map(|duration| duration.as_millis() as u64),
+        gilrs_gamepads_before,
+        gilrs_gamepads_after,
+        evdev_devices_before,
+        evdev_devices_after,
+        fan_mode_reapplied,
+        errors,
+        recovery_ms: started.elapsed().as_millis() as u64,
+    };
+    info!("Resume recovery finished in {}ms: {:?}", resumed.recovery_ms, resumed);
+    if let Err(e) = app.emit(SYSTEM_RESUMED, &resumed) {
+        error_counters::errors().record(ErrorCategory::Emit, format!("{}: {}", SYSTEM_RESUMED, e));
+    }
+}
@@ -0,0 +1,289 @@
+use crate::error_counters::{self, ErrorCategory};
+use crate::health::PollSource;
+use crate::locking::MutexExt;
+use crate::poll_cadence;
+use serde::{Deserialize, Serialize};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Mutex, MutexGuard, OnceLock};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+/// Seconds of history kept; also the longest reported window.
+const HISTORY_SECS: usize = 60;
+const WINDOWS_SECS: [u64; 3] = [1, 10, 60];
+/// Lock waits at least this long are counted as contention errors.
+const LOCK_CONTENTION_THRESHOLD: Duration = Duration::from_millis(50);
+
+#[derive(Debug, Clone, Copy)]
+pub enum Counter {
+    GilrsEvents,
+    EvdevEvents,
+    Emits,
+    EmitFailures,
+    /// Per-input emits skipped because the window is hidden or nobody subscribed
+    EmitsSuppressed,
+    /// Samples replaced by a newer one while the event bus was full
+    EventsCoalesced,
+    /// Samples discarded while the event bus was full
+    EventsDropped,
+}
+
+const COUNTER_COUNT: usize = 7;
+
+#[derive(Debug, Clone, Copy)]
+pub enum Timing {
+    GilrsPoll,
+    EvdevPoll,
+    GilrsLockWait,
+    StatesLockWait,
+    ComboTrackerLockWait,
+    TimeSeriesLockWait,
+    /// Time inputs spend queued before the dispatcher handles them
+    DispatchQueueWait,
+    /// Cloning controller states into the snapshot commands read
+    StatePublish,
+}
+
+const TIMING_COUNT: usize = 8;
+
+/// Counters for one wall-clock second. Reused in a ring, so `second` says which
+/// second the values belong to.
+struct Bucket {
+    second: AtomicU64,
+    counters: [AtomicU64; COUNTER_COUNT],
+    timing_count: [AtomicU64; TIMING_COUNT],
+    timing_total_ns: [AtomicU64; TIMING_COUNT],
+    timing_max_ns: [AtomicU64; TIMING_COUNT],
+    max_light_sends_in_flight: AtomicU64,
+}
+
+impl Default for Bucket {
+    fn default() -> Self {
+        Self {
+            second: AtomicU64::new(0),
+            counters: std::array::from_fn(|_| AtomicU64::new(0)),
+            timing_count: std::array::from_fn(|_| AtomicU64::new(0)),
+            timing_total_ns: std::array::from_fn(|_| AtomicU64::new(0)),
+            timing_max_ns: std::array::from_fn(|_| AtomicU64::new(0)),
+            max_light_sends_in_flight: AtomicU64::new(0),
+        }
+    }
+}
+
+impl Bucket {
+    fn clear(&self) {
+        let all = self.counters.iter()
+            .chain(&self.timing_count)
+            .chain(&self.timing_total_ns)
+            .chain(&self.timing_max_ns)
+            .chain(std::iter::once(&self.max_light_sends_in_flight));
+        for value in all {
+            value.store(0, Ordering::Relaxed);
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct TimingSnapshot {
+    pub count: u64,
+    pub avg_ns: u64,
+    pub max_ns: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WindowSnapshot {
+    pub window_secs: u64,
+    pub gilrs_events_per_sec: f64,
+    pub evdev_events_per_sec: f64,
+    pub emits: u64,
+    pub emit_failures: u64,
+    pub emits_suppressed: u64,
+    pub events_coalesced: u64,
+    pub events_dropped: u64,
+    pub gilrs_poll: TimingSnapshot,
+    pub evdev_poll: TimingSnapshot,
+    pub gilrs_lock_wait: TimingSnapshot,
+    pub states_lock_wait: TimingSnapshot,
+    pub combo_tracker_lock_wait: TimingSnapshot,
+    pub time_series_lock_wait: TimingSnapshot,
+    pub dispatch_queue_wait: TimingSnapshot,
+    pub state_publish: TimingSnapshot,
+    pub max_light_sends_in_flight: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RuntimeMetricsSnapshot {
+    /// Aggregates over the last 1, 10 and 60 complete seconds
+    pub windows: Vec<WindowSnapshot>,
+    pub light_sends_in_flight: u64,
+    /// Interval each polling thread is currently sleeping between passes
+    pub gilrs_poll_interval_ms: u64,
+    pub evdev_poll_interval_ms: u64,
+    /// Whether the polling threads have slowed down for lack of input
+    pub poll_idle: bool,
+    /// Idle with no controllers and the window hidden, polling at the slowest interval
+    pub poll_dormant: bool,
+}
+
+/// Polling loop and event pipeline metrics, kept as per-second buckets of atomics so
+/// recording from the hot path never allocates or blocks.
+///
+/// A bucket is cleared by whichever thread first records into it in a new second; a
+/// concurrent record landing during that clear can be lost, which is fine for metrics.
+pub struct RuntimeMetrics {
+    buckets: [Bucket; HISTORY_SECS],
+    light_sends_in_flight: AtomicU64,
+}
+
+impl Default for RuntimeMetrics {
+    fn default() -> Self {
+        Self {
+            buckets: std::array::from_fn(|_| Bucket::default()),
+            light_sends_in_flight: AtomicU64::new(0),
+        }
+    }
+}
+
+fn current_second() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs()
+}
+
+impl RuntimeMetrics {
+    fn bucket(&self) -> &Bucket {
+        let second = current_second();
+        let bucket = &self.buckets[second as usize % HISTORY_SECS];
+        if bucket.second.load(Ordering::Relaxed) != second
+            && bucket.second.swap(second, Ordering::Relaxed) != second
+        {
+            bucket.clear();
+        }
+        bucket
+    }
+
+    pub fn increment(&self, counter: Counter) {
+        self.bucket().counters[counter as usize].fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn record_emit(&self, succeeded: bool) {
+        let bucket = self.bucket();
+        bucket.counters[Counter::Emits as usize].fetch_add(1, Ordering::Relaxed);
+        if !succeeded {
+            bucket.counters[Counter::EmitFailures as usize].fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    pub fn record_timing(&self, timing: Timing, duration: Duration) {
+        let duration_ns = duration.as_nanos().min(u64::MAX as u128) as u64;
+        let bucket = self.bucket();
+        bucket.timing_count[timing as usize].fetch_add(1, Ordering::Relaxed);
+        bucket.timing_total_ns[timing as usize].fetch_add(duration_ns, Ordering::Relaxed);
+        bucket.timing_max_ns[timing as usize].fetch_max(duration_ns, Ordering::Relaxed);
+    }
+
+    /// Locks `mutex`, recording how long the acquisition waited.
+    pub fn lock<'a, T>(&self, timing: Timing, mutex: &'a Mutex<T>) -> MutexGuard<'a, T> {
+        let started = Instant::now();
+        let guard = mutex.lock_or_recover();
+        let waited = started.elapsed();
+        self.record_timing(timing, waited);
+        if waited >= LOCK_CONTENTION_THRESHOLD {
+            error_counters::errors().record(
+                ErrorCategory::LockContention,
+                format!("Waited {}ms for {:?}", waited.as_millis(), timing),
+            );
+        }
+        guard
+    }
+
+    pub fn light_send_started(&self) {
+        let in_flight = self.light_sends_in_flight.fetch_add(1, Ordering::Relaxed) + 1;
+        self.bucket().max_light_sends_in_flight.fetch_max(in_flight, Ordering::Relaxed);
+    }
+
+    pub fn light_send_finished(&self) {
+        self.light_sends_in_flight.fetch_sub(1, Ordering::Relaxed);
+    }
+
+    pub fn snapshot(&self) -> RuntimeMetricsSnapshot {
+        let now = current_second();
+        let cadence = poll_cadence::cadence();
+        RuntimeMetricsSnapshot {
+            windows: WINDOWS_SECS.iter().map(|&secs| self.window(now, secs)).collect(),
+            light_sends_in_flight: self.light_sends_in_flight.load(Ordering::Relaxed),
+            gilrs_poll_interval_ms: cadence.current_interval_ms(PollSource::Gilrs),
+            evdev_poll_interval_ms: cadence.current_interval_ms(PollSource::Evdev),
+            poll_idle: cadence.is_idle(),
+            poll_dormant: cadence.is_dormant(),
+        }
+    }
+
+    fn window(&self, now: u64, window_secs: u64) -> WindowSnapshot {
+        // Only complete seconds, so the 1s window isn't a partially filled bucket
+        let first = now.saturating_sub(window_secs);
+        let buckets: Vec<&Bucket> = self.buckets.iter()
+            .filter(|bucket| {
+                let second = bucket.second.load(Ordering::Relaxed);
+                second >= first && second < now
+            })
+            .collect();
+
+        let counter = |counter: Counter| -> u64 {
+            buckets.iter()
+                .map(|bucket| bucket.counters[counter as usize].load(Ordering::Relaxed))
+                .sum()
+        };
+        let timing = |timing: Timing| -> TimingSnapshot {
+            let index = timing as usize;
+            let count: u64 = buckets.iter().map(|b| b.timing_count[index].load(Ordering::Relaxed)).sum();
+            let total: u64 = buckets.iter().map(|b| b.timing_total_ns[index].load(Ordering::Relaxed)).sum();
+            TimingSnapshot {
+                count,
+                avg_ns: total.checked_div(count).unwrap_or(0),
+                max_ns: buckets.iter()
+                    .map(|b| b.timing_max_ns[index].load(Ordering::Relaxed))
+                    .max()
+                    .unwrap_or(0),
+            }
+        };
+
+        WindowSnapshot {
+            window_secs,
+            gilrs_events_per_sec: counter(Counter::GilrsEvents) as f64 / window_secs as f64,
+            evdev_events_per_sec: counter(Counter::EvdevEvents) as f64 / window_secs as f64,
+            emits: counter(Counter::Emits),
+            emit_failures: counter(Counter::EmitFailures),
+            emits_suppressed: counter(Counter::EmitsSuppressed),
+            events_coalesced: counter(Counter::EventsCoalesced),
+            events_dropped: counter(Counter::EventsDropped),
+            gilrs_poll: timing(Timing::GilrsPoll),
+            evdev_poll: timing(Timing::EvdevPoll),
+            gilrs_lock_wait: timing(Timing::GilrsLockWait),
+            states_lock_wait: timing(Timing::StatesLockWait),
+            combo_tracker_lock_wait: timing(Timing::ComboTrackerLockWait),
+            time_series_lock_wait: timing(Timing::TimeSeriesLockWait),
+            dispatch_queue_wait: timing(Timing::DispatchQueueWait),
+            state_publish: timing(Timing::StatePublish),
+            max_light_sends_in_flight: buckets.iter()
+                .map(|bucket| bucket.max_light_sends_in_flight.load(Ordering::Relaxed))
+                .max()
+                .unwrap_or(0),
+        }
+    }
+
+    /// Clears all history. The in-flight gauge is live state, so it's kept.
+    pub fn reset(&self) {
+        for bucket in &self.buckets {
+            bucket.second.store(0, Ordering::Relaxed);
+            bucket.clear();
+        }
+    }
+}
+
+static RUNTIME_METRICS: OnceLock<RuntimeMetrics> = OnceLock::new();
+
+/// Process-wide metrics shared by the polling thread, managers and commands.
+pub fn metrics() -> &'static RuntimeMetrics {
+    RUNTIME_METRICS.get_or_init(RuntimeMetrics::default)
+}
@@ -0,0 +1,37 @@
+//! Throwaway directories for tests that fake a sysfs tree or a Steam user folder.
+
+use std::path::{Path, PathBuf};
+
+/// A directory under the system temp dir, emptied on creation and removed on drop.
+pub struct ScratchDir(PathBuf);
+
+impl ScratchDir {
+    /// `label` only needs to be unique among the tests of this process.
+    pub fn new(label: &str) -> Self {
+        let root = std::env::temp_dir().join(format!("{}-{}", label, std::process::id()));
+        let _ = std::fs::remove_dir_all(&root);
+        std::fs::create_dir_all(&root).unwrap();
+        Self(root)
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.0
+    }
+
+    /// Creates `dir` holding one file per attribute, newline-terminated like sysfs, and
+    /// returns its path.
+    pub fn attributes(&self, dir: &str, attributes: &[(&str, &str)]) -> PathBuf {
+        let path = self.0.join(dir);
+        std::fs::create_dir_all(&path).unwrap();
+        for (name, value) in attributes {
+            std::fs::write(path.join(name), format!("{}\n", value)).unwrap();
+        }
+        path
+    }
+}
+
+impl Drop for ScratchDir {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.0);
+    }
+}
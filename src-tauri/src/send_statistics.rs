@@ -0,0 +1,154 @@
+use crate::memory_stats::{deque_bytes, BufferStats};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, VecDeque};
+
+/// Upper bounds of the round-trip buckets; a final bucket takes anything slower.
+pub const BUCKET_BOUNDS_MS: [u64; 10] = [5, 10, 25, 50, 100, 250, 500, 1000, 2500, 5000];
+const BUCKET_COUNT: usize = BUCKET_BOUNDS_MS.len() + 1;
+const SLOW_WINDOW_MS: u64 = 60_000;
+/// Sends needed in the window before a p95 means anything.
+const MIN_SLOW_SAMPLES: usize = 5;
+/// Round trips kept per endpoint for the p95, dropping the oldest past it.
+pub const MAX_RECENT_ROUND_TRIPS: usize = 10_000;
+pub const DEFAULT_SLOW_THRESHOLD_MS: u64 = 250;
+
+/// Payload of `light-server-slow`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LightServerSlow {
+    pub endpoint: String,
+    pub p95_ms: u64,
+    pub threshold_ms: u64,
+    pub samples: usize,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EndpointSendStatistics {
+    pub endpoint: String,
+    pub succeeded: u64,
+    pub failed: u64,
+    /// Counts per `BUCKET_BOUNDS_MS` bucket, plus one for slower sends
+    pub buckets: Vec<u64>,
+    /// `None` with fewer than a handful of sends in the last minute
+    pub p95_last_minute_ms: Option<u64>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SendStatisticsSnapshot {
+    pub bucket_bounds_ms: Vec<u64>,
+    /// `None` when slow warnings are off
+    pub slow_threshold_ms: Option<u64>,
+    pub endpoints: Vec<EndpointSendStatistics>,
+}
+
+#[derive(Default)]
+struct EndpointHistogram {
+    buckets: [u64; BUCKET_COUNT],
+    succeeded: u64,
+    failed: u64,
+    /// (sent at, round trip) in milliseconds, within the slow window
+    recent: VecDeque<(u64, u64)>,
+    last_warned_at: Option<u64>,
+}
+
+impl EndpointHistogram {
+    fn prune(&mut self, now_ms: u64) {
+        while self.recent.front().is_some_and(|&(at, _)| now_ms.saturating_sub(at) > SLOW_WINDOW_MS) {
+            self.recent.pop_front();
+        }
+    }
+
+    fn p95(&self) -> Option<u64> {
+        if self.recent.len() < MIN_SLOW_SAMPLES {
+            return None;
+        }
+        let mut round_trips: Vec<u64> = self.recent.iter().map(|&(_, rtt)| rtt).collect();
+        round_trips.sort_unstable();
+        let index = ((round_trips.len() as f64 * 0.95).ceil() as usize).saturating_sub(1);
+        round_trips.get(index).copied()
+    }
+}
+
+/// Round-trip histograms and success counts per light server endpoint.
+pub struct SendStatistics {
+    endpoints: BTreeMap<String, EndpointHistogram>,
+    slow_threshold_ms: Option<u64>,
+}
+
+impl Default for SendStatistics {
+    fn default() -> Self {
+        Self {
+            endpoints: BTreeMap::new(),
+            slow_threshold_ms: Some(DEFAULT_SLOW_THRESHOLD_MS),
+        }
+    }
+}
+
+impl SendStatistics {
+    /// Counts a send, returning a warning (at most once a minute per endpoint) when the
+    /// last minute's p95 is over the threshold.
+    pub fn record(&mut self, endpoint: &str, ok: bool, rtt_ms: u64, now_ms: u64) -> Option<LightServerSlow> {
+        let histogram = self.endpoints.entry(endpoint.to_string()).or_default();
+        let bucket = BUCKET_BOUNDS_MS.iter().position(|&bound| rtt_ms <= bound).unwrap_or(BUCKET_COUNT - 1);
+        histogram.buckets[bucket] += 1;
+        if ok {
+            histogram.succeeded += 1;
+        } else {
+            histogram.failed += 1;
+        }
+        if histogram.recent.len() >= MAX_RECENT_ROUND_TRIPS {
+            histogram.recent.pop_front();
+        }
+        histogram.recent.push_back((now_ms, rtt_ms));
+        histogram.prune(now_ms);
+
+        let threshold_ms = self.slow_threshold_ms?;
+        let p95_ms = histogram.p95().filter(|&p95| p95 > threshold_ms)?;
+        if histogram.last_warned_at.is_some_and(|at| now_ms.saturating_sub(at) < SLOW_WINDOW_MS) {
+            return None;
+        }
+        histogram.last_warned_at = Some(now_ms);
+        Some(LightServerSlow {
+            endpoint: endpoint.to_string(),
+            p95_ms,
+            threshold_ms,
+            samples: histogram.recent.len(),
+        })
+    }
+
+    pub fn buffer_stats(&self) -> BufferStats {
+        let len = self.endpoints.values().map(|histogram| histogram.recent.len()).sum();
+        let approx_bytes = self.endpoints.iter()
+            .map(|(endpoint, histogram)| endpoint.capacity() + std::mem::size_of::<EndpointHistogram>() + deque_bytes(&histogram.recent))
+            .sum();
+        BufferStats::new("send_round_trips", len, Some(MAX_RECENT_ROUND_TRIPS), self.endpoints.len(), approx_bytes)
+    }
+
+    pub fn set_slow_threshold(&mut self, threshold_ms: Option<u64>) {
+        self.slow_threshold_ms = threshold_ms;
+    }
+
+    pub fn snapshot(&mut self, now_ms: u64) -> SendStatisticsSnapshot {
+        let endpoints = self.endpoints.iter_mut()
+            .map(|(endpoint, histogram)| {
+                histogram.prune(now_ms);
+                EndpointSendStatistics {
+                    endpoint: endpoint.clone(),
+                    succeeded: histogram.succeeded,
+                    failed: histogram.failed,
+                    buckets: histogram.buckets.to_vec(),
+                    p95_last_minute_ms: histogram.p95(),
+                }
+            })
+            .collect();
+        SendStatisticsSnapshot {
+            bucket_bounds_ms: BUCKET_BOUNDS_MS.to_vec(),
+            slow_threshold_ms: self.slow_threshold_ms,
+            endpoints,
+        }
+    }
+
+    /// Clears the counts, e.g. between shows. The threshold is kept.
+    pub fn reset(&mut self) {
+        self.endpoints.clear();
+    }
+}
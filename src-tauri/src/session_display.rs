@@ -0,0 +1,74 @@
+use crate::app_state::AppState;
+use crate::background::MAIN_WINDOW;
+use crate::steam_deck::SessionKind;
+use crate::steam_shortcut;
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Manager};
+use tracing::{info, warn};
+
+/// Zoom factors `set_ui_scale` accepts.
+pub const UI_SCALE_RANGE: (f64, f64) = (0.5, 3.0);
+
+/// Window scale and kiosk mode in effect, and the session defaults they fall back to.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DisplayStatus {
+    pub session_kind: SessionKind,
+    pub ui_scale: f64,
+    /// False when the scale comes from the session default
+    pub ui_scale_overridden: bool,
+    pub kiosk_mode: bool,
+    pub kiosk_mode_overridden: bool,
+    /// Show the "Steam Input may hold the controller" warning
+    pub warn_steam_input: bool,
+}
+
+pub fn status(app: &AppHandle) -> DisplayStatus {
+    let state = app.state::<AppState>();
+    let settings = state.settings.get();
+    let session_kind = state.system_info.session_kind;
+    let defaults = session_kind.defaults();
+    DisplayStatus {
+        session_kind,
+        ui_scale: settings.ui_scale.unwrap_or(defaults.ui_scale),
+        ui_scale_overridden: settings.ui_scale.is_some(),
+        kiosk_mode: settings.kiosk_mode.unwrap_or(defaults.kiosk_mode || steam_shortcut::launched_with_kiosk_flag()),
+        kiosk_mode_overridden: settings.kiosk_mode.is_some(),
+        warn_steam_input: defaults.warn_steam_input,
+    }
+}
+
+pub fn validate_ui_scale(scale: f64) -> Result<(), String> {
+    let (min, max) = UI_SCALE_RANGE;
+    if !(min..=max).contains(&scale) {
+        return Err(format!("UI scale must be between {} and {}, got {}", min, max, scale));
+    }
+    Ok(())
+}
+
+/// Zooms the webview and switches the window between kiosk (fullscreen, undecorated, on
+/// top) and a normal desktop window.
+pub fn apply(app: &AppHandle) -> DisplayStatus {
+    let status = status(app);
+    let Some(window) = app.get_webview_window(MAIN_WINDOW) else {
+        return status;
+    };
+    if let Err(e) = window.set_zoom(status.ui_scale) {
+        warn!("Failed to set the UI scale to {}: {}", status.ui_scale, e);
+    }
+    let kiosk = status.kiosk_mode;
+    let result = window
+        .set_fullscreen(kiosk)
+        .and_then(|_| window.set_decorations(!kiosk))
+        .and_then(|_| window.set_resizable(!kiosk))
+        .and_then(|_| window.set_always_on_top(kiosk));
+    if let Err(e) = result {
+        warn!("Failed to {} kiosk mode: {}", if kiosk { "enter" } else { "leave" }, e);
+    }
+    info!(
+        "{}: UI scale {}, kiosk mode {}",
+        status.session_kind.label(),
+        status.ui_scale,
+        if kiosk { "on" } else { "off" }
+    );
+    status
+}
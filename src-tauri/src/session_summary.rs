@@ -0,0 +1,200 @@
+use crate::app_state::AppState;
+use crate::error_counters::{self, ErrorCategorySnapshot};
+use crate::panic_report::PanicReport;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tauri::{AppHandle, Manager};
+use tracing::{info, warn};
+
+/// Session files kept in the app data dir; older ones are deleted.
+const MAX_SESSION_FILES: usize = 20;
+/// Longest the shutdown path waits for the summary to be written.
+pub const SHUTDOWN_WRITE_TIMEOUT: Duration = Duration::from_secs(1);
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ControllerUsage {
+    pub controller_id: usize,
+    /// `None` for controllers only seen through their input
+    pub name: Option<String>,
+}
+
+/// Per-session input counts that no other statistics structure keeps.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SessionInputStats {
+    pub started_at: u64,
+    controllers: BTreeMap<usize, Option<String>>,
+    pub button_presses: u64,
+    pub disconnects: u64,
+    pub battery_min_percent: Option<u8>,
+    pub battery_max_percent: Option<u8>,
+}
+
+impl Default for SessionInputStats {
+    fn default() -> Self {
+        Self {
+            started_at: now_ms(),
+            controllers: BTreeMap::new(),
+            button_presses: 0,
+            disconnects: 0,
+            battery_min_percent: None,
+            battery_max_percent: None,
+        }
+    }
+}
+
+impl SessionInputStats {
+    pub fn controller_seen(&mut self, controller_id: usize) {
+        self.controllers.entry(controller_id).or_default();
+    }
+
+    pub fn controller_connected(&mut self, controller_id: usize, name: &str) {
+        self.controllers.insert(controller_id, Some(name.to_string()));
+    }
+
+    pub fn battery_sample(&mut self, percent: u8) {
+        self.battery_min_percent = Some(self.battery_min_percent.map_or(percent, |min| min.min(percent)));
+        self.battery_max_percent = Some(self.battery_max_percent.map_or(percent, |max| max.max(percent)));
+    }
+
+    pub fn controllers_used(&self) -> Vec<ControllerUsage> {
+        self.controllers.iter()
+            .map(|(&controller_id, name)| ControllerUsage { controller_id, name: name.clone() })
+            .collect()
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SessionSummary {
+    pub started_at: u64,
+    pub generated_at: u64,
+    pub duration_secs: u64,
+    pub controllers_used: Vec<ControllerUsage>,
+    pub button_presses: u64,
+    pub light_actions_sent: u64,
+    pub light_action_failures: u64,
+    pub disconnects: u64,
+    /// Lowest and highest controller battery seen, from gilrs's power info
+    pub battery_min_percent: Option<u8>,
+    pub battery_max_percent: Option<u8>,
+    pub poll_panics: u64,
+    pub last_panic: Option<PanicReport>,
+    pub poll_stalls: u64,
+    /// Error categories with at least one failure since the counters were last reset
+    pub warnings: Vec<ErrorCategorySnapshot>,
+}
+
+/// Builds the summary from the statistics the managers already keep.
+pub fn collect(app: &AppHandle) -> SessionSummary {
+    let state = app.state::<AppState>();
+    let input = state.gamepad_manager.session_input_stats();
+    let light_server = state.light_server.get_status();
+    let generated_at = now_ms();
+
+    SessionSummary {
+        started_at: input.started_at,
+        generated_at,
+        duration_secs: generated_at.saturating_sub(input.started_at) / 1000,
+        controllers_used: input.controllers_used(),
+        button_presses: input.button_presses,
+        light_actions_sent: light_server.requests_sent,
+        light_action_failures: light_server.requests_failed,
+        disconnects: input.disconnects,
+        battery_min_percent: input.battery_min_percent,
+        battery_max_percent: input.battery_max_percent,
+        poll_panics: state.panic_monitor.poll_panics(),
+        last_panic: state.panic_monitor.last_panic(),
+        poll_stalls: state.health_monitor.poll_stalls(),
+        warnings: error_counters::errors().snapshot().categories
+            .into_iter()
+            .filter(|category| category.count > 0)
+            .collect(),
+    }
+}
+
+/// Writes the session summary on a helper thread, giving up after
+/// `SHUTDOWN_WRITE_TIMEOUT` so a stuck lock or slow disk can't hold up exit.
+pub fn write_on_shutdown(app: &AppHandle) {
+    let dir = match app.path().app_data_dir() {
+        Ok(dir) => dir,
+        Err(e) => {
+            warn!("Not writing session summary: no app data dir: {}", e);
+            return;
+        }
+    };
+    let app = app.clone();
+    let (done_tx, done_rx) = mpsc::channel();
+    std::thread::spawn(move || {
+        let _ = done_tx.send(write_summary(&dir, &collect(&app)));
+    });
+    match done_rx.recv_timeout(SHUTDOWN_WRITE_TIMEOUT) {
+        Ok(Ok(path)) => info!("Wrote session summary to {}", path.display()),
+        Ok(Err(e)) => warn!("{}", e),
+        Err(_) => warn!("Gave up writing the session summary after {:?}", SHUTDOWN_WRITE_TIMEOUT),
+    }
+}
+
+fn write_summary(dir: &Path, summary: &SessionSummary) -> Result<PathBuf, String> {
+    std::fs::create_dir_all(dir)
+        .map_err(|e| format!("Failed to create {}: {}", dir.display(), e))?;
+    let path = dir.join(format!("session-{}.json", format_utc_minute(summary.started_at)));
+    let contents = serde_json::to_string_pretty(summary)
+        .map_err(|e| format!("Failed to serialize session summary: {}", e))?;
+    std::fs::write(&path, contents)
+        .map_err(|e| format!("Failed to write {}: {}", path.display(), e))?;
+    prune_sessions(dir);
+    Ok(path)
+}
+
+/// Deletes all but the newest `MAX_SESSION_FILES` summaries. Names sort by date.
+fn prune_sessions(dir: &Path) {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return;
+    };
+    let mut sessions: Vec<PathBuf> = entries
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| {
+            path.file_name()
+                .and_then(|name| name.to_str())
+                .is_some_and(|name| name.starts_with("session-") && name.ends_with(".json"))
+        })
+        .collect();
+    sessions.sort();
+    let excess = sessions.len().saturating_sub(MAX_SESSION_FILES);
+    for path in &sessions[..excess] {
+        if let Err(e) = std::fs::remove_file(path) {
+            warn!("Failed to remove old session summary {}: {}", path.display(), e);
+        }
+    }
+}
+
+/// `YYYYMMDD-HHMM` in UTC for a Unix timestamp in milliseconds.
+fn format_utc_minute(timestamp_ms: u64) -> String {
+    let secs = timestamp_ms / 1000;
+    let (year, month, day) = civil_from_days((secs / 86_400) as i64);
+    let minute_of_day = secs % 86_400 / 60;
+    format!("{:04}{:02}{:02}-{:02}{:02}", year, month, day, minute_of_day / 60, minute_of_day % 60)
+}
+
+/// Proleptic Gregorian date for days since 1970-01-01 (Howard Hinnant's algorithm).
+pub fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = (doy - (153 * mp + 2) / 5 + 1) as u32;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+    let year = yoe + era * 400 + i64::from(month <= 2);
+    (year, month, day)
+}
+
+fn now_ms() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_millis() as u64
+}
@@ -0,0 +1,155 @@
+use crate::dock::DockPolicy;
+use crate::event_script::EventScript;
+use crate::event_stream::EventStreamConfig;
+use crate::gamepad::ControllerLayout;
+use crate::health::PollSource;
+use crate::iio_motion::MotionConfig;
+use crate::key_emulation::KeyEmulationConfig;
+use crate::locking::MutexExt;
+use crate::midi_output::MidiConfig;
+use crate::opc_output::OpcConfig;
+use crate::payload_template::PayloadTemplate;
+use crate::input_pipeline::EventBusConfig;
+use crate::poll_cadence::PollCadenceConfig;
+use crate::processing_pause::PauseMode;
+use crate::touch_zones::TouchZone;
+use crate::virtual_pads::VirtualPadPolicy;
+use crate::window_chord::ShowWindowChord;
+use crate::window_focus::BackgroundInput;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::Mutex;
+use tracing::warn;
+
+/// User preferences persisted as JSON in the app config directory.
+///
+/// Every field has a default so settings files written by older versions keep loading.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Settings {
+    /// Manual layout choice per controller, for pads whose inferred layout is wrong. Keyed
+    /// by gilrs UUID, and also by evdev capability hash when the pad's node is known.
+    pub layout_overrides: HashMap<String, ControllerLayout>,
+    /// Light server used by backend-originated sends that don't name their own endpoint.
+    pub light_server_endpoint: Option<String>,
+    /// Hosts whose self-signed HTTPS certificates are accepted.
+    pub self_signed_hosts: Vec<String>,
+    /// Log filter chosen in the debug panel; `RUST_LOG` takes precedence when set.
+    pub log_filter: Option<String>,
+    /// How events are rendered for the light server's `/batch` endpoint.
+    pub payload_template: Option<PayloadTemplate>,
+    /// Stops emitting `gamepad-input` / `evdev-gamepad-input` next to `controller-event`.
+    pub disable_legacy_events: bool,
+    /// Kept so a restart while paused doesn't quietly start sending again.
+    pub processing_paused: Option<PauseMode>,
+    /// Adaptive polling thresholds.
+    pub poll_cadence: PollCadenceConfig,
+    /// Input queue size and what it drops when full.
+    pub event_bus: EventBusConfig,
+    /// Input backends that aren't polled.
+    pub disabled_backends: Vec<PollSource>,
+    /// Closing the window hides it and input keeps being forwarded, instead of exiting.
+    pub background_mode: bool,
+    pub show_window_chord: ShowWindowChord,
+    /// Leaves the polling and dispatch threads at normal scheduling priority.
+    pub disable_input_priority: bool,
+    /// Webview zoom; `None` follows the session (larger in game mode).
+    pub ui_scale: Option<f64>,
+    /// Fullscreen, undecorated and on top; `None` follows the session (on in game mode).
+    pub kiosk_mode: Option<bool>,
+    /// Motion source, and the IIO device and rate used when the evdev node is missing.
+    pub motion: MotionConfig,
+    /// Turns off the built-in controller's keyboard and mouse emulation while the window
+    /// is focused.
+    pub disable_lizard_mode: bool,
+    /// System battery percentages that emit `system-battery-low`; `None` is 20, 10 and 5.
+    pub battery_warning_thresholds: Option<Vec<u8>>,
+    /// Whether a Steam virtual pad or the controller feeding it sends input when both are
+    /// visible.
+    pub virtual_pad_policy: VirtualPadPolicy,
+    /// Which controller goes in the primary slot when docked and when handheld.
+    pub dock_policy: DockPolicy,
+    /// Tappable areas of the touch overlay, each pressing the button it's named after.
+    pub touch_zones: Vec<TouchZone>,
+    /// What's done about input while the window isn't focused and input is forwarded.
+    pub background_input: BackgroundInput,
+    /// Keyboard and mouse emulation rules and the chord that turns emulation off.
+    pub key_emulation: KeyEmulationConfig,
+    /// MIDI output port and the rules feeding it.
+    pub midi: MidiConfig,
+    /// The event stream to disk while one runs. Kept so the stream picks up again in the
+    /// same file after a restart, such as the one that installs an update.
+    pub event_stream: Option<EventStreamConfig>,
+    /// Scripts run on each controller event, off ones included.
+    pub event_scripts: Vec<EventScript>,
+    /// The OPC server, pixels and generator, reconnected at startup; `None` while off.
+    pub opc: Option<OpcConfig>,
+    /// Evdev nodes left alone by scans, by capability hash or, failing that, by name.
+    pub ignored_devices: Vec<String>,
+}
+
+pub struct SettingsManager {
+    path: PathBuf,
+    settings: Mutex<Settings>,
+}
+
+impl SettingsManager {
+    pub fn load(path: PathBuf) -> Self {
+        let settings = match std::fs::read_to_string(&path) {
+            Ok(contents) => serde_json::from_str(&contents).unwrap_or_else(|e| {
+                warn!("Ignoring unreadable settings file {}: {}", path.display(), e);
+                Settings::default()
+            }),
+            Err(_) => Settings::default(),
+        };
+
+        Self {
+            path,
+            settings: Mutex::new(settings),
+        }
+    }
+
+    pub fn get(&self) -> Settings {
+        self.settings.lock_or_recover().clone()
+    }
+
+    /// Applies `change` and writes the result to disk.
+    pub fn update<F: FnOnce(&mut Settings)>(&self, change: F) -> Result<(), String> {
+        let mut settings = self.settings.lock_or_recover();
+        change(&mut settings);
+        self.save(&settings)
+    }
+
+    /// Checks that settings can be saved, without touching the settings file itself.
+    pub fn check_writable(&self) -> Result<(), String> {
+        // Held so the probe can't race a save using the same temporary file
+        let _settings = self.settings.lock_or_recover();
+        if let Some(parent) = self.path.parent() {
+            std::fs::create_dir_all(parent)
+                .map_err(|e| format!("Failed to create settings directory: {}", e))?;
+        }
+        let tmp_path = self.path.with_extension("json.tmp");
+        std::fs::write(&tmp_path, "")
+            .map_err(|e| format!("Failed to write to {}: {}", tmp_path.display(), e))?;
+        std::fs::remove_file(&tmp_path)
+            .map_err(|e| format!("Failed to remove {}: {}", tmp_path.display(), e))
+    }
+
+    fn save(&self, settings: &Settings) -> Result<(), String> {
+        if let Some(parent) = self.path.parent() {
+            std::fs::create_dir_all(parent)
+                .map_err(|e| format!("Failed to create settings directory: {}", e))?;
+        }
+
+        let contents = serde_json::to_string_pretty(settings)
+            .map_err(|e| format!("Failed to serialize settings: {}", e))?;
+
+        // Write to a temporary file first so a crash mid-write can't truncate the settings
+        let tmp_path = self.path.with_extension("json.tmp");
+        std::fs::write(&tmp_path, contents)
+            .map_err(|e| format!("Failed to write settings: {}", e))?;
+        std::fs::rename(&tmp_path, &self.path)
+            .map_err(|e| format!("Failed to save settings: {}", e))
+    }
+}
@@ -0,0 +1,706 @@
+use crate::emitter::EventEmitter;
+use crate::event_script::{self, ScriptAction};
+use crate::locking::MutexExt;
+use serde::de::Error as _;
+use serde::{Deserialize, Deserializer, Serialize};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Condvar, Mutex, PoisonError};
+use std::time::{Duration, Instant};
+use tauri::AppHandle;
+use tracing::{debug, info, warn};
+
+/// The show file version this app reads.
+pub const SHOW_VERSION: u32 = 1;
+pub const MAX_CUES: usize = 10_000;
+/// Emitted with a `ShowProgress` while a show plays, and when it starts, pauses, seeks
+/// or ends.
+pub const PROGRESS_EVENT: &str = "show-progress";
+/// How far the d-pad's left and right move the playhead; up and down move it a tenth of
+/// this.
+pub const NUDGE_MS: u64 = 1_000;
+
+const MAX_FILE_BYTES: u64 = 4 * 1024 * 1024;
+const PROGRESS_INTERVAL: Duration = Duration::from_millis(100);
+/// Sleeping is only trusted to within this; the rest of the wait yields instead
+const SPIN_MARGIN: Duration = Duration::from_millis(2);
+
+/// `m:ss`, `h:mm:ss` or plain seconds, each with up to three decimal places, as
+/// milliseconds.
+pub fn parse_timecode(text: &str) -> Result<u64, String> {
+    let invalid = || format!("'{}' isn't a time; write it as m:ss.fff, like 1:12.5", text);
+    let parts: Vec<&str> = text.trim().split(':').collect();
+    if parts.len() > 3 {
+        return Err(invalid());
+    }
+    let (whole, fraction) = match parts[parts.len() - 1].split_once('.') {
+        Some((whole, fraction)) => (whole, fraction),
+        None => (parts[parts.len() - 1], ""),
+    };
+    if fraction.len() > 3 || !fraction.chars().all(|c| c.is_ascii_digit()) {
+        return Err(invalid());
+    }
+    let millis = format!("{:0<3}", fraction).parse::<u64>().map_err(|_| invalid())?;
+    let mut seconds = 0u64;
+    for (index, part) in parts[..parts.len() - 1].iter().chain([&whole]).enumerate() {
+        let value = part.parse::<u64>().map_err(|_| invalid())?;
+        if index > 0 && value >= 60 {
+            return Err(invalid());
+        }
+        seconds = seconds.checked_mul(60).and_then(|s| s.checked_add(value)).ok_or_else(invalid)?;
+    }
+    seconds.checked_mul(1000).and_then(|ms| ms.checked_add(millis)).ok_or_else(invalid)
+}
+
+/// `m:ss.fff`, the form error messages and the timeline show times in.
+pub fn format_timecode(ms: u64) -> String {
+    format!("{}:{:02}.{:03}", ms / 60_000, ms / 1000 % 60, ms % 1000)
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum RawTime {
+    Millis(u64),
+    Text(String),
+}
+
+fn timecode<'de, D: Deserializer<'de>>(deserializer: D) -> Result<u64, D::Error> {
+    match RawTime::deserialize(deserializer)? {
+        RawTime::Millis(ms) => Ok(ms),
+        RawTime::Text(text) => parse_timecode(&text).map_err(D::Error::custom),
+    }
+}
+
+fn optional_timecode<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Option<u64>, D::Error> {
+    match Option::<RawTime>::deserialize(deserializer)? {
+        None => Ok(None),
+        Some(RawTime::Millis(ms)) => Ok(Some(ms)),
+        Some(RawTime::Text(text)) => parse_timecode(&text).map(Some).map_err(D::Error::custom),
+    }
+}
+
+/// One moment in a show. Times are milliseconds, or strings like `"1:12.5"`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ShowCue {
+    #[serde(alias = "at", deserialize_with = "timecode")]
+    pub at_ms: u64,
+    pub label: String,
+    /// Sent to the light server when the cue is reached, like a script's `send`
+    pub payload: serde_json::Value,
+    /// Overrides the light server endpoint for this cue
+    #[serde(default)]
+    pub endpoint: Option<String>,
+    /// For cues that last, like a strobe; no other cue may start until it ends
+    #[serde(default, alias = "duration", deserialize_with = "optional_timecode")]
+    pub duration_ms: Option<u64>,
+    /// Sent when a lasting cue ends
+    #[serde(default)]
+    pub end_payload: Option<serde_json::Value>,
+}
+
+impl ShowCue {
+    pub fn end_ms(&self) -> u64 {
+        self.at_ms + self.duration_ms.unwrap_or(0)
+    }
+
+    fn describe(&self, index: usize) -> String {
+        format!("Cue {} '{}' at {}", index + 1, self.label, format_timecode(self.at_ms))
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ShowFile {
+    pub version: u32,
+    #[serde(default)]
+    pub name: Option<String>,
+    pub cues: Vec<ShowCue>,
+}
+
+impl ShowFile {
+    /// Cues must be in time order, and none may start while a lasting one runs.
+    pub fn validate(&self) -> Result<(), String> {
+        if self.version != SHOW_VERSION {
+            return Err(format!("Show file version {} isn't supported; this app reads version {}", self.version, SHOW_VERSION));
+        }
+        if self.cues.is_empty() {
+            return Err("The show has no cues".to_string());
+        }
+        if self.cues.len() > MAX_CUES {
+            return Err(format!("At most {} cues per show, got {}", MAX_CUES, self.cues.len()));
+        }
+        for (index, cue) in self.cues.iter().enumerate() {
+            if cue.label.trim().is_empty() {
+                return Err(format!("Cue {} at {} needs a label", index + 1, format_timecode(cue.at_ms)));
+            }
+            if cue.duration_ms == Some(0) {
+                return Err(format!("{}: a duration must be longer than 0", cue.describe(index)));
+            }
+            if cue.end_payload.is_some() && cue.duration_ms.is_none() {
+                return Err(format!("{} has an end payload but no duration", cue.describe(index)));
+            }
+            if cue.at_ms.checked_add(cue.duration_ms.unwrap_or(0)).is_none() {
+                return Err(format!("{} lasts too long", cue.describe(index)));
+            }
+        }
+        for (index, pair) in self.cues.windows(2).enumerate() {
+            let (before, cue) = (&pair[0], &pair[1]);
+            if cue.at_ms < before.at_ms {
+                return Err(format!(
+                    "{} comes before cue {} '{}' at {}; cues must be in time order",
+                    cue.describe(index + 1),
+                    index + 1,
+                    before.label,
+                    format_timecode(before.at_ms)
+                ));
+            }
+            if cue.at_ms < before.end_ms() {
+                return Err(format!(
+                    "{} starts while cue {} '{}' is still running ({} to {})",
+                    cue.describe(index + 1),
+                    index + 1,
+                    before.label,
+                    format_timecode(before.at_ms),
+                    format_timecode(before.end_ms())
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    /// When the last cue ends.
+    pub fn duration_ms(&self) -> u64 {
+        self.cues.iter().map(ShowCue::end_ms).max().unwrap_or(0)
+    }
+}
+
+#[derive(Deserialize)]
+struct ShowHeader {
+    version: Option<u32>,
+}
+
+/// Reads and validates a show file's contents. The version is checked before the rest,
+/// so a newer file says so rather than failing on fields it has changed.
+pub fn parse_show(text: &str) -> Result<ShowFile, String> {
+    let header: ShowHeader = serde_json::from_str(text).map_err(|e| format!("Show file isn't valid JSON: {}", e))?;
+    match header.version {
+        None => return Err("Show file has no version; add \"version\": 1".to_string()),
+        Some(version) if version != SHOW_VERSION => {
+            return Err(format!("Show file version {} isn't supported; this app reads version {}", version, SHOW_VERSION));
+        }
+        Some(_) => {}
+    }
+    let show: ShowFile = serde_json::from_str(text).map_err(|e| format!("Show file isn't valid: {}", e))?;
+    show.validate()?;
+    Ok(show)
+}
+
+/// A cue being reached, or a lasting cue ending, by index.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CueStep {
+    Start(usize),
+    End(usize),
+}
+
+/// Which cues are due as the playhead moves. Holds no clock, so tests can drive it alone.
+pub struct ShowPlayer {
+    show: ShowFile,
+    position_ms: u64,
+    /// The first cue not yet reached
+    next_cue: usize,
+    /// The lasting cue started whose end hasn't been sent
+    running: Option<usize>,
+}
+
+impl ShowPlayer {
+    pub fn new(show: ShowFile) -> Self {
+        Self { show, position_ms: 0, next_cue: 0, running: None }
+    }
+
+    pub fn show(&self) -> &ShowFile {
+        &self.show
+    }
+
+    pub fn next_cue(&self) -> Option<usize> {
+        (self.next_cue < self.show.cues.len()).then_some(self.next_cue)
+    }
+
+    pub fn running_cue(&self) -> Option<usize> {
+        self.running
+    }
+
+    /// Whether every cue has been reached and ended.
+    pub fn finished(&self) -> bool {
+        self.next_cue >= self.show.cues.len() && self.running.is_none()
+    }
+
+    /// When the next start or end is due.
+    pub fn next_due_ms(&self) -> Option<u64> {
+        let end = self.running.map(|index| self.show.cues[index].end_ms());
+        let start = self.show.cues.get(self.next_cue).map(|cue| cue.at_ms);
+        end.into_iter().chain(start).min()
+    }
+
+    /// Moves the playhead forward to `to_ms`, returning the starts and ends passed, in
+    /// order.
+    pub fn advance(&mut self, to_ms: u64) -> Vec<CueStep> {
+        let mut steps = Vec::new();
+        loop {
+            let end = self.running.map(|index| (index, self.show.cues[index].end_ms())).filter(|(_, end)| *end <= to_ms);
+            let start = self.show.cues.get(self.next_cue).map(|cue| cue.at_ms).filter(|at| *at <= to_ms);
+            match (end, start) {
+                // An end goes before a start at the same moment
+                (Some((index, end)), start) if !matches!(start, Some(start) if start < end) => {
+                    steps.push(CueStep::End(index));
+                    self.running = None;
+                }
+                (_, Some(_)) => {
+                    let index = self.next_cue;
+                    steps.push(CueStep::Start(index));
+                    if self.show.cues[index].duration_ms.is_some() {
+                        self.running = Some(index);
+                    }
+                    self.next_cue += 1;
+                }
+                _ => break,
+            }
+        }
+        self.position_ms = self.position_ms.max(to_ms);
+        steps
+    }
+
+    /// Moves the playhead anywhere without reaching the cues in between. A lasting cue
+    /// left behind is ended; one landed in the middle of isn't started.
+    pub fn seek(&mut self, to_ms: u64) -> Vec<CueStep> {
+        let mut steps = Vec::new();
+        if let Some(index) = self.running {
+            let cue = &self.show.cues[index];
+            if to_ms < cue.at_ms || to_ms >= cue.end_ms() {
+                steps.push(CueStep::End(index));
+                self.running = None;
+            }
+        }
+        self.next_cue = self.show.cues.partition_point(|cue| cue.at_ms < to_ms);
+        self.position_ms = to_ms;
+        steps
+    }
+}
+
+/// The playhead, emitted as `show-progress`.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ShowProgress {
+    pub playing: bool,
+    pub position_ms: u64,
+    pub duration_ms: u64,
+    pub next_cue: Option<usize>,
+    pub running_cue: Option<usize>,
+    pub finished: bool,
+}
+
+/// Answer to the show commands: the cues and where playback is.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ShowStatus {
+    pub loaded: bool,
+    pub path: Option<String>,
+    pub name: Option<String>,
+    pub cues: Vec<ShowCue>,
+    pub progress: ShowProgress,
+    /// Cue starts and ends sent since the show was loaded
+    pub dispatched: u64,
+    /// The furthest behind its time a cue has been sent
+    pub max_late_ms: u64,
+}
+
+#[derive(Default)]
+struct Playback {
+    path: Option<String>,
+    player: Option<ShowPlayer>,
+    /// Set while playing, to when the playhead was at `anchor_ms`
+    playing_since: Option<Instant>,
+    anchor_ms: u64,
+    /// Bumped by each start, pause and load, which ends the thread started before
+    generation: u64,
+    dispatched: u64,
+    max_late_ms: u64,
+}
+
+impl Playback {
+    fn position_ms(&self) -> u64 {
+        match self.playing_since {
+            Some(since) => self.anchor_ms + since.elapsed().as_millis() as u64,
+            None => self.anchor_ms,
+        }
+    }
+
+    fn progress(&self) -> ShowProgress {
+        let Some(player) = self.player.as_ref() else {
+            return ShowProgress::default();
+        };
+        let duration_ms = player.show().duration_ms();
+        ShowProgress {
+            playing: self.playing_since.is_some(),
+            position_ms: self.position_ms().min(duration_ms),
+            duration_ms,
+            next_cue: player.next_cue(),
+            running_cue: player.running_cue(),
+            finished: player.finished(),
+        }
+    }
+
+    fn status(&self) -> ShowStatus {
+        ShowStatus {
+            loaded: self.player.is_some(),
+            path: self.path.clone(),
+            name: self.player.as_ref().and_then(|player| player.show().name.clone()),
+            cues: self.player.as_ref().map(|player| player.show().cues.clone()).unwrap_or_default(),
+            progress: self.progress(),
+            dispatched: self.dispatched,
+            max_late_ms: self.max_late_ms,
+        }
+    }
+
+    /// The cues `steps` name, to send once the lock is released.
+    fn take_dispatches(&mut self, steps: &[CueStep], position_ms: u64) -> Vec<(CueStep, ShowCue)> {
+        let Some(player) = self.player.as_ref() else {
+            return Vec::new();
+        };
+        let dispatches: Vec<(CueStep, ShowCue)> = steps.iter().map(|step| {
+            let (CueStep::Start(index) | CueStep::End(index)) = *step;
+            (*step, player.show().cues[index].clone())
+        }).collect();
+        for (step, cue) in &dispatches {
+            let due = match step {
+                CueStep::Start(_) => cue.at_ms,
+                CueStep::End(_) => cue.end_ms(),
+            };
+            self.max_late_ms = self.max_late_ms.max(position_ms.saturating_sub(due));
+        }
+        self.dispatched += dispatches.len() as u64;
+        dispatches
+    }
+}
+
+/// The loaded show and its playback, with the controller as transport: A starts, B
+/// pauses and the d-pad nudges the playhead. Cues go out through `app`'s pipeline.
+#[derive(Default)]
+pub struct ShowPlayback {
+    /// Checked before locking, so input costs nothing while no show is loaded
+    loaded: AtomicBool,
+    playback: Mutex<Playback>,
+    /// Wakes the playback thread when the playhead is moved or stopped
+    changed: Condvar,
+}
+
+impl ShowPlayback {
+    /// Reads and validates the show at `path`, replacing any loaded one, paused at the
+    /// start.
+    pub fn load(&self, app: &AppHandle, path: &str) -> Result<ShowStatus, String> {
+        let size = std::fs::metadata(path).map_err(|e| format!("Couldn't read show file {}: {}", path, e))?.len();
+        if size > MAX_FILE_BYTES {
+            return Err(format!("Show file {} is {} bytes; at most {} are read", path, size, MAX_FILE_BYTES));
+        }
+        let text = std::fs::read_to_string(path).map_err(|e| format!("Couldn't read show file {}: {}", path, e))?;
+        let show = parse_show(&text)?;
+        info!("Loaded show {} with {} cues, {} long", path, show.cues.len(), format_timecode(show.duration_ms()));
+        let (ending, status) = {
+            let mut playback = self.playback.lock_or_recover();
+            let ending = playback.player.as_mut().map(|player| player.seek(u64::MAX)).unwrap_or_default();
+            let dispatches = playback.take_dispatches(&ending, 0);
+            playback.generation += 1;
+            playback.path = Some(path.to_string());
+            playback.player = Some(ShowPlayer::new(show));
+            playback.playing_since = None;
+            playback.anchor_ms = 0;
+            playback.dispatched = 0;
+            playback.max_late_ms = 0;
+            (dispatches, playback.status())
+        };
+        self.changed.notify_all();
+        self.loaded.store(true, Ordering::Relaxed);
+        dispatch(app, ending);
+        emit_progress(app, status.progress.clone());
+        Ok(status)
+    }
+
+    /// Plays from the playhead, or from the start once the show has finished.
+    pub fn start(self: &Arc<Self>, app: &AppHandle) -> Result<ShowStatus, String> {
+        let mut playback = self.playback.lock_or_recover();
+        let finished = playback.player.as_ref().ok_or("Load a show first")?.finished();
+        if playback.playing_since.is_some() {
+            return Ok(playback.status());
+        }
+        if finished {
+            if let Some(player) = playback.player.as_mut() {
+                player.seek(0);
+            }
+            playback.anchor_ms = 0;
+        }
+        playback.generation += 1;
+        playback.playing_since = Some(Instant::now());
+        let generation = playback.generation;
+        let status = playback.status();
+        drop(playback);
+        let shows = self.clone();
+        let app = app.clone();
+        std::thread::Builder::new()
+            .name("show-playback".to_string())
+            .spawn(move || play(&shows, generation, app))
+            .map_err(|e| format!("Failed to start the show: {}", e))?;
+        debug!("Show started at {}", format_timecode(status.progress.position_ms));
+        Ok(status)
+    }
+
+    /// Stops the playhead where it is. A lasting cue that's running carries on until it's
+    /// played past.
+    pub fn pause(&self, app: &AppHandle) -> Result<ShowStatus, String> {
+        let status = {
+            let mut playback = self.playback.lock_or_recover();
+            if playback.player.is_none() {
+                return Err("No show is loaded".to_string());
+            }
+            if playback.playing_since.is_some() {
+                playback.anchor_ms = playback.position_ms();
+                playback.playing_since = None;
+                playback.generation += 1;
+            }
+            playback.status()
+        };
+        self.changed.notify_all();
+        emit_progress(app, status.progress.clone());
+        Ok(status)
+    }
+
+    /// Moves the playhead to `position_ms`, playing on from there if it was playing.
+    pub fn seek(&self, app: &AppHandle, position_ms: u64) -> Result<ShowStatus, String> {
+        let (dispatches, status) = {
+            let mut playback = self.playback.lock_or_recover();
+            let player = playback.player.as_mut().ok_or("No show is loaded")?;
+            let position_ms = position_ms.min(player.show().duration_ms());
+            let steps = player.seek(position_ms);
+            let dispatches = playback.take_dispatches(&steps, position_ms);
+            playback.anchor_ms = position_ms;
+            if playback.playing_since.is_some() {
+                playback.playing_since = Some(Instant::now());
+            }
+            (dispatches, playback.status())
+        };
+        self.changed.notify_all();
+        dispatch(app, dispatches);
+        emit_progress(app, status.progress.clone());
+        Ok(status)
+    }
+
+    /// Moves the playhead by `delta_ms`, stopping at either end of the show.
+    pub fn nudge(&self, app: &AppHandle, delta_ms: i64) -> Result<ShowStatus, String> {
+        let position_ms = self.playback.lock_or_recover().position_ms();
+        self.seek(app, position_ms.saturating_add_signed(delta_ms))
+    }
+
+    /// Transport from any controller, while a show is loaded.
+    pub fn button_changed(self: &Arc<Self>, app: &AppHandle, button: &str, pressed: bool) {
+        if !pressed || !self.loaded.load(Ordering::Relaxed) {
+            return;
+        }
+        let nudge = NUDGE_MS as i64;
+        let done = match button {
+            "South" => self.start(app),
+            "East" => self.pause(app),
+            "DPadLeft" => self.nudge(app, -nudge),
+            "DPadRight" => self.nudge(app, nudge),
+            "DPadDown" => self.nudge(app, -nudge / 10),
+            "DPadUp" => self.nudge(app, nudge / 10),
+            _ => return,
+        };
+        if let Err(e) = done {
+            debug!("Show transport {} ignored: {}", button, e);
+        }
+    }
+
+    pub fn status(&self) -> ShowStatus {
+        self.playback.lock_or_recover().status()
+    }
+}
+
+fn dispatch(app: &AppHandle, dispatches: Vec<(CueStep, ShowCue)>) {
+    for (step, cue) in dispatches {
+        let payload = match step {
+            CueStep::Start(_) => Some(cue.payload),
+            CueStep::End(_) => cue.end_payload,
+        };
+        if let Some(payload) = payload {
+            debug!("Show cue '{}' {}", cue.label, if matches!(step, CueStep::Start(_)) { "reached" } else { "ended" });
+            event_script::execute(app, "Show", None, ScriptAction::Send { payload, endpoint: cue.endpoint });
+        }
+    }
+}
+
+fn emit_progress(app: &AppHandle, progress: ShowProgress) {
+    app.emit_event(PROGRESS_EVENT, progress).ok();
+}
+
+/// The playback thread: sends each cue as its time comes and reports the playhead, until
+/// the show ends, pauses or is replaced.
+fn play(shows: &ShowPlayback, generation: u64, app: AppHandle) {
+    let mut progress_sent: Option<Instant> = None;
+    loop {
+        let mut playback = shows.playback.lock_or_recover();
+        if playback.generation != generation || playback.playing_since.is_none() {
+            return;
+        }
+        let position_ms = playback.position_ms();
+        let Some(player) = playback.player.as_mut() else {
+            return;
+        };
+        let steps = player.advance(position_ms);
+        let next_due = player.next_due_ms();
+        let finished = player.finished();
+        let dispatches = playback.take_dispatches(&steps, position_ms);
+        if finished {
+            playback.anchor_ms = position_ms;
+            playback.playing_since = None;
+            info!("Show finished after {} cue start(s) and end(s)", playback.dispatched);
+        }
+        let report = finished || !steps.is_empty() || progress_sent.is_none_or(|sent| sent.elapsed() >= PROGRESS_INTERVAL);
+        let progress = report.then(|| playback.progress());
+        drop(playback);
+
+        dispatch(&app, dispatches);
+        if let Some(progress) = progress {
+            if let Err(e) = app.emit_event(PROGRESS_EVENT, progress) {
+                warn!("Failed to emit show progress: {}", e);
+            }
+            progress_sent = Some(Instant::now());
+        }
+        if finished {
+            return;
+        }
+
+        let until_due = next_due.map_or(PROGRESS_INTERVAL, |due| Duration::from_millis(due.saturating_sub(position_ms)));
+        let wait = until_due.min(PROGRESS_INTERVAL);
+        if wait > SPIN_MARGIN {
+            let playback = shows.playback.lock_or_recover();
+            if playback.generation == generation {
+                let _ = shows.changed.wait_timeout(playback, wait - SPIN_MARGIN).unwrap_or_else(PoisonError::into_inner);
+            }
+        } else {
+            std::thread::yield_now();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn show(cues: serde_json::Value) -> String {
+        json!({ "version": 1, "name": "Rehearsal", "cues": cues }).to_string()
+    }
+
+    fn player() -> ShowPlayer {
+        ShowPlayer::new(parse_show(&show(json!([
+            { "at": "0:00", "label": "blackout", "payload": { "scene": 0 } },
+            { "at": "0:04.5", "label": "preset 2", "payload": { "scene": 2 } },
+            { "at": "1:12", "label": "strobe", "payload": { "strobe": true }, "duration": "0:02", "end_payload": { "strobe": false } },
+            { "at": 74_000, "label": "preset 3", "payload": { "scene": 3 } },
+        ])))
+        .unwrap())
+    }
+
+    #[test]
+    fn timecodes_read_as_milliseconds() {
+        assert_eq!(parse_timecode("0:04.5"), Ok(4_500));
+        assert_eq!(parse_timecode("1:12"), Ok(72_000));
+        assert_eq!(parse_timecode("1:02:03.25"), Ok(3_723_250));
+        assert_eq!(parse_timecode("90.125"), Ok(90_125));
+        assert!(parse_timecode("1:75").is_err());
+        assert!(parse_timecode("0:01.2345").is_err());
+        assert!(parse_timecode("soon").is_err());
+        assert_eq!(format_timecode(72_500), "1:12.500");
+    }
+
+    #[test]
+    fn cues_read_times_in_either_form() {
+        let player = player();
+        let cues = &player.show().cues;
+        assert_eq!(cues.iter().map(|cue| cue.at_ms).collect::<Vec<_>>(), vec![0, 4_500, 72_000, 74_000]);
+        assert_eq!(cues[2].duration_ms, Some(2_000));
+        assert_eq!(player.show().duration_ms(), 74_000);
+    }
+
+    #[test]
+    fn versions_other_than_1_are_refused_first() {
+        let error = parse_show(&json!({ "version": 2, "cues": "changed" }).to_string()).unwrap_err();
+        assert!(error.contains("version 2"), "{}", error);
+        assert!(parse_show(&json!({ "cues": [] }).to_string()).unwrap_err().contains("no version"));
+        assert!(parse_show("{").unwrap_err().contains("JSON"));
+    }
+
+    #[test]
+    fn out_of_order_cues_are_refused() {
+        let error = parse_show(&show(json!([
+            { "at": "0:05", "label": "blackout", "payload": {} },
+            { "at": "0:04.5", "label": "preset 2", "payload": {} },
+        ])))
+        .unwrap_err();
+        assert_eq!(error, "Cue 2 'preset 2' at 0:04.500 comes before cue 1 'blackout' at 0:05.000; cues must be in time order");
+    }
+
+    #[test]
+    fn overlapping_cues_are_refused() {
+        let error = parse_show(&show(json!([
+            { "at": "1:12", "label": "strobe", "payload": {}, "duration": 2_000 },
+            { "at": "1:13", "label": "preset 3", "payload": {} },
+        ])))
+        .unwrap_err();
+        assert_eq!(error, "Cue 2 'preset 3' at 1:13.000 starts while cue 1 'strobe' is still running (1:12.000 to 1:14.000)");
+        let back_to_back = show(json!([
+            { "at": "1:12", "label": "strobe", "payload": {}, "duration": 2_000 },
+            { "at": "1:14", "label": "preset 3", "payload": {} },
+        ]));
+        assert!(parse_show(&back_to_back).is_ok());
+    }
+
+    #[test]
+    fn malformed_cues_are_refused() {
+        assert!(parse_show(&show(json!([]))).is_err());
+        assert!(parse_show(&show(json!([{ "at": "0:01", "label": " ", "payload": {} }]))).is_err());
+        assert!(parse_show(&show(json!([{ "at": "0:01", "label": "a", "payload": {}, "duration": 0 }]))).is_err());
+        assert!(parse_show(&show(json!([{ "at": "0:01", "label": "a", "payload": {}, "end_payload": {} }]))).is_err());
+        assert!(parse_show(&show(json!([{ "at": "later", "label": "a", "payload": {} }]))).is_err());
+    }
+
+    #[test]
+    fn cues_are_sent_as_the_playhead_reaches_them() {
+        let mut player = player();
+        assert_eq!(player.advance(0), vec![CueStep::Start(0)]);
+        assert_eq!(player.next_due_ms(), Some(4_500));
+        assert_eq!(player.advance(4_499), Vec::<CueStep>::new());
+        assert_eq!(player.advance(4_500), vec![CueStep::Start(1)]);
+        assert_eq!(player.advance(73_000), vec![CueStep::Start(2)]);
+        assert_eq!(player.running_cue(), Some(2));
+        assert_eq!(player.next_due_ms(), Some(74_000));
+        assert_eq!(player.advance(74_000), vec![CueStep::End(2), CueStep::Start(3)]);
+        assert!(player.finished());
+    }
+
+    #[test]
+    fn a_late_tick_sends_everything_it_passed_in_order() {
+        let mut player = player();
+        assert_eq!(
+            player.advance(80_000),
+            vec![CueStep::Start(0), CueStep::Start(1), CueStep::Start(2), CueStep::End(2), CueStep::Start(3)]
+        );
+    }
+
+    #[test]
+    fn seeking_skips_cues_and_ends_the_one_left_behind() {
+        let mut player = player();
+        assert_eq!(player.seek(60_000), Vec::<CueStep>::new());
+        assert_eq!(player.next_cue(), Some(2));
+        assert_eq!(player.advance(72_500), vec![CueStep::Start(2)]);
+        assert_eq!(player.seek(73_000), Vec::<CueStep>::new());
+        assert_eq!(player.seek(1_000), vec![CueStep::End(2)]);
+        assert_eq!(player.next_cue(), Some(1));
+        assert_eq!(player.seek(4_500), Vec::<CueStep>::new());
+        assert_eq!(player.advance(4_500), vec![CueStep::Start(1)]);
+    }
+}
@@ -0,0 +1,84 @@
+use crate::health::PollSource;
+use crate::locking::MutexExt;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+use std::thread::JoinHandle;
+use std::time::{Duration, Instant};
+
+/// Longest exit waits for each polling thread to finish its pass.
+pub const POLL_THREAD_JOIN_TIMEOUT: Duration = Duration::from_secs(1);
+/// Longest exit waits for managers to flush what they have pending.
+pub const FLUSH_TIMEOUT: Duration = Duration::from_secs(1);
+
+#[derive(Default)]
+struct PollThread {
+    stop: AtomicBool,
+    handle: Mutex<Option<JoinHandle<()>>>,
+}
+
+/// Tells the background threads to stop, and owns the polling threads so exit can wait
+/// for them rather than killing them mid-pass. Each pipeline has its own stop flag and
+/// handle, so one that hangs doesn't hold up the other.
+#[derive(Default)]
+pub struct ShutdownSignal {
+    requested: AtomicBool,
+    poll_threads: [PollThread; 2],
+}
+
+impl ShutdownSignal {
+    fn poll_thread(&self, source: PollSource) -> &PollThread {
+        &self.poll_threads[source.index()]
+    }
+
+    /// Returns true the first time, so callers can log once.
+    pub fn request(&self) -> bool {
+        !self.requested.swap(true, Ordering::SeqCst)
+    }
+
+    pub fn is_requested(&self) -> bool {
+        self.requested.load(Ordering::SeqCst)
+    }
+
+    /// Whether the `source` polling thread should keep going.
+    pub fn should_poll(&self, source: PollSource) -> bool {
+        !self.is_requested() && !self.poll_thread(source).stop.load(Ordering::SeqCst)
+    }
+
+    pub fn set_poll_thread(&self, source: PollSource, handle: JoinHandle<()>) {
+        *self.poll_thread(source).handle.lock_or_recover() = Some(handle);
+    }
+
+    pub fn is_poll_thread_running(&self, source: PollSource) -> bool {
+        self.poll_thread(source).handle.lock_or_recover().as_ref().is_some_and(|handle| !handle.is_finished())
+    }
+
+    /// Starts a new `source` polling thread with `spawn` if the current one has exited,
+    /// unless it's being stopped. Returns whether it did.
+    pub fn restart_poll_thread_if_finished<F: FnOnce() -> JoinHandle<()>>(&self, source: PollSource, spawn: F) -> bool {
+        let mut handle = self.poll_thread(source).handle.lock_or_recover();
+        if !self.should_poll(source) || !handle.as_ref().is_some_and(JoinHandle::is_finished) {
+            return false;
+        }
+        *handle = Some(spawn());
+        true
+    }
+
+    /// Stops the `source` polling thread and waits up to `timeout` for it to exit.
+    /// Returns false if it's still running.
+    pub fn stop_poll_thread(&self, source: PollSource, timeout: Duration) -> bool {
+        let poll_thread = self.poll_thread(source);
+        poll_thread.stop.store(true, Ordering::SeqCst);
+        let Some(handle) = poll_thread.handle.lock_or_recover().take() else {
+            return true;
+        };
+        let deadline = Instant::now() + timeout;
+        while !handle.is_finished() {
+            if Instant::now() >= deadline {
+                return false;
+            }
+            std::thread::sleep(Duration::from_millis(5));
+        }
+        let _ = handle.join();
+        true
+    }
+}
@@ -0,0 +1,113 @@
+use crate::memory_stats::deque_bytes;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, VecDeque};
+
+/// Report intervals kept for the jitter and gap figures.
+const INTERVAL_WINDOW: usize = 1000;
+/// A repeat of the same type/code/value within this is counted as a duplicate.
+const DUPLICATE_WINDOW_US: u64 = 1000;
+const RATE_WINDOW_US: u64 = 1_000_000;
+/// Event times kept for the rate; a device sending more than this a second reads as this.
+const MAX_RECENT_EVENTS: usize = 10_000;
+pub const WINDOW_CAPACITY: usize = INTERVAL_WINDOW + MAX_RECENT_EVENTS;
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct DeviceSignalQuality {
+    /// Standard deviation of the time between SYN_REPORT frames, over the last 1000
+    pub event_jitter_us_std_dev: f32,
+    /// Longest time between SYN_REPORT frames over the same window
+    pub event_gap_us_max: f32,
+    pub duplicate_event_count: u64,
+    /// Events whose kernel timestamp went backwards
+    pub out_of_order_count: u64,
+    /// Non-SYN events with a kernel timestamp in the last second
+    pub events_in_last_second: u32,
+    /// Non-SYN events since the device was opened
+    pub total_events: u64,
+}
+
+/// Timing statistics for one device's raw event stream, fed in kernel timestamps.
+///
+/// Events within a frame share the frame's timestamp, so jitter and gaps are measured
+/// between SYN_REPORTs rather than between individual events.
+#[derive(Default)]
+pub struct SignalQualityTracker {
+    last_timestamp_us: Option<u64>,
+    last_report_us: Option<u64>,
+    report_intervals_us: VecDeque<u64>,
+    /// Last value and time per (event type, code)
+    last_values: HashMap<(u16, u16), (i32, u64)>,
+    recent_events_us: VecDeque<u64>,
+    duplicate_event_count: u64,
+    out_of_order_count: u64,
+    total_events: u64,
+}
+
+impl SignalQualityTracker {
+    pub fn record(&mut self, event_type: u16, code: u16, value: i32, timestamp_us: u64, is_report: bool) {
+        if self.last_timestamp_us.is_some_and(|last| timestamp_us < last) {
+            self.out_of_order_count += 1;
+        }
+        self.last_timestamp_us = Some(timestamp_us);
+
+        if is_report {
+            if let Some(last) = self.last_report_us {
+                if self.report_intervals_us.len() == INTERVAL_WINDOW {
+                    self.report_intervals_us.pop_front();
+                }
+                self.report_intervals_us.push_back(timestamp_us.saturating_sub(last));
+            }
+            self.last_report_us = Some(timestamp_us);
+            return;
+        }
+
+        self.total_events += 1;
+        let previous = self.last_values.insert((event_type, code), (value, timestamp_us));
+        if previous.is_some_and(|(last_value, at)| {
+            last_value == value && timestamp_us.saturating_sub(at) <= DUPLICATE_WINDOW_US
+        }) {
+            self.duplicate_event_count += 1;
+        }
+
+        if self.recent_events_us.len() >= MAX_RECENT_EVENTS {
+            self.recent_events_us.pop_front();
+        }
+        self.recent_events_us.push_back(timestamp_us);
+        while self.recent_events_us.front().is_some_and(|&at| timestamp_us.saturating_sub(at) > RATE_WINDOW_US) {
+            self.recent_events_us.pop_front();
+        }
+    }
+
+    pub fn quality(&self, now_us: u64) -> DeviceSignalQuality {
+        let intervals = &self.report_intervals_us;
+        let event_jitter_us_std_dev = if intervals.len() < 2 {
+            0.0
+        } else {
+            let mean = intervals.iter().sum::<u64>() as f64 / intervals.len() as f64;
+            let variance = intervals.iter()
+                .map(|&interval| (interval as f64 - mean).powi(2))
+                .sum::<f64>() / intervals.len() as f64;
+            variance.sqrt() as f32
+        };
+
+        DeviceSignalQuality {
+            event_jitter_us_std_dev,
+            event_gap_us_max: intervals.iter().max().copied().unwrap_or(0) as f32,
+            duplicate_event_count: self.duplicate_event_count,
+            out_of_order_count: self.out_of_order_count,
+            events_in_last_second: self.recent_events_us.iter()
+                .filter(|&&at| now_us.saturating_sub(at) <= RATE_WINDOW_US)
+                .count() as u32,
+            total_events: self.total_events,
+        }
+    }
+    /// Entries in the interval and rate windows (at most `WINDOW_CAPACITY`), and the heap
+    /// held by them and the last-value table.
+    pub fn occupancy(&self) -> (usize, usize) {
+        let len = self.report_intervals_us.len() + self.recent_events_us.len();
+        let approx_bytes = deque_bytes(&self.report_intervals_us)
+            + deque_bytes(&self.recent_events_us)
+            + self.last_values.capacity() * std::mem::size_of::<((u16, u16), (i32, u64))>();
+        (len, approx_bytes)
+    }
+}
@@ -0,0 +1,51 @@
+use crate::locking::MutexExt;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::sync::Mutex;
+
+pub const SLOT_NAMES: [&str; 4] = ["P1", "P2", "P3", "P4"];
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SlotAssignment {
+    pub slot_name: String,
+    pub controller_id: Option<usize>,
+}
+
+/// Which controller sits in each player slot. A controller holds at most one slot.
+#[derive(Default)]
+pub struct SlotAssignments {
+    slots: Mutex<BTreeMap<String, usize>>,
+}
+
+impl SlotAssignments {
+    /// Puts `controller_id` in `slot_name`, moving it out of any slot it held and
+    /// unassigning whichever controller was there before.
+    pub fn assign(&self, slot_name: &str, controller_id: usize) -> Result<(), String> {
+        if !SLOT_NAMES.contains(&slot_name) {
+            return Err(format!("Unknown slot {}; expected one of {}", slot_name, SLOT_NAMES.join(", ")));
+        }
+        let mut slots = self.slots.lock_or_recover();
+        slots.retain(|_, assigned| *assigned != controller_id);
+        slots.insert(slot_name.to_string(), controller_id);
+        Ok(())
+    }
+
+    /// Returns false if the controller wasn't in a slot.
+    pub fn unassign(&self, controller_id: usize) -> bool {
+        let mut slots = self.slots.lock_or_recover();
+        let before = slots.len();
+        slots.retain(|_, assigned| *assigned != controller_id);
+        slots.len() != before
+    }
+
+    /// Every slot in order, empty ones included.
+    pub fn get(&self) -> Vec<SlotAssignment> {
+        let slots = self.slots.lock_or_recover();
+        SLOT_NAMES.iter()
+            .map(|&slot_name| SlotAssignment {
+                slot_name: slot_name.to_string(),
+                controller_id: slots.get(slot_name).copied(),
+            })
+            .collect()
+    }
+}
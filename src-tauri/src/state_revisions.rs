@@ -0,0 +1,95 @@
+use crate::gamepad::ControllerState;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// Removals remembered for deltas. Older ones are forgotten, and clients that last synced
+/// before the newest forgotten removal get a full resync instead.
+pub const MAX_REMOVED_CONTROLLERS: usize = 64;
+
+/// Revision of the controller states, bumped by the dispatcher on every change, along
+/// with the revision each controller last changed or was removed at.
+#[derive(Debug, Clone, Default)]
+pub struct StateRevisions {
+    revision: u64,
+    changed_at: HashMap<usize, u64>,
+    /// Kept so clients that last synced before a disconnect learn about it
+    removed_at: HashMap<usize, u64>,
+    /// Revision of the newest removal dropped from `removed_at`
+    forgotten_through: u64,
+}
+
+impl StateRevisions {
+    pub fn revision(&self) -> u64 {
+        self.revision
+    }
+
+    pub fn changed(&mut self, controller_id: usize) {
+        self.revision += 1;
+        self.changed_at.insert(controller_id, self.revision);
+        self.removed_at.remove(&controller_id);
+    }
+
+    pub fn removed(&mut self, controller_id: usize) {
+        self.revision += 1;
+        self.changed_at.remove(&controller_id);
+        self.removed_at.insert(controller_id, self.revision);
+        if self.removed_at.len() > MAX_REMOVED_CONTROLLERS {
+            if let Some((&oldest, &at)) = self.removed_at.iter().min_by_key(|(_, &at)| at) {
+                self.removed_at.remove(&oldest);
+                self.forgotten_through = self.forgotten_through.max(at);
+            }
+        }
+    }
+
+    /// Removed controllers still remembered for deltas.
+    pub fn removed_count(&self) -> usize {
+        self.removed_at.len()
+    }
+}
+
+/// Controller states as of `revisions.revision()`, published by the dispatcher.
+#[derive(Debug, Clone, Default)]
+pub struct StateSnapshot {
+    pub states: HashMap<usize, ControllerState>,
+    pub revisions: StateRevisions,
+}
+
+/// What changed since a client's last revision.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ControllerStatesDelta {
+    pub revision: u64,
+    /// `changed` holds every controller and replaces the client's copy, rather than
+    /// being merged into it. Sent for revision 0, for revisions from before a restart and
+    /// for revisions older than the removals still remembered.
+    pub full: bool,
+    pub changed: HashMap<usize, ControllerState>,
+    pub removed: Vec<usize>,
+}
+
+impl StateSnapshot {
+    pub fn delta(&self, since_revision: u64) -> ControllerStatesDelta {
+        let revision = self.revisions.revision;
+        if since_revision == 0 || since_revision > revision || since_revision < self.revisions.forgotten_through {
+            return ControllerStatesDelta {
+                revision,
+                full: true,
+                changed: self.states.clone(),
+                removed: Vec::new(),
+            };
+        }
+        let changed = self.states.iter()
+            .filter(|(id, _)| self.revisions.changed_at.get(id).is_some_and(|&at| at > since_revision))
+            .map(|(&id, state)| (id, state.clone()))
+            .collect();
+        let removed = self.revisions.removed_at.iter()
+            .filter(|(_, &at)| at > since_revision)
+            .map(|(&id, _)| id)
+            .collect();
+        ControllerStatesDelta {
+            revision,
+            full: false,
+            changed,
+            removed,
+        }
+    }
+}
@@ -0,0 +1,325 @@
+use crate::evdev_types::EvdevGamepadInfo;
+use crate::hidraw::{self, ControllerFirmwareInfo, HapticCapability};
+use crate::system_info;
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+const STEAM_ENV_VARS: [&str; 3] = ["STEAM_COMPAT_DATA_PATH", "STEAM_COMPAT_CLIENT_INSTALL_PATH", "SteamAppId"];
+/// Gamescope sets these for the clients it runs, e.g. `GAMESCOPE_WAYLAND_DISPLAY`.
+const GAMESCOPE_ENV_PREFIX: &str = "GAMESCOPE_";
+/// Gamescope's own Wayland socket, under `XDG_RUNTIME_DIR`.
+const GAMESCOPE_SOCKET: &str = "gamescope-0";
+const DMI_DIR: &str = "/sys/devices/virtual/dmi/id";
+const USB_DEVICES_DIR: &str = "/sys/bus/usb/devices";
+const VALVE_USB_VENDOR_ID: &str = "28de";
+const DECK_CONTROLLER_PRODUCT_ID: &str = "1205";
+
+/// Which Deck this is, from the DMI product name. Anything else is `Other`, never a guess.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DeckModel {
+    /// "Jupiter"
+    Lcd,
+    /// "Galileo"
+    Oled,
+    Other,
+}
+
+impl DeckModel {
+    fn from_product_name(product_name: Option<&str>) -> Self {
+        match product_name {
+            Some("Jupiter") => Self::Lcd,
+            Some("Galileo") => Self::Oled,
+            _ => Self::Other,
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Self::Lcd => "Steam Deck LCD",
+            Self::Oled => "Steam Deck OLED",
+            Self::Other => "Not Steam Deck hardware",
+        }
+    }
+}
+
+/// Version of the built-in controller's firmware as its USB device reports it
+/// (`bcdDevice`), e.g. "0300". `None` off a Deck or when USB sysfs isn't readable.
+fn controller_firmware() -> Option<String> {
+    let entries = std::fs::read_dir(USB_DEVICES_DIR).ok()?;
+    entries.flatten().find_map(|entry| {
+        let path = entry.path();
+        let read = |name: &str| std::fs::read_to_string(path.join(name)).ok().map(|value| value.trim().to_string());
+        if read("idVendor")? != VALVE_USB_VENDOR_ID || read("idProduct")? != DECK_CONTROLLER_PRODUCT_ID {
+            return None;
+        }
+        read("bcdDevice")
+    })
+}
+
+/// Steam Deck game mode (gamescope is the session) vs desktop mode, as far as we can tell.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SessionKind {
+    Desktop,
+    GameMode,
+    Unknown,
+}
+
+/// What the app does by default in a session, until the user picks otherwise.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct SessionDefaults {
+    pub ui_scale: f64,
+    /// Fullscreen, undecorated and on top
+    pub kiosk_mode: bool,
+    /// Steam Input is always on in game mode and may hold the physical controller
+    pub warn_steam_input: bool,
+}
+
+impl SessionKind {
+    /// Decides from the gamescope environment variables and socket, the XDG session and
+    /// whether a gamescope process runs (`None` when that couldn't be checked).
+    pub fn detect(gamescope_running: Option<bool>) -> Self {
+        let env = |name: &str| std::env::var(name).ok().filter(|value| !value.is_empty());
+        let current_desktop = env("XDG_CURRENT_DESKTOP");
+        let gamescope_env = std::env::vars().any(|(name, _)| name.starts_with(GAMESCOPE_ENV_PREFIX));
+        let gamescope_desktop = current_desktop.as_deref().is_some_and(|desktop| desktop.eq_ignore_ascii_case("gamescope"));
+        if gamescope_env || gamescope_desktop {
+            return Self::GameMode;
+        }
+        // Desktop mode has a regular desktop, where gamescope only ever runs nested
+        if current_desktop.is_some() {
+            return Self::Desktop;
+        }
+        let gamescope_socket = env("XDG_RUNTIME_DIR").is_some_and(|dir| Path::new(&dir).join(GAMESCOPE_SOCKET).exists());
+        if gamescope_socket || gamescope_running == Some(true) {
+            return Self::GameMode;
+        }
+        match env("XDG_SESSION_TYPE").as_deref() {
+            Some("wayland") | Some("x11") => Self::Desktop,
+            _ => Self::Unknown,
+        }
+    }
+
+    /// Game mode gets a larger fullscreen UI; desktop mode a normal window. Unknown keeps
+    /// the fullscreen window the app always opened with.
+    pub fn defaults(self) -> SessionDefaults {
+        match self {
+            Self::GameMode => SessionDefaults { ui_scale: 1.25, kiosk_mode: true, warn_steam_input: true },
+            Self::Desktop => SessionDefaults { ui_scale: 1.0, kiosk_mode: false, warn_steam_input: false },
+            Self::Unknown => SessionDefaults { ui_scale: 1.0, kiosk_mode: true, warn_steam_input: false },
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Self::Desktop => "Desktop mode",
+            Self::GameMode => "Game mode",
+            Self::Unknown => "Unknown session",
+        }
+    }
+}
+
+/// Whether the back grips (L4/L5/R4/R5) can reach the app.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BackGripStatus {
+    /// An evdev node reports them, normally hid-steam's "Steam Deck" device
+    Available,
+    /// Steam holds the built-in controller and hid-steam's node is gone. Its virtual pad has
+    /// no back grips, so they only arrive as whatever buttons the Steam layout binds them to.
+    SteamInputOnly,
+    /// No node reports them and Steam isn't the reason, or this isn't a Deck
+    Unavailable,
+}
+
+impl BackGripStatus {
+    pub fn hint(self) -> &'static str {
+        match self {
+            Self::Available => "Back grips L4/L5/R4/R5 are reported directly",
+            Self::SteamInputOnly => "Steam holds the built-in controller, so the back grips only arrive as the buttons this app's Steam controller layout binds them to; bind L4/L5/R4/R5 to buttons you don't otherwise use, or close Steam to read them directly",
+            Self::Unavailable => "No device reports the back grips L4/L5/R4/R5",
+        }
+    }
+}
+
+/// Whether a process named exactly `name` is running, or `None` when `pgrep` couldn't be run.
+pub fn process_running(name: &str) -> Option<bool> {
+    std::process::Command::new("pgrep")
+        // Exact, or this app's own "steam-deck-controller" process would count as Steam
+        .args(["-x", name])
+        .output()
+        .ok()
+        .map(|output| output.status.success() && !output.stdout.is_empty())
+}
+
+/// Steam Deck / Steam environment facts relevant to controller input.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SteamDeckInfo {
+    pub is_steam_deck: bool,
+    pub deck_user_present: bool,
+    /// DMI product name; "Jupiter" (LCD) or "Galileo" (OLED) on a Deck
+    pub product_name: Option<String>,
+    pub model: DeckModel,
+    /// DMI board name and version; the board version tells hardware revisions apart
+    pub board_name: Option<String>,
+    pub board_version: Option<String>,
+    pub bios_version: Option<String>,
+    /// `VERSION_ID` and `BUILD_ID` from os-release, when the OS is SteamOS
+    pub steamos_version: Option<String>,
+    pub steamos_build_id: Option<String>,
+    pub controller_firmware: Option<String>,
+    /// The controller's own build info over hidraw; `None` off a Deck or without access
+    pub controller_build: Option<ControllerFirmwareInfo>,
+    /// `None` when `pgrep` couldn't be run
+    pub steam_running: Option<bool>,
+    pub steam_env: Vec<EnvVarValue>,
+    pub session_kind: SessionKind,
+    pub back_grips: BackGripStatus,
+    pub haptics: HapticCapability,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EnvVarValue {
+    pub name: String,
+    pub value: Option<String>,
+}
+
+impl SteamDeckInfo {
+    /// `devices` are the detected evdev gamepads, checked for the back grips.
+    pub fn collect(devices: &[EvdevGamepadInfo]) -> Self {
+        let deck_user_present = Path::new("/home/deck").exists();
+        let dmi = |name: &str| {
+            std::fs::read_to_string(Path::new(DMI_DIR).join(name))
+                .ok()
+                .map(|value| value.trim().to_string())
+                .filter(|value| !value.is_empty())
+        };
+        let product_name = dmi("product_name");
+        let model = DeckModel::from_product_name(product_name.as_deref());
+        let is_deck_hardware = model != DeckModel::Other;
+
+        let os_release = system_info::read_os_release();
+        let steamos = os_release.get("ID").is_some_and(|id| id == "steamos");
+        let steamos_field = |key: &str| os_release.get(key).filter(|_| steamos).cloned();
+
+        let steam_running = process_running("steam");
+
+        let steam_env = STEAM_ENV_VARS
+            .iter()
+            .map(|name| EnvVarValue {
+                name: name.to_string(),
+                value: std::env::var(name).ok(),
+            })
+            .collect();
+
+        let is_steam_deck = is_deck_hardware || deck_user_present;
+        let back_grips = if devices.iter().any(EvdevGamepadInfo::has_back_grips) {
+            BackGripStatus::Available
+        } else if is_steam_deck && steam_running == Some(true) && devices.iter().any(EvdevGamepadInfo::is_steam_virtual) {
+            BackGripStatus::SteamInputOnly
+        } else {
+            BackGripStatus::Unavailable
+        };
+
+        Self {
+            is_steam_deck,
+            deck_user_present,
+            product_name,
+            model,
+            board_name: dmi("board_name"),
+            board_version: dmi("board_version"),
+            bios_version: dmi("bios_version"),
+            steamos_version: steamos_field("VERSION_ID"),
+            steamos_build_id: steamos_field("BUILD_ID"),
+            controller_firmware: if is_deck_hardware { controller_firmware() } else { None },
+            controller_build: if is_deck_hardware { hidraw::controller_firmware_info(steam_running) } else { None },
+            steam_running,
+            steam_env,
+            session_kind: SessionKind::detect(process_running("gamescope")),
+            back_grips,
+            haptics: hidraw::haptic_capability(),
+        }
+    }
+
+    /// Human-readable multi-line summary, the format `get_steam_deck_info` has always returned.
+    pub fn summary(&self) -> String {
+        let mut info = Vec::new();
+
+        if self.deck_user_present {
+            info.push("✅ Running on Steam Deck (deck user detected)".to_string());
+        } else if self.is_steam_deck {
+            info.push("✅ Running on Steam Deck hardware".to_string());
+        } else {
+            info.push("❓ Not running on Steam Deck (no deck user)".to_string());
+        }
+
+        if self.model == DeckModel::Other {
+            info.push(format!(
+                "🖥️ {} (product: {})",
+                self.model.label(),
+                self.product_name.as_deref().unwrap_or("unknown")
+            ));
+        } else {
+            let mut hardware = format!("🧩 {}", self.model.label());
+            if let Some(board) = &self.board_name {
+                hardware.push_str(&format!(", board {}", board));
+                if let Some(version) = &self.board_version {
+                    hardware.push_str(&format!(" {}", version));
+                }
+            }
+            if let Some(bios) = &self.bios_version {
+                hardware.push_str(&format!(", BIOS {}", bios));
+            }
+            hardware.push_str(&format!(
+                ", controller firmware {}",
+                self.controller_firmware.as_deref().unwrap_or("unknown")
+            ));
+            if let Some(build) = &self.controller_build {
+                hardware.push_str(&format!(" (built {})", build.firmware_build));
+            }
+            info.push(hardware);
+        }
+
+        match (&self.steamos_version, &self.steamos_build_id) {
+            (Some(version), Some(build)) => info.push(format!("🐧 SteamOS {} (build {})", version, build)),
+            (Some(version), None) => info.push(format!("🐧 SteamOS {}", version)),
+            (None, Some(build)) => info.push(format!("🐧 SteamOS build {}", build)),
+            (None, None) => info.push("🐧 Not running SteamOS".to_string()),
+        }
+
+        match self.session_kind {
+            SessionKind::GameMode => info.push("🎮 Game mode (gamescope session)".to_string()),
+            SessionKind::Desktop => info.push("🖥️ Desktop mode".to_string()),
+            SessionKind::Unknown => info.push("❓ Could not tell game mode from desktop mode".to_string()),
+        }
+
+        match self.steam_running {
+            Some(true) => info.push("🎮 Steam is running".to_string()),
+            Some(false) => info.push("❌ Steam is not running".to_string()),
+            None => info.push("❓ Could not check Steam status".to_string()),
+        }
+
+        match self.back_grips {
+            BackGripStatus::Available => info.push(format!("✅ {}", self.back_grips.hint())),
+            BackGripStatus::SteamInputOnly => info.push(format!("⚠️ {}", self.back_grips.hint())),
+            BackGripStatus::Unavailable if self.is_steam_deck => info.push(format!("❌ {}", self.back_grips.hint())),
+            BackGripStatus::Unavailable => {}
+        }
+
+        match self.haptics {
+            HapticCapability::Available => info.push(format!("✅ {}", self.haptics.hint())),
+            HapticCapability::NoController if !self.is_steam_deck => {}
+            _ => info.push(format!("⚠️ {}", self.haptics.hint())),
+        }
+
+        for var in &self.steam_env {
+            match &var.value {
+                Some(value) => info.push(format!("🎮 {}: {}", var.name, value)),
+                None => info.push(format!("❌ {} not set", var.name)),
+            }
+        }
+
+        info.join("\n")
+    }
+}
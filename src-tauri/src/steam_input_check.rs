@@ -0,0 +1,367 @@
+use crate::app_state::AppState;
+use crate::evdev_types::EvdevGamepadInfo;
+use crate::locking::MutexExt;
+use crate::steam_deck;
+use serde::{Deserialize, Serialize};
+use std::sync::Mutex;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tauri::{AppHandle, Emitter, Manager};
+use tracing::{info, warn};
+
+/// How often the emulation nodes are drained.
+const SAMPLE_INTERVAL: Duration = Duration::from_secs(1);
+/// Samples between verdicts, i.e. the evidence window.
+const SAMPLES_PER_CHECK: u32 = 10;
+/// Samples between looks for new emulation nodes.
+const SAMPLES_PER_RESCAN: u32 = 30;
+/// Emulated keyboard/mouse events in a window that count as the emulation being in use.
+const MIN_EMULATION_EVENTS: u64 = 5;
+const VALVE_VENDOR_ID: u16 = 0x28de;
+const DECK_CONTROLLER_PRODUCT_ID: u16 = 0x1205;
+
+/// How Steam Input is keeping gamepad events from the app, as far as the evidence goes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SteamInputInterference {
+    None,
+    /// Steam turns the controller into keyboard and mouse: the emulation nodes are busy
+    /// while the built-in gamepad node is silent or gone
+    DesktopLayout,
+    /// Only Steam's virtual pad is left, which carries whatever the Steam layout maps
+    VirtualGamepadOnly,
+}
+
+impl SteamInputInterference {
+    pub fn advice(self) -> Option<&'static str> {
+        match self {
+            Self::None => None,
+            Self::DesktopLayout => Some("Steam is turning the controller into keyboard and mouse input, so no gamepad events reach the app. In Steam, open this app's controller settings and pick the Gamepad layout, or add the app as a non-Steam game and choose the \"Gamepad\" template"),
+            Self::VirtualGamepadOnly => Some("Steam holds the built-in controller and only its virtual gamepad is visible, so the app sees whatever the Steam layout maps. Make sure this app's Steam Input layout is the Gamepad template, or close Steam to read the controller directly"),
+        }
+    }
+}
+
+/// Activity of one input node over the last evidence window.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NodeActivity {
+    pub device_path: String,
+    pub name: String,
+    /// Non-SYN events in the window
+    pub events: u64,
+}
+
+/// What the verdict was based on, so false positives can be traced.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SteamInputEvidence {
+    pub steam_running: Option<bool>,
+    /// hid-steam's gamepad node for the built-in controller, when it's open
+    pub builtin_gamepad: Option<NodeActivity>,
+    /// Valve keyboard and mouse nodes: the controller's lizard mode interfaces, or Steam's
+    pub emulation_nodes: Vec<NodeActivity>,
+    /// Valve keyboard and mouse nodes that couldn't be opened
+    pub unreadable_emulation_nodes: Vec<String>,
+    pub steam_virtual_gamepads: Vec<String>,
+    pub window_secs: u64,
+}
+
+/// Answer to `get_steam_input_check` and payload of `steam-input-interference`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SteamInputCheck {
+    pub interference: SteamInputInterference,
+    pub advice: Option<String>,
+    /// `None` until the first window has passed
+    pub evidence: Option<SteamInputEvidence>,
+    pub checked_at: Option<u64>,
+}
+
+impl Default for SteamInputCheck {
+    fn default() -> Self {
+        Self {
+            interference: SteamInputInterference::None,
+            advice: None,
+            evidence: None,
+            checked_at: None,
+        }
+    }
+}
+
+/// A desktop layout needs Steam running, the emulation in use and no gamepad events; it
+/// stays the verdict while nothing is touched, until gamepad events show up or Steam exits.
+pub fn judge(previous: SteamInputInterference, evidence: &SteamInputEvidence) -> SteamInputInterference {
+    let gamepad_active = evidence.builtin_gamepad.as_ref().is_some_and(|node| node.events > 0);
+    if evidence.steam_running != Some(true) || gamepad_active {
+        return SteamInputInterference::None;
+    }
+    let emulation_events: u64 = evidence.emulation_nodes.iter().map(|node| node.events).sum();
+    if emulation_events >= MIN_EMULATION_EVENTS {
+        return SteamInputInterference::DesktopLayout;
+    }
+    if previous == SteamInputInterference::DesktopLayout {
+        return previous;
+    }
+    if evidence.builtin_gamepad.is_none() && !evidence.steam_virtual_gamepads.is_empty() {
+        return SteamInputInterference::VirtualGamepadOnly;
+    }
+    previous
+}
+
+fn is_builtin_gamepad(device: &EvdevGamepadInfo) -> bool {
+    device.is_gamepad
+        && device.accessible
+        && !device.is_steam_virtual()
+        && device.vendor_id == Some(VALVE_VENDOR_ID)
+        && device.product_id == Some(DECK_CONTROLLER_PRODUCT_ID)
+}
+
+/// Gathers evidence every `SAMPLES_PER_CHECK` seconds and emits `steam-input-interference`
+/// when the verdict changes.
+pub fn spawn_monitor(app: AppHandle) {
+    let state = app.state::<AppState>();
+    let evdev_manager = state.evdev_manager.clone();
+    let shutdown = state.shutdown.clone();
+    let latest = state.steam_input.clone();
+    std::thread::Builder::new()
+        .name("steam-input-check".to_string())
+        .spawn(move || {
+            let mut watcher = EmulationWatcher::default();
+            // Built-in gamepad node and its event total at the start of the window
+            let mut gamepad_baseline: Option<(String, u64)> = None;
+            let mut sample = 0u32;
+            // Refreshed with the rescan rather than spawning pgrep for every verdict
+            let mut steam_running = None;
+            while !shutdown.is_requested() {
+                if sample.is_multiple_of(SAMPLES_PER_RESCAN) {
+                    watcher.rescan();
+                    steam_running = steam_deck::process_running("steam");
+                }
+                watcher.drain();
+                sample = sample.wrapping_add(1);
+                if sample.is_multiple_of(SAMPLES_PER_CHECK) {
+                    let devices = evdev_manager.get_detected_devices();
+                    let builtin_gamepad = devices.iter().find(|device| is_builtin_gamepad(device)).map(|device| {
+                        let total = evdev_manager.get_signal_quality(&device.device_path)
+                            .map(|quality| quality.total_events)
+                            .unwrap_or_default();
+                        let baseline = gamepad_baseline.as_ref()
+                            .filter(|(path, start)| *path == device.device_path && *start <= total)
+                            .map_or(0, |(_, start)| *start);
+                        gamepad_baseline = Some((device.device_path.clone(), total));
+                        NodeActivity {
+                            device_path: device.device_path.clone(),
+                            name: device.name.clone(),
+                            events: total - baseline,
+                        }
+                    });
+                    let evidence = SteamInputEvidence {
+                        steam_running,
+                        builtin_gamepad,
+                        emulation_nodes: watcher.take_activity(),
+                        unreadable_emulation_nodes: watcher.unreadable(),
+                        steam_virtual_gamepads: devices.iter()
+                            .filter(|device| device.is_steam_virtual())
+                            .map(|device| device.name.clone())
+                            .collect(),
+                        window_secs: (SAMPLE_INTERVAL * SAMPLES_PER_CHECK).as_secs(),
+                    };
+                    record(&app, &latest, evidence);
+                }
+                std::thread::sleep(SAMPLE_INTERVAL);
+            }
+        })
+        .expect("Failed to spawn Steam Input check thread");
+}
+
+fn record(app: &AppHandle, latest: &Mutex<SteamInputCheck>, evidence: SteamInputEvidence) {
+    let mut latest = latest.lock_or_recover();
+    let interference = judge(latest.interference, &evidence);
+    let changed = interference != latest.interference;
+    *latest = SteamInputCheck {
+        interference,
+        advice: interference.advice().map(str::to_string),
+        evidence: Some(evidence),
+        checked_at: Some(SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default().as_millis() as u64),
+    };
+    if changed {
+        match interference {
+            SteamInputInterference::None => info!("Steam Input no longer looks like it's holding back gamepad input"),
+            _ => warn!("Steam Input interference: {:?}", interference),
+        }
+        if let Err(e) = app.emit("steam-input-interference", latest.clone()) {
+            warn!("Failed to emit steam-input-interference: {}", e);
+        }
+    }
+}
+
+/// Counts events on Valve keyboard and mouse nodes without grabbing them.
+#[cfg(target_os = "linux")]
+#[derive(Default)]
+struct EmulationWatcher {
+    nodes: Vec<WatchedNode>,
+    unreadable: Vec<String>,
+}
+
+#[cfg(target_os = "linux")]
+struct WatchedNode {
+    device_path: String,
+    name: String,
+    device: evdev::Device,
+    events: u64,
+}
+
+#[cfg(target_os = "linux")]
+impl EmulationWatcher {
+    fn rescan(&mut self) {
+        use evdev::{Key, RelativeAxisType};
+        let Ok(entries) = std::fs::read_dir("/dev/input") else {
+            return;
+        };
+        let mut nodes = Vec::new();
+        let mut unreadable = Vec::new();
+        for entry in entries.flatten() {
+            let event_name = entry.file_name().to_string_lossy().to_string();
+            if !event_name.starts_with("event") {
+                continue;
+            }
+            // sysfs says whose node it is without opening every keyboard and mouse
+            let vendor = std::fs::read_to_string(format!("/sys/class/input/{}/device/id/vendor", event_name))
+                .ok()
+                .and_then(|vendor| u16::from_str_radix(vendor.trim(), 16).ok());
+            if vendor != Some(VALVE_VENDOR_ID) {
+                continue;
+            }
+            let device_path = entry.path().to_string_lossy().to_string();
+            if let Some(index) = self.nodes.iter().position(|node| node.device_path == device_path) {
+                nodes.push(self.nodes.swap_remove(index));
+                continue;
+            }
+            let device = match evdev::Device::open(&device_path) {
+                Ok(device) => device,
+                Err(_) => {
+                    unreadable.push(device_path);
+                    continue;
+                }
+            };
+            let keys = device.supported_keys();
+            let gamepad = keys.is_some_and(|keys| keys.contains(Key::BTN_SOUTH));
+            let keyboard = keys.is_some_and(|keys| keys.contains(Key::KEY_ENTER));
+            let mouse = device.supported_relative_axes().is_some_and(|axes| axes.contains(RelativeAxisType::REL_X));
+            if gamepad || !(keyboard || mouse) || crate::evdev_gamepad::set_nonblocking(&device).is_err() {
+                continue;
+            }
+            nodes.push(WatchedNode {
+                device_path,
+                name: device.name().unwrap_or("Unknown").to_string(),
+                device,
+                events: 0,
+            });
+        }
+        self.nodes = nodes;
+        self.unreadable = unreadable;
+    }
+
+    fn drain(&mut self) {
+        self.nodes.retain_mut(|node| match node.device.fetch_events() {
+            Ok(events) => {
+                node.events += events.filter(|event| event.event_type() != evdev::EventType::SYNCHRONIZATION).count() as u64;
+                true
+            }
+            Err(e) => e.kind() == std::io::ErrorKind::WouldBlock,
+        });
+    }
+
+    fn take_activity(&mut self) -> Vec<NodeActivity> {
+        self.nodes.iter_mut()
+            .map(|node| NodeActivity {
+                device_path: node.device_path.clone(),
+                name: node.name.clone(),
+                events: std::mem::take(&mut node.events),
+            })
+            .collect()
+    }
+
+    fn unreadable(&self) -> Vec<String> {
+        self.unreadable.clone()
+    }
+}
+
+#[cfg(not(target_os = "linux"))]
+#[derive(Default)]
+struct EmulationWatcher;
+
+#[cfg(not(target_os = "linux"))]
+impl EmulationWatcher {
+    fn rescan(&mut self) {}
+
+    fn drain(&mut self) {}
+
+    fn take_activity(&mut self) -> Vec<NodeActivity> {
+        Vec::new()
+    }
+
+    fn unreadable(&self) -> Vec<String> {
+        Vec::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn node(name: &str, events: u64) -> NodeActivity {
+        NodeActivity {
+            device_path: format!("/dev/input/{}", name),
+            name: name.to_string(),
+            events,
+        }
+    }
+
+    fn evidence(steam_running: bool, gamepad_events: Option<u64>, emulation_events: u64, virtual_pad: bool) -> SteamInputEvidence {
+        SteamInputEvidence {
+            steam_running: Some(steam_running),
+            builtin_gamepad: gamepad_events.map(|events| node("event5", events)),
+            emulation_nodes: vec![node("event3", emulation_events)],
+            unreadable_emulation_nodes: Vec::new(),
+            steam_virtual_gamepads: if virtual_pad { vec!["Steam Virtual Gamepad".to_string()] } else { Vec::new() },
+            window_secs: 10,
+        }
+    }
+
+    #[test]
+    fn busy_emulation_with_a_silent_gamepad_is_a_desktop_layout() {
+        let verdict = judge(SteamInputInterference::None, &evidence(true, Some(0), 40, false));
+        assert_eq!(verdict, SteamInputInterference::DesktopLayout);
+        // Also when Steam has taken the gamepad node away entirely
+        let verdict = judge(SteamInputInterference::None, &evidence(true, None, 40, true));
+        assert_eq!(verdict, SteamInputInterference::DesktopLayout);
+    }
+
+    #[test]
+    fn gamepad_events_or_no_steam_clear_the_warning() {
+        assert_eq!(
+            judge(SteamInputInterference::DesktopLayout, &evidence(true, Some(12), 40, false)),
+            SteamInputInterference::None
+        );
+        assert_eq!(
+            judge(SteamInputInterference::DesktopLayout, &evidence(false, Some(0), 40, false)),
+            SteamInputInterference::None
+        );
+    }
+
+    #[test]
+    fn an_idle_window_keeps_the_previous_verdict() {
+        assert_eq!(
+            judge(SteamInputInterference::DesktopLayout, &evidence(true, Some(0), 0, false)),
+            SteamInputInterference::DesktopLayout
+        );
+        assert_eq!(judge(SteamInputInterference::None, &evidence(true, Some(0), 2, false)), SteamInputInterference::None);
+    }
+
+    #[test]
+    fn only_a_virtual_pad_left_is_flagged() {
+        assert_eq!(
+            judge(SteamInputInterference::None, &evidence(true, None, 0, true)),
+            SteamInputInterference::VirtualGamepadOnly
+        );
+        assert_eq!(judge(SteamInputInterference::None, &evidence(true, Some(0), 0, true)), SteamInputInterference::None);
+    }
+}
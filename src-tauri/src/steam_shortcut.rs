@@ -0,0 +1,689 @@
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+use tracing::{info, warn};
+
+/// Name of the non-Steam game entry, and what `check_steam_shortcut` looks for.
+pub const SHORTCUT_NAME: &str = "Steam Deck Controller";
+pub const KIOSK_FLAG: &str = "--kiosk";
+
+const TYPE_MAP: u8 = 0x00;
+const TYPE_STRING: u8 = 0x01;
+const TYPE_INT: u8 = 0x02;
+const TYPE_UINT64: u8 = 0x07;
+const TYPE_END: u8 = 0x08;
+const ARTWORK_EXTENSIONS: [&str; 3] = ["png", "jpg", "jpeg"];
+
+/// A value in Steam's binary KeyValues format, which `shortcuts.vdf` is written in.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum VdfValue {
+    Map(VdfMap),
+    String(String),
+    /// Steam writes these signed; the bits are kept as they are
+    Int(u32),
+    Uint64(u64),
+}
+
+/// Keys in file order, which Steam doesn't mind but keeps diffs of the file small.
+pub type VdfMap = Vec<(String, VdfValue)>;
+
+struct Reader<'a> {
+    bytes: &'a [u8],
+    position: usize,
+}
+
+impl Reader<'_> {
+    fn take(&mut self, count: usize) -> Result<&[u8], String> {
+        let end = self.position + count;
+        let taken = self.bytes.get(self.position..end)
+            .ok_or_else(|| format!("File ends early at byte {}", self.position))?;
+        self.position = end;
+        Ok(taken)
+    }
+
+    fn byte(&mut self) -> Result<u8, String> {
+        Ok(self.take(1)?[0])
+    }
+
+    fn string(&mut self) -> Result<String, String> {
+        let start = self.position;
+        let length = self.bytes[start..].iter()
+            .position(|&byte| byte == 0)
+            .ok_or_else(|| format!("Unterminated string at byte {}", start))?;
+        let text = String::from_utf8(self.take(length)?.to_vec())
+            .map_err(|_| format!("String at byte {} isn't UTF-8", start))?;
+        self.position += 1;
+        Ok(text)
+    }
+
+    fn map(&mut self) -> Result<VdfMap, String> {
+        let mut map = Vec::new();
+        loop {
+            let kind = self.byte()?;
+            if kind == TYPE_END {
+                return Ok(map);
+            }
+            let key = self.string()?;
+            let value = match kind {
+                TYPE_MAP => VdfValue::Map(self.map()?),
+                TYPE_STRING => VdfValue::String(self.string()?),
+                TYPE_INT => VdfValue::Int(u32::from_le_bytes(self.take(4)?.try_into().unwrap_or_default())),
+                TYPE_UINT64 => VdfValue::Uint64(u64::from_le_bytes(self.take(8)?.try_into().unwrap_or_default())),
+                // Anything else couldn't be written back as it was, so the file is left alone
+                other => return Err(format!("Unsupported value type {:#04x} for '{}'", other, key)),
+            };
+            map.push((key, value));
+        }
+    }
+}
+
+/// An empty file reads as an empty map.
+pub fn parse_vdf(bytes: &[u8]) -> Result<VdfMap, String> {
+    if bytes.is_empty() {
+        return Ok(Vec::new());
+    }
+    Reader { bytes, position: 0 }.map()
+}
+
+pub fn write_vdf(map: &VdfMap) -> Vec<u8> {
+    let mut bytes = Vec::new();
+    write_map(&mut bytes, map);
+    bytes
+}
+
+fn write_map(bytes: &mut Vec<u8>, map: &VdfMap) {
+    for (key, value) in map {
+        let kind = match value {
+            VdfValue::Map(_) => TYPE_MAP,
+            VdfValue::String(_) => TYPE_STRING,
+            VdfValue::Int(_) => TYPE_INT,
+            VdfValue::Uint64(_) => TYPE_UINT64,
+        };
+        bytes.push(kind);
+        write_string(bytes, key);
+        match value {
+            VdfValue::Map(inner) => write_map(bytes, inner),
+            VdfValue::String(text) => write_string(bytes, text),
+            VdfValue::Int(number) => bytes.extend_from_slice(&number.to_le_bytes()),
+            VdfValue::Uint64(number) => bytes.extend_from_slice(&number.to_le_bytes()),
+        }
+    }
+    bytes.push(TYPE_END);
+}
+
+fn write_string(bytes: &mut Vec<u8>, text: &str) {
+    bytes.extend_from_slice(text.as_bytes());
+    bytes.push(0);
+}
+
+// Older clients wrote lower-case keys ("appname", "exe"), so lookups ignore case
+fn get<'a>(map: &'a VdfMap, key: &str) -> Option<&'a VdfValue> {
+    map.iter().find(|(name, _)| name.eq_ignore_ascii_case(key)).map(|(_, value)| value)
+}
+
+fn get_str<'a>(map: &'a VdfMap, key: &str) -> Option<&'a str> {
+    match get(map, key) {
+        Some(VdfValue::String(text)) => Some(text),
+        _ => None,
+    }
+}
+
+/// Returns whether the value changed.
+fn set(map: &mut VdfMap, key: &str, value: VdfValue) -> bool {
+    match map.iter_mut().find(|(name, _)| name.eq_ignore_ascii_case(key)) {
+        Some((_, existing)) if *existing == value => false,
+        Some((_, existing)) => {
+            *existing = value;
+            true
+        }
+        None => {
+            map.push((key.to_string(), value));
+            true
+        }
+    }
+}
+
+fn quoted(path: &str) -> String {
+    format!("\"{}\"", path)
+}
+
+fn crc32(bytes: &[u8]) -> u32 {
+    let mut crc = !0u32;
+    for &byte in bytes {
+        crc ^= u32::from(byte);
+        for _ in 0..8 {
+            crc = if crc & 1 == 1 { (crc >> 1) ^ 0xEDB8_8320 } else { crc >> 1 };
+        }
+    }
+    !crc
+}
+
+/// The app ID Steam gives a non-Steam game, from its quoted exe and name. Artwork in
+/// `config/grid` is named after it.
+pub fn shortcut_app_id(exe: &str, app_name: &str) -> u32 {
+    crc32(format!("{}{}", quoted(exe), app_name).as_bytes()) | 0x8000_0000
+}
+
+/// What the shortcut should say. Paths are unquoted; Steam's quotes are added on write.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ShortcutSpec {
+    pub app_name: String,
+    pub exe: String,
+    pub start_dir: String,
+    pub launch_options: String,
+    pub icon: Option<String>,
+}
+
+/// Image files copied into Steam's grid folder for the shortcut.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ShortcutArtwork {
+    /// Library capsule, 600x900
+    pub portrait: Option<String>,
+    /// Recent games capsule, 920x430
+    pub landscape: Option<String>,
+    pub hero: Option<String>,
+    pub logo: Option<String>,
+    /// Shown in the library list; Steam reads it from where it is
+    pub icon: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ShortcutChange {
+    Added,
+    Updated,
+    Unchanged,
+}
+
+/// The shortcut as Steam has it.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct InstalledShortcut {
+    pub key: String,
+    pub app_id: u32,
+    pub app_name: String,
+    pub exe: String,
+    pub start_dir: String,
+    pub launch_options: String,
+}
+
+fn installed(key: &str, entry: &VdfMap) -> InstalledShortcut {
+    let unquoted = |key: &str| get_str(entry, key).unwrap_or_default().trim_matches('"').to_string();
+    let app_name = get_str(entry, "AppName").unwrap_or_default().to_string();
+    let exe = unquoted("Exe");
+    InstalledShortcut {
+        key: key.to_string(),
+        app_id: match get(entry, "appid") {
+            Some(VdfValue::Int(app_id)) => *app_id,
+            _ => shortcut_app_id(&exe, &app_name),
+        },
+        start_dir: unquoted("StartDir"),
+        launch_options: get_str(entry, "LaunchOptions").unwrap_or_default().to_string(),
+        app_name,
+        exe,
+    }
+}
+
+fn shortcuts(root: &VdfMap) -> &[(String, VdfValue)] {
+    match get(root, "shortcuts") {
+        Some(VdfValue::Map(entries)) => entries,
+        _ => &[],
+    }
+}
+
+/// The entry named `app_name`, else the one launching `exe`, so an entry survives a rename
+/// of either.
+pub fn find_shortcut(root: &VdfMap, app_name: &str, exe: &str) -> Option<InstalledShortcut> {
+    let entries: Vec<InstalledShortcut> = shortcuts(root).iter()
+        .filter_map(|(key, value)| match value {
+            VdfValue::Map(entry) => Some(installed(key, entry)),
+            _ => None,
+        })
+        .collect();
+    let by_name = entries.iter().position(|entry| entry.app_name == app_name);
+    let by_exe = entries.iter().position(|entry| entry.exe == exe);
+    by_name.or(by_exe).map(|index| entries[index].clone())
+}
+
+/// Adds the shortcut, or brings the existing one up to date. Fields the user may have
+/// changed in Steam, like tags or the desktop layout switch, are only set on add.
+pub fn upsert_shortcut(root: &mut VdfMap, spec: &ShortcutSpec) -> (ShortcutChange, u32) {
+    let existing = find_shortcut(root, &spec.app_name, &spec.exe);
+    if !matches!(get(root, "shortcuts"), Some(VdfValue::Map(_))) {
+        set(root, "shortcuts", VdfValue::Map(Vec::new()));
+    }
+    let Some(VdfValue::Map(entries)) = root.iter_mut()
+        .find(|(name, _)| name.eq_ignore_ascii_case("shortcuts"))
+        .map(|(_, value)| value)
+    else {
+        unreachable!("shortcuts map was just inserted");
+    };
+
+    let mut wanted = vec![
+        ("AppName", VdfValue::String(spec.app_name.clone())),
+        ("Exe", VdfValue::String(quoted(&spec.exe))),
+        ("StartDir", VdfValue::String(quoted(&spec.start_dir))),
+        ("LaunchOptions", VdfValue::String(spec.launch_options.clone())),
+    ];
+    if let Some(icon) = &spec.icon {
+        wanted.push(("icon", VdfValue::String(icon.clone())));
+    }
+
+    if let Some(existing) = existing {
+        let entry = entries.iter_mut()
+            .find(|(key, _)| *key == existing.key)
+            .and_then(|(_, value)| match value {
+                VdfValue::Map(entry) => Some(entry),
+                _ => None,
+            });
+        let mut changed = false;
+        if let Some(entry) = entry {
+            for (key, value) in wanted {
+                changed |= set(entry, key, value);
+            }
+        }
+        let change = if changed { ShortcutChange::Updated } else { ShortcutChange::Unchanged };
+        return (change, existing.app_id);
+    }
+
+    let app_id = shortcut_app_id(&spec.exe, &spec.app_name);
+    let mut entry: VdfMap = vec![("appid".to_string(), VdfValue::Int(app_id))];
+    for (key, value) in wanted {
+        set(&mut entry, key, value);
+    }
+    for (key, value) in [
+        ("icon", VdfValue::String(String::new())),
+        ("ShortcutPath", VdfValue::String(String::new())),
+        ("IsHidden", VdfValue::Int(0)),
+        // The desktop layout maps the sticks to a mouse, which is what this app can't use
+        ("AllowDesktopConfig", VdfValue::Int(0)),
+        ("AllowOverlay", VdfValue::Int(1)),
+        ("OpenVR", VdfValue::Int(0)),
+        ("Devkit", VdfValue::Int(0)),
+        ("DevkitGameID", VdfValue::String(String::new())),
+        ("DevkitOverrideAppID", VdfValue::Int(0)),
+        ("LastPlayTime", VdfValue::Int(0)),
+        ("FlatpakAppID", VdfValue::String(String::new())),
+        ("tags", VdfValue::Map(Vec::new())),
+    ] {
+        if get(&entry, key).is_none() {
+            entry.push((key.to_string(), value));
+        }
+    }
+    let index = entries.iter()
+        .filter_map(|(key, _)| key.parse::<usize>().ok())
+        .max()
+        .map_or(0, |last| last + 1);
+    entries.push((index.to_string(), VdfValue::Map(entry)));
+    (ShortcutChange::Added, app_id)
+}
+
+/// Where Steam keeps its data, trying the native, `~/.steam` and Flatpak installs.
+pub fn steam_root() -> Option<PathBuf> {
+    let home = PathBuf::from(std::env::var_os("HOME")?);
+    [
+        home.join(".local/share/Steam"),
+        home.join(".steam/steam"),
+        home.join(".var/app/com.valvesoftware.Steam/.local/share/Steam"),
+    ]
+    .into_iter()
+    .find(|root| root.join("userdata").is_dir())
+}
+
+/// The Steam user folder shortcuts are installed for.
+pub fn current_user_dir() -> Result<PathBuf, String> {
+    let root = steam_root()
+        .ok_or("No Steam install found in ~/.local/share/Steam, ~/.steam/steam or the Flatpak")?;
+    find_user_dir(&root)
+}
+
+/// The `userdata/<account id>` folder of the user who last used Steam, judged by when
+/// their `localconfig.vdf` was written.
+pub fn find_user_dir(steam_root: &Path) -> Result<PathBuf, String> {
+    let userdata = steam_root.join("userdata");
+    let entries = std::fs::read_dir(&userdata)
+        .map_err(|e| format!("Failed to read {}: {}", userdata.display(), e))?;
+    entries.flatten()
+        .filter(|entry| {
+            let name = entry.file_name().to_string_lossy().to_string();
+            // 0 is the anonymous user Steam uses before anyone logs in
+            name != "0" && !name.is_empty() && name.chars().all(|c| c.is_ascii_digit())
+        })
+        .map(|entry| entry.path())
+        .max_by_key(|path| {
+            std::fs::metadata(path.join("config/localconfig.vdf"))
+                .or_else(|_| std::fs::metadata(path))
+                .and_then(|metadata| metadata.modified())
+                .unwrap_or(UNIX_EPOCH)
+        })
+        .ok_or_else(|| format!("No Steam user in {}; log in to Steam once first", userdata.display()))
+}
+
+fn shortcuts_path(user_dir: &Path) -> PathBuf {
+    user_dir.join("config/shortcuts.vdf")
+}
+
+/// Nothing to read is an empty file; a file we can't read back faithfully is an error,
+/// so it's never overwritten.
+fn read_shortcuts(path: &Path) -> Result<(Option<Vec<u8>>, VdfMap), String> {
+    match std::fs::read(path) {
+        Ok(bytes) => {
+            let root = parse_vdf(&bytes)
+                .map_err(|e| format!("{} couldn't be read, so it was left alone: {}", path.display(), e))?;
+            Ok((Some(bytes), root))
+        }
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok((None, Vec::new())),
+        Err(e) => Err(format!("Failed to read {}: {}", path.display(), e)),
+    }
+}
+
+/// Answer to `install_steam_shortcut`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SteamShortcutResult {
+    pub shortcuts_path: String,
+    pub change: ShortcutChange,
+    pub app_id: u32,
+    pub launch_options: String,
+    /// The previous `shortcuts.vdf`, when there was one and it changed
+    pub backup_path: Option<String>,
+    pub artwork_written: Vec<String>,
+    /// `None` when it couldn't be checked
+    pub steam_running: Option<bool>,
+    /// Steam only reads its shortcuts on start
+    pub restart_required: bool,
+}
+
+/// Writes the shortcut into `user_dir`'s `shortcuts.vdf`, backing up the old file first,
+/// then copies the artwork.
+pub fn install(user_dir: &Path, spec: &ShortcutSpec, artwork: &ShortcutArtwork, steam_running: Option<bool>) -> Result<SteamShortcutResult, String> {
+    let path = shortcuts_path(user_dir);
+    let images = artwork_files(artwork)?;
+    let (original, mut root) = read_shortcuts(&path)?;
+    let mut spec = spec.clone();
+    if artwork.icon.is_some() {
+        spec.icon = artwork.icon.clone();
+    }
+    let (change, app_id) = upsert_shortcut(&mut root, &spec);
+
+    let mut backup_path = None;
+    if change != ShortcutChange::Unchanged {
+        let config = user_dir.join("config");
+        std::fs::create_dir_all(&config)
+            .map_err(|e| format!("Failed to create {}: {}", config.display(), e))?;
+        if let Some(original) = &original {
+            let stamp = SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |elapsed| elapsed.as_secs());
+            let backup = config.join(format!("shortcuts.vdf.{}.bak", stamp));
+            std::fs::write(&backup, original)
+                .map_err(|e| format!("Failed to back up {} to {}: {}", path.display(), backup.display(), e))?;
+            backup_path = Some(backup.to_string_lossy().to_string());
+        }
+        // Written beside it and renamed over, so Steam never sees half a file
+        let staging = config.join("shortcuts.vdf.tmp");
+        std::fs::write(&staging, write_vdf(&root))
+            .and_then(|_| std::fs::rename(&staging, &path))
+            .map_err(|e| {
+                let _ = std::fs::remove_file(&staging);
+                format!("Failed to write {}: {}; if Steam has it open, close Steam and try again", path.display(), e)
+            })?;
+        info!("{:?} the Steam shortcut in {}", change, path.display());
+    }
+
+    let artwork_written = copy_artwork(&user_dir.join("config/grid"), app_id, &images);
+    Ok(SteamShortcutResult {
+        shortcuts_path: path.to_string_lossy().to_string(),
+        change,
+        app_id,
+        launch_options: spec.launch_options,
+        backup_path,
+        restart_required: steam_running != Some(false) && (change != ShortcutChange::Unchanged || !artwork_written.is_empty()),
+        artwork_written,
+        steam_running,
+    })
+}
+
+/// Each image with the suffix Steam looks for after the app ID: `p` for the portrait
+/// capsule, none for the landscape one, then `_hero` and `_logo`. Checked before anything
+/// is written.
+fn artwork_files(artwork: &ShortcutArtwork) -> Result<Vec<(PathBuf, &'static str, String)>, String> {
+    [
+        (&artwork.portrait, "p"),
+        (&artwork.landscape, ""),
+        (&artwork.hero, "_hero"),
+        (&artwork.logo, "_logo"),
+    ]
+    .into_iter()
+    .filter_map(|(source, suffix)| source.as_ref().map(|source| (PathBuf::from(source), suffix)))
+    .map(|(source, suffix)| {
+        let extension = source.extension()
+            .map(|extension| extension.to_string_lossy().to_lowercase())
+            .filter(|extension| ARTWORK_EXTENSIONS.contains(&extension.as_str()))
+            .ok_or_else(|| format!("Artwork must be a PNG or JPEG, got {}", source.display()))?;
+        Ok((source, suffix, extension))
+    })
+    .collect()
+}
+
+/// A missing image is logged and skipped rather than failing an install that already happened.
+fn copy_artwork(grid: &Path, app_id: u32, images: &[(PathBuf, &'static str, String)]) -> Vec<String> {
+    if !images.is_empty() {
+        if let Err(e) = std::fs::create_dir_all(grid) {
+            warn!("Failed to create {}: {}", grid.display(), e);
+            return Vec::new();
+        }
+    }
+    images.iter()
+        .filter_map(|(source, suffix, extension)| {
+            let target = grid.join(format!("{}{}.{}", app_id, suffix, extension));
+            match std::fs::copy(source, &target) {
+                Ok(_) => Some(target.to_string_lossy().to_string()),
+                Err(e) => {
+                    warn!("Failed to copy artwork {} to {}: {}", source.display(), target.display(), e);
+                    None
+                }
+            }
+        })
+        .collect()
+}
+
+/// Answer to `check_steam_shortcut`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SteamShortcutCheck {
+    pub shortcuts_path: String,
+    pub shortcut: Option<InstalledShortcut>,
+    pub expected_exe: String,
+    /// False when the entry points at an old install, e.g. after an update moved the app
+    pub exe_matches: bool,
+    pub launch_options_match: bool,
+}
+
+impl SteamShortcutCheck {
+    pub fn up_to_date(&self) -> bool {
+        self.shortcut.is_some() && self.exe_matches && self.launch_options_match
+    }
+}
+
+pub fn check(user_dir: &Path, spec: &ShortcutSpec) -> Result<SteamShortcutCheck, String> {
+    let path = shortcuts_path(user_dir);
+    let (_, root) = read_shortcuts(&path)?;
+    let shortcut = find_shortcut(&root, &spec.app_name, &spec.exe);
+    Ok(SteamShortcutCheck {
+        shortcuts_path: path.to_string_lossy().to_string(),
+        exe_matches: shortcut.as_ref().is_some_and(|shortcut| shortcut.exe == spec.exe),
+        launch_options_match: shortcut.as_ref().is_some_and(|shortcut| shortcut.launch_options == spec.launch_options),
+        expected_exe: spec.exe.clone(),
+        shortcut,
+    })
+}
+
+/// The shortcut for this install. An AppImage is launched through the image, not the
+/// binary it unpacks to a temporary mount.
+pub fn current_spec(kiosk_mode: bool) -> Result<ShortcutSpec, String> {
+    let exe = match std::env::var_os("APPIMAGE") {
+        Some(appimage) => PathBuf::from(appimage),
+        None => std::env::current_exe().map_err(|e| format!("Failed to find the app's executable: {}", e))?,
+    };
+    let start_dir = exe.parent().map(|dir| dir.to_string_lossy().to_string()).unwrap_or_default();
+    Ok(ShortcutSpec {
+        app_name: SHORTCUT_NAME.to_string(),
+        exe: exe.to_string_lossy().to_string(),
+        start_dir,
+        launch_options: if kiosk_mode { KIOSK_FLAG.to_string() } else { String::new() },
+        icon: None,
+    })
+}
+
+/// Whether the app was started with `--kiosk`, as the Steam shortcut does when kiosk
+/// mode is on.
+pub fn launched_with_kiosk_flag() -> bool {
+    std::env::args().skip(1).any(|arg| arg == KIOSK_FLAG)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::scratch_dir::ScratchDir;
+
+    /// A scratch Steam user folder.
+    struct FakeUser(ScratchDir);
+
+    impl FakeUser {
+        fn new(label: &str) -> Self {
+            let root = ScratchDir::new(&format!("steam-user-{}", label));
+            root.attributes("config", &[]);
+            Self(root)
+        }
+
+        fn path(&self) -> &Path {
+            self.0.path()
+        }
+
+        fn shortcuts(&self) -> Vec<u8> {
+            std::fs::read(self.path().join("config/shortcuts.vdf")).unwrap()
+        }
+
+        fn backups(&self) -> usize {
+            std::fs::read_dir(self.path().join("config")).unwrap()
+                .flatten()
+                .filter(|entry| entry.file_name().to_string_lossy().ends_with(".bak"))
+                .count()
+        }
+    }
+
+    fn spec(exe: &str) -> ShortcutSpec {
+        ShortcutSpec {
+            app_name: "Steam Deck Controller".to_string(),
+            exe: exe.to_string(),
+            start_dir: "/home/deck".to_string(),
+            launch_options: "--kiosk".to_string(),
+            icon: None,
+        }
+    }
+
+    fn other_game() -> Vec<(String, VdfValue)> {
+        let entry = vec![
+            ("appid".to_string(), VdfValue::Int(0x8123_4567)),
+            ("AppName".to_string(), VdfValue::String("Emulator".to_string())),
+            ("Exe".to_string(), VdfValue::String("\"/usr/bin/emulator\"".to_string())),
+            ("tags".to_string(), VdfValue::Map(vec![("0".to_string(), VdfValue::String("Favorite".to_string()))])),
+        ];
+        vec![("shortcuts".to_string(), VdfValue::Map(vec![("0".to_string(), VdfValue::Map(entry))]))]
+    }
+
+    #[test]
+    fn app_ids_match_steams() {
+        assert_eq!(shortcut_app_id("/home/deck/app", "Steam Deck Controller"), 0xe70f_e3b7);
+    }
+
+    #[test]
+    fn shortcuts_files_round_trip() {
+        let root = other_game();
+        let bytes = write_vdf(&root);
+        assert_eq!(bytes[0], 0x00);
+        assert_eq!(&bytes[bytes.len() - 3..], &[0x08, 0x08, 0x08]);
+        assert_eq!(parse_vdf(&bytes).unwrap(), root);
+        assert!(parse_vdf(&bytes[..bytes.len() - 2]).is_err());
+    }
+
+    #[test]
+    fn a_missing_file_is_created_without_a_backup() {
+        let user = FakeUser::new("missing");
+        let result = install(user.path(), &spec("/home/deck/app"), &ShortcutArtwork::default(), Some(false)).unwrap();
+        assert_eq!(result.change, ShortcutChange::Added);
+        assert_eq!(result.app_id, shortcut_app_id("/home/deck/app", "Steam Deck Controller"));
+        assert!(result.backup_path.is_none());
+        assert!(!result.restart_required);
+        assert_eq!(user.backups(), 0);
+
+        let status = check(user.path(), &spec("/home/deck/app")).unwrap();
+        assert!(status.up_to_date());
+    }
+
+    #[test]
+    fn installing_keeps_other_shortcuts_and_backs_up_the_file() {
+        let user = FakeUser::new("existing");
+        std::fs::write(user.path().join("config/shortcuts.vdf"), write_vdf(&other_game())).unwrap();
+
+        let result = install(user.path(), &spec("/home/deck/app"), &ShortcutArtwork::default(), Some(true)).unwrap();
+        assert_eq!(result.change, ShortcutChange::Added);
+        assert!(result.restart_required);
+        assert_eq!(user.backups(), 1);
+        assert_eq!(std::fs::read(result.backup_path.unwrap()).unwrap(), write_vdf(&other_game()));
+
+        let root = parse_vdf(&user.shortcuts()).unwrap();
+        let VdfValue::Map(entries) = &root[0].1 else {
+            panic!("shortcuts isn't a map");
+        };
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[1].0, "1");
+        let original = other_game();
+        let VdfValue::Map(original_entries) = &original[0].1 else {
+            unreachable!();
+        };
+        assert_eq!(entries[0], original_entries[0]);
+    }
+
+    #[test]
+    fn a_moved_install_is_updated_in_place() {
+        let user = FakeUser::new("moved");
+        let first = install(user.path(), &spec("/home/deck/old/app"), &ShortcutArtwork::default(), Some(false)).unwrap();
+
+        let stale = check(user.path(), &spec("/home/deck/new/app")).unwrap();
+        assert!(stale.shortcut.is_some());
+        assert!(!stale.exe_matches);
+        assert!(!stale.up_to_date());
+
+        let second = install(user.path(), &spec("/home/deck/new/app"), &ShortcutArtwork::default(), Some(false)).unwrap();
+        assert_eq!(second.change, ShortcutChange::Updated);
+        // Keeping the ID keeps the artwork and play time Steam filed under it
+        assert_eq!(second.app_id, first.app_id);
+        assert!(check(user.path(), &spec("/home/deck/new/app")).unwrap().up_to_date());
+
+        let third = install(user.path(), &spec("/home/deck/new/app"), &ShortcutArtwork::default(), Some(true)).unwrap();
+        assert_eq!(third.change, ShortcutChange::Unchanged);
+        assert!(!third.restart_required);
+    }
+
+    #[test]
+    fn an_unreadable_file_is_left_alone() {
+        let user = FakeUser::new("corrupt");
+        let garbage = vec![0x00, b's', 0x00, 0x05, b'x', 0x00];
+        std::fs::write(user.path().join("config/shortcuts.vdf"), &garbage).unwrap();
+        assert!(install(user.path(), &spec("/home/deck/app"), &ShortcutArtwork::default(), Some(false)).is_err());
+        assert_eq!(user.shortcuts(), garbage);
+        assert_eq!(user.backups(), 0);
+    }
+
+    #[test]
+    fn artwork_is_named_after_the_app_id() {
+        let user = FakeUser::new("artwork");
+        let image = user.path().join("capsule.png");
+        std::fs::write(&image, b"png").unwrap();
+        let artwork = ShortcutArtwork { portrait: Some(image.to_string_lossy().to_string()), ..Default::default() };
+        let result = install(user.path(), &spec("/home/deck/app"), &artwork, Some(false)).unwrap();
+        assert_eq!(result.artwork_written.len(), 1);
+        assert!(user.path().join(format!("config/grid/{}p.png", result.app_id)).exists());
+
+        let text = ShortcutArtwork { hero: Some("notes.txt".to_string()), ..Default::default() };
+        assert!(install(user.path(), &spec("/home/deck/app"), &text, Some(false)).is_err());
+    }
+}
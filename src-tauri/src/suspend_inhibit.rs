@@ -0,0 +1,197 @@
+use crate::locking::MutexExt;
+use serde::{Deserialize, Serialize};
+use std::sync::Mutex;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tracing::{info, warn};
+
+/// How often forwarding and replay are checked for the automatic inhibitor.
+pub const INHIBIT_CHECK_INTERVAL: Duration = Duration::from_secs(2);
+/// Used when `set_suspend_inhibit` isn't given a reason.
+pub const DEFAULT_MANUAL_REASON: &str = "Inhibited from the app";
+const FORWARDING_REASON: &str = "Forwarding controller input to the light server";
+const REPLAY_REASON: &str = "Replaying a recording";
+#[cfg(target_os = "linux")]
+const INHIBITOR_WHO: &str = "Steam Deck Controller";
+
+/// Why suspend should be held off, if at all: a manual reason wins, then forwarding,
+/// then a replay.
+pub fn wanted_reason(manual: Option<&str>, forwarding: bool, replaying: bool) -> Option<String> {
+    manual.map(str::to_string).or_else(|| {
+        if forwarding {
+            Some(FORWARDING_REASON.to_string())
+        } else if replaying {
+            Some(REPLAY_REASON.to_string())
+        } else {
+            None
+        }
+    })
+}
+
+/// Answer to `get_suspend_inhibit` and `set_suspend_inhibit`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SuspendInhibitStatus {
+    /// Whether logind holds an inhibitor for the app right now
+    pub active: bool,
+    /// What the held inhibitor was taken for
+    pub reason: Option<String>,
+    /// Set through `set_suspend_inhibit`; takes precedence over the automatic reasons
+    pub manual_reason: Option<String>,
+    /// Milliseconds since the epoch
+    pub since: Option<u64>,
+    /// Why the inhibitor couldn't be taken, e.g. no system bus
+    pub last_error: Option<String>,
+}
+
+#[cfg(target_os = "linux")]
+type Inhibitor = zbus::zvariant::OwnedFd;
+#[cfg(not(target_os = "linux"))]
+type Inhibitor = ();
+
+struct HeldInhibitor {
+    reason: String,
+    since: u64,
+    /// logind keeps the inhibitor until this is closed, which the kernel also does if
+    /// the process dies
+    _inhibitor: Inhibitor,
+}
+
+#[derive(Default)]
+struct InhibitState {
+    manual: Option<String>,
+    forwarding: bool,
+    replaying: bool,
+    held: Option<HeldInhibitor>,
+    /// A reason that couldn't be inhibited for, so it isn't retried on every check
+    failed: Option<String>,
+    last_error: Option<String>,
+    shut_down: bool,
+}
+
+/// Holds a logind `sleep:idle` inhibitor while there's a reason to keep the system awake,
+/// and lets go of it as soon as there isn't.
+#[derive(Default)]
+pub struct SuspendInhibitor {
+    state: Mutex<InhibitState>,
+}
+
+impl SuspendInhibitor {
+    /// `None` drops the manual reason; the automatic ones still apply.
+    pub fn set_manual(&self, reason: Option<String>) -> SuspendInhibitStatus {
+        let mut state = self.state.lock_or_recover();
+        state.manual = reason;
+        apply(&mut state);
+        status(&state)
+    }
+
+    pub fn set_automatic(&self, forwarding: bool, replaying: bool) {
+        let mut state = self.state.lock_or_recover();
+        if (state.forwarding, state.replaying) != (forwarding, replaying) {
+            state.forwarding = forwarding;
+            state.replaying = replaying;
+            apply(&mut state);
+        }
+    }
+
+    /// Lets go of the inhibitor for good; called on every way out of the app.
+    pub fn release(&self) {
+        let mut state = self.state.lock_or_recover();
+        state.shut_down = true;
+        apply(&mut state);
+    }
+
+    pub fn status(&self) -> SuspendInhibitStatus {
+        status(&self.state.lock_or_recover())
+    }
+}
+
+fn apply(state: &mut InhibitState) {
+    let wanted = if state.shut_down {
+        None
+    } else {
+        wanted_reason(state.manual.as_deref(), state.forwarding, state.replaying)
+    };
+    if state.held.as_ref().map(|held| &held.reason) == wanted.as_ref() {
+        return;
+    }
+    let Some(reason) = wanted else {
+        if let Some(held) = state.held.take() {
+            info!("Released the suspend inhibitor ({})", held.reason);
+        }
+        state.failed = None;
+        return;
+    };
+    if state.failed.as_ref() == Some(&reason) {
+        return;
+    }
+    // Taken before the previous one is dropped, so there's no window to suspend in
+    match take_inhibitor(&reason) {
+        Ok(inhibitor) => {
+            info!("Inhibiting suspend: {}", reason);
+            state.held = Some(HeldInhibitor {
+                reason,
+                since: SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default().as_millis() as u64,
+                _inhibitor: inhibitor,
+            });
+            state.failed = None;
+            state.last_error = None;
+        }
+        Err(e) => {
+            warn!("Couldn't inhibit suspend ({}), so the system may still sleep: {}", reason, e);
+            state.failed = Some(reason);
+            state.last_error = Some(e);
+        }
+    }
+}
+
+fn status(state: &InhibitState) -> SuspendInhibitStatus {
+    SuspendInhibitStatus {
+        active: state.held.is_some(),
+        reason: state.held.as_ref().map(|held| held.reason.clone()),
+        manual_reason: state.manual.clone(),
+        since: state.held.as_ref().map(|held| held.since),
+        last_error: state.last_error.clone(),
+    }
+}
+
+#[cfg(target_os = "linux")]
+fn take_inhibitor(reason: &str) -> Result<Inhibitor, String> {
+    let inhibit = || -> zbus::Result<Inhibitor> {
+        let connection = zbus::blocking::Connection::system()?;
+        let proxy = zbus::blocking::Proxy::new(
+            &connection,
+            "org.freedesktop.login1",
+            "/org/freedesktop/login1",
+            "org.freedesktop.login1.Manager",
+        )?;
+        proxy.call("Inhibit", &("sleep:idle", INHIBITOR_WHO, reason, "block"))
+    };
+    inhibit().map_err(|e| format!("logind Inhibit failed: {}", e))
+}
+
+#[cfg(not(target_os = "linux"))]
+fn take_inhibitor(_reason: &str) -> Result<Inhibitor, String> {
+    Err("Suspend can only be inhibited through logind, on Linux".to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn nothing_going_on_lets_the_system_sleep() {
+        assert_eq!(wanted_reason(None, false, false), None);
+    }
+
+    #[test]
+    fn forwarding_or_a_replay_inhibits_suspend() {
+        assert!(wanted_reason(None, true, false).is_some_and(|reason| reason.contains("Forwarding")));
+        assert!(wanted_reason(None, false, true).is_some_and(|reason| reason.contains("Replaying")));
+        assert_eq!(wanted_reason(None, true, true), wanted_reason(None, true, false));
+    }
+
+    #[test]
+    fn a_manual_reason_takes_precedence() {
+        assert_eq!(wanted_reason(Some("Light show"), true, true).as_deref(), Some("Light show"));
+        assert_eq!(wanted_reason(Some("Light show"), false, false).as_deref(), Some("Light show"));
+    }
+}
@@ -0,0 +1,138 @@
+use crate::confinement::{self, Confinement, ConfinementKind};
+use crate::dock::DockStatus;
+use crate::locking::MutexExt;
+use crate::steam_deck::{self, SessionKind};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::sync::Mutex;
+
+/// Prefixes of environment variables SDL and gilrs read for controller mappings and quirks.
+const INPUT_ENV_PREFIXES: [&str; 2] = ["SDL_", "GILRS_"];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SessionType {
+    Gamescope,
+    Wayland,
+    X11,
+    Unknown,
+}
+
+/// System and session facts collected once at startup, so reports say which
+/// environment a problem happened in.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SystemInfo {
+    pub kernel_version: Option<String>,
+    /// `PRETTY_NAME` from os-release (the host's when sandboxed)
+    pub os_name: Option<String>,
+    pub os_id: Option<String>,
+    pub os_version: Option<String>,
+    /// SteamOS puts its image version in `BUILD_ID`
+    pub os_build_id: Option<String>,
+    pub session_type: SessionType,
+    pub current_desktop: Option<String>,
+    pub gamescope_running: Option<bool>,
+    pub session_kind: SessionKind,
+    pub flatpak: bool,
+    pub pressure_vessel: bool,
+    /// Every sandbox or container found, with the evidence for it
+    pub confinement: Confinement,
+    pub input_env: BTreeMap<String, String>,
+    /// Filled in from the dock monitor whenever system info is asked for
+    pub dock: DockStatus,
+}
+
+impl SystemInfo {
+    pub fn collect() -> Self {
+        let kernel_version = std::fs::read_to_string("/proc/sys/kernel/osrelease")
+            .ok()
+            .map(|version| version.trim().to_string());
+
+        let os_release = read_os_release();
+
+        let env = |name: &str| std::env::var(name).ok().filter(|value| !value.is_empty());
+        let current_desktop = env("XDG_CURRENT_DESKTOP");
+        let in_gamescope = env("GAMESCOPE_WAYLAND_DISPLAY").is_some()
+            || current_desktop.as_deref().is_some_and(|desktop| desktop.eq_ignore_ascii_case("gamescope"));
+        let session_type = if in_gamescope {
+            SessionType::Gamescope
+        } else {
+            match env("XDG_SESSION_TYPE").as_deref() {
+                Some("wayland") => SessionType::Wayland,
+                Some("x11") => SessionType::X11,
+                _ if env("WAYLAND_DISPLAY").is_some() => SessionType::Wayland,
+                _ if env("DISPLAY").is_some() => SessionType::X11,
+                _ => SessionType::Unknown,
+            }
+        };
+
+        let gamescope_running = steam_deck::process_running("gamescope");
+        let confinement = confinement::current();
+
+        let input_env = std::env::vars()
+            .filter(|(name, _)| INPUT_ENV_PREFIXES.iter().any(|prefix| name.starts_with(prefix)))
+            .collect();
+
+        Self {
+            kernel_version,
+            os_name: os_release.get("PRETTY_NAME").or(os_release.get("NAME")).cloned(),
+            os_id: os_release.get("ID").cloned(),
+            os_version: os_release.get("VERSION_ID").cloned(),
+            os_build_id: os_release.get("BUILD_ID").cloned(),
+            session_type,
+            current_desktop,
+            gamescope_running,
+            session_kind: SessionKind::detect(gamescope_running),
+            flatpak: confinement.kinds.contains(&ConfinementKind::Flatpak),
+            pressure_vessel: confinement.kinds.contains(&ConfinementKind::PressureVessel),
+            confinement: confinement.clone(),
+            input_env,
+            dock: DockStatus::default(),
+        }
+    }
+
+    /// A copy with the dock state as of now rather than at startup.
+    pub fn with_current_dock(&self, dock: &Mutex<DockStatus>) -> Self {
+        Self {
+            dock: dock.lock_or_recover().clone(),
+            ..self.clone()
+        }
+    }
+
+    /// One-line summary for the startup log.
+    pub fn summary(&self) -> String {
+        format!(
+            "{} (kernel {}), session {:?}, {}{}{}{}",
+            self.os_name.as_deref().unwrap_or("unknown OS"),
+            self.kernel_version.as_deref().unwrap_or("unknown"),
+            self.session_type,
+            self.session_kind.label(),
+            if self.flatpak { ", flatpak" } else { "" },
+            if self.pressure_vessel { ", pressure-vessel" } else { "" },
+            self.confinement.container_runtime.as_deref().map(|runtime| format!(", {} container", runtime)).unwrap_or_default(),
+        )
+    }
+}
+
+/// The host's os-release fields; empty when there's none.
+pub fn read_os_release() -> HashMap<String, String> {
+    // Inside flatpak /etc/os-release describes the runtime; the host's is under /run/host
+    ["/run/host/os-release", "/etc/os-release", "/usr/lib/os-release"]
+        .iter()
+        .find_map(|path| std::fs::read_to_string(path).ok())
+        .map(|contents| parse_os_release(&contents))
+        .unwrap_or_default()
+}
+
+/// `KEY=value` lines, with optional quotes around the value.
+fn parse_os_release(contents: &str) -> HashMap<String, String> {
+    contents
+        .lines()
+        .filter(|line| !line.trim_start().starts_with('#'))
+        .filter_map(|line| line.split_once('='))
+        .map(|(key, value)| {
+            let value = value.trim().trim_matches('"').trim_matches('\'');
+            (key.trim().to_string(), value.to_string())
+        })
+        .collect()
+}
@@ -0,0 +1,285 @@
+use crate::locking::MutexExt;
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+use std::sync::Mutex;
+use tauri::{AppHandle, Emitter};
+use tracing::{info, warn};
+
+pub const POWER_SUPPLY_DIR: &str = "/sys/class/power_supply";
+/// Battery percentages `system-battery-low` fires at unless configured otherwise.
+pub const DEFAULT_BATTERY_WARNINGS: [u8; 3] = [20, 10, 5];
+const MAX_BATTERY_WARNINGS: usize = 5;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BatteryState {
+    Charging,
+    Discharging,
+    Full,
+    /// Plugged in but held below full, e.g. by a charge limit
+    NotCharging,
+    Unknown,
+}
+
+impl BatteryState {
+    fn parse(status: &str) -> Self {
+        match status {
+            "Charging" => Self::Charging,
+            "Discharging" => Self::Discharging,
+            "Full" => Self::Full,
+            "Not charging" => Self::NotCharging,
+            _ => Self::Unknown,
+        }
+    }
+
+    pub fn on_external_power(self) -> bool {
+        matches!(self, Self::Charging | Self::Full | Self::NotCharging)
+    }
+}
+
+/// The machine's own battery, i.e. the first `Battery` power supply that isn't a device's.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BatteryInfo {
+    /// Power supply name, e.g. `BAT1`
+    pub name: String,
+    pub percent: u8,
+    pub state: BatteryState,
+    /// Watts drawn from (or into) the battery, when the driver reports it
+    pub power_draw_w: Option<f32>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PowerSource {
+    Battery,
+    /// No battery, e.g. a desktop PC
+    AcOnly,
+}
+
+/// Answer to `get_system_power`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SystemPower {
+    pub source: PowerSource,
+    pub battery: Option<BatteryInfo>,
+    /// Whether a mains adapter is plugged in; `None` when there's no `Mains` supply to ask
+    pub ac_online: Option<bool>,
+}
+
+/// Reads the power supplies under `root`, normally [`POWER_SUPPLY_DIR`]. A missing
+/// directory reads as AC only.
+pub fn read_power(root: &Path) -> SystemPower {
+    let mut supplies: Vec<_> = std::fs::read_dir(root)
+        .map(|entries| entries.flatten().map(|entry| entry.path()).collect())
+        .unwrap_or_default();
+    supplies.sort();
+
+    let mut battery = None;
+    let mut ac_online = None;
+    for path in supplies {
+        let read = |name: &str| std::fs::read_to_string(path.join(name)).ok().map(|value| value.trim().to_string());
+        match read("type").as_deref() {
+            Some("Mains") => {
+                let online = read("online").is_some_and(|online| online == "1");
+                ac_online = Some(ac_online.unwrap_or(false) || online);
+            }
+            // Controllers and mice report their batteries here too, with scope "Device"
+            Some("Battery") if battery.is_none() && read("scope").as_deref() != Some("Device") => {
+                let Some(percent) = read("capacity").and_then(|capacity| capacity.parse::<u8>().ok()) else {
+                    continue;
+                };
+                let microunits = |name: &str| read(name).and_then(|value| value.parse::<i64>().ok()).map(|value| value.unsigned_abs() as f64);
+                let power_draw_w = microunits("power_now")
+                    .map(|microwatts| microwatts / 1e6)
+                    .or_else(|| Some(microunits("current_now")? * microunits("voltage_now")? / 1e12))
+                    .map(|watts| watts as f32);
+                battery = Some(BatteryInfo {
+                    name: path.file_name().unwrap_or_default().to_string_lossy().to_string(),
+                    percent: percent.min(100),
+                    state: read("status").map_or(BatteryState::Unknown, |status| BatteryState::parse(&status)),
+                    power_draw_w,
+                });
+            }
+            _ => {}
+        }
+    }
+
+    SystemPower {
+        source: if battery.is_some() { PowerSource::Battery } else { PowerSource::AcOnly },
+        battery,
+        ac_online,
+    }
+}
+
+/// Sorted highest first, without duplicates.
+pub fn validate_thresholds(thresholds: &[u8]) -> Result<Vec<u8>, String> {
+    if let Some(threshold) = thresholds.iter().find(|threshold| !(1..=100).contains(*threshold)) {
+        return Err(format!("Battery warning thresholds must be between 1 and 100, got {}", threshold));
+    }
+    let mut sorted = thresholds.to_vec();
+    sorted.sort_unstable_by(|a, b| b.cmp(a));
+    sorted.dedup();
+    if sorted.len() > MAX_BATTERY_WARNINGS {
+        return Err(format!("At most {} battery warning thresholds, got {}", MAX_BATTERY_WARNINGS, sorted.len()));
+    }
+    Ok(sorted)
+}
+
+/// Payload of `system-battery-low`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SystemBatteryLow {
+    pub percent: u8,
+    pub threshold: u8,
+    /// 1 for the highest threshold, up to `levels` for the lowest
+    pub level: u8,
+    pub levels: u8,
+}
+
+#[derive(Default)]
+struct MonitorState {
+    latest: Option<SystemPower>,
+    thresholds: Vec<u8>,
+    /// Thresholds already warned about on this discharge
+    warned: Vec<u8>,
+}
+
+/// Polls the system battery and warns once per threshold as it drains. A threshold re-arms
+/// when the battery climbs back above it or goes on external power.
+pub struct SystemPowerMonitor {
+    state: Mutex<MonitorState>,
+}
+
+impl SystemPowerMonitor {
+    pub fn new(thresholds: Vec<u8>) -> Self {
+        Self {
+            state: Mutex::new(MonitorState { thresholds, ..MonitorState::default() }),
+        }
+    }
+
+    /// `thresholds` must have been through [`validate_thresholds`].
+    pub fn set_thresholds(&self, thresholds: Vec<u8>) {
+        let mut state = self.state.lock_or_recover();
+        info!("System battery warnings at {:?}%", thresholds);
+        state.warned.retain(|threshold| thresholds.contains(threshold));
+        state.thresholds = thresholds;
+    }
+
+    /// The last polled reading, or a fresh one before the first poll.
+    pub fn latest(&self) -> SystemPower {
+        let latest = self.state.lock_or_recover().latest.clone();
+        latest.unwrap_or_else(|| read_power(Path::new(POWER_SUPPLY_DIR)))
+    }
+
+    pub fn poll(&self, app: &AppHandle) {
+        let power = read_power(Path::new(POWER_SUPPLY_DIR));
+        let warning = self.check(&power);
+        self.state.lock_or_recover().latest = Some(power);
+        if let Some(warning) = warning {
+            warn!("System battery at {}% (warning threshold {}%)", warning.percent, warning.threshold);
+            if let Err(e) = app.emit("system-battery-low", warning) {
+                warn!("Failed to emit system-battery-low: {}", e);
+            }
+        }
+    }
+
+    /// The most urgent threshold `power` has newly dropped to, if any. Crossing several at
+    /// once (e.g. starting at 4%) gives one warning for the lowest.
+    pub fn check(&self, power: &SystemPower) -> Option<SystemBatteryLow> {
+        let mut state = self.state.lock_or_recover();
+        let Some(battery) = power.battery.as_ref().filter(|battery| !battery.state.on_external_power()) else {
+            state.warned.clear();
+            return None;
+        };
+        state.warned.retain(|threshold| battery.percent <= *threshold);
+        let crossed: Vec<u8> = state.thresholds.iter()
+            .copied()
+            .filter(|threshold| battery.percent <= *threshold && !state.warned.contains(threshold))
+            .collect();
+        let threshold = crossed.iter().copied().min()?;
+        state.warned.extend(crossed);
+        let level = state.thresholds.iter().position(|candidate| *candidate == threshold).unwrap_or_default() + 1;
+        Some(SystemBatteryLow {
+            percent: battery.percent,
+            threshold,
+            level: level as u8,
+            levels: state.thresholds.len() as u8,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::scratch_dir::ScratchDir;
+    use std::path::Path;
+
+    fn discharging_at(percent: u8) -> SystemPower {
+        let supplies = ScratchDir::new(&format!("power-supply-discharging-{}", percent));
+        supplies.attributes("BAT1", &[("type", "Battery"), ("capacity", &percent.to_string()), ("status", "Discharging")]);
+        read_power(supplies.path())
+    }
+
+    #[test]
+    fn reads_the_system_battery_and_skips_device_batteries() {
+        let supplies = ScratchDir::new("power-supply-deck");
+        supplies.attributes("ACAD", &[("type", "Mains"), ("online", "0")]);
+        // A controller's battery sorts first but isn't the system's
+        supplies.attributes("AAA-controller", &[("type", "Battery"), ("scope", "Device"), ("capacity", "90")]);
+        supplies.attributes(
+            "BAT1",
+            &[
+                ("type", "Battery"),
+                ("capacity", "42"),
+                ("status", "Discharging"),
+                ("current_now", "-1500000"),
+                ("voltage_now", "8000000"),
+            ],
+        );
+
+        let power = read_power(supplies.path());
+        assert_eq!(power.source, PowerSource::Battery);
+        assert_eq!(power.ac_online, Some(false));
+        let battery = power.battery.expect("battery");
+        assert_eq!(battery.name, "BAT1");
+        assert_eq!(battery.percent, 42);
+        assert_eq!(battery.state, BatteryState::Discharging);
+        assert!((battery.power_draw_w.unwrap() - 12.0).abs() < 0.001, "{:?}", battery.power_draw_w);
+    }
+
+    #[test]
+    fn no_battery_is_ac_only() {
+        let supplies = ScratchDir::new("power-supply-desktop");
+        supplies.attributes("AC", &[("type", "Mains"), ("online", "1")]);
+
+        let power = read_power(supplies.path());
+        assert_eq!(power.source, PowerSource::AcOnly);
+        assert!(power.battery.is_none());
+        assert_eq!(power.ac_online, Some(true));
+        assert_eq!(read_power(Path::new("/nonexistent/power_supply")).source, PowerSource::AcOnly);
+    }
+
+    #[test]
+    fn each_threshold_warns_once_per_discharge() {
+        let monitor = SystemPowerMonitor::new(vec![20, 10, 5]);
+
+        assert!(monitor.check(&discharging_at(50)).is_none());
+        let first = monitor.check(&discharging_at(20)).expect("20% warning");
+        assert_eq!((first.threshold, first.level, first.levels), (20, 1, 3));
+        assert!(monitor.check(&discharging_at(15)).is_none());
+        // Skipping past two thresholds between polls warns once, for the lower one
+        let second = monitor.check(&discharging_at(4)).expect("5% warning");
+        assert_eq!((second.threshold, second.level), (5, 3));
+        assert!(monitor.check(&discharging_at(3)).is_none());
+    }
+
+    #[test]
+    fn charging_rearms_the_warnings() {
+        let monitor = SystemPowerMonitor::new(vec![20, 10, 5]);
+        assert!(monitor.check(&discharging_at(10)).is_some());
+
+        let mut charging = discharging_at(10);
+        charging.battery.as_mut().unwrap().state = BatteryState::Charging;
+        assert!(monitor.check(&charging).is_none());
+
+        assert_eq!(monitor.check(&discharging_at(10)).map(|warning| warning.threshold), Some(10));
+    }
+}
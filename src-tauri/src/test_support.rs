@@ -0,0 +1,140 @@
+//! The input and forwarding cores, for integration tests to drive without a running app.
+//! Only built with the `test-support` feature: `cargo test --features test-support`.
+
+pub use crate::connection_stats::ConnectionStatsStore;
+pub use crate::controller_event::{NormalizedInput, UnifiedControllerEvent, CONTROLLER_EVENT};
+pub use crate::dead_zone::{DeadZoneShape, StickDeadZones, StickSide};
+pub use crate::emitter::{EventEmitter, RecordingEmitter};
+pub use crate::event_batcher::EventBatcher;
+pub use crate::evdev_types::EvdevGamepadInfo;
+pub use crate::flood_alarm::EventFloodAlarm;
+pub use crate::http_client::HttpClients;
+pub use crate::input_backend::BackendControllerState;
+pub use crate::light_server::{BatchEventResult, LightServer};
+pub use crate::memory_stats::BufferStats;
+pub use crate::pipeline_metrics::{PipelineCounter, PipelineMetricsStore};
+pub use crate::send_statistics::SendStatistics;
+pub use crate::signal_quality::SignalQualityTracker;
+pub use crate::state_revisions::{StateRevisions, MAX_REMOVED_CONTROLLERS};
+pub use crate::time_series::AxisTimeSeriesStore;
+pub use crate::uinput_output::UinputOutput;
+pub use crate::gamepad::{ControllerEvent, ControllerState, DebugInfo, GamepadInput};
+
+use crate::gamepad::GamepadManager;
+use crate::input_pipeline::{self, InputMessage, InputReceiver};
+use crate::permissions::PermissionsAnalysis;
+use crate::recording::InputSession;
+use crate::settings::SettingsManager;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+#[cfg(target_os = "linux")]
+pub use linux::EvdevHarness;
+
+/// A gilrs manager whose queued input is applied straight away, recording what it emits
+/// instead of sending it to a frontend. Settings start from defaults and aren't saved.
+pub struct GamepadHarness {
+    manager: GamepadManager,
+    receiver: InputReceiver,
+    emitter: RecordingEmitter,
+}
+
+impl GamepadHarness {
+    pub fn new() -> Result<Self, String> {
+        let (input_tx, receiver) = input_pipeline::channel();
+        let settings_path = std::env::temp_dir().join(format!("gamepad-harness-{}", std::process::id())).join("settings.json");
+        let http = HttpClients::new().map_err(|e| e.to_string())?;
+        let manager = GamepadManager::new(
+            Arc::new(SettingsManager::load(settings_path)),
+            Arc::new(LightServer::new(Arc::new(ConnectionStatsStore::load()), Arc::new(http))),
+            Arc::new(Mutex::new(EventBatcher::default())),
+            Arc::new(InputSession::new()),
+            input_tx,
+        )?;
+        Ok(Self { manager, receiver, emitter: RecordingEmitter::default() })
+    }
+
+    /// Queues `input` as mock input would and handles it as the dispatcher would.
+    pub fn input(&self, controller_id: usize, input: GamepadInput) {
+        self.manager.handle_input(controller_id, input, true);
+        while let Some(message) = self.receiver.try_recv() {
+            if let InputMessage::Gilrs { controller_id, input, synthetic, source_time } = message {
+                self.manager.process_input(&self.emitter, controller_id, input, synthetic, source_time);
+            }
+        }
+        self.manager.publish_state_snapshot();
+    }
+
+    pub fn poison_locks(&self) {
+        self.manager.poison_locks();
+    }
+
+    pub fn controller_states(&self) -> HashMap<usize, ControllerState> {
+        self.manager.get_controller_states()
+    }
+
+    pub fn recent_events(&self) -> Vec<ControllerEvent> {
+        self.manager.get_recent_events()
+    }
+
+    /// What `get_debug_info` returns, minus the evdev side.
+    pub fn debug_info(&self) -> DebugInfo {
+        self.manager.get_debug_info(&PermissionsAnalysis::collect(&[], false))
+    }
+}
+
+#[cfg(target_os = "linux")]
+mod linux {
+    use super::{BackendControllerState, EvdevGamepadInfo, RecordingEmitter, UnifiedControllerEvent, CONTROLLER_EVENT};
+    use crate::evdev_gamepad::EvdevGamepadManager;
+    use crate::input_backend::InputBackend;
+    use crate::input_pipeline::{self, InputMessage, InputReceiver};
+    use crate::recording::InputSession;
+    use std::sync::Arc;
+
+    /// An evdev manager whose queued input is handled straight away, recording what it
+    /// emits instead of sending it to a frontend.
+    pub struct EvdevHarness {
+        manager: EvdevGamepadManager,
+        receiver: InputReceiver,
+        emitter: RecordingEmitter,
+    }
+
+    impl EvdevHarness {
+        pub fn new() -> Result<Self, String> {
+            let (input_tx, receiver) = input_pipeline::channel();
+            let manager = EvdevGamepadManager::new(Arc::new(InputSession::new()), input_tx)?;
+            Ok(Self { manager, receiver, emitter: RecordingEmitter::default() })
+        }
+
+        /// Rescans `/dev/input`, returning the devices found.
+        pub fn scan(&self) -> Result<Vec<EvdevGamepadInfo>, String> {
+            self.manager.scan_for_gamepad_devices(&self.emitter)?;
+            Ok(self.manager.get_detected_devices())
+        }
+
+        /// One maintenance and polling pass, with everything it queued handled as the
+        /// dispatcher would. Returns the `controller-event`s emitted, oldest first.
+        pub fn poll(&self) -> Result<Vec<UnifiedControllerEvent>, String> {
+            self.manager.maintain(&self.emitter);
+            self.manager.poll_events(&self.emitter)?;
+            while let Some(message) = self.receiver.try_recv() {
+                if let InputMessage::Evdev { event, category } = message {
+                    self.manager.handle_event(&self.emitter, event, category);
+                }
+            }
+            self.emitter.take(CONTROLLER_EVENT)
+                .into_iter()
+                .map(|payload| serde_json::from_value(payload).map_err(|e| format!("Bad {}: {}", CONTROLLER_EVENT, e)))
+                .collect()
+        }
+
+        pub fn current_states(&self) -> Vec<BackendControllerState> {
+            self.manager.current_states()
+        }
+
+        pub fn poison_locks(&self) {
+            self.manager.poison_locks();
+        }
+    }
+}
@@ -0,0 +1,137 @@
+use crate::locking::MutexExt;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Mutex, OnceLock};
+use std::thread::JoinHandle;
+use tracing::info;
+#[cfg(target_os = "linux")]
+use tracing::warn;
+
+/// `SCHED_RR` priority for the input threads: above every normal thread, including the
+/// webview's, but well below the kernel's and audio's realtime threads.
+pub const REALTIME_PRIORITY: i32 = 10;
+/// Niceness used when realtime scheduling isn't permitted.
+pub const INPUT_NICE: i32 = -10;
+
+/// How an input thread ended up being scheduled.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case", tag = "kind")]
+pub enum ThreadPriority {
+    /// Left alone: turned off in settings, or neither change was permitted
+    Normal,
+    Nice { nice: i32 },
+    Realtime { priority: i32 },
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct InputThreadPriority {
+    pub thread: String,
+    pub priority: ThreadPriority,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct InputPriorityStatus {
+    /// The setting; a change applies from the next start
+    pub enabled: bool,
+    pub threads: Vec<InputThreadPriority>,
+}
+
+static THREADS: OnceLock<Mutex<BTreeMap<String, ThreadPriority>>> = OnceLock::new();
+static REALTIME_DENIED: AtomicBool = AtomicBool::new(false);
+#[cfg(target_os = "linux")]
+static NICE_DENIED: AtomicBool = AtomicBool::new(false);
+
+fn threads() -> &'static Mutex<BTreeMap<String, ThreadPriority>> {
+    THREADS.get_or_init(|| Mutex::new(BTreeMap::new()))
+}
+
+/// Raises the calling thread so UI rendering can't starve input: `SCHED_RR` where allowed
+/// (root, `CAP_SYS_NICE` or an `RLIMIT_RTPRIO` grant), otherwise a negative niceness.
+/// Each kind of refusal is logged once however many threads hit it. `enabled` is false
+/// when the user turned the change off; the thread is still listed in debug info.
+pub fn raise_input_thread(thread: &str, enabled: bool) -> ThreadPriority {
+    let priority = if enabled { raise_current_thread() } else { ThreadPriority::Normal };
+    match priority {
+        ThreadPriority::Realtime { priority } => info!("{} thread running with SCHED_RR priority {}", thread, priority),
+        ThreadPriority::Nice { nice } => info!("{} thread running at niceness {}", thread, nice),
+        ThreadPriority::Normal => {}
+    }
+    threads().lock_or_recover().insert(thread.to_string(), priority);
+    priority
+}
+
+/// Spawns a thread for slow work (HTTP, file writes) from an input thread. Threads inherit
+/// their parent's scheduling, so this one drops back to normal before running `work`.
+pub fn spawn_normal<F: FnOnce() + Send + 'static>(work: F) -> JoinHandle<()> {
+    std::thread::spawn(move || {
+        reset_current_thread();
+        work();
+    })
+}
+
+/// The input threads and how each is scheduled, for debug info and benchmark reports.
+pub fn snapshot() -> Vec<InputThreadPriority> {
+    threads().lock_or_recover()
+        .iter()
+        .map(|(thread, priority)| InputThreadPriority { thread: thread.clone(), priority: *priority })
+        .collect()
+}
+
+#[cfg(target_os = "linux")]
+fn raise_current_thread() -> ThreadPriority {
+    let param = libc::sched_param { sched_priority: REALTIME_PRIORITY };
+    // pthread_self is always a valid handle for the calling thread
+    let result = unsafe { libc::pthread_setschedparam(libc::pthread_self(), libc::SCHED_RR, &param) };
+    if result == 0 {
+        return ThreadPriority::Realtime { priority: REALTIME_PRIORITY };
+    }
+    if !REALTIME_DENIED.swap(true, Ordering::Relaxed) {
+        info!("Realtime scheduling for input isn't permitted ({}); lowering niceness instead",
+              std::io::Error::from_raw_os_error(result));
+    }
+    match set_current_thread_nice(INPUT_NICE) {
+        Ok(()) => ThreadPriority::Nice { nice: INPUT_NICE },
+        Err(e) => {
+            if !NICE_DENIED.swap(true, Ordering::Relaxed) {
+                warn!("Couldn't raise the priority of the input threads ({}); they run at normal priority", e);
+            }
+            ThreadPriority::Normal
+        }
+    }
+}
+
+#[cfg(not(target_os = "linux"))]
+fn raise_current_thread() -> ThreadPriority {
+    if !REALTIME_DENIED.swap(true, Ordering::Relaxed) {
+        info!("Input thread priority is only raised on Linux");
+    }
+    ThreadPriority::Normal
+}
+
+/// Back to `SCHED_OTHER` at niceness 0; dropping priority is always permitted.
+#[cfg(target_os = "linux")]
+fn reset_current_thread() {
+    let param = libc::sched_param { sched_priority: 0 };
+    // As above, the handle is the calling thread's own
+    unsafe { libc::pthread_setschedparam(libc::pthread_self(), libc::SCHED_OTHER, &param) };
+    set_current_thread_nice(0).ok();
+}
+
+#[cfg(not(target_os = "linux"))]
+fn reset_current_thread() {}
+
+/// Linux applies niceness per thread when given a thread ID.
+#[cfg(target_os = "linux")]
+fn set_current_thread_nice(nice: i32) -> std::io::Result<()> {
+    // gettid can't fail and setpriority only reads its arguments
+    let result = unsafe {
+        let tid = libc::syscall(libc::SYS_gettid) as libc::id_t;
+        libc::setpriority(libc::PRIO_PROCESS, tid, nice)
+    };
+    if result == 0 {
+        Ok(())
+    } else {
+        Err(std::io::Error::last_os_error())
+    }
+}
@@ -0,0 +1,160 @@
+use crate::memory_stats::BufferStats;
+use std::collections::{HashMap, VecDeque};
+
+pub const DEFAULT_RETENTION_MS: u64 = 60_000;
+pub const MAX_RETENTION_MS: u64 = 60 * 60 * 1000;
+/// Samples kept per axis whatever the retention, dropping the oldest: an hour of a
+/// 1kHz axis would otherwise be 57MB on its own.
+pub const MAX_SAMPLES_PER_SERIES: usize = 100_000;
+
+/// Recent `(timestamp_ms, value)` samples per controller axis, pruned to a retention window.
+pub struct AxisTimeSeriesStore {
+    /// By controller, then axis, so a push only allocates the first time an axis is seen
+    series: HashMap<usize, HashMap<String, VecDeque<(u64, f32)>>>,
+    retention_ms: u64,
+}
+
+impl Default for AxisTimeSeriesStore {
+    fn default() -> Self {
+        Self {
+            series: HashMap::new(),
+            retention_ms: DEFAULT_RETENTION_MS,
+        }
+    }
+}
+
+impl AxisTimeSeriesStore {
+    pub fn push(&mut self, controller_id: usize, axis: &str, timestamp_ms: u64, value: f32) {
+        let cutoff = timestamp_ms.saturating_sub(self.retention_ms);
+        let axes = self.series.entry(controller_id).or_default();
+        if !axes.contains_key(axis) {
+            axes.insert(axis.to_string(), VecDeque::new());
+        }
+        let Some(samples) = axes.get_mut(axis) else {
+            return;
+        };
+        if samples.len() >= MAX_SAMPLES_PER_SERIES {
+            samples.pop_front();
+        }
+        samples.push_back((timestamp_ms, value));
+        while samples.front().is_some_and(|(t, _)| *t < cutoff) {
+            samples.pop_front();
+        }
+    }
+
+    pub fn query(
+        &self,
+        controller_id: usize,
+        axis: &str,
+        from_ms: u64,
+        to_ms: u64,
+        max_points: usize,
+    ) -> Vec<(u64, f32)> {
+        let Some(samples) = self.series.get(&controller_id).and_then(|axes| axes.get(axis)) else {
+            return Vec::new();
+        };
+
+        let in_range: Vec<(u64, f32)> = samples
+            .iter()
+            .filter(|(t, _)| *t >= from_ms && *t <= to_ms)
+            .copied()
+            .collect();
+
+        largest_triangle_three_buckets(&in_range, max_points)
+    }
+
+    pub fn set_retention(&mut self, retention_ms: u64, now_ms: u64) {
+        self.retention_ms = retention_ms;
+        let cutoff = now_ms.saturating_sub(retention_ms);
+        for axes in self.series.values_mut() {
+            for samples in axes.values_mut() {
+                while samples.front().is_some_and(|(t, _)| *t < cutoff) {
+                    samples.pop_front();
+                }
+            }
+            axes.retain(|_, samples| !samples.is_empty());
+        }
+        self.series.retain(|_, axes| !axes.is_empty());
+    }
+
+    pub fn remove_controller(&mut self, controller_id: usize) {
+        self.series.remove(&controller_id);
+    }
+
+    pub fn buffer_stats(&self) -> BufferStats {
+        let axes = self.series.values().flat_map(HashMap::values);
+        let (series, samples) = axes.fold((0, 0), |(series, samples), axis| (series + 1, samples + axis.len()));
+        BufferStats::new("axis_time_series", samples, Some(MAX_SAMPLES_PER_SERIES), series, self.memory_usage())
+    }
+
+    /// Approximate heap bytes held by the stored samples and their keys.
+    pub fn memory_usage(&self) -> usize {
+        self.series
+            .values()
+            .flat_map(HashMap::iter)
+            .map(|(axis, samples)| {
+                std::mem::size_of::<String>()
+                    + axis.capacity()
+                    + std::mem::size_of::<VecDeque<(u64, f32)>>()
+                    + samples.capacity() * std::mem::size_of::<(u64, f32)>()
+            })
+            .sum()
+    }
+}
+
+/// Downsamples `data` to at most `threshold` points while preserving its visual shape
+/// (Steinarsson's Largest-Triangle-Three-Buckets). The first and last points are always kept.
+pub fn largest_triangle_three_buckets(data: &[(u64, f32)], threshold: usize) -> Vec<(u64, f32)> {
+    if threshold == 0 || threshold >= data.len() {
+        return data.to_vec();
+    }
+    if threshold == 1 {
+        return vec![data[0]];
+    }
+    if threshold == 2 {
+        return vec![data[0], data[data.len() - 1]];
+    }
+
+    // Work in f64 relative to the first timestamp so epoch-millisecond values keep precision
+    let origin = data[0].0;
+    let x = |i: usize| (data[i].0 - origin) as f64;
+    let y = |i: usize| data[i].1 as f64;
+
+    let bucket_size = (data.len() - 2) as f64 / (threshold - 2) as f64;
+    let mut sampled = Vec::with_capacity(threshold);
+    sampled.push(data[0]);
+    let mut selected = 0;
+
+    for bucket in 0..threshold - 2 {
+        let next_start = ((bucket + 1) as f64 * bucket_size).floor() as usize + 1;
+        let next_end = (((bucket + 2) as f64 * bucket_size).floor() as usize + 1).min(data.len());
+        let (avg_x, avg_y) = if next_start < next_end {
+            let count = (next_end - next_start) as f64;
+            let sum_x: f64 = (next_start..next_end).map(x).sum();
+            let sum_y: f64 = (next_start..next_end).map(y).sum();
+            (sum_x / count, sum_y / count)
+        } else {
+            (x(data.len() - 1), y(data.len() - 1))
+        };
+
+        let start = (bucket as f64 * bucket_size).floor() as usize + 1;
+        let end = (next_start).min(data.len() - 1);
+        let (ax, ay) = (x(selected), y(selected));
+
+        let mut best = start;
+        let mut best_area = -1.0;
+        for i in start..end.max(start + 1) {
+            let area = ((ax - avg_x) * (y(i) - ay) - (ax - x(i)) * (avg_y - ay)).abs();
+            if area > best_area {
+                best_area = area;
+                best = i;
+            }
+        }
+
+        sampled.push(data[best]);
+        selected = best;
+    }
+
+    sampled.push(data[data.len() - 1]);
+    sampled
+}
@@ -0,0 +1,89 @@
+use crate::gamepad::{GamepadInput, GamepadManager};
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+/// Controller ID touch zones press their buttons on, well clear of the IDs gilrs hands out.
+pub const TOUCH_CONTROLLER_ID: usize = 1000;
+const MAX_ZONES: usize = 32;
+const MAX_NAME_LEN: usize = 32;
+
+/// A tappable rectangle on the touch overlay. Coordinates are fractions of the overlay,
+/// so zones keep their place at any window size.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TouchZone {
+    /// Also the button name its taps press
+    pub name: String,
+    pub x: f32,
+    pub y: f32,
+    pub width: f32,
+    pub height: f32,
+}
+
+pub fn validate_zones(zones: &[TouchZone]) -> Result<(), String> {
+    if zones.len() > MAX_ZONES {
+        return Err(format!("At most {} touch zones, got {}", MAX_ZONES, zones.len()));
+    }
+    let mut names = HashSet::new();
+    for zone in zones {
+        let name = zone.name.trim();
+        if name.is_empty() || name.len() > MAX_NAME_LEN {
+            return Err(format!("Touch zone names must be 1 to {} characters, got '{}'", MAX_NAME_LEN, zone.name));
+        }
+        if !names.insert(name) {
+            return Err(format!("Touch zone '{}' is defined twice", name));
+        }
+        let inside = |start: f32, size: f32| start.is_finite() && size.is_finite() && start >= 0.0 && size > 0.0 && start + size <= 1.0;
+        if !inside(zone.x, zone.width) || !inside(zone.y, zone.height) {
+            return Err(format!("Touch zone '{}' must lie within the overlay", name));
+        }
+    }
+    Ok(())
+}
+
+static TOUCH_CONNECTED: AtomicBool = AtomicBool::new(false);
+
+/// Queues a synthetic press or release on the touch controller, announcing the controller
+/// first so its state exists when the button arrives. From there it's an ordinary
+/// button to combos, ensembles and forwarding.
+pub fn inject_button(gamepad_manager: &GamepadManager, controller_id: usize, button: String, pressed: bool) {
+    if controller_id == TOUCH_CONTROLLER_ID && !TOUCH_CONNECTED.swap(true, Ordering::Relaxed) {
+        gamepad_manager.handle_input(controller_id, GamepadInput::Connected, true);
+    }
+    let input = if pressed {
+        GamepadInput::ButtonPressed { button }
+    } else {
+        GamepadInput::ButtonReleased { button }
+    };
+    gamepad_manager.handle_input(controller_id, input, true);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn zone(name: &str, x: f32, y: f32, width: f32, height: f32) -> TouchZone {
+        TouchZone { name: name.to_string(), x, y, width, height }
+    }
+
+    #[test]
+    fn zones_inside_the_overlay_are_accepted() {
+        let zones = [zone("Strobe", 0.0, 0.0, 0.5, 0.5), zone("Blackout", 0.5, 0.5, 0.5, 0.5)];
+        assert!(validate_zones(&zones).is_ok());
+        assert!(validate_zones(&[]).is_ok());
+    }
+
+    #[test]
+    fn zones_past_the_edge_or_empty_are_rejected() {
+        assert!(validate_zones(&[zone("Wide", 0.6, 0.0, 0.5, 0.2)]).is_err());
+        assert!(validate_zones(&[zone("Flat", 0.1, 0.1, 0.3, 0.0)]).is_err());
+        assert!(validate_zones(&[zone("Nan", f32::NAN, 0.1, 0.3, 0.3)]).is_err());
+    }
+
+    #[test]
+    fn names_must_be_present_and_unique() {
+        assert!(validate_zones(&[zone(" ", 0.0, 0.0, 0.2, 0.2)]).is_err());
+        let twice = [zone("Strobe", 0.0, 0.0, 0.2, 0.2), zone("Strobe", 0.5, 0.5, 0.2, 0.2)];
+        assert!(validate_zones(&twice).unwrap_err().contains("twice"));
+    }
+}
@@ -0,0 +1,145 @@
+#[cfg(target_os = "linux")]
+use evdev::{AbsoluteAxisType, Device};
+use serde::{Deserialize, Serialize};
+#[cfg(target_os = "linux")]
+use std::collections::HashMap;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TouchpadSide {
+    Left,
+    Right,
+}
+
+impl TouchpadSide {
+    /// Which pad a multitouch node belongs to, from its name; the Deck's right pad is
+    /// the one named for it, anything else is treated as the left.
+    fn from_device_name(name: &str) -> Self {
+        if name.to_lowercase().contains("right") {
+            TouchpadSide::Right
+        } else {
+            TouchpadSide::Left
+        }
+    }
+}
+
+/// Range and resolution of an absolute axis, as cached when the device was opened.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AbsInfo {
+    pub minimum: i32,
+    pub maximum: i32,
+    pub fuzz: i32,
+    pub flat: i32,
+    pub resolution: i32,
+}
+
+impl AbsInfo {
+    /// Scales `value` to -1.0..1.0 across the axis' range.
+    fn normalize(&self, value: i32) -> f32 {
+        let span = (self.maximum - self.minimum).max(1) as f32;
+        ((value - self.minimum) as f32 / span * 2.0 - 1.0).clamp(-1.0, 1.0)
+    }
+}
+
+/// Payload of `touchpad-contact`: one finger on a pad, sent whenever it moves, lands or lifts.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TouchpadContact {
+    pub device_path: String,
+    pub side: TouchpadSide,
+    pub slot: i32,
+    /// False once the finger has lifted; the position is where it was last seen
+    pub active: bool,
+    pub x: i32,
+    pub y: i32,
+    pub normalized_x: f32,
+    pub normalized_y: f32,
+    pub distance_from_center: f32,
+    /// Radians, counterclockwise from the +x axis
+    pub angle_from_center: f32,
+}
+
+#[derive(Default, Clone, Copy)]
+struct SlotState {
+    active: bool,
+    x: i32,
+    y: i32,
+}
+
+/// Follows the kernel's multitouch slot protocol for one touchpad node.
+#[cfg(target_os = "linux")]
+pub struct TouchSlotTracker {
+    side: TouchpadSide,
+    x_info: AbsInfo,
+    y_info: AbsInfo,
+    current_slot: i32,
+    slots: HashMap<i32, SlotState>,
+}
+
+#[cfg(target_os = "linux")]
+impl TouchSlotTracker {
+    /// A tracker for `device` if it reports multitouch positions.
+    pub fn for_device(device: &Device) -> Option<Self> {
+        let abs_state = device.get_abs_state().ok()?;
+        let axes = device.supported_absolute_axes()?;
+        let read = |axis: AbsoluteAxisType| {
+            if !axes.contains(axis) {
+                return None;
+            }
+            let info = abs_state.get(axis.0 as usize)?;
+            Some(AbsInfo {
+                minimum: info.minimum,
+                maximum: info.maximum,
+                fuzz: info.fuzz,
+                flat: info.flat,
+                resolution: info.resolution,
+            })
+        };
+        Some(Self {
+            side: TouchpadSide::from_device_name(device.name().unwrap_or("")),
+            x_info: read(AbsoluteAxisType::ABS_MT_POSITION_X)?,
+            y_info: read(AbsoluteAxisType::ABS_MT_POSITION_Y)?,
+            current_slot: 0,
+            slots: HashMap::new(),
+        })
+    }
+
+    pub fn side(&self) -> TouchpadSide {
+        self.side
+    }
+
+    /// Cached (x, y) position axis info.
+    pub fn abs_info(&self) -> (AbsInfo, AbsInfo) {
+        (self.x_info, self.y_info)
+    }
+
+    /// Feeds one absolute event, returning the contact it changed, if any.
+    pub fn handle(&mut self, device_path: &str, code: u16, value: i32) -> Option<TouchpadContact> {
+        let slot = self.slots.entry(self.current_slot).or_default();
+        match AbsoluteAxisType(code) {
+            AbsoluteAxisType::ABS_MT_SLOT => {
+                self.current_slot = value;
+                return None;
+            }
+            // The kernel sends -1 when the finger lifts
+            AbsoluteAxisType::ABS_MT_TRACKING_ID => slot.active = value >= 0,
+            AbsoluteAxisType::ABS_MT_POSITION_X => slot.x = value,
+            AbsoluteAxisType::ABS_MT_POSITION_Y => slot.y = value,
+            _ => return None,
+        }
+        let slot = *slot;
+        let normalized_x = self.x_info.normalize(slot.x);
+        let normalized_y = self.y_info.normalize(slot.y);
+        Some(TouchpadContact {
+            device_path: device_path.to_string(),
+            side: self.side,
+            slot: self.current_slot,
+            active: slot.active,
+            x: slot.x,
+            y: slot.y,
+            normalized_x,
+            normalized_y,
+            distance_from_center: (normalized_x * normalized_x + normalized_y * normalized_y).sqrt(),
+            angle_from_center: normalized_y.atan2(normalized_x),
+        })
+    }
+}
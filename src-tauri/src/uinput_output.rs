@@ -0,0 +1,497 @@
+use crate::controller_event::normalize_axis;
+use crate::dead_zone::{DeadZoneShape, StickDeadZones, StickSide};
+use crate::evdev_gamepad::EvdevGamepadManager;
+use crate::key_codes;
+use crate::locking::MutexExt;
+#[cfg(not(target_os = "linux"))]
+use crate::evdev_types::EVDEV_UNSUPPORTED;
+#[cfg(target_os = "linux")]
+use evdev::uinput::{VirtualDevice, VirtualDeviceBuilder};
+#[cfg(target_os = "linux")]
+use evdev::{AbsInfo, AbsoluteAxisType, AttributeSet, BusType, EventType, InputEvent, InputId, Key, UinputAbsSetup};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::{SystemTime, UNIX_EPOCH};
+use tracing::{debug, info, warn};
+
+pub const UINPUT_PATH: &str = "/dev/uinput";
+/// Longest name uinput takes, less its terminating NUL (`UINPUT_MAX_NAME_SIZE`).
+pub const MAX_NAME_LEN: usize = 79;
+
+const EV_SYN: u16 = 0x00;
+const EV_KEY: u16 = 0x01;
+const EV_ABS: u16 = 0x03;
+const SYN_REPORT: u16 = 0x00;
+
+const ABS_X: u16 = 0x00;
+const ABS_Y: u16 = 0x01;
+const ABS_Z: u16 = 0x02;
+const ABS_RX: u16 = 0x03;
+const ABS_RY: u16 = 0x04;
+const ABS_RZ: u16 = 0x05;
+const ABS_HAT0X: u16 = 0x10;
+const ABS_HAT0Y: u16 = 0x11;
+/// hid-steam's trigger axes on the Deck: right on `ABS_HAT2X`, left on `ABS_HAT2Y`
+const ABS_HAT2X: u16 = 0x14;
+const ABS_HAT2Y: u16 = 0x15;
+
+/// The buttons of a standard gamepad, as the kernel's gamepad API lays them out: face
+/// buttons, bumpers, digital triggers, select/start/mode, stick clicks and the d-pad.
+const GAMEPAD_BUTTONS: [u16; 17] = [
+    0x130, 0x131, 0x133, 0x134, // BTN_SOUTH, BTN_EAST, BTN_NORTH, BTN_WEST
+    0x136, 0x137, 0x138, 0x139, // BTN_TL, BTN_TR, BTN_TL2, BTN_TR2
+    0x13a, 0x13b, 0x13c, // BTN_SELECT, BTN_START, BTN_MODE
+    0x13d, 0x13e, // BTN_THUMBL, BTN_THUMBR
+    0x220, 0x221, 0x222, 0x223, // BTN_DPAD_UP, BTN_DPAD_DOWN, BTN_DPAD_LEFT, BTN_DPAD_RIGHT
+];
+
+/// The axes of a standard gamepad with the ranges an Xbox pad reports them in: sticks,
+/// analog triggers and the d-pad hat.
+const GAMEPAD_AXES: [(u16, (i32, i32)); 8] = [
+    (ABS_X, (-32768, 32767)),
+    (ABS_Y, (-32768, 32767)),
+    (ABS_RX, (-32768, 32767)),
+    (ABS_RY, (-32768, 32767)),
+    (ABS_Z, (0, 255)),
+    (ABS_RZ, (0, 255)),
+    (ABS_HAT0X, (-1, 1)),
+    (ABS_HAT0Y, (-1, 1)),
+];
+
+/// Remaps applied unless the config remaps the same code: the Deck's triggers onto the
+/// standard trigger axes, which the virtual gamepad would otherwise drop.
+const DEFAULT_AXIS_REMAP: [(u16, u16); 2] = [(ABS_HAT2Y, ABS_Z), (ABS_HAT2X, ABS_RZ)];
+
+fn now_ms() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_millis() as u64
+}
+
+/// What's forwarded to the virtual gamepad, and how.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PassthroughConfig {
+    /// Event node to read, e.g. `/dev/input/event5`
+    pub source_device_path: String,
+    /// Source code to output code by kernel name, e.g. `"BTN_SOUTH": "BTN_EAST"` or
+    /// `"ABS_X": "ABS_RX"`. Buttons map onto buttons and axes onto axes.
+    #[serde(default)]
+    pub remap: HashMap<String, String>,
+    /// Dead zone applied to both of the output's sticks
+    #[serde(default)]
+    pub dead_zone: Option<DeadZoneShape>,
+    /// Grab the source node, so other consumers see only the virtual gamepad
+    #[serde(default)]
+    pub exclusive: bool,
+}
+
+/// One event to write to the virtual gamepad.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct OutputEvent {
+    pub event_type: u16,
+    pub code: u16,
+    pub value: i32,
+}
+
+fn output_axis_range(code: u16) -> Option<(i32, i32)> {
+    GAMEPAD_AXES.iter().find(|(axis, _)| *axis == code).map(|(_, range)| *range)
+}
+
+/// The gilrs-style axis name `StickDeadZones` knows an output stick axis by.
+fn stick_axis_name(code: u16) -> Option<&'static str> {
+    let (side, is_x) = match code {
+        ABS_X => (StickSide::Left, true),
+        ABS_Y => (StickSide::Left, false),
+        ABS_RX => (StickSide::Right, true),
+        ABS_RY => (StickSide::Right, false),
+        _ => return None,
+    };
+    let (x, y) = side.axes();
+    Some(if is_x { x } else { y })
+}
+
+fn stick_axis_code(name: &str) -> Option<u16> {
+    [ABS_X, ABS_Y, ABS_RX, ABS_RY].into_iter().find(|&code| stick_axis_name(code) == Some(name))
+}
+
+/// The reverse of `normalize_axis`: a normalized value in an output axis' range.
+fn scale_axis(value: f32, (minimum, maximum): (i32, i32)) -> i32 {
+    let scaled = if minimum < 0 {
+        let extent = minimum.unsigned_abs().max(maximum.unsigned_abs()) as f32;
+        value * extent
+    } else {
+        minimum as f32 + value * (maximum - minimum) as f32
+    };
+    (scaled.round() as i32).clamp(minimum, maximum)
+}
+
+/// Resolves a kernel code name to (event type, code).
+fn resolve_code(name: &str) -> Result<(u16, u16), String> {
+    if let Some((code, _)) = key_codes::all_key_codes().into_iter().find(|(_, known)| known == name) {
+        return Ok((EV_KEY, code));
+    }
+    key_codes::all_abs_codes()
+        .into_iter()
+        .find(|(_, known)| known == name)
+        .map(|(code, _)| (EV_ABS, code))
+        .ok_or_else(|| format!("Unknown button or axis {}", name))
+}
+
+/// Turns the source device's events into the virtual gamepad's: remaps codes, rescales
+/// axes to the output's ranges and applies the stick dead zone. Codes the virtual gamepad
+/// doesn't have are dropped.
+pub struct PassthroughMapper {
+    keys: HashMap<u16, u16>,
+    axes: HashMap<u16, u16>,
+    /// (minimum, maximum) of each source axis, by code
+    source_ranges: HashMap<u16, (i32, i32)>,
+    dead_zones: Option<StickDeadZones>,
+}
+
+impl PassthroughMapper {
+    pub fn new(config: &PassthroughConfig, source_ranges: HashMap<u16, (i32, i32)>) -> Result<Self, String> {
+        let mut keys = HashMap::new();
+        let mut axes: HashMap<u16, u16> = DEFAULT_AXIS_REMAP.into_iter().collect();
+        for (from, to) in &config.remap {
+            match (resolve_code(from)?, resolve_code(to)?) {
+                ((EV_KEY, from), (EV_KEY, to)) if GAMEPAD_BUTTONS.contains(&to) => {
+                    keys.insert(from, to);
+                }
+                ((EV_ABS, from), (EV_ABS, to)) if output_axis_range(to).is_some() => {
+                    axes.insert(from, to);
+                }
+                ((from_type, _), (to_type, _)) if from_type != to_type => {
+                    return Err(format!("Can't remap {} onto {}: buttons map onto buttons and axes onto axes", from, to));
+                }
+                _ => return Err(format!("The virtual gamepad has no {}", to)),
+            }
+        }
+        let dead_zones = config.dead_zone.map(|shape| {
+            let mut dead_zones = StickDeadZones::default();
+            dead_zones.set_shape(0, StickSide::Left, shape);
+            dead_zones.set_shape(0, StickSide::Right, shape);
+            dead_zones
+        });
+        Ok(Self { keys, axes, source_ranges, dead_zones })
+    }
+
+    /// The output events for one source event; none if the virtual gamepad has nothing
+    /// it maps onto.
+    pub fn map(&mut self, event_type: u16, code: u16, value: i32) -> Vec<OutputEvent> {
+        match event_type {
+            EV_KEY => {
+                let code = self.keys.get(&code).copied().unwrap_or(code);
+                if !GAMEPAD_BUTTONS.contains(&code) {
+                    return Vec::new();
+                }
+                vec![OutputEvent { event_type: EV_KEY, code, value }]
+            }
+            EV_ABS => {
+                let target = self.axes.get(&code).copied().unwrap_or(code);
+                let Some(output_range) = output_axis_range(target) else {
+                    return Vec::new();
+                };
+                // A source axis without a known range is taken to be in the output's
+                let normalized = normalize_axis(value, self.source_ranges.get(&code).copied().unwrap_or(output_range));
+                match (self.dead_zones.as_mut(), stick_axis_name(target)) {
+                    (Some(dead_zones), Some(axis)) => dead_zones.apply(0, axis, normalized)
+                        .into_iter()
+                        .filter_map(|(axis, value)| {
+                            let code = stick_axis_code(&axis)?;
+                            Some(OutputEvent { event_type: EV_ABS, code, value: scale_axis(value, output_axis_range(code)?) })
+                        })
+                        .collect(),
+                    _ => vec![OutputEvent { event_type: EV_ABS, code: target, value: scale_axis(normalized, output_range) }],
+                }
+            }
+            _ => Vec::new(),
+        }
+    }
+}
+
+#[cfg(target_os = "linux")]
+fn build_device(name: &str) -> std::io::Result<VirtualDevice> {
+    let mut keys = AttributeSet::<Key>::new();
+    for code in GAMEPAD_BUTTONS {
+        keys.insert(Key::new(code));
+    }
+    let mut builder = VirtualDeviceBuilder::new()?
+        .name(name)
+        .input_id(InputId::new(BusType::BUS_VIRTUAL, 0, 0, 1))
+        .with_keys(&keys)?;
+    for (code, (minimum, maximum)) in GAMEPAD_AXES {
+        // No fuzz or flat: the passthrough has already applied the dead zones it was asked for
+        let info = AbsInfo::new(0, minimum, maximum, 0, 0, 0);
+        builder = builder.with_absolute_axis(&UinputAbsSetup::new(AbsoluteAxisType(code), info))?;
+    }
+    builder.build()
+}
+
+/// A passthrough in progress. Dropping it lets go of the source node's grab.
+struct Passthrough {
+    config: PassthroughConfig,
+    mapper: PassthroughMapper,
+    /// Output events of the source's current report, sent at its `SYN_REPORT`
+    pending: Vec<OutputEvent>,
+    manager: Arc<EvdevGamepadManager>,
+}
+
+impl Drop for Passthrough {
+    fn drop(&mut self) {
+        if self.config.exclusive {
+            // Fails only for a source that's gone, which took its grab with it
+            let _ = self.manager.set_passthrough_grab(None);
+        }
+        info!("Stopped passing {} through to the virtual gamepad", self.config.source_device_path);
+    }
+}
+
+/// The uinput device. The kernel removes it once its descriptor closes, so dropping this,
+/// or the process ending by any means, tears it down.
+struct VirtualGamepad {
+    name: String,
+    created_at: u64,
+    #[cfg(target_os = "linux")]
+    device: VirtualDevice,
+    passthrough: Option<Passthrough>,
+    forwarded_events: u64,
+    write_errors: u64,
+}
+
+impl Drop for VirtualGamepad {
+    fn drop(&mut self) {
+        // The passthrough goes first, so the source isn't left grabbed with nothing to read it
+        self.passthrough = None;
+        info!("Destroyed virtual gamepad {}", self.name);
+    }
+}
+
+/// Answer to the virtual gamepad commands, and part of debug info.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct VirtualGamepadStatus {
+    pub active: bool,
+    pub name: Option<String>,
+    /// Milliseconds since the epoch
+    pub created_at: Option<u64>,
+    pub passthrough: Option<PassthroughConfig>,
+    /// Events written to the virtual gamepad since it was created
+    pub forwarded_events: u64,
+    pub write_errors: u64,
+}
+
+/// The app's one virtual gamepad, if it has made one.
+#[derive(Default)]
+pub struct UinputOutput {
+    gamepad: Mutex<Option<VirtualGamepad>>,
+}
+
+impl UinputOutput {
+    /// Creates the virtual gamepad, replacing any the app made before. `name` is at most
+    /// `MAX_NAME_LEN` bytes.
+    #[cfg(target_os = "linux")]
+    pub fn create(&self, name: &str) -> Result<VirtualGamepadStatus, String> {
+        let mut gamepad = self.gamepad.lock_or_recover();
+        // Dropped first, so a replacement with the same name isn't mistaken for the old one
+        *gamepad = None;
+        let device = build_device(name).map_err(|e| format!("Failed to create the virtual gamepad through {}: {}", UINPUT_PATH, e))?;
+        info!("Created virtual gamepad {}", name);
+        *gamepad = Some(VirtualGamepad {
+            name: name.to_string(),
+            created_at: now_ms(),
+            device,
+            passthrough: None,
+            forwarded_events: 0,
+            write_errors: 0,
+        });
+        drop(gamepad);
+        Ok(self.status())
+    }
+
+    #[cfg(not(target_os = "linux"))]
+    pub fn create(&self, _name: &str) -> Result<VirtualGamepadStatus, String> {
+        Err(EVDEV_UNSUPPORTED.to_string())
+    }
+
+    /// Destroys the virtual gamepad, ending any passthrough. Returns whether there was one.
+    pub fn destroy(&self) -> bool {
+        self.gamepad.lock_or_recover().take().is_some()
+    }
+
+    /// Starts forwarding `config.source_device_path` to the virtual gamepad through
+    /// `mapper`, in place of any passthrough before. With `config.exclusive`, the source is
+    /// grabbed until the passthrough ends.
+    pub fn start_passthrough(
+        &self,
+        config: PassthroughConfig,
+        mapper: PassthroughMapper,
+        manager: Arc<EvdevGamepadManager>,
+    ) -> Result<VirtualGamepadStatus, String> {
+        let mut gamepad = self.gamepad.lock_or_recover();
+        let Some(gamepad) = gamepad.as_mut() else {
+            return Err("Create the virtual gamepad first".to_string());
+        };
+        // The old passthrough releases its grab before the new one takes one
+        gamepad.passthrough = None;
+        if config.exclusive {
+            manager.set_passthrough_grab(Some(&config.source_device_path))?;
+        }
+        info!("Passing {} through to virtual gamepad {}", config.source_device_path, gamepad.name);
+        gamepad.passthrough = Some(Passthrough { config, mapper, pending: Vec::new(), manager });
+        Ok(status_of(Some(&*gamepad)))
+    }
+
+    pub fn stop_passthrough(&self) -> VirtualGamepadStatus {
+        let mut gamepad = self.gamepad.lock_or_recover();
+        if let Some(gamepad) = gamepad.as_mut() {
+            gamepad.passthrough = None;
+        }
+        status_of(gamepad.as_ref())
+    }
+
+    /// Node being passed through, checked by the evdev poll loop once per pass.
+    pub fn source_path(&self) -> Option<String> {
+        self.gamepad.lock_or_recover()
+            .as_ref()
+            .and_then(|gamepad| gamepad.passthrough.as_ref())
+            .map(|passthrough| passthrough.config.source_device_path.clone())
+    }
+
+    /// Name of the virtual gamepad, so the app can leave its own output out of its input.
+    pub fn name(&self) -> Option<String> {
+        self.gamepad.lock_or_recover().as_ref().map(|gamepad| gamepad.name.clone())
+    }
+
+    /// Writes the source's events out as (type, code, value), a frame per `SYN_REPORT`.
+    /// Events after the last report wait for the next call.
+    pub fn forward(&self, events: &[(u16, u16, i32)]) {
+        let mut gamepad = self.gamepad.lock_or_recover();
+        let Some(gamepad) = gamepad.as_mut() else {
+            return;
+        };
+        let Some(passthrough) = gamepad.passthrough.as_mut() else {
+            return;
+        };
+        for &(event_type, code, value) in events {
+            if event_type != EV_SYN {
+                let mapped = passthrough.mapper.map(event_type, code, value);
+                passthrough.pending.extend(mapped);
+                continue;
+            }
+            if code != SYN_REPORT || passthrough.pending.is_empty() {
+                continue;
+            }
+            let frame: Vec<OutputEvent> = passthrough.pending.drain(..).collect();
+            #[cfg(target_os = "linux")]
+            {
+                let events: Vec<InputEvent> = frame.iter()
+                    .map(|event| InputEvent::new(EventType(event.event_type), event.code, event.value))
+                    .collect();
+                // emit adds the SYN_REPORT
+                match gamepad.device.emit(&events) {
+                    Ok(()) => gamepad.forwarded_events += events.len() as u64,
+                    Err(e) => {
+                        gamepad.write_errors += 1;
+                        if gamepad.write_errors == 1 {
+                            warn!("Failed to write to virtual gamepad {}: {}", gamepad.name, e);
+                        } else {
+                            debug!("Failed to write to virtual gamepad {}: {}", gamepad.name, e);
+                        }
+                    }
+                }
+            }
+            #[cfg(not(target_os = "linux"))]
+            drop(frame);
+        }
+    }
+
+    pub fn status(&self) -> VirtualGamepadStatus {
+        status_of(self.gamepad.lock_or_recover().as_ref())
+    }
+}
+
+fn status_of(gamepad: Option<&VirtualGamepad>) -> VirtualGamepadStatus {
+    let Some(gamepad) = gamepad else {
+        return VirtualGamepadStatus::default();
+    };
+    VirtualGamepadStatus {
+        active: true,
+        name: Some(gamepad.name.clone()),
+        created_at: Some(gamepad.created_at),
+        passthrough: gamepad.passthrough.as_ref().map(|passthrough| passthrough.config.clone()),
+        forwarded_events: gamepad.forwarded_events,
+        write_errors: gamepad.write_errors,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const BTN_SOUTH: u16 = 0x130;
+    const BTN_EAST: u16 = 0x131;
+
+    fn config(remap: &[(&str, &str)], dead_zone: Option<DeadZoneShape>) -> PassthroughConfig {
+        PassthroughConfig {
+            source_device_path: "/dev/input/event99".to_string(),
+            remap: remap.iter().map(|(from, to)| (from.to_string(), to.to_string())).collect(),
+            dead_zone,
+            exclusive: false,
+        }
+    }
+
+    /// A Deck-like source: sticks in -32767..32767 and triggers in 0..32767.
+    fn deck_ranges() -> HashMap<u16, (i32, i32)> {
+        HashMap::from([(ABS_X, (-32767, 32767)), (ABS_Y, (-32767, 32767)), (ABS_RX, (-32767, 32767)), (ABS_HAT2Y, (0, 32767))])
+    }
+
+    fn key(code: u16, value: i32) -> OutputEvent {
+        OutputEvent { event_type: EV_KEY, code, value }
+    }
+
+    fn abs(code: u16, value: i32) -> OutputEvent {
+        OutputEvent { event_type: EV_ABS, code, value }
+    }
+
+    #[test]
+    fn buttons_pass_through_and_follow_the_remap() {
+        let mut mapper = PassthroughMapper::new(&config(&[("BTN_SOUTH", "BTN_EAST")], None), deck_ranges()).unwrap();
+        assert_eq!(mapper.map(EV_KEY, BTN_SOUTH, 1), [key(BTN_EAST, 1)]);
+        assert_eq!(mapper.map(EV_KEY, BTN_EAST, 0), [key(BTN_EAST, 0)]);
+        // A back grip isn't a standard gamepad button
+        assert!(mapper.map(EV_KEY, 0x224, 1).is_empty());
+
+        let mut mapper = PassthroughMapper::new(&config(&[("L4", "BTN_SOUTH")], None), deck_ranges()).unwrap();
+        assert_eq!(mapper.map(EV_KEY, 0x224, 1), [key(BTN_SOUTH, 1)]);
+    }
+
+    #[test]
+    fn axes_are_rescaled_to_the_output_ranges() {
+        let mut mapper = PassthroughMapper::new(&config(&[("ABS_X", "ABS_RX")], None), deck_ranges()).unwrap();
+        assert_eq!(mapper.map(EV_ABS, ABS_X, 32767), [abs(ABS_RX, 32767)]);
+        assert_eq!(mapper.map(EV_ABS, ABS_X, -32767), [abs(ABS_RX, -32768)]);
+        // The Deck's trigger lands on the standard trigger axis by default
+        assert_eq!(mapper.map(EV_ABS, ABS_HAT2Y, 32767), [abs(ABS_Z, 255)]);
+        assert_eq!(mapper.map(EV_ABS, ABS_HAT2Y, 0), [abs(ABS_Z, 0)]);
+        // Touchpad positions have nowhere to go
+        assert!(mapper.map(EV_ABS, 0x35, 100).is_empty());
+    }
+
+    #[test]
+    fn the_dead_zone_applies_to_both_axes_of_a_stick() {
+        let mut mapper = PassthroughMapper::new(&config(&[], Some(DeadZoneShape::Circular)), deck_ranges()).unwrap();
+        assert_eq!(mapper.map(EV_ABS, ABS_X, 32767 / 20), [abs(ABS_X, 0)]);
+        let events = mapper.map(EV_ABS, ABS_Y, 32767 / 2);
+        assert!(events[0].code == ABS_Y && (events[0].value - 16384).abs() <= 1, "{:?}", events);
+        assert!(events.contains(&abs(ABS_X, 1638)), "x leaves the dead zone with y: {:?}", events);
+    }
+
+    #[test]
+    fn remaps_the_virtual_gamepad_cant_carry_are_refused() {
+        let refused = |remap: &[(&str, &str)]| PassthroughMapper::new(&config(remap, None), HashMap::new()).err().unwrap_or_default();
+        assert!(refused(&[("BTN_SOUTH", "ABS_X")]).contains("buttons map onto buttons"));
+        assert!(refused(&[("BTN_SOUTH", "KEY_A")]).contains("no KEY_A"));
+        assert!(refused(&[("BTN_NOPE", "BTN_SOUTH")]).contains("Unknown"));
+    }
+}
@@ -0,0 +1,62 @@
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fmt::Debug;
+
+/// Distinct event kinds tracked; later kinds are only counted in `overflowed`.
+const MAX_KINDS: usize = 32;
+
+/// One kind of gilrs event the pipeline ignores, for deciding what to handle next.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UnhandledEventKind {
+    /// Variant name, e.g. `ButtonChanged`
+    pub kind: String,
+    pub count: u64,
+    pub first_seen: u64,
+    pub last_seen: u64,
+    pub last_controller_id: usize,
+    /// Debug rendering of the first event of this kind
+    pub sample: String,
+}
+
+#[derive(Default)]
+pub struct UnhandledEvents {
+    kinds: BTreeMap<String, UnhandledEventKind>,
+    overflowed: u64,
+}
+
+impl UnhandledEvents {
+    pub fn record<E: Debug>(&mut self, controller_id: usize, event: &E, now_ms: u64) {
+        let sample = format!("{:?}", event);
+        let kind = sample.split(['(', ' ', '{']).next().unwrap_or_default().to_string();
+        if let Some(entry) = self.kinds.get_mut(&kind) {
+            entry.count += 1;
+            entry.last_seen = now_ms;
+            entry.last_controller_id = controller_id;
+            return;
+        }
+        if self.kinds.len() >= MAX_KINDS {
+            self.overflowed += 1;
+            return;
+        }
+        self.kinds.insert(kind.clone(), UnhandledEventKind {
+            kind,
+            count: 1,
+            first_seen: now_ms,
+            last_seen: now_ms,
+            last_controller_id: controller_id,
+            sample,
+        });
+    }
+
+    /// Kinds seen, most frequent first.
+    pub fn snapshot(&self) -> Vec<UnhandledEventKind> {
+        let mut kinds: Vec<UnhandledEventKind> = self.kinds.values().cloned().collect();
+        kinds.sort_by_key(|kind| std::cmp::Reverse(kind.count));
+        kinds
+    }
+
+    /// Events dropped because `MAX_KINDS` kinds were already tracked.
+    pub fn overflowed(&self) -> u64 {
+        self.overflowed
+    }
+}
@@ -0,0 +1,314 @@
+use crate::evdev_types::EvdevGamepadInfo;
+use crate::gamepad::GamepadInfo;
+use crate::locking::MutexExt;
+use crate::uinput_output::UinputOutput;
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+#[cfg(target_os = "linux")]
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use tracing::info;
+
+const VALVE_VENDOR_ID: u16 = 0x28de;
+
+/// Which side of a Steam virtual pad and the controller feeding it gets to send input.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum VirtualPadPolicy {
+    /// Drops the virtual pad's input while its physical controller is visible
+    PreferPhysical,
+    /// Drops the physical controller's input while Steam feeds a virtual pad from it
+    PreferVirtual,
+    /// Both send input, so every press arrives twice
+    #[default]
+    ShowBoth,
+}
+
+/// A Steam virtual pad and the physical controller it most likely mirrors.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct VirtualPadLink {
+    pub virtual_path: String,
+    pub virtual_name: String,
+    /// `None` when no physical gamepad is visible, e.g. hid-steam dropped it for Steam
+    pub physical_path: Option<String>,
+    pub physical_name: Option<String>,
+}
+
+/// Whether an event node was made through uinput rather than by a driver for real
+/// hardware: those have no physical parent and live under `/sys/devices/virtual`.
+#[cfg(target_os = "linux")]
+pub fn sysfs_is_virtual(event_name: &str) -> bool {
+    let device = Path::new("/sys/class/input").join(event_name).join("device");
+    std::fs::canonicalize(device).is_ok_and(|path| path.starts_with("/sys/devices/virtual"))
+}
+
+/// Pairs each Steam virtual pad with the physical gamepad feeding it: the Deck's own
+/// controller, else the only Valve controller, else the only physical gamepad. Steam
+/// doesn't say which it mirrors, so with several candidates the pad is left unlinked.
+pub fn link_pads(devices: &[EvdevGamepadInfo]) -> Vec<VirtualPadLink> {
+    let physical: Vec<&EvdevGamepadInfo> = devices.iter()
+        .filter(|device| device.is_gamepad && !device.is_virtual)
+        .collect();
+    let valve: Vec<&EvdevGamepadInfo> = physical.iter()
+        .copied()
+        .filter(|device| device.vendor_id == Some(VALVE_VENDOR_ID))
+        .collect();
+    // The built-in controller's keyboard and mouse nodes are Valve's too; its gamepad
+    // node is the one with the back grips
+    let source = valve.iter()
+        .copied()
+        .find(|device| device.has_back_grips())
+        .or_else(|| only(&valve))
+        .or_else(|| only(&physical));
+    devices.iter()
+        .filter(|device| device.is_virtual && device.is_steam_virtual())
+        .map(|device| VirtualPadLink {
+            virtual_path: device.device_path.clone(),
+            virtual_name: device.name.clone(),
+            physical_path: source.map(|source| source.device_path.clone()),
+            physical_name: source.map(|source| source.name.clone()),
+        })
+        .collect()
+}
+
+fn only<'a>(candidates: &[&'a EvdevGamepadInfo]) -> Option<&'a EvdevGamepadInfo> {
+    match candidates {
+        [only] => Some(*only),
+        _ => None,
+    }
+}
+
+fn same_pad(gamepad: &GamepadInfo, device: &EvdevGamepadInfo) -> bool {
+    // gilrs reports the node's own name, so name and IDs together pick out one node
+    gamepad.name == device.name && gamepad.vendor_id == device.vendor_id && gamepad.product_id == device.product_id
+}
+
+/// Capability hash of the evdev node behind a gilrs gamepad, when there is one.
+pub fn capability_hash_of(gamepad: &GamepadInfo, devices: &[EvdevGamepadInfo]) -> Option<String> {
+    devices.iter()
+        .find(|device| same_pad(gamepad, device) && !device.capability_hash.is_empty())
+        .map(|device| device.capability_hash.clone())
+}
+
+/// Marks the gilrs gamepads that are Steam virtual pads, and names the controller each
+/// one mirrors.
+pub fn tag_gamepads(gamepads: &mut [GamepadInfo], devices: &[EvdevGamepadInfo]) {
+    let links = link_pads(devices);
+    for gamepad in gamepads.iter_mut() {
+        let node = devices.iter().find(|device| same_pad(gamepad, device));
+        gamepad.is_virtual = node.is_some_and(|device| device.is_virtual && device.is_steam_virtual())
+            || (node.is_none() && gamepad.name.contains("Steam Virtual"));
+        gamepad.physical_source = node
+            .and_then(|device| links.iter().find(|link| link.virtual_path == device.device_path))
+            .and_then(|link| link.physical_name.clone());
+        gamepad.capability_hash = capability_hash_of(gamepad, devices);
+    }
+}
+
+#[derive(Default)]
+struct Suppressed {
+    evdev: HashSet<String>,
+    gilrs: HashSet<usize>,
+}
+
+/// The policy and the devices it currently silences, checked for every input event.
+pub struct VirtualPadFilter {
+    policy: Mutex<VirtualPadPolicy>,
+    suppressed: Mutex<Suppressed>,
+    /// Lets the hot path skip the lock while nothing is suppressed
+    active: AtomicBool,
+    /// Set by background input while the window isn't focused; overrides the policy
+    prefer_physical: AtomicBool,
+    /// The app's own virtual gamepad, always silenced
+    virtual_gamepad: Arc<UinputOutput>,
+}
+
+impl VirtualPadFilter {
+    pub fn new(virtual_gamepad: Arc<UinputOutput>) -> Self {
+        Self {
+            policy: Mutex::new(VirtualPadPolicy::default()),
+            suppressed: Mutex::new(Suppressed::default()),
+            active: AtomicBool::new(false),
+            prefer_physical: AtomicBool::new(false),
+            virtual_gamepad,
+        }
+    }
+
+    pub fn policy(&self) -> VirtualPadPolicy {
+        *self.policy.lock_or_recover()
+    }
+
+    /// Takes effect on the next [`refresh`](Self::refresh).
+    pub fn set_policy(&self, policy: VirtualPadPolicy) {
+        let mut current = self.policy.lock_or_recover();
+        if *current != policy {
+            info!("Virtual pad policy: {:?}", policy);
+            *current = policy;
+        }
+    }
+
+    /// Prefers the physical controller whatever the policy, until cleared. Takes effect on
+    /// the next [`refresh`](Self::refresh).
+    pub fn set_prefer_physical(&self, prefer: bool) {
+        self.prefer_physical.store(prefer, Ordering::Relaxed);
+    }
+
+    /// Works out which devices the policy silences. Only linked pairs are affected: a
+    /// virtual pad on its own, or a controller Steam isn't mirroring, always sends input.
+    /// The app's own virtual gamepad is always silenced.
+    pub fn refresh(&self, devices: &[EvdevGamepadInfo], gamepads: &[GamepadInfo]) {
+        let policy = if self.prefer_physical.load(Ordering::Relaxed) {
+            VirtualPadPolicy::PreferPhysical
+        } else {
+            self.policy()
+        };
+        let mut suppressed = Suppressed::default();
+        if policy != VirtualPadPolicy::ShowBoth {
+            for link in link_pads(devices) {
+                let Some(physical_path) = link.physical_path else {
+                    continue;
+                };
+                let path = match policy {
+                    VirtualPadPolicy::PreferPhysical => link.virtual_path,
+                    _ => physical_path,
+                };
+                if let Some(device) = devices.iter().find(|device| device.device_path == path) {
+                    suppressed.gilrs.extend(gamepads.iter().filter(|gamepad| same_pad(gamepad, device)).map(|gamepad| gamepad.id));
+                }
+                suppressed.evdev.insert(path);
+            }
+        }
+        // The app's own virtual gamepad would feed what it forwards back in as input
+        if let Some(own) = self.virtual_gamepad.name() {
+            suppressed.evdev.extend(devices.iter()
+                .filter(|device| device.is_virtual && device.name == own)
+                .map(|device| device.device_path.clone()));
+            suppressed.gilrs.extend(gamepads.iter().filter(|gamepad| gamepad.name == own).map(|gamepad| gamepad.id));
+        }
+        self.active.store(!suppressed.evdev.is_empty() || !suppressed.gilrs.is_empty(), Ordering::Relaxed);
+        *self.suppressed.lock_or_recover() = suppressed;
+    }
+
+    pub fn evdev_suppressed(&self, device_path: &str) -> bool {
+        self.active.load(Ordering::Relaxed) && self.suppressed.lock_or_recover().evdev.contains(device_path)
+    }
+
+    pub fn gilrs_suppressed(&self, controller_id: usize) -> bool {
+        self.active.load(Ordering::Relaxed) && self.suppressed.lock_or_recover().gilrs.contains(&controller_id)
+    }
+
+    /// Event nodes whose input is being dropped.
+    pub fn suppressed_paths(&self) -> Vec<String> {
+        let mut paths: Vec<String> = self.suppressed.lock_or_recover().evdev.iter().cloned().collect();
+        paths.sort();
+        paths
+    }
+}
+
+/// Answer to `get_virtual_pads` and `set_virtual_pad_policy`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct VirtualPadStatus {
+    pub policy: VirtualPadPolicy,
+    /// Background input is preferring the physical controller over the policy
+    pub background_override: bool,
+    pub links: Vec<VirtualPadLink>,
+    /// Event nodes whose input the policy is dropping
+    pub ignored_devices: Vec<String>,
+}
+
+impl VirtualPadStatus {
+    pub fn collect(filter: &VirtualPadFilter, devices: &[EvdevGamepadInfo]) -> Self {
+        Self {
+            policy: filter.policy(),
+            background_override: filter.prefer_physical.load(Ordering::Relaxed),
+            links: link_pads(devices),
+            ignored_devices: filter.suppressed_paths(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pad(event: &str, name: &str, ids: (u16, u16), is_virtual: bool) -> EvdevGamepadInfo {
+        EvdevGamepadInfo {
+            device_path: format!("/dev/input/{}", event),
+            name: name.to_string(),
+            vendor_id: Some(ids.0),
+            product_id: Some(ids.1),
+            version: None,
+            is_gamepad: true,
+            capabilities: Vec::new(),
+            capability_hash: String::new(),
+            accessible: true,
+            fd_errors: 0,
+            is_virtual,
+        }
+    }
+
+    fn steam_virtual() -> EvdevGamepadInfo {
+        pad("event20", "Steam Virtual Gamepad", (0x28de, 0x11ff), true)
+    }
+
+    #[test]
+    fn links_the_virtual_pad_to_the_only_physical_controller() {
+        let devices = [pad("event5", "8BitDo Pro 2", (0x2dc8, 0x6003), false), steam_virtual()];
+        let links = link_pads(&devices);
+        assert_eq!(links.len(), 1);
+        assert_eq!(links[0].virtual_path, "/dev/input/event20");
+        assert_eq!(links[0].physical_name.as_deref(), Some("8BitDo Pro 2"));
+    }
+
+    #[test]
+    fn an_uinput_xbox_pad_counts_as_steam_virtual_but_a_real_one_does_not() {
+        let emulated = pad("event21", "Microsoft X-Box 360 pad 0", (0x045e, 0x028e), true);
+        let real = pad("event6", "Microsoft X-Box 360 pad", (0x045e, 0x028e), false);
+        assert!(emulated.is_steam_virtual());
+        assert!(!real.is_steam_virtual());
+        let links = link_pads(&[real, emulated]);
+        assert_eq!(links.len(), 1);
+        assert_eq!(links[0].physical_path.as_deref(), Some("/dev/input/event6"));
+    }
+
+    #[test]
+    fn several_candidates_leave_the_pad_unlinked() {
+        let devices = [
+            pad("event5", "8BitDo Pro 2", (0x2dc8, 0x6003), false),
+            pad("event6", "Wireless Controller", (0x054c, 0x09cc), false),
+            steam_virtual(),
+        ];
+        assert_eq!(link_pads(&devices)[0].physical_path, None);
+    }
+
+    #[test]
+    fn the_policy_silences_one_side_of_a_linked_pair() {
+        let devices = [pad("event5", "8BitDo Pro 2", (0x2dc8, 0x6003), false), steam_virtual()];
+        let filter = VirtualPadFilter::new(Arc::default());
+
+        filter.set_policy(VirtualPadPolicy::ShowBoth);
+        filter.refresh(&devices, &[]);
+        assert!(!filter.evdev_suppressed("/dev/input/event5"));
+        assert!(!filter.evdev_suppressed("/dev/input/event20"));
+
+        filter.set_policy(VirtualPadPolicy::PreferPhysical);
+        filter.refresh(&devices, &[]);
+        assert!(filter.evdev_suppressed("/dev/input/event20"));
+        assert!(!filter.evdev_suppressed("/dev/input/event5"));
+
+        filter.set_policy(VirtualPadPolicy::PreferVirtual);
+        filter.refresh(&devices, &[]);
+        assert!(filter.evdev_suppressed("/dev/input/event5"));
+        assert!(!filter.evdev_suppressed("/dev/input/event20"));
+
+        // Background input prefers the physical controller whatever the policy
+        filter.set_prefer_physical(true);
+        filter.refresh(&devices, &[]);
+        assert!(filter.evdev_suppressed("/dev/input/event20"));
+        assert!(!filter.evdev_suppressed("/dev/input/event5"));
+        filter.set_prefer_physical(false);
+        filter.refresh(&devices, &[]);
+        assert!(filter.evdev_suppressed("/dev/input/event5"));
+    }
+}
@@ -0,0 +1,73 @@
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::time::{Duration, Instant};
+
+/// Buttons that, held together on one controller, bring the hidden window back. For game
+/// mode, where there's no tray to do it from.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ShowWindowChord {
+    /// gilrs button names; empty turns the chord off
+    pub buttons: Vec<String>,
+    pub hold_ms: u64,
+}
+
+impl Default for ShowWindowChord {
+    fn default() -> Self {
+        Self {
+            buttons: vec!["Select".to_string(), "Start".to_string()],
+            hold_ms: 1500,
+        }
+    }
+}
+
+/// Watches held buttons for the show-window chord. Like combinations, it fires once per
+/// hold and has to be released before it fires again.
+#[derive(Default)]
+pub struct ChordTracker {
+    chord: ShowWindowChord,
+    pressed: HashMap<usize, HashSet<String>>,
+    completed_at: HashMap<usize, Instant>,
+    fired: HashSet<usize>,
+}
+
+impl ChordTracker {
+    pub fn set_chord(&mut self, chord: ShowWindowChord) {
+        self.chord = chord;
+        self.completed_at.clear();
+        self.fired.clear();
+    }
+
+    pub fn button_changed(&mut self, controller_id: usize, button: &str, pressed: bool) {
+        let held = self.pressed.entry(controller_id).or_default();
+        if pressed {
+            held.insert(button.to_string());
+        } else {
+            held.remove(button);
+        }
+        let complete = !self.chord.buttons.is_empty() && self.chord.buttons.iter().all(|b| held.contains(b));
+        if complete {
+            self.completed_at.entry(controller_id).or_insert_with(Instant::now);
+        } else {
+            self.completed_at.remove(&controller_id);
+            self.fired.remove(&controller_id);
+        }
+    }
+
+    pub fn controller_disconnected(&mut self, controller_id: usize) {
+        self.pressed.remove(&controller_id);
+        self.completed_at.remove(&controller_id);
+        self.fired.remove(&controller_id);
+    }
+
+    /// Whether the chord has now been held long enough on some controller, once per hold.
+    pub fn take_due(&mut self) -> bool {
+        let hold = Duration::from_millis(self.chord.hold_ms);
+        let due: Vec<usize> = self.completed_at.iter()
+            .filter(|(controller_id, completed_at)| completed_at.elapsed() >= hold && !self.fired.contains(controller_id))
+            .map(|(controller_id, _)| *controller_id)
+            .collect();
+        self.fired.extend(&due);
+        !due.is_empty()
+    }
+}
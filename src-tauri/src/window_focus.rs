@@ -0,0 +1,295 @@
+use crate::app_state::AppState;
+use crate::health::PollSource;
+use crate::locking::MutexExt;
+use crate::processing_pause;
+use crate::steam_deck::SessionKind;
+use serde::{Deserialize, Serialize};
+use std::sync::Mutex;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tauri::{AppHandle, Emitter, Manager};
+use tracing::{info, warn};
+
+/// How often focus and forwarding are checked against the background input setting.
+const FOCUS_CHECK_INTERVAL: Duration = Duration::from_secs(1);
+/// Checks between asking gamescope which app it has focused, as that runs `xprop`.
+const CHECKS_PER_GAMESCOPE_QUERY: u32 = 2;
+/// Root window property holding the Steam app ID of the app gamescope has focused.
+const GAMESCOPE_FOCUSED_APP: &str = "GAMESCOPE_FOCUSED_APP";
+/// Emitted with a [`WindowFocusStatus`] whenever what's done in the background changes.
+pub const BACKGROUND_INPUT_EVENT: &str = "background-input";
+
+const UNFOCUSED_WARNING: &str = "The app isn't focused, so Steam Input may hand the controller to the focused app and input may not reach the light server until the app is focused again. Set background input to read the controller through evdev to keep it working";
+const NO_EVDEV_WARNING: &str = "The app isn't focused and no physical controller can be read through evdev (Steam holds the built-in controller, or the evdev backend is off), so input may not reach the light server until the app is focused again. Close Steam or turn the evdev backend on";
+
+/// What happens to controller input while the window isn't focused and input is being
+/// forwarded.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BackgroundInput {
+    /// Input is read as usual, with a warning that it may stop arriving
+    #[default]
+    Warn,
+    /// The physical controller's evdev node is read in place of Steam's virtual pad, which
+    /// Steam Input rebinds to the focused app
+    Evdev,
+    /// As `Evdev`, and the physical nodes are grabbed so the focused app doesn't act on them too
+    EvdevGrab,
+}
+
+/// What's done about input while in the background.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BackgroundPlan {
+    /// Steam's virtual pads are dropped in favour of the physical controller
+    pub prefer_physical: bool,
+    /// The physical controller's event nodes are grabbed
+    pub grab: bool,
+    /// Why input may not arrive until the window is focused again
+    pub warning: Option<String>,
+}
+
+/// Nothing is done while focused or not forwarding. `physical_evdev` is whether a physical
+/// gamepad's evdev node is open and being read.
+pub fn plan(mode: BackgroundInput, focused: bool, forwarding: bool, physical_evdev: bool) -> BackgroundPlan {
+    if focused || !forwarding {
+        return BackgroundPlan::default();
+    }
+    match mode {
+        BackgroundInput::Warn => BackgroundPlan { warning: Some(UNFOCUSED_WARNING.to_string()), ..Default::default() },
+        _ if !physical_evdev => BackgroundPlan { warning: Some(NO_EVDEV_WARNING.to_string()), ..Default::default() },
+        mode => BackgroundPlan { prefer_physical: true, grab: mode == BackgroundInput::EvdevGrab, warning: None },
+    }
+}
+
+/// The first value of an `xprop` line such as `GAMESCOPE_FOCUSED_APP(CARDINAL) = 769`.
+pub fn parse_xprop_cardinal(output: &str) -> Option<u32> {
+    output.split_once('=')?.1.split(',').next()?.trim().parse().ok()
+}
+
+/// Steam app IDs the app was launched under: `SteamAppId`, and for a non-Steam game the
+/// shortcut's ID, which Steam puts in the top half of `SteamGameId`.
+fn own_app_ids() -> Vec<u32> {
+    let env = |name: &str| std::env::var(name).ok().and_then(|value| value.trim().parse::<u64>().ok());
+    let mut ids = Vec::new();
+    if let Some(app_id) = env("SteamAppId").filter(|&id| id != 0) {
+        ids.push(app_id as u32);
+    }
+    if let Some(game_id) = env("SteamGameId") {
+        ids.push(if game_id > u64::from(u32::MAX) { (game_id >> 32) as u32 } else { game_id as u32 });
+    }
+    ids
+}
+
+/// Whether gamescope has the app focused. `None` when Steam didn't launch the app or
+/// `xprop` can't say.
+fn gamescope_focused() -> Option<bool> {
+    let ids = own_app_ids();
+    if ids.is_empty() {
+        return None;
+    }
+    let output = std::process::Command::new("xprop").args(["-root", GAMESCOPE_FOCUSED_APP]).output().ok()?;
+    let focused_app = parse_xprop_cardinal(&String::from_utf8_lossy(&output.stdout))?;
+    Some(ids.contains(&focused_app))
+}
+
+/// Answer to `get_window_focus` and `set_background_input`, and part of debug info.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WindowFocusStatus {
+    /// Gamescope's answer where it has one, else the window's
+    pub focused: bool,
+    pub window_focused: bool,
+    /// `None` outside game mode, or when gamescope couldn't be asked
+    pub gamescope_focused: Option<bool>,
+    /// Milliseconds since the epoch
+    pub unfocused_since: Option<u64>,
+    pub background_input: BackgroundInput,
+    pub forwarding: bool,
+    pub plan: BackgroundPlan,
+    /// Physical event nodes grabbed while in the background
+    pub grabbed_devices: usize,
+}
+
+struct FocusState {
+    window_focused: bool,
+    gamescope_focused: Option<bool>,
+    unfocused_since: Option<u64>,
+    mode: BackgroundInput,
+    forwarding: bool,
+    plan: BackgroundPlan,
+    grabbed_devices: usize,
+}
+
+impl FocusState {
+    fn focused(&self) -> bool {
+        self.gamescope_focused.unwrap_or(self.window_focused)
+    }
+}
+
+/// Whether the app is focused, from Tauri's window events and, in game mode, gamescope.
+pub struct WindowFocus {
+    state: Mutex<FocusState>,
+}
+
+impl Default for WindowFocus {
+    fn default() -> Self {
+        Self {
+            state: Mutex::new(FocusState {
+                // Until the window says otherwise, so startup doesn't warn
+                window_focused: true,
+                gamescope_focused: None,
+                unfocused_since: None,
+                mode: BackgroundInput::default(),
+                forwarding: false,
+                plan: BackgroundPlan::default(),
+                grabbed_devices: 0,
+            }),
+        }
+    }
+}
+
+impl WindowFocus {
+    pub fn set_window_focused(&self, focused: bool) {
+        let mut state = self.state.lock_or_recover();
+        state.window_focused = focused;
+        note_focus(&mut state);
+    }
+
+    fn set_gamescope_focused(&self, focused: Option<bool>) {
+        let mut state = self.state.lock_or_recover();
+        state.gamescope_focused = focused;
+        note_focus(&mut state);
+    }
+
+    /// Takes effect on the monitor's next check.
+    pub fn set_background_input(&self, mode: BackgroundInput) {
+        let mut state = self.state.lock_or_recover();
+        if state.mode != mode {
+            info!("Background input: {:?}", mode);
+            state.mode = mode;
+        }
+    }
+
+    pub fn status(&self) -> WindowFocusStatus {
+        let state = self.state.lock_or_recover();
+        WindowFocusStatus {
+            focused: state.focused(),
+            window_focused: state.window_focused,
+            gamescope_focused: state.gamescope_focused,
+            unfocused_since: state.unfocused_since,
+            background_input: state.mode,
+            forwarding: state.forwarding,
+            plan: state.plan.clone(),
+            grabbed_devices: state.grabbed_devices,
+        }
+    }
+}
+
+fn note_focus(state: &mut FocusState) {
+    match (state.focused(), state.unfocused_since) {
+        (true, Some(_)) => state.unfocused_since = None,
+        (false, None) => state.unfocused_since = Some(SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default().as_millis() as u64),
+        _ => {}
+    }
+}
+
+/// Checks focus and forwarding every second, and applies the background input setting
+/// when either changes: preferring and grabbing the physical controller, or warning.
+pub fn spawn_monitor(app: AppHandle) {
+    let state = app.state::<AppState>();
+    let backends = state.backends.clone();
+    let evdev_manager = state.evdev_manager.clone();
+    let gamepad_manager = state.gamepad_manager.clone();
+    let settings = state.settings.clone();
+    let shutdown = state.shutdown.clone();
+    let filter = state.virtual_pads.clone();
+    let focus = state.window_focus.clone();
+    let game_mode = state.system_info.session_kind == SessionKind::GameMode;
+    std::thread::Builder::new()
+        .name("window-focus".to_string())
+        .spawn(move || {
+            let mut check = 0u32;
+            while !shutdown.is_requested() {
+                let forwarding = settings.get().light_server_endpoint.is_some() && !processing_pause::forwarding_paused();
+                // Steam switching apps doesn't always move the window's own focus
+                if game_mode && forwarding && check.is_multiple_of(CHECKS_PER_GAMESCOPE_QUERY) {
+                    focus.set_gamescope_focused(gamescope_focused());
+                } else if !forwarding {
+                    focus.set_gamescope_focused(None);
+                }
+                check = check.wrapping_add(1);
+
+                let devices = evdev_manager.get_detected_devices();
+                let physical_evdev = backends.is_enabled(PollSource::Evdev)
+                    && devices.iter().any(|device| device.is_gamepad && !device.is_virtual && device.accessible);
+                let mut state = focus.state.lock_or_recover();
+                state.forwarding = forwarding;
+                let wanted = plan(state.mode, state.focused(), forwarding, physical_evdev);
+                // Nodes opened since the last check are grabbed too
+                if wanted.grab || state.grabbed_devices > 0 {
+                    state.grabbed_devices = evdev_manager.set_exclusive(wanted.grab);
+                }
+                if wanted != state.plan {
+                    match &wanted.warning {
+                        Some(warning) => warn!("{}", warning),
+                        None if wanted.prefer_physical => info!("In the background: reading the physical controller through evdev"),
+                        None => info!("Background input back to normal"),
+                    }
+                    state.plan = wanted.clone();
+                    drop(state);
+
+                    filter.set_prefer_physical(wanted.prefer_physical);
+                    filter.refresh(&devices, &gamepad_manager.gamepad_infos());
+                    if let Err(e) = app.emit(BACKGROUND_INPUT_EVENT, focus.status()) {
+                        warn!("Failed to emit {}: {}", BACKGROUND_INPUT_EVENT, e);
+                    }
+                }
+                std::thread::sleep(FOCUS_CHECK_INTERVAL);
+            }
+        })
+        .expect("Failed to spawn window focus monitor thread");
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn nothing_changes_while_focused_or_not_forwarding() {
+        for mode in [BackgroundInput::Warn, BackgroundInput::Evdev, BackgroundInput::EvdevGrab] {
+            assert_eq!(plan(mode, true, true, true), BackgroundPlan::default());
+            assert_eq!(plan(mode, false, false, true), BackgroundPlan::default());
+        }
+    }
+
+    #[test]
+    fn warn_only_warns() {
+        let wanted = plan(BackgroundInput::Warn, false, true, true);
+        assert!(!wanted.prefer_physical);
+        assert!(!wanted.grab);
+        assert!(wanted.warning.is_some_and(|warning| warning.contains("focused again")));
+    }
+
+    #[test]
+    fn evdev_reads_the_physical_controller() {
+        let wanted = plan(BackgroundInput::Evdev, false, true, true);
+        assert_eq!(wanted, BackgroundPlan { prefer_physical: true, grab: false, warning: None });
+
+        let wanted = plan(BackgroundInput::EvdevGrab, false, true, true);
+        assert_eq!(wanted, BackgroundPlan { prefer_physical: true, grab: true, warning: None });
+    }
+
+    #[test]
+    fn evdev_without_a_physical_node_explains_why() {
+        let wanted = plan(BackgroundInput::EvdevGrab, false, true, false);
+        assert!(!wanted.prefer_physical);
+        assert!(!wanted.grab);
+        assert!(wanted.warning.is_some_and(|warning| warning.contains("evdev")));
+    }
+
+    #[test]
+    fn xprop_cardinals_are_parsed() {
+        assert_eq!(parse_xprop_cardinal("GAMESCOPE_FOCUSED_APP(CARDINAL) = 769\n"), Some(769));
+        assert_eq!(parse_xprop_cardinal("GAMESCOPE_FOCUSABLE_APPS(CARDINAL) = 769, 3875646903"), Some(769));
+        assert_eq!(parse_xprop_cardinal("GAMESCOPE_FOCUSED_APP:  not found."), None);
+        assert_eq!(parse_xprop_cardinal(""), None);
+    }
+}
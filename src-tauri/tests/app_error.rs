@@ -0,0 +1,48 @@
+use serde_json::json;
+use steam_deck_controller_lib::error::AppError;
+
+#[test]
+fn network_error_serializes_with_kind_status_and_message() {
+    let error = AppError::network(Some(503), "Server returned error: 503 Service Unavailable");
+    assert_eq!(
+        serde_json::to_value(&error).unwrap(),
+        json!({
+            "kind": "network",
+            "status": 503,
+            "message": "Server returned error: 503 Service Unavailable",
+        })
+    );
+}
+
+#[test]
+fn every_kind_round_trips_with_its_stable_name() {
+    let cases = [
+        (AppError::Io { message: "disk".into() }, "io"),
+        (AppError::permission("denied"), "permission"),
+        (AppError::device_not_found("gone"), "device_not_found"),
+        (AppError::network(None, "unreachable"), "network"),
+        (AppError::updater("bad signature"), "updater"),
+        (AppError::invalid_argument("out of range"), "invalid_argument"),
+        (AppError::internal("oops"), "internal"),
+    ];
+    for (error, kind) in cases {
+        let value = serde_json::to_value(&error).unwrap();
+        assert_eq!(value["kind"], kind);
+        assert_eq!(value["message"], error.message());
+        assert_eq!(serde_json::from_value::<AppError>(value).unwrap(), error);
+    }
+}
+
+#[test]
+fn io_permission_denied_maps_to_permission() {
+    let error = AppError::from(std::io::Error::new(std::io::ErrorKind::PermissionDenied, "/dev/input/event3"));
+    assert!(matches!(error, AppError::Permission { .. }));
+
+    let error = AppError::from(std::io::Error::new(std::io::ErrorKind::NotFound, "/dev/input/event3"));
+    assert!(matches!(error, AppError::Io { .. }));
+}
+
+#[test]
+fn plain_string_errors_are_internal() {
+    assert_eq!(AppError::from("Controller 3 not found".to_string()), AppError::internal("Controller 3 not found"));
+}
@@ -0,0 +1,60 @@
+//! `generate_handler!` only sees the commands it's given, so a command that's defined but
+//! never registered fails at runtime in the frontend. This compares the list in
+//! `commands::handler` with every `#[tauri::command]` in the command modules.
+
+use std::collections::BTreeSet;
+use std::path::Path;
+
+const MODULES: [&str; 4] = ["input", "network", "system", "updater"];
+
+fn read(path: &str) -> String {
+    let path = Path::new(env!("CARGO_MANIFEST_DIR")).join("src/commands").join(path);
+    std::fs::read_to_string(&path).unwrap_or_else(|e| panic!("reading {}: {}", path.display(), e))
+}
+
+/// `module::name` for each entry of the `generate_handler!` list.
+fn registered() -> Vec<String> {
+    let source = read("mod.rs");
+    let (_, list) = source.split_once("generate_handler![").expect("generate_handler! in commands/mod.rs");
+    list.lines()
+        .map(str::trim)
+        .take_while(|line| *line != "]")
+        .filter(|line| !line.is_empty() && !line.starts_with("#[") && !line.starts_with("//"))
+        .map(|line| line.trim_end_matches(',').to_string())
+        .collect()
+}
+
+/// `module::name` for each `#[tauri::command]` function in `module`.
+fn defined(module: &str) -> Vec<String> {
+    let source = read(&format!("{}.rs", module));
+    let mut lines = source.lines().map(str::trim);
+    let mut commands = Vec::new();
+    while let Some(line) = lines.next() {
+        if line != "#[tauri::command]" {
+            continue;
+        }
+        let signature = lines.by_ref().find(|line| !line.starts_with("#[")).unwrap_or_default();
+        let name = signature
+            .strip_prefix("pub ")
+            .map(|rest| rest.strip_prefix("async ").unwrap_or(rest))
+            .and_then(|rest| rest.strip_prefix("fn "))
+            .and_then(|rest| rest.split(['(', '<']).next())
+            .unwrap_or_else(|| panic!("#[tauri::command] on something other than a pub fn: {}", signature));
+        commands.push(format!("{}::{}", module, name));
+    }
+    commands
+}
+
+#[test]
+fn every_command_is_registered_exactly_once() {
+    let registered = registered();
+    let unique: BTreeSet<&String> = registered.iter().collect();
+    assert_eq!(unique.len(), registered.len(), "a command is registered twice");
+
+    let defined: BTreeSet<String> = MODULES.iter().flat_map(|module| defined(module)).collect();
+    let registered: BTreeSet<String> = registered.into_iter().collect();
+    let missing: Vec<_> = defined.difference(&registered).collect();
+    let unknown: Vec<_> = registered.difference(&defined).collect();
+    assert!(missing.is_empty(), "commands missing from commands::handler: {:?}", missing);
+    assert!(unknown.is_empty(), "registered commands that aren't defined: {:?}", unknown);
+}
@@ -0,0 +1,159 @@
+#![cfg(feature = "test-support")]
+
+use serde_json::{json, Value};
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::sync::mpsc;
+use std::sync::Arc;
+use std::thread;
+use steam_deck_controller_lib::test_support::{
+    ConnectionStatsStore, EventBatcher, HttpClients, LightServer, NormalizedInput, UnifiedControllerEvent,
+};
+
+/// A received request: its path and JSON body.
+type Request = (String, Value);
+
+/// Answers each request with the next of `responses` (status, body) and reports what it got.
+fn mock_server(responses: Vec<(u16, String)>) -> (String, mpsc::Receiver<Request>) {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let endpoint = format!("http://{}/input", listener.local_addr().unwrap());
+    let (tx, rx) = mpsc::channel();
+    thread::spawn(move || {
+        for (status, body) in responses {
+            let (stream, _) = listener.accept().unwrap();
+            let mut reader = BufReader::new(stream);
+            tx.send(read_request(&mut reader)).unwrap();
+            respond(reader.get_mut(), status, &body, true);
+        }
+    });
+    (endpoint, rx)
+}
+
+/// Like `mock_server`, but accepts a single connection and answers every request on it.
+fn keep_alive_server(requests: usize) -> (String, mpsc::Receiver<Request>) {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let endpoint = format!("http://{}/input", listener.local_addr().unwrap());
+    let (tx, rx) = mpsc::channel();
+    thread::spawn(move || {
+        let (stream, _) = listener.accept().unwrap();
+        let mut reader = BufReader::new(stream);
+        for _ in 0..requests {
+            tx.send(read_request(&mut reader)).unwrap();
+            respond(reader.get_mut(), 200, "", false);
+        }
+    });
+    (endpoint, rx)
+}
+
+fn read_request(reader: &mut BufReader<TcpStream>) -> Request {
+    let mut request_line = String::new();
+    reader.read_line(&mut request_line).unwrap();
+    let path = request_line.split_whitespace().nth(1).unwrap_or_default().to_string();
+    let mut content_length = 0;
+    loop {
+        let mut header = String::new();
+        reader.read_line(&mut header).unwrap();
+        if header.trim().is_empty() {
+            break;
+        }
+        if let Some((name, value)) = header.split_once(':') {
+            if name.eq_ignore_ascii_case("content-length") {
+                content_length = value.trim().parse().unwrap();
+            }
+        }
+    }
+    let mut request_body = vec![0; content_length];
+    reader.read_exact(&mut request_body).unwrap();
+    (path, serde_json::from_slice(&request_body).unwrap())
+}
+
+fn respond(stream: &mut TcpStream, status: u16, body: &str, close: bool) {
+    let response = format!(
+        "HTTP/1.1 {} Mock\r\nContent-Type: application/json\r\nContent-Length: {}\r\n{}\r\n{}",
+        status,
+        body.len(),
+        if close { "Connection: close\r\n" } else { "" },
+        body
+    );
+    stream.write_all(response.as_bytes()).unwrap();
+}
+
+fn light_server() -> LightServer {
+    LightServer::new(Arc::new(ConnectionStatsStore::load()), Arc::new(HttpClients::new().unwrap()))
+}
+
+fn button_event(name: &str, pressed: bool) -> UnifiedControllerEvent {
+    serde_json::from_value(json!({
+        "source": "gilrs",
+        "device_id": "gilrs:test",
+        "controller_id": 0,
+        "device_path": null,
+        "input": { "kind": "button", "name": name, "pressed": pressed },
+        "raw_code": null,
+        "raw_value": null,
+        "kernel_timestamp_ms": null,
+        "emit_timestamp_ms": 0,
+    }))
+    .unwrap()
+}
+
+#[test]
+fn full_batches_are_posted_with_per_event_results() {
+    let (endpoint, requests) = mock_server(vec![(200, r#"[{"ok":true},{"ok":false,"error":"unknown button"}]"#.to_string())]);
+    let server = light_server();
+    let mut batcher = EventBatcher::default();
+    batcher.configure(60_000, 2).unwrap();
+
+    assert!(batcher.push(button_event("South", true)).is_none());
+    let batch = batcher.push(button_event("South", false)).expect("batch is full");
+    let results = server.post_batch(&endpoint, &batch).unwrap();
+
+    let (path, body) = requests.recv().unwrap();
+    assert_eq!(path, "/batch");
+    let sent: Vec<UnifiedControllerEvent> = serde_json::from_value(body).unwrap();
+    let inputs: Vec<NormalizedInput> = sent.into_iter().map(|event| event.input).collect();
+    assert_eq!(inputs, [
+        NormalizedInput::Button { name: "South".to_string(), pressed: true },
+        NormalizedInput::Button { name: "South".to_string(), pressed: false },
+    ]);
+    assert!(results[0].ok);
+    assert_eq!(results[1].error.as_deref(), Some("unknown button"));
+
+    let status = server.get_status();
+    assert_eq!(status.batches_sent, 1);
+    assert_eq!(status.batched_events_sent, 2);
+    assert_eq!(status.requests_failed, 0);
+}
+
+#[test]
+fn server_errors_are_reported_and_counted() {
+    let (endpoint, requests) = mock_server(vec![(200, String::new()), (503, String::new())]);
+    let server = light_server();
+    let action = json!({ "controller_id": 0, "action": "button:South" });
+
+    server.post_json(&endpoint, &action).unwrap();
+    let error = server.post_json(&endpoint, &action).unwrap_err();
+
+    assert!(error.to_string().contains("503"), "{}", error);
+    assert_eq!(requests.recv().unwrap(), ("/input".to_string(), action.clone()));
+    assert_eq!(requests.recv().unwrap(), ("/input".to_string(), action));
+    let status = server.get_status();
+    assert_eq!(status.requests_sent, 2);
+    assert_eq!(status.requests_failed, 1);
+    assert!(status.last_error.is_some());
+    assert_eq!(server.get_recent_sends().len(), 2);
+}
+
+#[test]
+fn repeat_sends_reuse_the_connection() {
+    // The server only ever accepts one connection, so a second one would time out
+    let (endpoint, requests) = keep_alive_server(3);
+    let server = light_server();
+    let action = json!({ "controller_id": 0, "action": "button:South" });
+
+    for _ in 0..3 {
+        server.post_json(&endpoint, &action).unwrap();
+        assert_eq!(requests.recv().unwrap(), ("/input".to_string(), action.clone()));
+    }
+    assert_eq!(server.get_status().requests_failed, 0);
+}
@@ -0,0 +1,75 @@
+//! A panic while a lock is held mustn't break later callers.
+
+#[cfg(feature = "mock")]
+mod mock {
+    use std::sync::Arc;
+    use steam_deck_controller_lib::mock_gamepad::{MockGamepadConfig, MockGamepadManager};
+    use steam_deck_controller_lib::ControllerEvent;
+
+    fn event(json: &str) -> ControllerEvent {
+        serde_json::from_str(json).unwrap()
+    }
+
+    #[tokio::test]
+    async fn panics_while_locked_do_not_break_later_callers() {
+        let manager = Arc::new(MockGamepadManager::new(MockGamepadConfig::default()));
+        let connected = event(r#"{"controller_id":0,"event_type":"connected","button":null,"axis":null,"value":null,"timestamp":0}"#);
+        manager.play(&[connected], &mut |_| {}).await;
+
+        let panicking: Vec<_> = (0..32)
+            .map(|_| {
+                let manager = manager.clone();
+                std::thread::spawn(move || manager.panic_while_locked())
+            })
+            .collect();
+        let readers: Vec<_> = (0..32)
+            .map(|_| {
+                let manager = manager.clone();
+                std::thread::spawn(move || {
+                    for _ in 0..100 {
+                        manager.get_controller_states();
+                    }
+                })
+            })
+            .collect();
+        for handle in panicking {
+            assert!(handle.join().is_err());
+        }
+        for handle in readers {
+            handle.join().expect("reader panicked on a poisoned lock");
+        }
+
+        let pressed = event(r#"{"controller_id":0,"event_type":"button-pressed","button":"South","axis":null,"value":null,"timestamp":0}"#);
+        manager.play(&[pressed], &mut |_| {}).await;
+        let state = manager.get_controller_state(0).expect("controller state lost");
+        assert!(state.connected);
+        assert_eq!(state.buttons.get("South"), Some(&true));
+    }
+}
+
+#[cfg(feature = "test-support")]
+mod gilrs {
+    use steam_deck_controller_lib::test_support::{GamepadHarness, GamepadInput};
+
+    fn press(button: &str) -> GamepadInput {
+        GamepadInput::ButtonPressed { button: button.to_string() }
+    }
+
+    #[test]
+    fn the_real_manager_keeps_working_after_its_locks_are_poisoned() {
+        let harness = GamepadHarness::new().expect("gamepad manager");
+        harness.input(0, GamepadInput::Connected);
+        harness.input(0, press("South"));
+
+        harness.poison_locks();
+
+        harness.input(0, press("East"));
+        let states = harness.controller_states();
+        let state = states.get(&0).expect("controller state lost");
+        assert!(state.connected);
+        assert_eq!(state.buttons.get("South"), Some(&true));
+        assert_eq!(state.buttons.get("East"), Some(&true));
+        assert!(harness.recent_events().iter().any(|event| event.button.as_deref() == Some("East")));
+        assert!(harness.debug_info().last_event_time.is_some());
+    }
+}
@@ -0,0 +1,118 @@
+#![cfg(feature = "test-support")]
+
+//! Replays an hour of controller input through the dispatcher's long-lived stores on a
+//! simulated clock and checks that what they hold stops growing once their windows fill.
+
+use steam_deck_controller_lib::test_support::{
+    AxisTimeSeriesStore, BufferStats, EventFloodAlarm, PipelineCounter, PipelineMetricsStore, SendStatistics,
+    SignalQualityTracker, StateRevisions, MAX_REMOVED_CONTROLLERS,
+};
+
+const HOUR_MS: u64 = 60 * 60 * 1000;
+const CHECKPOINT_MS: u64 = 5 * 60 * 1000;
+/// Sticks and triggers report every 16ms, the IMU every 8ms
+const AXIS_INTERVAL_MS: u64 = 16;
+const MOTION_INTERVAL_MS: u64 = 8;
+const BUTTON_INTERVAL_MS: u64 = 300;
+const SEND_INTERVAL_MS: u64 = 50;
+/// A controller drops out and comes back under a new id this often, so ids that never
+/// return would pile up unless the stores shed them. Often enough that every per-id cap
+/// is reached before the first checkpoint.
+const RECONNECT_INTERVAL_MS: u64 = 4_000;
+const AXES: [&str; 4] = ["LeftStickX", "LeftStickY", "RightStickX", "RightStickY"];
+const ABS_X: u16 = 0x00;
+const EV_ABS: u16 = 3;
+const EV_SYN: u16 = 0;
+
+#[derive(Default)]
+struct Stores {
+    time_series: AxisTimeSeriesStore,
+    flood_alarm: EventFloodAlarm,
+    pipeline_metrics: PipelineMetricsStore,
+    revisions: StateRevisions,
+    send_statistics: SendStatistics,
+    signal_quality: SignalQualityTracker,
+}
+
+impl Stores {
+    fn input(&mut self, controller_id: usize, now_ms: u64) {
+        self.flood_alarm.record(controller_id, now_ms);
+        self.pipeline_metrics.increment(controller_id, PipelineCounter::Received);
+        self.pipeline_metrics.increment(controller_id, PipelineCounter::Emitted);
+        self.revisions.changed(controller_id);
+    }
+
+    fn disconnect(&mut self, controller_id: usize) {
+        self.time_series.remove_controller(controller_id);
+        self.flood_alarm.controller_disconnected(controller_id);
+        self.revisions.removed(controller_id);
+    }
+
+    fn buffers(&self) -> Vec<BufferStats> {
+        let (quality_len, quality_bytes) = self.signal_quality.occupancy();
+        vec![
+            self.time_series.buffer_stats(),
+            self.flood_alarm.buffer_stats(),
+            self.pipeline_metrics.buffer_stats(),
+            self.send_statistics.buffer_stats(),
+            BufferStats::new("signal_quality", quality_len, None, 1, quality_bytes),
+        ]
+    }
+}
+
+fn total_bytes(buffers: &[BufferStats]) -> usize {
+    buffers.iter().map(|buffer| buffer.approx_bytes).sum()
+}
+
+#[test]
+fn an_hour_of_input_keeps_memory_flat() {
+    let mut stores = Stores::default();
+    let mut controller_id = 0;
+    let mut checkpoints = Vec::new();
+
+    for now_ms in 1..=HOUR_MS {
+        if now_ms % RECONNECT_INTERVAL_MS == 0 {
+            stores.disconnect(controller_id);
+            controller_id += 1;
+            stores.revisions.changed(controller_id);
+        }
+        if now_ms % MOTION_INTERVAL_MS == 0 {
+            let now_us = now_ms * 1000;
+            let value = ((now_ms as f64 / 500.0).sin() * 1000.0) as i32;
+            stores.signal_quality.record(EV_ABS, ABS_X, value, now_us, false);
+            stores.signal_quality.record(EV_SYN, 0, 0, now_us, true);
+        }
+        if now_ms % AXIS_INTERVAL_MS == 0 {
+            for (index, axis) in AXES.iter().enumerate() {
+                let value = ((now_ms as f32 / 700.0) + index as f32).sin();
+                stores.time_series.push(controller_id, axis, now_ms, value);
+                stores.input(controller_id, now_ms);
+            }
+        }
+        if now_ms % BUTTON_INTERVAL_MS == 0 {
+            stores.input(controller_id, now_ms);
+        }
+        if now_ms % SEND_INTERVAL_MS == 0 {
+            stores.send_statistics.record("http://127.0.0.1:8080/batch", true, 5 + now_ms % 40, now_ms);
+        }
+        if now_ms % CHECKPOINT_MS == 0 {
+            let buffers = stores.buffers();
+            for buffer in &buffers {
+                assert!(!buffer.over_capacity(), "{} over capacity at {}ms: {:?}", buffer.name, now_ms, buffer);
+            }
+            checkpoints.push((now_ms, total_bytes(&buffers), buffers));
+        }
+    }
+
+    // By the first checkpoint every window (the longest is a minute) and per-id cap is full
+    let (_, settled, settled_buffers) = &checkpoints[0];
+    for (at_ms, bytes, buffers) in &checkpoints[1..] {
+        assert!(
+            *bytes <= settled + settled / 10,
+            "retained {} bytes at {}ms, up from {} after warm-up\nthen: {:?}\nnow: {:?}",
+            bytes, at_ms, settled, settled_buffers, buffers
+        );
+    }
+    assert!(stores.revisions.removed_count() <= MAX_REMOVED_CONTROLLERS);
+    assert!(controller_id > MAX_REMOVED_CONTROLLERS, "the hour should retire more ids than are remembered");
+}
@@ -0,0 +1,136 @@
+#![cfg(feature = "mock")]
+
+use std::time::Instant;
+use steam_deck_controller_lib::mock_gamepad::{MockGamepadConfig, MockGamepadManager};
+use steam_deck_controller_lib::ControllerEvent;
+
+fn write_script(name: &str, lines: &[&str]) -> String {
+    let path = std::env::temp_dir().join(format!("{}-{}.ndjson", name, std::process::id()));
+    std::fs::write(&path, lines.join("\n")).unwrap();
+    path.to_string_lossy().to_string()
+}
+
+fn script_config(script_file: String) -> MockGamepadConfig {
+    MockGamepadConfig {
+        script_file: Some(script_file),
+        random_noise: false,
+        noise_amplitude: 0.0,
+    }
+}
+
+#[tokio::test]
+async fn script_events_are_emitted_in_order_with_timing() {
+    let script = write_script("mock-order", &[
+        r#"{"controller_id":0,"event_type":"connected","button":null,"axis":null,"value":null,"timestamp":1000}"#,
+        r#"{"controller_id":0,"event_type":"button-pressed","button":"South","axis":null,"value":null,"timestamp":1020}"#,
+        r#"{"controller_id":0,"event_type":"axis-changed","button":null,"axis":"LeftStickX","value":0.5,"timestamp":1050}"#,
+        r#"{"controller_id":0,"event_type":"button-released","button":"South","axis":null,"value":null,"timestamp":1080}"#,
+    ]);
+    let manager = MockGamepadManager::new(script_config(script));
+
+    let started = Instant::now();
+    let mut emitted: Vec<ControllerEvent> = Vec::new();
+    manager.run(|event| emitted.push(event)).await.unwrap();
+
+    assert!(started.elapsed().as_millis() >= 80, "script gaps should be slept through");
+    let types: Vec<&str> = emitted.iter().map(|e| e.event_type.as_str()).collect();
+    assert_eq!(types, ["connected", "button-pressed", "axis-changed", "button-released"]);
+    assert!(emitted.iter().all(|e| e.synthetic));
+    assert!(emitted.windows(2).all(|pair| pair[0].timestamp <= pair[1].timestamp));
+}
+
+#[tokio::test]
+async fn controller_state_reflects_script() {
+    let script = write_script("mock-state", &[
+        r#"{"controller_id":1,"event_type":"connected","button":null,"axis":null,"value":null,"timestamp":0}"#,
+        r#"{"controller_id":1,"event_type":"button-pressed","button":"East","axis":null,"value":null,"timestamp":0}"#,
+        r#"{"controller_id":1,"event_type":"axis-changed","button":null,"axis":"RightStickY","value":-0.25,"timestamp":0}"#,
+    ]);
+    let manager = MockGamepadManager::new(script_config(script));
+    manager.run(|_| {}).await.unwrap();
+
+    let states = manager.get_controller_states();
+    assert_eq!(states.len(), 1);
+    let state = manager.get_controller_state(1).expect("controller 1 connected");
+    assert!(state.connected);
+    assert_eq!(state.buttons.get("East"), Some(&true));
+    assert_eq!(state.axes.get("RightStickY"), Some(&-0.25));
+    assert!(manager.get_controller_state(0).is_none());
+}
+
+#[tokio::test]
+async fn disconnect_removes_controller() {
+    let script = write_script("mock-disconnect", &[
+        r#"{"controller_id":2,"event_type":"connected","button":null,"axis":null,"value":null,"timestamp":0}"#,
+        r#"{"controller_id":2,"event_type":"disconnected","button":null,"axis":null,"value":null,"timestamp":0}"#,
+    ]);
+    let manager = MockGamepadManager::new(script_config(script));
+    manager.run(|_| {}).await.unwrap();
+
+    assert!(manager.get_controller_states().is_empty());
+}
+
+#[tokio::test]
+async fn delta_returns_only_changed_controllers() {
+    let first = write_script("mock-delta-first", &[
+        r#"{"controller_id":0,"event_type":"connected","button":null,"axis":null,"value":null,"timestamp":0}"#,
+        r#"{"controller_id":1,"event_type":"connected","button":null,"axis":null,"value":null,"timestamp":0}"#,
+        r#"{"controller_id":2,"event_type":"connected","button":null,"axis":null,"value":null,"timestamp":0}"#,
+    ]);
+    let manager = MockGamepadManager::new(script_config(first));
+    manager.run(|_| {}).await.unwrap();
+
+    let initial = manager.get_controller_states_delta(0);
+    assert!(initial.full);
+    assert_eq!(initial.changed.len(), 3);
+
+    let second = write_script("mock-delta-second", &[
+        r#"{"controller_id":1,"event_type":"button-pressed","button":"South","axis":null,"value":null,"timestamp":0}"#,
+        r#"{"controller_id":2,"event_type":"disconnected","button":null,"axis":null,"value":null,"timestamp":0}"#,
+    ]);
+    let events = MockGamepadManager::load_script(&second).unwrap();
+    manager.play(&events, &mut |_: ControllerEvent| {}).await;
+
+    let delta = manager.get_controller_states_delta(initial.revision);
+    assert!(!delta.full);
+    assert!(delta.revision > initial.revision);
+    assert_eq!(delta.changed.keys().copied().collect::<Vec<_>>(), [1]);
+    assert_eq!(delta.changed[&1].buttons.get("South"), Some(&true));
+    assert_eq!(delta.removed, [2]);
+
+    let unchanged = manager.get_controller_states_delta(delta.revision);
+    assert!(unchanged.changed.is_empty() && unchanged.removed.is_empty());
+}
+
+#[tokio::test]
+async fn invalid_script_is_reported() {
+    let script = write_script("mock-invalid", &["not json"]);
+    let manager = MockGamepadManager::new(script_config(script));
+
+    let error = manager.run(|_| {}).await.unwrap_err();
+    assert!(error.contains("line 1"), "unexpected error: {}", error);
+}
+
+#[tokio::test]
+async fn noise_stays_within_amplitude_until_stopped() {
+    let manager = std::sync::Arc::new(MockGamepadManager::new(MockGamepadConfig {
+        script_file: None,
+        random_noise: true,
+        noise_amplitude: 0.3,
+    }));
+
+    let stopper = manager.clone();
+    let mut emitted: Vec<ControllerEvent> = Vec::new();
+    manager.run(|event| {
+        emitted.push(event);
+        if emitted.len() >= 20 {
+            stopper.stop();
+        }
+    }).await.unwrap();
+
+    assert_eq!(emitted[0].event_type, "connected");
+    let values: Vec<f32> = emitted[1..].iter().filter_map(|e| e.value).collect();
+    assert!(!values.is_empty());
+    assert!(values.iter().all(|v| v.abs() <= 0.3));
+    assert!(manager.get_controller_state(0).is_some());
+}
@@ -0,0 +1,273 @@
+#![cfg(all(feature = "test-support", target_os = "linux"))]
+
+//! Drives the evdev scanner and poll pipeline with a virtual gamepad. Needs write access to
+//! `/dev/uinput`; each test passes without checking anything when it isn't available.
+
+use evdev::uinput::{VirtualDevice, VirtualDeviceBuilder};
+use evdev::{AbsInfo, AbsoluteAxisType, AttributeSet, EventType, InputEvent, Key, UinputAbsSetup};
+use std::thread::sleep;
+use std::time::Duration;
+use steam_deck_controller_lib::test_support::{
+    DeadZoneShape, EvdevHarness, NormalizedInput, StickDeadZones, StickSide, UinputOutput, UnifiedControllerEvent,
+};
+
+const AXIS_MAX: i32 = 32767;
+/// `BTN_GRIPL` (L4) and `BTN_GRIPR2` (R5), as hid-steam reports two of the Deck's back grips
+const BTN_GRIPL: u16 = 0x224;
+const BTN_GRIPR2: u16 = 0x227;
+
+struct VirtualGamepad {
+    device: VirtualDevice,
+    path: String,
+}
+
+impl VirtualGamepad {
+    /// Creates the device and waits for the harness' scan to pick it up, or returns None
+    /// when uinput isn't usable here.
+    fn create(harness: &EvdevHarness, label: &str) -> Option<Self> {
+        let name = format!("Harness Gamepad {} {}", label, std::process::id());
+        let device = match build_device(&name) {
+            Ok(device) => device,
+            Err(e) => {
+                eprintln!("Skipping: can't create a uinput device: {}", e);
+                return None;
+            }
+        };
+        // udev creates the node asynchronously
+        for _ in 0..50 {
+            let found = harness.scan().expect("scan")
+                .into_iter()
+                .find(|info| info.name == name && info.accessible);
+            if let Some(info) = found {
+                return Some(Self { device, path: info.device_path });
+            }
+            sleep(Duration::from_millis(20));
+        }
+        panic!("virtual gamepad {} never showed up in a scan", name);
+    }
+
+    fn press(&mut self, key: Key, pressed: bool) {
+        self.device.emit(&[InputEvent::new(EventType::KEY, key.code(), pressed as i32)]).unwrap();
+    }
+
+    fn move_stick(&mut self, x: i32, y: i32) {
+        self.device.emit(&[
+            InputEvent::new(EventType::ABSOLUTE, AbsoluteAxisType::ABS_X.0, x),
+            InputEvent::new(EventType::ABSOLUTE, AbsoluteAxisType::ABS_Y.0, y),
+        ]).unwrap();
+    }
+}
+
+fn build_device(name: &str) -> std::io::Result<VirtualDevice> {
+    let mut keys = AttributeSet::<Key>::new();
+    keys.insert(Key::BTN_SOUTH);
+    keys.insert(Key::BTN_EAST);
+    keys.insert(Key::new(BTN_GRIPL));
+    keys.insert(Key::new(BTN_GRIPR2));
+    let stick = AbsInfo::new(0, -AXIS_MAX, AXIS_MAX, 0, 0, 0);
+    VirtualDeviceBuilder::new()?
+        .name(name)
+        .with_keys(&keys)?
+        .with_absolute_axis(&UinputAbsSetup::new(AbsoluteAxisType::ABS_X, stick))?
+        .with_absolute_axis(&UinputAbsSetup::new(AbsoluteAxisType::ABS_Y, stick))?
+        .build()
+}
+
+fn new_harness() -> EvdevHarness {
+    EvdevHarness::new().expect("evdev manager")
+}
+
+/// Polls until something from `path` comes through.
+fn poll_device(harness: &EvdevHarness, path: &str) -> Vec<UnifiedControllerEvent> {
+    for _ in 0..50 {
+        let events: Vec<_> = harness.poll().expect("poll")
+            .into_iter()
+            .filter(|event| event.device_path.as_deref() == Some(path))
+            .collect();
+        if !events.is_empty() {
+            return events;
+        }
+        sleep(Duration::from_millis(10));
+    }
+    Vec::new()
+}
+
+fn axis(events: &[UnifiedControllerEvent], axis_name: &str) -> Vec<f32> {
+    events.iter()
+        .filter_map(|event| match &event.input {
+            NormalizedInput::Axis { name, value } if name == axis_name => Some(*value),
+            _ => None,
+        })
+        .collect()
+}
+
+#[test]
+fn buttons_and_axes_decode_to_unified_events() {
+    let harness = new_harness();
+    let Some(mut gamepad) = VirtualGamepad::create(&harness, "decode") else {
+        return;
+    };
+
+    gamepad.press(Key::BTN_SOUTH, true);
+    gamepad.move_stick(AXIS_MAX, -AXIS_MAX / 2);
+    gamepad.press(Key::BTN_SOUTH, false);
+    let events = poll_device(&harness, &gamepad.path);
+
+    let inputs: Vec<&NormalizedInput> = events.iter().map(|event| &event.input).collect();
+    assert_eq!(inputs[0], &NormalizedInput::Button { name: "BTN_SOUTH".to_string(), pressed: true });
+    assert_eq!(inputs.last().copied(), Some(&NormalizedInput::Button { name: "BTN_SOUTH".to_string(), pressed: false }));
+    assert_eq!(axis(&events, "ABS_X"), [1.0]);
+    assert!((axis(&events, "ABS_Y")[0] + 0.5).abs() < 0.001);
+    assert!(events.iter().all(|event| event.device_id.starts_with("evdev:") && event.raw_code.is_some()));
+    assert!(events.iter().all(|event| event.kernel_timestamp_ms.is_some()));
+}
+
+#[test]
+fn current_state_tracks_held_buttons_and_axes() {
+    let harness = new_harness();
+    let Some(mut gamepad) = VirtualGamepad::create(&harness, "state") else {
+        return;
+    };
+
+    gamepad.press(Key::BTN_EAST, true);
+    gamepad.move_stick(-AXIS_MAX, 0);
+    let events = poll_device(&harness, &gamepad.path);
+
+    let device_id = &events[0].device_id;
+    let states = harness.current_states();
+    let state = states.iter().find(|state| &state.device_id == device_id).expect("state for the virtual gamepad");
+    assert_eq!(state.buttons.get("BTN_EAST"), Some(&true));
+    assert!(!state.buttons.contains_key("BTN_SOUTH"));
+    assert_eq!(state.axes.get("ABS_X"), Some(&-1.0));
+    assert_eq!(state.axes.get("ABS_Y"), Some(&0.0));
+}
+
+#[test]
+fn poisoned_locks_do_not_stop_scanning_or_polling() {
+    let harness = new_harness();
+    let Some(mut gamepad) = VirtualGamepad::create(&harness, "poisoned") else {
+        return;
+    };
+
+    harness.poison_locks();
+
+    assert!(harness.scan().expect("scan").iter().any(|info| info.device_path == gamepad.path));
+    gamepad.press(Key::BTN_SOUTH, true);
+    let events = poll_device(&harness, &gamepad.path);
+    assert_eq!(events[0].input, NormalizedInput::Button { name: "BTN_SOUTH".to_string(), pressed: true });
+    let states = harness.current_states();
+    let state = states.iter().find(|state| state.device_id == events[0].device_id).expect("state for the virtual gamepad");
+    assert_eq!(state.buttons.get("BTN_SOUTH"), Some(&true));
+}
+
+#[test]
+fn back_grips_decode_as_l4_to_r5() {
+    let harness = new_harness();
+    let Some(mut gamepad) = VirtualGamepad::create(&harness, "grips") else {
+        return;
+    };
+    let info = harness.scan().expect("scan")
+        .into_iter()
+        .find(|info| info.device_path == gamepad.path)
+        .expect("scanned virtual gamepad");
+    assert!(info.has_back_grips());
+
+    gamepad.press(Key::new(BTN_GRIPL), true);
+    gamepad.press(Key::new(BTN_GRIPR2), true);
+    gamepad.press(Key::new(BTN_GRIPR2), false);
+    let events = poll_device(&harness, &gamepad.path);
+
+    let inputs: Vec<&NormalizedInput> = events.iter().map(|event| &event.input).collect();
+    assert_eq!(inputs[0], &NormalizedInput::Button { name: "L4".to_string(), pressed: true });
+    assert!(inputs.contains(&&NormalizedInput::Button { name: "R5".to_string(), pressed: false }));
+    let states = harness.current_states();
+    let state = states.iter().find(|state| state.device_id == events[0].device_id).expect("state for the virtual gamepad");
+    assert_eq!(state.buttons.get("L4"), Some(&true));
+    assert!(!state.buttons.contains_key("R5"));
+}
+
+#[test]
+fn dead_zone_applies_to_decoded_stick_values() {
+    let harness = new_harness();
+    let Some(mut gamepad) = VirtualGamepad::create(&harness, "deadzone") else {
+        return;
+    };
+    let mut dead_zones = StickDeadZones::default();
+    dead_zones.set_shape(0, StickSide::Left, DeadZoneShape::Circular);
+    let mut apply = |events: &[UnifiedControllerEvent]| {
+        let mut changes: Vec<_> = axis(events, "ABS_X").into_iter().flat_map(|value| dead_zones.apply(0, "LeftStickX", value)).collect();
+        changes.extend(axis(events, "ABS_Y").into_iter().flat_map(|value| dead_zones.apply(0, "LeftStickY", value)));
+        changes
+    };
+
+    // 0.05 on each axis: inside the 0.1 circle
+    gamepad.move_stick(AXIS_MAX / 20, AXIS_MAX / 20);
+    let changes = apply(&poll_device(&harness, &gamepad.path));
+    assert!(changes.iter().all(|(_, value)| *value == 0.0), "{:?}", changes);
+
+    gamepad.move_stick(AXIS_MAX / 2, AXIS_MAX / 20);
+    let changes = apply(&poll_device(&harness, &gamepad.path));
+    let (_, y) = changes.iter().rev().find(|(name, _)| name == "LeftStickY").unwrap();
+    assert!((y - 0.05).abs() < 0.001, "y leaves the dead zone with x: {:?}", changes);
+}
+
+#[test]
+fn recovers_state_after_syn_dropped() {
+    let harness = new_harness();
+    let Some(mut gamepad) = VirtualGamepad::create(&harness, "dropped") else {
+        return;
+    };
+
+    // Far more reports than the kernel buffers for one client, so it has to drop some
+    for i in 0..2000 {
+        gamepad.move_stick(i % AXIS_MAX, -(i % AXIS_MAX));
+        gamepad.press(Key::BTN_SOUTH, i % 2 == 0);
+    }
+    gamepad.move_stick(AXIS_MAX, 0);
+    gamepad.press(Key::BTN_EAST, true);
+
+    let mut events = Vec::new();
+    loop {
+        let polled = poll_device(&harness, &gamepad.path);
+        if polled.is_empty() {
+            break;
+        }
+        events.extend(polled);
+    }
+    assert!(events.len() < 6000, "no events were dropped, so nothing was resynced");
+    assert_eq!(axis(&events, "ABS_X").last(), Some(&1.0));
+    assert!(events.iter().any(|event| event.input == NormalizedInput::Button { name: "BTN_EAST".to_string(), pressed: true }));
+
+    let states = harness.current_states();
+    let state = states.iter()
+        .find(|state| state.buttons.contains_key("BTN_EAST") && state.axes.get("ABS_X") == Some(&1.0));
+    assert!(state.is_some(), "state after resync: {:?}", states);
+}
+
+#[test]
+fn the_apps_virtual_gamepad_is_a_virtual_gamepad_until_destroyed() {
+    let harness = new_harness();
+    let output = UinputOutput::default();
+    let name = format!("Harness Passthrough {}", std::process::id());
+    if let Err(e) = output.create(&name) {
+        eprintln!("Skipping: {}", e);
+        return;
+    }
+    assert_eq!(output.name().as_deref(), Some(name.as_str()));
+
+    let mut found = None;
+    for _ in 0..50 {
+        found = harness.scan().expect("scan").into_iter().find(|info| info.name == name);
+        if found.is_some() {
+            break;
+        }
+        sleep(Duration::from_millis(20));
+    }
+    let info = found.expect("the virtual gamepad never showed up in a scan");
+    assert!(info.is_gamepad && info.is_virtual, "{:?}", info);
+
+    assert!(output.destroy());
+    assert!(!output.destroy());
+    sleep(Duration::from_millis(100));
+    assert!(harness.scan().expect("scan").iter().all(|info| info.name != name));
+}
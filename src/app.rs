@@ -1,10 +1,14 @@
 #![allow(non_snake_case)]
 
+use crate::logger;
 use dioxus::prelude::*;
 use gloo_timers::future::TimeoutFuture;
 use serde::{Deserialize, Serialize};
 use wasm_bindgen::prelude::*;
-use std::collections::HashMap;
+use wasm_bindgen::JsCast;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::io::Read;
+use std::rc::Rc;
 
 #[wasm_bindgen]
 extern "C" {
@@ -13,6 +17,9 @@ extern "C" {
     
     #[wasm_bindgen(js_namespace = ["window", "__TAURI__", "event"], catch)]
     async fn listen(event: &str, handler: &Closure<dyn FnMut(JsValue)>) -> Result<JsValue, JsValue>;
+    
+    #[wasm_bindgen(js_namespace = ["navigator", "clipboard"], js_name = writeText, catch)]
+    async fn write_clipboard_text(text: &str) -> Result<JsValue, JsValue>;
 }
 
 // Helper function to invoke commands without arguments
@@ -21,6 +28,102 @@ async fn invoke_without_args(cmd: &str) -> Result<JsValue, JsValue> {
     invoke(cmd, empty_args).await
 }
 
+/// Passes of the main poll between full debug fetches while the debug panel is closed.
+const FULL_DEBUG_REFRESH_PASSES: u64 = 10;
+
+/// Hidden to the tray or minimized. The polling loops skip their fetches meanwhile, as
+/// there's nobody to show the results to.
+fn page_hidden() -> bool {
+    web_sys::window().and_then(|window| window.document()).is_some_and(|document| document.hidden())
+}
+
+/// Set once `frontend_hello` has answered. It drops every subscription, so subscribing
+/// before then would be undone.
+#[derive(Clone, Copy)]
+struct FrontendSynced(Signal<bool>);
+
+/// Subscribes to the event `channels` (`buttons`, `axes`, `motion`, `trackpad`, `devices`,
+/// `network`, `updates`) while the calling component is mounted, so the backend can skip
+/// events no view is showing.
+fn use_event_subscription(channels: &'static [&'static str]) {
+    let synced = use_context::<FrontendSynced>().0;
+    let subscribed = use_hook(|| std::rc::Rc::new(std::cell::Cell::new(false)));
+    let args = move || serde_wasm_bindgen::to_value(&serde_json::json!({ "channels": channels })).unwrap();
+    let subscribing = subscribed.clone();
+    use_effect(move || {
+        if *synced.read() && !subscribing.replace(true) {
+            spawn(async move {
+                let _ = invoke("subscribe_events", args()).await;
+            });
+        }
+    });
+    use_drop(move || {
+        if subscribed.get() {
+            // The component's own tasks are cancelled as it unmounts
+            spawn_forever(async move {
+                let _ = invoke("unsubscribe_events", args()).await;
+            });
+        }
+    });
+}
+
+/// Error returned by a command, mirroring the backend's `AppError`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum AppError {
+    Io { message: String },
+    Permission { message: String },
+    DeviceNotFound { message: String },
+    Network { status: Option<u16>, message: String },
+    Updater { message: String },
+    InvalidArgument { message: String },
+    Internal { message: String },
+}
+
+impl AppError {
+    /// Reads a rejected `invoke`, treating anything that isn't an `AppError` as internal.
+    pub fn from_js(value: &JsValue) -> Self {
+        serde_wasm_bindgen::from_value(value.clone()).unwrap_or_else(|_| AppError::Internal {
+            message: value.as_string().unwrap_or_else(|| format!("{:?}", value)),
+        })
+    }
+
+    pub fn message(&self) -> &str {
+        match self {
+            AppError::Io { message }
+            | AppError::Permission { message }
+            | AppError::DeviceNotFound { message }
+            | AppError::Network { message, .. }
+            | AppError::Updater { message }
+            | AppError::InvalidArgument { message }
+            | AppError::Internal { message } => message,
+        }
+    }
+
+    /// The message with a hint about what to do for kinds the user can act on.
+    pub fn user_message(&self) -> String {
+        match self {
+            AppError::Permission { message } => {
+                format!("{} (check that you're in the input group or have udev rules installed)", message)
+            }
+            AppError::DeviceNotFound { message } => format!("{} (is the controller connected?)", message),
+            AppError::Network { status: Some(status), message } if *status >= 500 => {
+                format!("{} (the server had a problem; try again shortly)", message)
+            }
+            AppError::Network { status: None, message } => {
+                format!("{} (couldn't reach the server; check your connection)", message)
+            }
+            other => other.message().to_string(),
+        }
+    }
+
+    /// Network errors without a response, or with a 5xx one, are worth retrying.
+    pub fn is_retryable(&self) -> bool {
+        matches!(self, AppError::Network { status: None, .. })
+            || matches!(self, AppError::Network { status: Some(status), .. } if *status >= 500)
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ControllerState {
     pub buttons: HashMap<String, bool>,
@@ -30,13 +133,28 @@ pub struct ControllerState {
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct ControllerEvent {
-    pub controller_id: usize,
-    pub event_type: String,
-    pub button: Option<String>,
-    pub axis: Option<String>,
-    pub value: Option<f32>,
-    pub timestamp: u64,
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum NormalizedInput {
+    Connected,
+    Disconnected,
+    Button { name: String, pressed: bool },
+    Axis { name: String, value: f32 },
+    Other { event_type: String, name: Option<String> },
+}
+
+/// Payload of `controller-event`, for gilrs and evdev input alike.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UnifiedControllerEvent {
+    /// "gilrs", "evdev" or "synthetic"
+    pub source: String,
+    pub device_id: String,
+    pub controller_id: Option<usize>,
+    pub device_path: Option<String>,
+    pub input: NormalizedInput,
+    pub raw_code: Option<u16>,
+    pub raw_value: Option<i32>,
+    pub kernel_timestamp_ms: Option<u64>,
+    pub emit_timestamp_ms: u64,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -47,6 +165,23 @@ pub struct DebugInfo {
     pub input_devices: Vec<String>,
     pub permissions_check: String,
     pub last_event_time: Option<u64>,
+    #[serde(default)]
+    pub unhandled_events: Vec<UnhandledEventKind>,
+    #[serde(default)]
+    pub unhandled_events_overflowed: u64,
+    /// "full" or "forwarding_only" while input processing is paused
+    #[serde(default)]
+    pub processing_paused: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UnhandledEventKind {
+    pub kind: String,
+    pub count: u64,
+    pub first_seen: u64,
+    pub last_seen: u64,
+    pub last_controller_id: usize,
+    pub sample: String,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -55,44 +190,6125 @@ pub struct GamepadInfo {
     pub name: String,
     pub is_connected: bool,
     pub power_info: String,
+    #[serde(default)]
+    pub uuid: String,
+    #[serde(default)]
+    pub mapping_name: Option<String>,
+    #[serde(default = "unknown_layout")]
+    pub layout: String,
+    #[serde(default)]
+    pub layout_override: Option<String>,
+    #[serde(default)]
+    pub ff_supported: bool,
+    #[serde(default)]
+    pub is_virtual: bool,
+    #[serde(default)]
+    pub physical_source: Option<String>,
+}
+
+fn unknown_layout() -> String {
+    "unknown".to_string()
+}
+
+fn layout_icon(layout: &str) -> &'static str {
+    match layout {
+        "xbox" => "🟢",
+        "playstation" => "🔷",
+        "nintendo" => "🔴",
+        "steam_deck" => "🎮",
+        _ => "❔",
+    }
+}
+
+// Gilrs names face buttons by position (South/East/West/North); label them the way
+// they're printed on the pad so on-screen prompts match what the operator sees.
+fn face_button_label(layout: &str, button: &str) -> String {
+    let label = match (layout, button) {
+        ("playstation", "South") => "✕",
+        ("playstation", "East") => "○",
+        ("playstation", "West") => "□",
+        ("playstation", "North") => "△",
+        ("nintendo", "South") => "B",
+        ("nintendo", "East") => "A",
+        ("nintendo", "West") => "Y",
+        ("nintendo", "North") => "X",
+        (_, "South") => "A",
+        (_, "East") => "B",
+        (_, "West") => "X",
+        (_, "North") => "Y",
+        _ => button,
+    };
+    label.to_string()
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EvdevGamepadInfo {
+    pub device_path: String,
+    pub name: String,
+    pub vendor_id: Option<u16>,
+    pub product_id: Option<u16>,
+    #[serde(default)]
+    pub version: Option<u16>,
+    pub is_gamepad: bool,
+    pub capabilities: Vec<String>,
+    #[serde(default)]
+    pub capability_hash: String,
+    #[serde(default = "default_true")]
+    pub accessible: bool,
+    #[serde(default)]
+    pub fd_errors: u64,
+    #[serde(default)]
+    pub is_virtual: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UnhealthyDevice {
+    pub device_path: String,
+    pub name: String,
+    pub reason: String,
+}
+
+fn default_true() -> bool {
+    true
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EvdevStats {
+    pub detected_devices: usize,
+    pub accessible_devices: usize,
+    pub inaccessible_devices: usize,
+    pub last_scan_time: Option<u64>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BuildInfo {
+    pub version: String,
+    pub target_os: String,
+    pub target_arch: String,
+    pub debug_build: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LightServerStatus {
+    pub last_endpoint: Option<String>,
+    pub requests_sent: u64,
+    pub requests_failed: u64,
+    pub last_success_time: Option<u64>,
+    pub last_error: Option<String>,
+    pub last_error_time: Option<u64>,
+    #[serde(default)]
+    pub batches_sent: u64,
+    #[serde(default)]
+    pub events_per_batch_avg: f64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CommandMetricSnapshot {
+    pub invocation_count: u64,
+    pub error_count: u64,
+    pub total_duration_ns: u64,
+    pub min_duration_ns: u64,
+    pub max_duration_ns: u64,
+    pub avg_duration_ns: u64,
+}
+
+fn format_duration_ns(ns: u64) -> String {
+    if ns >= 1_000_000 {
+        format!("{:.2} ms", ns as f64 / 1_000_000.0)
+    } else {
+        format!("{:.1} µs", ns as f64 / 1_000.0)
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct TimingSnapshot {
+    pub count: u64,
+    pub avg_ns: u64,
+    pub max_ns: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WindowSnapshot {
+    pub window_secs: u64,
+    pub gilrs_events_per_sec: f64,
+    pub evdev_events_per_sec: f64,
+    pub emits: u64,
+    pub emit_failures: u64,
+    #[serde(default)]
+    pub emits_suppressed: u64,
+    #[serde(default)]
+    pub events_coalesced: u64,
+    #[serde(default)]
+    pub events_dropped: u64,
+    pub gilrs_poll: TimingSnapshot,
+    pub evdev_poll: TimingSnapshot,
+    pub gilrs_lock_wait: TimingSnapshot,
+    pub states_lock_wait: TimingSnapshot,
+    pub combo_tracker_lock_wait: TimingSnapshot,
+    pub time_series_lock_wait: TimingSnapshot,
+    #[serde(default)]
+    pub dispatch_queue_wait: TimingSnapshot,
+    #[serde(default)]
+    pub state_publish: TimingSnapshot,
+    pub max_light_sends_in_flight: u64,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct PipelineMetrics {
+    pub received: u64,
+    pub emitted: u64,
+    pub dropped_rate_limit: u64,
+    pub dropped_dedup: u64,
+    pub dropped_filter: u64,
+    pub transformed: u64,
+    pub macro_triggered: u64,
+}
+
+impl PipelineMetrics {
+    fn dropped(&self) -> u64 {
+        self.dropped_rate_limit + self.dropped_dedup + self.dropped_filter
+    }
+
+    fn drop_percentage(&self) -> f64 {
+        if self.received == 0 {
+            return 0.0;
+        }
+        self.dropped() as f64 / self.received as f64 * 100.0
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RuntimeMetricsSnapshot {
+    pub windows: Vec<WindowSnapshot>,
+    pub light_sends_in_flight: u64,
+    #[serde(default)]
+    pub gilrs_poll_interval_ms: u64,
+    #[serde(default)]
+    pub evdev_poll_interval_ms: u64,
+    #[serde(default)]
+    pub poll_idle: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ConnectionStats {
+    pub requests_sent: u64,
+    pub requests_succeeded: u64,
+    pub requests_failed: u64,
+    pub total_latency_ms: u64,
+    pub min_latency_ms: u64,
+    pub max_latency_ms: u64,
+    pub circuit_open_count: u32,
+    pub last_failure_reason: Option<String>,
+}
+
+impl ConnectionStats {
+    fn reliability_percentage(&self) -> f32 {
+        if self.requests_sent == 0 {
+            return 100.0;
+        }
+        self.requests_succeeded as f32 / self.requests_sent as f32 * 100.0
+    }
+
+    fn avg_latency_ms(&self) -> u64 {
+        self.total_latency_ms.checked_div(self.requests_sent).unwrap_or(0)
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DetectionCause {
+    pub kind: String,
+    pub detail: String,
+    pub suggested_fix: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UndetectedDevice {
+    pub device_path: String,
+    pub name: String,
+    pub vendor_id: Option<u16>,
+    pub product_id: Option<u16>,
+    pub sdl_guid: Option<String>,
+    pub causes: Vec<DetectionCause>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DetectedDevice {
+    pub device_path: String,
+    pub gilrs_id: usize,
+    pub gilrs_name: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DetectionDiagnosis {
+    pub gilrs_available: bool,
+    pub gilrs_gamepads: usize,
+    pub detected: Vec<DetectedDevice>,
+    pub undetected: Vec<UndetectedDevice>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RawEvdevEvent {
+    pub event_type: String,
+    pub code: u16,
+    pub code_name: Option<String>,
+    pub value: i32,
+    pub timestamp_us: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RawEventBatch {
+    pub device_path: String,
+    pub events: Vec<RawEvdevEvent>,
+    pub dropped: u64,
+    pub total_dropped: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RawDumpStopped {
+    pub device_path: String,
+    pub reason: String,
+    pub total_events: u64,
+    pub total_dropped: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EndpointSendStatistics {
+    pub endpoint: String,
+    pub succeeded: u64,
+    pub failed: u64,
+    pub buckets: Vec<u64>,
+    pub p95_last_minute_ms: Option<u64>,
+}
+
+impl EndpointSendStatistics {
+    fn p95_label(&self) -> String {
+        self.p95_last_minute_ms.map_or_else(|| "—".to_string(), |p95| format!("{}ms", p95))
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SendStatisticsSnapshot {
+    pub bucket_bounds_ms: Vec<u64>,
+    pub slow_threshold_ms: Option<u64>,
+    pub endpoints: Vec<EndpointSendStatistics>,
+}
+
+impl SendStatisticsSnapshot {
+    fn is_slow(&self, endpoint: &EndpointSendStatistics) -> bool {
+        matches!(
+            (endpoint.p95_last_minute_ms, self.slow_threshold_ms),
+            (Some(p95), Some(threshold)) if p95 > threshold
+        )
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DeviceSignalQuality {
+    pub event_jitter_us_std_dev: f32,
+    pub event_gap_us_max: f32,
+    pub duplicate_event_count: u64,
+    pub out_of_order_count: u64,
+    pub events_in_last_second: u32,
+}
+
+impl DeviceSignalQuality {
+    /// "pass", "warn" or "fail", in the health check's terms. Timestamps going
+    /// backwards or frames 20ms+ apart are red; 2ms+ jitter or any duplicates are yellow.
+    fn status(&self) -> &'static str {
+        if self.out_of_order_count > 0 || self.event_gap_us_max >= 20_000.0 {
+            "fail"
+        } else if self.event_jitter_us_std_dev >= 2_000.0 || self.duplicate_event_count > 0 {
+            "warn"
+        } else {
+            "pass"
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RawDumpStatus {
+    pub device_path: String,
+    pub remaining_secs: u64,
+    pub total_events: u64,
+    pub total_dropped: u64,
+}
+
+/// Raw dump lines kept for export; the view only shows the newest `RAW_DUMP_VIEW_LEN`.
+const RAW_DUMP_BUFFER_LEN: usize = 10_000;
+const RAW_DUMP_VIEW_LEN: usize = 200;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ErrorCategorySnapshot {
+    pub category: String,
+    pub count: u64,
+    pub last_message: Option<String>,
+    pub last_at: Option<u64>,
+    pub last_minute: u64,
+    pub threshold_per_min: Option<u32>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ErrorsSnapshot {
+    pub categories: Vec<ErrorCategorySnapshot>,
+    pub since: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BackendWarning {
+    pub category: String,
+    pub last_minute: u64,
+    pub threshold_per_min: u32,
+    pub message: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EventPressure {
+    pub capacity: usize,
+    pub queued: usize,
+    pub coalesced_total: u64,
+    pub dropped_total: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SystemResumed {
+    pub trigger: String,
+    pub suspended_for_ms: Option<u64>,
+    pub gilrs_gamepads_before: usize,
+    pub gilrs_gamepads_after: Option<usize>,
+    pub evdev_devices_before: usize,
+    pub evdev_devices_after: usize,
+    pub fan_mode_reapplied: bool,
+    pub errors: Vec<String>,
+    pub recovery_ms: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ShowWindowChord {
+    pub buttons: Vec<String>,
+    pub hold_ms: u64,
+}
+
+/// Game mode (gamescope session) or desktop mode; shown in the header so screenshots say
+/// which one a report came from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SessionKind {
+    Desktop,
+    GameMode,
+    Unknown,
+}
+
+impl SessionKind {
+    fn label(self) -> &'static str {
+        match self {
+            Self::Desktop => "🖥️ Desktop mode",
+            Self::GameMode => "🎮 Game mode",
+            Self::Unknown => "❓ Unknown session",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DisplayStatus {
+    pub session_kind: SessionKind,
+    pub ui_scale: f64,
+    pub ui_scale_overridden: bool,
+    pub kiosk_mode: bool,
+    pub kiosk_mode_overridden: bool,
+    pub warn_steam_input: bool,
+}
+
+/// Scales offered next to "session default".
+const UI_SCALE_PRESETS: [f64; 5] = [0.75, 1.0, 1.25, 1.5, 2.0];
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BackgroundModeStatus {
+    pub enabled: bool,
+    pub tray_shown: bool,
+    pub show_window_chord: ShowWindowChord,
+}
+
+/// How long a backend warning toast stays up.
+const TOAST_DURATION_MS: f64 = 8000.0;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ControllerUsage {
+    pub controller_id: usize,
+    pub name: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SessionSummary {
+    pub started_at: u64,
+    pub generated_at: u64,
+    pub duration_secs: u64,
+    pub controllers_used: Vec<ControllerUsage>,
+    pub button_presses: u64,
+    pub light_actions_sent: u64,
+    pub light_action_failures: u64,
+    pub disconnects: u64,
+    pub battery_min_percent: Option<u8>,
+    pub battery_max_percent: Option<u8>,
+    pub poll_panics: u64,
+    pub last_panic: Option<PanicReport>,
+    pub poll_stalls: u64,
+    pub warnings: Vec<ErrorCategorySnapshot>,
+}
+
+impl SessionSummary {
+    fn duration_label(&self) -> String {
+        format!("{}h {:02}m", self.duration_secs / 3600, self.duration_secs / 60 % 60)
+    }
+
+    fn battery_range(&self) -> String {
+        match (self.battery_min_percent, self.battery_max_percent) {
+            (Some(min), Some(max)) => format!("{}–{}%", min, max),
+            _ => "battery n/a".to_string(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SlotAssignment {
+    pub slot_name: String,
+    pub controller_id: Option<usize>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DeviceOpenFailure {
+    pub device_path: String,
+    pub name: String,
+    pub errno: Option<i32>,
+    pub error: String,
+    /// "permission", "busy", "gone" or "other"
+    pub cause: String,
+    pub remediation: String,
+    pub failed_at: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct InputGroupReport {
+    pub user_name: String,
+    pub current_groups: Vec<String>,
+    pub input_group_exists: bool,
+    pub user_in_input_group: bool,
+    pub device_group: Option<String>,
+    pub suggestion: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StageLatency {
+    pub stage: String,
+    pub samples: usize,
+    pub p50_us: u64,
+    pub p95_us: u64,
+    pub p99_us: u64,
+    pub max_us: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ControllerStatesDelta {
+    pub revision: u64,
+    pub full: bool,
+    pub changed: HashMap<usize, ControllerState>,
+    pub removed: Vec<usize>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LowBatteryConfig {
+    pub threshold: u8,
+    pub action: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BatteryThresholdReached {
+    pub percent: u8,
+    pub threshold: u8,
+}
+
+const LOW_BATTERY_ACTIONS: [(&str, &str); 4] = [
+    ("notify", "Notify"),
+    ("suspend", "Suspend"),
+    ("hibernate", "Hibernate"),
+    ("shut_down", "Shut down"),
+];
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LatencyReport {
+    pub duration_secs: u64,
+    pub finished_at: u64,
+    pub stages: Vec<StageLatency>,
+    #[serde(default)]
+    pub input_threads: Vec<InputThreadPriority>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case", tag = "kind")]
+pub enum ThreadPriority {
+    Normal,
+    Nice { nice: i32 },
+    Realtime { priority: i32 },
+}
+
+impl ThreadPriority {
+    fn label(&self) -> String {
+        match self {
+            ThreadPriority::Normal => "normal".to_string(),
+            ThreadPriority::Nice { nice } => format!("nice {}", nice),
+            ThreadPriority::Realtime { priority } => format!("SCHED_RR {}", priority),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct InputThreadPriority {
+    pub thread: String,
+    pub priority: ThreadPriority,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct InputPriorityStatus {
+    pub enabled: bool,
+    pub threads: Vec<InputThreadPriority>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HealthCheck {
+    pub name: String,
+    /// "pass", "warn" or "fail"
+    pub status: String,
+    pub message: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HealthReport {
+    pub overall: String,
+    pub checks: Vec<HealthCheck>,
+    pub checked_at: u64,
+}
+
+fn health_icon(status: &str) -> &'static str {
+    match status {
+        "pass" => "🟢",
+        "warn" => "🟡",
+        _ => "🔴",
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GroupInfo {
+    pub gid: u32,
+    pub name: Option<String>,
+}
+
+impl GroupInfo {
+    fn label(&self) -> String {
+        self.name.clone().unwrap_or_else(|| self.gid.to_string())
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DeviceAccess {
+    pub device_path: String,
+    pub name: String,
+    pub owner_uid: Option<u32>,
+    pub group: Option<GroupInfo>,
+    pub mode: Option<String>,
+    pub readable_by_us: bool,
+    pub open_error: Option<String>,
+    pub grabbed: Option<bool>,
+}
+
+impl DeviceAccess {
+    fn group_label(&self) -> String {
+        self.group.as_ref().map(GroupInfo::label).unwrap_or_else(|| "?".to_string())
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Remediation {
+    pub kind: String,
+    pub message: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PermissionsAnalysis {
+    pub uid: Option<u32>,
+    pub gid: Option<u32>,
+    pub groups: Vec<GroupInfo>,
+    pub in_input_group: bool,
+    pub devices: Vec<DeviceAccess>,
+    pub hidraw_devices: Vec<DeviceAccess>,
+    #[serde(default)]
+    pub uinput: Option<DeviceAccess>,
+    pub remediations: Vec<Remediation>,
+    #[serde(default)]
+    pub sandbox: Option<String>,
+}
+
+impl PermissionsAnalysis {
+    fn identity(&self) -> String {
+        let id = |id: Option<u32>| id.map_or_else(|| "?".to_string(), |id| id.to_string());
+        let groups: Vec<String> = self.groups.iter().map(GroupInfo::label).collect();
+        format!("uid {}, gid {}, groups: {}", id(self.uid), id(self.gid), groups.join(", "))
+    }
+}
+
+impl ErrorCategorySnapshot {
+    fn threshold_label(&self) -> String {
+        self.threshold_per_min.map_or_else(|| "off".to_string(), |threshold| threshold.to_string())
+    }
+
+    fn last_error_label(&self, now: u64) -> String {
+        match (&self.last_message, self.last_at) {
+            (Some(message), Some(at)) => format!("{}s ago: {}", now.saturating_sub(at) / 1000, message),
+            _ => "—".to_string(),
+        }
+    }
+}
+
+/// Samples of the 1s window kept for the runtime graphs.
+const RUNTIME_HISTORY_LEN: usize = 60;
+
+/// SVG polyline points for `values`, scaled to a `width` x `height` box.
+fn sparkline_points(values: &[f64], width: f64, height: f64) -> String {
+    let max = values.iter().cloned().fold(0.0, f64::max).max(f64::EPSILON);
+    let step = width / (RUNTIME_HISTORY_LEN - 1) as f64;
+    values
+        .iter()
+        .enumerate()
+        .map(|(i, value)| format!("{:.1},{:.1}", i as f64 * step, height - value / max * height))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DebugReportResult {
+    pub path: String,
+    pub size_bytes: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FullDebugInfo {
+    pub build: BuildInfo,
+    pub gamepad: DebugInfo,
+    pub evdev_devices: Vec<EvdevGamepadInfo>,
+    pub evdev_stats: EvdevStats,
+    pub steam_deck_summary: String,
+    #[serde(default)]
+    pub steam_deck: Option<SteamDeckHardware>,
+    #[serde(default)]
+    pub system: Option<SystemInfo>,
+    pub settings: serde_json::Value,
+    pub light_server: LightServerStatus,
+    #[serde(default)]
+    pub poll_panics: u64,
+    #[serde(default)]
+    pub last_panic: Option<PanicReport>,
+    #[serde(default)]
+    pub poll_stalls: u64,
+    #[serde(default)]
+    pub pipelines: Vec<PipelineStatus>,
+    #[serde(default)]
+    pub input_backends: Vec<BackendDebugInfo>,
+    #[serde(default)]
+    pub event_subscriptions: Option<EventSubscriptions>,
+    #[serde(default)]
+    pub motion: Option<MotionStatus>,
+    #[serde(default)]
+    pub system_power: Option<SystemPower>,
+}
+
+/// The hardware and OS fields of the backend's `SteamDeckInfo`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SteamDeckHardware {
+    pub model: String,
+    pub steamos_version: Option<String>,
+    pub steamos_build_id: Option<String>,
+    pub controller_firmware: Option<String>,
+    #[serde(default)]
+    pub controller_build: Option<ControllerBuild>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ControllerBuild {
+    pub firmware_build: String,
+}
+
+impl SteamDeckHardware {
+    fn label(&self) -> String {
+        let mut parts = vec![match self.model.as_str() {
+            "lcd" => "Steam Deck LCD".to_string(),
+            "oled" => "Steam Deck OLED".to_string(),
+            _ => "Not Steam Deck hardware".to_string(),
+        }];
+        match (&self.steamos_version, &self.steamos_build_id) {
+            (Some(version), _) => parts.push(format!("SteamOS {}", version)),
+            (None, Some(build)) => parts.push(format!("SteamOS build {}", build)),
+            (None, None) => {}
+        }
+        if let Some(firmware) = &self.controller_firmware {
+            parts.push(format!("controller firmware {}", firmware));
+        }
+        if let Some(build) = &self.controller_build {
+            parts.push(format!("built {}", build.firmware_build));
+        }
+        parts.join(" · ")
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BatteryInfo {
+    pub name: String,
+    pub percent: u8,
+    pub state: String,
+    pub power_draw_w: Option<f32>,
+}
+
+/// The machine's battery; `source` is "ac_only" when there is none.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SystemPower {
+    pub source: String,
+    pub battery: Option<BatteryInfo>,
+    pub ac_online: Option<bool>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SystemBatteryLow {
+    pub percent: u8,
+    pub threshold: u8,
+    pub level: u8,
+    pub levels: u8,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MotionConfig {
+    pub source: String,
+    pub iio_device: Option<String>,
+    pub iio_sample_rate_hz: u32,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct IioDeviceInfo {
+    pub id: String,
+    pub name: String,
+    pub path: String,
+    pub axes: Vec<String>,
+}
+
+/// Which motion source is active: the evdev motion node or the IIO sensor fallback.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MotionStatus {
+    pub config: MotionConfig,
+    pub active: String,
+    pub iio_device: Option<String>,
+    pub iio_devices: Vec<IioDeviceInfo>,
+    pub iio_samples: u64,
+    pub last_error: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EventSubscriptions {
+    pub consumers: BTreeMap<String, Vec<String>>,
+    pub frontend_default: bool,
+    pub active_channels: Vec<String>,
+    pub window_visible: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BackendDebugInfo {
+    pub source: String,
+    pub enabled: bool,
+    pub available: bool,
+    pub device_count: usize,
+    pub last_scan_time: Option<u64>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PipelineStatus {
+    pub source: String,
+    pub alive: bool,
+    pub stalled: bool,
+    pub heartbeat_age_ms: Option<u64>,
+    pub stalls: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SystemInfo {
+    pub kernel_version: Option<String>,
+    pub os_name: Option<String>,
+    pub os_id: Option<String>,
+    pub os_version: Option<String>,
+    pub os_build_id: Option<String>,
+    pub session_type: String,
+    pub current_desktop: Option<String>,
+    pub gamescope_running: Option<bool>,
+    pub session_kind: SessionKind,
+    pub flatpak: bool,
+    pub pressure_vessel: bool,
+    pub input_env: BTreeMap<String, String>,
+    #[serde(default)]
+    pub confinement: Confinement,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Confinement {
+    pub kinds: Vec<String>,
+    pub evidence: Vec<String>,
+    pub container_runtime: Option<String>,
+    pub dev_input_visible: bool,
+}
+
+impl Confinement {
+    fn label(&self) -> String {
+        format!("{} ({})", self.kinds.join(", "), self.evidence.join(", "))
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PanicReport {
+    pub message: String,
+    pub location: Option<String>,
+    pub thread: Option<String>,
+    pub backtrace: String,
+    pub timestamp: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RecordingSummary {
+    pub path: String,
+    pub event_count: u64,
+    pub duration_ms: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReplayProgress {
+    pub path: String,
+    pub position_ms: u64,
+    pub duration_ms: u64,
+    pub events_played: usize,
+    pub total_events: usize,
+    pub iteration: u32,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReplayFinished {
+    pub path: String,
+    pub cancelled: bool,
+    pub iterations: u32,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UpdateInfo {
+    pub available: bool,
+    pub version: Option<String>,
+    pub current_version: String,
+    pub body: Option<String>,
+    pub date: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case", tag = "stage")]
+pub enum UpdateStage {
+    Idle,
+    Downloading { downloaded_bytes: u64, total_bytes: Option<u64> },
+    Installing,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UpdateStatus {
+    pub last_check: Option<UpdateInfo>,
+    pub stage: UpdateStage,
+}
+
+/// The saved settings the main view shows; the rest are left to the panels that use them.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SettingsSnapshot {
+    pub light_server_endpoint: Option<String>,
+    pub self_signed_hosts: Vec<String>,
+}
+
+/// `frontend_hello`'s answer: the backend's current state, to start from after a load.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FrontendSnapshot {
+    pub controllers: ControllerStatesDelta,
+    pub evdev_devices: Vec<EvdevGamepadInfo>,
+    pub settings: SettingsSnapshot,
+    pub display: DisplayStatus,
+    pub update: UpdateStatus,
+    pub app_version: String,
+    pub log_file_path: Option<String>,
+    pub hello_count: u64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Theme {
+    Light,
+    Dark,
+}
+
+impl Theme {
+    const STORAGE_KEY: &'static str = "theme";
+
+    fn as_str(self) -> &'static str {
+        match self {
+            Theme::Light => "light",
+            Theme::Dark => "dark",
+        }
+    }
+
+    fn parse(value: &str) -> Option<Self> {
+        match value {
+            "light" => Some(Theme::Light),
+            "dark" => Some(Theme::Dark),
+            _ => None,
+        }
+    }
+
+    fn toggled(self) -> Self {
+        match self {
+            Theme::Light => Theme::Dark,
+            Theme::Dark => Theme::Light,
+        }
+    }
+
+    fn load_stored() -> Option<Self> {
+        let storage = web_sys::window()?.local_storage().ok()??;
+        storage.get_item(Self::STORAGE_KEY).ok()?.as_deref().and_then(Self::parse)
+    }
+
+    fn store(self) {
+        if let Some(storage) = web_sys::window().and_then(|w| w.local_storage().ok().flatten()) {
+            let _ = storage.set_item(Self::STORAGE_KEY, self.as_str());
+        }
+    }
+
+    /// Follows the OS `prefers-color-scheme` when the user hasn't picked a theme yet.
+    fn system_preference() -> Self {
+        web_sys::window()
+            .and_then(|w| w.match_media("(prefers-color-scheme: dark)").ok().flatten())
+            .map(|query| if query.matches() { Theme::Dark } else { Theme::Light })
+            .unwrap_or(Theme::Light)
+    }
+
+    /// Mirrors the theme onto <html> so the page background outside <main> matches.
+    fn apply_to_document(self) {
+        if let Some(root) = web_sys::window()
+            .and_then(|w| w.document())
+            .and_then(|d| d.document_element())
+        {
+            let _ = root.set_attribute("data-theme", self.as_str());
+        }
+    }
+}
+
+#[component]
+fn ThemeToggle() -> Element {
+    let mut theme = use_context::<Signal<Theme>>();
+    let current = *theme.read();
+
+    rsx! {
+        button {
+            class: "theme-toggle",
+            title: if current == Theme::Dark { "Switch to light theme" } else { "Switch to dark theme" },
+            onclick: move |_| {
+                let next = theme.read().toggled();
+                theme.set(next);
+                next.store();
+            },
+            if current == Theme::Dark { "☀️" } else { "🌙" }
+        }
+    }
+}
+
+/// Decompresses a `export_recording_compressed` payload and saves it as `filename`.
+/// Returns the size of the decompressed file.
+fn download_compressed_recording(encoded: &str, filename: &str) -> Result<usize, String> {
+    use base64::Engine;
+
+    let compressed = base64::engine::general_purpose::STANDARD.decode(encoded)
+        .map_err(|e| format!("invalid base64: {}", e))?;
+    let mut bytes = Vec::new();
+    flate2::read::GzDecoder::new(compressed.as_slice())
+        .read_to_end(&mut bytes)
+        .map_err(|e| format!("invalid gzip: {}", e))?;
+    download_bytes(&bytes, filename, "application/x-ndjson")?;
+    Ok(bytes.len())
+}
+
+/// Saves `bytes` as a file through a temporary object URL.
+fn download_bytes(bytes: &[u8], filename: &str, mime_type: &str) -> Result<(), String> {
+    let parts = js_sys::Array::of1(&js_sys::Uint8Array::from(bytes));
+    let options = web_sys::BlobPropertyBag::new();
+    options.set_type(mime_type);
+    let blob = web_sys::Blob::new_with_u8_array_sequence_and_options(&parts, &options)
+        .map_err(|e| format!("{:?}", e))?;
+    let url = web_sys::Url::create_object_url_with_blob(&blob)
+        .map_err(|e| format!("{:?}", e))?;
+
+    let anchor = web_sys::window()
+        .and_then(|window| window.document())
+        .and_then(|document| document.create_element("a").ok())
+        .and_then(|element| element.dyn_into::<web_sys::HtmlAnchorElement>().ok())
+        .ok_or("no document to download into")?;
+    anchor.set_href(&url);
+    anchor.set_download(filename);
+    anchor.click();
+    let _ = web_sys::Url::revoke_object_url(&url);
+    Ok(())
+}
+
+#[component]
+fn RecordingPanel() -> Element {
+    let mut path = use_signal(|| "/tmp/controller-recording.jsonl".to_string());
+    let mut speed = use_signal(|| "1.0".to_string());
+    let mut looping = use_signal(|| false);
+    let mut merge_live = use_signal(|| false);
+    let mut is_recording = use_signal(|| false);
+    let mut progress = use_signal(|| None::<ReplayProgress>);
+    let mut status = use_signal(|| None::<String>);
+
+    use_effect(move || {
+        spawn(async move {
+            let progress_handler = Closure::<dyn FnMut(JsValue)>::new(move |event: JsValue| {
+                if let Ok(update) = serde_wasm_bindgen::from_value::<ReplayProgress>(event) {
+                    progress.set(Some(update));
+                }
+            });
+            let finished_handler = Closure::<dyn FnMut(JsValue)>::new(move |event: JsValue| {
+                if let Ok(finished) = serde_wasm_bindgen::from_value::<ReplayFinished>(event) {
+                    progress.set(None);
+                    status.set(Some(format!(
+                        "Replay {} after {} pass(es)",
+                        if finished.cancelled { "cancelled" } else { "finished" },
+                        finished.iterations
+                    )));
+                }
+            });
+
+            let _ = listen("replay-progress", &progress_handler).await;
+            let _ = listen("replay-finished", &finished_handler).await;
+
+            progress_handler.forget();
+            finished_handler.forget();
+        });
+    });
+
+    let toggle_recording = move |_| {
+        spawn(async move {
+            if *is_recording.read() {
+                match invoke_without_args("stop_recording").await {
+                    Ok(result) => {
+                        if let Ok(summary) = serde_wasm_bindgen::from_value::<RecordingSummary>(result) {
+                            status.set(Some(format!(
+                                "Saved {} events ({:.1}s) to {}",
+                                summary.event_count,
+                                summary.duration_ms as f64 / 1000.0,
+                                summary.path
+                            )));
+                        }
+                    }
+                    Err(e) => status.set(Some(format!("Failed to stop recording: {}", AppError::from_js(&e).user_message()))),
+                }
+                is_recording.set(false);
+            } else {
+                let args = serde_wasm_bindgen::to_value(&serde_json::json!({
+                    "path": path.read().clone()
+                })).unwrap();
+                match invoke("start_recording", args).await {
+                    Ok(_) => {
+                        is_recording.set(true);
+                        status.set(Some("Recording...".to_string()));
+                    }
+                    Err(e) => status.set(Some(format!("Failed to start recording: {}", AppError::from_js(&e).user_message()))),
+                }
+            }
+        });
+    };
+
+    let start_replay = move |_| {
+        spawn(async move {
+            let args = serde_wasm_bindgen::to_value(&serde_json::json!({
+                "path": path.read().clone(),
+                "speed": speed.read().parse::<f64>().unwrap_or(1.0),
+                "looping": *looping.read(),
+                "liveInput": if *merge_live.read() { "merge" } else { "pause" }
+            })).unwrap();
+            match invoke("replay_recording", args).await {
+                Ok(result) => {
+                    if let Ok(initial) = serde_wasm_bindgen::from_value::<ReplayProgress>(result) {
+                        progress.set(Some(initial));
+                    }
+                    status.set(Some("Replaying...".to_string()));
+                }
+                Err(e) => status.set(Some(format!("Failed to start replay: {}", AppError::from_js(&e).user_message()))),
+            }
+        });
+    };
+
+    let stop_replay = move |_| {
+        spawn(async move {
+            let _ = invoke_without_args("stop_replay").await;
+        });
+    };
+
+    let download = move |_| {
+        spawn(async move {
+            let args = serde_wasm_bindgen::to_value(&serde_json::json!({
+                "path": path.read().clone()
+            })).unwrap();
+            let encoded = match invoke("export_recording_compressed", args).await {
+                Ok(result) => serde_wasm_bindgen::from_value::<String>(result).unwrap_or_default(),
+                Err(e) => {
+                    status.set(Some(format!("Failed to export recording: {}", AppError::from_js(&e).user_message())));
+                    return;
+                }
+            };
+            let filename = path.read()
+                .rsplit('/')
+                .next()
+                .filter(|name| !name.is_empty())
+                .unwrap_or("controller-recording.jsonl")
+                .to_string();
+            match download_compressed_recording(&encoded, &filename) {
+                Ok(size) => status.set(Some(format!(
+                    "Downloaded {} ({} KB, {} KB compressed)",
+                    filename,
+                    size / 1024,
+                    encoded.len() * 3 / 4 / 1024
+                ))),
+                Err(e) => status.set(Some(format!("Failed to download recording: {}", e))),
+            }
+        });
+    };
+
+    rsx! {
+        div {
+            class: "debug-section",
+            h3 { "🎬 Record & Replay" }
+            input {
+                value: "{path}",
+                oninput: move |event| path.set(event.value()),
+            }
+            div {
+                class: "button-group",
+                button {
+                    onclick: toggle_recording,
+                    if *is_recording.read() { "Stop Recording" } else { "Start Recording" }
+                }
+                button {
+                    onclick: start_replay,
+                    disabled: *is_recording.read(),
+                    "Replay"
+                }
+                button {
+                    onclick: stop_replay,
+                    disabled: progress.read().is_none(),
+                    "Stop Replay"
+                }
+                button {
+                    onclick: download,
+                    disabled: *is_recording.read(),
+                    "Download"
+                }
+            }
+            label {
+                "Speed "
+                input {
+                    r#type: "number",
+                    step: "0.25",
+                    min: "0.25",
+                    value: "{speed}",
+                    oninput: move |event| speed.set(event.value()),
+                }
+            }
+            label {
+                input {
+                    r#type: "checkbox",
+                    checked: *looping.read(),
+                    onchange: move |event| looping.set(event.checked()),
+                }
+                " Loop"
+            }
+            label {
+                input {
+                    r#type: "checkbox",
+                    checked: *merge_live.read(),
+                    onchange: move |event| merge_live.set(event.checked()),
+                }
+                " Keep live input during replay"
+            }
+            if let Some(current) = progress.read().as_ref() {
+                div {
+                    class: "download-progress",
+                    div {
+                        class: "progress-bar",
+                        div {
+                            class: "progress-fill",
+                            style: "width: {current.position_ms as f64 / current.duration_ms.max(1) as f64 * 100.0}%"
+                        }
+                    }
+                    p { "{current.events_played}/{current.total_events} events, pass {current.iteration + 1}" }
+                }
+            }
+            if let Some(message) = status.read().as_ref() {
+                p { "{message}" }
+            }
+        }
+    }
+}
+
+#[component]
+fn PanicBanner() -> Element {
+    let mut last_panic = use_signal(|| None::<PanicReport>);
+    let mut copy_status = use_signal(|| None::<String>);
+
+    use_effect(move || {
+        spawn(async move {
+            if let Ok(result) = invoke_without_args("get_last_panic").await {
+                if let Ok(report) = serde_wasm_bindgen::from_value::<Option<PanicReport>>(result) {
+                    last_panic.set(report);
+                }
+            }
+
+            let panic_handler = Closure::<dyn FnMut(JsValue)>::new(move |event: JsValue| {
+                if let Ok(report) = serde_wasm_bindgen::from_value::<PanicReport>(event) {
+                    copy_status.set(None);
+                    last_panic.set(Some(report));
+                }
+            });
+            let _ = listen("backend-panic", &panic_handler).await;
+            panic_handler.forget();
+        });
+    });
+
+    let Some(report) = last_panic.read().clone() else {
+        return rsx! {};
+    };
+    let location = report.location.clone().unwrap_or_else(|| "unknown location".to_string());
+    let details = format!(
+        "Backend panic in thread '{}' at {}: {}\n\n{}",
+        report.thread.as_deref().unwrap_or("<unnamed>"),
+        location,
+        report.message,
+        report.backtrace
+    );
+
+    rsx! {
+        div {
+            class: "panic-banner",
+            strong { "💥 The backend crashed: {report.message}" }
+            p { "at {location}. Input may have stopped updating." }
+            div {
+                class: "button-group",
+                button {
+                    onclick: move |_| {
+                        let details = details.clone();
+                        spawn(async move {
+                            match write_clipboard_text(&details).await {
+                                Ok(_) => copy_status.set(Some("Copied".to_string())),
+                                Err(e) => copy_status.set(Some(format!("Copy failed: {:?}", e))),
+                            }
+                        });
+                    },
+                    "Copy details"
+                }
+                button {
+                    onclick: move |_| {
+                        spawn(async move {
+                            let _ = invoke_without_args("restart_app").await;
+                        });
+                    },
+                    "Restart backend"
+                }
+                button {
+                    onclick: move |_| {
+                        spawn(async move {
+                            let _ = invoke_without_args("clear_last_panic").await;
+                            last_panic.set(None);
+                        });
+                    },
+                    "Dismiss"
+                }
+                if let Some(status) = copy_status.read().as_ref() {
+                    span { "{status}" }
+                }
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BackendStalled {
+    pub source: String,
+    pub heartbeat_age_ms: u64,
+    pub restarted_poll_thread: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BackendRecovered {
+    pub source: String,
+}
+
+#[component]
+fn StallBanner() -> Element {
+    // Keyed by pipeline, since gilrs and evdev stall and recover independently
+    let mut stalls = use_signal(Vec::<BackendStalled>::new);
+
+    use_effect(move || {
+        spawn(async move {
+            let stalled_handler = Closure::<dyn FnMut(JsValue)>::new(move |event: JsValue| {
+                if let Ok(stalled) = serde_wasm_bindgen::from_value::<BackendStalled>(event) {
+                    let mut stalls = stalls.write();
+                    stalls.retain(|existing| existing.source != stalled.source);
+                    stalls.push(stalled);
+                }
+            });
+            let _ = listen("backend-stalled", &stalled_handler).await;
+            stalled_handler.forget();
+
+            let recovered_handler = Closure::<dyn FnMut(JsValue)>::new(move |event: JsValue| {
+                if let Ok(recovered) = serde_wasm_bindgen::from_value::<BackendRecovered>(event) {
+                    stalls.write().retain(|existing| existing.source != recovered.source);
+                }
+            });
+            let _ = listen("backend-recovered", &recovered_handler).await;
+            recovered_handler.forget();
+        });
+    });
+
+    if stalls.read().is_empty() {
+        return rsx! {};
+    }
+
+    rsx! {
+        for stalled in stalls.read().iter().cloned() {
+            div {
+                key: "{stalled.source}",
+                class: "panic-banner",
+                strong { "⏸️ {stalled.source} polling stalled" }
+                p { "No {stalled.source} polling heartbeat for {stalled.heartbeat_age_ms}ms; its controller input isn't updating." }
+                if stalled.restarted_poll_thread {
+                    p { "The {stalled.source} polling thread had exited and was restarted." }
+                }
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SteamInputCheck {
+    pub interference: String,
+    pub advice: Option<String>,
+}
+
+/// Shown while Steam Input looks to be turning the controller into keyboard and mouse, or
+/// leaving only its virtual pad. Dismissing it hides it until the verdict changes.
+#[component]
+fn SteamInputBanner() -> Element {
+    let mut check = use_signal(|| None::<SteamInputCheck>);
+    let mut dismissed = use_signal(|| None::<String>);
+
+    use_effect(move || {
+        spawn(async move {
+            if let Ok(result) = invoke_without_args("get_steam_input_check").await {
+                if let Ok(current) = serde_wasm_bindgen::from_value::<SteamInputCheck>(result) {
+                    check.set(Some(current));
+                }
+            }
+            let handler = Closure::<dyn FnMut(JsValue)>::new(move |event: JsValue| {
+                if let Ok(latest) = serde_wasm_bindgen::from_value::<SteamInputCheck>(event) {
+                    check.set(Some(latest));
+                }
+            });
+            let _ = listen("steam-input-interference", &handler).await;
+            handler.forget();
+        });
+    });
+
+    let Some(current) = check.read().clone() else {
+        return rsx! {};
+    };
+    let Some(advice) = current.advice.clone() else {
+        return rsx! {};
+    };
+    if dismissed.read().as_deref() == Some(current.interference.as_str()) {
+        return rsx! {};
+    }
+    let title = if current.interference == "desktop_layout" {
+        "🎮 Steam is turning the controller into keyboard and mouse"
+    } else {
+        "🎮 Only Steam's virtual gamepad is visible"
+    };
+
+    rsx! {
+        div {
+            class: "panic-banner",
+            strong { "{title}" }
+            p { "{advice}" }
+            p { "The evidence is in the debug info under steam_input." }
+            button {
+                onclick: move |_| dismissed.set(Some(current.interference.clone())),
+                "Dismiss"
+            }
+        }
+    }
+}
+
+/// Big banner while input processing is paused, with a way to resume; pause buttons otherwise.
+#[component]
+fn ProcessingPauseBanner() -> Element {
+    let mut paused = use_signal(|| None::<String>);
+
+    use_effect(move || {
+        spawn(async move {
+            if let Ok(result) = invoke_without_args("get_input_processing_pause").await {
+                if let Ok(mode) = serde_wasm_bindgen::from_value::<Option<String>>(result) {
+                    paused.set(mode);
+                }
+            }
+            let handler = Closure::<dyn FnMut(JsValue)>::new(move |event: JsValue| {
+                if let Ok(mode) = serde_wasm_bindgen::from_value::<Option<String>>(event) {
+                    paused.set(mode);
+                }
+            });
+            let _ = listen("input-processing-pause-changed", &handler).await;
+            handler.forget();
+        });
+    });
+
+    let pause = move |mode: &'static str| {
+        spawn(async move {
+            let args = serde_wasm_bindgen::to_value(&serde_json::json!({ "mode": mode })).unwrap();
+            if let Err(e) = invoke("pause_input_processing", args).await {
+                logger::error(&format!("Failed to pause input processing: {}", AppError::from_js(&e).message()));
+            }
+        });
+    };
+    let resume = move |_| {
+        spawn(async move {
+            if let Err(e) = invoke_without_args("resume_input_processing").await {
+                logger::error(&format!("Failed to resume input processing: {}", AppError::from_js(&e).message()));
+            }
+        });
+    };
+
+    let mode = paused.read().clone();
+    match mode.as_deref() {
+        Some(mode) => {
+            let detail = if mode == "forwarding_only" {
+                "Controllers still update here, but nothing is sent to the light server."
+            } else {
+                "Controller input is being read and discarded; nothing updates or is sent."
+            };
+            rsx! {
+                div {
+                    class: "panic-banner pause-banner",
+                    strong { "⏸️ Input processing is paused" }
+                    p { "{detail} This stays paused across restarts until you resume." }
+                    button {
+                        onclick: resume,
+                        "Resume"
+                    }
+                }
+            }
+        }
+        None => rsx! {
+            div {
+                class: "pause-controls",
+                button {
+                    onclick: move |_| pause("full"),
+                    "Pause input"
+                }
+                button {
+                    onclick: move |_| pause("forwarding_only"),
+                    "Pause sending only"
+                }
+            }
+        },
+    }
+}
+
+const LOG_LEVEL_PRESETS: [&str; 5] = ["error", "warn", "info", "debug", "trace"];
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LogRecord {
+    pub seq: u64,
+    pub timestamp: u64,
+    pub level: String,
+    pub target: String,
+    pub message: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LogBatch {
+    pub records: Vec<LogRecord>,
+    pub next_after_seq: u64,
+    pub missed: bool,
+}
+
+/// Matches the backend's ring buffer size.
+const LOG_VIEWER_CAPACITY: usize = 2000;
+
+#[component]
+fn LogViewer() -> Element {
+    let mut records = use_signal(Vec::<LogRecord>::new);
+    let mut min_level = use_signal(|| "info".to_string());
+    let mut search = use_signal(String::new);
+    let mut auto_scroll = use_signal(|| true);
+    let mut copy_status = use_signal(|| None::<String>);
+
+    // Restarts from scratch whenever the level changes, then fetches incrementally
+    use_effect(move || {
+        let level = min_level.read().clone();
+        records.set(Vec::new());
+        spawn(async move {
+            let mut after_seq: Option<u64> = None;
+            loop {
+                if *min_level.peek() != level {
+                    break;
+                }
+                if page_hidden() {
+                    TimeoutFuture::new(1000).await;
+                    continue;
+                }
+                let args = serde_wasm_bindgen::to_value(&serde_json::json!({
+                    "minLevel": level,
+                    "limit": LOG_VIEWER_CAPACITY,
+                    "afterSeq": after_seq
+                })).unwrap();
+                if let Ok(result) = invoke("get_recent_logs", args).await {
+                    if let Ok(batch) = serde_wasm_bindgen::from_value::<LogBatch>(result) {
+                        after_seq = Some(batch.next_after_seq);
+                        if !batch.records.is_empty() {
+                            let mut current = records.write();
+                            current.extend(batch.records);
+                            let overflow = current.len().saturating_sub(LOG_VIEWER_CAPACITY);
+                            current.drain(..overflow);
+                        }
+                    }
+                }
+                TimeoutFuture::new(1000).await;
+            }
+        });
+    });
+
+    use_effect(move || {
+        let _ = records.read().len();
+        if *auto_scroll.read() {
+            if let Some(element) = web_sys::window()
+                .and_then(|window| window.document())
+                .and_then(|document| document.get_element_by_id("log-viewer-lines"))
+            {
+                element.set_scroll_top(element.scroll_height());
+            }
+        }
+    });
+
+    let query = search.read().to_lowercase();
+    let visible: Vec<LogRecord> = records
+        .read()
+        .iter()
+        .filter(|record| {
+            query.is_empty()
+                || record.message.to_lowercase().contains(&query)
+                || record.target.to_lowercase().contains(&query)
+        })
+        .cloned()
+        .collect();
+
+    let copy_text = visible
+        .iter()
+        .map(|record| format!("{} {} {}: {}", record.timestamp, record.level, record.target, record.message))
+        .collect::<Vec<_>>()
+        .join("\n");
+
+    rsx! {
+        div {
+            class: "log-viewer",
+            div {
+                class: "log-viewer-controls",
+                select {
+                    value: "{min_level}",
+                    onchange: move |event| min_level.set(event.value()),
+                    for preset in LOG_LEVEL_PRESETS {
+                        option { value: "{preset}", "{preset}" }
+                    }
+                }
+                input {
+                    value: "{search}",
+                    placeholder: "Search logs",
+                    oninput: move |event| search.set(event.value()),
+                }
+                label {
+                    input {
+                        r#type: "checkbox",
+                        checked: *auto_scroll.read(),
+                        onchange: move |event| auto_scroll.set(event.checked()),
+                    }
+                    " Auto-scroll"
+                }
+                button {
+                    onclick: move |_| {
+                        let text = copy_text.clone();
+                        spawn(async move {
+                            match write_clipboard_text(&text).await {
+                                Ok(_) => copy_status.set(Some("Copied".to_string())),
+                                Err(e) => copy_status.set(Some(format!("Copy failed: {:?}", e))),
+                            }
+                        });
+                    },
+                    "Copy"
+                }
+                if let Some(message) = copy_status.read().as_ref() {
+                    span { "{message}" }
+                }
+            }
+            div {
+                id: "log-viewer-lines",
+                class: "log-viewer-lines",
+                for record in visible.iter() {
+                    div {
+                        key: "{record.seq}",
+                        class: "log-line log-{record.level.to_lowercase()}",
+                        span { class: "log-level", "{record.level}" }
+                        span { class: "log-target", "{record.target}" }
+                        span { "{record.message}" }
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[component]
+fn BackgroundModeToggle() -> Element {
+    let mut status = use_signal(|| None::<BackgroundModeStatus>);
+
+    use_effect(move || {
+        spawn(async move {
+            if let Ok(result) = invoke_without_args("get_background_mode").await {
+                if let Ok(current) = serde_wasm_bindgen::from_value::<BackgroundModeStatus>(result) {
+                    status.set(Some(current));
+                }
+            }
+        });
+    });
+
+    let Some(current) = status.read().clone() else {
+        return rsx! {};
+    };
+    let chord = current.show_window_chord.buttons.join(" + ");
+    let hint = if !current.enabled {
+        String::new()
+    } else if current.tray_shown {
+        "Closing hides the window; use the tray icon to bring it back.".to_string()
+    } else if chord.is_empty() {
+        "Closing hides the window, and there's no tray or show-window chord to bring it back.".to_string()
+    } else {
+        format!("Closing hides the window; hold {} for {}ms to bring it back.", chord, current.show_window_chord.hold_ms)
+    };
+
+    rsx! {
+        div {
+            class: "background-mode",
+            label {
+                input {
+                    r#type: "checkbox",
+                    checked: current.enabled,
+                    onchange: move |event| {
+                        let enabled = event.checked();
+                        spawn(async move {
+                            let args = serde_wasm_bindgen::to_value(&serde_json::json!({
+                                "enabled": enabled
+                            })).unwrap();
+                            if let Ok(result) = invoke("set_background_mode", args).await {
+                                if let Ok(updated) = serde_wasm_bindgen::from_value::<BackgroundModeStatus>(result) {
+                                    status.set(Some(updated));
+                                }
+                            }
+                        });
+                    },
+                }
+                " Keep forwarding in the background when the window is closed"
+            }
+            if !hint.is_empty() {
+                p { "{hint}" }
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LizardModeStatus {
+    pub enabled: bool,
+    pub suppressed: bool,
+    pub device_path: Option<String>,
+    pub managed_by_kernel: bool,
+    pub last_error: Option<String>,
+}
+
+/// Turns the built-in controller's keyboard and mouse emulation off while the window is focused.
+#[component]
+fn LizardModeToggle() -> Element {
+    let mut status = use_signal(|| None::<LizardModeStatus>);
+
+    use_effect(move || {
+        spawn(async move {
+            if let Ok(result) = invoke_without_args("get_lizard_mode").await {
+                if let Ok(current) = serde_wasm_bindgen::from_value::<LizardModeStatus>(result) {
+                    status.set(Some(current));
+                }
+            }
+        });
+    });
+
+    let Some(current) = status.read().clone() else {
+        return rsx! {};
+    };
+    let hint = if current.enabled {
+        String::new()
+    } else if current.managed_by_kernel {
+        "The kernel's hid-steam driver owns the controller and keeps lizard mode off while its gamepad node is open.".to_string()
+    } else if let Some(error) = &current.last_error {
+        error.clone()
+    } else if current.suppressed {
+        "Trackpads and triggers won't move the cursor or type while this window is focused.".to_string()
+    } else {
+        "Lizard mode turns off when this window is focused.".to_string()
+    };
+
+    rsx! {
+        div {
+            class: "background-mode",
+            label {
+                input {
+                    r#type: "checkbox",
+                    checked: !current.enabled,
+                    onchange: move |event| {
+                        let enabled = !event.checked();
+                        spawn(async move {
+                            let args = serde_wasm_bindgen::to_value(&serde_json::json!({
+                                "enabled": enabled
+                            })).unwrap();
+                            if let Ok(result) = invoke("set_lizard_mode", args).await {
+                                if let Ok(updated) = serde_wasm_bindgen::from_value::<LizardModeStatus>(result) {
+                                    status.set(Some(updated));
+                                }
+                            }
+                        });
+                    },
+                }
+                " Disable trackpad mouse and keyboard emulation (lizard mode) while focused"
+            }
+            if !hint.is_empty() {
+                p { "{hint}" }
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct VirtualPadLink {
+    pub virtual_path: String,
+    pub virtual_name: String,
+    pub physical_path: Option<String>,
+    pub physical_name: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct VirtualPadStatus {
+    pub policy: String,
+    pub links: Vec<VirtualPadLink>,
+    pub ignored_devices: Vec<String>,
+}
+
+#[component]
+fn VirtualPadPolicySelect() -> Element {
+    let mut status = use_signal(|| None::<VirtualPadStatus>);
+
+    use_effect(move || {
+        spawn(async move {
+            if let Ok(result) = invoke_without_args("get_virtual_pads").await {
+                if let Ok(current) = serde_wasm_bindgen::from_value::<VirtualPadStatus>(result) {
+                    status.set(Some(current));
+                }
+            }
+        });
+    });
+
+    let Some(current) = status.read().clone() else {
+        return rsx! {};
+    };
+
+    rsx! {
+        div {
+            class: "background-mode",
+            label {
+                "Steam virtual pads: "
+                select {
+                    value: "{current.policy}",
+                    onchange: move |event| {
+                        let policy = event.value();
+                        spawn(async move {
+                            let args = serde_wasm_bindgen::to_value(&serde_json::json!({
+                                "policy": policy
+                            })).unwrap();
+                            if let Ok(result) = invoke("set_virtual_pad_policy", args).await {
+                                if let Ok(updated) = serde_wasm_bindgen::from_value::<VirtualPadStatus>(result) {
+                                    status.set(Some(updated));
+                                }
+                            }
+                        });
+                    },
+                    option { value: "show_both", "Show both (input arrives twice)" }
+                    option { value: "prefer_physical", "Prefer the physical controller" }
+                    option { value: "prefer_virtual", "Prefer Steam's virtual pad" }
+                }
+            }
+            for link in current.links.iter() {
+                p {
+                    key: "{link.virtual_path}",
+                    class: "virtual-pad-link",
+                    if let Some(physical) = &link.physical_name {
+                        "🔗 {link.virtual_name} ← {physical}"
+                    } else {
+                        "🔗 {link.virtual_name} (physical controller not visible)"
+                    }
+                    if current.ignored_devices.contains(&link.virtual_path) {
+                        " · virtual pad ignored"
+                    } else if link.physical_path.as_ref().is_some_and(|path| current.ignored_devices.contains(path)) {
+                        " · physical controller ignored"
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BackgroundPlan {
+    pub prefer_physical: bool,
+    pub warning: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WindowFocusStatus {
+    pub focused: bool,
+    pub background_input: String,
+    pub plan: BackgroundPlan,
+    pub grabbed_devices: usize,
+}
+
+impl WindowFocusStatus {
+    fn summary(&self) -> String {
+        if self.focused {
+            "Focused".to_string()
+        } else if let Some(warning) = &self.plan.warning {
+            format!("⚠️ {}", warning)
+        } else if self.plan.prefer_physical {
+            format!("In the background, reading the physical controller ({} node(s) grabbed)", self.grabbed_devices)
+        } else {
+            "In the background, not forwarding".to_string()
+        }
+    }
+}
+
+#[component]
+fn BackgroundInputSelect() -> Element {
+    let mut status = use_signal(|| None::<WindowFocusStatus>);
+
+    use_effect(move || {
+        spawn(async move {
+            if let Ok(result) = invoke_without_args("get_window_focus").await {
+                if let Ok(current) = serde_wasm_bindgen::from_value::<WindowFocusStatus>(result) {
+                    status.set(Some(current));
+                }
+            }
+            let handler = Closure::<dyn FnMut(JsValue)>::new(move |event: JsValue| {
+                if let Ok(latest) = serde_wasm_bindgen::from_value::<WindowFocusStatus>(event) {
+                    status.set(Some(latest));
+                }
+            });
+            let _ = listen("background-input", &handler).await;
+            handler.forget();
+        });
+    });
+
+    let Some(current) = status.read().clone() else {
+        return rsx! {};
+    };
+
+    rsx! {
+        div {
+            class: "background-mode",
+            label {
+                "Controller input in the background: "
+                select {
+                    value: "{current.background_input}",
+                    onchange: move |event| {
+                        let mode = event.value();
+                        spawn(async move {
+                            let args = serde_wasm_bindgen::to_value(&serde_json::json!({
+                                "mode": mode
+                            })).unwrap();
+                            if let Ok(result) = invoke("set_background_input", args).await {
+                                if let Ok(updated) = serde_wasm_bindgen::from_value::<WindowFocusStatus>(result) {
+                                    status.set(Some(updated));
+                                }
+                            }
+                        });
+                    },
+                    option { value: "warn", "Warn that it may stop" }
+                    option { value: "evdev", "Read the controller through evdev" }
+                    option { value: "evdev_grab", "Read it through evdev and keep it from the focused app" }
+                }
+            }
+            p { "{current.summary()}" }
+        }
+    }
+}
+
+/// Shown once the app has been in the background with input at risk, so missed input
+/// comes with a reason. Stays after refocusing until dismissed.
+#[component]
+fn BackgroundInputBanner() -> Element {
+    let mut warning = use_signal(|| None::<String>);
+
+    use_effect(move || {
+        spawn(async move {
+            let handler = Closure::<dyn FnMut(JsValue)>::new(move |event: JsValue| {
+                if let Ok(latest) = serde_wasm_bindgen::from_value::<WindowFocusStatus>(event) {
+                    if let Some(message) = latest.plan.warning {
+                        warning.set(Some(message));
+                    } else if latest.plan.prefer_physical {
+                        warning.set(None);
+                    }
+                }
+            });
+            let _ = listen("background-input", &handler).await;
+            handler.forget();
+        });
+    });
+
+    let Some(message) = warning.read().clone() else {
+        return rsx! {};
+    };
+
+    rsx! {
+        div {
+            class: "panic-banner",
+            strong { "🎮 Controller input may not have arrived while the app was in the background" }
+            p { "{message}" }
+            button {
+                onclick: move |_| warning.set(None),
+                "Dismiss"
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SuspendInhibitStatus {
+    pub active: bool,
+    pub reason: Option<String>,
+    pub manual_reason: Option<String>,
+    pub since: Option<u64>,
+    pub last_error: Option<String>,
+}
+
+#[component]
+fn SuspendInhibitToggle() -> Element {
+    let mut status = use_signal(|| None::<SuspendInhibitStatus>);
+    let mut reason = use_signal(String::new);
+
+    // The automatic inhibitor comes and goes with forwarding and replays
+    use_coroutine(move |_: UnboundedReceiver<()>| async move {
+        loop {
+            if let Ok(result) = invoke_without_args("get_suspend_inhibit").await {
+                if let Ok(current) = serde_wasm_bindgen::from_value::<SuspendInhibitStatus>(result) {
+                    status.set(Some(current));
+                }
+            }
+            TimeoutFuture::new(5000).await;
+        }
+    });
+
+    let Some(current) = status.read().clone() else {
+        return rsx! {};
+    };
+    let summary = match (&current.reason, &current.last_error) {
+        (Some(held), _) => format!("💤 Suspend inhibited: {}", held),
+        (None, Some(error)) => format!("⚠️ Couldn't inhibit suspend: {}", error),
+        (None, None) => "The system may suspend as usual".to_string(),
+    };
+
+    rsx! {
+        div {
+            class: "background-mode",
+            label {
+                input {
+                    r#type: "checkbox",
+                    checked: current.manual_reason.is_some(),
+                    onchange: move |event| {
+                        let enabled = event.checked();
+                        let reason = Some(reason.read().trim().to_string()).filter(|reason| !reason.is_empty());
+                        spawn(async move {
+                            let args = serde_wasm_bindgen::to_value(&serde_json::json!({
+                                "enabled": enabled,
+                                "reason": reason
+                            })).unwrap();
+                            if let Ok(result) = invoke("set_suspend_inhibit", args).await {
+                                if let Ok(updated) = serde_wasm_bindgen::from_value::<SuspendInhibitStatus>(result) {
+                                    status.set(Some(updated));
+                                }
+                            }
+                        });
+                    },
+                }
+                " Keep the system awake"
+            }
+            input {
+                value: "{reason}",
+                oninput: move |event| reason.set(event.value()),
+                placeholder: "Reason, e.g. Light show"
+            }
+            p { "{summary}" }
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DockPolicy {
+    pub docked: String,
+    pub handheld: String,
+    #[serde(default)]
+    pub force_state: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DockStatus {
+    pub state: String,
+    pub detected: String,
+    pub external_displays: Vec<String>,
+    pub ac_online: Option<bool>,
+    pub policy: DockPolicy,
+    pub primary_controller: Option<usize>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DockChange {
+    pub status: DockStatus,
+    pub slots: Vec<SlotAssignment>,
+}
+
+/// Fetches the dock status and keeps it current from `dock-state-changed`.
+fn use_dock_status() -> Signal<Option<DockStatus>> {
+    let mut status = use_signal(|| None::<DockStatus>);
+    use_effect(move || {
+        spawn(async move {
+            if let Ok(result) = invoke_without_args("get_dock_status").await {
+                if let Ok(current) = serde_wasm_bindgen::from_value::<DockStatus>(result) {
+                    status.set(Some(current));
+                }
+            }
+            let handler = Closure::<dyn FnMut(JsValue)>::new(move |event: JsValue| {
+                if let Ok(change) = serde_wasm_bindgen::from_value::<DockChange>(event) {
+                    status.set(Some(change.status));
+                }
+            });
+            let _ = listen("dock-state-changed", &handler).await;
+            handler.forget();
+        });
+    });
+    status
+}
+
+#[component]
+fn DockIndicator() -> Element {
+    let status = use_dock_status();
+    let Some(current) = status.read().clone() else {
+        return rsx! {};
+    };
+    let (label, title) = match current.state.as_str() {
+        "docked" => ("🖥️ Docked", format!("External display: {}", current.external_displays.join(", "))),
+        "handheld" => ("🎮 Handheld", "Only the built-in display is connected".to_string()),
+        _ => return rsx! {},
+    };
+    let forced = if current.policy.force_state.is_some() { " (forced)" } else { "" };
+
+    rsx! {
+        span {
+            class: "dock-indicator",
+            title: "{title}",
+            "{label}{forced}"
+        }
+    }
+}
+
+#[component]
+fn DockPolicySettings() -> Element {
+    let mut status = use_dock_status();
+    let Some(current) = status.read().clone() else {
+        return rsx! {};
+    };
+    let policy = current.policy.clone();
+    let force_value = policy.force_state.clone().unwrap_or_default();
+    let save = move |policy: DockPolicy| {
+        spawn(async move {
+            let args = serde_wasm_bindgen::to_value(&serde_json::json!({
+                "policy": policy
+            })).unwrap();
+            if let Ok(result) = invoke("set_dock_policy", args).await {
+                if let Ok(updated) = serde_wasm_bindgen::from_value::<DockStatus>(result) {
+                    status.set(Some(updated));
+                }
+            }
+        });
+    };
+    let docked_policy = policy.clone();
+    let handheld_policy = policy.clone();
+    let force_policy = policy.clone();
+
+    rsx! {
+        div {
+            class: "background-mode",
+            label {
+                "Primary controller when docked: "
+                select {
+                    value: "{policy.docked}",
+                    onchange: move |event| save(DockPolicy { docked: event.value(), ..docked_policy.clone() }),
+                    option { value: "external", "External pad" }
+                    option { value: "built_in", "Built-in controls" }
+                    option { value: "unchanged", "Leave as is" }
+                }
+            }
+            label {
+                " handheld: "
+                select {
+                    value: "{policy.handheld}",
+                    onchange: move |event| save(DockPolicy { handheld: event.value(), ..handheld_policy.clone() }),
+                    option { value: "built_in", "Built-in controls" }
+                    option { value: "external", "External pad" }
+                    option { value: "unchanged", "Leave as is" }
+                }
+            }
+            label {
+                " dock state: "
+                select {
+                    value: "{force_value}",
+                    onchange: move |event| {
+                        let value = event.value();
+                        save(DockPolicy {
+                            force_state: if value.is_empty() { None } else { Some(value) },
+                            ..force_policy.clone()
+                        });
+                    },
+                    option { value: "", "Detect ({current.detected})" }
+                    option { value: "docked", "Always docked" }
+                    option { value: "handheld", "Always handheld" }
+                }
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HapticOutcome {
+    pub played: bool,
+    pub capability: String,
+}
+
+/// Plays each built-in haptic pattern on the right trackpad.
+#[component]
+fn DeckHapticTest() -> Element {
+    let mut outcome = use_signal(|| None::<String>);
+
+    let play = move |pattern: &'static str| {
+        spawn(async move {
+            let args = serde_wasm_bindgen::to_value(&serde_json::json!({
+                "side": "right",
+                "intensity": 0.5,
+                "durationMs": 60,
+                "pattern": pattern
+            })).unwrap();
+            let message = match invoke("play_deck_haptic", args).await {
+                Ok(result) => match serde_wasm_bindgen::from_value::<HapticOutcome>(result) {
+                    Ok(result) if result.played => format!("Played {}", pattern),
+                    Ok(result) => format!("Not played: {}", result.capability.replace('_', " ")),
+                    Err(_) => "Unexpected response".to_string(),
+                },
+                Err(e) => AppError::from_js(&e).user_message(),
+            };
+            outcome.set(Some(message));
+        });
+    };
+
+    rsx! {
+        div {
+            class: "debug-section",
+            h3 { "📳 Trackpad Haptics" }
+            button { onclick: move |_| play("tick"), "Tick" }
+            button { onclick: move |_| play("double_tick"), "Double tick" }
+            button { onclick: move |_| play("buzz"), "Buzz" }
+            if let Some(message) = outcome.read().as_ref() {
+                p { "{message}" }
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct InstalledShortcut {
+    pub app_id: u32,
+    pub exe: String,
+    pub launch_options: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SteamShortcutCheck {
+    pub shortcut: Option<InstalledShortcut>,
+    pub expected_exe: String,
+    pub exe_matches: bool,
+    pub launch_options_match: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SteamShortcutResult {
+    pub change: String,
+    pub backup_path: Option<String>,
+    pub restart_required: bool,
+}
+
+/// Adds the app to Steam as a non-Steam game, so it can be launched from game mode.
+#[component]
+fn SteamShortcutSettings() -> Element {
+    let mut check = use_signal(|| None::<SteamShortcutCheck>);
+    let mut message = use_signal(|| None::<String>);
+
+    let refresh = move || {
+        spawn(async move {
+            match invoke_without_args("check_steam_shortcut").await {
+                Ok(result) => check.set(serde_wasm_bindgen::from_value::<SteamShortcutCheck>(result).ok()),
+                Err(e) => message.set(Some(AppError::from_js(&e).user_message())),
+            }
+        });
+    };
+    use_hook(refresh);
+
+    let install = move |_| {
+        spawn(async move {
+            let text = match invoke_without_args("install_steam_shortcut").await {
+                Ok(result) => match serde_wasm_bindgen::from_value::<SteamShortcutResult>(result) {
+                    Ok(result) if result.change == "unchanged" => "The Steam shortcut is already up to date".to_string(),
+                    Ok(result) => {
+                        let mut text = format!("Steam shortcut {}", result.change);
+                        if let Some(backup) = result.backup_path {
+                            text.push_str(&format!(", previous shortcuts saved to {}", backup));
+                        }
+                        if result.restart_required {
+                            text.push_str(". Restart Steam to see it");
+                        }
+                        text
+                    }
+                    Err(_) => "Unexpected response".to_string(),
+                },
+                Err(e) => AppError::from_js(&e).user_message(),
+            };
+            message.set(Some(text));
+            refresh();
+        });
+    };
+
+    let summary = match check.read().as_ref() {
+        None => "Checking Steam shortcuts...".to_string(),
+        Some(current) => match &current.shortcut {
+            None => "Not in Steam yet".to_string(),
+            Some(shortcut) if !current.exe_matches => format!("The Steam shortcut still launches {}", shortcut.exe),
+            Some(_) if !current.launch_options_match => "The Steam shortcut's launch options are out of date".to_string(),
+            Some(shortcut) => format!("In Steam as app {}", shortcut.app_id),
+        },
+    };
+    let label = if check.read().as_ref().is_some_and(|current| current.shortcut.is_some()) {
+        "Update Steam Shortcut"
+    } else {
+        "Add to Steam"
+    };
+
+    rsx! {
+        div {
+            class: "background-mode",
+            p { "🎮 {summary}" }
+            button { onclick: install, "{label}" }
+            if let Some(text) = message.read().as_ref() {
+                p { "{text}" }
+            }
+        }
+    }
+}
+
+/// UI scale and kiosk mode, each defaulting to what suits the detected session.
+#[component]
+fn DisplaySettings(display: Signal<Option<DisplayStatus>>) -> Element {
+    let mut display = display;
+    let mut error = use_signal(|| None::<String>);
+
+    let Some(current) = display.read().clone() else {
+        return rsx! {};
+    };
+    let apply = move |command: &'static str, args: serde_json::Value| {
+        spawn(async move {
+            let args = serde_wasm_bindgen::to_value(&args).unwrap();
+            match invoke(command, args).await {
+                Ok(result) => {
+                    if let Ok(updated) = serde_wasm_bindgen::from_value::<DisplayStatus>(result) {
+                        display.set(Some(updated));
+                    }
+                    error.set(None);
+                }
+                Err(e) => error.set(Some(AppError::from_js(&e).user_message())),
+            }
+        });
+    };
+    let selected_scale = if current.ui_scale_overridden { current.ui_scale.to_string() } else { String::new() };
+    let selected_kiosk = match (current.kiosk_mode_overridden, current.kiosk_mode) {
+        (false, _) => "",
+        (true, true) => "on",
+        (true, false) => "off",
+    };
+    let kiosk_label = if current.kiosk_mode { "on" } else { "off" };
+
+    rsx! {
+        div {
+            class: "display-settings",
+            label {
+                "UI scale "
+                select {
+                    value: "{selected_scale}",
+                    onchange: move |event| {
+                        let scale = event.value().parse::<f64>().ok();
+                        apply("set_ui_scale", serde_json::json!({ "scale": scale }));
+                    },
+                    option { value: "", "Session default" }
+                    for scale in UI_SCALE_PRESETS {
+                        option { value: "{scale}", "{scale * 100.0}%" }
+                    }
+                }
+            }
+            label {
+                "Kiosk mode "
+                select {
+                    value: "{selected_kiosk}",
+                    onchange: move |event| {
+                        let enabled = match event.value().as_str() {
+                            "on" => Some(true),
+                            "off" => Some(false),
+                            _ => None,
+                        };
+                        apply("set_kiosk_mode", serde_json::json!({ "enabled": enabled }));
+                    },
+                    option { value: "", "Session default" }
+                    option { value: "on", "On (fullscreen)" }
+                    option { value: "off", "Off (window)" }
+                }
+            }
+            p { "{current.session_kind.label()}: {current.ui_scale * 100.0}% scale, kiosk mode {kiosk_label}" }
+            if let Some(message) = error.read().as_ref() {
+                p { class: "error", "{message}" }
+            }
+        }
+    }
+}
+
+#[component]
+fn LogLevelControl() -> Element {
+    let mut filter = use_signal(String::new);
+    let mut status = use_signal(|| None::<String>);
+
+    use_effect(move || {
+        spawn(async move {
+            if let Ok(result) = invoke_without_args("get_log_level").await {
+                if let Ok(Some(current)) = serde_wasm_bindgen::from_value::<Option<String>>(result) {
+                    filter.set(current);
+                }
+            }
+        });
+    });
+
+    let apply = move |value: String| {
+        spawn(async move {
+            let args = serde_wasm_bindgen::to_value(&serde_json::json!({
+                "filter": value
+            })).unwrap();
+            match invoke("set_log_level", args).await {
+                Ok(_) => {
+                    filter.set(value.clone());
+                    status.set(Some(format!("Log filter: {}", value)));
+                }
+                Err(e) => status.set(Some(AppError::from_js(&e).user_message())),
+            }
+        });
+    };
+
+    let selected_preset = if LOG_LEVEL_PRESETS.contains(&filter.read().as_str()) {
+        filter.read().clone()
+    } else {
+        String::new()
+    };
+
+    rsx! {
+        div {
+            class: "log-level-control",
+            select {
+                value: "{selected_preset}",
+                onchange: move |event| {
+                    let value = event.value();
+                    if !value.is_empty() {
+                        apply(value);
+                    }
+                },
+                option { value: "", "Custom" }
+                for preset in LOG_LEVEL_PRESETS {
+                    option { value: "{preset}", "{preset}" }
+                }
+            }
+            input {
+                value: "{filter}",
+                placeholder: "info,steam_deck_controller_lib::evdev_gamepad=trace",
+                oninput: move |event| filter.set(event.value()),
+            }
+            button {
+                onclick: move |_| apply(filter.read().clone()),
+                "Apply"
+            }
+            if let Some(message) = status.read().as_ref() {
+                p { "{message}" }
+            }
+        }
+    }
+}
+
+#[component]
+fn SignalQualityPanel(evdev_devices: Signal<Vec<EvdevGamepadInfo>>) -> Element {
+    let mut qualities = use_signal(BTreeMap::<String, DeviceSignalQuality>::new);
+
+    use_coroutine(move |_: UnboundedReceiver<()>| async move {
+        loop {
+            if page_hidden() {
+                TimeoutFuture::new(1000).await;
+                continue;
+            }
+            let paths: Vec<String> = evdev_devices.peek().iter()
+                .filter(|device| device.accessible)
+                .map(|device| device.device_path.clone())
+                .collect();
+            let mut latest = BTreeMap::new();
+            for path in paths {
+                let args = serde_wasm_bindgen::to_value(&serde_json::json!({ "devicePath": path })).unwrap();
+                if let Ok(result) = invoke("get_device_signal_quality", args).await {
+                    if let Ok(quality) = serde_wasm_bindgen::from_value::<DeviceSignalQuality>(result) {
+                        latest.insert(path, quality);
+                    }
+                }
+            }
+            qualities.set(latest);
+            TimeoutFuture::new(1000).await;
+        }
+    });
+
+    rsx! {
+        div {
+            class: "debug-section",
+            h3 { "📶 Signal Quality" }
+            if qualities.read().is_empty() {
+                p { "No open evdev devices" }
+            }
+            ul {
+                class: "health-list",
+                for (path, quality) in qualities.read().iter() {
+                    li {
+                        key: "{path}",
+                        class: "health-{quality.status()}",
+                        strong { "{health_icon(quality.status())} {path}" }
+                        span {
+                            " — jitter {quality.event_jitter_us_std_dev:.0}µs, max gap {quality.event_gap_us_max:.0}µs, {quality.events_in_last_second} events/s, {quality.duplicate_event_count} duplicate(s), {quality.out_of_order_count} out of order"
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[component]
+fn RawEventDumpPanel(evdev_devices: Signal<Vec<EvdevGamepadInfo>>) -> Element {
+    let mut selected = use_signal(String::new);
+    let mut duration = use_signal(|| "60".to_string());
+    let mut active = use_signal(|| None::<RawDumpStatus>);
+    let mut lines = use_signal(Vec::<String>::new);
+    let mut paused = use_signal(|| false);
+    let mut status = use_signal(|| None::<String>);
+    use_event_subscription(&["devices"]);
+
+    use_effect(move || {
+        spawn(async move {
+            if let Ok(result) = invoke_without_args("get_raw_dump_status").await {
+                if let Ok(current) = serde_wasm_bindgen::from_value::<Option<RawDumpStatus>>(result) {
+                    if let Some(current) = &current {
+                        selected.set(current.device_path.clone());
+                    }
+                    active.set(current);
+                }
+            }
+
+            let raw_handler = Closure::<dyn FnMut(JsValue)>::new(move |event: JsValue| {
+                let Ok(batch) = serde_wasm_bindgen::from_value::<RawEventBatch>(event) else {
+                    return;
+                };
+                if *paused.peek() {
+                    return;
+                }
+                let mut lines = lines.write();
+                for event in &batch.events {
+                    lines.push(format!(
+                        "{}.{:06} {} {} ({}) = {}",
+                        event.timestamp_us / 1_000_000,
+                        event.timestamp_us % 1_000_000,
+                        event.event_type,
+                        event.code_name.as_deref().unwrap_or("?"),
+                        event.code,
+                        event.value
+                    ));
+                }
+                if batch.dropped > 0 {
+                    lines.push(format!("... {} events dropped by the rate cap ({} total)", batch.dropped, batch.total_dropped));
+                }
+                let overflow = lines.len().saturating_sub(RAW_DUMP_BUFFER_LEN);
+                lines.drain(..overflow);
+            });
+            let stopped_handler = Closure::<dyn FnMut(JsValue)>::new(move |event: JsValue| {
+                if let Ok(stopped) = serde_wasm_bindgen::from_value::<RawDumpStopped>(event) {
+                    active.set(None);
+                    status.set(Some(format!(
+                        "Dump of {} stopped ({}): {} events, {} dropped",
+                        stopped.device_path, stopped.reason, stopped.total_events, stopped.total_dropped
+                    )));
+                }
+            });
+
+            let _ = listen("evdev-raw", &raw_handler).await;
+            let _ = listen("evdev-raw-stopped", &stopped_handler).await;
+
+            raw_handler.forget();
+            stopped_handler.forget();
+        });
+    });
+
+    let set_dump = move |enabled: bool| {
+        spawn(async move {
+            let args = serde_wasm_bindgen::to_value(&serde_json::json!({
+                "devicePath": selected.read().clone(),
+                "enabled": enabled,
+                "durationSecs": duration.read().parse::<u64>().ok()
+            })).unwrap();
+            match invoke("set_raw_dump", args).await {
+                Ok(result) => {
+                    let current = serde_wasm_bindgen::from_value::<Option<RawDumpStatus>>(result).ok().flatten();
+                    if let Some(current) = &current {
+                        lines.write().clear();
+                        status.set(Some(format!("Dumping {} for {}s", current.device_path, current.remaining_secs)));
+                    }
+                    active.set(current);
+                }
+                Err(e) => status.set(Some(format!("Failed to set raw dump: {}", AppError::from_js(&e).user_message()))),
+            }
+        });
+    };
+
+    let export = move |_| {
+        let text = lines.read().join("\n");
+        let filename = format!(
+            "evdev-raw-{}.txt",
+            selected.read().rsplit('/').next().unwrap_or("device")
+        );
+        if let Err(e) = download_bytes(text.as_bytes(), &filename, "text/plain") {
+            status.set(Some(format!("Failed to export: {}", e)));
+        }
+    };
+
+    let shown = lines.read().len().min(RAW_DUMP_VIEW_LEN);
+    let view = lines.read()[lines.read().len() - shown..].join("\n");
+
+    rsx! {
+        div {
+            class: "debug-section",
+            h3 { "🔬 Raw Event Dump" }
+            div {
+                class: "button-group",
+                select {
+                    value: "{selected}",
+                    onchange: move |event| selected.set(event.value()),
+                    option { value: "", "Select a device" }
+                    for device in evdev_devices.read().iter().filter(|device| device.accessible) {
+                        option {
+                            key: "{device.device_path}",
+                            value: "{device.device_path}",
+                            "{device.name} ({device.device_path})"
+                        }
+                    }
+                }
+                label {
+                    "Auto-stop after (s) "
+                    input {
+                        r#type: "number",
+                        min: "1",
+                        value: "{duration}",
+                        oninput: move |event| duration.set(event.value()),
+                    }
+                }
+            }
+            div {
+                class: "button-group",
+                if active.read().is_some() {
+                    button {
+                        onclick: move |_| set_dump(false),
+                        "Stop Dump"
+                    }
+                } else {
+                    button {
+                        onclick: move |_| set_dump(true),
+                        disabled: selected.read().is_empty(),
+                        "Start Dump"
+                    }
+                }
+                button {
+                    onclick: move |_| {
+                        let next = !*paused.read();
+                        paused.set(next);
+                    },
+                    if *paused.read() { "Resume" } else { "Pause" }
+                }
+                button {
+                    onclick: move |_| lines.write().clear(),
+                    "Clear"
+                }
+                button {
+                    onclick: export,
+                    disabled: lines.read().is_empty(),
+                    "Export"
+                }
+            }
+            if let Some(message) = status.read().as_ref() {
+                p { "{message}" }
+            }
+            p { "{lines.read().len()} events buffered, showing the last {shown}" }
+            pre {
+                class: "raw-dump",
+                "{view}"
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PassthroughConfig {
+    pub source_device_path: String,
+    #[serde(default)]
+    pub exclusive: bool,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct VirtualGamepadStatus {
+    pub active: bool,
+    pub name: Option<String>,
+    pub passthrough: Option<PassthroughConfig>,
+    pub forwarded_events: u64,
+    pub write_errors: u64,
+}
+
+/// A uinput gamepad fed from one evdev node, so games see the remapped controller.
+#[component]
+fn VirtualGamepadPanel(evdev_devices: Signal<Vec<EvdevGamepadInfo>>) -> Element {
+    let mut current = use_signal(VirtualGamepadStatus::default);
+    let mut name = use_signal(|| "Steam Deck Controller Passthrough".to_string());
+    let mut source = use_signal(String::new);
+    let mut exclusive = use_signal(|| false);
+    let mut message = use_signal(|| None::<String>);
+
+    use_effect(move || {
+        spawn(async move {
+            if let Ok(result) = invoke_without_args("get_virtual_gamepad").await {
+                if let Ok(status) = serde_wasm_bindgen::from_value::<VirtualGamepadStatus>(result) {
+                    current.set(status);
+                }
+            }
+        });
+    });
+
+    let run = move |command: &'static str, args: serde_json::Value| {
+        spawn(async move {
+            let args = serde_wasm_bindgen::to_value(&args).unwrap();
+            match invoke(command, args).await {
+                Ok(result) => {
+                    if let Ok(status) = serde_wasm_bindgen::from_value::<VirtualGamepadStatus>(result) {
+                        current.set(status);
+                    }
+                    message.set(None);
+                }
+                Err(e) => message.set(Some(AppError::from_js(&e).user_message())),
+            }
+        });
+    };
+
+    let destroy = move |_| {
+        spawn(async move {
+            if invoke_without_args("destroy_virtual_gamepad").await.is_ok() {
+                current.set(VirtualGamepadStatus::default());
+            }
+        });
+    };
+
+    let status = current.read().clone();
+    let summary = match (&status.name, &status.passthrough) {
+        (None, _) => "No virtual gamepad".to_string(),
+        (Some(name), None) => format!("{} is up, with nothing passed through", name),
+        (Some(name), Some(passthrough)) => format!(
+            "{} is fed from {}{}: {} events written, {} write errors",
+            name,
+            passthrough.source_device_path,
+            if passthrough.exclusive { " (grabbed)" } else { "" },
+            status.forwarded_events,
+            status.write_errors
+        ),
+    };
+
+    rsx! {
+        div {
+            class: "debug-section",
+            h3 { "🕹️ Virtual Gamepad" }
+            p { "{summary}" }
+            div {
+                class: "button-group",
+                if status.active {
+                    button { onclick: destroy, "Destroy" }
+                } else {
+                    input {
+                        value: "{name}",
+                        oninput: move |event| name.set(event.value()),
+                    }
+                    button {
+                        onclick: move |_| run("create_virtual_gamepad", serde_json::json!({ "name": name.read().clone() })),
+                        "Create"
+                    }
+                }
+            }
+            if status.active {
+                div {
+                    class: "button-group",
+                    select {
+                        value: "{source}",
+                        onchange: move |event| source.set(event.value()),
+                        option { value: "", "Select a source" }
+                        for device in evdev_devices.read().iter().filter(|device| device.accessible && Some(&device.name) != status.name.as_ref()) {
+                            option {
+                                key: "{device.device_path}",
+                                value: "{device.device_path}",
+                                "{device.name} ({device.device_path})"
+                            }
+                        }
+                    }
+                    label {
+                        input {
+                            r#type: "checkbox",
+                            checked: *exclusive.read(),
+                            onchange: move |event| exclusive.set(event.checked()),
+                        }
+                        " Hide the source from other apps"
+                    }
+                    button {
+                        disabled: source.read().is_empty(),
+                        onclick: move |_| run("set_gamepad_passthrough", serde_json::json!({
+                            "config": { "source_device_path": source.read().clone(), "exclusive": *exclusive.read() }
+                        })),
+                        "Pass Through"
+                    }
+                    if status.passthrough.is_some() {
+                        button {
+                            onclick: move |_| run("set_gamepad_passthrough", serde_json::json!({ "config": null })),
+                            "Stop"
+                        }
+                    }
+                }
+            }
+            if let Some(text) = message.read().as_ref() {
+                p { "{text}" }
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum EmulationAction {
+    KeyPress { key: String },
+    KeyCombo { keys: Vec<String> },
+    MouseMove { scale: f32 },
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct EmulationRule {
+    pub id: String,
+    pub input: String,
+    pub action: EmulationAction,
+    pub enabled: bool,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct KeyEmulationConfig {
+    pub rules: Vec<EmulationRule>,
+    pub off_chord: Vec<String>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct KeyEmulationStatus {
+    pub enabled: bool,
+    pub active_rules: usize,
+    pub off_chord: Vec<String>,
+    pub stopped_by_chord: bool,
+    pub written_events: u64,
+    pub write_errors: u64,
+}
+
+fn describe_action(action: &EmulationAction) -> String {
+    match action {
+        EmulationAction::KeyPress { key } => format!("presses {}", key),
+        EmulationAction::KeyCombo { keys } => format!("presses {}", keys.join(" + ")),
+        EmulationAction::MouseMove { scale } => format!("moves the mouse at {} px/s", scale),
+    }
+}
+
+/// Rules that turn buttons into key presses and a stick into mouse motion, for software
+/// that only takes keyboard shortcuts. Off until switched on here, every session.
+#[component]
+fn KeyEmulationPanel() -> Element {
+    let mut config = use_signal(KeyEmulationConfig::default);
+    let mut current = use_signal(KeyEmulationStatus::default);
+    let mut input = use_signal(|| "North".to_string());
+    let mut kind = use_signal(|| "key_press".to_string());
+    let mut keys = use_signal(|| "F5".to_string());
+    let mut scale = use_signal(|| "800".to_string());
+    let mut message = use_signal(|| None::<String>);
+
+    use_effect(move || {
+        spawn(async move {
+            if let Ok(result) = invoke_without_args("get_key_emulation_config").await {
+                if let Ok(saved) = serde_wasm_bindgen::from_value::<KeyEmulationConfig>(result) {
+                    config.set(saved);
+                }
+            }
+            if let Ok(result) = invoke_without_args("get_key_emulation_status").await {
+                if let Ok(status) = serde_wasm_bindgen::from_value::<KeyEmulationStatus>(result) {
+                    current.set(status);
+                }
+            }
+        });
+    });
+
+    let save = move |updated: KeyEmulationConfig| {
+        spawn(async move {
+            let args = serde_wasm_bindgen::to_value(&serde_json::json!({ "config": updated })).unwrap();
+            match invoke("set_key_emulation_config", args).await {
+                Ok(result) => {
+                    if let Ok(saved) = serde_wasm_bindgen::from_value::<KeyEmulationConfig>(result) {
+                        config.set(saved);
+                    }
+                    if let Ok(result) = invoke_without_args("get_key_emulation_status").await {
+                        if let Ok(status) = serde_wasm_bindgen::from_value::<KeyEmulationStatus>(result) {
+                            current.set(status);
+                        }
+                    }
+                    message.set(None);
+                }
+                Err(e) => message.set(Some(AppError::from_js(&e).user_message())),
+            }
+        });
+    };
+
+    let set_enabled = move |enabled: bool| {
+        spawn(async move {
+            let args = serde_wasm_bindgen::to_value(&serde_json::json!({ "enabled": enabled })).unwrap();
+            match invoke("set_key_emulation_enabled", args).await {
+                Ok(result) => {
+                    if let Ok(status) = serde_wasm_bindgen::from_value::<KeyEmulationStatus>(result) {
+                        current.set(status);
+                    }
+                    message.set(None);
+                }
+                Err(e) => message.set(Some(AppError::from_js(&e).user_message())),
+            }
+        });
+    };
+
+    let add_rule = move |_| {
+        let action = if kind.read().as_str() == "mouse_move" {
+            match scale.read().trim().parse::<f32>() {
+                Ok(scale) => EmulationAction::MouseMove { scale },
+                Err(_) => {
+                    message.set(Some("The mouse speed must be a number".to_string()));
+                    return;
+                }
+            }
+        } else {
+            let mut names: Vec<String> = keys.read().split(',').map(|key| key.trim().to_string()).collect();
+            if names.len() == 1 {
+                EmulationAction::KeyPress { key: names.remove(0) }
+            } else {
+                EmulationAction::KeyCombo { keys: names }
+            }
+        };
+        let mut updated = config.read().clone();
+        let trigger = input.read().trim().to_string();
+        let id = (1..)
+            .map(|n| format!("{}-{}", trigger, n))
+            .find(|id| !updated.rules.iter().any(|rule| &rule.id == id))
+            .unwrap_or_default();
+        updated.rules.push(EmulationRule { id, input: trigger, action, enabled: true });
+        save(updated);
+    };
+
+    let status = current.read().clone();
+    let chord = if status.off_chord.is_empty() {
+        "no off chord".to_string()
+    } else {
+        format!("{} together turns it off", status.off_chord.join(" + "))
+    };
+    let summary = if status.enabled {
+        format!(
+            "On with {} active rule(s): {} events written, {} write errors; {}",
+            status.active_rules, status.written_events, status.write_errors, chord
+        )
+    } else if status.stopped_by_chord {
+        "Off: the off chord was pressed".to_string()
+    } else {
+        "Off".to_string()
+    };
+    let rules = config.read().rules.clone();
+
+    rsx! {
+        div {
+            class: "debug-section",
+            h3 { "⌨️ Keyboard and Mouse Emulation" }
+            p { "{summary}" }
+            div {
+                class: "button-group",
+                if status.enabled {
+                    button { onclick: move |_| set_enabled(false), "Turn Off" }
+                } else {
+                    button { onclick: move |_| set_enabled(true), "Turn On" }
+                }
+            }
+            for (index, rule) in rules.into_iter().enumerate() {
+                div {
+                    key: "{rule.id}",
+                    class: "button-group",
+                    label {
+                        input {
+                            r#type: "checkbox",
+                            checked: rule.enabled,
+                            onchange: move |event| {
+                                let mut updated = config.read().clone();
+                                updated.rules[index].enabled = event.checked();
+                                save(updated);
+                            },
+                        }
+                        " {rule.input} {describe_action(&rule.action)}"
+                    }
+                    button {
+                        onclick: move |_| {
+                            let mut updated = config.read().clone();
+                            updated.rules.remove(index);
+                            save(updated);
+                        },
+                        "Remove"
+                    }
+                }
+            }
+            div {
+                class: "button-group",
+                select {
+                    value: "{kind}",
+                    onchange: move |event| kind.set(event.value()),
+                    option { value: "key_press", "Button presses keys" }
+                    option { value: "mouse_move", "Stick moves the mouse" }
+                }
+                input {
+                    value: "{input}",
+                    placeholder: "North, or RightStick",
+                    oninput: move |event| input.set(event.value()),
+                }
+                if kind.read().as_str() == "mouse_move" {
+                    input {
+                        value: "{scale}",
+                        placeholder: "Pixels per second",
+                        oninput: move |event| scale.set(event.value()),
+                    }
+                } else {
+                    input {
+                        value: "{keys}",
+                        placeholder: "F5, or Ctrl, S",
+                        oninput: move |event| keys.set(event.value()),
+                    }
+                }
+                button { onclick: add_rule, "Add Rule" }
+            }
+            if let Some(text) = message.read().as_ref() {
+                p { "{text}" }
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum MidiAction {
+    Note { channel: u8, note: u8, velocity: u8 },
+    ControlChange { channel: u8, controller: u8, #[serde(default)] threshold: u8 },
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MidiRule {
+    pub id: String,
+    pub input: String,
+    pub action: MidiAction,
+    pub enabled: bool,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct MidiStatus {
+    pub open: bool,
+    pub port: Option<String>,
+    pub virtual_port: bool,
+    pub active_rules: usize,
+    pub sent_messages: u64,
+    pub suppressed_messages: u64,
+    pub send_errors: u64,
+    pub last_error: Option<String>,
+}
+
+fn describe_midi_action(action: &MidiAction) -> String {
+    match action {
+        MidiAction::Note { channel, note, velocity } => format!("plays note {} at velocity {} on channel {}", note, velocity, channel),
+        MidiAction::ControlChange { channel, controller, threshold } => {
+            format!("sends CC {} on channel {} (threshold {})", controller, channel, threshold)
+        }
+    }
+}
+
+/// A MIDI output port and the rules that turn buttons into notes and axes into CCs.
+#[component]
+fn MidiPanel() -> Element {
+    let mut outputs = use_signal(Vec::<String>::new);
+    let mut selected = use_signal(|| "virtual".to_string());
+    let mut current = use_signal(MidiStatus::default);
+    let mut rules = use_signal(Vec::<MidiRule>::new);
+    let mut kind = use_signal(|| "note".to_string());
+    let mut input = use_signal(|| "South".to_string());
+    let mut channel = use_signal(|| "1".to_string());
+    let mut number = use_signal(|| "60".to_string());
+    let mut extra = use_signal(|| "100".to_string());
+    let mut message = use_signal(|| None::<String>);
+
+    let refresh = move || {
+        spawn(async move {
+            if let Ok(result) = invoke_without_args("list_midi_outputs").await {
+                if let Ok(names) = serde_wasm_bindgen::from_value::<Vec<String>>(result) {
+                    outputs.set(names);
+                }
+            }
+            if let Ok(result) = invoke_without_args("get_midi_status").await {
+                if let Ok(status) = serde_wasm_bindgen::from_value::<MidiStatus>(result) {
+                    current.set(status);
+                }
+            }
+        });
+    };
+
+    use_effect(move || {
+        refresh();
+        spawn(async move {
+            if let Ok(result) = invoke_without_args("get_midi_rules").await {
+                if let Ok(saved) = serde_wasm_bindgen::from_value::<Vec<MidiRule>>(result) {
+                    rules.set(saved);
+                }
+            }
+        });
+    });
+
+    let open = move |_| {
+        spawn(async move {
+            let args = serde_wasm_bindgen::to_value(&serde_json::json!({ "name": selected.read().clone() })).unwrap();
+            match invoke("open_midi_output", args).await {
+                Ok(result) => {
+                    if let Ok(status) = serde_wasm_bindgen::from_value::<MidiStatus>(result) {
+                        current.set(status);
+                    }
+                    message.set(None);
+                }
+                Err(e) => message.set(Some(AppError::from_js(&e).user_message())),
+            }
+        });
+    };
+
+    let close = move |_| {
+        spawn(async move {
+            if invoke_without_args("close_midi_output").await.is_ok() {
+                refresh();
+            }
+        });
+    };
+
+    let save = move |updated: Vec<MidiRule>| {
+        spawn(async move {
+            let args = serde_wasm_bindgen::to_value(&serde_json::json!({ "rules": updated })).unwrap();
+            match invoke("set_midi_rules", args).await {
+                Ok(result) => {
+                    if let Ok(saved) = serde_wasm_bindgen::from_value::<Vec<MidiRule>>(result) {
+                        rules.set(saved);
+                    }
+                    refresh();
+                    message.set(None);
+                }
+                Err(e) => message.set(Some(AppError::from_js(&e).user_message())),
+            }
+        });
+    };
+
+    let add_rule = move |_| {
+        let parsed = (
+            channel.read().trim().parse::<u8>(),
+            number.read().trim().parse::<u8>(),
+            extra.read().trim().parse::<u8>(),
+        );
+        let (Ok(channel), Ok(number), Ok(extra)) = parsed else {
+            message.set(Some("Channel, note or controller, and velocity or threshold must be numbers".to_string()));
+            return;
+        };
+        let action = if kind.read().as_str() == "control_change" {
+            MidiAction::ControlChange { channel, controller: number, threshold: extra }
+        } else {
+            MidiAction::Note { channel, note: number, velocity: extra }
+        };
+        let mut updated = rules.read().clone();
+        let trigger = input.read().trim().to_string();
+        let id = (1..)
+            .map(|n| format!("midi-{}-{}", trigger, n))
+            .find(|id| !updated.iter().any(|rule| &rule.id == id))
+            .unwrap_or_default();
+        updated.push(MidiRule { id, input: trigger, action, enabled: true });
+        save(updated);
+    };
+
+    let status = current.read().clone();
+    let summary = match &status.port {
+        Some(port) => format!(
+            "Sending to {}{}: {} sent, {} held back, {} failed, {} active rule(s)",
+            port,
+            if status.virtual_port { " (virtual port)" } else { "" },
+            status.sent_messages,
+            status.suppressed_messages,
+            status.send_errors,
+            status.active_rules
+        ),
+        None => "No MIDI output open".to_string(),
+    };
+
+    rsx! {
+        div {
+            class: "debug-section",
+            h3 { "🎹 MIDI Output" }
+            p { "{summary}" }
+            if let Some(error) = status.last_error.as_ref() {
+                p { "Last error: {error}" }
+            }
+            div {
+                class: "button-group",
+                select {
+                    value: "{selected}",
+                    onchange: move |event| selected.set(event.value()),
+                    option { value: "virtual", "Virtual port" }
+                    for name in outputs.read().iter() {
+                        option { key: "{name}", value: "{name}", "{name}" }
+                    }
+                }
+                button { onclick: move |_| refresh(), "Refresh" }
+                button { onclick: open, "Open" }
+                if status.open {
+                    button { onclick: close, "Close" }
+                }
+            }
+            for (index, rule) in rules.read().clone().into_iter().enumerate() {
+                div {
+                    key: "{rule.id}",
+                    class: "button-group",
+                    label {
+                        input {
+                            r#type: "checkbox",
+                            checked: rule.enabled,
+                            onchange: move |event| {
+                                let mut updated = rules.read().clone();
+                                updated[index].enabled = event.checked();
+                                save(updated);
+                            },
+                        }
+                        " {rule.input} {describe_midi_action(&rule.action)}"
+                    }
+                    button {
+                        onclick: move |_| {
+                            let mut updated = rules.read().clone();
+                            updated.remove(index);
+                            save(updated);
+                        },
+                        "Remove"
+                    }
+                }
+            }
+            div {
+                class: "button-group",
+                select {
+                    value: "{kind}",
+                    onchange: move |event| {
+                        extra.set(if event.value() == "control_change" { "2" } else { "100" }.to_string());
+                        kind.set(event.value());
+                    },
+                    option { value: "note", "Button plays a note" }
+                    option { value: "control_change", "Axis sends a CC" }
+                }
+                input {
+                    value: "{input}",
+                    placeholder: "South, or LeftStickX",
+                    oninput: move |event| input.set(event.value()),
+                }
+                input {
+                    value: "{channel}",
+                    placeholder: "Channel",
+                    oninput: move |event| channel.set(event.value()),
+                }
+                input {
+                    value: "{number}",
+                    placeholder: "Note or controller",
+                    oninput: move |event| number.set(event.value()),
+                }
+                input {
+                    value: "{extra}",
+                    placeholder: "Velocity or threshold",
+                    oninput: move |event| extra.set(event.value()),
+                }
+                button { onclick: add_rule, "Add Rule" }
+            }
+            if let Some(text) = message.read().as_ref() {
+                p { "{text}" }
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct EventStreamConfig {
+    pub path: String,
+    pub format: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EventStreamStopped {
+    pub path: String,
+    pub reason: String,
+    pub event_count: u64,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct EventStreamStatus {
+    pub active: bool,
+    pub config: Option<EventStreamConfig>,
+    pub file_bytes: u64,
+    pub event_count: u64,
+    pub send_count: u64,
+    pub rotations: u32,
+    pub write_errors: u64,
+    pub last_stopped: Option<EventStreamStopped>,
+}
+
+#[component]
+fn EventStreamPanel() -> Element {
+    let mut current = use_signal(EventStreamStatus::default);
+    let mut path = use_signal(|| "/home/deck/controller-events.jsonl".to_string());
+    let mut format = use_signal(|| "jsonl".to_string());
+    let mut include_sends = use_signal(|| false);
+    let mut message = use_signal(|| None::<String>);
+
+    let refresh = move || {
+        spawn(async move {
+            if let Ok(result) = invoke_without_args("get_event_stream_status").await {
+                if let Ok(status) = serde_wasm_bindgen::from_value::<EventStreamStatus>(result) {
+                    current.set(status);
+                }
+            }
+        });
+    };
+
+    use_effect(move || {
+        refresh();
+        spawn(async move {
+            let handler = Closure::<dyn FnMut(JsValue)>::new(move |event: JsValue| {
+                if let Ok(stopped) = serde_wasm_bindgen::from_value::<EventStreamStopped>(event) {
+                    message.set(Some(format!("Stopped streaming to {}: {}", stopped.path, stopped.reason)));
+                    refresh();
+                }
+            });
+            let _ = listen("event-stream-stopped", &handler).await;
+            handler.forget();
+        });
+    });
+
+    let start = move |_| {
+        spawn(async move {
+            let args = serde_wasm_bindgen::to_value(&serde_json::json!({
+                "path": path.read().trim(),
+                "format": format.read().clone(),
+                "includeSendResults": *include_sends.read(),
+            }))
+            .unwrap();
+            match invoke("start_event_stream", args).await {
+                Ok(result) => {
+                    if let Ok(status) = serde_wasm_bindgen::from_value::<EventStreamStatus>(result) {
+                        current.set(status);
+                    }
+                    message.set(None);
+                }
+                Err(e) => message.set(Some(AppError::from_js(&e).user_message())),
+            }
+        });
+    };
+
+    let stop = move |_| {
+        spawn(async move {
+            match invoke_without_args("stop_event_stream").await {
+                Ok(_) => refresh(),
+                Err(e) => message.set(Some(AppError::from_js(&e).user_message())),
+            }
+        });
+    };
+
+    let status = current.read().clone();
+    let summary = match (&status.config, status.active) {
+        (Some(config), true) => format!(
+            "Streaming to {} ({}): {} KiB, {} event(s), {} send(s), {} rotation(s), {} write error(s)",
+            config.path,
+            config.format,
+            status.file_bytes / 1024,
+            status.event_count,
+            status.send_count,
+            status.rotations,
+            status.write_errors
+        ),
+        _ => "Not streaming".to_string(),
+    };
+
+    rsx! {
+        div {
+            class: "debug-section",
+            h3 { "💾 Event Stream" }
+            p { "{summary}" }
+            div {
+                class: "button-group",
+                input {
+                    value: "{path}",
+                    placeholder: "File to append to",
+                    oninput: move |event| path.set(event.value()),
+                }
+                select {
+                    value: "{format}",
+                    onchange: move |event| format.set(event.value()),
+                    option { value: "jsonl", "JSONL" }
+                    option { value: "csv", "CSV" }
+                }
+                label {
+                    input {
+                        r#type: "checkbox",
+                        checked: *include_sends.read(),
+                        onchange: move |event| include_sends.set(event.checked()),
+                    }
+                    " Include send results"
+                }
+                if status.active {
+                    button { onclick: stop, "Stop" }
+                } else {
+                    button { onclick: start, "Start" }
+                }
+                button { onclick: move |_| refresh(), "Refresh" }
+            }
+            if let Some(text) = message.read().as_ref() {
+                p { "{text}" }
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct EventScript {
+    pub name: String,
+    pub source: String,
+    pub enabled: bool,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct EventScriptStatus {
+    pub name: String,
+    pub source: String,
+    pub enabled: bool,
+    pub runs: u64,
+    pub failures: u64,
+    pub last_error: Option<String>,
+    pub max_run_us: u64,
+    pub disabled_reason: Option<String>,
+}
+
+#[component]
+fn EventScriptPanel() -> Element {
+    let mut scripts = use_signal(Vec::<EventScriptStatus>::new);
+    let mut examples = use_signal(Vec::<EventScript>::new);
+    let mut name = use_signal(String::new);
+    let mut source = use_signal(String::new);
+    let mut error = use_signal(|| None::<String>);
+
+    let refresh = move || {
+        spawn(async move {
+            if let Ok(result) = invoke_without_args("list_event_scripts").await {
+                if let Ok(statuses) = serde_wasm_bindgen::from_value::<Vec<EventScriptStatus>>(result) {
+                    scripts.set(statuses);
+                }
+            }
+        });
+    };
+
+    use_effect(move || {
+        refresh();
+        spawn(async move {
+            if let Ok(result) = invoke_without_args("get_event_script_examples").await {
+                if let Ok(loaded) = serde_wasm_bindgen::from_value::<Vec<EventScript>>(result) {
+                    examples.set(loaded);
+                }
+            }
+            let handler = Closure::<dyn FnMut(JsValue)>::new(move |event: JsValue| {
+                if let Ok(status) = serde_wasm_bindgen::from_value::<EventScriptStatus>(event) {
+                    error.set(Some(format!("Turned '{}' off: {}", status.name, status.disabled_reason.unwrap_or_default())));
+                    refresh();
+                }
+            });
+            let _ = listen("event-script-disabled", &handler).await;
+            handler.forget();
+        });
+    });
+
+    let save = move |_| {
+        spawn(async move {
+            let args = serde_wasm_bindgen::to_value(&serde_json::json!({
+                "name": name.read().trim(),
+                "source": source.read().clone(),
+            }))
+            .unwrap();
+            match invoke("set_event_script", args).await {
+                Ok(_) => {
+                    error.set(None);
+                    refresh();
+                }
+                Err(e) => error.set(Some(AppError::from_js(&e).user_message())),
+            }
+        });
+    };
+
+    let set_enabled = move |script: String, enabled: bool| {
+        spawn(async move {
+            let args = serde_wasm_bindgen::to_value(&serde_json::json!({ "name": script, "enabled": enabled })).unwrap();
+            if let Err(e) = invoke("set_event_script_enabled", args).await {
+                error.set(Some(AppError::from_js(&e).user_message()));
+            }
+            refresh();
+        });
+    };
+
+    let remove = move |script: String| {
+        spawn(async move {
+            let args = serde_wasm_bindgen::to_value(&serde_json::json!({ "name": script })).unwrap();
+            if invoke("remove_event_script", args).await.is_ok() {
+                refresh();
+            }
+        });
+    };
+
+    rsx! {
+        div {
+            class: "debug-section",
+            h3 { "📜 Event Scripts" }
+            if scripts.read().is_empty() {
+                p { "No scripts" }
+            }
+            for script in scripts.read().clone().into_iter() {
+                div {
+                    key: "{script.name}",
+                    class: "button-group",
+                    label {
+                        input {
+                            r#type: "checkbox",
+                            checked: script.enabled,
+                            onchange: {
+                                let script = script.name.clone();
+                                move |event: FormEvent| set_enabled(script.clone(), event.checked())
+                            },
+                        }
+                        " {script.name}: {script.runs} run(s), {script.failures} failure(s), longest {script.max_run_us}µs"
+                    }
+                    button {
+                        onclick: {
+                            let script = script.clone();
+                            move |_| {
+                                name.set(script.name.clone());
+                                source.set(script.source.clone());
+                            }
+                        },
+                        "Edit"
+                    }
+                    button {
+                        onclick: {
+                            let script = script.name.clone();
+                            move |_| remove(script.clone())
+                        },
+                        "Remove"
+                    }
+                    if let Some(reason) = script.disabled_reason.as_ref().or(script.last_error.as_ref()) {
+                        p { "{reason}" }
+                    }
+                }
+            }
+            div {
+                class: "button-group",
+                input {
+                    value: "{name}",
+                    placeholder: "Script name",
+                    oninput: move |event| name.set(event.value()),
+                }
+                select {
+                    value: "",
+                    onchange: move |event| {
+                        let chosen = examples.read().iter().find(|example| example.name == event.value()).cloned();
+                        if let Some(example) = chosen {
+                            name.set(example.name);
+                            source.set(example.source);
+                        }
+                    },
+                    option { value: "", "Start from an example…" }
+                    for example in examples.read().iter() {
+                        option { key: "{example.name}", value: "{example.name}", "{example.name}" }
+                    }
+                }
+            }
+            textarea {
+                class: "script-editor",
+                rows: "12",
+                spellcheck: "false",
+                value: "{source}",
+                oninput: move |event| source.set(event.value()),
+            }
+            div {
+                class: "button-group",
+                button { onclick: save, "Save Script" }
+            }
+            if let Some(text) = error.read().as_ref() {
+                p { class: "error", "{text}" }
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ExternalProcessorStatus {
+    pub running: bool,
+    pub command: Option<String>,
+    pub pid: Option<u32>,
+    pub restarts: u32,
+    pub events_sent: u64,
+    pub coalesced_events: u64,
+    pub dropped_events: u64,
+    pub actions_received: u64,
+    pub bad_lines: u64,
+    pub last_error: Option<String>,
+    pub last_stderr: Option<String>,
+}
+
+#[component]
+fn ExternalProcessorPanel() -> Element {
+    let mut current = use_signal(ExternalProcessorStatus::default);
+    let mut command = use_signal(|| "python3".to_string());
+    let mut args = use_signal(String::new);
+    let mut message = use_signal(|| None::<String>);
+
+    let refresh = move || {
+        spawn(async move {
+            if let Ok(result) = invoke_without_args("get_external_processor_status").await {
+                if let Ok(status) = serde_wasm_bindgen::from_value::<ExternalProcessorStatus>(result) {
+                    current.set(status);
+                }
+            }
+        });
+    };
+
+    use_effect(refresh);
+
+    let start = move |_| {
+        spawn(async move {
+            let split: Vec<String> = args.read().split_whitespace().map(str::to_string).collect();
+            let args = serde_wasm_bindgen::to_value(&serde_json::json!({
+                "command": command.read().trim(),
+                "args": split,
+            }))
+            .unwrap();
+            match invoke("start_external_processor", args).await {
+                Ok(result) => {
+                    if let Ok(status) = serde_wasm_bindgen::from_value::<ExternalProcessorStatus>(result) {
+                        current.set(status);
+                    }
+                    message.set(None);
+                }
+                Err(e) => message.set(Some(AppError::from_js(&e).user_message())),
+            }
+        });
+    };
+
+    let stop = move |_| {
+        spawn(async move {
+            if invoke_without_args("stop_external_processor").await.is_ok() {
+                refresh();
+            }
+        });
+    };
+
+    let status = current.read().clone();
+    let summary = match (&status.command, status.running) {
+        (Some(command), true) => format!(
+            "Running {} (pid {}): {} event(s) sent, {} coalesced, {} dropped, {} action(s), {} bad line(s), {} restart(s)",
+            command,
+            status.pid.map(|pid| pid.to_string()).unwrap_or_default(),
+            status.events_sent,
+            status.coalesced_events,
+            status.dropped_events,
+            status.actions_received,
+            status.bad_lines,
+            status.restarts
+        ),
+        _ => "Not running".to_string(),
+    };
+
+    rsx! {
+        div {
+            class: "debug-section",
+            h3 { "🔌 External Processor" }
+            p { "{summary}" }
+            if let Some(error) = status.last_error.as_ref() {
+                p { "Last error: {error}" }
+            }
+            if let Some(line) = status.last_stderr.as_ref() {
+                p { "stderr: {line}" }
+            }
+            div {
+                class: "button-group",
+                input {
+                    value: "{command}",
+                    placeholder: "Program",
+                    oninput: move |event| command.set(event.value()),
+                }
+                input {
+                    value: "{args}",
+                    placeholder: "Arguments",
+                    oninput: move |event| args.set(event.value()),
+                }
+                button { onclick: start, "Start" }
+                button { onclick: stop, "Kill" }
+                button { onclick: move |_| refresh(), "Refresh" }
+            }
+            if let Some(text) = message.read().as_ref() {
+                p { class: "error", "{text}" }
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct OpcStatus {
+    pub configured: bool,
+    pub host: Option<String>,
+    pub port: Option<u16>,
+    pub channel: Option<u8>,
+    pub pixel_count: Option<usize>,
+    pub frame_rate: Option<u32>,
+    pub generator: Option<serde_json::Value>,
+    pub connected: bool,
+    pub connected_at: Option<u64>,
+    pub frames_sent: u64,
+    pub reconnects: u64,
+    pub last_error: Option<String>,
+}
+
+/// A generator of each kind, painting from the left stick, the right trigger or the face
+/// buttons.
+fn opc_generator(kind: &str) -> serde_json::Value {
+    match kind {
+        "solid" => serde_json::json!({ "type": "solid", "x_axis": "LeftStickX", "y_axis": "LeftStickY" }),
+        "bar" => serde_json::json!({ "type": "bar", "axis": "RightZ" }),
+        "button_flashes" => serde_json::json!({
+            "type": "button_flashes",
+            "buttons": ["South", "East", "West", "North"],
+            "color": [255, 255, 255],
+        }),
+        _ => serde_json::Value::Null,
+    }
+}
+
+#[component]
+fn OpcPanel() -> Element {
+    let mut current = use_signal(OpcStatus::default);
+    let mut host = use_signal(|| "localhost".to_string());
+    let mut port = use_signal(|| "7890".to_string());
+    let mut channel = use_signal(|| "0".to_string());
+    let mut pixel_count = use_signal(|| "64".to_string());
+    let mut message = use_signal(|| None::<String>);
+
+    let refresh = move || {
+        spawn(async move {
+            if let Ok(result) = invoke_without_args("get_opc_status").await {
+                if let Ok(status) = serde_wasm_bindgen::from_value::<OpcStatus>(result) {
+                    current.set(status);
+                }
+            }
+        });
+    };
+
+    use_effect(move || {
+        refresh();
+        spawn(async move {
+            let status_handler = Closure::<dyn FnMut(JsValue)>::new(move |event: JsValue| {
+                if let Ok(status) = serde_wasm_bindgen::from_value::<OpcStatus>(event) {
+                    current.set(status);
+                }
+            });
+            let _ = listen("opc-status", &status_handler).await;
+            status_handler.forget();
+        });
+    });
+
+    let mut apply = move |result: Result<JsValue, JsValue>| match result {
+        Ok(result) => {
+            if let Ok(status) = serde_wasm_bindgen::from_value::<OpcStatus>(result) {
+                current.set(status);
+            }
+            message.set(None);
+        }
+        Err(e) => message.set(Some(AppError::from_js(&e).user_message())),
+    };
+
+    let connect = move |_| {
+        let parsed = (
+            port.read().trim().parse::<u16>(),
+            channel.read().trim().parse::<u8>(),
+            pixel_count.read().trim().parse::<usize>(),
+        );
+        let (Ok(port), Ok(channel), Ok(pixel_count)) = parsed else {
+            message.set(Some("Port, channel and pixel count must be numbers".to_string()));
+            return;
+        };
+        spawn(async move {
+            let args = serde_wasm_bindgen::to_value(&serde_json::json!({
+                "host": host.read().trim(),
+                "port": port,
+                "channel": channel,
+                "pixelCount": pixel_count,
+            }))
+            .unwrap();
+            apply(invoke("configure_opc", args).await);
+        });
+    };
+
+    let stop = move |_| {
+        spawn(async move {
+            if invoke_without_args("stop_opc").await.is_ok() {
+                refresh();
+            }
+        });
+    };
+
+    let status = current.read().clone();
+    let kind = status.generator.as_ref()
+        .and_then(|generator| generator.get("type"))
+        .and_then(|kind| kind.as_str())
+        .unwrap_or("none")
+        .to_string();
+    let summary = match (&status.host, status.port) {
+        (Some(host), Some(port)) => format!(
+            "{} {}:{} channel {}, {} pixels at {} fps: {} frame(s) sent, {} reconnect(s)",
+            if status.connected { "Connected to" } else { "Waiting for" },
+            host,
+            port,
+            status.channel.unwrap_or_default(),
+            status.pixel_count.unwrap_or_default(),
+            status.frame_rate.unwrap_or_default(),
+            status.frames_sent,
+            status.reconnects
+        ),
+        _ => "No OPC server configured".to_string(),
+    };
+
+    rsx! {
+        div {
+            class: "debug-section",
+            h3 { "💡 OPC Pixels" }
+            p { "{summary}" }
+            if let Some(error) = status.last_error.as_ref() {
+                p { "Last error: {error}" }
+            }
+            div {
+                class: "button-group",
+                input {
+                    value: "{host}",
+                    placeholder: "Host",
+                    oninput: move |event| host.set(event.value()),
+                }
+                input {
+                    value: "{port}",
+                    placeholder: "Port",
+                    oninput: move |event| port.set(event.value()),
+                }
+                input {
+                    value: "{channel}",
+                    placeholder: "Channel",
+                    oninput: move |event| channel.set(event.value()),
+                }
+                input {
+                    value: "{pixel_count}",
+                    placeholder: "Pixels",
+                    oninput: move |event| pixel_count.set(event.value()),
+                }
+                button { onclick: connect, "Connect" }
+                if status.configured {
+                    button { onclick: stop, "Stop" }
+                }
+            }
+            if status.configured {
+                div {
+                    class: "button-group",
+                    select {
+                        value: "{kind}",
+                        onchange: move |event| {
+                            let generator = opc_generator(&event.value());
+                            spawn(async move {
+                                let args = serde_wasm_bindgen::to_value(&serde_json::json!({ "generator": generator })).unwrap();
+                                apply(invoke("set_opc_generator", args).await);
+                            });
+                        },
+                        option { value: "none", "No generator" }
+                        option { value: "solid", "Left stick as color" }
+                        option { value: "bar", "Right trigger as a bar" }
+                        option { value: "button_flashes", "Face button flashes" }
+                    }
+                }
+            }
+            if let Some(text) = message.read().as_ref() {
+                p { class: "error", "{text}" }
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ShowCue {
+    pub at_ms: u64,
+    pub label: String,
+    pub duration_ms: Option<u64>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ShowProgress {
+    pub playing: bool,
+    pub position_ms: u64,
+    pub duration_ms: u64,
+    pub next_cue: Option<usize>,
+    pub running_cue: Option<usize>,
+    pub finished: bool,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ShowStatus {
+    pub loaded: bool,
+    pub path: Option<String>,
+    pub name: Option<String>,
+    pub cues: Vec<ShowCue>,
+    pub progress: ShowProgress,
+    pub dispatched: u64,
+    pub max_late_ms: u64,
+}
+
+fn format_timecode(ms: u64) -> String {
+    format!("{}:{:02}.{:03}", ms / 60_000, ms / 1000 % 60, ms % 1000)
+}
+
+fn cue_class(progress: &ShowProgress, index: usize) -> &'static str {
+    if progress.running_cue == Some(index) {
+        "running"
+    } else if progress.next_cue == Some(index) {
+        "next"
+    } else {
+        ""
+    }
+}
+
+#[component]
+fn ShowPanel() -> Element {
+    let mut current = use_signal(ShowStatus::default);
+    let mut progress = use_signal(ShowProgress::default);
+    let mut path = use_signal(String::new);
+    let mut message = use_signal(|| None::<String>);
+
+    let mut apply = move |result: Result<JsValue, JsValue>| match result {
+        Ok(result) => {
+            if let Ok(status) = serde_wasm_bindgen::from_value::<ShowStatus>(result) {
+                progress.set(status.progress.clone());
+                current.set(status);
+            }
+            message.set(None);
+        }
+        Err(e) => message.set(Some(AppError::from_js(&e).user_message())),
+    };
+
+    use_effect(move || {
+        spawn(async move {
+            apply(invoke_without_args("get_show_status").await);
+            let progress_handler = Closure::<dyn FnMut(JsValue)>::new(move |event: JsValue| {
+                if let Ok(update) = serde_wasm_bindgen::from_value::<ShowProgress>(event) {
+                    progress.set(update);
+                }
+            });
+            let _ = listen("show-progress", &progress_handler).await;
+            progress_handler.forget();
+        });
+    });
+
+    let load = move |_| {
+        spawn(async move {
+            let args = serde_wasm_bindgen::to_value(&serde_json::json!({ "path": path.read().trim() })).unwrap();
+            apply(invoke("load_show", args).await);
+        });
+    };
+
+    let seek = move |ms: u64| {
+        spawn(async move {
+            let args = serde_wasm_bindgen::to_value(&serde_json::json!({ "ms": ms })).unwrap();
+            apply(invoke("seek_show", args).await);
+        });
+    };
+
+    let status = current.read().clone();
+    let playhead = progress.read().clone();
+    let percent = |ms: u64| ms as f64 / playhead.duration_ms.max(1) as f64 * 100.0;
+    let summary = match (&status.path, status.loaded) {
+        (Some(path), true) => format!(
+            "{} — {} cue(s), {} of {}{}",
+            status.name.clone().unwrap_or_else(|| path.clone()),
+            status.cues.len(),
+            format_timecode(playhead.position_ms),
+            format_timecode(playhead.duration_ms),
+            if playhead.playing { ", playing" } else if playhead.finished { ", finished" } else { ", paused" }
+        ),
+        _ => "No show loaded".to_string(),
+    };
+
+    rsx! {
+        div {
+            class: "debug-section",
+            h3 { "🎬 Show Playback" }
+            p { "{summary}" }
+            div {
+                class: "button-group",
+                input {
+                    value: "{path}",
+                    placeholder: "/home/deck/show.json",
+                    oninput: move |event| path.set(event.value()),
+                }
+                button { onclick: load, "Load" }
+                if status.loaded {
+                    button {
+                        onclick: move |_| {
+                            spawn(async move { apply(invoke_without_args("start_show").await); });
+                        },
+                        "Start"
+                    }
+                    button {
+                        onclick: move |_| {
+                            spawn(async move { apply(invoke_without_args("pause_show").await); });
+                        },
+                        "Pause"
+                    }
+                    button { onclick: move |_| seek(0), "Rewind" }
+                }
+            }
+            if status.loaded {
+                div {
+                    class: "show-timeline",
+                    for (index, cue) in status.cues.iter().enumerate() {
+                        div {
+                            key: "{index}",
+                            class: "show-cue-marker",
+                            title: "{cue.label}",
+                            style: "left: {percent(cue.at_ms)}%; width: {percent(cue.duration_ms.unwrap_or(0))}%",
+                        }
+                    }
+                    div {
+                        class: "show-playhead",
+                        style: "left: {percent(playhead.position_ms)}%",
+                    }
+                }
+                p { "{status.dispatched} cue(s) sent, at most {status.max_late_ms}ms late. A starts, B pauses, the d-pad nudges" }
+                ul {
+                    class: "show-cue-list",
+                    for (index, cue) in status.cues.clone().into_iter().enumerate() {
+                        li {
+                            key: "{index}",
+                            class: "{cue_class(&playhead, index)}",
+                            onclick: move |_| seek(cue.at_ms),
+                            "{format_timecode(cue.at_ms)} {cue.label}"
+                            if let Some(duration) = cue.duration_ms {
+                                " (for {format_timecode(duration)})"
+                            }
+                        }
+                    }
+                }
+            }
+            if let Some(text) = message.read().as_ref() {
+                p { class: "error", "{text}" }
+            }
+        }
+    }
+}
+
+#[component]
+fn BackendWarningToasts() -> Element {
+    // (id, expiry in ms since the epoch, warning)
+    let mut toasts = use_signal(Vec::<(u32, f64, BackendWarning)>::new);
+    let mut next_id = use_signal(|| 0u32);
+
+    use_effect(move || {
+        spawn(async move {
+            let warning_handler = Closure::<dyn FnMut(JsValue)>::new(move |event: JsValue| {
+                if let Ok(warning) = serde_wasm_bindgen::from_value::<BackendWarning>(event) {
+                    let id = *next_id.peek();
+                    next_id.set(id + 1);
+                    toasts.write().push((id, js_sys::Date::now() + TOAST_DURATION_MS, warning));
+                }
+            });
+            let _ = listen("backend-warning", &warning_handler).await;
+            warning_handler.forget();
+        });
+    });
+
+    use_coroutine(move |_: UnboundedReceiver<()>| async move {
+        loop {
+            TimeoutFuture::new(500).await;
+            let now = js_sys::Date::now();
+            if toasts.peek().iter().any(|(_, expires_at, _)| *expires_at <= now) {
+                toasts.write().retain(|(_, expires_at, _)| *expires_at > now);
+            }
+        }
+    });
+
+    rsx! {
+        for (id, _, warning) in toasts.read().iter().cloned() {
+            div {
+                key: "{id}",
+                class: "toast",
+                onclick: move |_| toasts.write().retain(|(toast_id, _, _)| *toast_id != id),
+                strong { "⚠️ {warning.category}: {warning.last_minute} failures in the last minute" }
+                p { "{warning.message}" }
+            }
+        }
+    }
+}
+
+/// Explains why stick and motion updates thinned out when the input queue overflowed.
+#[component]
+fn EventPressureToast() -> Element {
+    // (expiry in ms since the epoch, pressure)
+    let mut pressure = use_signal(|| None::<(f64, EventPressure)>);
+
+    use_effect(move || {
+        spawn(async move {
+            let handler = Closure::<dyn FnMut(JsValue)>::new(move |event: JsValue| {
+                if let Ok(latest) = serde_wasm_bindgen::from_value::<EventPressure>(event) {
+                    pressure.set(Some((js_sys::Date::now() + TOAST_DURATION_MS, latest)));
+                }
+            });
+            let _ = listen("event-pressure", &handler).await;
+            handler.forget();
+        });
+    });
+
+    use_coroutine(move |_: UnboundedReceiver<()>| async move {
+        loop {
+            TimeoutFuture::new(500).await;
+            let expired = pressure.peek().as_ref().is_some_and(|(expires_at, _)| *expires_at <= js_sys::Date::now());
+            if expired {
+                pressure.set(None);
+            }
+        }
+    });
+
+    let Some((_, latest)) = pressure.read().clone() else {
+        return rsx! {};
+    };
+
+    rsx! {
+        div {
+            class: "toast",
+            onclick: move |_| pressure.set(None),
+            strong { "⚠️ Input is arriving faster than it can be handled" }
+            p {
+                "The queue of {latest.capacity} events is full, so stick, motion and touchpad updates are being thinned to their latest values. Button presses are never dropped."
+            }
+        }
+    }
+}
+
+/// What was brought back after the system woke from sleep.
+#[component]
+fn ResumeToast() -> Element {
+    // (expiry in ms since the epoch, summary)
+    let mut resumed = use_signal(|| None::<(f64, SystemResumed)>);
+
+    use_effect(move || {
+        spawn(async move {
+            let handler = Closure::<dyn FnMut(JsValue)>::new(move |event: JsValue| {
+                if let Ok(latest) = serde_wasm_bindgen::from_value::<SystemResumed>(event) {
+                    resumed.set(Some((js_sys::Date::now() + TOAST_DURATION_MS, latest)));
+                }
+            });
+            let _ = listen("system-resumed", &handler).await;
+            handler.forget();
+        });
+    });
+
+    use_coroutine(move |_: UnboundedReceiver<()>| async move {
+        loop {
+            TimeoutFuture::new(500).await;
+            let expired = resumed.peek().as_ref().is_some_and(|(expires_at, _)| *expires_at <= js_sys::Date::now());
+            if expired {
+                resumed.set(None);
+            }
+        }
+    });
+
+    let Some((_, latest)) = resumed.read().clone() else {
+        return rsx! {};
+    };
+    let gamepads = latest.gilrs_gamepads_after.map_or("the gamepad backend didn't restart".to_string(), |after| format!("{} gamepad(s)", after));
+
+    rsx! {
+        div {
+            class: "toast",
+            onclick: move |_| resumed.set(None),
+            strong { "🌅 Recovered input after sleep" }
+            p {
+                "Reopened {latest.evdev_devices_after} evdev device(s) and {gamepads} in {latest.recovery_ms}ms."
+            }
+            for error in latest.errors.iter() {
+                p { "⚠️ {error}" }
+            }
+        }
+    }
+}
+
+/// Warns as the system battery drains, louder at each threshold. The last one stays up
+/// until dismissed.
+#[component]
+fn SystemBatteryToast() -> Element {
+    // (expiry in ms since the epoch, or none for the last threshold; warning)
+    let mut low = use_signal(|| None::<(Option<f64>, SystemBatteryLow)>);
+
+    use_effect(move || {
+        spawn(async move {
+            let handler = Closure::<dyn FnMut(JsValue)>::new(move |event: JsValue| {
+                if let Ok(latest) = serde_wasm_bindgen::from_value::<SystemBatteryLow>(event) {
+                    let expires_at = (latest.level < latest.levels).then(|| js_sys::Date::now() + TOAST_DURATION_MS);
+                    low.set(Some((expires_at, latest)));
+                }
+            });
+            let _ = listen("system-battery-low", &handler).await;
+            handler.forget();
+        });
+    });
+
+    use_coroutine(move |_: UnboundedReceiver<()>| async move {
+        loop {
+            TimeoutFuture::new(500).await;
+            let expired = low.peek().as_ref().is_some_and(|(expires_at, _)| expires_at.is_some_and(|expires_at| expires_at <= js_sys::Date::now()));
+            if expired {
+                low.set(None);
+            }
+        }
+    });
+
+    let Some((_, latest)) = low.read().clone() else {
+        return rsx! {};
+    };
+    let (class, title) = if latest.level >= latest.levels {
+        ("toast battery-low critical", "🪫 Battery critical")
+    } else if latest.level > 1 {
+        ("toast battery-low urgent", "🪫 Battery very low")
+    } else {
+        ("toast battery-low", "🔋 Battery low")
+    };
+
+    rsx! {
+        div {
+            class: "{class}",
+            onclick: move |_| low.set(None),
+            strong { "{title}: {latest.percent}%" }
+            p { "Plug the Deck in before it shuts down mid-show." }
+        }
+    }
+}
+
+#[component]
+fn SessionSummaryCard() -> Element {
+    let mut summary = use_signal(|| None::<SessionSummary>);
+
+    use_coroutine(move |_: UnboundedReceiver<()>| async move {
+        loop {
+            if let Ok(result) = invoke_without_args("get_current_session_summary").await {
+                if let Ok(latest) = serde_wasm_bindgen::from_value::<SessionSummary>(result) {
+                    summary.set(Some(latest));
+                }
+            }
+            TimeoutFuture::new(2000).await;
+        }
+    });
+
+    let Some(summary) = summary.read().clone() else {
+        return rsx! {};
+    };
+    let warnings: u64 = summary.warnings.iter().map(|warning| warning.count).sum();
+
+    rsx! {
+        div {
+            class: "session-card",
+            h3 { "🎬 This session" }
+            div {
+                class: "session-stats",
+                span { "⏱️ {summary.duration_label()}" }
+                span { "🎮 {summary.controllers_used.len()} controller(s)" }
+                span { "🔘 {summary.button_presses} presses" }
+                span { "💡 {summary.light_actions_sent} light actions ({summary.light_action_failures} failed)" }
+                span { "🔌 {summary.disconnects} disconnects" }
+                span { "🔋 {summary.battery_range()}" }
+                span { "⚠️ {warnings} errors, {summary.poll_panics} panics, {summary.poll_stalls} stalls" }
+            }
+        }
+    }
+}
+
+/// Rumble controls per connected controller; hidden unless one of them supports force feedback.
+#[component]
+fn RumbleTestPanel(controllers: Signal<HashMap<usize, ControllerState>>) -> Element {
+    let mut supported = use_signal(BTreeMap::<usize, bool>::new);
+
+    use_effect(move || {
+        let ids: Vec<usize> = controllers.read().keys().copied().collect();
+        spawn(async move {
+            let mut latest = BTreeMap::new();
+            for controller_id in ids {
+                let args = serde_wasm_bindgen::to_value(&serde_json::json!({ "controllerId": controller_id })).unwrap();
+                let is_supported = match invoke("is_rumble_supported", args).await {
+                    Ok(result) => serde_wasm_bindgen::from_value::<bool>(result).unwrap_or(false),
+                    Err(_) => false,
+                };
+                latest.insert(controller_id, is_supported);
+            }
+            supported.set(latest);
+        });
+    });
+
+    if !supported.read().values().any(|&is_supported| is_supported) {
+        return rsx! {};
+    }
+
+    rsx! {
+        div {
+            class: "rumble-panel",
+            h3 { "📳 Rumble Test" }
+            for (controller_id, is_supported) in supported.read().iter().map(|(&id, &is_supported)| (id, is_supported)) {
+                if is_supported {
+                    RumbleTestRow { key: "{controller_id}", controller_id }
+                } else {
+                    div {
+                        key: "{controller_id}",
+                        class: "rumble-row",
+                        strong { "Controller {controller_id}" }
+                        span { " — Rumble not supported" }
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[component]
+fn LowBatteryPanel() -> Element {
+    let mut config = use_signal(|| None::<LowBatteryConfig>);
+    let mut threshold = use_signal(|| 10u8);
+    let mut action = use_signal(|| "notify".to_string());
+    let mut confirming = use_signal(|| false);
+    let mut reached = use_signal(|| None::<BatteryThresholdReached>);
+    let mut error = use_signal(|| None::<String>);
+
+    use_effect(move || {
+        spawn(async move {
+            if let Ok(result) = invoke_without_args("get_low_battery_config").await {
+                if let Ok(Some(current)) = serde_wasm_bindgen::from_value::<Option<LowBatteryConfig>>(result) {
+                    threshold.set(current.threshold);
+                    action.set(current.action.clone());
+                    config.set(Some(current));
+                }
+            }
+            let handler = Closure::<dyn FnMut(JsValue)>::new(move |event: JsValue| {
+                if let Ok(notice) = serde_wasm_bindgen::from_value::<BatteryThresholdReached>(event) {
+                    reached.set(Some(notice));
+                }
+            });
+            let _ = listen("battery-action-threshold", &handler).await;
+            handler.forget();
+        });
+    });
+
+    let apply = move || {
+        spawn(async move {
+            confirming.set(false);
+            error.set(None);
+            let latest = LowBatteryConfig {
+                threshold: *threshold.read(),
+                action: action.read().clone(),
+            };
+            let args = serde_wasm_bindgen::to_value(&serde_json::json!({
+                "threshold": latest.threshold,
+                "action": latest.action
+            })).unwrap();
+            match invoke("configure_low_battery_action", args).await {
+                Ok(_) => config.set(Some(latest)),
+                Err(e) => error.set(Some(AppError::from_js(&e).user_message())),
+            }
+        });
+    };
+    // Suspending, hibernating or shutting down on its own needs a second, explicit click
+    let save = move |_| {
+        if action.read().as_str() == "notify" {
+            apply();
+        } else {
+            confirming.set(true);
+        }
+    };
+    let clear = move |_| {
+        spawn(async move {
+            confirming.set(false);
+            match invoke_without_args("clear_low_battery_action").await {
+                Ok(_) => config.set(None),
+                Err(e) => error.set(Some(AppError::from_js(&e).user_message())),
+            }
+        });
+    };
+
+    let action_label = |value: &str| {
+        LOW_BATTERY_ACTIONS
+            .iter()
+            .find(|(key, _)| *key == value)
+            .map(|(_, label)| *label)
+            .unwrap_or("Unknown")
+    };
+
+    let summary = match config.read().as_ref() {
+        Some(current) => format!("{} at {}% while on battery.", action_label(&current.action), current.threshold),
+        None => "No low battery action is set.".to_string(),
+    };
+    let confirm_action = action_label(&action.read()).to_lowercase();
+
+    rsx! {
+        div {
+            class: "low-battery-panel",
+            h3 { "🔋 Low Battery Action" }
+            p { "{summary}" }
+            if let Some(notice) = reached.read().as_ref() {
+                p { class: "low-battery-notice", "Battery is at {notice.percent}% (threshold {notice.threshold}%)." }
+            }
+            div {
+                class: "low-battery-controls",
+                input {
+                    r#type: "number",
+                    min: "1",
+                    max: "100",
+                    value: "{threshold}",
+                    oninput: move |event| {
+                        if let Ok(percent) = event.value().parse() {
+                            threshold.set(percent);
+                        }
+                    }
+                }
+                select {
+                    value: "{action}",
+                    onchange: move |event| {
+                        action.set(event.value());
+                        confirming.set(false);
+                    },
+                    for (value, label) in LOW_BATTERY_ACTIONS {
+                        option { value: "{value}", "{label}" }
+                    }
+                }
+                button {
+                    onclick: save,
+                    "Save"
+                }
+                if config.read().is_some() {
+                    button {
+                        onclick: clear,
+                        "Clear"
+                    }
+                }
+            }
+            if *confirming.read() {
+                div {
+                    class: "low-battery-confirm",
+                    p {
+                        "The Deck will {confirm_action} automatically once the battery drops to {threshold}%. Unsaved work in other apps may be lost."
+                    }
+                    button {
+                        onclick: move |_| apply(),
+                        "Yes, enable"
+                    }
+                    button {
+                        onclick: move |_| confirming.set(false),
+                        "Cancel"
+                    }
+                }
+            }
+            if let Some(message) = error.read().as_ref() {
+                p { class: "debug-error", "{message}" }
+            }
+        }
+    }
+}
+
+#[component]
+fn RumbleTestRow(controller_id: usize) -> Element {
+    let mut strong_magnitude = use_signal(|| 0.5f32);
+    let mut weak_magnitude = use_signal(|| 0.5f32);
+    let mut duration_ms = use_signal(|| 500u32);
+    let mut rumble_progress = use_signal(|| 0.0f64);
+    let mut error = use_signal(|| None::<String>);
+
+    let test_rumble = move |_| {
+        let duration = *duration_ms.read();
+        spawn(async move {
+            let args = serde_wasm_bindgen::to_value(&serde_json::json!({
+                "controllerId": controller_id,
+                "strong": *strong_magnitude.read(),
+                "weak": *weak_magnitude.read(),
+                "durationMs": duration,
+            })).unwrap();
+            if let Err(e) = invoke("rumble_controller", args).await {
+                error.set(Some(AppError::from_js(&e).user_message()));
+                return;
+            }
+            error.set(None);
+            let started = js_sys::Date::now();
+            loop {
+                let elapsed = js_sys::Date::now() - started;
+                if elapsed >= duration as f64 {
+                    break;
+                }
+                rumble_progress.set(elapsed / duration as f64);
+                TimeoutFuture::new(50).await;
+            }
+            rumble_progress.set(0.0);
+        });
+    };
+
+    rsx! {
+        div {
+            class: "rumble-row",
+            strong { "Controller {controller_id}" }
+            label {
+                "Strong {strong_magnitude:.2} "
+                input {
+                    r#type: "range",
+                    min: "0",
+                    max: "1",
+                    step: "0.05",
+                    value: "{strong_magnitude}",
+                    oninput: move |event| {
+                        if let Ok(value) = event.value().parse() {
+                            strong_magnitude.set(value);
+                        }
+                    }
+                }
+            }
+            label {
+                "Weak {weak_magnitude:.2} "
+                input {
+                    r#type: "range",
+                    min: "0",
+                    max: "1",
+                    step: "0.05",
+                    value: "{weak_magnitude}",
+                    oninput: move |event| {
+                        if let Ok(value) = event.value().parse() {
+                            weak_magnitude.set(value);
+                        }
+                    }
+                }
+            }
+            label {
+                "Duration (ms) "
+                input {
+                    r#type: "number",
+                    min: "1",
+                    max: "5000",
+                    value: "{duration_ms}",
+                    oninput: move |event| {
+                        if let Ok(value) = event.value().parse() {
+                            duration_ms.set(value);
+                        }
+                    }
+                }
+            }
+            button {
+                disabled: *rumble_progress.read() > 0.0,
+                onclick: test_rumble,
+                "Test Rumble"
+            }
+            progress {
+                max: "1",
+                value: "{rumble_progress}",
+            }
+            if let Some(error) = error.read().as_ref() {
+                p { class: "debug-error", "{error}" }
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TouchZone {
+    pub name: String,
+    pub x: f32,
+    pub y: f32,
+    pub width: f32,
+    pub height: f32,
+}
+
+/// A drag on the touch overlay while editing, in fractions of the overlay.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum ZoneDrag {
+    Create { start: (f32, f32), current: (f32, f32) },
+    Resize { index: usize },
+}
+
+/// Smallest zone a drag creates, as a fraction of the overlay.
+const MIN_ZONE_SIZE: f32 = 0.03;
+
+fn overlay_fraction(rect: &dioxus::html::geometry::PixelsRect, x: f64, y: f64) -> (f32, f32) {
+    let fx = ((x - rect.origin.x) / rect.size.width.max(1.0)).clamp(0.0, 1.0);
+    let fy = ((y - rect.origin.y) / rect.size.height.max(1.0)).clamp(0.0, 1.0);
+    (fx as f32, fy as f32)
+}
+
+fn zone_from_corners(name: String, (x1, y1): (f32, f32), (x2, y2): (f32, f32)) -> TouchZone {
+    TouchZone {
+        name,
+        x: x1.min(x2),
+        y: y1.min(y2),
+        width: (x1 - x2).abs(),
+        height: (y1 - y2).abs(),
+    }
+}
+
+fn zone_style(zone: &TouchZone) -> String {
+    format!(
+        "left: {}%; top: {}%; width: {}%; height: {}%;",
+        zone.x * 100.0,
+        zone.y * 100.0,
+        zone.width * 100.0,
+        zone.height * 100.0
+    )
+}
+
+fn inject_zone(name: String, pressed: bool) {
+    spawn(async move {
+        let args = serde_wasm_bindgen::to_value(&serde_json::json!({
+            "button": name,
+            "pressed": pressed
+        })).unwrap();
+        if let Err(e) = invoke("inject_input_event", args).await {
+            logger::error(&format!("Failed to inject touch zone input: {}", AppError::from_js(&e).message()));
+        }
+    });
+}
+
+/// Tappable zones that press buttons on the touch controller. Each finger holds its own
+/// zone, so two zones can be held at once.
+#[component]
+fn TouchZonePanel() -> Element {
+    let mut zones = use_signal(Vec::<TouchZone>::new);
+    let mut editing = use_signal(|| false);
+    let mut new_name = use_signal(String::new);
+    let mut status = use_signal(|| None::<String>);
+    // Pointer ID to the zone it's holding
+    let mut held = use_signal(HashMap::<i32, String>::new);
+    let mut overlay = use_signal(|| None::<Rc<MountedData>>);
+    let mut overlay_rect = use_signal(|| None::<dioxus::html::geometry::PixelsRect>);
+    let mut drag = use_signal(|| None::<ZoneDrag>);
+
+    use_effect(move || {
+        spawn(async move {
+            if let Ok(result) = invoke_without_args("get_touch_zones").await {
+                if let Ok(saved) = serde_wasm_bindgen::from_value::<Vec<TouchZone>>(result) {
+                    zones.set(saved);
+                }
+            }
+        });
+    });
+
+    let save = move |updated: Vec<TouchZone>| {
+        spawn(async move {
+            let args = serde_wasm_bindgen::to_value(&serde_json::json!({
+                "zones": updated
+            })).unwrap();
+            match invoke("set_touch_zones", args).await {
+                Ok(result) => {
+                    if let Ok(saved) = serde_wasm_bindgen::from_value::<Vec<TouchZone>>(result) {
+                        zones.set(saved);
+                        status.set(None);
+                    }
+                }
+                Err(e) => status.set(Some(AppError::from_js(&e).user_message())),
+            }
+        });
+    };
+
+    // A zone is released once the last finger on it lifts
+    let mut release_pointer = move |pointer_id: i32| {
+        let released = held.write().remove(&pointer_id);
+        if let Some(name) = released {
+            if !held.read().values().any(|holding| *holding == name) {
+                inject_zone(name, false);
+            }
+        }
+    };
+
+    let is_editing = *editing.read();
+    let held_names: HashSet<String> = held.read().values().cloned().collect();
+    let draft = match *drag.read() {
+        Some(ZoneDrag::Create { start, current }) => Some(zone_from_corners(String::new(), start, current)),
+        _ => None,
+    };
+
+    rsx! {
+        div {
+            class: "touch-zones",
+            h2 { "👆 Touch Zones" }
+            div {
+                class: "button-group",
+                button {
+                    onclick: move |_| {
+                        let now_editing = !*editing.read();
+                        editing.set(now_editing);
+                        drag.set(None);
+                    },
+                    if is_editing { "Done" } else { "Edit Zones" }
+                }
+                if is_editing {
+                    input {
+                        value: "{new_name}",
+                        oninput: move |event| new_name.set(event.value()),
+                        placeholder: "Name for the next zone (its button name)"
+                    }
+                }
+            }
+            if is_editing {
+                p { "Drag on the overlay to add a zone; drag a zone's corner to resize it." }
+            }
+            div {
+                class: if is_editing { "touch-overlay editing" } else { "touch-overlay" },
+                onmounted: move |event: MountedEvent| overlay.set(Some(event.data())),
+                onpointerdown: move |event: PointerEvent| {
+                    if !*editing.read() {
+                        return;
+                    }
+                    let (x, y) = (event.client_coordinates().x, event.client_coordinates().y);
+                    let Some(mounted) = overlay.read().clone() else {
+                        return;
+                    };
+                    // The overlay may have moved since it was measured, so measure at each drag
+                    spawn(async move {
+                        if let Ok(rect) = mounted.get_client_rect().await {
+                            let point = overlay_fraction(&rect, x, y);
+                            overlay_rect.set(Some(rect));
+                            drag.set(Some(ZoneDrag::Create { start: point, current: point }));
+                        }
+                    });
+                },
+                onpointermove: move |event: PointerEvent| {
+                    let Some(current_drag) = *drag.read() else {
+                        return;
+                    };
+                    let Some(rect) = *overlay_rect.read() else {
+                        return;
+                    };
+                    let point = overlay_fraction(&rect, event.client_coordinates().x, event.client_coordinates().y);
+                    match current_drag {
+                        ZoneDrag::Create { start, .. } => drag.set(Some(ZoneDrag::Create { start, current: point })),
+                        ZoneDrag::Resize { index } => {
+                            if let Some(zone) = zones.write().get_mut(index) {
+                                zone.width = (point.0 - zone.x).max(MIN_ZONE_SIZE).min(1.0 - zone.x);
+                                zone.height = (point.1 - zone.y).max(MIN_ZONE_SIZE).min(1.0 - zone.y);
+                            }
+                        }
+                    }
+                },
+                onpointerup: move |_| {
+                    let Some(finished) = drag.write().take() else {
+                        return;
+                    };
+                    let mut updated = zones.read().clone();
+                    if let ZoneDrag::Create { start, current } = finished {
+                        let typed = new_name.read().trim().to_string();
+                        let name = if typed.is_empty() { format!("Zone {}", updated.len() + 1) } else { typed };
+                        let zone = zone_from_corners(name, start, current);
+                        if zone.width < MIN_ZONE_SIZE || zone.height < MIN_ZONE_SIZE {
+                            return;
+                        }
+                        updated.push(zone);
+                        new_name.set(String::new());
+                    }
+                    save(updated);
+                },
+                for (index, zone) in zones.read().iter().enumerate() {
+                    div {
+                        key: "{zone.name}",
+                        class: if held_names.contains(&zone.name) { "touch-zone pressed" } else { "touch-zone" },
+                        style: "{zone_style(zone)}",
+                        onpointerdown: {
+                            let name = zone.name.clone();
+                            move |event: PointerEvent| {
+                                if *editing.read() {
+                                    return;
+                                }
+                                event.prevent_default();
+                                let already_held = held.read().values().any(|holding| *holding == name);
+                                held.write().insert(event.pointer_id(), name.clone());
+                                if !already_held {
+                                    inject_zone(name.clone(), true);
+                                }
+                            }
+                        },
+                        onpointerup: move |event: PointerEvent| release_pointer(event.pointer_id()),
+                        onpointercancel: move |event: PointerEvent| release_pointer(event.pointer_id()),
+                        span { "{zone.name}" }
+                        if is_editing {
+                            div {
+                                class: "touch-zone-handle",
+                                onpointerdown: move |event: PointerEvent| {
+                                    event.stop_propagation();
+                                    let Some(mounted) = overlay.read().clone() else {
+                                        return;
+                                    };
+                                    spawn(async move {
+                                        if let Ok(rect) = mounted.get_client_rect().await {
+                                            overlay_rect.set(Some(rect));
+                                            drag.set(Some(ZoneDrag::Resize { index }));
+                                        }
+                                    });
+                                },
+                            }
+                            button {
+                                class: "touch-zone-delete",
+                                onpointerdown: move |event: PointerEvent| event.stop_propagation(),
+                                onclick: move |_| {
+                                    let mut updated = zones.read().clone();
+                                    if index < updated.len() {
+                                        updated.remove(index);
+                                    }
+                                    save(updated);
+                                },
+                                "✕"
+                            }
+                        }
+                    }
+                }
+                if let Some(draft) = draft {
+                    div {
+                        class: "touch-zone draft",
+                        style: "{zone_style(&draft)}",
+                    }
+                }
+            }
+            if zones.read().is_empty() && !is_editing {
+                p { "No touch zones yet. Press Edit Zones to draw some." }
+            }
+            if let Some(message) = status.read().as_ref() {
+                p { class: "error-message", "{message}" }
+            }
+        }
+    }
+}
+
+#[component]
+fn ControllerSlotAssignment(controllers: Signal<HashMap<usize, ControllerState>>) -> Element {
+    let mut slots = use_signal(Vec::<SlotAssignment>::new);
+    let mut dragging = use_signal(|| None::<usize>);
+    let mut drop_target = use_signal(|| None::<String>);
+    let mut status = use_signal(|| None::<String>);
+
+    use_effect(move || {
+        spawn(async move {
+            if let Ok(result) = invoke_without_args("get_slot_assignments").await {
+                if let Ok(latest) = serde_wasm_bindgen::from_value::<Vec<SlotAssignment>>(result) {
+                    slots.set(latest);
+                }
+            }
+            // Docking or undocking may move the preferred controller into P1
+            let handler = Closure::<dyn FnMut(JsValue)>::new(move |event: JsValue| {
+                if let Ok(change) = serde_wasm_bindgen::from_value::<DockChange>(event) {
+                    slots.set(change.slots);
+                }
+            });
+            let _ = listen("dock-state-changed", &handler).await;
+            handler.forget();
+        });
+    });
+
+    // Both commands return the full assignment list
+    let update_slots = move |command: &'static str, args: serde_json::Value| {
+        spawn(async move {
+            let args = serde_wasm_bindgen::to_value(&args).unwrap();
+            match invoke(command, args).await {
+                Ok(result) => {
+                    if let Ok(latest) = serde_wasm_bindgen::from_value::<Vec<SlotAssignment>>(result) {
+                        slots.set(latest);
+                        status.set(None);
+                    }
+                }
+                Err(e) => status.set(Some(AppError::from_js(&e).user_message())),
+            }
+        });
+    };
+
+    let mut controller_ids: Vec<usize> = controllers.read().keys().copied().collect();
+    controller_ids.sort_unstable();
+    let slot_of = |controller_id: usize| {
+        slots.read().iter()
+            .find(|slot| slot.controller_id == Some(controller_id))
+            .map(|slot| slot.slot_name.clone())
+    };
+    let unassigned: Vec<usize> = controller_ids.iter().copied().filter(|&id| slot_of(id).is_none()).collect();
+    let target_class = |name: &str| {
+        if drop_target.read().as_deref() == Some(name) { "slot-target drag-over" } else { "slot-target" }
+    };
+    let controller_card = move |controller_id: usize, badge: Option<String>| {
+        let connected = controllers.read().contains_key(&controller_id);
+        rsx! {
+            div {
+                class: "slot-card",
+                draggable: "true",
+                ondragstart: move |_| dragging.set(Some(controller_id)),
+                ondragend: move |_| {
+                    dragging.set(None);
+                    drop_target.set(None);
+                },
+                if let Some(badge) = badge {
+                    span { class: "slot-badge", "{badge}" }
+                }
+                "Controller {controller_id}"
+                if !connected {
+                    span { class: "slot-empty", " (disconnected)" }
+                }
+            }
+        }
+    };
+
+    rsx! {
+        div {
+            class: "controllers-section",
+            h2 { "🕹️ Player Slots" }
+            p { "Drag a controller onto a slot to assign it." }
+            div {
+                class: "slot-row",
+                for slot in slots.read().iter().cloned() {
+                    div {
+                        key: "{slot.slot_name}",
+                        class: target_class(&slot.slot_name),
+                        ondragover: {
+                            let slot_name = slot.slot_name.clone();
+                            move |event: DragEvent| {
+                                event.prevent_default();
+                                drop_target.set(Some(slot_name.clone()));
+                            }
+                        },
+                        ondragleave: move |_| drop_target.set(None),
+                        ondrop: {
+                            let slot_name = slot.slot_name.clone();
+                            move |event: DragEvent| {
+                                event.prevent_default();
+                                drop_target.set(None);
+                                if let Some(controller_id) = dragging.take() {
+                                    update_slots("assign_controller_to_slot", serde_json::json!({
+                                        "slotName": slot_name,
+                                        "controllerId": controller_id
+                                    }));
+                                }
+                            }
+                        },
+                        match slot.controller_id {
+                            Some(controller_id) => controller_card(controller_id, Some(slot.slot_name.clone())),
+                            None => rsx! {
+                                span { class: "slot-badge", "{slot.slot_name}" }
+                                span { class: "slot-empty", "Empty" }
+                            },
+                        }
+                    }
+                }
+            }
+            h3 { "Unassigned" }
+            div {
+                class: target_class(""),
+                ondragover: move |event: DragEvent| {
+                    event.prevent_default();
+                    drop_target.set(Some(String::new()));
+                },
+                ondragleave: move |_| drop_target.set(None),
+                ondrop: move |event: DragEvent| {
+                    event.prevent_default();
+                    drop_target.set(None);
+                    if let Some(controller_id) = dragging.take() {
+                        update_slots("unassign_controller_slot", serde_json::json!({
+                            "controllerId": controller_id
+                        }));
+                    }
+                },
+                if unassigned.is_empty() {
+                    p { class: "slot-empty", "No unassigned controllers" }
+                }
+                for controller_id in unassigned {
+                    div {
+                        key: "{controller_id}",
+                        {controller_card(controller_id, None)}
+                    }
+                }
+            }
+            if let Some(message) = status.read().as_ref() {
+                p { "{message}" }
+            }
+        }
+    }
+}
+
+/// Toasts each device that failed to open for lack of permission, once per device
+/// for the session however often it's rescanned.
+#[component]
+fn DeviceOpenFailureToasts(mut show_debug: Signal<bool>) -> Element {
+    let mut failures = use_signal(Vec::<DeviceOpenFailure>::new);
+    let mut toasted = use_signal(HashSet::<String>::new);
+
+    let mut add = move |failure: DeviceOpenFailure| {
+        if failure.cause == "permission" && toasted.write().insert(failure.device_path.clone()) {
+            failures.write().push(failure);
+        }
+    };
+
+    use_effect(move || {
+        spawn(async move {
+            // The startup scan runs before this listener exists
+            if let Ok(result) = invoke_without_args("get_device_open_failures").await {
+                if let Ok(recent) = serde_wasm_bindgen::from_value::<Vec<DeviceOpenFailure>>(result) {
+                    recent.into_iter().for_each(&mut add);
+                }
+            }
+
+            let failure_handler = Closure::<dyn FnMut(JsValue)>::new(move |event: JsValue| {
+                if let Ok(failure) = serde_wasm_bindgen::from_value::<DeviceOpenFailure>(event) {
+                    add(failure);
+                }
+            });
+            let _ = listen("device-open-failed", &failure_handler).await;
+            failure_handler.forget();
+        });
+    });
+
+    let open_permissions = move |_| {
+        failures.write().clear();
+        show_debug.set(true);
+        spawn(async move {
+            // Give the debug panel a frame to render before scrolling to it
+            TimeoutFuture::new(50).await;
+            if let Some(section) = web_sys::window()
+                .and_then(|window| window.document())
+                .and_then(|document| document.get_element_by_id("permissions-checklist"))
+            {
+                section.scroll_into_view();
+            }
+        });
+    };
+
+    rsx! {
+        for failure in failures.read().iter().cloned() {
+            div {
+                key: "{failure.device_path}",
+                class: "toast",
+                strong { "🔒 Can't open {failure.name}" }
+                p { "{failure.device_path}: {failure.error}" }
+                p { "{failure.remediation}" }
+                div {
+                    class: "button-group",
+                    button {
+                        onclick: open_permissions,
+                        "Fix permissions…"
+                    }
+                    button {
+                        onclick: move |_| failures.write().retain(|shown| shown.device_path != failure.device_path),
+                        "Dismiss"
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[component]
+fn InputBackendsPanel() -> Element {
+    let mut backends = use_signal(Vec::<BackendDebugInfo>::new);
+    let mut error = use_signal(|| None::<String>);
+    let mut restart_status = use_signal(|| None::<String>);
+
+    let refresh = move || {
+        spawn(async move {
+            if let Ok(result) = invoke_without_args("get_input_backends").await {
+                if let Ok(latest) = serde_wasm_bindgen::from_value::<Vec<BackendDebugInfo>>(result) {
+                    backends.set(latest);
+                }
+            }
+        });
+    };
+
+    use_effect(refresh);
+
+    let rescan = move |_| {
+        spawn(async move {
+            error.set(None);
+            match invoke_without_args("rescan_input_devices").await {
+                Ok(result) => {
+                    if let Ok(latest) = serde_wasm_bindgen::from_value::<Vec<BackendDebugInfo>>(result) {
+                        backends.set(latest);
+                    }
+                }
+                Err(e) => error.set(Some(AppError::from_js(&e).user_message())),
+            }
+        });
+    };
+
+    let restart_gilrs = move |_| {
+        spawn(async move {
+            error.set(None);
+            restart_status.set(Some("Restarting gamepad backend...".to_string()));
+            match invoke_without_args("reinit_gamepad_backend").await {
+                Ok(result) => {
+                    let found = serde_wasm_bindgen::from_value::<Vec<GamepadInfo>>(result)
+                        .map(|gamepads| gamepads.len())
+                        .unwrap_or_default();
+                    restart_status.set(Some(format!("Gamepad backend restarted; {} gamepad(s) found", found)));
+                }
+                Err(e) => {
+                    restart_status.set(None);
+                    error.set(Some(AppError::from_js(&e).user_message()));
+                }
+            }
+            refresh();
+        });
+    };
+
+    let set_enabled = move |source: String, enabled: bool| {
+        spawn(async move {
+            error.set(None);
+            let args = serde_wasm_bindgen::to_value(&serde_json::json!({
+                "source": source,
+                "enabled": enabled
+            })).unwrap();
+            if let Err(e) = invoke("set_input_backend_enabled", args).await {
+                error.set(Some(AppError::from_js(&e).user_message()));
+            }
+            refresh();
+        });
+    };
+
+    let now = js_sys::Date::now() as u64;
+    let scan_age = |scanned_at: Option<u64>| match scanned_at {
+        Some(scanned_at) => format!("{}s ago", now.saturating_sub(scanned_at) / 1000),
+        None => "—".to_string(),
+    };
+
+    rsx! {
+        div {
+            class: "debug-section",
+            h3 { "🔌 Input Backends" }
+            button {
+                onclick: rescan,
+                "Rescan all"
+            }
+            button {
+                onclick: restart_gilrs,
+                "Restart gamepad backend"
+            }
+            if let Some(status) = restart_status.read().as_ref() {
+                p { "{status}" }
+            }
+            if let Some(message) = error.read().as_ref() {
+                p { class: "debug-error", "{message}" }
+            }
+            table {
+                class: "metrics-table",
+                thead {
+                    tr {
+                        th { "Backend" }
+                        th { "Enabled" }
+                        th { "Available" }
+                        th { "Devices" }
+                        th { "Last scan" }
+                    }
+                }
+                tbody {
+                    for backend in backends.read().iter().cloned() {
+                        tr {
+                            key: "{backend.source}",
+                            td { "{backend.source}" }
+                            td {
+                                input {
+                                    r#type: "checkbox",
+                                    checked: backend.enabled,
+                                    onchange: {
+                                        let source = backend.source.clone();
+                                        move |event: Event<FormData>| set_enabled(source.clone(), event.checked())
+                                    }
+                                }
+                            }
+                            td { if backend.available { "Yes" } else { "No" } }
+                            td { "{backend.device_count}" }
+                            td { "{scan_age(backend.last_scan_time)}" }
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[component]
+fn ErrorCountersPanel() -> Element {
+    let mut snapshot = use_signal(|| None::<ErrorsSnapshot>);
+
+    use_coroutine(move |_: UnboundedReceiver<()>| async move {
+        loop {
+            if let Ok(result) = invoke_without_args("get_error_counters").await {
+                if let Ok(errors) = serde_wasm_bindgen::from_value::<ErrorsSnapshot>(result) {
+                    snapshot.set(Some(errors));
+                }
+            }
+            TimeoutFuture::new(2000).await;
+        }
+    });
+
+    let reset = move |_| {
+        spawn(async move {
+            let _ = invoke_without_args("reset_error_counters").await;
+            if let Ok(result) = invoke_without_args("get_error_counters").await {
+                if let Ok(errors) = serde_wasm_bindgen::from_value::<ErrorsSnapshot>(result) {
+                    snapshot.set(Some(errors));
+                }
+            }
+        });
+    };
+
+    let now = js_sys::Date::now() as u64;
+
+    rsx! {
+        div {
+            class: "debug-section",
+            h3 { "🚨 Errors" }
+            button {
+                onclick: reset,
+                "Reset"
+            }
+            if let Some(errors) = snapshot.read().as_ref() {
+                p { "Since {now.saturating_sub(errors.since) / 1000}s ago" }
+                table {
+                    class: "metrics-table",
+                    thead {
+                        tr {
+                            th { "Category" }
+                            th { "Total" }
+                            th { "Last min (limit)" }
+                            th { "Last error" }
+                        }
+                    }
+                    tbody {
+                        for category in errors.categories.iter() {
+                            tr {
+                                key: "{category.category}",
+                                td { "{category.category}" }
+                                td { "{category.count}" }
+                                td { "{category.last_minute} ({category.threshold_label()})" }
+                                td { "{category.last_error_label(now)}" }
+                            }
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Per-controller and overall counts of what the gilrs pipeline did with its input.
+#[component]
+fn PipelineMetricsPanel(debug_info: Signal<Option<DebugInfo>>) -> Element {
+    let mut global = use_signal(PipelineMetrics::default);
+    let mut events_per_sec = use_signal(|| 0u64);
+    let mut per_controller = use_signal(Vec::<(usize, PipelineMetrics)>::new);
+
+    use_coroutine(move |_: UnboundedReceiver<()>| async move {
+        loop {
+            if page_hidden() {
+                TimeoutFuture::new(1000).await;
+                continue;
+            }
+            if let Ok(result) = invoke_without_args("get_global_pipeline_metrics").await {
+                if let Ok(metrics) = serde_wasm_bindgen::from_value::<PipelineMetrics>(result) {
+                    let previous = *global.peek();
+                    events_per_sec.set(metrics.received.saturating_sub(previous.received));
+                    global.set(metrics);
+                }
+            }
+            let mut controllers = Vec::new();
+            let ids: Vec<usize> = debug_info.peek().iter()
+                .flat_map(|debug| debug.connected_gamepads.iter().map(|gamepad| gamepad.id))
+                .collect();
+            for id in ids {
+                let args = serde_wasm_bindgen::to_value(&serde_json::json!({ "controllerId": id })).unwrap();
+                if let Ok(result) = invoke("get_pipeline_metrics", args).await {
+                    if let Ok(metrics) = serde_wasm_bindgen::from_value::<PipelineMetrics>(result) {
+                        controllers.push((id, metrics));
+                    }
+                }
+            }
+            per_controller.set(controllers);
+            TimeoutFuture::new(1000).await;
+        }
+    });
+
+    let reset = move |id: usize| {
+        spawn(async move {
+            let args = serde_wasm_bindgen::to_value(&serde_json::json!({ "controllerId": id })).unwrap();
+            if let Err(e) = invoke("reset_pipeline_metrics", args).await {
+                logger::error(&format!("Failed to reset pipeline metrics: {}", AppError::from_js(&e).message()));
+            }
+        });
+    };
+
+    let totals = *global.read();
+    rsx! {
+        div {
+            class: "debug-section",
+            h3 { "🚰 Pipeline" }
+            div {
+                class: "pipeline-dashboard",
+                div { class: "pipeline-stat", strong { "{events_per_sec}" } span { "events/s in" } }
+                div { class: "pipeline-stat", strong { "{totals.emitted}" } span { "emitted" } }
+                div { class: "pipeline-stat", strong { "{totals.drop_percentage():.1}%" } span { "dropped" } }
+                div { class: "pipeline-stat", strong { "{totals.transformed}" } span { "transformed" } }
+                div { class: "pipeline-stat", strong { "{totals.macro_triggered}" } span { "macros" } }
+            }
+            if !per_controller.read().is_empty() {
+                table {
+                    class: "metrics-table",
+                    thead {
+                        tr {
+                            th { "Controller" }
+                            th { "Received" }
+                            th { "Emitted" }
+                            th { "Rate limited" }
+                            th { "Deduped" }
+                            th { "Filtered" }
+                            th { "Drop %" }
+                            th { "" }
+                        }
+                    }
+                    tbody {
+                        for (id, metrics) in per_controller.read().iter().copied() {
+                            tr {
+                                key: "{id}",
+                                td { "{id}" }
+                                td { "{metrics.received}" }
+                                td { "{metrics.emitted}" }
+                                td { "{metrics.dropped_rate_limit}" }
+                                td { "{metrics.dropped_dedup}" }
+                                td { "{metrics.dropped_filter}" }
+                                td { "{metrics.drop_percentage():.1}" }
+                                td {
+                                    button {
+                                        onclick: move |_| reset(id),
+                                        "Reset"
+                                    }
+                                }
+                            }
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[component]
+fn RuntimeMetricsPanel() -> Element {
+    let mut snapshot = use_signal(|| None::<RuntimeMetricsSnapshot>);
+    let mut history = use_signal(Vec::<WindowSnapshot>::new);
+    let mut send_stats = use_signal(|| None::<SendStatisticsSnapshot>);
+
+    use_coroutine(move |_: UnboundedReceiver<()>| async move {
+        loop {
+            if page_hidden() {
+                TimeoutFuture::new(1000).await;
+                continue;
+            }
+            if let Ok(result) = invoke_without_args("get_send_statistics").await {
+                if let Ok(stats) = serde_wasm_bindgen::from_value::<SendStatisticsSnapshot>(result) {
+                    send_stats.set(Some(stats));
+                }
+            }
+            if let Ok(result) = invoke_without_args("get_runtime_metrics").await {
+                if let Ok(metrics) = serde_wasm_bindgen::from_value::<RuntimeMetricsSnapshot>(result) {
+                    if let Some(latest) = metrics.windows.first() {
+                        let mut samples = history.write();
+                        if samples.len() == RUNTIME_HISTORY_LEN {
+                            samples.remove(0);
+                        }
+                        samples.push(latest.clone());
+                    }
+                    snapshot.set(Some(metrics));
+                }
+            }
+            TimeoutFuture::new(1000).await;
+        }
+    });
+
+    let reset = move |_| {
+        spawn(async move {
+            let _ = invoke_without_args("reset_metrics").await;
+            history.set(Vec::new());
+            send_stats.set(None);
+        });
+    };
+
+    let poll_points = sparkline_points(
+        &history.read().iter().map(|w| w.gilrs_poll.avg_ns as f64).collect::<Vec<_>>(),
+        240.0,
+        40.0,
+    );
+    let event_points = sparkline_points(
+        &history.read().iter().map(|w| w.gilrs_events_per_sec + w.evdev_events_per_sec).collect::<Vec<_>>(),
+        240.0,
+        40.0,
+    );
+
+    rsx! {
+        div {
+            class: "debug-section",
+            h3 { "📈 Runtime" }
+            button {
+                onclick: reset,
+                "Reset"
+            }
+            div {
+                class: "sparklines",
+                div {
+                    p { "Gilrs poll avg" }
+                    svg {
+                        class: "sparkline",
+                        view_box: "0 0 240 40",
+                        polyline { points: "{poll_points}" }
+                    }
+                }
+                div {
+                    p { "Events/sec" }
+                    svg {
+                        class: "sparkline",
+                        view_box: "0 0 240 40",
+                        polyline { points: "{event_points}" }
+                    }
+                }
+            }
+            if let Some(metrics) = snapshot.read().as_ref() {
+                p { "Light server sends in flight: {metrics.light_sends_in_flight}" }
+                p {
+                    "Poll interval: gilrs {metrics.gilrs_poll_interval_ms}ms / evdev {metrics.evdev_poll_interval_ms}ms"
+                    if metrics.poll_idle { " (idle)" }
+                }
+                table {
+                    class: "metrics-table",
+                    thead {
+                        tr {
+                            th { "Window" }
+                            th { "Gilrs ev/s" }
+                            th { "Evdev ev/s" }
+                            th { "Emits (failed / suppressed)" }
+                            th { "Coalesced / dropped" }
+                            th { "Gilrs poll avg/max" }
+                            th { "Evdev poll avg/max" }
+                            th { "Lock wait max" }
+                            th { "Dispatch wait avg/max" }
+                            th { "State publish avg/max" }
+                            th { "Max sends" }
+                        }
+                    }
+                    tbody {
+                        for window in metrics.windows.iter() {
+                            tr {
+                                key: "{window.window_secs}",
+                                td { "{window.window_secs}s" }
+                                td { "{window.gilrs_events_per_sec:.1}" }
+                                td { "{window.evdev_events_per_sec:.1}" }
+                                td { "{window.emits} ({window.emit_failures} / {window.emits_suppressed})" }
+                                td { "{window.events_coalesced} / {window.events_dropped}" }
+                                td { "{format_duration_ns(window.gilrs_poll.avg_ns)} / {format_duration_ns(window.gilrs_poll.max_ns)}" }
+                                td { "{format_duration_ns(window.evdev_poll.avg_ns)} / {format_duration_ns(window.evdev_poll.max_ns)}" }
+                                td {
+                                    title: "gilrs / states / combos / time series",
+                                    "{format_duration_ns(window.gilrs_lock_wait.max_ns)} / {format_duration_ns(window.states_lock_wait.max_ns)} / {format_duration_ns(window.combo_tracker_lock_wait.max_ns)} / {format_duration_ns(window.time_series_lock_wait.max_ns)}"
+                                }
+                                td { "{format_duration_ns(window.dispatch_queue_wait.avg_ns)} / {format_duration_ns(window.dispatch_queue_wait.max_ns)}" }
+                                td { "{format_duration_ns(window.state_publish.avg_ns)} / {format_duration_ns(window.state_publish.max_ns)}" }
+                                td { "{window.max_light_sends_in_flight}" }
+                            }
+                        }
+                    }
+                }
+            }
+            if let Some(stats) = send_stats.read().as_ref() {
+                h4 { "Light server round trips" }
+                if stats.endpoints.is_empty() {
+                    p { "No sends yet" }
+                }
+                div {
+                    class: "sparklines",
+                    for endpoint in stats.endpoints.iter() {
+                        div {
+                            key: "{endpoint.endpoint}",
+                            class: if stats.is_slow(endpoint) { "health-warn" } else { "" },
+                            p { "{endpoint.endpoint}" }
+                            svg {
+                                class: "sparkline histogram",
+                                view_box: "0 0 240 40",
+                                for (x, height, label) in histogram_bars(&endpoint.buckets, &stats.bucket_bounds_ms, 240.0, 40.0) {
+                                    rect {
+                                        key: "{label}",
+                                        x: "{x}",
+                                        y: "{40.0 - height}",
+                                        width: "{240.0 / endpoint.buckets.len().max(1) as f64 - 1.0}",
+                                        height: "{height}",
+                                        title { "{label}" }
+                                    }
+                                }
+                            }
+                            strong { "p95 (last minute): {endpoint.p95_label()}" }
+                            p { "{endpoint.succeeded} ok, {endpoint.failed} failed" }
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// (x, height, tooltip) per histogram bucket, scaled so the fullest bucket fills `height`.
+fn histogram_bars(buckets: &[u64], bounds_ms: &[u64], width: f64, height: f64) -> Vec<(f64, f64, String)> {
+    let max = buckets.iter().copied().max().unwrap_or(0).max(1) as f64;
+    let bar_width = width / buckets.len().max(1) as f64;
+    buckets.iter()
+        .enumerate()
+        .map(|(index, &count)| {
+            let label = match bounds_ms.get(index) {
+                Some(bound) => format!("≤{}ms: {}", bound, count),
+                None => format!(">{}ms: {}", bounds_ms.last().copied().unwrap_or(0), count),
+            };
+            (index as f64 * bar_width, count as f64 / max * height, label)
+        })
+        .collect()
+}
+
+const VALVE_VENDOR_ID: u16 = 0x28de;
+const STEAM_VIRTUAL_GAMEPAD_PRODUCT_ID: u16 = 0x11ff;
+
+/// Virtual pads Steam Input creates for games; matches the backend's check.
+fn is_steam_virtual(device: &EvdevGamepadInfo) -> bool {
+    (device.vendor_id == Some(VALVE_VENDOR_ID) && device.product_id == Some(STEAM_VIRTUAL_GAMEPAD_PRODUCT_ID))
+        || device.name.contains("Steam Virtual")
+}
+
+#[component]
+fn EvdevDeviceSearch(evdev_devices: Signal<Vec<EvdevGamepadInfo>>) -> Element {
+    let mut search_query = use_signal(String::new);
+    let mut show_inaccessible = use_signal(|| true);
+    let mut show_non_gamepad = use_signal(|| true);
+    let mut show_steam_virtual = use_signal(|| true);
+    let mut sort_by = use_signal(|| "name".to_string());
+
+    let devices = evdev_devices.read();
+    let query = search_query.read().to_lowercase();
+    let mut filtered: Vec<EvdevGamepadInfo> = devices.iter()
+        .filter(|device| *show_inaccessible.read() || device.accessible)
+        .filter(|device| *show_non_gamepad.read() || device.is_gamepad)
+        .filter(|device| *show_steam_virtual.read() || !is_steam_virtual(device))
+        .filter(|device| {
+            query.is_empty()
+                || device.device_path.to_lowercase().contains(&query)
+                || device.name.to_lowercase().contains(&query)
+                || device.capabilities.iter().any(|capability| capability.to_lowercase().contains(&query))
+        })
+        .cloned()
+        .collect();
+    match sort_by.read().as_str() {
+        "path" => filtered.sort_by(|a, b| a.device_path.cmp(&b.device_path)),
+        "capabilities" => filtered.sort_by_key(|device| std::cmp::Reverse(device.capabilities.len())),
+        // Devices without IDs go last
+        "vid_pid" => filtered.sort_by_key(|device| (device.vendor_id.is_none(), device.vendor_id, device.product_id)),
+        _ => filtered.sort_by_key(|device| device.name.to_lowercase()),
+    }
+    let total = devices.len();
+
+    rsx! {
+        div {
+            class: "device-search",
+            input {
+                r#type: "search",
+                placeholder: "Search path, name or capability",
+                value: "{search_query}",
+                oninput: move |event| search_query.set(event.value())
+            }
+            label {
+                input {
+                    r#type: "checkbox",
+                    checked: *show_inaccessible.read(),
+                    onchange: move |event| show_inaccessible.set(event.checked())
+                }
+                "Inaccessible"
+            }
+            label {
+                input {
+                    r#type: "checkbox",
+                    checked: *show_non_gamepad.read(),
+                    onchange: move |event| show_non_gamepad.set(event.checked())
+                }
+                "Non-gamepad"
+            }
+            label {
+                input {
+                    r#type: "checkbox",
+                    checked: *show_steam_virtual.read(),
+                    onchange: move |event| show_steam_virtual.set(event.checked())
+                }
+                "Steam virtual"
+            }
+            label {
+                "Sort by "
+                select {
+                    value: "{sort_by}",
+                    onchange: move |event| sort_by.set(event.value()),
+                    option { value: "name", "Name" }
+                    option { value: "path", "Path" }
+                    option { value: "capabilities", "Capabilities count" }
+                    option { value: "vid_pid", "VID/PID" }
+                }
+            }
+            p { "Showing {filtered.len()} of {total} devices" }
+        }
+        for device in filtered.iter() {
+            div {
+                key: "{device.device_path}",
+                class: "debug-gamepad",
+                p { "Path: {device.device_path}" }
+                p { "Name: {device.name}" }
+                if let (Some(vid), Some(pid)) = (device.vendor_id, device.product_id) {
+                    p { "VID/PID: {vid:04x}:{pid:04x}" }
+                }
+                if let Some(version) = device.version {
+                    p { "Firmware version: {version:#06x}" }
+                }
+                p { "Capabilities: {device.capabilities.join(\", \")}" }
+                if device.is_virtual {
+                    p { class: "virtual-pad-link", "🔗 Virtual (uinput), no physical parent" }
+                }
+                if device.fd_errors > 0 {
+                    p { "FD errors: {device.fd_errors}" }
+                }
+                if !device.capability_hash.is_empty() {
+                    p {
+                        title: "{device.capability_hash}",
+                        "Fingerprint: {&device.capability_hash[..device.capability_hash.len().min(16)]}"
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[component]
+fn ConnectionStatsPanel() -> Element {
+    let mut stats = use_signal(|| None::<ConnectionStats>);
+
+    use_coroutine(move |_: UnboundedReceiver<()>| async move {
+        loop {
+            if let Ok(result) = invoke_without_args("get_connection_stats").await {
+                if let Ok(latest) = serde_wasm_bindgen::from_value::<ConnectionStats>(result) {
+                    stats.set(Some(latest));
+                }
+            }
+            TimeoutFuture::new(5000).await;
+        }
+    });
+
+    let reset = move |_| {
+        spawn(async move {
+            if invoke_without_args("reset_connection_stats").await.is_ok() {
+                if let Ok(result) = invoke_without_args("get_connection_stats").await {
+                    if let Ok(latest) = serde_wasm_bindgen::from_value::<ConnectionStats>(result) {
+                        stats.set(Some(latest));
+                    }
+                }
+            }
+        });
+    };
+
+    rsx! {
+        div {
+            class: "debug-section",
+            h3 { "📶 Light Server Reliability" }
+            button {
+                onclick: reset,
+                "Reset"
+            }
+            if let Some(current) = stats.read().as_ref() {
+                p { "Reliability: {current.reliability_percentage():.1}% ({current.requests_succeeded}/{current.requests_sent} succeeded, {current.requests_failed} failed)" }
+                p { "Latency: avg {current.avg_latency_ms()}ms, min {current.min_latency_ms}ms, max {current.max_latency_ms}ms" }
+                p { "Circuit opened: {current.circuit_open_count} time(s)" }
+                if let Some(reason) = &current.last_failure_reason {
+                    p { class: "debug-error", "Last failure: {reason}" }
+                }
+            }
+        }
+    }
+}
+
+#[component]
+fn DetectionDiagnosisPanel() -> Element {
+    let mut diagnosis = use_signal(|| None::<DetectionDiagnosis>);
+    let mut checking = use_signal(|| false);
+
+    let diagnose = move || {
+        spawn(async move {
+            checking.set(true);
+            if let Ok(result) = invoke_without_args("diagnose_gamepad_detection").await {
+                if let Ok(latest) = serde_wasm_bindgen::from_value::<DetectionDiagnosis>(result) {
+                    diagnosis.set(Some(latest));
+                }
+            }
+            checking.set(false);
+        });
+    };
+
+    use_effect(diagnose);
+
+    rsx! {
+        div {
+            class: "debug-section",
+            h3 { "🔍 Why isn't my controller detected?" }
+            button {
+                disabled: *checking.read(),
+                onclick: move |_| diagnose(),
+                if *checking.read() { "Checking..." } else { "Re-check" }
+            }
+            if let Some(result) = diagnosis.read().as_ref() {
+                if !result.gilrs_available {
+                    p { "❌ The gamepad backend isn't available, so no evdev device can show up in it" }
+                }
+                p { "{result.detected.len()} evdev gamepad(s) matched to {result.gilrs_gamepads} gilrs gamepad(s)" }
+                if result.undetected.is_empty() {
+                    p { "✅ Every evdev gamepad is visible to gilrs" }
+                }
+                for device in result.undetected.iter() {
+                    div {
+                        key: "{device.device_path}",
+                        class: "debug-gamepad",
+                        p {
+                            strong { "{device.name}" }
+                            " ({device.device_path}, GUID {device.sdl_guid.as_deref().unwrap_or(\"unknown\")})"
+                        }
+                        if device.causes.is_empty() {
+                            p { "No probable cause found" }
+                        }
+                        ul {
+                            class: "health-list",
+                            for cause in device.causes.iter() {
+                                li {
+                                    key: "{cause.kind}",
+                                    "❌ {cause.detail}"
+                                    br {}
+                                    "👉 {cause.suggested_fix}"
+                                }
+                            }
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[component]
+fn PermissionsChecklist() -> Element {
+    let mut analysis = use_signal(|| None::<PermissionsAnalysis>);
+    let mut checking = use_signal(|| false);
+
+    let analyze = move || {
+        spawn(async move {
+            checking.set(true);
+            if let Ok(result) = invoke_without_args("analyze_permissions").await {
+                if let Ok(latest) = serde_wasm_bindgen::from_value::<PermissionsAnalysis>(result) {
+                    analysis.set(Some(latest));
+                }
+            }
+            checking.set(false);
+        });
+    };
+
+    use_effect(analyze);
+
+    let check_mark = |ok: bool| if ok { "✅" } else { "❌" };
+
+    rsx! {
+        div {
+            class: "debug-section",
+            id: "permissions-checklist",
+            h3 { "Permissions Check" }
+            button {
+                disabled: *checking.read(),
+                onclick: move |_| analyze(),
+                if *checking.read() { "Checking..." } else { "Re-check" }
+            }
+            if let Some(result) = analysis.read().as_ref() {
+                ul {
+                    class: "health-list",
+                    li {
+                        "{result.identity()}"
+                    }
+                    li {
+                        "{check_mark(result.in_input_group)} Member of the input group"
+                    }
+                    if let Some(sandbox) = &result.sandbox {
+                        li { "❌ {sandbox}" }
+                    }
+                    if result.devices.is_empty() {
+                        li { "❌ No gamepad event nodes found" }
+                    }
+                    for device in result.devices.iter() {
+                        li {
+                            key: "{device.device_path}",
+                            "{check_mark(device.readable_by_us)} {device.device_path} ({device.name}) — group {device.group_label()}, mode {device.mode.as_deref().unwrap_or(\"?\")}"
+                            if let Some(error) = &device.open_error {
+                                span { ": {error}" }
+                            }
+                            if device.grabbed == Some(true) {
+                                span { " — grabbed by another process" }
+                            }
+                        }
+                    }
+                    for device in result.hidraw_devices.iter() {
+                        li {
+                            key: "{device.device_path}",
+                            "{check_mark(device.readable_by_us)} {device.device_path} ({device.name}) — hidraw, mode {device.mode.as_deref().unwrap_or(\"?\")}"
+                            if let Some(error) = &device.open_error {
+                                span { ": {error}" }
+                            }
+                        }
+                    }
+                    if let Some(uinput) = &result.uinput {
+                        li {
+                            "{check_mark(uinput.readable_by_us)} {uinput.device_path} — virtual gamepad, mode {uinput.mode.as_deref().unwrap_or(\"?\")}"
+                            if let Some(error) = &uinput.open_error {
+                                span { ": {error}" }
+                            }
+                        }
+                    }
+                }
+                if !result.remediations.is_empty() {
+                    h4 { "Suggested fixes" }
+                    ul {
+                        for remediation in result.remediations.iter() {
+                            li { key: "{remediation.kind}", "👉 {remediation.message}" }
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Shown once any device has failed to open, with the group change that would fix it.
+#[component]
+fn InputGroupPanel() -> Element {
+    let mut report = use_signal(|| None::<InputGroupReport>);
+
+    use_effect(move || {
+        spawn(async move {
+            let Ok(result) = invoke_without_args("get_device_open_failures").await else {
+                return;
+            };
+            let failures = serde_wasm_bindgen::from_value::<Vec<DeviceOpenFailure>>(result).unwrap_or_default();
+            if failures.is_empty() {
+                return;
+            }
+            if let Ok(result) = invoke_without_args("check_input_group_membership").await {
+                if let Ok(latest) = serde_wasm_bindgen::from_value::<InputGroupReport>(result) {
+                    report.set(Some(latest));
+                }
+            }
+        });
+    });
+
+    rsx! {
+        if let Some(report) = report.read().as_ref() {
+            div {
+                class: "debug-section",
+                h3 { "👥 Input Group" }
+                p { "User: {report.user_name}" }
+                p { "Groups: {report.current_groups.join(\", \")}" }
+                p { "Input group exists: {report.input_group_exists}, user in it: {report.user_in_input_group}" }
+                if let Some(group) = &report.device_group {
+                    p { "Failed device's group: {group}" }
+                }
+                p { "👉 {report.suggestion}" }
+            }
+        }
+    }
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct EvdevGamepadInfo {
-    pub device_path: String,
-    pub name: String,
-    pub vendor_id: Option<u16>,
-    pub product_id: Option<u16>,
-    pub is_gamepad: bool,
-    pub capabilities: Vec<String>,
-}
+#[component]
+fn HealthCheckPanel() -> Element {
+    let mut report = use_signal(|| None::<HealthReport>);
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct EvdevControllerEvent {
-    pub device_path: String,
-    pub event_type: String,
-    pub code: u16,
-    pub value: i32,
-    pub timestamp: u64,
+    use_coroutine(move |_: UnboundedReceiver<()>| async move {
+        loop {
+            if let Ok(result) = invoke_without_args("health_check").await {
+                if let Ok(latest) = serde_wasm_bindgen::from_value::<HealthReport>(result) {
+                    report.set(Some(latest));
+                }
+            }
+            TimeoutFuture::new(5000).await;
+        }
+    });
+
+    rsx! {
+        div {
+            class: "debug-section health-panel",
+            if let Some(health) = report.read().as_ref() {
+                h3 {
+                    class: "health-{health.overall}",
+                    "{health_icon(&health.overall)} Health: {health.overall}"
+                }
+                ul {
+                    class: "health-list",
+                    for check in health.checks.iter() {
+                        li {
+                            key: "{check.name}",
+                            class: "health-{check.status}",
+                            span { "{health_icon(&check.status)} " }
+                            strong { "{check.name}" }
+                            span { " — {check.message}" }
+                        }
+                    }
+                }
+            } else {
+                h3 { "Health: checking..." }
+            }
+        }
+    }
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct UpdateInfo {
-    pub available: bool,
-    pub version: Option<String>,
-    pub current_version: String,
-    pub body: Option<String>,
-    pub date: Option<String>,
+#[component]
+fn LatencyBenchmarkPanel() -> Element {
+    let mut duration_secs = use_signal(|| 10u64);
+    let mut running = use_signal(|| false);
+    let mut report = use_signal(|| None::<LatencyReport>);
+    let mut error = use_signal(|| None::<String>);
+    let mut priority = use_signal(|| None::<InputPriorityStatus>);
+
+    use_effect(move || {
+        spawn(async move {
+            if let Ok(result) = invoke_without_args("get_input_priority").await {
+                if let Ok(current) = serde_wasm_bindgen::from_value::<InputPriorityStatus>(result) {
+                    priority.set(Some(current));
+                }
+            }
+        });
+    });
+
+    let run = move |_| {
+        spawn(async move {
+            running.set(true);
+            error.set(None);
+            let args = serde_wasm_bindgen::to_value(&serde_json::json!({
+                "durationSecs": *duration_secs.read()
+            })).unwrap();
+            match invoke("run_latency_benchmark", args).await {
+                Ok(result) => match serde_wasm_bindgen::from_value::<LatencyReport>(result) {
+                    Ok(latest) => report.set(Some(latest)),
+                    Err(e) => error.set(Some(format!("Unexpected benchmark result: {}", e))),
+                },
+                Err(e) => error.set(Some(AppError::from_js(&e).user_message())),
+            }
+            running.set(false);
+        });
+    };
+
+    rsx! {
+        div {
+            class: "debug-section",
+            h3 { "⏱️ Input Latency" }
+            p { "Press buttons while the benchmark runs. Dispatch is only measured while batching to the light server is enabled." }
+            if let Some(current) = priority.read().clone() {
+                label {
+                    input {
+                        r#type: "checkbox",
+                        checked: current.enabled,
+                        onchange: move |event| {
+                            let enabled = event.checked();
+                            spawn(async move {
+                                let args = serde_wasm_bindgen::to_value(&serde_json::json!({
+                                    "enabled": enabled
+                                })).unwrap();
+                                match invoke("set_input_priority_enabled", args).await {
+                                    Ok(result) => {
+                                        if let Ok(updated) = serde_wasm_bindgen::from_value::<InputPriorityStatus>(result) {
+                                            priority.set(Some(updated));
+                                        }
+                                    }
+                                    Err(e) => error.set(Some(AppError::from_js(&e).user_message())),
+                                }
+                            });
+                        }
+                    }
+                    " Raise input thread priority (after restart)"
+                }
+            }
+            input {
+                r#type: "number",
+                min: "1",
+                max: "300",
+                value: "{duration_secs}",
+                oninput: move |event| {
+                    if let Ok(secs) = event.value().parse() {
+                        duration_secs.set(secs);
+                    }
+                }
+            }
+            button {
+                disabled: *running.read(),
+                onclick: run,
+                if *running.read() { "Running..." } else { "Run benchmark" }
+            }
+            if let Some(message) = error.read().as_ref() {
+                p { class: "debug-error", "{message}" }
+            }
+            if let Some(latest) = report.read().as_ref() {
+                p { "Last run: {latest.duration_secs}s" }
+                if !latest.input_threads.is_empty() {
+                    p {
+                        "Input threads: "
+                        {latest.input_threads.iter()
+                            .map(|thread| format!("{} ({})", thread.thread, thread.priority.label()))
+                            .collect::<Vec<_>>()
+                            .join(", ")}
+                    }
+                }
+                table {
+                    class: "metrics-table",
+                    thead {
+                        tr {
+                            th { "Stage" }
+                            th { "Samples" }
+                            th { "p50" }
+                            th { "p95" }
+                            th { "p99" }
+                            th { "Max" }
+                        }
+                    }
+                    tbody {
+                        for stage in latest.stages.iter() {
+                            tr {
+                                key: "{stage.stage}",
+                                td { "{stage.stage}" }
+                                td { "{stage.samples}" }
+                                td { "{format_duration_ns(stage.p50_us * 1000)}" }
+                                td { "{format_duration_ns(stage.p95_us * 1000)}" }
+                                td { "{format_duration_ns(stage.p99_us * 1000)}" }
+                                td { "{format_duration_ns(stage.max_us * 1000)}" }
+                            }
+                        }
+                    }
+                }
+            }
+        }
+    }
 }
 
 pub fn App() -> Element {
+    let mut theme = use_context_provider(|| Signal::new(Theme::Light));
+    use_effect(move || {
+        theme.set(Theme::load_stored().unwrap_or_else(Theme::system_preference));
+    });
+    use_effect(move || {
+        theme.read().apply_to_document();
+    });
+
+    let mut synced = use_context_provider(|| FrontendSynced(Signal::new(false))).0;
+    use_event_subscription(&["buttons", "axes", "motion", "trackpad", "devices", "updates"]);
+
     let controllers = use_signal(|| HashMap::<usize, ControllerState>::new());
     let mut server_endpoint = use_signal(|| "0.1.13".to_string());
+    let mut self_signed_hosts = use_signal(String::new);
     let last_event = use_signal(|| String::new());
     let app_version = use_signal(|| "0.1.13".to_string());
     let debug_info = use_signal(|| None::<DebugInfo>);
+    let full_debug_info = use_signal(|| None::<FullDebugInfo>);
     let mut mouse_position = use_signal(|| (0.0, 0.0));
-    let show_debug = use_signal(|| true);
+    let show_debug = use_signal(|| false);
     let mut last_key_event = use_signal(|| "0.1.13".to_string());
     let evdev_devices = use_signal(|| Vec::<EvdevGamepadInfo>::new());
     let steam_deck_info = use_signal(|| "0.1.13".to_string());
@@ -103,42 +6319,135 @@ pub fn App() -> Element {
     let is_downloading_update = use_signal(|| false);
     let download_progress = use_signal(|| 0u64);
     let download_total = use_signal(|| 0u64);
+    let mut log_file_path = use_signal(|| None::<String>);
+    let debug_report_status = use_signal(|| None::<String>);
+    let command_metrics = use_signal(Vec::<(String, CommandMetricSnapshot)>::new);
+    let mut display = use_signal(|| None::<DisplayStatus>);
+
+    // Also runs after a webview reload, when events sent in the meantime are lost: the
+    // snapshot replaces whatever they would have updated, then views subscribe again
+    let mut controllers_sync = controllers;
+    let mut evdev_devices_sync = evdev_devices;
+    let mut app_version_sync = app_version;
+    let mut update_info_sync = update_info;
+    let mut update_status_sync = update_status;
+    let mut is_downloading_sync = is_downloading_update;
+    let mut download_progress_sync = download_progress;
+    let mut download_total_sync = download_total;
+    use_effect(move || {
+        spawn(async move {
+            match invoke_without_args("frontend_hello").await {
+                Ok(result) => match serde_wasm_bindgen::from_value::<FrontendSnapshot>(result) {
+                    Ok(snapshot) => {
+                        if snapshot.hello_count > 1 {
+                            logger::info(&format!("Resynchronized with the backend after a reload (load {})", snapshot.hello_count));
+                        }
+                        controllers_sync.set(snapshot.controllers.changed);
+                        evdev_devices_sync.set(snapshot.evdev_devices);
+                        app_version_sync.set(snapshot.app_version);
+                        log_file_path.set(snapshot.log_file_path);
+                        if let Some(endpoint) = snapshot.settings.light_server_endpoint {
+                            server_endpoint.set(endpoint);
+                        }
+                        self_signed_hosts.set(snapshot.settings.self_signed_hosts.join(", "));
+                        display.set(Some(snapshot.display));
+                        update_info_sync.set(snapshot.update.last_check);
+                        match snapshot.update.stage {
+                            UpdateStage::Idle => {}
+                            UpdateStage::Downloading { downloaded_bytes, total_bytes } => {
+                                is_downloading_sync.set(true);
+                                download_progress_sync.set(downloaded_bytes);
+                                download_total_sync.set(total_bytes.unwrap_or_default());
+                                update_status_sync.set("Downloading update...".to_string());
+                            }
+                            UpdateStage::Installing => {
+                                is_downloading_sync.set(true);
+                                update_status_sync.set("Installing update...".to_string());
+                            }
+                        }
+                    }
+                    Err(e) => logger::error(&format!("Failed to read the backend snapshot: {}", e)),
+                },
+                Err(e) => logger::error(&format!("frontend_hello failed: {}", AppError::from_js(&e).message())),
+            }
+            synced.set(true);
+        });
+    });
+
+    use_effect(move || {
+        spawn(async move {
+            // Answered so the backend can tell when this page has stopped listening
+            let heartbeat_handler = Closure::<dyn FnMut(JsValue)>::new(move |event: JsValue| {
+                if let Ok(sequence) = serde_wasm_bindgen::from_value::<u64>(event) {
+                    wasm_bindgen_futures::spawn_local(async move {
+                        let args = serde_wasm_bindgen::to_value(&serde_json::json!({ "sequence": sequence })).unwrap();
+                        let _ = invoke("frontend_heartbeat_ack", args).await;
+                    });
+                }
+            });
+            let _ = listen("frontend-heartbeat", &heartbeat_handler).await;
+            heartbeat_handler.forget();
+        });
+    });
 
     // Poll for connected controllers and debug info
     let mut controllers_clone = controllers.clone();
     let mut debug_info_clone = debug_info.clone();
     let mut evdev_devices_clone = evdev_devices.clone();
     let mut steam_deck_info_clone = steam_deck_info.clone();
+    let mut full_debug_info_clone = full_debug_info;
+    let mut command_metrics_clone = command_metrics;
     use_coroutine(move |_: UnboundedReceiver<()>| async move {
+        let mut revision = 0u64;
+        let mut pass = 0u64;
         loop {
-            // Get controller states
-            if let Ok(result) = invoke_without_args("get_connected_controllers").await {
-                if let Ok(controllers_map) = serde_wasm_bindgen::from_value::<HashMap<usize, ControllerState>>(result) {
-                    controllers_clone.set(controllers_map);
-                }
+            if page_hidden() {
+                TimeoutFuture::new(1000).await;
+                continue;
             }
-            
-            // Get debug info
-            if let Ok(debug_result) = invoke_without_args("get_debug_info").await {
-                if let Ok(debug_data) = serde_wasm_bindgen::from_value::<DebugInfo>(debug_result) {
-                    debug_info_clone.set(Some(debug_data));
+            // Only controllers that changed since the last poll come back
+            let args = serde_wasm_bindgen::to_value(&serde_json::json!({ "sinceRevision": revision })).unwrap();
+            if let Ok(result) = invoke("get_controller_states_delta", args).await {
+                if let Ok(delta) = serde_wasm_bindgen::from_value::<ControllerStatesDelta>(result) {
+                    revision = delta.revision;
+                    if delta.full {
+                        controllers_clone.set(delta.changed);
+                    } else if !delta.changed.is_empty() || !delta.removed.is_empty() {
+                        let mut controllers_map = controllers_clone.write();
+                        for id in delta.removed {
+                            controllers_map.remove(&id);
+                        }
+                        controllers_map.extend(delta.changed);
+                    }
                 }
             }
             
-            // Get evdev devices
-            if let Ok(evdev_result) = invoke_without_args("get_evdev_devices").await {
-                if let Ok(evdev_data) = serde_wasm_bindgen::from_value::<Vec<EvdevGamepadInfo>>(evdev_result) {
-                    evdev_devices_clone.set(evdev_data);
+            // Debug info, evdev devices and Steam Deck info all arrive in one response. It's
+            // the costliest call, so with the debug panel closed it only keeps the controller
+            // cards' details fresh
+            let debug_open = *show_debug.peek();
+            if debug_open || pass.is_multiple_of(FULL_DEBUG_REFRESH_PASSES) {
+                if let Ok(debug_result) = invoke_without_args("get_full_debug_info").await {
+                    if let Ok(full) = serde_wasm_bindgen::from_value::<FullDebugInfo>(debug_result) {
+                        debug_info_clone.set(Some(full.gamepad.clone()));
+                        evdev_devices_clone.set(full.evdev_devices.clone());
+                        steam_deck_info_clone.set(full.steam_deck_summary.clone());
+                        full_debug_info_clone.set(Some(full));
+                    }
                 }
             }
             
-            // Get Steam Deck info
-            if let Ok(steam_result) = invoke_without_args("get_steam_deck_info").await {
-                if let Ok(steam_data) = serde_wasm_bindgen::from_value::<String>(steam_result) {
-                    steam_deck_info_clone.set(steam_data);
+            if debug_open {
+                if let Ok(metrics_result) = invoke_without_args("get_command_metrics").await {
+                    if let Ok(metrics) = serde_wasm_bindgen::from_value::<HashMap<String, CommandMetricSnapshot>>(metrics_result) {
+                        let mut sorted: Vec<_> = metrics.into_iter().collect();
+                        sorted.sort_by_key(|(_, metric)| std::cmp::Reverse(metric.total_duration_ns));
+                        command_metrics_clone.set(sorted);
+                    }
                 }
             }
             
+            pass += 1;
             TimeoutFuture::new(1000).await;
         }
     });
@@ -151,29 +6460,44 @@ pub fn App() -> Element {
     let mut update_status_clone = update_status.clone();
     use_effect(move || {
         spawn(async move {
-            // Set up gamepad event listener
-            let gamepad_handler = Closure::<dyn FnMut(JsValue)>::new(move |event: JsValue| {
-                if let Ok(event_data) = serde_wasm_bindgen::from_value::<ControllerEvent>(event) {
-                    last_event_clone.set(format!(
-                        "Controller {}: {} - {:?}{:?} = {:?}",
-                        event_data.controller_id,
-                        event_data.event_type,
-                        event_data.button.as_deref().unwrap_or(""),
-                        event_data.axis.as_deref().unwrap_or(""),
-                        event_data.value
-                    ));
+            // Gilrs and evdev input both arrive on `controller-event`
+            let controller_handler = Closure::<dyn FnMut(JsValue)>::new(move |event: JsValue| {
+                if let Ok(event_data) = serde_wasm_bindgen::from_value::<UnifiedControllerEvent>(event) {
+                    let input = match &event_data.input {
+                        NormalizedInput::Connected => "connected".to_string(),
+                        NormalizedInput::Disconnected => "disconnected".to_string(),
+                        NormalizedInput::Button { name, pressed } => format!("{} {}", name, if *pressed { "pressed" } else { "released" }),
+                        NormalizedInput::Axis { name, value } => format!("{} = {:.3}", name, value),
+                        NormalizedInput::Other { event_type, name } => format!("{} {}", event_type, name.as_deref().unwrap_or("")),
+                    };
+                    let replay = if event_data.source == "synthetic" { "[replay] " } else { "" };
+                    match (&event_data.device_path, event_data.controller_id) {
+                        (Some(device_path), _) => last_evdev_event_clone.set(format!(
+                            "{}EVDEV {}: {} (code={} value={})",
+                            replay,
+                            device_path,
+                            input,
+                            event_data.raw_code.unwrap_or_default(),
+                            event_data.raw_value.unwrap_or_default()
+                        )),
+                        (None, controller_id) => last_event_clone.set(format!(
+                            "{}Controller {}: {}",
+                            replay,
+                            controller_id.map(|id| id.to_string()).unwrap_or_else(|| event_data.device_id.clone()),
+                            input
+                        )),
+                    }
                 }
             });
             
-            // Set up evdev event listener
-            let evdev_handler = Closure::<dyn FnMut(JsValue)>::new(move |event: JsValue| {
-                if let Ok(event_data) = serde_wasm_bindgen::from_value::<EvdevControllerEvent>(event) {
-                    last_evdev_event_clone.set(format!(
-                        "EVDEV {}: {} code={} value={}",
-                        event_data.device_path,
-                        event_data.event_type,
-                        event_data.code,
-                        event_data.value
+            let mut last_evdev_event_clone2 = last_evdev_event_clone;
+            let unhealthy_handler = Closure::<dyn FnMut(JsValue)>::new(move |event: JsValue| {
+                if let Ok(device) = serde_wasm_bindgen::from_value::<UnhealthyDevice>(event) {
+                    last_evdev_event_clone2.set(format!(
+                        "⚠️ {} ({}) lost: {}",
+                        device.device_path,
+                        device.name,
+                        device.reason
                     ));
                 }
             });
@@ -186,7 +6510,7 @@ pub fn App() -> Element {
                     if let Some(size) = content_length {
                         download_total_clone2.set(size);
                         update_status_clone2.set(format!("Downloading update... ({:.2} MB)", size as f64 / 1024.0 / 1024.0));
-                        gloo_console::log!(&format!("Download started - size: {} bytes", size));
+                        logger::info(&format!("Download started - size: {} bytes", size));
                     }
                 }
             });
@@ -209,17 +6533,17 @@ pub fn App() -> Element {
             let mut update_status_clone3 = update_status_clone.clone();
             let installing_handler = Closure::<dyn FnMut(JsValue)>::new(move |_: JsValue| {
                 update_status_clone3.set("Installing update...".to_string());
-                gloo_console::log!("Installing update...");
+                logger::info("Installing update...");
             });
             
-            let _ = listen("gamepad-input", &gamepad_handler).await;
-            let _ = listen("evdev-gamepad-input", &evdev_handler).await;
+            let _ = listen("controller-event", &controller_handler).await;
+            let _ = listen("evdev-device-unhealthy", &unhealthy_handler).await;
             let _ = listen("update-download-started", &download_started_handler).await;
             let _ = listen("update-download-progress", &download_progress_handler).await;
             let _ = listen("update-installing", &installing_handler).await;
             
-            gamepad_handler.forget();
-            evdev_handler.forget();
+            controller_handler.forget();
+            unhealthy_handler.forget();
             download_started_handler.forget();
             download_progress_handler.forget();
             installing_handler.forget();
@@ -260,14 +6584,14 @@ pub fn App() -> Element {
             spawn(async move {
                 is_checking_update.set(true);
                 update_status.set("Checking for updates...".to_string());
-                gloo_console::log!("🔍 Starting update check...");
+                logger::info("🔍 Starting update check...");
                 
                 let result = invoke_without_args("check_for_updates").await;
                 
                 match result {
                     Ok(update_data) => {
                         if let Ok(info) = serde_wasm_bindgen::from_value::<UpdateInfo>(update_data) {
-                            gloo_console::log!("✅ Update check complete");
+                            logger::info("✅ Update check complete");
                             
                             if info.available {
                                 update_status.set(format!(
@@ -284,14 +6608,17 @@ pub fn App() -> Element {
                             
                             update_info.set(Some(info));
                         } else {
-                            gloo_console::error!("Failed to parse update info");
+                            logger::error("Failed to parse update info");
                             update_status.set("Failed to parse update info".to_string());
                         }
                     }
                     Err(e) => {
-                        let error_msg = format!("Error checking updates: {:?}", e);
-                        gloo_console::error!(&error_msg);
-                        update_status.set(error_msg);
+                        let error = AppError::from_js(&e);
+                        logger::error(&format!("Error checking updates: {}", error.message()));
+                        update_status.set(match &error {
+                            AppError::Network { .. } => "Couldn't reach the update server. Check your connection and try again.".to_string(),
+                            _ => format!("Error checking updates: {}", error.user_message()),
+                        });
                     }
                 }
                 
@@ -308,6 +6635,11 @@ pub fn App() -> Element {
         }
     };
 
+    // Off Linux, or without /dev/input, there is no evdev backend to show
+    let evdev_available = full_debug_info.read().as_ref().is_some_and(|full| {
+        full.input_backends.iter().any(|backend| backend.source == "evdev" && backend.available)
+    });
+    
     let rescan_evdev = {
         let mut evdev_devices = evdev_devices.clone();
         move |_| {
@@ -323,10 +6655,43 @@ pub fn App() -> Element {
         }
     };
     
+    let export_debug_report = {
+        let mut debug_report_status = debug_report_status;
+        move |_| {
+            spawn(async move {
+                debug_report_status.set(Some("Generating debug report...".to_string()));
+                match invoke_without_args("export_debug_report").await {
+                    Ok(result) => {
+                        if let Ok(report) = serde_wasm_bindgen::from_value::<DebugReportResult>(result) {
+                            debug_report_status.set(Some(format!(
+                                "Saved to {} ({:.1} KB)",
+                                report.path,
+                                report.size_bytes as f64 / 1024.0
+                            )));
+                        }
+                    }
+                    Err(e) => {
+                        debug_report_status.set(Some(format!("Failed to export debug report: {}", AppError::from_js(&e).user_message())));
+                    }
+                }
+            });
+        }
+    };
+    
+    let reset_command_metrics = {
+        let mut command_metrics = command_metrics;
+        move |_| {
+            spawn(async move {
+                let _ = invoke_without_args("reset_command_metrics").await;
+                command_metrics.set(Vec::new());
+            });
+        }
+    };
+    
     let exit_app = move |_| {
         spawn(async move {
-            gloo_console::log!("Exiting application...");
-            let _ = invoke_without_args("exit_app").await;
+            logger::info("Exiting application...");
+            let _ = invoke_without_args("shutdown_app").await;
         });
     };
     
@@ -348,25 +6713,29 @@ pub fn App() -> Element {
                 download_progress.set(0);
                 download_total.set(0);
                 
-                gloo_console::log!("📦 Starting update download...");
+                logger::info("📦 Starting update download...");
                 
                 let result = invoke_without_args("download_and_install_update").await;
                 
                 match result {
                     Ok(_) => {
-                        gloo_console::log!("✅ Update installed successfully!");
+                        logger::info("✅ Update installed successfully!");
                         update_status.set("Update installed! Restarting application...".to_string());
                         
                         // Wait a moment to show the message, then restart
                         TimeoutFuture::new(2000).await;
                         
-                        gloo_console::log!("🔄 Triggering application restart...");
+                        logger::info("🔄 Triggering application restart...");
                         let _ = invoke_without_args("restart_app").await;
                     }
                     Err(e) => {
-                        let error_msg = format!("Failed to install update: {:?}", e);
-                        gloo_console::error!(&error_msg);
-                        update_status.set(error_msg);
+                        let error = AppError::from_js(&e);
+                        logger::error(&format!("Failed to install update: {}", error.message()));
+                        update_status.set(if error.is_retryable() {
+                            "Update download was interrupted. Check your connection and try again.".to_string()
+                        } else {
+                            format!("Failed to install update: {}", error.user_message())
+                        });
                     }
                 }
                 
@@ -375,10 +6744,24 @@ pub fn App() -> Element {
         }
     };
 
+    let pipeline_lines: Vec<(String, String)> = full_debug_info.read().as_ref()
+        .map(|full| full.pipelines.iter().map(|pipeline| {
+            let state = if !pipeline.alive { "exited" } else if pipeline.stalled { "stalled" } else { "alive" };
+            let heartbeat = pipeline.heartbeat_age_ms
+                .map(|age| format!("{}ms ago", age))
+                .unwrap_or_else(|| "none yet".to_string());
+            (pipeline.source.clone(), format!("{}: {}, heartbeat {}, stalls: {}", pipeline.source, state, heartbeat, pipeline.stalls))
+        }).collect())
+        .unwrap_or_default();
+    let ac_adapter = full_debug_info.read().as_ref()
+        .and_then(|full| full.system_power.as_ref()?.ac_online)
+        .map(|online| if online { "connected" } else { "disconnected" });
+
     rsx! {
         link { rel: "stylesheet", href: "styles.css" }
         main {
             class: "container",
+            "data-theme": theme.read().as_str(),
             tabindex: "0",
             onmousemove: move |event| {
                 mouse_position.set((event.client_coordinates().x, event.client_coordinates().y));
@@ -390,7 +6773,33 @@ pub fn App() -> Element {
                 last_key_event.set(format!("KeyUp: {} (code: {})", event.key(), event.code()));
             },
             
+            PanicBanner {}
+            ProcessingPauseBanner {}
+            StallBanner {}
+            SteamInputBanner {}
+            BackgroundInputBanner {}
+            div {
+                class: "toast-stack",
+                BackendWarningToasts {}
+                EventPressureToast {}
+                ResumeToast {}
+                SystemBatteryToast {}
+                DeviceOpenFailureToasts { show_debug }
+            }
+            
             h1 { "Steam Deck Controller Light Show Control" }
+            if let Some(current) = display.read().as_ref() {
+                div {
+                    class: "session-header",
+                    span { class: "session-badge", "{current.session_kind.label()}" }
+                    if current.warn_steam_input {
+                        span {
+                            class: "session-warning",
+                            "Steam Input is always on in game mode; if input stops arriving, disable it in this app's controller settings in Steam"
+                        }
+                    }
+                }
+            }
             
             div {
                 class: "version-info",
@@ -450,6 +6859,8 @@ pub fn App() -> Element {
                 
                 div {
                     class: "button-group",
+                    DockIndicator {}
+                    ThemeToggle {}
                     button {
                         onclick: toggle_debug,
                         if *show_debug.read() { "Hide Debug" } else { "Show Debug" }
@@ -468,10 +6879,45 @@ pub fn App() -> Element {
                 input {
                     value: "{server_endpoint}",
                     oninput: move |event| server_endpoint.set(event.value()),
+                    onchange: move |event| {
+                        // Backend-originated sends (combination effects) use the persisted endpoint
+                        let endpoint = event.value();
+                        spawn(async move {
+                            let args = serde_wasm_bindgen::to_value(&serde_json::json!({
+                                "endpoint": endpoint
+                            })).unwrap();
+                            let _ = invoke("set_light_server_endpoint", args).await;
+                        });
+                    },
                     placeholder: "http://localhost:8080/light-control"
                 }
+                input {
+                    title: "Hosts whose self-signed HTTPS certificates are accepted",
+                    value: "{self_signed_hosts}",
+                    oninput: move |event| self_signed_hosts.set(event.value()),
+                    onchange: move |event| {
+                        let hosts: Vec<String> = event.value().split(',').map(|host| host.trim().to_string()).collect();
+                        spawn(async move {
+                            let args = serde_wasm_bindgen::to_value(&serde_json::json!({
+                                "hosts": hosts
+                            })).unwrap();
+                            let _ = invoke("set_self_signed_hosts", args).await;
+                        });
+                    },
+                    placeholder: "Self-signed hosts, comma separated"
+                }
+                BackgroundModeToggle {}
+                LizardModeToggle {}
+                VirtualPadPolicySelect {}
+                BackgroundInputSelect {}
+                SuspendInhibitToggle {}
+                DockPolicySettings {}
+                DisplaySettings { display }
+                SteamShortcutSettings {}
             }
             
+            SessionSummaryCard {}
+            
             div {
                 class: "controllers-section",
                 h2 { "Connected Controllers" }
@@ -481,8 +6927,22 @@ pub fn App() -> Element {
                 } else {
                     {controllers.read().iter().map(|(id, controller)| {
                         let controller_id = *id;
+                        let gamepad_info = debug_info.read().as_ref().and_then(|debug| {
+                            debug.connected_gamepads.iter().find(|g| g.id == controller_id).cloned()
+                        });
+                        let inferred_layout = gamepad_info.as_ref().map(|g| g.layout.clone()).unwrap_or_else(unknown_layout);
+                        let layout_override = gamepad_info.as_ref().and_then(|g| g.layout_override.clone());
+                        let layout = layout_override.clone().unwrap_or_else(|| inferred_layout.clone());
+                        let layout_select_value = layout_override.unwrap_or_default();
+                        let mapping_name = gamepad_info.as_ref().and_then(|g| g.mapping_name.clone());
+                        let virtual_link = gamepad_info.as_ref().filter(|g| g.is_virtual).map(|g| match &g.physical_source {
+                            Some(physical) => format!("🔗 Steam virtual pad ← {}", physical),
+                            None => "🔗 Steam virtual pad".to_string(),
+                        });
+                        let layout_icon = layout_icon(&layout);
                         let buttons_elements = controller.buttons.iter().map(|(button, pressed)| {
                             let button_name = button.clone();
+                            let button_label = face_button_label(&layout, button);
                             let button_action = button.clone();
                             let is_pressed = *pressed;
                             rsx! {
@@ -492,7 +6952,7 @@ pub fn App() -> Element {
                                     onclick: move |_| {
                                         send_to_server(controller_id, format!("button:{}", button_action));
                                     },
-                                    "{button_name}: {is_pressed}"
+                                    "{button_label}: {is_pressed}"
                                 }
                             }
                         });
@@ -520,7 +6980,38 @@ pub fn App() -> Element {
                             div {
                                 key: "{controller_id}",
                                 class: "controller-card",
-                                h3 { "Controller {controller_id}" }
+                                div {
+                                    class: "controller-card-header",
+                                    h3 { "{layout_icon} Controller {controller_id}" }
+                                    if let Some(mapping) = &mapping_name {
+                                        span { class: "mapping-name", "{mapping}" }
+                                    }
+                                    if let Some(link) = &virtual_link {
+                                        span { class: "virtual-pad-link", "{link}" }
+                                    }
+                                    select {
+                                        class: "layout-select",
+                                        value: "{layout_select_value}",
+                                        onchange: move |event| {
+                                            let value = event.value();
+                                            spawn(async move {
+                                                let layout = if value.is_empty() { None } else { Some(value) };
+                                                let args = serde_wasm_bindgen::to_value(&serde_json::json!({
+                                                    "controllerId": controller_id,
+                                                    "layout": layout
+                                                })).unwrap();
+                                                if let Err(e) = invoke("set_controller_layout_override", args).await {
+                                                    logger::error(&format!("Failed to set layout override: {}", AppError::from_js(&e).message()));
+                                                }
+                                            });
+                                        },
+                                        option { value: "", "Auto ({inferred_layout})" }
+                                        option { value: "xbox", "Xbox" }
+                                        option { value: "playstation", "PlayStation" }
+                                        option { value: "nintendo", "Nintendo" }
+                                        option { value: "steam_deck", "Steam Deck" }
+                                    }
+                                }
                                 
                                 div {
                                     class: "buttons-grid",
@@ -539,11 +7030,35 @@ pub fn App() -> Element {
                 }
             }
             
+            ControllerSlotAssignment { controllers }
+            
+            TouchZonePanel {}
+            
+            RumbleTestPanel { controllers }
+            
+            LowBatteryPanel {}
+            
             if *show_debug.read() {
                 div {
                     class: "debug-panel",
                     h2 { "🐛 Debug Information" }
                     
+                    HealthCheckPanel {}
+                    
+                    div {
+                        class: "debug-section",
+                        h3 { "📋 Debug Report" }
+                        button {
+                            onclick: export_debug_report,
+                            "Export Debug Report"
+                        }
+                        if let Some(status) = debug_report_status.read().as_ref() {
+                            pre { "{status}" }
+                        }
+                    }
+                    
+                    RecordingPanel {}
+                    
                     div {
                         class: "debug-section",
                         h3 { "Input Events" }
@@ -551,12 +7066,131 @@ pub fn App() -> Element {
                         p { "Keyboard: {last_key_event}" }
                     }
                     
+                    if let Some(full) = full_debug_info.read().as_ref() {
+                        div {
+                            class: "debug-section",
+                            h3 { "🏷️ Build" }
+                            p { "Version: {full.build.version} ({full.build.target_os}/{full.build.target_arch})" }
+                            p { "Debug build: {full.build.debug_build}" }
+                            p { "Recovered poll panics: {full.poll_panics}" }
+                            p { "Polling stalls: {full.poll_stalls}" }
+                            for (source, line) in pipeline_lines.iter() {
+                                p {
+                                    key: "{source}",
+                                    "{line}"
+                                }
+                            }
+                        }
+                        
+                        if let Some(subscriptions) = &full.event_subscriptions {
+                            div {
+                                class: "debug-section",
+                                h3 { "📡 Event Channels" }
+                                if subscriptions.frontend_default {
+                                    p { "The frontend hasn't subscribed yet, so every channel is emitted" }
+                                } else {
+                                    p { "Emitted: {subscriptions.active_channels.join(\", \")}" }
+                                }
+                                for (consumer, channels) in subscriptions.consumers.iter() {
+                                    p { key: "{consumer}", "{consumer}: {channels.join(\", \")}" }
+                                }
+                                if !subscriptions.window_visible {
+                                    p { "Window hidden: per-input events are throttled" }
+                                }
+                            }
+                        }
+                        
+                        if let Some(motion) = &full.motion {
+                            div {
+                                class: "debug-section",
+                                h3 { "🧭 Motion" }
+                                p { "Source: {motion.config.source}, active: {motion.active}" }
+                                if let Some(device) = &motion.iio_device {
+                                    p { "Sampling IIO device {device} at {motion.config.iio_sample_rate_hz}Hz ({motion.iio_samples} samples)" }
+                                }
+                                if let Some(error) = &motion.last_error {
+                                    p { class: "error", "Last IIO error: {error}" }
+                                }
+                                for device in motion.iio_devices.iter() {
+                                    p { "{device.id} ({device.name}): {device.axes.join(\", \")}" }
+                                }
+                            }
+                        }
+                        
+                        DeckHapticTest {}
+                        
+                        if let Some(power) = &full.system_power {
+                            div {
+                                class: "debug-section",
+                                h3 { "🔋 System Power" }
+                                if let Some(battery) = &power.battery {
+                                    p { "{battery.name}: {battery.percent}%, {battery.state.replace('_', \" \")}" }
+                                    if let Some(watts) = battery.power_draw_w {
+                                        p { "Power draw: {watts:.1}W" }
+                                    }
+                                } else {
+                                    p { "No battery: AC only" }
+                                }
+                                if let Some(ac_adapter) = ac_adapter {
+                                    p { "AC adapter: {ac_adapter}" }
+                                }
+                            }
+                        }
+                        
+                        if let Some(system) = &full.system {
+                            div {
+                                class: "debug-section",
+                                h3 { "🖥️ System" }
+                                p { "OS: {system.os_name.as_deref().unwrap_or(\"unknown\")} (build {system.os_build_id.as_deref().unwrap_or(\"n/a\")})" }
+                                p { "Kernel: {system.kernel_version.as_deref().unwrap_or(\"unknown\")}" }
+                                p { "Session: {system.session_type}, desktop: {system.current_desktop.as_deref().unwrap_or(\"none\")}" }
+                                p { "Mode: {system.session_kind.label()}" }
+                                if !system.confinement.kinds.is_empty() {
+                                    p { "Sandbox: {system.confinement.label()}" }
+                                    if let Some(runtime) = &system.confinement.container_runtime {
+                                        p { "Container runtime: {runtime}" }
+                                    }
+                                    if !system.confinement.dev_input_visible {
+                                        p { "⚠️ /dev/input isn't visible inside the sandbox" }
+                                    }
+                                } else if system.flatpak || system.pressure_vessel {
+                                    p { "Sandbox: flatpak={system.flatpak}, pressure-vessel={system.pressure_vessel}" }
+                                }
+                                if system.input_env.is_empty() {
+                                    p { "No SDL/gilrs environment variables set" }
+                                } else {
+                                    for (name, value) in system.input_env.iter() {
+                                        p { key: "{name}", "{name}={value}" }
+                                    }
+                                }
+                            }
+                        }
+                        
+                        div {
+                            class: "debug-section",
+                            h3 { "🌐 Light Server" }
+                            p { "Endpoint: {full.light_server.last_endpoint.as_deref().unwrap_or(\"none used yet\")}" }
+                            p { "Requests: {full.light_server.requests_sent} sent, {full.light_server.requests_failed} failed" }
+                            p { "Batches: {full.light_server.batches_sent} sent, {full.light_server.events_per_batch_avg:.1} events/batch" }
+                            if let Some(error) = &full.light_server.last_error {
+                                p { class: "debug-error", "Last error: {error}" }
+                            }
+                        }
+                        
+                        ConnectionStatsPanel {}
+                    }
+                    
                     if let Some(debug) = debug_info.read().as_ref() {
                         div {
                             class: "debug-section",
                             h3 { "Gamepad System Status" }
                             p { "GilRs Initialized: {debug.gilrs_initialized}" }
                             p { "Total Gamepads: {debug.total_gamepads}" }
+                            if let Some(mode) = &debug.processing_paused {
+                                p { "Processing: ⏸️ paused ({mode})" }
+                            } else {
+                                p { "Processing: running" }
+                            }
                             if let Some(last_time) = debug.last_event_time {
                                 p { "Last Event: {last_time}" }
                             } else {
@@ -577,11 +7211,42 @@ pub fn App() -> Element {
                                         p { "Name: {gamepad.name}" }
                                         p { "Connected: {gamepad.is_connected}" }
                                         p { "Power: {gamepad.power_info}" }
+                                        p { "Mapping: {gamepad.mapping_name.as_deref().unwrap_or(\"none\")}" }
+                                        p { "Layout: {layout_icon(&gamepad.layout)} {gamepad.layout}" }
+                                    }
+                                }
+                            }
+                        }
+                        
+                        if !debug.unhandled_events.is_empty() {
+                            div {
+                                class: "debug-section",
+                                h3 { "❓ Unhandled Events" }
+                                table {
+                                    tr {
+                                        th { "Kind" }
+                                        th { "Count" }
+                                        th { "Last controller" }
+                                        th { "Sample" }
+                                    }
+                                    for event in debug.unhandled_events.iter() {
+                                        tr {
+                                            key: "{event.kind}",
+                                            td { "{event.kind}" }
+                                            td { "{event.count}" }
+                                            td { "{event.last_controller_id}" }
+                                            td { code { "{event.sample}" } }
+                                        }
                                     }
                                 }
+                                if debug.unhandled_events_overflowed > 0 {
+                                    p { "{debug.unhandled_events_overflowed} more of other kinds" }
+                                }
                             }
                         }
                         
+                        DetectionDiagnosisPanel {}
+                        
                         div {
                             class: "debug-section",
                             h3 { "Input Devices (/dev/input/)" }
@@ -594,40 +7259,112 @@ pub fn App() -> Element {
                             }
                         }
                         
+                        PipelineMetricsPanel { debug_info }
+                        
+                        RuntimeMetricsPanel {}
+                        
+                        InputBackendsPanel {}
+                        
+                        ErrorCountersPanel {}
+                        
+                        LatencyBenchmarkPanel {}
+                        
                         div {
                             class: "debug-section",
-                            h3 { "Permissions Check" }
-                            pre { "{debug.permissions_check}" }
+                            h3 { "⏱️ Command Perf" }
+                            button {
+                                onclick: reset_command_metrics,
+                                "Reset"
+                            }
+                            if command_metrics.read().is_empty() {
+                                p { "No commands recorded yet" }
+                            } else {
+                                table {
+                                    class: "metrics-table",
+                                    thead {
+                                        tr {
+                                            th { "Command" }
+                                            th { "Calls" }
+                                            th { "Errors" }
+                                            th { "Total" }
+                                            th { "Avg" }
+                                            th { "Min" }
+                                            th { "Max" }
+                                        }
+                                    }
+                                    tbody {
+                                        for (name, metric) in command_metrics.read().iter() {
+                                            tr {
+                                                key: "{name}",
+                                                td { "{name}" }
+                                                td { "{metric.invocation_count}" }
+                                                td { "{metric.error_count}" }
+                                                td { "{format_duration_ns(metric.total_duration_ns)}" }
+                                                td { "{format_duration_ns(metric.avg_duration_ns)}" }
+                                                td { "{format_duration_ns(metric.min_duration_ns)}" }
+                                                td { "{format_duration_ns(metric.max_duration_ns)}" }
+                                            }
+                                        }
+                                    }
+                                }
+                            }
                         }
                         
                         div {
                             class: "debug-section",
-                            h3 { "🎮 Steam Deck Compatibility" }
-                            pre { "{steam_deck_info}" }
+                            h3 { "📝 Log File" }
+                            if let Some(path) = log_file_path.read().as_ref() {
+                                pre { "{path}" }
+                            } else {
+                                p { "File logging unavailable" }
+                            }
+                            LogLevelControl {}
+                            LogViewer {}
                         }
                         
+                        PermissionsChecklist {}
+                        
+                        InputGroupPanel {}
+                        
                         div {
                             class: "debug-section",
-                            h3 { "⚡ Direct Evdev Devices" }
-                            button {
-                                onclick: rescan_evdev,
-                                "🔄 Rescan Devices"
+                            h3 { "🎮 Steam Deck Compatibility" }
+                            if let Some(hardware) = full_debug_info.read().as_ref().and_then(|full| full.steam_deck.clone()) {
+                                p { class: "deck-hardware", "{hardware.label()}" }
                             }
-                            if evdev_devices.read().is_empty() {
-                                p { "❌ No evdev gamepad devices detected" }
-                            } else {
-                                for device in evdev_devices.read().iter() {
-                                    div {
-                                        class: "debug-gamepad",
-                                        p { "Path: {device.device_path}" }
-                                        p { "Name: {device.name}" }
-                                        if let (Some(vid), Some(pid)) = (device.vendor_id, device.product_id) {
-                                            p { "VID/PID: {vid:04x}:{pid:04x}" }
-                                        }
-                                        p { "Capabilities: {device.capabilities.join(\", \")}" }
+                            pre { "{steam_deck_info}" }
+                        }
+                        
+                        if evdev_available {
+                            div {
+                                class: "debug-section",
+                                h3 { "⚡ Direct Evdev Devices" }
+                                button {
+                                    onclick: rescan_evdev,
+                                    "🔄 Rescan Devices"
+                                }
+                                if let Some(full) = full_debug_info.read().as_ref() {
+                                    p {
+                                        "Detected: {full.evdev_stats.detected_devices}, accessible: {full.evdev_stats.accessible_devices}, no access: {full.evdev_stats.inaccessible_devices}"
                                     }
                                 }
+                                if evdev_devices.read().is_empty() {
+                                    p { "❌ No evdev gamepad devices detected" }
+                                } else {
+                                    EvdevDeviceSearch { evdev_devices }
+                                }
                             }
+                            
+                            RawEventDumpPanel { evdev_devices }
+                            SignalQualityPanel { evdev_devices }
+                            VirtualGamepadPanel { evdev_devices }
+                            KeyEmulationPanel {}
+                            MidiPanel {}
+                            EventStreamPanel {}
+                            EventScriptPanel {}
+                            ExternalProcessorPanel {}
+                            OpcPanel {}
+                            ShowPanel {}
                         }
                     } else {
                         p { "Loading debug information..." }
@@ -0,0 +1,102 @@
+//! Logging that reaches the browser console and, batched, the backend log file, since
+//! there's no devtools to open on the Deck.
+
+use gloo_timers::future::TimeoutFuture;
+use serde::Serialize;
+use std::cell::RefCell;
+use wasm_bindgen::prelude::*;
+
+/// How long messages are collected before being sent in one command.
+const FLUSH_DELAY_MS: u32 = 250;
+/// Messages held per flush; a log storm past this is counted rather than forwarded.
+const MAX_PENDING: usize = 200;
+
+#[wasm_bindgen]
+extern "C" {
+    /// Not `async`, so the IPC call is made immediately, which still works from a panic hook.
+    #[wasm_bindgen(js_namespace = ["window", "__TAURI__", "core"], js_name = invoke)]
+    fn invoke_now(cmd: &str, args: JsValue) -> js_sys::Promise;
+}
+
+#[derive(Serialize)]
+struct LogEntry {
+    level: &'static str,
+    message: String,
+}
+
+#[derive(Default)]
+struct PendingLogs {
+    entries: Vec<LogEntry>,
+    dropped: usize,
+    flush_scheduled: bool,
+}
+
+thread_local! {
+    static PENDING: RefCell<PendingLogs> = RefCell::new(PendingLogs::default());
+}
+
+pub fn info(message: &str) {
+    gloo_console::log!(message);
+    forward("info", message);
+}
+
+pub fn error(message: &str) {
+    gloo_console::error!(message);
+    forward("error", message);
+}
+
+fn forward(level: &'static str, message: &str) {
+    let schedule = PENDING.with(|pending| {
+        let mut pending = pending.borrow_mut();
+        if pending.entries.len() < MAX_PENDING {
+            pending.entries.push(LogEntry { level, message: message.to_string() });
+        } else {
+            pending.dropped += 1;
+        }
+        !std::mem::replace(&mut pending.flush_scheduled, true)
+    });
+    if schedule {
+        wasm_bindgen_futures::spawn_local(async {
+            TimeoutFuture::new(FLUSH_DELAY_MS).await;
+            flush();
+        });
+    }
+}
+
+fn flush() {
+    let (mut entries, dropped) = PENDING.with(|pending| {
+        let mut pending = pending.borrow_mut();
+        pending.flush_scheduled = false;
+        (std::mem::take(&mut pending.entries), std::mem::take(&mut pending.dropped))
+    });
+    if dropped > 0 {
+        entries.push(LogEntry {
+            level: "warn",
+            message: format!("Dropped {} frontend log messages", dropped),
+        });
+    }
+    if entries.is_empty() {
+        return;
+    }
+    send("log_frontend_messages", &serde_json::json!({ "entries": entries }));
+}
+
+fn send(cmd: &str, args: &serde_json::Value) {
+    let Ok(args) = serde_wasm_bindgen::to_value(args) else {
+        return;
+    };
+    // A rejected send surfaces as an unhandled rejection in the console, which is all
+    // that could be done with it anyway
+    let _ = invoke_now(cmd, args);
+}
+
+/// Logs panics to the console and forwards them to the backend straight away, skipping
+/// the batch since the app may not get to run the flush.
+pub fn install_panic_hook() {
+    std::panic::set_hook(Box::new(|info| {
+        let message = format!("Frontend panic: {}", info);
+        gloo_console::error!(&message);
+        flush();
+        send("log_frontend_message", &serde_json::json!({ "level": "error", "message": message }));
+    }));
+}
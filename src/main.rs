@@ -1,4 +1,5 @@
 mod app;
+mod logger;
 
 use app::App;
 use dioxus::prelude::*;
@@ -6,5 +7,6 @@ use dioxus_logger::tracing::Level;
 
 fn main() {
     dioxus_logger::init(Level::INFO).expect("failed to init logger");
+    logger::install_panic_hook();
     launch(App);
 }